@@ -11,6 +11,8 @@ use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
+use crate::gitignore::GitignoreStack;
+use crate::limits::CancelFlag;
 use crate::protocol::*;
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -41,7 +43,7 @@ pub(crate) fn resolve_path(raw: &str) -> PathBuf {
 // ═══════════════════════════════════════════════════════════════════════════
 
 /// Decode standard base64 (RFC 4648) to bytes.
-fn base64_decode(input: &str) -> Result<Vec<u8>, String> {
+pub(crate) fn base64_decode(input: &str) -> Result<Vec<u8>, String> {
     const TABLE: [u8; 256] = {
         let mut t = [0xFFu8; 256];
         let alphabet = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
@@ -83,7 +85,7 @@ fn base64_decode(input: &str) -> Result<Vec<u8>, String> {
 }
 
 /// Encode bytes to standard base64 (RFC 4648).
-fn base64_encode(data: &[u8]) -> String {
+pub(crate) fn base64_encode(data: &[u8]) -> String {
     const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
     let mut out = String::with_capacity((data.len() + 2) / 3 * 4);
     for chunk in data.chunks(3) {
@@ -111,47 +113,86 @@ fn base64_encode(data: &[u8]) -> String {
 // Helpers
 // ═══════════════════════════════════════════════════════════════════════════
 
-/// Compute SHA-256 hex digest of a byte slice (minimal, no external crate).
+/// SHA-256 round constants
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5,
+    0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3,
+    0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc,
+    0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7,
+    0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13,
+    0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3,
+    0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5,
+    0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208,
+    0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Incremental SHA-256 (minimal, no external crate).
 ///
 /// This is a pure-Rust SHA-256 implementation to avoid adding dependencies.
-/// Performance is adequate for the file sizes we handle (<10MB).
-pub fn sha256_hex(data: &[u8]) -> String {
-    // SHA-256 constants
-    const K: [u32; 64] = [
-        0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5,
-        0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
-        0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3,
-        0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
-        0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc,
-        0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
-        0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7,
-        0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
-        0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13,
-        0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
-        0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3,
-        0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
-        0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5,
-        0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
-        0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208,
-        0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
-    ];
-
-    let mut h: [u32; 8] = [
-        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a,
-        0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
-    ];
-
-    // Pre-processing: padding
-    let bit_len = (data.len() as u64) * 8;
-    let mut msg = data.to_vec();
-    msg.push(0x80);
-    while (msg.len() % 64) != 56 {
-        msg.push(0);
-    }
-    msg.extend_from_slice(&bit_len.to_be_bytes());
-
-    // Process each 512-bit block
-    for chunk in msg.chunks(64) {
+/// Performance is adequate for the file sizes we handle; streamed reads feed
+/// it one chunk at a time.
+pub struct Sha256 {
+    h: [u32; 8],
+    /// Bytes not yet forming a full 64-byte block
+    block: Vec<u8>,
+    len: u64,
+}
+
+impl Sha256 {
+    pub fn new() -> Self {
+        Self {
+            h: [
+                0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a,
+                0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+            ],
+            block: Vec::with_capacity(64),
+            len: 0,
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.len += data.len() as u64;
+        while !data.is_empty() {
+            let take = (64 - self.block.len()).min(data.len());
+            self.block.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.block.len() == 64 {
+                let block = std::mem::replace(&mut self.block, Vec::with_capacity(64));
+                self.compress(&block);
+            }
+        }
+    }
+
+    /// Pad, process the final block(s) and return the hex digest.
+    pub fn finish(mut self) -> String {
+        let bit_len = self.len * 8;
+        let mut tail = std::mem::take(&mut self.block);
+        tail.push(0x80);
+        while (tail.len() % 64) != 56 {
+            tail.push(0);
+        }
+        tail.extend_from_slice(&bit_len.to_be_bytes());
+        for chunk in tail.chunks(64) {
+            self.compress(chunk);
+        }
+
+        self.h
+            .iter()
+            .map(|x| format!("{:08x}", x))
+            .collect::<Vec<_>>()
+            .join("")
+    }
+
+    /// Process one 512-bit block.
+    fn compress(&mut self, chunk: &[u8]) {
+        let h = &mut self.h;
         let mut w = [0u32; 64];
         for i in 0..16 {
             w[i] = u32::from_be_bytes([
@@ -204,11 +245,13 @@ pub fn sha256_hex(data: &[u8]) -> String {
         h[6] = h[6].wrapping_add(g);
         h[7] = h[7].wrapping_add(hh);
     }
+}
 
-    h.iter()
-        .map(|x| format!("{:08x}", x))
-        .collect::<Vec<_>>()
-        .join("")
+/// Compute SHA-256 hex digest of a byte slice.
+pub fn sha256_hex(data: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    hasher.finish()
 }
 
 /// Get mtime as unix timestamp (seconds since epoch).
@@ -240,6 +283,39 @@ fn file_type_str(metadata: &fs::Metadata) -> &'static str {
     }
 }
 
+/// How much of a file the binary check looks at.
+const BINARY_SNIFF_BYTES: usize = 8 * 1024;
+
+/// Text/binary heuristic: any NUL byte, or more than 10% of the bytes not
+/// forming valid UTF-8, means binary.
+pub(crate) fn is_binary(sample: &[u8]) -> bool {
+    if sample.contains(&0) {
+        return true;
+    }
+    let mut invalid = 0;
+    let mut rest = sample;
+    while let Err(e) = std::str::from_utf8(rest) {
+        match e.error_len() {
+            Some(len) => {
+                invalid += len;
+                rest = &rest[e.valid_up_to() + len..];
+            }
+            // Sample ends partway through a multi-byte character
+            None => break,
+        }
+    }
+    invalid * 10 > sample.len()
+}
+
+/// Run [`is_binary`] on the first [`BINARY_SNIFF_BYTES`] of a file.
+fn sniff_binary(path: &Path) -> io::Result<bool> {
+    let mut sample = Vec::with_capacity(BINARY_SNIFF_BYTES);
+    fs::File::open(path)?
+        .take(BINARY_SNIFF_BYTES as u64)
+        .read_to_end(&mut sample)?;
+    Ok(is_binary(&sample))
+}
+
 fn map_io_error(e: &io::Error) -> (i32, String) {
     match e.kind() {
         io::ErrorKind::NotFound => (ERR_NOT_FOUND, e.to_string()),
@@ -278,6 +354,17 @@ pub fn read_file(params: ReadFileParams) -> Result<ReadFileResult, (i32, String)
     file.read_to_end(&mut content_bytes)
         .map_err(|e| map_io_error(&e))?;
 
+    let sample = &content_bytes[..content_bytes.len().min(BINARY_SNIFF_BYTES)];
+    if !params.binary && is_binary(sample) {
+        return Err((
+            ERR_INVALID_PARAMS,
+            format!(
+                "Binary file: {} (pass binary=true to read it as base64)",
+                params.path
+            ),
+        ));
+    }
+
     let hash = sha256_hex(&content_bytes);
     let mtime = mtime_secs(&metadata);
 
@@ -298,6 +385,16 @@ pub fn read_file(params: ReadFileParams) -> Result<ReadFileResult, (i32, String)
         }
     }
 
+    if params.binary {
+        return Ok(ReadFileResult {
+            content: base64_encode(&content_bytes),
+            hash,
+            size,
+            mtime,
+            encoding: "base64".to_string(),
+        });
+    }
+
     let content = String::from_utf8_lossy(&content_bytes).into_owned();
 
     Ok(ReadFileResult {
@@ -309,6 +406,53 @@ pub fn read_file(params: ReadFileParams) -> Result<ReadFileResult, (i32, String)
     })
 }
 
+/// Smallest and largest `chunk_size` accepted by fs/readFileStream.
+const MIN_STREAM_CHUNK: usize = 4 * 1024;
+const MAX_STREAM_CHUNK: usize = 4 * 1024 * 1024;
+
+/// Read a file of any size, handing it to `on_chunk` piece by piece.
+///
+/// Unlike [`read_file`] nothing is buffered beyond one chunk, there is no
+/// size cap and binary content is passed through as-is. Stops with
+/// `ERR_CANCELLED` between chunks once `cancel` is set.
+pub fn read_file_stream(
+    params: ReadFileStreamParams,
+    cancel: &CancelFlag,
+    mut on_chunk: impl FnMut(&[u8]),
+) -> Result<ReadFileStreamResult, (i32, String)> {
+    let path = resolve_path(&params.path);
+    let mut file = fs::File::open(&path).map_err(|e| map_io_error(&e))?;
+    let metadata = file.metadata().map_err(|e| map_io_error(&e))?;
+    if !metadata.is_file() {
+        return Err((ERR_IO, format!("Not a regular file: {}", params.path)));
+    }
+
+    let mut buf = vec![0u8; params.chunk_size.clamp(MIN_STREAM_CHUNK, MAX_STREAM_CHUNK)];
+    let mut hasher = Sha256::new();
+    let mut size = 0u64;
+    let mut chunks = 0u64;
+    loop {
+        cancel.check()?;
+        let n = match file.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(map_io_error(&e)),
+        };
+        hasher.update(&buf[..n]);
+        on_chunk(&buf[..n]);
+        size += n as u64;
+        chunks += 1;
+    }
+
+    Ok(ReadFileStreamResult {
+        hash: hasher.finish(),
+        size,
+        mtime: mtime_secs(&metadata),
+        chunks,
+    })
+}
+
 /// Write file content with POSIX atomic rename.
 ///
 /// Strategy:
@@ -317,7 +461,7 @@ pub fn read_file(params: ReadFileParams) -> Result<ReadFileResult, (i32, String)
 ///
 /// This is the key advantage over SFTP: POSIX rename **always** overwrites
 /// the target atomically, no need for remove-then-rename workarounds.
-pub fn write_file(params: WriteFileParams) -> Result<WriteFileResult, (i32, String)> {
+pub fn write_file(params: WriteFileParams) -> Result<WriteFileResult, WriteFileError> {
     let path = resolve_path(&params.path);
 
     // Optimistic lock: if caller provided expected hash, verify it
@@ -330,18 +474,41 @@ pub fn write_file(params: WriteFileParams) -> Result<WriteFileResult, (i32, Stri
                 }
                 let current_hash = sha256_hex(&existing);
                 if &current_hash != expected_hash {
-                    return Err((
-                        ERR_CONFLICT,
-                        format!(
-                            "CONFLICT: File modified externally (expected hash: {}, actual: {})",
-                            expected_hash, current_hash
-                        ),
-                    ));
+                    return Err(WriteFileError::Conflict(WriteConflict {
+                        expected_hash: expected_hash.clone(),
+                        current_hash,
+                    }));
                 }
             }
         }
     }
 
+    write_file_unchecked(&path, params).map_err(|(code, msg)| WriteFileError::Failed(code, msg))
+}
+
+/// Why fs/writeFile failed.
+#[derive(Debug)]
+pub enum WriteFileError {
+    /// `expect_hash` did not match the file on disk; nothing was written.
+    Conflict(WriteConflict),
+    /// Any other failure, as `(code, message)`.
+    Failed(i32, String),
+}
+
+impl std::fmt::Display for WriteConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "CONFLICT: File modified externally (expected hash: {}, actual: {})",
+            self.expected_hash, self.current_hash
+        )
+    }
+}
+
+fn write_file_unchecked(
+    path: &Path,
+    params: WriteFileParams,
+) -> Result<WriteFileResult, (i32, String)> {
     // Ensure parent directory exists
     if let Some(parent) = path.parent() {
         if !parent.exists() {
@@ -389,19 +556,19 @@ pub fn write_file(params: WriteFileParams) -> Result<WriteFileResult, (i32, Stri
     }
 
     // Preserve original permissions if the file already exists
-    if let Ok(original_meta) = fs::metadata(&path) {
+    if let Ok(original_meta) = fs::metadata(path) {
         let _ = fs::set_permissions(&temp_path, original_meta.permissions());
     }
 
     // Atomic rename: POSIX guarantees this overwrites the target
-    fs::rename(&temp_path, &path).map_err(|e| {
+    fs::rename(&temp_path, path).map_err(|e| {
         // Clean up temp file on failure
         let _ = fs::remove_file(&temp_path);
         map_io_error(&e)
     })?;
 
     // Read back metadata
-    let metadata = fs::metadata(&path).map_err(|e| map_io_error(&e))?;
+    let metadata = fs::metadata(path).map_err(|e| map_io_error(&e))?;
     let hash = sha256_hex(&content_bytes);
 
     Ok(WriteFileResult {
@@ -416,13 +583,19 @@ pub fn write_file(params: WriteFileParams) -> Result<WriteFileResult, (i32, Stri
 pub fn stat(params: StatParams) -> Result<StatResult, (i32, String)> {
     let path = resolve_path(&params.path);
 
-    match fs::symlink_metadata(path) {
+    match fs::symlink_metadata(&path) {
         Ok(metadata) => Ok(StatResult {
             exists: true,
             file_type: Some(file_type_str(&metadata).to_string()),
             size: Some(metadata.len()),
             mtime: Some(mtime_secs(&metadata)),
             permissions: Some(perms_octal(&metadata)),
+            // Unreadable files just go unclassified
+            is_binary: if params.detect_binary && metadata.is_file() {
+                sniff_binary(&path).ok()
+            } else {
+                None
+            },
         }),
         Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(StatResult {
             exists: false,
@@ -430,6 +603,7 @@ pub fn stat(params: StatParams) -> Result<StatResult, (i32, String)> {
             size: None,
             mtime: None,
             permissions: None,
+            is_binary: None,
         }),
         Err(e) => Err(map_io_error(&e)),
     }
@@ -466,6 +640,7 @@ pub fn list_dir(params: ListDirParams) -> Result<Vec<FileEntry>, (i32, String)>
             permissions: Some(perms_octal(&metadata)),
             children: None,
             truncated: false,
+            continued: false,
         });
     }
 
@@ -483,121 +658,252 @@ pub fn list_dir(params: ListDirParams) -> Result<Vec<FileEntry>, (i32, String)>
     Ok(entries)
 }
 
+/// Directories that are listed but never descended into.
+const TREE_SKIP_DIRS: &[&str] = &[".git", "node_modules", ".hg", "__pycache__"];
+
 /// Recursive directory listing with depth and count limits.
-pub fn list_tree(params: ListTreeParams) -> Result<ListTreeResult, (i32, String)> {
+///
+/// Traversal is pre-order over a stable sort (directories first, then
+/// case-insensitive name), so a page that hits `max_entries` can hand out a
+/// `next_cursor` naming the last entry it returned. Passing that cursor back
+/// resumes right after it. In continuation pages, ancestors of the resume
+/// point are repeated with `continued: true` purely as containers for their
+/// new children; clients merge them by `path`.
+///
+/// Stops with `ERR_CANCELLED` before the next directory once `cancel` is set.
+pub fn list_tree(
+    params: ListTreeParams,
+    cancel: &CancelFlag,
+) -> Result<ListTreeResult, (i32, String)> {
     let path = resolve_path(&params.path);
-    let mut count: u32 = 0;
-    let entries = list_tree_recursive(&path, 0, params.max_depth, params.max_entries, &mut count)?;
-    let truncated = count >= params.max_entries;
+    let resume = match params.cursor.as_deref() {
+        Some(raw) => Some(TreeCursor::decode(raw).ok_or_else(|| {
+            (
+                ERR_INVALID_PARAMS,
+                format!("Invalid listTree cursor: {}", raw),
+            )
+        })?),
+        None => None,
+    };
+
+    let mut walk = TreeWalk {
+        max_depth: params.max_depth,
+        max_entries: params.max_entries,
+        count: 0,
+        truncated: false,
+        stack: Vec::new(),
+        last_emitted: None,
+        gitignore: params
+            .respect_gitignore
+            .then(|| GitignoreStack::for_root(&path)),
+        cancel: cancel.clone(),
+    };
+    let entries = walk.walk(&path, 0, resume.as_ref().map(|c| c.as_resume()))?;
+
+    let next_cursor = if walk.truncated {
+        walk.last_emitted.map(|c| c.encode())
+    } else {
+        None
+    };
+
     Ok(ListTreeResult {
         entries,
-        truncated,
-        total_scanned: count,
+        truncated: walk.truncated,
+        total_scanned: walk.count,
+        next_cursor,
     })
 }
 
-fn list_tree_recursive(
-    dir: &Path,
-    depth: u32,
+/// Position of an entry in the listTree traversal: its path components
+/// relative to the listed root, plus whether the final component is a
+/// directory (needed to place it in the dirs-first order if it disappears).
+struct TreeCursor {
+    components: Vec<String>,
+    is_dir: bool,
+}
+
+impl TreeCursor {
+    /// Opaque wire format: `d:` or `f:` followed by the `/`-joined components.
+    fn encode(&self) -> String {
+        let kind = if self.is_dir { 'd' } else { 'f' };
+        format!("{}:{}", kind, self.components.join("/"))
+    }
+
+    fn decode(raw: &str) -> Option<Self> {
+        let (kind, rel) = raw.split_once(':')?;
+        let is_dir = match kind {
+            "d" => true,
+            "f" => false,
+            _ => return None,
+        };
+        let components: Vec<String> = rel.split('/').map(str::to_string).collect();
+        if components
+            .iter()
+            .any(|c| c.is_empty() || c == "." || c == "..")
+        {
+            return None;
+        }
+        Some(Self { components, is_dir })
+    }
+
+    fn as_resume(&self) -> Resume<'_> {
+        Resume {
+            components: &self.components,
+            is_dir: self.is_dir,
+        }
+    }
+}
+
+/// Borrowed view of the remaining cursor path while descending.
+#[derive(Clone, Copy)]
+struct Resume<'a> {
+    components: &'a [String],
+    /// Kind of the final component
+    is_dir: bool,
+}
+
+/// Sort key shared by traversal and cursor comparison.
+fn tree_order(a_is_dir: bool, a_name: &str, b_is_dir: bool, b_name: &str) -> std::cmp::Ordering {
+    b_is_dir
+        .cmp(&a_is_dir)
+        .then_with(|| a_name.to_lowercase().cmp(&b_name.to_lowercase()))
+        .then_with(|| a_name.cmp(b_name))
+}
+
+/// Traversal state for one listTree page.
+struct TreeWalk {
     max_depth: u32,
     max_entries: u32,
-    count: &mut u32,
-) -> Result<Vec<FileEntry>, (i32, String)> {
-    let read_dir = fs::read_dir(dir).map_err(|e| map_io_error(&e))?;
-    let mut entries = Vec::new();
-    let mut dir_truncated = false;
+    /// Entries emitted in this page
+    count: u32,
+    /// Budget ran out with entries still unvisited
+    truncated: bool,
+    /// Components of the directory currently being walked
+    stack: Vec<String>,
+    /// Cursor of the most recently emitted entry
+    last_emitted: Option<TreeCursor>,
+    /// Active `.gitignore` rules when `respect_gitignore` is set
+    gitignore: Option<GitignoreStack>,
+    cancel: CancelFlag,
+}
 
-    for entry_result in read_dir {
-        if *count >= max_entries {
-            // Budget exhausted — mark this directory as truncated so the
-            // frontend knows there are more items it hasn't seen.
-            dir_truncated = true;
-            break;
+impl TreeWalk {
+    fn walk(
+        &mut self,
+        dir: &Path,
+        depth: u32,
+        resume: Option<Resume<'_>>,
+    ) -> Result<Vec<FileEntry>, (i32, String)> {
+        self.cancel.check()?;
+        let read_dir = fs::read_dir(dir).map_err(|e| map_io_error(&e))?;
+
+        // Collect and sort first: the cursor is only meaningful over a
+        // deterministic order, which read_dir does not provide.
+        let mut children: Vec<(String, PathBuf, fs::Metadata)> = read_dir
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let path = entry.path();
+                let metadata = fs::symlink_metadata(&path).ok()?;
+                Some((
+                    entry.file_name().to_string_lossy().to_string(),
+                    path,
+                    metadata,
+                ))
+            })
+            .collect();
+        children.sort_by(|(a_name, _, a_meta), (b_name, _, b_meta)| {
+            tree_order(a_meta.is_dir(), a_name, b_meta.is_dir(), b_name)
+        });
+
+        let pushed = self.gitignore.as_mut().is_some_and(|gi| gi.push_dir(dir));
+        if let Some(gi) = &self.gitignore {
+            children.retain(|(_, path, meta)| !gi.is_ignored(path, meta.is_dir()));
         }
 
-        let entry = match entry_result {
-            Ok(e) => e,
-            Err(_) => continue,
-        };
+        let mut entries = Vec::new();
 
-        let name = entry.file_name().to_string_lossy().to_string();
+        for (name, entry_path, metadata) in children {
+            if self.truncated {
+                break;
+            }
 
-        // Skip hidden dirs that are typically large/irrelevant
-        if name == ".git" || name == "node_modules" || name == ".hg" || name == "__pycache__" {
-            // Still include the entry, but don't recurse into it
-            let entry_path = entry.path();
-            if let Ok(metadata) = fs::symlink_metadata(&entry_path) {
-                *count += 1;
-                entries.push(FileEntry {
-                    name,
-                    path: entry_path.to_string_lossy().to_string(),
-                    file_type: file_type_str(&metadata).to_string(),
-                    size: metadata.len(),
-                    mtime: Some(mtime_secs(&metadata)),
-                    permissions: Some(perms_octal(&metadata)),
-                    children: None, // Don't recurse
-                    truncated: false,
-                });
+            // Where does this entry sit relative to the resume point?
+            let mut descend_resume = None;
+            let mut already_sent = false;
+            if let Some(r) = resume {
+                let target_is_dir = r.components.len() > 1 || r.is_dir;
+                match tree_order(metadata.is_dir(), &name, target_is_dir, &r.components[0]) {
+                    std::cmp::Ordering::Less => continue,
+                    std::cmp::Ordering::Equal => {
+                        already_sent = true;
+                        if r.components.len() > 1 {
+                            descend_resume = Some(Resume {
+                                components: &r.components[1..],
+                                is_dir: r.is_dir,
+                            });
+                        }
+                    }
+                    std::cmp::Ordering::Greater => {}
+                }
             }
-            continue;
-        }
 
-        let entry_path = entry.path();
-        let metadata = match fs::symlink_metadata(&entry_path) {
-            Ok(m) => m,
-            Err(_) => continue,
-        };
+            if !already_sent {
+                if self.count >= self.max_entries {
+                    // Budget exhausted with this entry still unseen
+                    self.truncated = true;
+                    break;
+                }
+                self.count += 1;
+                let mut components = self.stack.clone();
+                components.push(name.clone());
+                self.last_emitted = Some(TreeCursor {
+                    components,
+                    is_dir: metadata.is_dir(),
+                });
+            }
 
-        *count += 1;
+            let can_descend = metadata.is_dir()
+                && depth < self.max_depth
+                && !TREE_SKIP_DIRS.contains(&name.as_str());
 
-        let (children, child_truncated) = if metadata.is_dir() && depth < max_depth {
-            match list_tree_recursive(&entry_path, depth + 1, max_depth, max_entries, count) {
-                Ok(c) => {
+            let (children, child_truncated) = if can_descend {
+                self.stack.push(name.clone());
+                let result = self.walk(&entry_path, depth + 1, descend_resume);
+                self.stack.pop();
+                match result {
                     // If the global budget was hit during recursion, this
                     // child dir's listing is incomplete.
-                    let was_truncated = *count >= max_entries;
-                    (Some(c), was_truncated)
-                },
-                Err(_) => (None, false), // Permission errors etc. — just omit children
-            }
-        } else {
-            (None, false)
-        };
+                    Ok(c) => (Some(c), self.truncated),
+                    Err(e) if e.0 == ERR_CANCELLED => return Err(e),
+                    Err(_) => (None, false), // Permission errors etc. — just omit children
+                }
+            } else {
+                (None, false)
+            };
 
-        entries.push(FileEntry {
-            name,
-            path: entry_path.to_string_lossy().to_string(),
-            file_type: file_type_str(&metadata).to_string(),
-            size: metadata.len(),
-            mtime: Some(mtime_secs(&metadata)),
-            permissions: Some(perms_octal(&metadata)),
-            children,
-            truncated: child_truncated,
-        });
-    }
+            if already_sent && children.as_ref().is_none_or(|c| c.is_empty()) {
+                // Nothing new below an entry the client already has
+                continue;
+            }
 
-    // Sort: directories first, then alphabetical
-    entries.sort_by(|a, b| {
-        let a_is_dir = a.file_type == "directory";
-        let b_is_dir = b.file_type == "directory";
-        match (a_is_dir, b_is_dir) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            entries.push(FileEntry {
+                name,
+                path: entry_path.to_string_lossy().to_string(),
+                file_type: file_type_str(&metadata).to_string(),
+                size: metadata.len(),
+                mtime: Some(mtime_secs(&metadata)),
+                permissions: Some(perms_octal(&metadata)),
+                children,
+                truncated: child_truncated,
+                continued: already_sent,
+            });
         }
-    });
 
-    // If this directory itself was truncated (budget expired mid-iteration),
-    // propagate a sentinel to the caller via the top-level truncated flag.
-    // The per-entry truncated field on the last directory entries handles the
-    // intermediate levels; `dir_truncated` is for the CURRENT level.
-    if dir_truncated && !entries.is_empty() {
-        // Mark the last entry as a hint — but more importantly, the caller's
-        // ListTreeResult.truncated will already be true. This per-dir flag
-        // helps the frontend identify WHICH directories were incomplete.
+        if let Some(gi) = self.gitignore.as_mut() {
+            gi.pop_dir(pushed);
+        }
+        Ok(entries)
     }
-
-    Ok(entries)
 }
 
 /// Create directory (optionally recursive).
@@ -647,19 +953,67 @@ pub fn chmod(params: ChmodParams) -> Result<(), (i32, String)> {
 }
 
 /// Search files using grep-like functionality (pure Rust, no external grep).
-pub fn grep(params: GrepParams) -> Result<Vec<GrepMatch>, (i32, String)> {
+///
+/// Stops with `ERR_CANCELLED` before the next directory once `cancel` is set.
+///
+/// Collection stops at `max_results` matches, or once the serialized result
+/// would exceed `max_bytes`, so an oversized result is never built.
+pub fn grep(
+    params: GrepParams,
+    cancel: &CancelFlag,
+    max_bytes: usize,
+) -> Result<Vec<GrepMatch>, (i32, String)> {
     let root = resolve_path(&params.path);
-    let mut results = Vec::new();
-    grep_recursive(&root, &params, &mut results)?;
-    Ok(results)
+    let mut results = GrepResults {
+        matches: Vec::new(),
+        // The enclosing `[]`
+        bytes: 2,
+        max_results: params.max_results as usize,
+        max_bytes,
+    };
+    let mut gitignore = params
+        .respect_gitignore
+        .then(|| GitignoreStack::for_root(&root));
+    grep_recursive(&root, &params, cancel, &mut gitignore, &mut results)?;
+    Ok(results.matches)
+}
+
+/// Matches collected by [`grep`] so far.
+struct GrepResults {
+    matches: Vec<GrepMatch>,
+    /// Serialized size of `matches` as a JSON array.
+    bytes: usize,
+    max_results: usize,
+    max_bytes: usize,
+}
+
+impl GrepResults {
+    fn is_full(&self) -> bool {
+        self.matches.len() >= self.max_results || self.bytes >= self.max_bytes
+    }
+
+    /// Add a match, or stop collecting if it would not fit in `max_bytes`.
+    fn push(&mut self, m: GrepMatch) {
+        // Plus the separating comma
+        let size = serde_json::to_string(&m).map_or(0, |json| json.len()) + 1;
+        if self.bytes + size > self.max_bytes {
+            self.bytes = self.max_bytes;
+            return;
+        }
+        self.bytes += size;
+        self.matches.push(m);
+    }
 }
 
 fn grep_recursive(
     dir: &Path,
     params: &GrepParams,
-    results: &mut Vec<GrepMatch>,
+    cancel: &CancelFlag,
+    gitignore: &mut Option<GitignoreStack>,
+    results: &mut GrepResults,
 ) -> Result<(), (i32, String)> {
-    if results.len() >= params.max_results as usize {
+    cancel.check()?;
+    if results.is_full() {
         return Ok(());
     }
 
@@ -668,8 +1022,23 @@ fn grep_recursive(
         Err(_) => return Ok(()), // Skip unreadable directories
     };
 
+    let pushed = gitignore.as_mut().is_some_and(|gi| gi.push_dir(dir));
+    let result = grep_entries(read_dir, params, cancel, gitignore, results);
+    if let Some(gi) = gitignore.as_mut() {
+        gi.pop_dir(pushed);
+    }
+    result
+}
+
+fn grep_entries(
+    read_dir: fs::ReadDir,
+    params: &GrepParams,
+    cancel: &CancelFlag,
+    gitignore: &mut Option<GitignoreStack>,
+    results: &mut GrepResults,
+) -> Result<(), (i32, String)> {
     for entry_result in read_dir {
-        if results.len() >= params.max_results as usize {
+        if results.is_full() {
             return Ok(());
         }
 
@@ -697,8 +1066,16 @@ fn grep_recursive(
             Err(_) => continue,
         };
 
+        // Gitignore rules only see what the explicit list let through
+        if gitignore
+            .as_ref()
+            .is_some_and(|gi| gi.is_ignored(&path, metadata.is_dir()))
+        {
+            continue;
+        }
+
         if metadata.is_dir() {
-            grep_recursive(&path, params, results)?;
+            grep_recursive(&path, params, cancel, gitignore, results)?;
         } else if metadata.is_file() && metadata.len() < 1_000_000 {
             // Only search files < 1MB
             grep_file(&path, params, results);
@@ -708,7 +1085,7 @@ fn grep_recursive(
     Ok(())
 }
 
-fn grep_file(path: &Path, params: &GrepParams, results: &mut Vec<GrepMatch>) {
+fn grep_file(path: &Path, params: &GrepParams, results: &mut GrepResults) {
     let content = match fs::read_to_string(path) {
         Ok(c) => c,
         Err(_) => return, // Skip binary/unreadable files
@@ -725,7 +1102,7 @@ fn grep_file(path: &Path, params: &GrepParams, results: &mut Vec<GrepMatch>) {
     };
 
     for (line_idx, line) in content.lines().enumerate() {
-        if results.len() >= params.max_results as usize {
+        if results.is_full() {
             return;
         }
 
@@ -747,7 +1124,7 @@ fn grep_file(path: &Path, params: &GrepParams, results: &mut Vec<GrepMatch>) {
                 });
                 // Move past this match to find the next one
                 search_from += col + pattern.len().max(1);
-                if results.len() >= params.max_results as usize {
+                if results.is_full() {
                     return;
                 }
             } else {
@@ -818,6 +1195,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_sha256_incremental_matches_one_shot() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i % 251) as u8).collect();
+        let mut hasher = Sha256::new();
+        for piece in data.chunks(37) {
+            hasher.update(piece);
+        }
+        assert_eq!(hasher.finish(), sha256_hex(&data));
+    }
+
+    #[test]
+    fn test_read_file_stream_chunks_whole_file() {
+        let root = make_tree_root("stream");
+        let path = root.join("big.bin");
+        let data: Vec<u8> = (0..10_000u32).map(|i| (i % 256) as u8).collect();
+        fs::write(&path, &data).unwrap();
+
+        let mut received = Vec::new();
+        let mut pieces = 0;
+        let result = read_file_stream(
+            ReadFileStreamParams {
+                path: path.to_string_lossy().to_string(),
+                chunk_size: 1,
+            },
+            &CancelFlag::default(),
+            |chunk| {
+                assert!(chunk.len() <= MIN_STREAM_CHUNK);
+                received.extend_from_slice(chunk);
+                pieces += 1;
+            },
+        )
+        .unwrap();
+
+        assert_eq!(received, data);
+        assert_eq!(result.size, data.len() as u64);
+        assert_eq!(result.chunks, pieces);
+        assert!(pieces >= 3);
+        assert_eq!(result.hash, sha256_hex(&data));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
     #[test]
     fn test_sha256_hello() {
         let hash = sha256_hex(b"hello");
@@ -826,4 +1245,407 @@ mod tests {
             "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
         );
     }
+
+    /// Temp dir with 2 subdirectories and 10 entries in total:
+    /// `alpha/{a1,a2,a3}`, `beta/{b1,b2}`, `f1`, `f2`, `f3`.
+    fn make_tree(tag: &str) -> PathBuf {
+        let root = make_tree_root(tag);
+        for (dir, files) in [
+            ("alpha", &["a1", "a2", "a3"][..]),
+            ("beta", &["b1", "b2"][..]),
+        ] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            for f in files {
+                fs::write(root.join(dir).join(f), b"x").unwrap();
+            }
+        }
+        for f in ["f1", "f2", "f3"] {
+            fs::write(root.join(f), b"x").unwrap();
+        }
+        root
+    }
+
+    /// Collect new (non-`continued`) entry paths, depth-first.
+    fn collect_paths(entries: &[FileEntry], out: &mut Vec<String>) {
+        for e in entries {
+            if !e.continued {
+                out.push(e.path.clone());
+            }
+            if let Some(children) = &e.children {
+                collect_paths(children, out);
+            }
+        }
+    }
+
+    fn tree_page(root: &Path, max_entries: u32, cursor: Option<String>) -> ListTreeResult {
+        list_tree(
+            ListTreeParams {
+                path: root.to_string_lossy().to_string(),
+                max_depth: 3,
+                max_entries,
+                cursor,
+                respect_gitignore: false,
+            },
+            &CancelFlag::default(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_list_tree_paginates_across_two_calls() {
+        let root = make_tree("paged");
+
+        let first = tree_page(&root, 6, None);
+        assert!(first.truncated);
+        assert_eq!(first.total_scanned, 6);
+        let cursor = first
+            .next_cursor
+            .clone()
+            .expect("first page must have a cursor");
+
+        let second = tree_page(&root, 6, Some(cursor));
+        assert!(!second.truncated);
+        assert!(second.next_cursor.is_none());
+        assert_eq!(second.total_scanned, 4);
+
+        let mut paths = Vec::new();
+        collect_paths(&first.entries, &mut paths);
+        collect_paths(&second.entries, &mut paths);
+
+        let expected: Vec<String> = [
+            "alpha", "alpha/a1", "alpha/a2", "alpha/a3", "beta", "beta/b1", "beta/b2", "f1", "f2",
+            "f3",
+        ]
+        .iter()
+        .map(|p| root.join(p).to_string_lossy().to_string())
+        .collect();
+        assert_eq!(paths, expected);
+
+        // beta was sent on page one; page two repeats it only as a container
+        let beta = second.entries.iter().find(|e| e.name == "beta").unwrap();
+        assert!(beta.continued);
+        assert_eq!(beta.children.as_ref().unwrap().len(), 1);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_list_tree_small_pages_cover_everything_once() {
+        let root = make_tree("small-pages");
+        let full = tree_page(&root, 5000, None);
+        assert!(!full.truncated);
+        let mut expected = Vec::new();
+        collect_paths(&full.entries, &mut expected);
+
+        let mut paths = Vec::new();
+        let mut cursor = None;
+        let mut pages = 0;
+        loop {
+            let page = tree_page(&root, 3, cursor);
+            collect_paths(&page.entries, &mut paths);
+            pages += 1;
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+
+        assert_eq!(pages, 4);
+        assert_eq!(paths, expected);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_list_tree_rejects_bad_cursor() {
+        let root = make_tree("bad-cursor");
+        let err = list_tree(
+            ListTreeParams {
+                path: root.to_string_lossy().to_string(),
+                max_depth: 3,
+                max_entries: 10,
+                cursor: Some("x:../etc".to_string()),
+                respect_gitignore: false,
+            },
+            &CancelFlag::default(),
+        )
+        .unwrap_err();
+        assert_eq!(err.0, ERR_INVALID_PARAMS);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_write_with_stale_hash_is_conflict() {
+        let root = make_tree_root("write-conflict");
+        let path = root.join("doc.txt").to_string_lossy().to_string();
+        let write = |content: &str, expect_hash: Option<String>| {
+            write_file(WriteFileParams {
+                path: path.clone(),
+                content: content.to_string(),
+                expect_hash,
+                encoding: "plain".to_string(),
+            })
+        };
+
+        let first = write("v1", None).unwrap();
+        let stale = first.hash.clone();
+        // Someone else saves in between
+        let second = write("v2", Some(stale.clone())).unwrap();
+
+        match write("v3", Some(stale.clone())) {
+            Err(WriteFileError::Conflict(conflict)) => {
+                assert_eq!(conflict.expected_hash, stale);
+                assert_eq!(conflict.current_hash, second.hash);
+            }
+            other => panic!("expected conflict, got {:?}", other),
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "v2");
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    /// A PNG header: NUL bytes right away.
+    const PNG_BYTES: &[u8] = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR\x00\x00\x01\x00";
+
+    #[test]
+    fn test_is_binary_heuristic() {
+        assert!(!is_binary(b"fn main() {\n    println!(\"hi\");\n}\n"));
+        assert!(!is_binary("héllo 世界\n".as_bytes()));
+        assert!(!is_binary(b""));
+        // Sample cut in the middle of a 3-byte character
+        assert!(!is_binary(&"abc世".as_bytes()[..4]));
+        // A stray Latin-1 byte in otherwise ASCII text
+        assert!(!is_binary(b"caf\xe9 au lait, s'il vous plait"));
+
+        assert!(is_binary(PNG_BYTES));
+        assert!(is_binary(&[0xff, 0xfe, 0xc3, 0x28, 0xa0, 0xa1, b'a', b'b']));
+    }
+
+    #[test]
+    fn test_read_file_refuses_binary_unless_asked() {
+        let root = make_tree_root("binary-read");
+        let png = root.join("logo.png");
+        fs::write(&png, PNG_BYTES).unwrap();
+        fs::write(root.join("notes.txt"), "plain text\n").unwrap();
+        let read = |name: &str, binary: bool| {
+            read_file(ReadFileParams {
+                path: root.join(name).to_string_lossy().to_string(),
+                max_size: 1024,
+                binary,
+            })
+        };
+
+        let (code, msg) = read("logo.png", false).unwrap_err();
+        assert_eq!(code, ERR_INVALID_PARAMS);
+        assert!(msg.contains("Binary file"));
+
+        let raw = read("logo.png", true).unwrap();
+        assert_eq!(raw.encoding, "base64");
+        assert_eq!(base64_decode(&raw.content).unwrap(), PNG_BYTES);
+        assert_eq!(raw.hash, sha256_hex(PNG_BYTES));
+
+        let text = read("notes.txt", false).unwrap();
+        assert_eq!(text.encoding, "plain");
+        assert_eq!(text.content, "plain text\n");
+
+        let stat_of = |name: &str, detect_binary| {
+            stat(StatParams {
+                path: root.join(name).to_string_lossy().to_string(),
+                detect_binary,
+            })
+            .unwrap()
+            .is_binary
+        };
+        assert_eq!(stat_of("logo.png", true), Some(true));
+        assert_eq!(stat_of("notes.txt", true), Some(false));
+        assert_eq!(stat_of("", true), None);
+        // Content is only read when asked for
+        assert_eq!(stat_of("logo.png", false), None);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    /// Project whose `.gitignore` excludes `build/` and `*.log`, with a nested
+    /// `src/.gitignore` re-including `keep.log`. Every file contains "needle".
+    fn make_gitignore_tree(tag: &str) -> PathBuf {
+        let root = make_tree_root(tag);
+        for (file, content) in [
+            (".gitignore", "build/\n*.log\n!vendor/\n"),
+            ("build/out.txt", "needle"),
+            ("src/.gitignore", "!keep.log\n"),
+            ("src/main.rs", "needle"),
+            ("src/debug.log", "needle"),
+            ("src/keep.log", "needle"),
+            ("vendor/lib.rs", "needle"),
+        ] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        root
+    }
+
+    fn make_tree_root(tag: &str) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("oxideterm-agent-{}-{}", tag, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    fn relative_paths(root: &Path, entries: &[FileEntry]) -> Vec<String> {
+        let mut paths = Vec::new();
+        collect_paths(entries, &mut paths);
+        paths
+            .iter()
+            .map(|p| {
+                Path::new(p)
+                    .strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_list_tree_respects_gitignore() {
+        let root = make_gitignore_tree("gitignore-tree");
+        let list = |respect_gitignore| {
+            list_tree(
+                ListTreeParams {
+                    path: root.to_string_lossy().to_string(),
+                    max_depth: 3,
+                    max_entries: 100,
+                    cursor: None,
+                    respect_gitignore,
+                },
+                &CancelFlag::default(),
+            )
+            .unwrap()
+        };
+
+        let filtered = relative_paths(&root, &list(true).entries);
+        assert_eq!(
+            filtered,
+            [
+                "src",
+                "src/.gitignore",
+                "src/keep.log",
+                "src/main.rs",
+                "vendor",
+                "vendor/lib.rs",
+                ".gitignore",
+            ]
+        );
+
+        // Off by default: everything is listed
+        let all = relative_paths(&root, &list(false).entries);
+        assert!(all.contains(&"build/out.txt".to_string()));
+        assert!(all.contains(&"src/debug.log".to_string()));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_grep_explicit_ignore_beats_gitignore() {
+        let root = make_gitignore_tree("gitignore-grep");
+        let matches = grep(
+            GrepParams {
+                pattern: "needle".to_string(),
+                path: root.to_string_lossy().to_string(),
+                is_regex: false,
+                case_sensitive: true,
+                max_results: 100,
+                // `!vendor/` in .gitignore must not re-include this
+                ignore: vec!["vendor".to_string()],
+                respect_gitignore: true,
+            },
+            &CancelFlag::default(),
+            usize::MAX,
+        )
+        .unwrap();
+
+        let mut files: Vec<String> = matches
+            .iter()
+            .map(|m| {
+                Path::new(&m.path)
+                    .strip_prefix(&root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+        files.sort();
+        assert_eq!(files, ["src/keep.log", "src/main.rs"]);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_grep_stops_at_byte_budget() {
+        let root = make_tree("grep-budget");
+        let search = |max_bytes| {
+            grep(
+                GrepParams {
+                    pattern: "x".to_string(),
+                    path: root.to_string_lossy().to_string(),
+                    is_regex: false,
+                    case_sensitive: true,
+                    max_results: 100,
+                    ignore: Vec::new(),
+                    respect_gitignore: false,
+                },
+                &CancelFlag::default(),
+                max_bytes,
+            )
+            .unwrap()
+        };
+
+        let all = search(usize::MAX);
+        assert_eq!(all.len(), 8);
+        let budget = serde_json::to_string(&all).unwrap().len() / 2;
+        let capped = search(budget);
+        assert!(!capped.is_empty() && capped.len() < all.len());
+        assert!(serde_json::to_string(&capped).unwrap().len() <= budget);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_cancelled_walks_stop() {
+        let root = make_tree("cancelled");
+        let cancel = CancelFlag::default();
+        cancel.cancel();
+
+        let err = list_tree(
+            ListTreeParams {
+                path: root.to_string_lossy().to_string(),
+                max_depth: 3,
+                max_entries: 100,
+                cursor: None,
+                respect_gitignore: false,
+            },
+            &cancel,
+        )
+        .unwrap_err();
+        assert_eq!(err.0, ERR_CANCELLED);
+
+        let err = grep(
+            GrepParams {
+                pattern: "x".to_string(),
+                path: root.to_string_lossy().to_string(),
+                is_regex: false,
+                case_sensitive: true,
+                max_results: 100,
+                ignore: Vec::new(),
+                respect_gitignore: false,
+            },
+            &cancel,
+            usize::MAX,
+        )
+        .unwrap_err();
+        assert_eq!(err.0, ERR_CANCELLED);
+
+        let _ = fs::remove_dir_all(&root);
+    }
 }
@@ -0,0 +1,133 @@
+//! `.gitignore` support for directory walks (`respect_gitignore`).
+//!
+//! The walkers in `fs_ops` keep their own sorted traversal (listTree
+//! pagination depends on it), so instead of handing the walk to the `ignore`
+//! crate we only borrow its matcher and keep a stack of per-directory rules:
+//!
+//! - `.git/info/exclude` and every `.gitignore` from the repository top down
+//!   to the listed root are loaded up front
+//! - entering a directory pushes its `.gitignore` (if any), leaving pops it
+//! - the deepest rule that matches wins, so a nested `!pattern` can
+//!   re-include something a parent `.gitignore` excluded
+//!
+//! Nested `.gitignore` files are honored even when the root is not inside a
+//! git repository.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+/// Stack of gitignore matchers for the directory currently being walked.
+pub struct GitignoreStack {
+    /// Root as passed by the caller (prefix of every walked path)
+    listed_root: PathBuf,
+    /// Canonical form of `listed_root`; matchers are built against it
+    canonical_root: PathBuf,
+    /// Matchers ordered outermost first
+    layers: Vec<Gitignore>,
+}
+
+impl GitignoreStack {
+    /// Load the repository-level rules that apply above `root`.
+    ///
+    /// The `.gitignore` of `root` itself is picked up by the first
+    /// `push_dir(root)`.
+    pub fn for_root(root: &Path) -> Self {
+        let canonical_root = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+        let mut layers = Vec::new();
+
+        // ancestors[0] is root itself; ancestors[top] is the repository top
+        let ancestors: Vec<&Path> = canonical_root.ancestors().collect();
+        if let Some(top) = ancestors.iter().position(|dir| dir.join(".git").exists()) {
+            let repo = ancestors[top];
+            if let Some(exclude) = load(repo, &repo.join(".git/info/exclude")) {
+                layers.push(exclude);
+            }
+            // Outermost first, stopping short of root
+            if top > 0 {
+                for dir in ancestors[1..=top].iter().rev() {
+                    if let Some(gi) = load(dir, &dir.join(".gitignore")) {
+                        layers.push(gi);
+                    }
+                }
+            }
+        }
+
+        Self {
+            listed_root: root.to_path_buf(),
+            canonical_root,
+            layers,
+        }
+    }
+
+    /// Enter `dir`: push its `.gitignore` if present.
+    ///
+    /// Returns whether a layer was pushed; pass it to `pop_dir` when leaving.
+    pub fn push_dir(&mut self, dir: &Path) -> bool {
+        let dir = self.to_canonical(dir);
+        match load(&dir, &dir.join(".gitignore")) {
+            Some(gi) => {
+                self.layers.push(gi);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Leave a directory entered with `push_dir`.
+    pub fn pop_dir(&mut self, pushed: bool) {
+        if pushed {
+            self.layers.pop();
+        }
+    }
+
+    /// Whether `path` is excluded by the rules currently on the stack.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let path = self.to_canonical(path);
+        for gi in self.layers.iter().rev() {
+            let m = gi.matched(&path, is_dir);
+            if m.is_ignore() {
+                return true;
+            }
+            if m.is_whitelist() {
+                return false;
+            }
+        }
+        false
+    }
+
+    /// Re-root a walked path onto the canonical root so that it lines up
+    /// with the matchers' own roots.
+    fn to_canonical(&self, path: &Path) -> PathBuf {
+        match path.strip_prefix(&self.listed_root) {
+            Ok(rel) if rel.as_os_str().is_empty() => self.canonical_root.clone(),
+            Ok(rel) => self.canonical_root.join(rel),
+            Err(_) => path.to_path_buf(),
+        }
+    }
+}
+
+/// Build a matcher rooted at `dir` from a single ignore file.
+fn load(dir: &Path, file: &Path) -> Option<Gitignore> {
+    if !file.is_file() {
+        return None;
+    }
+    let mut builder = GitignoreBuilder::new(dir);
+    // Malformed lines are skipped; the rest of the file still applies
+    let _ = builder.add(file);
+    builder.build().ok().filter(|gi| !gi.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_root_at_filesystem_root() {
+        // `/` has a single ancestor (itself)
+        let stack = GitignoreStack::for_root(Path::new("/"));
+        assert_eq!(stack.listed_root, Path::new("/"));
+        assert!(!stack.is_ignored(Path::new("/"), true));
+    }
+}
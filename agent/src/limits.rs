@@ -0,0 +1,342 @@
+//! Per-session resource limits.
+//!
+//! The agent runs on hosts the user may share with others, so each session
+//! caps how many heavy requests run at once, how large a buffered result may
+//! grow and how many directories it watches. Defaults can be overridden at
+//! launch with `--max-ops`, `--max-result-bytes` and `--max-watches`.
+//!
+//! Heavy requests can also be cancelled by id through `sys/cancel`; the walk
+//! checks its [`CancelFlag`] between directories and stops early.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::fs_ops::base64_encode;
+use crate::protocol::*;
+
+pub const DEFAULT_MAX_CONCURRENT_OPS: usize = 4;
+pub const DEFAULT_MAX_RESULT_BYTES: usize = 64 * 1024 * 1024;
+pub const DEFAULT_MAX_WATCHED_DIRS: usize = 8192;
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrent_ops: DEFAULT_MAX_CONCURRENT_OPS,
+            max_result_bytes: DEFAULT_MAX_RESULT_BYTES,
+            max_watched_dirs: DEFAULT_MAX_WATCHED_DIRS,
+        }
+    }
+}
+
+impl ResourceLimits {
+    /// Parse `--max-ops N`, `--max-result-bytes N` and `--max-watches N` from
+    /// the command line (program name excluded). Unknown arguments are ignored.
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let mut limits = Self::default();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let slot = match arg.as_str() {
+                "--max-ops" => &mut limits.max_concurrent_ops,
+                "--max-result-bytes" => &mut limits.max_result_bytes,
+                "--max-watches" => &mut limits.max_watched_dirs,
+                _ => continue,
+            };
+            let value = iter
+                .next()
+                .ok_or_else(|| format!("{} requires a value", arg))?;
+            *slot = value
+                .parse::<usize>()
+                .ok()
+                .filter(|n| *n > 0)
+                .ok_or_else(|| format!("{} expects a positive integer, got {:?}", arg, value))?;
+        }
+        Ok(limits)
+    }
+}
+
+/// Methods that walk the file system and are run off the main loop,
+/// subject to `max_concurrent_ops`.
+pub fn is_heavy(method: &str) -> bool {
+    matches!(
+        method,
+        "fs/listTree" | "fs/readFileStream" | "search/grep" | "git/status" | "symbols/index"
+    )
+}
+
+/// Counts heavy operations in flight and refuses new ones past the cap.
+pub struct OperationGate {
+    in_flight: Arc<AtomicUsize>,
+    max: usize,
+}
+
+/// Held for the duration of one heavy operation; releases its slot on drop.
+pub struct OperationPermit {
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl OperationGate {
+    pub fn new(max: usize) -> Self {
+        Self {
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            max,
+        }
+    }
+
+    pub fn try_acquire(&self) -> Result<OperationPermit, (i32, String)> {
+        let previous = self.in_flight.fetch_add(1, Ordering::SeqCst);
+        if previous >= self.max {
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            return Err((
+                ERR_CONFLICT,
+                format!(
+                    "Too many concurrent operations (limit {}); retry when one finishes",
+                    self.max
+                ),
+            ));
+        }
+        Ok(OperationPermit {
+            in_flight: Arc::clone(&self.in_flight),
+        })
+    }
+}
+
+impl Drop for OperationPermit {
+    fn drop(&mut self) {
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Results smaller than this are not worth compressing.
+pub const COMPRESS_THRESHOLD: usize = 32 * 1024;
+
+/// Serialize a response, replacing a result larger than `max_bytes` with an
+/// `ERR_INTERNAL` error so an oversized payload never reaches stdout.
+///
+/// When `compress` is set (the client saw `rpc-gzip` in sys/info), a result
+/// over [`COMPRESS_THRESHOLD`] is sent as a gzip+base64 string instead.
+pub fn encode_response(response: &Response, max_bytes: usize, compress: bool) -> Option<String> {
+    let json = serde_json::to_string(response).ok()?;
+    let Some(result) = &response.result else {
+        return Some(json);
+    };
+    if json.len() > max_bytes {
+        let error = Response::err(
+            response.id,
+            ERR_INTERNAL,
+            format!(
+                "Result is {} bytes, over the agent limit of {} bytes; narrow the request",
+                json.len(),
+                max_bytes
+            ),
+        );
+        return serde_json::to_string(&error).ok();
+    }
+
+    if compress && json.len() > COMPRESS_THRESHOLD {
+        let raw = serde_json::to_vec(result).ok()?;
+        if let Some(compressed) = gzip(&raw).filter(|c| c.len() < raw.len()) {
+            let packed = Response {
+                encoding: Some("gzip+base64".to_string()),
+                ..Response::ok(
+                    response.id,
+                    serde_json::Value::String(base64_encode(&compressed)),
+                )
+            };
+            return serde_json::to_string(&packed).ok();
+        }
+    }
+    Some(json)
+}
+
+fn gzip(data: &[u8]) -> Option<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).ok()?;
+    encoder.finish().ok()
+}
+
+/// Set once the client has cancelled the operation holding it.
+#[derive(Debug, Clone, Default)]
+pub struct CancelFlag(Arc<AtomicBool>);
+
+impl CancelFlag {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// `Err(ERR_CANCELLED)` once cancelled, for `?` in walk loops.
+    pub fn check(&self) -> Result<(), (i32, String)> {
+        if self.is_cancelled() {
+            return Err((ERR_CANCELLED, "Request cancelled".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// Cancel flags of the heavy requests in flight, by request id.
+#[derive(Clone, Default)]
+pub struct InFlightOps {
+    ops: Arc<Mutex<HashMap<u64, CancelFlag>>>,
+}
+
+/// Registration of one heavy request; unregisters it on drop.
+pub struct InFlightOp {
+    ops: Arc<Mutex<HashMap<u64, CancelFlag>>>,
+    id: u64,
+    flag: CancelFlag,
+}
+
+impl InFlightOps {
+    pub fn register(&self, id: u64) -> InFlightOp {
+        let flag = CancelFlag::default();
+        if let Ok(mut ops) = self.ops.lock() {
+            ops.insert(id, flag.clone());
+        }
+        InFlightOp {
+            ops: Arc::clone(&self.ops),
+            id,
+            flag,
+        }
+    }
+
+    /// Cancel request `id`. Returns false if it is not (or no longer) running.
+    pub fn cancel(&self, id: u64) -> bool {
+        let flag = self.ops.lock().ok().and_then(|ops| ops.get(&id).cloned());
+        match flag {
+            Some(flag) => {
+                flag.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl InFlightOp {
+    pub fn flag(&self) -> &CancelFlag {
+        &self.flag
+    }
+}
+
+impl Drop for InFlightOp {
+    fn drop(&mut self) {
+        if let Ok(mut ops) = self.ops.lock() {
+            ops.remove(&self.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_from_args_overrides_defaults() {
+        let limits =
+            ResourceLimits::from_args(&args(&["--max-ops", "2", "--max-watches", "10"])).unwrap();
+        assert_eq!(limits.max_concurrent_ops, 2);
+        assert_eq!(limits.max_watched_dirs, 10);
+        assert_eq!(limits.max_result_bytes, DEFAULT_MAX_RESULT_BYTES);
+    }
+
+    #[test]
+    fn test_from_args_rejects_bad_values() {
+        assert!(ResourceLimits::from_args(&args(&["--max-ops"])).is_err());
+        assert!(ResourceLimits::from_args(&args(&["--max-ops", "0"])).is_err());
+        assert!(ResourceLimits::from_args(&args(&["--max-result-bytes", "lots"])).is_err());
+    }
+
+    #[test]
+    fn test_gate_rejects_over_limit() {
+        let gate = OperationGate::new(2);
+        let first = gate.try_acquire().unwrap();
+        let _second = gate.try_acquire().unwrap();
+
+        let (code, _) = gate.try_acquire().err().unwrap();
+        assert_eq!(code, ERR_CONFLICT);
+
+        drop(first);
+        assert!(gate.try_acquire().is_ok());
+    }
+
+    #[test]
+    fn test_cancel_reaches_registered_op_only() {
+        let ops = InFlightOps::default();
+        let op = ops.register(7);
+        assert!(op.flag().check().is_ok());
+
+        assert!(!ops.cancel(8));
+        assert!(ops.cancel(7));
+        assert!(op.flag().is_cancelled());
+        assert_eq!(op.flag().check().unwrap_err().0, ERR_CANCELLED);
+
+        // Finished ops can no longer be cancelled
+        drop(op);
+        assert!(!ops.cancel(7));
+    }
+
+    fn large_result() -> serde_json::Value {
+        let entries: Vec<_> = (0..2000)
+            .map(|i| serde_json::json!({ "name": format!("file_{}.rs", i), "size": 1024 }))
+            .collect();
+        serde_json::json!({ "entries": entries })
+    }
+
+    #[test]
+    fn test_encode_response_rejects_oversized_result() {
+        let big = Response::ok(7, serde_json::json!({ "data": "x".repeat(200) }));
+        let json = encode_response(&big, 100, false).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["id"], 7);
+        assert_eq!(value["error"]["code"], ERR_INTERNAL);
+        assert!(value.get("result").is_none());
+
+        let small = Response::ok(8, serde_json::json!({ "ok": true }));
+        let json = encode_response(&small, 100, false).unwrap();
+        assert!(json.contains("\"result\""));
+    }
+
+    #[test]
+    fn test_large_result_round_trips_gzipped() {
+        use std::io::Read;
+
+        let result = large_result();
+        let json = encode_response(&Response::ok(3, result.clone()), usize::MAX, true).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["encoding"], "gzip+base64");
+
+        let packed = value["result"].as_str().unwrap();
+        assert!(packed.len() < serde_json::to_string(&result).unwrap().len());
+        let compressed = crate::fs_ops::base64_decode(packed).unwrap();
+        let mut inflated = Vec::new();
+        flate2::read::GzDecoder::new(compressed.as_slice())
+            .read_to_end(&mut inflated)
+            .unwrap();
+        let restored: serde_json::Value = serde_json::from_slice(&inflated).unwrap();
+        assert_eq!(restored, result);
+    }
+
+    #[test]
+    fn test_plain_unless_requested_or_large() {
+        let json = encode_response(&Response::ok(4, large_result()), usize::MAX, false).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert!(value.get("encoding").is_none());
+        assert_eq!(value["result"], large_result());
+
+        let small = Response::ok(5, serde_json::json!({ "ok": true }));
+        let json = encode_response(&small, usize::MAX, true).unwrap();
+        assert_eq!(json, r#"{"id":5,"result":{"ok":true}}"#);
+    }
+}
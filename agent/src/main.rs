@@ -9,18 +9,28 @@
 //! Line-delimited JSON over stdin/stdout.
 //! - Requests: `{"id": 1, "method": "fs/readFile", "params": {...}}`
 //! - Responses: `{"id": 1, "result": {...}}` or `{"id": 1, "error": {...}}`
-//! - Notifications: `{"method": "watch/event", "params": {...}}`
+//! - Notifications: `{"method": "watch/event", "params": {...}}`, or
+//!   `{"method": "watch/event-batch", "params": {"events": [...]}}` for
+//!   watches started with `batch: true`
+//! - `fs/readFileStream` sends `{"method": "fs/chunk", "params": {"id": 1, ...}}`
+//!   notifications before its response
 //!
 //! ## Design Principles
 //!
 //! - Zero async runtime (no tokio) — uses std::thread + blocking I/O
-//! - Minimal dependencies (serde + inotify only)
+//! - Minimal dependencies (serde, zstd, inotify, ignore for .gitignore rules)
 //! - Single static binary, musl-linked
 //! - Self-cleans on parent connection close (stdin EOF)
+//! - Bounded: heavy requests run on worker threads under per-session caps
+//!   (see [`limits`]) and can be cancelled with `sys/cancel`; a cancelled
+//!   request gets no response
 
 mod protocol;
 mod fs_ops;
+mod gitignore;
+mod limits;
 mod symbols;
+mod usage;
 mod watcher;
 
 use std::io::{self, BufRead, Write};
@@ -28,12 +38,49 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{mpsc, Arc, Mutex};
 use std::collections::HashMap;
 
+use limits::{CancelFlag, InFlightOps, OperationGate};
 use protocol::*;
-use watcher::Watcher;
+use watcher::{WatchOptions, Watcher, DEFAULT_DEBOUNCE};
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Most changes carried by one `watch/event-batch` notification.
+const MAX_BATCH_EVENTS: usize = 1000;
+
+/// Lines queued for the stdout writer. Senders block beyond this, so a file
+/// stream can't read ahead of what the SSH channel drains.
+const OUT_QUEUE_LINES: usize = 256;
+
+/// Every method handled by `dispatch` / `dispatch_heavy`, reported in
+/// sys/info so clients can check support instead of trying and failing.
+/// Keep in sync with the match arms below.
+const METHODS: &[&str] = &[
+    "fs/readFile",
+    "fs/readFileStream",
+    "fs/writeFile",
+    "fs/stat",
+    "fs/listDir",
+    "fs/listTree",
+    "fs/mkdir",
+    "fs/remove",
+    "fs/rename",
+    "fs/chmod",
+    "search/grep",
+    "git/status",
+    "watch/start",
+    "watch/stop",
+    "symbols/index",
+    "symbols/complete",
+    "symbols/definitions",
+    "sys/info",
+    "sys/ping",
+    "sys/shutdown",
+    "sys/cancel",
+];
+
 fn main() {
+    usage::mark_started();
+
     // Handle --version flag for deploy version check
     let args: Vec<String> = std::env::args().collect();
     if args.len() > 1 && (args[1] == "--version" || args[1] == "-V") {
@@ -41,10 +88,21 @@ fn main() {
         return;
     }
 
+    let limits = match ResourceLimits::from_args(&args[1..]) {
+        Ok(limits) => limits,
+        Err(e) => {
+            eprintln!("[oxideterm-agent] {}", e);
+            std::process::exit(2);
+        }
+    };
+
     // Stderr for agent logging (doesn't interfere with JSON-RPC on stdout)
     eprintln!("[oxideterm-agent] v{} starting (pid: {})", VERSION, std::process::id());
+    eprintln!("[oxideterm-agent] limits: {:?}", limits);
 
-    let watcher = Watcher::new();
+    let watcher = Watcher::new(limits.max_watched_dirs);
+    let gate = OperationGate::new(limits.max_concurrent_ops);
+    let in_flight = InFlightOps::default();
     let shutdown_flag = Arc::new(AtomicBool::new(false));
     
     // Symbol index cache: root_path → Vec<SymbolInfo>
@@ -52,7 +110,7 @@ fn main() {
         Arc::new(Mutex::new(HashMap::new()));
 
     // Channel for serialized JSON responses/notifications → stdout writer thread
-    let (out_tx, out_rx) = mpsc::channel::<String>();
+    let (out_tx, out_rx) = mpsc::sync_channel::<String>(OUT_QUEUE_LINES);
 
     // Stdout writer thread — ensures atomic line writes
     let writer_handle = std::thread::spawn(move || {
@@ -110,13 +168,11 @@ fn main() {
         // Try to drain watch events (non-blocking)
         loop {
             match watcher.rx.try_recv() {
-                Ok(event) => {
-                    let notification = Notification {
-                        method: "watch/event".to_string(),
-                        params: serde_json::to_value(&event).unwrap_or_default(),
-                    };
-                    if let Ok(json) = serde_json::to_string(&notification) {
-                        let _ = out_tx.send(json);
+                Ok(flush) => {
+                    for notification in watch_notifications(flush) {
+                        if let Ok(json) = serde_json::to_string(&notification) {
+                            let _ = out_tx.send(json);
+                        }
                     }
                 }
                 Err(mpsc::TryRecvError::Empty) => break,
@@ -126,9 +182,60 @@ fn main() {
 
         // Wait for next request (with timeout to check watch events periodically)
         match req_rx.recv_timeout(std::time::Duration::from_millis(100)) {
+            Ok(Some(request)) if limits::is_heavy(&request.method) => {
+                // Run tree walks off the main loop so watch events and
+                // light requests keep flowing; the gate bounds how many.
+                match gate.try_acquire() {
+                    Ok(permit) => {
+                        let out_tx = out_tx.clone();
+                        let symbol_cache = Arc::clone(&symbol_cache);
+                        let max_bytes = limits.max_result_bytes;
+                        let op = in_flight.register(request.id);
+                        std::thread::spawn(move || {
+                            let _permit = permit;
+                            let emit = |notification: Notification| {
+                                if let Ok(json) = serde_json::to_string(&notification) {
+                                    let _ = out_tx.send(json);
+                                }
+                            };
+                            let response = dispatch_heavy(
+                                &request,
+                                &symbol_cache,
+                                op.flag(),
+                                max_bytes,
+                                &emit,
+                            );
+                            if op.flag().is_cancelled() {
+                                // The client stopped waiting for this one
+                                return;
+                            }
+                            if let Some(json) =
+                                limits::encode_response(&response, max_bytes, request.compress)
+                            {
+                                let _ = out_tx.send(json);
+                            }
+                        });
+                    }
+                    Err((code, msg)) => {
+                        let response = Response::err(request.id, code, msg);
+                        if let Ok(json) = serde_json::to_string(&response) {
+                            let _ = out_tx.send(json);
+                        }
+                    }
+                }
+            }
             Ok(Some(request)) => {
-                let response = dispatch(&request, &watcher, &shutdown_flag, &symbol_cache);
-                if let Ok(json) = serde_json::to_string(&response) {
+                let response = dispatch(
+                    &request,
+                    &watcher,
+                    &shutdown_flag,
+                    &symbol_cache,
+                    &limits,
+                    &in_flight,
+                );
+                if let Some(json) =
+                    limits::encode_response(&response, limits.max_result_bytes, request.compress)
+                {
                     let _ = out_tx.send(json);
                 }
                 // Check if shutdown was requested
@@ -167,6 +274,8 @@ fn dispatch(
     watcher: &Watcher,
     shutdown_flag: &Arc<AtomicBool>,
     symbol_cache: &Arc<Mutex<HashMap<String, Vec<SymbolInfo>>>>,
+    limits: &ResourceLimits,
+    in_flight: &InFlightOps,
 ) -> Response {
     match req.method.as_str() {
         // ─── fs/* ────────────────────────────────────────────────────
@@ -181,7 +290,13 @@ fn dispatch(
         "fs/writeFile" => match serde_json::from_value::<WriteFileParams>(req.params.clone()) {
             Ok(params) => match fs_ops::write_file(params) {
                 Ok(result) => Response::ok(req.id, serde_json::to_value(result).unwrap()),
-                Err((code, msg)) => Response::err(req.id, code, msg),
+                Err(fs_ops::WriteFileError::Conflict(conflict)) => Response::err_with_data(
+                    req.id,
+                    ERR_CONFLICT,
+                    conflict.to_string(),
+                    serde_json::to_value(&conflict).unwrap(),
+                ),
+                Err(fs_ops::WriteFileError::Failed(code, msg)) => Response::err(req.id, code, msg),
             },
             Err(e) => Response::err(req.id, ERR_INVALID_PARAMS, e.to_string()),
         },
@@ -202,14 +317,6 @@ fn dispatch(
             Err(e) => Response::err(req.id, ERR_INVALID_PARAMS, e.to_string()),
         },
 
-        "fs/listTree" => match serde_json::from_value::<ListTreeParams>(req.params.clone()) {
-            Ok(params) => match fs_ops::list_tree(params) {
-                Ok(result) => Response::ok(req.id, serde_json::to_value(result).unwrap()),
-                Err((code, msg)) => Response::err(req.id, code, msg),
-            },
-            Err(e) => Response::err(req.id, ERR_INVALID_PARAMS, e.to_string()),
-        },
-
         "fs/mkdir" => match serde_json::from_value::<MkdirParams>(req.params.clone()) {
             Ok(params) => match fs_ops::mkdir(params) {
                 Ok(()) => Response::ok(req.id, serde_json::json!({})),
@@ -242,30 +349,20 @@ fn dispatch(
             Err(e) => Response::err(req.id, ERR_INVALID_PARAMS, e.to_string()),
         },
 
-        // ─── search/* ────────────────────────────────────────────────
-        "search/grep" => match serde_json::from_value::<GrepParams>(req.params.clone()) {
-            Ok(params) => match fs_ops::grep(params) {
-                Ok(result) => Response::ok(req.id, serde_json::to_value(result).unwrap()),
-                Err((code, msg)) => Response::err(req.id, code, msg),
-            },
-            Err(e) => Response::err(req.id, ERR_INVALID_PARAMS, e.to_string()),
-        },
-
-        // ─── git/* ──────────────────────────────────────────────────
-        "git/status" => match serde_json::from_value::<GitStatusParams>(req.params.clone()) {
-            Ok(params) => match fs_ops::git_status(params) {
-                Ok(result) => Response::ok(req.id, serde_json::to_value(result).unwrap()),
-                Err((code, msg)) => Response::err(req.id, code, msg),
-            },
-            Err(e) => Response::err(req.id, ERR_INVALID_PARAMS, e.to_string()),
-        },
-
         // ─── watch/* ────────────────────────────────────────────────
         "watch/start" => match serde_json::from_value::<WatchStartParams>(req.params.clone()) {
-            Ok(params) => match watcher.start(params.path, params.ignore) {
-                Ok(()) => Response::ok(req.id, serde_json::json!({})),
-                Err(msg) => Response::err(req.id, ERR_INTERNAL, msg),
-            },
+            Ok(params) => {
+                let options = WatchOptions {
+                    debounce: params
+                        .debounce_ms
+                        .map_or(DEFAULT_DEBOUNCE, std::time::Duration::from_millis),
+                    batch: params.batch,
+                };
+                match watcher.start(params.path, params.ignore, options) {
+                    Ok(()) => Response::ok(req.id, serde_json::json!({})),
+                    Err((code, msg)) => Response::err(req.id, code, msg),
+                }
+            }
             Err(e) => Response::err(req.id, ERR_INVALID_PARAMS, e.to_string()),
         },
 
@@ -278,24 +375,6 @@ fn dispatch(
         },
 
         // ─── symbols/* ────────────────────────────────────────────
-        "symbols/index" => match serde_json::from_value::<SymbolIndexParams>(req.params.clone()) {
-            Ok(params) => {
-                let root = fs_ops::resolve_path(&params.path);
-                let syms = symbols::index_directory(&root, params.max_files);
-                let file_count = syms.len() as u32;
-                // Cache the index for subsequent complete/definitions calls
-                if let Ok(mut cache) = symbol_cache.lock() {
-                    cache.insert(params.path.clone(), syms.clone());
-                }
-                let result = SymbolIndexResult {
-                    symbols: syms,
-                    file_count,
-                };
-                Response::ok(req.id, serde_json::to_value(result).unwrap())
-            }
-            Err(e) => Response::err(req.id, ERR_INVALID_PARAMS, e.to_string()),
-        },
-
         "symbols/complete" => match serde_json::from_value::<SymbolCompleteParams>(req.params.clone()) {
             Ok(params) => {
                 let cache = symbol_cache.lock().unwrap();
@@ -344,7 +423,11 @@ fn dispatch(
                 arch: std::env::consts::ARCH.to_string(),
                 os: std::env::consts::OS.to_string(),
                 pid: std::process::id(),
-                capabilities: vec!["zstd".to_string()],
+                capabilities: vec!["zstd".to_string(), "rpc-gzip".to_string()],
+                methods: METHODS.iter().map(|m| m.to_string()).collect(),
+                limits: *limits,
+                rss_bytes: usage::rss_bytes(),
+                uptime_secs: usage::uptime_secs(),
             };
             Response::ok(req.id, serde_json::to_value(info).unwrap())
         }
@@ -357,6 +440,14 @@ fn dispatch(
             Response::ok(req.id, serde_json::json!({"ok": true}))
         }
 
+        "sys/cancel" => match serde_json::from_value::<CancelParams>(req.params.clone()) {
+            Ok(params) => {
+                let cancelled = in_flight.cancel(params.id);
+                Response::ok(req.id, serde_json::json!({"cancelled": cancelled}))
+            }
+            Err(e) => Response::err(req.id, ERR_INVALID_PARAMS, e.to_string()),
+        },
+
         _ => Response::err(
             req.id,
             ERR_METHOD_NOT_FOUND,
@@ -364,3 +455,248 @@ fn dispatch(
         ),
     }
 }
+
+/// Handle a request listed in [`limits::is_heavy`]. Runs on a worker thread;
+/// tree walks and file streams stop early once `cancel` is set, and searches
+/// stop collecting at `max_bytes`. `emit` sends the notifications that
+/// precede a streamed response.
+fn dispatch_heavy(
+    req: &Request,
+    symbol_cache: &Arc<Mutex<HashMap<String, Vec<SymbolInfo>>>>,
+    cancel: &CancelFlag,
+    max_bytes: usize,
+    emit: &dyn Fn(Notification),
+) -> Response {
+    match req.method.as_str() {
+        // ─── fs/* ────────────────────────────────────────────────────
+        "fs/readFileStream" => {
+            match serde_json::from_value::<ReadFileStreamParams>(req.params.clone()) {
+                Ok(params) => {
+                    let send_chunk = |chunk: &[u8]| {
+                        let data = FileChunk {
+                            id: req.id,
+                            data: fs_ops::base64_encode(chunk),
+                        };
+                        emit(Notification {
+                            method: "fs/chunk".to_string(),
+                            params: serde_json::to_value(data).unwrap_or_default(),
+                        });
+                    };
+                    match fs_ops::read_file_stream(params, cancel, send_chunk) {
+                        Ok(result) => Response::ok(req.id, serde_json::to_value(result).unwrap()),
+                        Err((code, msg)) => Response::err(req.id, code, msg),
+                    }
+                }
+                Err(e) => Response::err(req.id, ERR_INVALID_PARAMS, e.to_string()),
+            }
+        }
+
+        "fs/listTree" => match serde_json::from_value::<ListTreeParams>(req.params.clone()) {
+            Ok(params) => match fs_ops::list_tree(params, cancel) {
+                Ok(result) => Response::ok(req.id, serde_json::to_value(result).unwrap()),
+                Err((code, msg)) => Response::err(req.id, code, msg),
+            },
+            Err(e) => Response::err(req.id, ERR_INVALID_PARAMS, e.to_string()),
+        },
+
+        // ─── search/* ────────────────────────────────────────────────
+        "search/grep" => match serde_json::from_value::<GrepParams>(req.params.clone()) {
+            Ok(params) => match fs_ops::grep(params, cancel, max_bytes) {
+                Ok(result) => Response::ok(req.id, serde_json::to_value(result).unwrap()),
+                Err((code, msg)) => Response::err(req.id, code, msg),
+            },
+            Err(e) => Response::err(req.id, ERR_INVALID_PARAMS, e.to_string()),
+        },
+
+        // ─── git/* ──────────────────────────────────────────────────
+        "git/status" => match serde_json::from_value::<GitStatusParams>(req.params.clone()) {
+            Ok(params) => match fs_ops::git_status(params) {
+                Ok(result) => Response::ok(req.id, serde_json::to_value(result).unwrap()),
+                Err((code, msg)) => Response::err(req.id, code, msg),
+            },
+            Err(e) => Response::err(req.id, ERR_INVALID_PARAMS, e.to_string()),
+        },
+
+        // ─── symbols/* ────────────────────────────────────────────
+        "symbols/index" => match serde_json::from_value::<SymbolIndexParams>(req.params.clone()) {
+            Ok(params) => {
+                let root = fs_ops::resolve_path(&params.path);
+                let syms = symbols::index_directory(&root, params.max_files);
+                let file_count = syms.len() as u32;
+                // Cache the index for subsequent complete/definitions calls
+                if let Ok(mut cache) = symbol_cache.lock() {
+                    cache.insert(params.path.clone(), syms.clone());
+                }
+                let result = SymbolIndexResult {
+                    symbols: syms,
+                    file_count,
+                };
+                Response::ok(req.id, serde_json::to_value(result).unwrap())
+            }
+            Err(e) => Response::err(req.id, ERR_INVALID_PARAMS, e.to_string()),
+        },
+
+        _ => Response::err(
+            req.id,
+            ERR_METHOD_NOT_FOUND,
+            format!("Unknown method: {}", req.method),
+        ),
+    }
+}
+
+/// Notifications for one watch flush: a `watch/event` per change, or
+/// `watch/event-batch` notifications of at most `MAX_BATCH_EVENTS` each.
+fn watch_notifications(flush: watcher::WatchFlush) -> Vec<Notification> {
+    if !flush.batch {
+        return flush
+            .events
+            .iter()
+            .map(|event| Notification {
+                method: "watch/event".to_string(),
+                params: serde_json::to_value(event).unwrap_or_default(),
+            })
+            .collect();
+    }
+
+    let mut events = flush.events;
+    let mut notifications = Vec::new();
+    while !events.is_empty() {
+        let rest = events.split_off(events.len().min(MAX_BATCH_EVENTS));
+        notifications.push(Notification {
+            method: "watch/event-batch".to_string(),
+            params: serde_json::to_value(WatchEventBatch { events }).unwrap_or_default(),
+        });
+        events = rest;
+    }
+    notifications
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(method: &str) -> Response {
+        route_with(method, serde_json::json!({}), &InFlightOps::default())
+    }
+
+    fn route_with(method: &str, params: serde_json::Value, in_flight: &InFlightOps) -> Response {
+        let req = Request {
+            id: 1,
+            method: method.to_string(),
+            params,
+            compress: false,
+        };
+        let cache = Arc::new(Mutex::new(HashMap::new()));
+        if limits::is_heavy(method) {
+            return dispatch_heavy(&req, &cache, &CancelFlag::default(), usize::MAX, &|_| {});
+        }
+        let watcher = Watcher::new(1);
+        let shutdown_flag = Arc::new(AtomicBool::new(false));
+        dispatch(
+            &req,
+            &watcher,
+            &shutdown_flag,
+            &cache,
+            &ResourceLimits::default(),
+            in_flight,
+        )
+    }
+
+    #[test]
+    fn test_every_reported_method_has_a_handler() {
+        let info = route("sys/info").result.unwrap();
+        let methods: Vec<&str> = info["methods"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m.as_str().unwrap())
+            .collect();
+        assert_eq!(methods, METHODS);
+        for method in methods {
+            let code = route(method).error.map(|e| e.code);
+            assert_ne!(
+                code,
+                Some(ERR_METHOD_NOT_FOUND),
+                "{} is reported but not handled",
+                method
+            );
+        }
+        assert_eq!(
+            route("fs/tail").error.map(|e| e.code),
+            Some(ERR_METHOD_NOT_FOUND)
+        );
+    }
+
+    #[test]
+    fn test_watch_flush_notifications() {
+        let flush = |count: usize, batch| watcher::WatchFlush {
+            events: (0..count)
+                .map(|i| WatchEvent {
+                    path: format!("/srv/app/{}.rs", i),
+                    kind: "modify".to_string(),
+                })
+                .collect(),
+            batch,
+        };
+
+        let single = watch_notifications(flush(3, false));
+        assert_eq!(single.len(), 3);
+        assert!(single.iter().all(|n| n.method == "watch/event"));
+        assert_eq!(single[2].params["path"], "/srv/app/2.rs");
+
+        let batched = watch_notifications(flush(MAX_BATCH_EVENTS + 1, true));
+        assert_eq!(batched.len(), 2);
+        assert!(batched.iter().all(|n| n.method == "watch/event-batch"));
+        assert_eq!(
+            batched[0].params["events"].as_array().unwrap().len(),
+            MAX_BATCH_EVENTS
+        );
+        assert_eq!(
+            batched[1].params["events"][0]["path"],
+            format!("/srv/app/{}.rs", MAX_BATCH_EVENTS)
+        );
+    }
+
+    #[test]
+    fn test_sys_cancel_flags_in_flight_request() {
+        let in_flight = InFlightOps::default();
+        let op = in_flight.register(42);
+
+        let response = route_with("sys/cancel", serde_json::json!({"id": 42}), &in_flight);
+        assert_eq!(response.result.unwrap()["cancelled"], true);
+        assert!(op.flag().is_cancelled());
+
+        drop(op);
+        let response = route_with("sys/cancel", serde_json::json!({"id": 42}), &in_flight);
+        assert_eq!(response.result.unwrap()["cancelled"], false);
+    }
+
+    #[test]
+    fn test_read_file_stream_emits_chunks_for_request() {
+        let path =
+            std::env::temp_dir().join(format!("oxideterm-agent-chunks-{}", std::process::id()));
+        std::fs::write(&path, vec![b'a'; 10_000]).unwrap();
+        let req = Request {
+            id: 9,
+            method: "fs/readFileStream".to_string(),
+            params: serde_json::json!({ "path": path, "chunk_size": 4096 }),
+            compress: false,
+        };
+
+        let chunks = Mutex::new(Vec::new());
+        let cache = Arc::new(Mutex::new(HashMap::new()));
+        let response = dispatch_heavy(&req, &cache, &CancelFlag::default(), usize::MAX, &|n| {
+            chunks.lock().unwrap().push(n)
+        });
+        let _ = std::fs::remove_file(&path);
+
+        let chunks = chunks.into_inner().unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks
+            .iter()
+            .all(|n| n.method == "fs/chunk" && n.params["id"] == 9));
+        let result = response.result.unwrap();
+        assert_eq!(result["size"], 10_000);
+        assert_eq!(result["chunks"], 3);
+    }
+}
@@ -18,6 +18,9 @@ pub struct Request {
     pub method: String,
     #[serde(default)]
     pub params: serde_json::Value,
+    /// Client accepts a gzip+base64 `result` (see `rpc-gzip` in sys/info).
+    #[serde(default)]
+    pub compress: bool,
 }
 
 /// Outgoing response to OxideTerm client.
@@ -28,6 +31,9 @@ pub struct Response {
     pub result: Option<serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<RpcError>,
+    /// Set to "gzip+base64" when `result` is a compressed JSON string.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
 }
 
 /// JSON-RPC error object.
@@ -35,6 +41,10 @@ pub struct Response {
 pub struct RpcError {
     pub code: i32,
     pub message: String,
+    /// Structured details for errors the client handles specially
+    /// (e.g. `WriteConflict` for `ERR_CONFLICT`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
 }
 
 /// Server-initiated notification (no `id`).
@@ -50,6 +60,7 @@ impl Response {
             id,
             result: Some(result),
             error: None,
+            encoding: None,
         }
     }
 
@@ -60,7 +71,27 @@ impl Response {
             error: Some(RpcError {
                 code,
                 message: message.into(),
+                data: None,
+            }),
+            encoding: None,
+        }
+    }
+
+    pub fn err_with_data(
+        id: u64,
+        code: i32,
+        message: impl Into<String>,
+        data: serde_json::Value,
+    ) -> Self {
+        Self {
+            id,
+            result: None,
+            error: Some(RpcError {
+                code,
+                message: message.into(),
+                data: Some(data),
             }),
+            encoding: None,
         }
     }
 }
@@ -77,6 +108,9 @@ pub const ERR_NOT_FOUND: i32 = -2;
 pub const ERR_PERMISSION: i32 = -3;
 pub const ERR_ALREADY_EXISTS: i32 = -4;
 pub const ERR_CONFLICT: i32 = -5;
+/// The request was cancelled via sys/cancel. Never sent: a cancelled
+/// request gets no response.
+pub const ERR_CANCELLED: i32 = -6;
 
 // ═══════════════════════════════════════════════════════════════════════════
 // fs/* params & results
@@ -89,6 +123,9 @@ pub struct ReadFileParams {
     /// Max file size in bytes (default: 10MB). Returns error if exceeded.
     #[serde(default = "default_max_size")]
     pub max_size: u64,
+    /// Return the raw bytes base64-encoded instead of refusing binary files.
+    #[serde(default)]
+    pub binary: bool,
 }
 
 fn default_max_size() -> u64 {
@@ -103,11 +140,44 @@ pub struct ReadFileResult {
     pub hash: String,
     pub size: u64,
     pub mtime: u64,
-    /// Content encoding: "plain" or "zstd+base64".
+    /// Content encoding: "plain", "base64" (binary reads) or "zstd+base64".
     #[serde(default = "default_encoding")]
     pub encoding: String,
 }
 
+/// fs/readFileStream params
+#[derive(Debug, Deserialize)]
+pub struct ReadFileStreamParams {
+    pub path: String,
+    /// Raw bytes per `fs/chunk` notification (clamped to 4KB..=4MB).
+    #[serde(default = "default_chunk_size")]
+    pub chunk_size: usize,
+}
+
+fn default_chunk_size() -> usize {
+    256 * 1024
+}
+
+/// fs/chunk notification params (server → client): one piece of an
+/// fs/readFileStream, in file order.
+#[derive(Debug, Serialize)]
+pub struct FileChunk {
+    /// `id` of the fs/readFileStream request.
+    pub id: u64,
+    /// Base64-encoded raw bytes.
+    pub data: String,
+}
+
+/// fs/readFileStream result, sent after the last fs/chunk.
+#[derive(Debug, Serialize)]
+pub struct ReadFileStreamResult {
+    /// SHA-256 hex digest of all streamed bytes.
+    pub hash: String,
+    pub size: u64,
+    pub mtime: u64,
+    pub chunks: u64,
+}
+
 /// fs/writeFile params
 #[derive(Debug, Deserialize)]
 pub struct WriteFileParams {
@@ -125,6 +195,16 @@ fn default_encoding() -> String {
     "plain".to_string()
 }
 
+/// `data` of an `ERR_CONFLICT` fs/writeFile error: the file changed since the
+/// client read it (`expect_hash` mismatch).
+#[derive(Debug, Serialize)]
+pub struct WriteConflict {
+    /// Hash the client expected.
+    pub expected_hash: String,
+    /// SHA-256 of the file as it is now on disk.
+    pub current_hash: String,
+}
+
 /// fs/writeFile result
 #[derive(Debug, Serialize)]
 pub struct WriteFileResult {
@@ -139,6 +219,9 @@ pub struct WriteFileResult {
 #[derive(Debug, Deserialize)]
 pub struct StatParams {
     pub path: String,
+    /// Read the start of a regular file to fill in `is_binary`.
+    #[serde(default)]
+    pub detect_binary: bool,
 }
 
 /// fs/stat result
@@ -153,6 +236,10 @@ pub struct StatResult {
     pub mtime: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub permissions: Option<String>, // octal, e.g. "755"
+    /// Regular files, when `detect_binary` was set: whether the start of the
+    /// file looks binary.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_binary: Option<bool>,
 }
 
 /// fs/listDir params
@@ -171,6 +258,13 @@ pub struct ListTreeParams {
     /// Maximum total entries to return (default: 5000).
     #[serde(default = "default_max_entries")]
     pub max_entries: u32,
+    /// Resume after this position (`next_cursor` from a previous page).
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Skip entries excluded by `.gitignore` files (see `GrepParams` for
+    /// precedence). Keep it the same across pages of one listing.
+    #[serde(default)]
+    pub respect_gitignore: bool,
 }
 
 fn default_max_depth() -> u32 {
@@ -187,8 +281,11 @@ pub struct ListTreeResult {
     pub entries: Vec<FileEntry>,
     /// True if max_entries was reached and results are incomplete.
     pub truncated: bool,
-    /// Total number of entries scanned (may exceed entries.len() if truncated).
+    /// Number of new entries returned in this page (nested entries included).
     pub total_scanned: u32,
+    /// Pass as `cursor` to fetch the next page; present only when truncated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 
 /// A single file/directory entry.
@@ -209,6 +306,10 @@ pub struct FileEntry {
     /// Frontend should show a "load more" indicator instead of assuming empty.
     #[serde(default, skip_serializing_if = "is_false")]
     pub truncated: bool,
+    /// True if this entry was already returned by an earlier listTree page and
+    /// is repeated only to carry newly listed children.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub continued: bool,
 }
 
 fn is_false(v: &bool) -> bool {
@@ -336,6 +437,13 @@ pub struct WatchStartParams {
     /// Glob patterns to ignore (e.g. ["node_modules", ".git"]).
     #[serde(default)]
     pub ignore: Vec<String>,
+    /// Quiet period before a changed path is reported (default 100ms).
+    #[serde(default)]
+    pub debounce_ms: Option<u64>,
+    /// Report each flush as one `watch/event-batch` notification instead of
+    /// a `watch/event` per path.
+    #[serde(default)]
+    pub batch: bool,
 }
 
 /// watch/stop params
@@ -352,6 +460,12 @@ pub struct WatchEvent {
     pub kind: String,
 }
 
+/// watch/event-batch notification params (server → client).
+#[derive(Debug, Serialize)]
+pub struct WatchEventBatch {
+    pub events: Vec<WatchEvent>,
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // search/* params & results
 // ═══════════════════════════════════════════════════════════════════════════
@@ -371,6 +485,15 @@ pub struct GrepParams {
     /// Glob patterns to ignore.
     #[serde(default)]
     pub ignore: Vec<String>,
+    /// Skip files and directories excluded by the repository's `.gitignore`
+    /// hierarchy (plus `.git/info/exclude`).
+    ///
+    /// Precedence: the explicit `ignore` list and the built-in skips
+    /// (`.git`, `node_modules`, ...) are checked first and always exclude —
+    /// a `!pattern` in a `.gitignore` cannot re-include them. Gitignore rules
+    /// then apply to whatever is left, deepest `.gitignore` winning.
+    #[serde(default)]
+    pub respect_gitignore: bool,
 }
 
 fn default_grep_max() -> u32 {
@@ -412,6 +535,13 @@ pub struct GitFileEntry {
 // sys/* params & results
 // ═══════════════════════════════════════════════════════════════════════════
 
+/// sys/cancel params
+#[derive(Debug, Deserialize)]
+pub struct CancelParams {
+    /// `id` of the request to cancel.
+    pub id: u64,
+}
+
 /// sys/info result
 #[derive(Debug, Serialize)]
 pub struct SysInfoResult {
@@ -419,7 +549,26 @@ pub struct SysInfoResult {
     pub arch: String,
     pub os: String,
     pub pid: u32,
-    /// Supported capabilities: ["zstd"]
+    /// Supported capabilities: ["zstd", "rpc-gzip"]
     #[serde(default)]
     pub capabilities: Vec<String>,
+    /// JSON-RPC methods this build handles.
+    pub methods: Vec<String>,
+    /// Resource caps this agent session enforces.
+    pub limits: ResourceLimits,
+    /// Resident memory of the agent process (0 where it can't be measured).
+    pub rss_bytes: u64,
+    /// Seconds since the agent started.
+    pub uptime_secs: u64,
+}
+
+/// Per-session resource caps, reported in sys/info.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub struct ResourceLimits {
+    /// Heavy requests (listTree, grep, git status, symbol indexing) in flight at once.
+    pub max_concurrent_ops: usize,
+    /// Largest serialized result the agent will buffer and send.
+    pub max_result_bytes: usize,
+    /// Directories watched via inotify, across all watch sessions.
+    pub max_watched_dirs: usize,
 }
@@ -0,0 +1,66 @@
+//! The agent's own footprint, self-reported in sys/info.
+//!
+//! Lets the client show how much memory a long-lived agent holds and how long
+//! it has been up without shelling out to `ps` on the remote host.
+
+use std::sync::OnceLock;
+use std::time::Instant;
+
+static STARTED: OnceLock<Instant> = OnceLock::new();
+
+/// Record the process start. Called first thing in `main`.
+pub fn mark_started() {
+    STARTED.get_or_init(Instant::now);
+}
+
+/// Whole seconds since [`mark_started`].
+pub fn uptime_secs() -> u64 {
+    STARTED.get().map_or(0, |t| t.elapsed().as_secs())
+}
+
+/// Resident set size in bytes, from `/proc/self/statm`.
+#[cfg(target_os = "linux")]
+pub fn rss_bytes() -> u64 {
+    // SAFETY: sysconf has no preconditions.
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    let Ok(page_size) = u64::try_from(page_size) else {
+        return 0;
+    };
+    std::fs::read_to_string("/proc/self/statm")
+        .ok()
+        .and_then(|statm| parse_statm_resident(&statm))
+        .map_or(0, |pages| pages * page_size)
+}
+
+/// No procfs outside Linux; report 0 rather than guess.
+#[cfg(not(target_os = "linux"))]
+pub fn rss_bytes() -> u64 {
+    0
+}
+
+/// Resident page count: the second field of `/proc/self/statm`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_statm_resident(statm: &str) -> Option<u64> {
+    statm.split_whitespace().nth(1)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_resident_pages() {
+        assert_eq!(
+            parse_statm_resident("5321 1203 890 120 0 700 0\n"),
+            Some(1203)
+        );
+        assert_eq!(parse_statm_resident("5321"), None);
+        assert_eq!(parse_statm_resident(""), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_reports_nonzero_rss_on_linux() {
+        assert!(rss_bytes() > 0);
+    }
+}
@@ -2,22 +2,61 @@
 //!
 //! Runs in a dedicated thread, sends `watch/event` notifications
 //! through a channel that the main loop consumes.
+//!
+//! Changes are debounced per path: a path is reported once it has been quiet
+//! for the session's debounce window, with its changes in that window merged
+//! (a file created and deleted again is not reported at all).
 
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::mpsc;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
-use crate::protocol::WatchEvent;
+use crate::protocol::{WatchEvent, ERR_CONFLICT, ERR_INTERNAL};
+
+/// Default quiet period before a changed path is reported.
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Longest debounce window a client may ask for.
+const MAX_DEBOUNCE: Duration = Duration::from_secs(10);
+
+/// Per-session watch settings from `watch/start`.
+#[derive(Debug, Clone, Copy)]
+pub struct WatchOptions {
+    pub debounce: Duration,
+    /// Send flushes as `watch/event-batch` notifications.
+    pub batch: bool,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self {
+            debounce: DEFAULT_DEBOUNCE,
+            batch: false,
+        }
+    }
+}
+
+/// Changes flushed together by one watch session.
+pub struct WatchFlush {
+    pub events: Vec<WatchEvent>,
+    /// The session asked for `watch/event-batch` notifications.
+    pub batch: bool,
+}
 
 /// Watcher handle — manages background watch threads.
 pub struct Watcher {
     /// Channel to receive watch events from background threads.
-    pub rx: mpsc::Receiver<WatchEvent>,
-    tx: mpsc::Sender<WatchEvent>,
+    pub rx: mpsc::Receiver<WatchFlush>,
+    tx: mpsc::Sender<WatchFlush>,
     /// Active watch sessions.
     watches: Arc<Mutex<HashMap<String, WatchHandle>>>,
+    /// Directories currently watched, summed over all sessions.
+    watched_dirs: Arc<AtomicUsize>,
+    /// Cap on `watched_dirs`.
+    max_watched_dirs: usize,
 }
 
 struct WatchHandle {
@@ -26,24 +65,50 @@ struct WatchHandle {
 }
 
 impl Watcher {
-    pub fn new() -> Self {
+    pub fn new(max_watched_dirs: usize) -> Self {
         let (tx, rx) = mpsc::channel();
         Self {
             rx,
             tx,
             watches: Arc::new(Mutex::new(HashMap::new())),
+            watched_dirs: Arc::new(AtomicUsize::new(0)),
+            max_watched_dirs,
         }
     }
 
     /// Start watching a directory path.
-    pub fn start(&self, path: String, ignore: Vec<String>) -> Result<(), String> {
-        let mut watches = self.watches.lock().map_err(|e| e.to_string())?;
+    ///
+    /// Fails with `ERR_CONFLICT` when the tree would push the session past
+    /// its watched-directory cap.
+    pub fn start(
+        &self,
+        path: String,
+        ignore: Vec<String>,
+        options: WatchOptions,
+    ) -> Result<(), (i32, String)> {
+        let mut watches = self
+            .watches
+            .lock()
+            .map_err(|e| (ERR_INTERNAL, e.to_string()))?;
 
         // Already watching?
         if watches.contains_key(&path) {
             return Ok(());
         }
 
+        let in_use = self.watched_dirs.load(Ordering::SeqCst);
+        let remaining = self.max_watched_dirs.saturating_sub(in_use);
+        let needed = count_watch_dirs(Path::new(&path), &ignore, remaining + 1);
+        if needed > remaining {
+            return Err((
+                ERR_CONFLICT,
+                format!(
+                    "Watching {} would exceed the limit of {} directories ({} in use)",
+                    path, self.max_watched_dirs, in_use
+                ),
+            ));
+        }
+
         let stop = Arc::new(Mutex::new(false));
         let handle = WatchHandle {
             stop: Arc::clone(&stop),
@@ -51,9 +116,17 @@ impl Watcher {
 
         let tx = self.tx.clone();
         let watch_path = path.clone();
+        let budget = WatchBudget {
+            used: Arc::clone(&self.watched_dirs),
+            max: self.max_watched_dirs,
+        };
+        let options = WatchOptions {
+            debounce: options.debounce.min(MAX_DEBOUNCE),
+            ..options
+        };
 
         std::thread::spawn(move || {
-            watch_thread(&watch_path, &ignore, &tx, &stop);
+            watch_thread(&watch_path, &ignore, &tx, &stop, &budget, options);
         });
 
         watches.insert(path, handle);
@@ -83,6 +156,128 @@ impl Watcher {
     }
 }
 
+/// Shared directory-watch allowance handed to each watch thread.
+struct WatchBudget {
+    used: Arc<AtomicUsize>,
+    max: usize,
+}
+
+impl WatchBudget {
+    /// Reserve one watch slot; false once the cap is reached.
+    fn try_take(&self) -> bool {
+        self.used
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                (n < self.max).then_some(n + 1)
+            })
+            .is_ok()
+    }
+
+    fn release(&self, n: usize) {
+        self.used.fetch_sub(n, Ordering::SeqCst);
+    }
+}
+
+/// Per-path change coalescing for one watch session.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+struct Debouncer {
+    window: Duration,
+    /// Merged change kind and time of the latest change, per path.
+    pending: HashMap<String, (&'static str, Instant)>,
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+impl Debouncer {
+    fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Record a change; the path's quiet period restarts.
+    fn record(&mut self, path: String, kind: &'static str, now: Instant) {
+        let merged = match self.pending.get(&path) {
+            Some((prev, _)) => merge_kinds(prev, kind),
+            None => Some(kind),
+        };
+        match merged {
+            Some(kind) => {
+                self.pending.insert(path, (kind, now));
+            }
+            None => {
+                self.pending.remove(&path);
+            }
+        }
+    }
+
+    /// Take the changes to paths that have been quiet for the window,
+    /// sorted by path.
+    fn flush_due(&mut self, now: Instant) -> Vec<WatchEvent> {
+        let window = self.window;
+        let mut due = Vec::new();
+        self.pending.retain(|path, (kind, at)| {
+            if now.duration_since(*at) < window {
+                return true;
+            }
+            due.push(WatchEvent {
+                path: path.clone(),
+                kind: kind.to_string(),
+            });
+            false
+        });
+        due.sort_by(|a, b| a.path.cmp(&b.path));
+        due
+    }
+}
+
+/// Net effect of two changes to one path within a debounce window;
+/// `None` when they cancel out.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn merge_kinds(prev: &str, next: &'static str) -> Option<&'static str> {
+    match (prev, next) {
+        // Never seen by the client
+        ("create", "delete") => None,
+        ("create", _) => Some("create"),
+        // Deleted, then something appeared at the same path
+        ("delete", "create" | "modify") => Some("modify"),
+        (_, "delete") => Some("delete"),
+        _ => Some("modify"),
+    }
+}
+
+/// Directory names never descended into when watching.
+fn is_skipped_dir(name: &str, ignore: &[String]) -> bool {
+    ignore.iter().any(|ig| ig == name)
+        || name == ".git"
+        || name == "node_modules"
+        || name == ".hg"
+        || name == "__pycache__"
+        || name == "target"
+}
+
+/// Count the directories a watch on `dir` would cover, stopping at `limit`.
+fn count_watch_dirs(dir: &Path, ignore: &[String], limit: usize) -> usize {
+    let mut count = 0;
+    let mut stack = vec![dir.to_path_buf()];
+    while let Some(current) = stack.pop() {
+        count += 1;
+        if count >= limit {
+            break;
+        }
+        if let Ok(entries) = std::fs::read_dir(&current) {
+            for entry in entries.flatten() {
+                if is_skipped_dir(&entry.file_name().to_string_lossy(), ignore) {
+                    continue;
+                }
+                if entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
+                    stack.push(entry.path());
+                }
+            }
+        }
+    }
+    count
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // Linux inotify implementation
 // ═══════════════════════════════════════════════════════════════════════════
@@ -91,8 +286,10 @@ impl Watcher {
 fn watch_thread(
     path: &str,
     ignore: &[String],
-    tx: &mpsc::Sender<WatchEvent>,
+    tx: &mpsc::Sender<WatchFlush>,
     stop: &Arc<Mutex<bool>>,
+    budget: &WatchBudget,
+    options: WatchOptions,
 ) {
     use inotify::{Inotify, WatchMask};
     use std::os::unix::io::AsRawFd;
@@ -131,13 +328,13 @@ fn watch_thread(
         &mut inotify,
         mask,
         &mut wd_to_path,
+        budget,
     );
 
     let mut buffer = [0; 4096];
 
-    // Debounce: accumulate events for 100ms before sending
-    let mut pending: HashMap<String, (String, Instant)> = HashMap::new();
-    let debounce_duration = Duration::from_millis(100);
+    // Debounce: accumulate events until each path has been quiet for the window
+    let mut debouncer = Debouncer::new(options.debounce);
 
     loop {
         // Check stop signal
@@ -151,6 +348,14 @@ fn watch_thread(
         match inotify.read_events(&mut buffer) {
             Ok(events) => {
                 for event in events {
+                    // The kernel dropped this watch (directory removed)
+                    if event.mask.contains(inotify::EventMask::IGNORED) {
+                        if wd_to_path.remove(&event.wd).is_some() {
+                            budget.release(1);
+                        }
+                        continue;
+                    }
+
                     let dir_path = wd_to_path
                         .get(&event.wd)
                         .cloned()
@@ -186,6 +391,7 @@ fn watch_thread(
                                 &mut inotify,
                                 mask,
                                 &mut wd_to_path,
+                                budget,
                             );
                         }
                         "create"
@@ -201,8 +407,7 @@ fn watch_thread(
                         continue;
                     };
 
-                    // Debounce: update pending event
-                    pending.insert(file_path_str, (kind.to_string(), Instant::now()));
+                    debouncer.record(file_path_str, kind, Instant::now());
                 }
             }
             Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
@@ -216,24 +421,20 @@ fn watch_thread(
         }
 
         // Flush debounced events
-        let now = Instant::now();
-        let mut flushed = Vec::new();
-        for (path_key, (kind, timestamp)) in &pending {
-            if now.duration_since(*timestamp) >= debounce_duration {
-                let _ = tx.send(WatchEvent {
-                    path: path_key.clone(),
-                    kind: kind.clone(),
-                });
-                flushed.push(path_key.clone());
-            }
-        }
-        for key in flushed {
-            pending.remove(&key);
+        let events = debouncer.flush_due(Instant::now());
+        if !events.is_empty() {
+            let _ = tx.send(WatchFlush {
+                events,
+                batch: options.batch,
+            });
         }
 
         // Small sleep to avoid busy-looping
         std::thread::sleep(Duration::from_millis(50));
     }
+
+    // Watches die with the inotify fd; hand their slots back.
+    budget.release(wd_to_path.len());
 }
 
 #[cfg(target_os = "linux")]
@@ -243,13 +444,27 @@ fn add_watches_recursive(
     inotify: &mut inotify::Inotify,
     mask: inotify::WatchMask,
     wd_map: &mut HashMap<inotify::WatchDescriptor, PathBuf>,
+    budget: &WatchBudget,
 ) {
+    if !budget.try_take() {
+        eprintln!(
+            "[agent] Watch limit of {} directories reached, not watching {}",
+            budget.max,
+            dir.display()
+        );
+        return;
+    }
+
     // Add watch for this directory
     match inotify.watches().add(dir, mask) {
         Ok(wd) => {
-            wd_map.insert(wd, dir.to_path_buf());
+            // Re-adding a known directory returns its existing descriptor
+            if wd_map.insert(wd, dir.to_path_buf()).is_some() {
+                budget.release(1);
+            }
         }
         Err(e) => {
+            budget.release(1);
             eprintln!(
                 "[agent] Failed to watch {}: {}",
                 dir.display(),
@@ -266,19 +481,13 @@ fn add_watches_recursive(
             let name_str = name.to_string_lossy();
 
             // Skip ignored directories
-            if ignore.iter().any(|ig| *ig == *name_str)
-                || name_str == ".git"
-                || name_str == "node_modules"
-                || name_str == ".hg"
-                || name_str == "__pycache__"
-                || name_str == "target"
-            {
+            if is_skipped_dir(&name_str, ignore) {
                 continue;
             }
 
             if let Ok(ft) = entry.file_type() {
                 if ft.is_dir() {
-                    add_watches_recursive(&entry.path(), ignore, inotify, mask, wd_map);
+                    add_watches_recursive(&entry.path(), ignore, inotify, mask, wd_map, budget);
                 }
             }
         }
@@ -293,8 +502,10 @@ fn add_watches_recursive(
 fn watch_thread(
     _path: &str,
     _ignore: &[String],
-    _tx: &mpsc::Sender<WatchEvent>,
+    _tx: &mpsc::Sender<WatchFlush>,
     stop: &Arc<Mutex<bool>>,
+    _budget: &WatchBudget,
+    _options: WatchOptions,
 ) {
     // On non-Linux platforms, the watcher is a no-op.
     // File watching will rely on the polling-based approach
@@ -309,3 +520,91 @@ fn watch_thread(
         std::thread::sleep(Duration::from_secs(5));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Temp dir with three nested directories: `a`, `a/b`, `c`, plus an
+    /// ignored `node_modules`.
+    fn make_dirs(tag: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!(
+            "oxideterm-agent-watch-{}-{}",
+            tag,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&root);
+        for dir in ["a/b", "c", "node_modules/pkg"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn test_count_watch_dirs_skips_ignored() {
+        let root = make_dirs("count");
+        assert_eq!(count_watch_dirs(&root, &[], usize::MAX), 4);
+        assert_eq!(count_watch_dirs(&root, &["c".to_string()], usize::MAX), 3);
+        assert_eq!(count_watch_dirs(&root, &[], 2), 2);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_start_rejects_tree_over_watch_limit() {
+        let root = make_dirs("limit");
+        let path = root.to_string_lossy().to_string();
+
+        let watcher = Watcher::new(3);
+        let (code, msg) = watcher
+            .start(path.clone(), vec![], WatchOptions::default())
+            .unwrap_err();
+        assert_eq!(code, ERR_CONFLICT);
+        assert!(msg.contains("limit of 3"));
+
+        let watcher = Watcher::new(4);
+        assert!(watcher.start(path, vec![], WatchOptions::default()).is_ok());
+        watcher.stop_all();
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_debouncer_collapses_create_then_delete() {
+        let window = Duration::from_millis(100);
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let mut debouncer = Debouncer::new(window);
+
+        // Build temp file that never outlives the window
+        debouncer.record("/p/target/.tmp1".into(), "create", at(0));
+        debouncer.record("/p/target/.tmp1".into(), "modify", at(10));
+        debouncer.record("/p/target/.tmp1".into(), "delete", at(20));
+        // Saved via delete + create: reported as a modification
+        debouncer.record("/p/src/main.rs".into(), "delete", at(0));
+        debouncer.record("/p/src/main.rs".into(), "create", at(5));
+        debouncer.record("/p/new.rs".into(), "create", at(0));
+        debouncer.record("/p/new.rs".into(), "modify", at(50));
+
+        let kinds = |events: Vec<WatchEvent>| -> Vec<(String, String)> {
+            events.into_iter().map(|e| (e.path, e.kind)).collect()
+        };
+        assert_eq!(
+            kinds(debouncer.flush_due(at(110))),
+            vec![("/p/src/main.rs".to_string(), "modify".to_string())]
+        );
+        // new.rs changed at 50ms: still inside its window
+        assert_eq!(
+            kinds(debouncer.flush_due(at(150))),
+            vec![("/p/new.rs".to_string(), "create".to_string())]
+        );
+        assert!(debouncer.flush_due(at(1000)).is_empty());
+    }
+
+    #[test]
+    fn test_merge_kinds() {
+        assert_eq!(merge_kinds("create", "delete"), None);
+        assert_eq!(merge_kinds("create", "modify"), Some("create"));
+        assert_eq!(merge_kinds("modify", "delete"), Some("delete"));
+        assert_eq!(merge_kinds("delete", "create"), Some("modify"));
+        assert_eq!(merge_kinds("modify", "modify"), Some("modify"));
+    }
+}
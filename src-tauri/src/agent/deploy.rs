@@ -1,41 +1,147 @@
 //! Agent Deployer — uploads and starts the agent on a remote host.
 //!
 //! Workflow:
-//! 1. Detect remote architecture via `uname -m`
-//! 2. Check if agent is already deployed (version match)
-//! 3. Upload the correct binary via SFTP
-//! 4. chmod +x
-//! 5. Start agent via SSH exec channel
-//! 6. Handshake: wait for `sys/info` response
+//! 1. Detect remote OS and architecture via `uname -sm`
+//! 2. Pick the first install directory that is writable and allows exec
+//!    (`~/.oxideterm`, `/tmp/.oxideterm-$USER`, `$XDG_RUNTIME_DIR/.oxideterm`)
+//! 3. Check if agent is already deployed (version and SHA-256 match)
+//! 4. Upload the correct binary via SFTP and verify its SHA-256
+//! 5. chmod +x
+//! 6. Start agent via SSH exec channel
+//! 7. Handshake: wait for `sys/info` response
 //!
-//! Agent binaries are bundled in the app resources as:
+//! Agent binaries are bundled in the app resources as
+//! `agents/oxideterm-agent-{target}`, e.g.:
 //! - `agents/oxideterm-agent-x86_64-linux-musl`
 //! - `agents/oxideterm-agent-aarch64-linux-musl`
+//! - `agents/oxideterm-agent-aarch64-apple-darwin`
+//! - `agents/oxideterm-agent-x86_64-unknown-freebsd`
 
 use std::path::PathBuf;
 
+use sha2::{Digest, Sha256};
 use tauri::Manager;
 use tracing::{debug, info, warn};
 
 use super::protocol::{AgentStatus, SysInfoResult};
-use super::transport::AgentTransport;
+use super::transport::{AgentTransport, RPC_COMPRESSION_CAPABILITY};
 use crate::sftp::session::SftpSession;
 use crate::ssh::HandleController;
 
-/// Remote path where the agent binary is stored.
-const AGENT_REMOTE_DIR: &str = ".oxideterm";
+/// Directories the agent may be installed in, tried in order. Each is a
+/// shell word expanded on the remote host; unset variables make it fail.
+const AGENT_DIR_CANDIDATES: &[&str] = &[
+    "~/.oxideterm",
+    "/tmp/.oxideterm-$USER",
+    "${XDG_RUNTIME_DIR:?}/.oxideterm",
+];
 const AGENT_BINARY_NAME: &str = "oxideterm-agent";
 
+/// Check that `{dir}` can hold and run the agent: create it (ours, not a
+/// symlink — `/tmp` is shared), then write and run a tiny script there.
+/// Running it catches noexec mounts, where `chmod +x` succeeds but exec
+/// fails. Prints `OXIDETERM_DIR_OK <absolute dir>` on success.
+const DIR_PROBE_SCRIPT: &str = r#"d={dir} && mkdir -p "$d" && [ -O "$d" ] && [ ! -L "$d" ] && chmod 700 "$d" && p="$d/.oxideterm-probe" && printf '#!/bin/sh\necho ok\n' > "$p" && chmod +x "$p" && [ "$("$p")" = ok ] && echo "OXIDETERM_DIR_OK $(cd "$d" && pwd)"; rm -f "$d/.oxideterm-probe""#;
+
 /// Current agent version (must match agent/Cargo.toml).
 const AGENT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Print the SHA-256 of `{path}` with whichever tool the remote host has
+/// (`sha256sum` on Linux, `shasum` on macOS/BSD).
+const REMOTE_SHA256_COMMAND: &str =
+    "sha256sum {path} 2>/dev/null || shasum -a 256 {path} 2>/dev/null";
+
+/// A bundled agent binary, read into memory with its digest.
+struct LocalBinary {
+    path: PathBuf,
+    data: Vec<u8>,
+    /// Lowercase hex SHA-256 of `data`.
+    sha256: String,
+}
+
+/// Lowercase hex SHA-256 of `data`.
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Extract the digest from `sha256sum` / `shasum -a 256` output
+/// (`<hex>  <path>`). `None` if no valid digest was printed.
+fn parse_sha256_output(output: &str) -> Option<String> {
+    let digest = output.split_whitespace().next()?;
+    if digest.len() == 64 && digest.bytes().all(|b| b.is_ascii_hexdigit()) {
+        Some(digest.to_ascii_lowercase())
+    } else {
+        None
+    }
+}
+
+/// Remote OS and CPU as reported by `uname -sm`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RemotePlatform {
+    /// `uname -s`, e.g. `Linux`, `Darwin`, `FreeBSD`
+    os: String,
+    /// `uname -m`, e.g. `x86_64`, `arm64`
+    arch: String,
+}
+
+impl std::fmt::Display for RemotePlatform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.os, self.arch)
+    }
+}
+
+/// Parse `uname -sm` output (`Linux x86_64`).
+fn parse_uname(output: &str) -> Option<RemotePlatform> {
+    let mut parts = output.split_whitespace();
+    let os = parts.next()?;
+    let arch = parts.next()?;
+    Some(RemotePlatform {
+        os: os.to_string(),
+        arch: arch.to_string(),
+    })
+}
+
+/// Single-quote `s` for a POSIX shell.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+/// Extract the directory from [`DIR_PROBE_SCRIPT`] output. `None` unless
+/// the probe passed and reported an absolute path.
+fn parse_probe_output(output: &str) -> Option<String> {
+    output
+        .lines()
+        .find_map(|line| line.strip_prefix("OXIDETERM_DIR_OK "))
+        .map(str::trim)
+        .filter(|dir| dir.starts_with('/'))
+        .map(str::to_string)
+}
+
+/// A started agent and where it is installed.
+pub struct AgentDeployment {
+    pub transport: AgentTransport,
+    pub info: SysInfoResult,
+    /// Absolute remote directory holding the agent binary.
+    pub remote_dir: String,
+}
+
 /// Deployer for the OxideTerm agent.
 pub struct AgentDeployer;
 
 impl AgentDeployer {
+    /// Path of the agent binary inside an install directory.
+    pub fn binary_path(remote_dir: &str) -> String {
+        format!("{}/{}", remote_dir, AGENT_BINARY_NAME)
+    }
+
     /// Deploy and start the agent on a remote host.
     ///
-    /// Returns a connected `AgentTransport` if successful.
+    /// `preferred_dir` (the directory a previous deploy used) is probed
+    /// before the default candidates. Returns the connected transport and
+    /// the directory that worked.
     ///
     /// For unsupported architectures, this method will:
     /// 1. Check if a manually uploaded agent binary exists at the remote path
@@ -45,94 +151,169 @@ impl AgentDeployer {
         controller: &HandleController,
         sftp: &SftpSession,
         app_handle: &tauri::AppHandle,
-    ) -> Result<(AgentTransport, SysInfoResult), DeployError> {
-        // Step 1: Detect remote architecture
-        let arch = Self::detect_arch(controller).await?;
-        info!("[agent-deploy] Remote architecture: {}", arch);
+        preferred_dir: Option<&str>,
+    ) -> Result<AgentDeployment, DeployError> {
+        // Step 1: Detect remote OS and architecture
+        let platform = Self::detect_arch(controller).await?;
+        info!("[agent-deploy] Remote platform: {}", platform);
 
-        // Step 2: Determine remote path
-        let remote_dir = format!("~/{}", AGENT_REMOTE_DIR);
-        let remote_path = format!("{}/{}", remote_dir, AGENT_BINARY_NAME);
+        // Step 2: Find a directory we can write to and execute from
+        let remote_dir = Self::find_install_dir(controller, preferred_dir).await?;
+        let remote_path = Self::binary_path(&remote_dir);
+        info!("[agent-deploy] Install directory: {}", remote_dir);
 
-        // Step 3: Try to resolve the local binary for this architecture
-        let local_binary_result = Self::resolve_binary(&arch, app_handle);
+        // Step 3: Try to resolve the local binary for this platform
+        let local_binary_result = Self::resolve_binary(&platform, app_handle);
 
         match local_binary_result {
             Ok(local_binary) => {
                 // Supported architecture — proceed with auto-deploy
                 info!(
-                    "[agent-deploy] Using binary: {}",
-                    local_binary.display()
+                    "[agent-deploy] Using binary: {} (sha256 {})",
+                    local_binary.path.display(),
+                    local_binary.sha256
                 );
 
-                // Step 4: Check if agent is already deployed (check version)
-                let needs_upload = Self::needs_upload(controller, &remote_path).await;
+                // Step 4: Check if agent is already deployed (version + hash)
+                let needs_upload =
+                    Self::needs_upload(controller, &remote_path, Some(&local_binary.sha256)).await;
 
                 if needs_upload {
                     // Step 5: Upload binary
                     info!("[agent-deploy] Uploading agent binary...");
 
-                    // Ensure remote directory exists
-                    Self::exec_simple(controller, &format!("mkdir -p {}", remote_dir)).await?;
-
-                    // Read the local binary
-                    let binary_data = tokio::fs::read(&local_binary)
-                        .await
-                        .map_err(|e| DeployError::LocalIo(e.to_string()))?;
-
                     info!(
                         "[agent-deploy] Binary size: {} bytes",
-                        binary_data.len()
+                        local_binary.data.len()
                     );
 
                     // Upload via SFTP
-                    sftp.write_content(&remote_path, &binary_data)
+                    sftp.write_content(&remote_path, &local_binary.data)
                         .await
                         .map_err(|e| DeployError::Upload(e.to_string()))?;
 
+                    // A partial write must not be executed (or kept for next time)
+                    match Self::remote_sha256(controller, &remote_path).await {
+                        Some(actual) if actual != local_binary.sha256 => {
+                            warn!(
+                                "[agent-deploy] Uploaded binary hash mismatch: expected {}, got {}",
+                                local_binary.sha256, actual
+                            );
+                            let rm = format!("rm -f {}", shell_quote(&remote_path));
+                            let _ = Self::exec_simple(controller, &rm).await;
+                            return Err(DeployError::IntegrityMismatch {
+                                expected: local_binary.sha256,
+                                actual,
+                            });
+                        }
+                        Some(_) => debug!("[agent-deploy] Uploaded binary hash verified"),
+                        None => warn!(
+                            "[agent-deploy] No sha256sum/shasum on remote host, upload not verified"
+                        ),
+                    }
+
                     // chmod +x
-                    Self::exec_simple(controller, &format!("chmod +x {}", remote_path)).await?;
+                    let chmod = format!("chmod +x {}", shell_quote(&remote_path));
+                    Self::exec_simple(controller, &chmod).await?;
 
                     info!("[agent-deploy] Upload complete");
                 } else {
                     info!("[agent-deploy] Agent already deployed, skipping upload");
                 }
             }
-            Err(DeployError::UnsupportedArch(ref unsupported_arch)) => {
-                // Unsupported architecture — check if user manually uploaded a binary
+            Err(ref e @ (DeployError::UnsupportedArch(_) | DeployError::NoBundledAgent(_))) => {
+                // No bundled binary — check if user manually uploaded one
                 info!(
-                    "[agent-deploy] Unsupported architecture '{}', checking for manual upload at {}",
-                    unsupported_arch, remote_path
+                    "[agent-deploy] {}, checking for manual upload at {}",
+                    e, remote_path
                 );
 
-                let needs_upload = Self::needs_upload(controller, &remote_path).await;
+                // Nothing to compare a user-provided binary's hash against
+                let needs_upload = Self::needs_upload(controller, &remote_path, None).await;
 
                 if needs_upload {
                     // No usable binary found — inform user to manually upload
                     info!(
-                        "[agent-deploy] No agent binary found for arch '{}', manual upload required",
-                        arch
+                        "[agent-deploy] No agent binary found for {}, manual upload required",
+                        platform
                     );
                     return Err(DeployError::ManualUploadRequired {
-                        arch: arch.clone(),
+                        arch: platform.to_string(),
                         remote_path: remote_path.clone(),
                     });
                 } else {
                     // User has manually uploaded a binary — proceed
                     info!(
-                        "[agent-deploy] Found manually uploaded agent for unsupported arch '{}'",
-                        arch
+                        "[agent-deploy] Found manually uploaded agent for {}",
+                        platform
                     );
                 }
             }
             Err(e) => return Err(e),
         }
 
-        // Step 6: Start the agent
-        let transport = Self::start_agent(controller, &remote_path).await?;
+        // Steps 6–7: Start the agent and handshake
+        let (transport, info) = Self::restart(controller, &remote_path).await?;
+        Ok(AgentDeployment {
+            transport,
+            info,
+            remote_dir,
+        })
+    }
+
+    /// First install directory that passes [`DIR_PROBE_SCRIPT`], trying
+    /// `preferred` before [`AGENT_DIR_CANDIDATES`].
+    async fn find_install_dir(
+        controller: &HandleController,
+        preferred: Option<&str>,
+    ) -> Result<String, DeployError> {
+        let candidates: Vec<String> = preferred
+            .map(shell_quote)
+            .into_iter()
+            .chain(AGENT_DIR_CANDIDATES.iter().map(|c| c.to_string()))
+            .collect();
+
+        for candidate in &candidates {
+            let script = DIR_PROBE_SCRIPT.replace("{dir}", candidate);
+            let result = crate::commands::ide::exec_command_inner(
+                controller.clone(),
+                format!("sh -c {}", shell_quote(&script)),
+                None,
+                Vec::new(),
+                Some(10),
+            )
+            .await;
+            match result.ok().and_then(|r| parse_probe_output(&r.stdout)) {
+                Some(dir) => return Ok(dir),
+                None => debug!(
+                    "[agent-deploy] {} is not writable or executable, trying next",
+                    candidate
+                ),
+            }
+        }
+
+        Err(DeployError::NoInstallDir(candidates.join(", ")))
+    }
+
+    /// Start an already deployed agent and handshake with it.
+    ///
+    /// Used by `deploy_and_start` once the binary is in place, and by the
+    /// registry to bring an agent back after its exec channel died.
+    pub async fn restart(
+        controller: &HandleController,
+        remote_path: &str,
+    ) -> Result<(AgentTransport, SysInfoResult), DeployError> {
+        let transport = Self::start_agent(controller, remote_path).await?;
 
-        // Step 7: Handshake — verify via sys/info
+        // Verify via sys/info
         let info = Self::handshake(&transport).await?;
+        if info
+            .capabilities
+            .iter()
+            .any(|c| c == RPC_COMPRESSION_CAPABILITY)
+        {
+            transport.enable_compression();
+        }
         info!(
             "[agent-deploy] Agent ready: v{} {} (pid {})",
             info.version, info.arch, info.pid
@@ -141,69 +322,111 @@ impl AgentDeployer {
         Ok((transport, info))
     }
 
-    /// Detect remote OS architecture via `uname -m`.
-    async fn detect_arch(controller: &HandleController) -> Result<String, DeployError> {
+    /// Detect remote OS and architecture via `uname -sm`.
+    async fn detect_arch(controller: &HandleController) -> Result<RemotePlatform, DeployError> {
         let result = crate::commands::ide::exec_command_inner(
             controller.clone(),
-            "uname -m".to_string(),
+            "uname -sm".to_string(),
             None,
+            Vec::new(),
             Some(10),
         )
         .await
         .map_err(|e| DeployError::ArchDetection(e))?;
 
-        let arch = result.stdout.trim().to_string();
-        if arch.is_empty() {
-            return Err(DeployError::ArchDetection(
-                "uname -m returned empty output".to_string(),
-            ));
-        }
-
-        Ok(arch)
+        parse_uname(&result.stdout).ok_or_else(|| {
+            DeployError::ArchDetection(format!(
+                "Unexpected uname -sm output: {:?}",
+                result.stdout.trim()
+            ))
+        })
     }
 
-    /// Map `uname -m` output to our binary suffix.
-    fn arch_to_target(arch: &str) -> Result<&'static str, DeployError> {
-        match arch {
-            "x86_64" | "amd64" => Ok("x86_64-linux-musl"),
-            "aarch64" | "arm64" => Ok("aarch64-linux-musl"),
-            other => Err(DeployError::UnsupportedArch(other.to_string())),
+    /// Map `uname -s` / `uname -m` output to our binary suffix.
+    fn arch_to_target(os: &str, arch: &str) -> Result<&'static str, DeployError> {
+        let cpu = match arch {
+            "x86_64" | "amd64" => "x86_64",
+            "aarch64" | "arm64" => "aarch64",
+            other => return Err(DeployError::UnsupportedArch(format!("{}/{}", os, other))),
+        };
+        match (os.to_ascii_lowercase().as_str(), cpu) {
+            ("linux", "x86_64") => Ok("x86_64-linux-musl"),
+            ("linux", _) => Ok("aarch64-linux-musl"),
+            ("darwin", "x86_64") => Ok("x86_64-apple-darwin"),
+            ("darwin", _) => Ok("aarch64-apple-darwin"),
+            ("freebsd", "x86_64") => Ok("x86_64-unknown-freebsd"),
+            ("freebsd", _) => Ok("aarch64-unknown-freebsd"),
+            _ => Err(DeployError::UnsupportedArch(format!("{}/{}", os, arch))),
         }
     }
 
-    /// Resolve the path to the bundled agent binary for the given arch.
-    fn resolve_binary(arch: &str, app_handle: &tauri::AppHandle) -> Result<PathBuf, DeployError> {
-        let target = Self::arch_to_target(arch)?;
+    /// Resolve and read the bundled agent binary for the given platform,
+    /// hashing it for the remote integrity check.
+    fn resolve_binary(
+        platform: &RemotePlatform,
+        app_handle: &tauri::AppHandle,
+    ) -> Result<LocalBinary, DeployError> {
+        let target = Self::arch_to_target(&platform.os, &platform.arch)?;
         let binary_name = format!("agents/oxideterm-agent-{}", target);
 
-        // Try to resolve from Tauri resources
+        // Try to resolve from Tauri resources; a known target may simply
+        // not be bundled in this build
         let resource_path = app_handle
             .path()
             .resolve(&binary_name, tauri::path::BaseDirectory::Resource)
             .map_err(|e| {
-                DeployError::BinaryNotFound(format!(
-                    "Resource '{}' not found: {}",
-                    binary_name, e
-                ))
+                debug!("[agent-deploy] Resource '{}' not found: {}", binary_name, e);
+                DeployError::NoBundledAgent(platform.to_string())
             })?;
 
         if !resource_path.exists() {
-            return Err(DeployError::BinaryNotFound(format!(
-                "Binary not found at {:?}",
-                resource_path
-            )));
+            debug!("[agent-deploy] Binary not found at {:?}", resource_path);
+            return Err(DeployError::NoBundledAgent(platform.to_string()));
         }
 
-        Ok(resource_path)
+        let data = std::fs::read(&resource_path).map_err(|e| DeployError::LocalIo(e.to_string()))?;
+        let sha256 = sha256_hex(&data);
+        Ok(LocalBinary {
+            path: resource_path,
+            data,
+            sha256,
+        })
+    }
+
+    /// SHA-256 of a remote file, or `None` if it can't be computed (missing
+    /// file, or neither `sha256sum` nor `shasum` installed).
+    async fn remote_sha256(controller: &HandleController, remote_path: &str) -> Option<String> {
+        let command = REMOTE_SHA256_COMMAND.replace("{path}", &shell_quote(remote_path));
+        let result = crate::commands::ide::exec_command_inner(
+            controller.clone(),
+            command,
+            None,
+            Vec::new(),
+            Some(10),
+        )
+        .await
+        .ok()?;
+        parse_sha256_output(&result.stdout)
     }
 
-    /// Check if the deployed version matches (fast check via --version flag).
-    async fn needs_upload(controller: &HandleController, remote_path: &str) -> bool {
+    /// Check if the deployed version matches (fast check via --version flag),
+    /// then, if `expected_sha256` is given, that the remote file is exactly
+    /// the bundled binary — a truncated or altered upload can still report
+    /// the right version.
+    async fn needs_upload(
+        controller: &HandleController,
+        remote_path: &str,
+        expected_sha256: Option<&str>,
+    ) -> bool {
         // Try running the agent with --version flag
         let result = crate::commands::ide::exec_command_inner(
             controller.clone(),
-            format!("{} --version 2>/dev/null || echo 'NOT_FOUND'", remote_path),
+            format!(
+                "{} --version 2>/dev/null || echo 'NOT_FOUND'",
+                shell_quote(remote_path)
+            ),
             None,
+            Vec::new(),
             Some(5),
         )
         .await;
@@ -216,7 +439,26 @@ impl AgentDeployer {
                     true
                 } else if output.contains(AGENT_VERSION) {
                     debug!("[agent-deploy] Version match: {}", output);
-                    false
+                    let Some(expected) = expected_sha256 else {
+                        return false;
+                    };
+                    match Self::remote_sha256(controller, remote_path).await {
+                        Some(actual) if actual == expected => {
+                            debug!("[agent-deploy] Hash match: {}", actual);
+                            false
+                        }
+                        Some(actual) => {
+                            warn!(
+                                "[agent-deploy] Hash mismatch for {}: expected {}, got {}",
+                                remote_path, expected, actual
+                            );
+                            true
+                        }
+                        None => {
+                            debug!("[agent-deploy] Could not hash remote binary, will upload");
+                            true
+                        }
+                    }
                 } else {
                     debug!(
                         "[agent-deploy] Version mismatch: got '{}', want '{}'",
@@ -242,7 +484,7 @@ impl AgentDeployer {
             .await
             .map_err(|e| DeployError::StartFailed(format!("Channel open failed: {}", e)))?;
 
-        let agent_command = remote_path.to_string();
+        let agent_command = shell_quote(remote_path);
 
         let transport = AgentTransport::new(channel, &agent_command)
             .await
@@ -279,6 +521,7 @@ impl AgentDeployer {
             controller.clone(),
             command.to_string(),
             None,
+            Vec::new(),
             Some(30),
         )
         .await
@@ -319,8 +562,11 @@ pub enum DeployError {
     #[error("Manual upload required for arch '{arch}': upload agent binary to {remote_path}")]
     ManualUploadRequired { arch: String, remote_path: String },
 
-    #[error("Agent binary not found: {0}")]
-    BinaryNotFound(String),
+    #[error("No writable directory allowing exec for the agent (tried {0})")]
+    NoInstallDir(String),
+
+    #[error("No bundled agent for {0}")]
+    NoBundledAgent(String),
 
     #[error("Local I/O error: {0}")]
     LocalIo(String),
@@ -336,6 +582,9 @@ pub enum DeployError {
 
     #[error("Handshake failed: {0}")]
     Handshake(String),
+
+    #[error("Uploaded agent binary is corrupt (sha256 {actual}, expected {expected})")]
+    IntegrityMismatch { expected: String, actual: String },
 }
 
 impl std::fmt::Display for AgentStatus {
@@ -346,6 +595,7 @@ impl std::fmt::Display for AgentStatus {
             AgentStatus::Ready { version, arch, pid } => {
                 write!(f, "Ready v{} {} (pid {})", version, arch, pid)
             }
+            AgentStatus::Reconnecting => write!(f, "Reconnecting..."),
             AgentStatus::Failed { reason } => write!(f, "Failed: {}", reason),
             AgentStatus::UnsupportedArch { arch } => write!(f, "Unsupported arch: {}", arch),
             AgentStatus::ManualUploadRequired { arch, remote_path } => {
@@ -354,3 +604,97 @@ impl std::fmt::Display for AgentStatus {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256_hex_matches_known_digest() {
+        assert_eq!(
+            sha256_hex(b"hello"),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+    }
+
+    #[test]
+    fn test_arch_to_target_table() {
+        let cases = [
+            ("Linux", "x86_64", "x86_64-linux-musl"),
+            ("Linux", "aarch64", "aarch64-linux-musl"),
+            ("Darwin", "x86_64", "x86_64-apple-darwin"),
+            ("Darwin", "arm64", "aarch64-apple-darwin"),
+            ("FreeBSD", "amd64", "x86_64-unknown-freebsd"),
+            ("FreeBSD", "arm64", "aarch64-unknown-freebsd"),
+        ];
+        for (os, arch, target) in cases {
+            assert_eq!(
+                AgentDeployer::arch_to_target(os, arch).unwrap(),
+                target,
+                "{}/{}",
+                os,
+                arch
+            );
+        }
+    }
+
+    #[test]
+    fn test_arch_to_target_rejects_unknown_platforms() {
+        let err = AgentDeployer::arch_to_target("Linux", "mips").unwrap_err();
+        assert!(matches!(err, DeployError::UnsupportedArch(ref p) if p == "Linux/mips"));
+        let err = AgentDeployer::arch_to_target("SunOS", "x86_64").unwrap_err();
+        assert!(matches!(err, DeployError::UnsupportedArch(ref p) if p == "SunOS/x86_64"));
+    }
+
+    #[test]
+    fn test_parse_uname() {
+        let platform = parse_uname("Darwin arm64\n").unwrap();
+        assert_eq!(platform.os, "Darwin");
+        assert_eq!(platform.arch, "arm64");
+        assert_eq!(platform.to_string(), "Darwin/arm64");
+        assert_eq!(
+            DeployError::NoBundledAgent(platform.to_string()).to_string(),
+            "No bundled agent for Darwin/arm64"
+        );
+        assert_eq!(parse_uname("x86_64"), None);
+        assert_eq!(parse_uname(""), None);
+    }
+
+    #[test]
+    fn test_parse_probe_output() {
+        assert_eq!(
+            parse_probe_output("OXIDETERM_DIR_OK /home/u/.oxideterm\n").as_deref(),
+            Some("/home/u/.oxideterm")
+        );
+        // noexec /tmp: the probe script never prints its marker
+        assert_eq!(
+            parse_probe_output("sh: /tmp/.oxideterm-u/.oxideterm-probe: Permission denied"),
+            None
+        );
+        assert_eq!(parse_probe_output(""), None);
+        assert_eq!(parse_probe_output("OXIDETERM_DIR_OK relative"), None);
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("/home/u/.oxideterm"), "'/home/u/.oxideterm'");
+        assert_eq!(shell_quote("/srv/it's"), r"'/srv/it'\''s'");
+    }
+
+    #[test]
+    fn test_parse_sha256_output() {
+        let digest = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        // sha256sum and shasum print the same `<hex>  <path>` line
+        let line = format!("{}  /home/u/.oxideterm/oxideterm-agent\n", digest);
+        assert_eq!(parse_sha256_output(&line).as_deref(), Some(digest));
+        assert_eq!(
+            parse_sha256_output(&line.to_uppercase()).as_deref(),
+            Some(digest)
+        );
+
+        // Missing file or tool: nothing (or an error) on stdout
+        assert_eq!(parse_sha256_output(""), None);
+        assert_eq!(parse_sha256_output("sha256sum: not found"), None);
+        assert_eq!(parse_sha256_output("2cf24dba  truncated"), None);
+    }
+}
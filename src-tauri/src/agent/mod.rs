@@ -11,7 +11,10 @@ mod protocol;
 mod registry;
 mod transport;
 
-pub use deploy::{AgentDeployer, DeployError};
+pub use deploy::{AgentDeployer, AgentDeployment, DeployError};
 pub use protocol::*;
-pub use registry::{AgentRegistry, AgentSession};
-pub use transport::AgentTransport;
+pub use registry::{
+    watch_event_name, AgentReconnectPayload, AgentRegistry, AgentSession, BackendChangedPayload,
+    OperationMode, AGENT_RECONNECT_EVENT, FS_BACKEND_CHANGED_EVENT, WATCH_EVENT_PREFIX,
+};
+pub use transport::{AgentTransport, StreamingResponse, TransportError};
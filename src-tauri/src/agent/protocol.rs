@@ -28,6 +28,34 @@ pub struct AgentRequest {
     pub method: String,
     #[serde(skip_serializing_if = "serde_json::Value::is_null")]
     pub params: serde_json::Value,
+    /// Accept a gzip+base64 `result` (only for agents advertising `rpc-gzip`).
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub compress: bool,
+    /// Safe to send again to a restarted agent if the channel dies before
+    /// the response arrives. Client-side only; see [`is_idempotent`].
+    #[serde(skip)]
+    pub idempotent: bool,
+}
+
+/// Whether `method` can be replayed after an agent reconnect without side
+/// effects. Only pure reads qualify: mutations such as `fs/writeFile` may
+/// already have been applied by the agent that died, and streamed reads have
+/// already handed chunks to their consumer.
+pub fn is_idempotent(method: &str) -> bool {
+    matches!(
+        method,
+        "fs/readFile"
+            | "fs/stat"
+            | "fs/listDir"
+            | "fs/listTree"
+            | "search/grep"
+            | "git/status"
+            | "symbols/index"
+            | "symbols/complete"
+            | "symbols/definitions"
+            | "sys/info"
+            | "sys/ping"
+    )
 }
 
 /// Incoming response from the agent.
@@ -38,6 +66,9 @@ pub struct AgentResponse {
     pub result: Option<serde_json::Value>,
     #[serde(default)]
     pub error: Option<AgentRpcError>,
+    /// "gzip+base64" when `result` is a compressed JSON string.
+    #[serde(default)]
+    pub encoding: Option<String>,
 }
 
 /// JSON-RPC error object.
@@ -45,6 +76,9 @@ pub struct AgentResponse {
 pub struct AgentRpcError {
     pub code: i32,
     pub message: String,
+    /// Structured details (e.g. `current_hash` for `ERR_CONFLICT`).
+    #[serde(default)]
+    pub data: Option<serde_json::Value>,
 }
 
 impl std::fmt::Display for AgentRpcError {
@@ -81,6 +115,7 @@ pub const ERR_IO: i32 = -1;
 pub const ERR_NOT_FOUND: i32 = -2;
 pub const ERR_PERMISSION: i32 = -3;
 pub const ERR_ALREADY_EXISTS: i32 = -4;
+pub const ERR_CONFLICT: i32 = -5;
 
 // ═══════════════════════════════════════════════════════════════════════════
 // fs/* result types (deserialized from agent responses)
@@ -93,7 +128,7 @@ pub struct ReadFileResult {
     pub hash: String,
     pub size: u64,
     pub mtime: u64,
-    /// Content encoding: "plain" or "zstd+base64".
+    /// Content encoding: "plain", "base64" (binary reads) or "zstd+base64".
     #[serde(default = "default_encoding")]
     pub encoding: String,
 }
@@ -102,6 +137,25 @@ fn default_encoding() -> String {
     "plain".to_string()
 }
 
+/// fs/chunk notification — one piece of an fs/readFileStream, in file order
+#[derive(Debug, Deserialize)]
+pub struct FileChunk {
+    /// `id` of the fs/readFileStream request.
+    pub id: u64,
+    /// Base64-encoded raw bytes.
+    pub data: String,
+}
+
+/// fs/readFileStream result, sent after the last fs/chunk
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ReadFileStreamResult {
+    /// SHA-256 hex digest of all streamed bytes.
+    pub hash: String,
+    pub size: u64,
+    pub mtime: u64,
+    pub chunks: u64,
+}
+
 /// fs/writeFile result
 #[derive(Debug, Deserialize, Serialize)]
 pub struct WriteFileResult {
@@ -119,6 +173,8 @@ pub struct StatResult {
     pub size: Option<u64>,
     pub mtime: Option<u64>,
     pub permissions: Option<String>,
+    /// Regular files, when requested: whether the start of the file looks binary.
+    pub is_binary: Option<bool>,
 }
 
 /// File entry (used by listDir/listTree)
@@ -134,6 +190,10 @@ pub struct FileEntry {
     /// True if this directory's listing was cut short by the entry budget.
     #[serde(default)]
     pub truncated: bool,
+    /// True if this entry was already sent in an earlier listTree page and is
+    /// repeated only to carry newly listed children.
+    #[serde(default)]
+    pub continued: bool,
 }
 
 /// fs/listTree result — entries + truncation metadata
@@ -144,6 +204,9 @@ pub struct ListTreeResult {
     pub truncated: bool,
     /// Total number of entries scanned.
     pub total_scanned: u32,
+    /// Cursor for the next page (present only when truncated).
+    #[serde(default)]
+    pub next_cursor: Option<String>,
 }
 
 /// search/grep match
@@ -169,12 +232,55 @@ pub struct GitFileEntry {
 }
 
 /// sys/info result
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SysInfoResult {
     pub version: String,
     pub arch: String,
     pub os: String,
     pub pid: u32,
+    /// Optional features, e.g. `"zstd"`.
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// JSON-RPC methods the agent handles (empty on agents that predate it).
+    #[serde(default)]
+    pub methods: Vec<String>,
+    /// Per-session resource caps (absent on agents that predate them).
+    #[serde(default)]
+    pub limits: Option<ResourceLimits>,
+    /// Resident memory of the agent process; 0 on agents that predate it or
+    /// can't measure it.
+    #[serde(default)]
+    pub rss_bytes: u64,
+    /// Seconds since the agent started (0 on agents that predate it).
+    #[serde(default)]
+    pub uptime_secs: u64,
+}
+
+/// Agent self-reported footprint, for the diagnostics panel.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentUsage {
+    pub pid: u32,
+    pub rss_bytes: u64,
+    pub uptime_secs: u64,
+}
+
+impl From<&SysInfoResult> for AgentUsage {
+    fn from(info: &SysInfoResult) -> Self {
+        Self {
+            pid: info.pid,
+            rss_bytes: info.rss_bytes,
+            uptime_secs: info.uptime_secs,
+        }
+    }
+}
+
+/// Resource caps reported by the agent in sys/info.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub struct ResourceLimits {
+    pub max_concurrent_ops: usize,
+    pub max_result_bytes: usize,
+    pub max_watched_dirs: usize,
 }
 
 /// watch/event notification
@@ -184,6 +290,12 @@ pub struct WatchEvent {
     pub kind: String,
 }
 
+/// watch/event-batch notification (watches started with `batch: true`)
+#[derive(Debug, Deserialize)]
+pub struct WatchEventBatch {
+    pub events: Vec<WatchEvent>,
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // symbols/* types (mirror of agent/src/protocol.rs)
 // ═══════════════════════════════════════════════════════════════════════════
@@ -242,6 +354,8 @@ pub enum AgentStatus {
         arch: String,
         pid: u32,
     },
+    /// Agent channel died; the registry is restarting it
+    Reconnecting,
     /// Agent failed to start
     Failed { reason: String },
     /// Architecture not supported (no binary available)
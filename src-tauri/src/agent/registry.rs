@@ -4,50 +4,178 @@
 //! The registry provides thread-safe access and automatic cleanup
 //! when connections are closed.
 
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use dashmap::DashMap;
-use tracing::info;
+use futures_util::stream::{self, Stream};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::{mpsc, watch};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
 
+use super::deploy::{AgentDeployer, DeployError};
 use super::protocol::{
-    AgentStatus, FileEntry, GitStatusResult, GrepMatch, ListTreeResult, ReadFileResult,
-    StatResult, SymbolIndexResult, SymbolInfo, SysInfoResult, WatchEvent, WriteFileResult,
+    is_idempotent, AgentStatus, AgentUsage, FileEntry, GitStatusResult, GrepMatch, ListTreeResult,
+    ReadFileResult, ReadFileStreamResult, StatResult, SymbolIndexResult, SymbolInfo,
+    SysInfoResult, WatchEvent, WriteFileResult,
 };
 use super::transport::{AgentTransport, TransportError};
+use crate::ssh::HandleController;
+
+/// Restart attempts after an agent channel dies before falling back to SFTP.
+const RECONNECT_ATTEMPTS: u32 = 3;
+
+/// Delay before the second restart attempt; doubles for each one after.
+const RECONNECT_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Longest an interrupted idempotent call waits for the agent to come back.
+const REPLAY_WAIT: Duration = Duration::from_secs(60);
+
+/// Methods every agent build has shipped with. Agents that predate the
+/// `methods` field in sys/info are assumed to support exactly these.
+const LEGACY_METHODS: &[&str] = &[
+    "fs/readFile",
+    "fs/writeFile",
+    "fs/stat",
+    "fs/listDir",
+    "fs/listTree",
+    "fs/mkdir",
+    "fs/remove",
+    "fs/rename",
+    "fs/chmod",
+    "search/grep",
+    "git/status",
+    "watch/start",
+    "watch/stop",
+    "symbols/index",
+    "symbols/complete",
+    "symbols/definitions",
+    "sys/info",
+    "sys/ping",
+    "sys/shutdown",
+];
+
+/// Whether an agent advertising `methods` handles `method`.
+fn method_supported(methods: &[String], method: &str) -> bool {
+    if methods.is_empty() {
+        return LEGACY_METHODS.contains(&method);
+    }
+    methods.iter().any(|m| m == method)
+}
+
+/// Whether a session's agent is usable, being restarted, or gone for good.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LinkState {
+    Up,
+    Reconnecting,
+    Down,
+}
+
+/// How to start a session's agent again after its channel dies.
+struct RestartTarget {
+    controller: HandleController,
+    remote_path: String,
+}
+
+/// Parameters of an active `watch/start`, re-issued after a restart.
+#[derive(Debug, Clone)]
+struct WatchSpec {
+    ignore: Vec<String>,
+    debounce_ms: Option<u64>,
+}
 
 /// An active agent session for a single SSH connection.
+///
+/// The transport is replaced when the registry restarts a dead agent;
+/// idempotent calls interrupted by the outage are replayed on the new one.
 pub struct AgentSession {
     /// JSON-RPC transport layer.
-    transport: AgentTransport,
+    transport: parking_lot::RwLock<Arc<AgentTransport>>,
+
+    /// Agent system info (from the latest handshake).
+    info: parking_lot::RwLock<SysInfoResult>,
 
-    /// Agent system info (from handshake).
-    info: SysInfoResult,
+    /// `None` for sessions that must not be restarted.
+    restart: Option<RestartTarget>,
+
+    link: watch::Sender<LinkState>,
+
+    /// Active watches by path; a restarted agent starts with none.
+    watches: parking_lot::Mutex<HashMap<String, WatchSpec>>,
+
+    /// Set by `shutdown` so the registry doesn't bring the agent back.
+    stopped: AtomicBool,
 }
 
 impl AgentSession {
     /// Create a new agent session.
     pub fn new(transport: AgentTransport, info: SysInfoResult) -> Self {
-        Self { transport, info }
+        Self {
+            transport: parking_lot::RwLock::new(Arc::new(transport)),
+            info: parking_lot::RwLock::new(info),
+            restart: None,
+            link: watch::Sender::new(LinkState::Up),
+            watches: parking_lot::Mutex::new(HashMap::new()),
+            stopped: AtomicBool::new(false),
+        }
+    }
+
+    /// Let the registry restart the agent at `remote_path` if its channel
+    /// dies while the SSH connection is still up.
+    pub fn with_restart(mut self, controller: HandleController, remote_path: String) -> Self {
+        self.restart = Some(RestartTarget {
+            controller,
+            remote_path,
+        });
+        self
+    }
+
+    fn transport(&self) -> Arc<AgentTransport> {
+        self.transport.read().clone()
     }
 
     /// Get the agent's system info.
-    pub fn info(&self) -> &SysInfoResult {
-        &self.info
+    pub fn info(&self) -> SysInfoResult {
+        self.info.read().clone()
+    }
+
+    /// Whether this agent handles `method`, per its handshake sys/info.
+    pub fn supports(&self, method: &str) -> bool {
+        method_supported(&self.info.read().methods, method)
     }
 
     /// Check if the agent is still alive.
     pub fn is_alive(&self) -> bool {
-        self.transport.is_alive()
+        self.transport().is_alive()
+    }
+
+    /// Whether the registry is restarting this session's agent right now.
+    pub fn is_reconnecting(&self) -> bool {
+        *self.link.borrow() == LinkState::Reconnecting
+    }
+
+    /// Wait until the current agent channel closes.
+    pub async fn closed(&self) {
+        self.transport().closed().await;
     }
 
     /// Get the agent status.
     pub fn status(&self) -> AgentStatus {
         if self.is_alive() {
+            let info = self.info.read();
             AgentStatus::Ready {
-                version: self.info.version.clone(),
-                arch: self.info.arch.clone(),
-                pid: self.info.pid,
+                version: info.version.clone(),
+                arch: info.arch.clone(),
+                pid: info.pid,
             }
+        } else if self.is_reconnecting() {
+            AgentStatus::Reconnecting
         } else {
             AgentStatus::Failed {
                 reason: "Agent channel closed".to_string(),
@@ -55,6 +183,107 @@ impl AgentSession {
         }
     }
 
+    // ═══════════════════════════════════════════════════════════════════
+    // Reconnection
+    // ═══════════════════════════════════════════════════════════════════
+
+    fn can_restart(&self) -> bool {
+        self.restart.is_some() && !self.stopped.load(Ordering::Acquire)
+    }
+
+    fn set_link(&self, state: LinkState) {
+        self.link.send_replace(state);
+    }
+
+    /// Start the agent again and swap in the new transport.
+    async fn restart_agent(&self) -> Result<(), DeployError> {
+        let Some(target) = &self.restart else {
+            return Err(DeployError::StartFailed(
+                "Session cannot be restarted".to_string(),
+            ));
+        };
+        let (transport, info) =
+            AgentDeployer::restart(&target.controller, &target.remote_path).await?;
+        *self.info.write() = info;
+        *self.transport.write() = Arc::new(transport);
+        Ok(())
+    }
+
+    /// Re-issue the watches that were active on the previous agent.
+    async fn restore_watches(&self) {
+        let watches: Vec<(String, WatchSpec)> = self
+            .watches
+            .lock()
+            .iter()
+            .map(|(path, spec)| (path.clone(), spec.clone()))
+            .collect();
+        for (path, spec) in watches {
+            if let Err(e) = self.send_watch_start(&path, &spec).await {
+                warn!("[agent-session] Failed to restore watch on {}: {}", path, e);
+            }
+        }
+    }
+
+    /// Wait until the registry is done with a dead agent channel. True once
+    /// a restarted agent is up; false if the session can't be restarted or
+    /// the registry gave up.
+    async fn wait_for_relink(&self) -> bool {
+        if self.restart.is_none() {
+            return false;
+        }
+        let mut rx = self.link.subscribe();
+        let settled = rx
+            .wait_for(|state| match state {
+                // `Up` with a dead transport: the registry hasn't noticed yet
+                LinkState::Up => self.transport().is_alive(),
+                LinkState::Reconnecting => false,
+                LinkState::Down => true,
+            })
+            .await;
+        matches!(settled.as_deref(), Ok(LinkState::Up))
+    }
+
+    /// Wait for a restarted agent. False if the session can't be restarted,
+    /// the registry gave up, or it took longer than [`REPLAY_WAIT`].
+    async fn wait_for_restart(&self) -> bool {
+        tokio::time::timeout(REPLAY_WAIT, self.wait_for_relink())
+            .await
+            .unwrap_or(false)
+    }
+
+    /// Run `send` on the current transport. If the agent dies before an
+    /// idempotent `method` is answered, wait for the registry to restart it
+    /// and send once more; anything else fails fast.
+    async fn replayable<T, F, Fut>(&self, method: &str, send: F) -> Result<T, TransportError>
+    where
+        F: Fn(Arc<AgentTransport>) -> Fut,
+        Fut: Future<Output = Result<T, TransportError>>,
+    {
+        let result = send(self.transport()).await;
+        let interrupted = matches!(
+            result,
+            Err(TransportError::Interrupted) | Err(TransportError::NotConnected)
+        );
+        if !interrupted || !is_idempotent(method) || !self.wait_for_restart().await {
+            return result;
+        }
+        info!("[agent-session] Replaying {} on the restarted agent", method);
+        send(self.transport()).await
+    }
+
+    /// Send an RPC, replaying it after a reconnect if `method` is idempotent.
+    async fn call(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value, TransportError> {
+        self.replayable(method, |transport| {
+            let params = params.clone();
+            async move { transport.call(method, params).await }
+        })
+        .await
+    }
+
     // ═══════════════════════════════════════════════════════════════════
     // fs/* operations
     // ═══════════════════════════════════════════════════════════════════
@@ -62,7 +291,6 @@ impl AgentSession {
     /// Read a file with content hash (auto-decompresses zstd+base64 responses).
     pub async fn read_file(&self, path: &str) -> Result<ReadFileResult, TransportError> {
         let result = self
-            .transport
             .call(
                 "fs/readFile",
                 serde_json::json!({ "path": path }),
@@ -87,6 +315,93 @@ impl AgentSession {
         Ok(file_result)
     }
 
+    /// Download a file of any size via fs/readFileStream into `dest`.
+    ///
+    /// Chunks are written to `<dest>.part` as they arrive and hashed on the
+    /// way; the file is only renamed into place once the final response
+    /// confirms both its size and SHA-256. On cancel, error or a mismatch
+    /// the partial file is removed and `dest` is left untouched.
+    pub async fn read_file_stream(
+        &self,
+        path: &str,
+        dest: &Path,
+        cancel: &CancellationToken,
+    ) -> Result<ReadFileStreamResult, TransportError> {
+        let mut part = dest.as_os_str().to_owned();
+        part.push(".part");
+        let part = PathBuf::from(part);
+
+        let result = self.stream_to_file(path, &part, cancel).await;
+        let result = match result {
+            Ok(result) => tokio::fs::rename(&part, dest)
+                .await
+                .map(|()| result)
+                .map_err(|e| TransportError::LocalIo(e.to_string())),
+            Err(e) => Err(e),
+        };
+        if result.is_err() {
+            let _ = tokio::fs::remove_file(&part).await;
+        }
+        result
+    }
+
+    async fn stream_to_file(
+        &self,
+        path: &str,
+        part: &Path,
+        cancel: &CancellationToken,
+    ) -> Result<ReadFileStreamResult, TransportError> {
+        use sha2::{Digest, Sha256};
+        use tokio::io::AsyncWriteExt;
+
+        let local_io = |e: std::io::Error| TransportError::LocalIo(e.to_string());
+        let mut file = tokio::fs::File::create(part).await.map_err(local_io)?;
+
+        let (mut chunks, response) = self
+            .transport()
+            .call_streaming("fs/readFileStream", serde_json::json!({ "path": path }))
+            .await?;
+
+        let mut hasher = Sha256::new();
+        let mut written = 0u64;
+        loop {
+            tokio::select! {
+                chunk = chunks.recv() => match chunk {
+                    Some(chunk) => {
+                        hasher.update(&chunk);
+                        file.write_all(&chunk).await.map_err(local_io)?;
+                        written += chunk.len() as u64;
+                    }
+                    None => break,
+                },
+                _ = cancel.cancelled() => return Err(TransportError::Cancelled),
+            }
+        }
+        file.flush().await.map_err(local_io)?;
+        file.sync_all().await.map_err(local_io)?;
+
+        let result: ReadFileStreamResult = serde_json::from_value(response.response(cancel).await?)
+            .map_err(|e| TransportError::DeserializeError(e.to_string()))?;
+        if written != result.size {
+            return Err(TransportError::DeserializeError(format!(
+                "Stream ended after {} of {} bytes",
+                written, result.size
+            )));
+        }
+        let hash: String = hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        if !hash.eq_ignore_ascii_case(&result.hash) {
+            return Err(TransportError::DeserializeError(format!(
+                "Streamed content hash {} does not match {}",
+                hash, result.hash
+            )));
+        }
+        Ok(result)
+    }
+
     /// Atomic write with optional optimistic locking (auto-compresses large content).
     pub async fn write_file(
         &self,
@@ -120,17 +435,24 @@ impl AgentSession {
             params["expect_hash"] = serde_json::Value::String(hash.to_string());
         }
 
-        let result = self.transport.call("fs/writeFile", params).await?;
+        let result = self.call("fs/writeFile", params).await?;
 
         serde_json::from_value(result)
             .map_err(|e| TransportError::DeserializeError(e.to_string()))
     }
 
-    /// Get file/directory metadata.
-    pub async fn stat(&self, path: &str) -> Result<StatResult, TransportError> {
+    /// Get file/directory metadata. With `detect_binary` the agent also reads
+    /// the start of a regular file to tell whether it looks binary.
+    pub async fn stat(
+        &self,
+        path: &str,
+        detect_binary: bool,
+    ) -> Result<StatResult, TransportError> {
         let result = self
-            .transport
-            .call("fs/stat", serde_json::json!({ "path": path }))
+            .call(
+                "fs/stat",
+                serde_json::json!({ "path": path, "detect_binary": detect_binary }),
+            )
             .await?;
 
         serde_json::from_value(result)
@@ -140,7 +462,6 @@ impl AgentSession {
     /// List directory contents (single level).
     pub async fn list_dir(&self, path: &str) -> Result<Vec<FileEntry>, TransportError> {
         let result = self
-            .transport
             .call("fs/listDir", serde_json::json!({ "path": path }))
             .await?;
 
@@ -149,67 +470,96 @@ impl AgentSession {
     }
 
     /// List directory tree (recursive) — returns entries + truncation metadata.
+    ///
+    /// Pass the previous page's `next_cursor` as `cursor` to continue a
+    /// truncated listing.
     pub async fn list_tree(
         &self,
         path: &str,
         max_depth: Option<u32>,
         max_entries: Option<u32>,
+        cursor: Option<&str>,
+        respect_gitignore: bool,
     ) -> Result<ListTreeResult, TransportError> {
-        let mut params = serde_json::json!({ "path": path });
-        if let Some(d) = max_depth {
-            params["max_depth"] = serde_json::json!(d);
-        }
-        if let Some(e) = max_entries {
-            params["max_entries"] = serde_json::json!(e);
-        }
-
-        let result = self.transport.call("fs/listTree", params).await?;
+        self.replayable("fs/listTree", |transport| async move {
+            transport
+                .list_tree_page(path, max_depth, max_entries, cursor, respect_gitignore)
+                .await
+        })
+        .await
+    }
 
-        serde_json::from_value(result)
-            .map_err(|e| TransportError::DeserializeError(e.to_string()))
+    /// Stream a whole `fs/listTree` listing page by page.
+    ///
+    /// Each item is one page of at most `page_size` new entries; the stream
+    /// follows `next_cursor` until the agent reports no more entries, and
+    /// ends after the first error.
+    pub fn list_tree_paged<'a>(
+        &'a self,
+        path: &'a str,
+        max_depth: Option<u32>,
+        page_size: Option<u32>,
+        respect_gitignore: bool,
+    ) -> impl Stream<Item = Result<ListTreeResult, TransportError>> + 'a {
+        // State: Some(cursor) while pages remain, None once finished
+        stream::unfold(Some(None::<String>), move |state| async move {
+            let cursor = state?;
+            match self
+                .list_tree(
+                    path,
+                    max_depth,
+                    page_size,
+                    cursor.as_deref(),
+                    respect_gitignore,
+                )
+                .await
+            {
+                Ok(page) => {
+                    let next = page.next_cursor.clone().map(Some);
+                    Some((Ok(page), next))
+                }
+                Err(e) => Some((Err(e), None)),
+            }
+        })
     }
 
     /// Create a directory.
     pub async fn mkdir(&self, path: &str, recursive: bool) -> Result<(), TransportError> {
-        self.transport
-            .call(
-                "fs/mkdir",
-                serde_json::json!({ "path": path, "recursive": recursive }),
-            )
-            .await?;
+        self.call(
+            "fs/mkdir",
+            serde_json::json!({ "path": path, "recursive": recursive }),
+        )
+        .await?;
         Ok(())
     }
 
     /// Remove a file or directory.
     pub async fn remove(&self, path: &str, recursive: bool) -> Result<(), TransportError> {
-        self.transport
-            .call(
-                "fs/remove",
-                serde_json::json!({ "path": path, "recursive": recursive }),
-            )
-            .await?;
+        self.call(
+            "fs/remove",
+            serde_json::json!({ "path": path, "recursive": recursive }),
+        )
+        .await?;
         Ok(())
     }
 
     /// Rename/move a file or directory.
     pub async fn rename(&self, old_path: &str, new_path: &str) -> Result<(), TransportError> {
-        self.transport
-            .call(
-                "fs/rename",
-                serde_json::json!({ "old_path": old_path, "new_path": new_path }),
-            )
-            .await?;
+        self.call(
+            "fs/rename",
+            serde_json::json!({ "old_path": old_path, "new_path": new_path }),
+        )
+        .await?;
         Ok(())
     }
 
     /// Change file permissions.
     pub async fn chmod(&self, path: &str, mode: &str) -> Result<(), TransportError> {
-        self.transport
-            .call(
-                "fs/chmod",
-                serde_json::json!({ "path": path, "mode": mode }),
-            )
-            .await?;
+        self.call(
+            "fs/chmod",
+            serde_json::json!({ "path": path, "mode": mode }),
+        )
+        .await?;
         Ok(())
     }
 
@@ -224,6 +574,7 @@ impl AgentSession {
         path: &str,
         case_sensitive: bool,
         max_results: Option<u32>,
+        respect_gitignore: bool,
     ) -> Result<Vec<GrepMatch>, TransportError> {
         let mut params = serde_json::json!({
             "pattern": pattern,
@@ -233,8 +584,11 @@ impl AgentSession {
         if let Some(max) = max_results {
             params["max_results"] = serde_json::json!(max);
         }
+        if respect_gitignore {
+            params["respect_gitignore"] = serde_json::json!(true);
+        }
 
-        let result = self.transport.call("search/grep", params).await?;
+        let result = self.call("search/grep", params).await?;
 
         serde_json::from_value(result)
             .map_err(|e| TransportError::DeserializeError(e.to_string()))
@@ -247,7 +601,6 @@ impl AgentSession {
     /// Get git status for a project directory.
     pub async fn git_status(&self, path: &str) -> Result<GitStatusResult, TransportError> {
         let result = self
-            .transport
             .call("git/status", serde_json::json!({ "path": path }))
             .await?;
 
@@ -260,33 +613,54 @@ impl AgentSession {
     // ═══════════════════════════════════════════════════════════════════
 
     /// Start watching a directory for changes.
+    ///
+    /// Changes to a path are reported once it has been quiet for
+    /// `debounce_ms` (agent default: 100ms). The agent is asked to send them
+    /// in batches; agents that predate batching send single events instead.
+    /// The watch is started again if the registry restarts the agent.
     pub async fn watch_start(
         &self,
         path: &str,
         ignore: Vec<String>,
+        debounce_ms: Option<u64>,
     ) -> Result<(), TransportError> {
-        self.transport
-            .call(
-                "watch/start",
-                serde_json::json!({ "path": path, "ignore": ignore }),
-            )
-            .await?;
+        let spec = WatchSpec {
+            ignore,
+            debounce_ms,
+        };
+        self.send_watch_start(path, &spec).await?;
+        self.watches.lock().insert(path.to_string(), spec);
+        Ok(())
+    }
+
+    async fn send_watch_start(&self, path: &str, spec: &WatchSpec) -> Result<(), TransportError> {
+        self.call(
+            "watch/start",
+            serde_json::json!({
+                "path": path,
+                "ignore": spec.ignore,
+                "debounce_ms": spec.debounce_ms,
+                "batch": true,
+            }),
+        )
+        .await?;
         Ok(())
     }
 
     /// Stop watching a directory.
     pub async fn watch_stop(&self, path: &str) -> Result<(), TransportError> {
-        self.transport
-            .call("watch/stop", serde_json::json!({ "path": path }))
+        self.watches.lock().remove(path);
+        self.call("watch/stop", serde_json::json!({ "path": path }))
             .await?;
         Ok(())
     }
 
     /// Take the watch event receiver (can only be called once).
-    pub async fn take_watch_rx(
-        &self,
-    ) -> Option<tokio::sync::mpsc::Receiver<WatchEvent>> {
-        self.transport.take_watch_rx().await
+    ///
+    /// The registry's watch pump is that consumer; see
+    /// `AgentRegistry::start_watch_pump`.
+    pub async fn take_watch_rx(&self) -> Option<mpsc::Receiver<WatchEvent>> {
+        self.transport().take_watch_rx().await
     }
 
     // ═══════════════════════════════════════════════════════════════════
@@ -303,7 +677,7 @@ impl AgentSession {
         if let Some(mf) = max_files {
             params["max_files"] = serde_json::json!(mf);
         }
-        let result = self.transport.call("symbols/index", params).await?;
+        let result = self.call("symbols/index", params).await?;
         serde_json::from_value(result)
             .map_err(|e| TransportError::DeserializeError(e.to_string()))
     }
@@ -319,7 +693,7 @@ impl AgentSession {
         if let Some(l) = limit {
             params["limit"] = serde_json::json!(l);
         }
-        let result = self.transport.call("symbols/complete", params).await?;
+        let result = self.call("symbols/complete", params).await?;
         serde_json::from_value(result)
             .map_err(|e| TransportError::DeserializeError(e.to_string()))
     }
@@ -331,7 +705,6 @@ impl AgentSession {
         name: &str,
     ) -> Result<Vec<SymbolInfo>, TransportError> {
         let result = self
-            .transport
             .call(
                 "symbols/definitions",
                 serde_json::json!({ "path": path, "name": name }),
@@ -347,15 +720,26 @@ impl AgentSession {
 
     /// Ping the agent (health check).
     pub async fn ping(&self) -> Result<(), TransportError> {
-        self.transport
-            .call("sys/ping", serde_json::json!({}))
-            .await?;
+        self.call("sys/ping", serde_json::json!({})).await?;
         Ok(())
     }
 
-    /// Gracefully shut down the agent.
+    /// Ask the agent for its current memory use and uptime. Also refreshes
+    /// the cached [`info`](Self::info).
+    pub async fn usage(&self) -> Result<AgentUsage, TransportError> {
+        let result = self.call("sys/info", serde_json::json!({})).await?;
+        let info: SysInfoResult = serde_json::from_value(result)
+            .map_err(|e| TransportError::DeserializeError(e.to_string()))?;
+        let usage = AgentUsage::from(&info);
+        *self.info.write() = info;
+        Ok(usage)
+    }
+
+    /// Gracefully shut down the agent; it is not restarted afterwards.
     pub async fn shutdown(&self) {
-        self.transport.shutdown().await;
+        self.stopped.store(true, Ordering::Release);
+        self.set_link(LinkState::Down);
+        self.transport().shutdown().await;
     }
 }
 
@@ -363,27 +747,327 @@ impl AgentSession {
 // Registry
 // ═══════════════════════════════════════════════════════════════════════════
 
+/// Event emitted when a connection's file operation backend changes.
+pub const FS_BACKEND_CHANGED_EVENT: &str = "fs:backend_changed";
+
+/// Event emitted when a connection's agent starts or finishes reconnecting.
+pub const AGENT_RECONNECT_EVENT: &str = "agent:reconnect";
+
+/// Prefix of the per-connection watch event (`agent:watch:{connectionId}`).
+pub const WATCH_EVENT_PREFIX: &str = "agent:watch:";
+
+/// Name of the Tauri event carrying `WatchEvent`s for a connection.
+pub fn watch_event_name(connection_id: &str) -> String {
+    format!("{}{}", WATCH_EVENT_PREFIX, connection_id)
+}
+
+/// Forward watch events to `emit` until the channel closes or `closed`
+/// resolves. Events already queued are delivered before stopping.
+///
+/// Returns the number of events forwarded.
+async fn pump_watch_events<F>(
+    mut rx: mpsc::Receiver<WatchEvent>,
+    closed: impl Future<Output = ()>,
+    mut emit: F,
+) -> usize
+where
+    F: FnMut(&WatchEvent),
+{
+    tokio::pin!(closed);
+    let mut forwarded = 0;
+    loop {
+        tokio::select! {
+            biased;
+            event = rx.recv() => match event {
+                Some(event) => {
+                    emit(&event);
+                    forwarded += 1;
+                }
+                None => break,
+            },
+            _ = &mut closed => break,
+        }
+    }
+    forwarded
+}
+
+/// Which backend serves file operations for a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OperationMode {
+    /// Remote agent is running and handles fs/search/git requests
+    Agent,
+    /// No live agent — operations go through SFTP (slower, no search/git)
+    SftpFallback,
+}
+
+/// Payload of `fs:backend_changed`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackendChangedPayload {
+    pub connection_id: String,
+    pub mode: OperationMode,
+    pub previous: OperationMode,
+    /// Why the backend changed (e.g. "agent exited")
+    pub reason: String,
+}
+
+/// Payload of `agent:reconnect`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentReconnectPayload {
+    pub connection_id: String,
+    /// True while the agent is being restarted.
+    pub reconnecting: bool,
+    /// Why the last attempt failed, once the registry gives up.
+    pub error: Option<String>,
+}
+
+/// Tracks the last mode reported to the frontend per connection and emits
+/// `fs:backend_changed` on transitions.
+struct BackendModeTracker {
+    app_handle: parking_lot::RwLock<Option<AppHandle>>,
+    /// Connections last reported as `Agent`; everything else is SFTP.
+    on_agent: DashMap<String, ()>,
+}
+
+impl BackendModeTracker {
+    fn report(&self, connection_id: &str, mode: OperationMode, reason: &str) {
+        let changed = match mode {
+            OperationMode::Agent => self
+                .on_agent
+                .insert(connection_id.to_string(), ())
+                .is_none(),
+            OperationMode::SftpFallback => self.on_agent.remove(connection_id).is_some(),
+        };
+        if !changed {
+            return;
+        }
+
+        let previous = match mode {
+            OperationMode::Agent => OperationMode::SftpFallback,
+            OperationMode::SftpFallback => OperationMode::Agent,
+        };
+        info!(
+            "[agent-registry] Connection {} fs backend: {:?} -> {:?} ({})",
+            connection_id, previous, mode, reason
+        );
+
+        if let Some(handle) = self.app_handle.read().as_ref() {
+            let payload = BackendChangedPayload {
+                connection_id: connection_id.to_string(),
+                mode,
+                previous,
+                reason: reason.to_string(),
+            };
+            if let Err(e) = handle.emit(FS_BACKEND_CHANGED_EVENT, &payload) {
+                warn!("[agent-registry] Failed to emit backend change: {}", e);
+            }
+        }
+    }
+
+    fn report_reconnect(&self, connection_id: &str, reconnecting: bool, error: Option<String>) {
+        if let Some(handle) = self.app_handle.read().as_ref() {
+            let payload = AgentReconnectPayload {
+                connection_id: connection_id.to_string(),
+                reconnecting,
+                error,
+            };
+            if let Err(e) = handle.emit(AGENT_RECONNECT_EVENT, &payload) {
+                warn!("[agent-registry] Failed to emit reconnect: {}", e);
+            }
+        }
+    }
+}
+
+/// Restart a session's agent after its channel died, backing off between
+/// attempts. Interrupted idempotent calls replay once this succeeds.
+async fn reconnect_session(
+    connection_id: &str,
+    session: &AgentSession,
+    modes: &BackendModeTracker,
+) -> bool {
+    session.set_link(LinkState::Reconnecting);
+    modes.report_reconnect(connection_id, true, None);
+
+    let mut last_error = String::new();
+    for attempt in 0..RECONNECT_ATTEMPTS {
+        if attempt > 0 {
+            tokio::time::sleep(RECONNECT_BACKOFF * 2u32.pow(attempt - 1)).await;
+        }
+        if !session.can_restart() {
+            last_error = "Agent was shut down".to_string();
+            break;
+        }
+        match session.restart_agent().await {
+            Ok(()) => {
+                info!(
+                    "[agent-registry] Agent for connection {} reconnected",
+                    connection_id
+                );
+                session.restore_watches().await;
+                session.set_link(LinkState::Up);
+                modes.report_reconnect(connection_id, false, None);
+                return true;
+            }
+            Err(e) => {
+                warn!(
+                    "[agent-registry] Reconnect attempt {} for connection {} failed: {}",
+                    attempt + 1,
+                    connection_id,
+                    e
+                );
+                last_error = e.to_string();
+            }
+        }
+    }
+
+    modes.report_reconnect(connection_id, false, Some(last_error));
+    false
+}
+
 /// Registry of active agent sessions, keyed by connection ID.
 pub struct AgentRegistry {
-    agents: DashMap<String, Arc<AgentSession>>,
+    agents: Arc<DashMap<String, Arc<AgentSession>>>,
+    modes: Arc<BackendModeTracker>,
+    /// Install directory of the last successful deploy per connection;
+    /// outlives the session so redeploys and removal find the binary.
+    install_dirs: DashMap<String, String>,
 }
 
 impl AgentRegistry {
     /// Create a new agent registry.
     pub fn new() -> Self {
         Self {
-            agents: DashMap::new(),
+            agents: Arc::new(DashMap::new()),
+            modes: Arc::new(BackendModeTracker {
+                app_handle: parking_lot::RwLock::new(None),
+                on_agent: DashMap::new(),
+            }),
+            install_dirs: DashMap::new(),
         }
     }
 
+    /// Set the AppHandle used to emit `fs:backend_changed` and watch events.
+    pub fn set_app_handle(&self, handle: AppHandle) {
+        *self.modes.app_handle.write() = Some(handle);
+    }
+
     /// Register an agent session for a connection.
+    ///
+    /// Spawns a watcher that restarts the agent if it dies while it is still
+    /// the registered session (see [`AgentSession::with_restart`]), and
+    /// reports the fallback to SFTP once that isn't possible.
     pub fn register(&self, connection_id: String, session: AgentSession) {
         info!(
             "[agent-registry] Registered agent for connection {}: {}",
             connection_id,
             session.status()
         );
-        self.agents.insert(connection_id, Arc::new(session));
+        let session = Arc::new(session);
+        self.agents.insert(connection_id.clone(), session.clone());
+        self.modes
+            .report(&connection_id, OperationMode::Agent, "agent started");
+
+        let agents = self.agents.clone();
+        let modes = self.modes.clone();
+        tokio::spawn(async move {
+            loop {
+                session.closed().await;
+                // A re-deploy may have replaced this session already
+                let current = agents
+                    .get(&connection_id)
+                    .map(|r| Arc::ptr_eq(r.value(), &session));
+                if current == Some(true)
+                    && session.can_restart()
+                    && reconnect_session(&connection_id, &session, &modes).await
+                {
+                    continue;
+                }
+                session.set_link(LinkState::Down);
+                if current != Some(false) {
+                    modes.report(&connection_id, OperationMode::SftpFallback, "agent exited");
+                }
+                return;
+            }
+        });
+    }
+
+    /// Start forwarding a connection's watch events as `agent:watch:{connectionId}`.
+    ///
+    /// The pump is the single consumer of the transport's watch receiver;
+    /// the frontend subscribes to the Tauri event instead. Returns `false`
+    /// when no agent is registered or the pump is already running. When the
+    /// registry restarts the agent, the pump moves over to the new transport;
+    /// it stops once the session is down for good.
+    pub async fn start_watch_pump(&self, connection_id: &str) -> bool {
+        let Some(session) = self.get(connection_id) else {
+            return false;
+        };
+        let Some(mut rx) = session.take_watch_rx().await else {
+            return false;
+        };
+
+        let modes = self.modes.clone();
+        let event_name = watch_event_name(connection_id);
+        let connection_id = connection_id.to_string();
+        tokio::spawn(async move {
+            info!(
+                "[agent-watch] Pump started for connection {}",
+                connection_id
+            );
+            let mut emit = |event: &WatchEvent| {
+                if let Some(handle) = modes.app_handle.read().as_ref() {
+                    if let Err(e) = handle.emit(&event_name, event) {
+                        warn!("[agent-watch] Failed to emit {}: {}", event_name, e);
+                    }
+                }
+            };
+            let mut forwarded = 0;
+            loop {
+                forwarded += pump_watch_events(rx, session.closed(), &mut emit).await;
+                if !session.wait_for_relink().await {
+                    break;
+                }
+                // A restart swaps in a transport with a fresh watch channel
+                match session.take_watch_rx().await {
+                    Some(next) => rx = next,
+                    None => break,
+                }
+            }
+            info!(
+                "[agent-watch] Pump for connection {} stopped after {} event(s)",
+                connection_id, forwarded
+            );
+        });
+        true
+    }
+
+    /// Backend currently serving file operations for a connection.
+    pub fn mode_for(&self, connection_id: &str) -> OperationMode {
+        if self.has_agent(connection_id) {
+            OperationMode::Agent
+        } else {
+            OperationMode::SftpFallback
+        }
+    }
+
+    /// Remember where the agent for a connection was installed.
+    pub fn set_install_dir(&self, connection_id: &str, remote_dir: String) {
+        self.install_dirs
+            .insert(connection_id.to_string(), remote_dir);
+    }
+
+    /// Directory the connection's agent was last installed in, if known.
+    pub fn install_dir(&self, connection_id: &str) -> Option<String> {
+        self.install_dirs
+            .get(connection_id)
+            .map(|r| r.value().clone())
+    }
+
+    /// Forget a connection's install directory (after removing the binary).
+    pub fn forget_install_dir(&self, connection_id: &str) {
+        self.install_dirs.remove(connection_id);
     }
 
     /// Get an agent session by connection ID.
@@ -391,6 +1075,12 @@ impl AgentRegistry {
         self.agents.get(connection_id).map(|r| r.value().clone())
     }
 
+    /// Whether the connection's agent handles `method`; false without an agent.
+    pub fn supports(&self, connection_id: &str, method: &str) -> bool {
+        self.get(connection_id)
+            .is_some_and(|session| session.supports(method))
+    }
+
     /// Check if a connection has an active agent.
     pub fn has_agent(&self, connection_id: &str) -> bool {
         self.agents
@@ -408,6 +1098,8 @@ impl AgentRegistry {
             );
             session.shutdown().await;
         }
+        self.modes
+            .report(connection_id, OperationMode::SftpFallback, "agent removed");
     }
 
     /// Remove all agent sessions (for app shutdown).
@@ -419,6 +1111,15 @@ impl AgentRegistry {
         info!("[agent-registry] All agents shut down");
     }
 
+    /// Live footprint of a connection's agent; `None` without an agent.
+    pub async fn usage(
+        &self,
+        connection_id: &str,
+    ) -> Option<Result<AgentUsage, TransportError>> {
+        let session = self.get(connection_id)?;
+        Some(session.usage().await)
+    }
+
     /// Get status for all connections.
     pub fn all_statuses(&self) -> Vec<(String, AgentStatus)> {
         self.agents
@@ -433,3 +1134,69 @@ impl Default for AgentRegistry {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(path: &str) -> WatchEvent {
+        WatchEvent {
+            path: path.to_string(),
+            kind: "modify".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_method_supported_uses_advertised_list() {
+        let methods = vec!["fs/readFile".to_string(), "fs/tail".to_string()];
+        assert!(method_supported(&methods, "fs/tail"));
+        assert!(!method_supported(&methods, "git/status"));
+    }
+
+    #[test]
+    fn test_method_supported_falls_back_for_legacy_agents() {
+        assert!(method_supported(&[], "git/status"));
+        assert!(!method_supported(&[], "fs/tail"));
+    }
+
+    #[test]
+    fn test_only_reads_replay_after_reconnect() {
+        for method in ["fs/readFile", "fs/stat", "fs/listTree", "search/grep", "git/status"] {
+            assert!(is_idempotent(method), "{} should replay", method);
+        }
+        // The agent that died may already have applied these
+        for method in ["fs/writeFile", "fs/remove", "fs/rename", "fs/mkdir", "watch/start"] {
+            assert!(!is_idempotent(method), "{} must not replay", method);
+        }
+        // Chunks were already handed to the consumer
+        assert!(!is_idempotent("fs/readFileStream"));
+    }
+
+    #[tokio::test]
+    async fn test_pump_drains_queued_events_then_stops_on_close() {
+        let (tx, rx) = mpsc::channel(8);
+        tx.send(event("/srv/a.rs")).await.unwrap();
+        tx.send(event("/srv/b.rs")).await.unwrap();
+
+        // Transport already closed, but `tx` is still held (as the transport
+        // does): queued events go out, then the pump ends instead of hanging
+        let mut seen = Vec::new();
+        let forwarded =
+            pump_watch_events(rx, std::future::ready(()), |e| seen.push(e.path.clone())).await;
+
+        assert_eq!(forwarded, 2);
+        assert_eq!(seen, vec!["/srv/a.rs", "/srv/b.rs"]);
+        drop(tx);
+    }
+
+    #[tokio::test]
+    async fn test_pump_stops_when_channel_closes() {
+        let (tx, rx) = mpsc::channel(8);
+        tx.send(event("/srv/a.rs")).await.unwrap();
+        drop(tx);
+
+        let forwarded = pump_watch_events(rx, std::future::pending(), |_| {}).await;
+        assert_eq!(forwarded, 1);
+        assert_eq!(watch_event_name("conn-1"), "agent:watch:conn-1");
+    }
+}
@@ -15,13 +15,21 @@
 //!
 //! - Requests are matched to responses by `id` field
 //! - Notifications (no `id`) are forwarded to a callback
+//! - `fs/chunk` notifications feed the chunk channel of the streaming call
+//!   they reference, until its response closes it
 //! - Timeouts prevent indefinite waits
+//! - When the channel dies, idempotent requests fail as `Interrupted` so the
+//!   session can replay them on a restarted agent; all others fail fast
+//! - A call abandoned by its caller (timeout, explicit cancel, or the future
+//!   being dropped) sends `sys/cancel` so the agent stops working on it
 
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use bytes::Bytes;
 use russh::ChannelMsg;
-use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::sync::{mpsc, oneshot, watch, Mutex};
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, warn};
 
 use super::protocol::*;
@@ -29,6 +37,23 @@ use super::protocol::*;
 /// Default timeout for RPC calls.
 const DEFAULT_RPC_TIMEOUT_SECS: u64 = 30;
 
+/// Capability an agent advertises when it can compress large results.
+pub const RPC_COMPRESSION_CAPABILITY: &str = "rpc-gzip";
+
+/// Chunks buffered per streaming call before the IO task waits on the
+/// consumer.
+const STREAM_CHUNK_QUEUE: usize = 64;
+
+/// How long the IO task waits on a full chunk queue. While it waits no other
+/// response or notification is read, so a consumer that stops draining
+/// would stall every RPC on the agent; past this the stream is dropped
+/// instead and its consumer sees a short read.
+const STREAM_CHUNK_STALL_SECS: u64 = 10;
+
+/// Cap on an inflated gzip+base64 result, so a hostile or broken agent
+/// can't make us allocate without bound.
+const MAX_INFLATED_RESULT_BYTES: u64 = 256 * 1024 * 1024;
+
 /// Agent transport error.
 #[derive(Debug, thiserror::Error)]
 pub enum TransportError {
@@ -41,6 +66,14 @@ pub enum TransportError {
     #[error("RPC timeout after {0}s")]
     Timeout(u64),
 
+    #[error("RPC cancelled")]
+    Cancelled,
+
+    /// The channel died before an idempotent request was answered; it is
+    /// safe to send again once the agent is back.
+    #[error("Agent channel lost before the response; request can be replayed")]
+    Interrupted,
+
     #[error("Failed to serialize request: {0}")]
     SerializeError(String),
 
@@ -50,12 +83,180 @@ pub enum TransportError {
     #[error("Agent RPC error: {0}")]
     RpcError(AgentRpcError),
 
+    /// fs/writeFile `expect_hash` mismatch — the remote file changed since
+    /// it was read. Nothing was written.
+    #[error("CONFLICT: File modified externally (current hash: {current_hash})")]
+    WriteConflict { current_hash: String },
+
     #[error("SSH error: {0}")]
     SshError(String),
+
+    /// Writing streamed content to the local disk failed.
+    #[error("Local I/O error: {0}")]
+    LocalIo(String),
+}
+
+impl From<AgentRpcError> for TransportError {
+    fn from(err: AgentRpcError) -> Self {
+        if err.code == ERR_CONFLICT {
+            let current_hash = err
+                .data
+                .as_ref()
+                .and_then(|d| d.get("current_hash"))
+                .and_then(|h| h.as_str());
+            if let Some(current_hash) = current_hash {
+                return TransportError::WriteConflict {
+                    current_hash: current_hash.to_string(),
+                };
+            }
+        }
+        TransportError::RpcError(err)
+    }
+}
+
+/// Decode a response `result` sent with a non-plain `encoding`, refusing
+/// to inflate past `limit` bytes.
+fn inflate_result(
+    result: serde_json::Value,
+    encoding: &str,
+    limit: u64,
+) -> Result<serde_json::Value, AgentRpcError> {
+    use base64::Engine;
+    use std::io::Read;
+
+    let fail = |message: String| AgentRpcError {
+        code: ERR_INTERNAL,
+        message,
+        data: None,
+    };
+
+    if encoding != "gzip+base64" {
+        return Err(fail(format!("Unsupported result encoding: {}", encoding)));
+    }
+    let packed = result
+        .as_str()
+        .ok_or_else(|| fail("Compressed result is not a string".to_string()))?;
+    let compressed = base64::engine::general_purpose::STANDARD
+        .decode(packed)
+        .map_err(|e| fail(format!("Base64 decode error: {}", e)))?;
+
+    let mut inflated = Vec::new();
+    flate2::read::GzDecoder::new(compressed.as_slice())
+        .take(limit + 1)
+        .read_to_end(&mut inflated)
+        .map_err(|e| fail(format!("Gzip decompress error: {}", e)))?;
+    if inflated.len() as u64 > limit {
+        return Err(fail(format!(
+            "Compressed result inflates past {} bytes",
+            limit
+        )));
+    }
+
+    serde_json::from_slice(&inflated).map_err(|e| fail(format!("Invalid inflated result: {}", e)))
+}
+
+/// A request waiting for its response.
+struct PendingCall {
+    tx: oneshot::Sender<Result<serde_json::Value, TransportError>>,
+    /// Copied from [`AgentRequest::idempotent`].
+    idempotent: bool,
+}
+
+/// Pending RPC handlers (request id → waiting call).
+type PendingMap = Arc<Mutex<HashMap<u64, PendingCall>>>;
+
+/// Chunk channels of streaming calls (request id → chunk sender).
+type StreamMap = Arc<Mutex<HashMap<u64, mpsc::Sender<Bytes>>>>;
+
+/// Decode an `fs/chunk` notification into its request id and raw bytes.
+fn decode_file_chunk(params: serde_json::Value) -> Result<(u64, Bytes), String> {
+    use base64::Engine;
+
+    let chunk: FileChunk = serde_json::from_value(params).map_err(|e| e.to_string())?;
+    let data = base64::engine::general_purpose::STANDARD
+        .decode(&chunk.data)
+        .map_err(|e| format!("Base64 decode error: {}", e))?;
+    Ok((chunk.id, Bytes::from(data)))
+}
+
+/// Fail every pending call after the channel closed. Idempotent ones get
+/// [`TransportError::Interrupted`] so callers know they may replay them.
+fn fail_pending(pending: &mut HashMap<u64, PendingCall>) {
+    for (_, call) in pending.drain() {
+        let err = if call.idempotent {
+            TransportError::Interrupted
+        } else {
+            TransportError::ChannelClosed
+        };
+        let _ = call.tx.send(Err(err));
+    }
+}
+
+/// A request the agent may still be working on.
+///
+/// Dropped before [`InFlightCall::finish`] — on timeout, explicit cancel or
+/// when the call future itself is dropped — it forgets the pending entry and
+/// any chunk channel, so late chunks are discarded, and sends `sys/cancel`
+/// for the request.
+struct InFlightCall {
+    id: u64,
+    pending: PendingMap,
+    streams: StreamMap,
+    write_tx: mpsc::Sender<String>,
+    finished: bool,
 }
 
-/// Pending RPC handlers (request id → oneshot sender).
-type PendingMap = Arc<Mutex<HashMap<u64, oneshot::Sender<Result<serde_json::Value, AgentRpcError>>>>>;
+impl InFlightCall {
+    /// The response arrived (or the channel closed); nothing to cancel.
+    fn finish(mut self) {
+        self.finished = true;
+    }
+}
+
+impl Drop for InFlightCall {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        // Drop can't await the pending lock or the writer; hand both off
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let id = self.id;
+        let pending = self.pending.clone();
+        let streams = self.streams.clone();
+        let write_tx = self.write_tx.clone();
+        runtime.spawn(async move {
+            streams.lock().await.remove(&id);
+            let cancel = AgentRequest {
+                id: next_request_id(),
+                method: "sys/cancel".to_string(),
+                params: serde_json::json!({ "id": id }),
+                compress: false,
+                idempotent: false,
+            };
+            let Ok(json) = serde_json::to_string(&cancel) else {
+                return;
+            };
+            {
+                let mut pending = pending.lock().await;
+                pending.remove(&id);
+                // Nobody waits for the sys/cancel reply; the closed receiver
+                // just keeps it from showing up as an unknown id
+                let (tx, _) = oneshot::channel();
+                pending.insert(
+                    cancel.id,
+                    PendingCall {
+                        tx,
+                        idempotent: false,
+                    },
+                );
+            }
+            debug!("[agent-transport] Cancelling request {}", id);
+            let _ = write_tx.send(json).await;
+        });
+    }
+}
 
 /// Agent transport — manages JSON-RPC communication over an SSH exec channel.
 pub struct AgentTransport {
@@ -65,6 +266,9 @@ pub struct AgentTransport {
     /// Pending request map for response matching.
     pending: PendingMap,
 
+    /// Chunk channels of in-progress streaming calls.
+    streams: StreamMap,
+
     /// Watch event receiver — notifications from the agent.
     watch_rx: Mutex<mpsc::Receiver<WatchEvent>>,
 
@@ -79,6 +283,12 @@ pub struct AgentTransport {
 
     /// Whether the transport is alive.
     alive: Arc<std::sync::atomic::AtomicBool>,
+
+    /// Resolves (sender dropped) when the IO task ends.
+    closed_rx: watch::Receiver<()>,
+
+    /// Ask the agent to compress large results (set after handshake).
+    compress: std::sync::atomic::AtomicBool,
 }
 
 impl AgentTransport {
@@ -99,14 +309,17 @@ impl AgentTransport {
             .map_err(|e| TransportError::SshError(format!("Failed to exec agent: {}", e)))?;
 
         let pending: PendingMap = Arc::new(Mutex::new(HashMap::new()));
+        let streams: StreamMap = Arc::new(Mutex::new(HashMap::new()));
         let alive = Arc::new(std::sync::atomic::AtomicBool::new(true));
 
         let (write_tx, mut write_rx) = mpsc::channel::<String>(256);
         let (watch_tx, watch_rx) = mpsc::channel::<WatchEvent>(1024);
         let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
+        let (closed_tx, closed_rx) = watch::channel(());
 
         // Combined IO task: handles both reading and writing on the single channel
         let pending_r = pending.clone();
+        let streams_r = streams.clone();
         let watch_tx_r = watch_tx.clone();
         let alive_r = alive.clone();
         tokio::spawn(async move {
@@ -140,21 +353,52 @@ impl AgentTransport {
                                     // Try to parse as AgentMessage
                                     match serde_json::from_str::<AgentMessage>(&line) {
                                         Ok(AgentMessage::Response(resp)) => {
+                                            // Every chunk precedes the response; end the stream
+                                            streams_r.lock().await.remove(&resp.id);
                                             let mut pending = pending_r.lock().await;
-                                            if let Some(tx) = pending.remove(&resp.id) {
+                                            if let Some(call) = pending.remove(&resp.id) {
                                                 let result = if let Some(err) = resp.error {
                                                     Err(err)
+                                                } else if let Some(encoding) = resp.encoding {
+                                                    inflate_result(
+                                                        resp.result.unwrap_or_default(),
+                                                        &encoding,
+                                                        MAX_INFLATED_RESULT_BYTES,
+                                                    )
                                                 } else {
                                                     Ok(resp.result.unwrap_or_default())
                                                 };
-                                                let _ = tx.send(result);
+                                                let _ = call.tx.send(result.map_err(TransportError::from));
                                             } else {
                                                 warn!("[agent-transport] Response for unknown id {}", resp.id);
                                             }
                                         }
+                                        Ok(AgentMessage::Notification(notif)) if notif.method == "fs/chunk" => {
+                                            match decode_file_chunk(notif.params) {
+                                                Ok((id, data)) => {
+                                                    let tx = streams_r.lock().await.get(&id).cloned();
+                                                    // No channel: the call was cancelled, drop the chunk
+                                                    if let Some(tx) = tx {
+                                                        let sent = tokio::time::timeout(
+                                                            std::time::Duration::from_secs(STREAM_CHUNK_STALL_SECS),
+                                                            tx.send(data),
+                                                        )
+                                                        .await;
+                                                        if sent.is_err() {
+                                                            warn!("[agent-transport] Stream {} stalled, dropping it", id);
+                                                            streams_r.lock().await.remove(&id);
+                                                        }
+                                                    }
+                                                }
+                                                Err(e) => {
+                                                    warn!("[agent-transport] Malformed fs/chunk notification: {}", e);
+                                                }
+                                            }
+                                        }
                                         Ok(AgentMessage::Notification(notif)) => {
-                                            if notif.method == "watch/event" {
-                                                if let Ok(event) = serde_json::from_value::<WatchEvent>(notif.params) {
+                                            // Batches are fanned out so watch_rx still sees single events
+                                            if let Some(events) = watch_notification_events(&notif.method, notif.params) {
+                                                for event in events {
                                                     let _ = watch_tx_r.send(event).await;
                                                 }
                                             } else {
@@ -197,34 +441,47 @@ impl AgentTransport {
 
             alive_r.store(false, std::sync::atomic::Ordering::Relaxed);
 
-            // Fail all pending requests
-            let mut pending = pending_r.lock().await;
-            for (_, tx) in pending.drain() {
-                let _ = tx.send(Err(AgentRpcError {
-                    code: ERR_INTERNAL,
-                    message: "Agent channel closed".to_string(),
-                }));
-            }
+            // Fail all pending requests; open streams end with them
+            streams_r.lock().await.clear();
+            fail_pending(&mut *pending_r.lock().await);
 
             debug!("[agent-transport] IO task ended");
+            drop(closed_tx);
         });
 
         Ok(Self {
             write_tx,
             pending,
+            streams,
             watch_rx: Mutex::new(watch_rx),
             watch_taken: std::sync::atomic::AtomicBool::new(false),
             _watch_tx: watch_tx,
             shutdown_tx,
             alive,
+            closed_rx,
+            compress: std::sync::atomic::AtomicBool::new(false),
         })
     }
 
+    /// Let the agent compress large results from now on. Only call this for
+    /// agents advertising [`RPC_COMPRESSION_CAPABILITY`].
+    pub fn enable_compression(&self) {
+        self.compress
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
     /// Check if the transport is alive.
     pub fn is_alive(&self) -> bool {
         self.alive.load(std::sync::atomic::Ordering::Relaxed)
     }
 
+    /// Wait until the agent channel has closed (agent exited, SSH dropped or
+    /// shut down). Returns immediately if it already has.
+    pub async fn closed(&self) {
+        let mut rx = self.closed_rx.clone();
+        while rx.changed().await.is_ok() {}
+    }
+
     /// Send an RPC request and wait for the response.
     pub async fn call(
         &self,
@@ -236,11 +493,29 @@ impl AgentTransport {
     }
 
     /// Send an RPC request with a custom timeout.
+    ///
+    /// On timeout the agent is told to drop the request via `sys/cancel`.
     pub async fn call_with_timeout(
         &self,
         method: &str,
         params: serde_json::Value,
         timeout_secs: u64,
+    ) -> Result<serde_json::Value, TransportError> {
+        self.call_cancellable(method, params, timeout_secs, &CancellationToken::new())
+            .await
+    }
+
+    /// Send an RPC request that the caller can abandon through `cancel`.
+    ///
+    /// Cancelling, timing out or dropping the returned future all send
+    /// `sys/cancel` to the agent; only heavy requests (tree walks, grep)
+    /// actually stop early, others simply have their response discarded.
+    pub async fn call_cancellable(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+        timeout_secs: u64,
+        cancel: &CancellationToken,
     ) -> Result<serde_json::Value, TransportError> {
         if !self.is_alive() {
             return Err(TransportError::NotConnected);
@@ -251,6 +526,8 @@ impl AgentTransport {
             id,
             method: method.to_string(),
             params,
+            compress: self.compress.load(std::sync::atomic::Ordering::Relaxed),
+            idempotent: is_idempotent(method),
         };
 
         let json = serde_json::to_string(&request)
@@ -260,7 +537,13 @@ impl AgentTransport {
         let (tx, rx) = oneshot::channel();
         {
             let mut pending = self.pending.lock().await;
-            pending.insert(id, tx);
+            pending.insert(
+                id,
+                PendingCall {
+                    tx,
+                    idempotent: request.idempotent,
+                },
+            );
         }
 
         // Send to writer task
@@ -268,26 +551,95 @@ impl AgentTransport {
             .send(json)
             .await
             .map_err(|_| TransportError::ChannelClosed)?;
+        let call = InFlightCall {
+            id,
+            pending: self.pending.clone(),
+            streams: self.streams.clone(),
+            write_tx: self.write_tx.clone(),
+            finished: false,
+        };
 
-        // Wait for response with timeout
-        match tokio::time::timeout(
-            tokio::time::Duration::from_secs(timeout_secs),
-            rx,
-        )
-        .await
-        {
-            Ok(Ok(Ok(result))) => Ok(result),
-            Ok(Ok(Err(rpc_err))) => Err(TransportError::RpcError(rpc_err)),
-            Ok(Err(_)) => Err(TransportError::ChannelClosed),
-            Err(_) => {
-                // Clean up pending entry on timeout
-                let mut pending = self.pending.lock().await;
-                pending.remove(&id);
-                Err(TransportError::Timeout(timeout_secs))
+        // Wait for response with timeout; `call` cancels on every early exit
+        let response = tokio::select! {
+            response = tokio::time::timeout(
+                tokio::time::Duration::from_secs(timeout_secs),
+                rx,
+            ) => response,
+            _ = cancel.cancelled() => return Err(TransportError::Cancelled),
+        };
+        match response {
+            Ok(Ok(Ok(result))) => {
+                call.finish();
+                Ok(result)
             }
+            Ok(Ok(Err(e))) => {
+                call.finish();
+                Err(e)
+            }
+            Ok(Err(_)) => {
+                call.finish();
+                Err(TransportError::ChannelClosed)
+            }
+            Err(_) => Err(TransportError::Timeout(timeout_secs)),
         }
     }
 
+    /// Send a request whose payload arrives as `fs/chunk` notifications
+    /// ahead of the response.
+    ///
+    /// Chunks are delivered on the returned receiver, which closes once the
+    /// response arrives; then await [`StreamingResponse::response`] for the
+    /// result. Drain the receiver first — the IO task waits while it is full,
+    /// holding up every other call, and abandons the stream (closing the
+    /// receiver early) if it stays full for `STREAM_CHUNK_STALL_SECS`.
+    /// Dropping the [`StreamingResponse`] before it resolves cancels the call:
+    /// the agent gets `sys/cancel` and chunks still in flight are discarded.
+    pub async fn call_streaming(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<(mpsc::Receiver<Bytes>, StreamingResponse), TransportError> {
+        if !self.is_alive() {
+            return Err(TransportError::NotConnected);
+        }
+
+        let id = next_request_id();
+        let request = AgentRequest {
+            id,
+            method: method.to_string(),
+            params,
+            compress: false,
+            idempotent: false,
+        };
+        let json = serde_json::to_string(&request)
+            .map_err(|e| TransportError::SerializeError(e.to_string()))?;
+
+        let (tx, rx) = oneshot::channel();
+        let (chunk_tx, chunk_rx) = mpsc::channel(STREAM_CHUNK_QUEUE);
+        self.pending.lock().await.insert(
+            id,
+            PendingCall {
+                tx,
+                idempotent: false,
+            },
+        );
+        self.streams.lock().await.insert(id, chunk_tx);
+
+        self.write_tx
+            .send(json)
+            .await
+            .map_err(|_| TransportError::ChannelClosed)?;
+        let call = InFlightCall {
+            id,
+            pending: self.pending.clone(),
+            streams: self.streams.clone(),
+            write_tx: self.write_tx.clone(),
+            finished: false,
+        };
+
+        Ok((chunk_rx, StreamingResponse { rx, call }))
+    }
+
     /// Send a fire-and-forget request (no response expected, but still gets one).
     pub async fn notify(&self, method: &str, params: serde_json::Value) -> Result<(), TransportError> {
         // Agent always sends responses since everything has an `id`,
@@ -296,6 +648,34 @@ impl AgentTransport {
         Ok(())
     }
 
+    /// Fetch a single `fs/listTree` page, resuming after `cursor` if given.
+    pub async fn list_tree_page(
+        &self,
+        path: &str,
+        max_depth: Option<u32>,
+        max_entries: Option<u32>,
+        cursor: Option<&str>,
+        respect_gitignore: bool,
+    ) -> Result<ListTreeResult, TransportError> {
+        let mut params = serde_json::json!({ "path": path });
+        if let Some(d) = max_depth {
+            params["max_depth"] = serde_json::json!(d);
+        }
+        if let Some(e) = max_entries {
+            params["max_entries"] = serde_json::json!(e);
+        }
+        if let Some(c) = cursor {
+            params["cursor"] = serde_json::json!(c);
+        }
+        if respect_gitignore {
+            params["respect_gitignore"] = serde_json::json!(true);
+        }
+
+        let result = self.call("fs/listTree", params).await?;
+
+        serde_json::from_value(result).map_err(|e| TransportError::DeserializeError(e.to_string()))
+    }
+
     /// Take the watch event receiver.
     ///
     /// Only one consumer should call this. Subsequent calls return None.
@@ -322,9 +702,283 @@ impl AgentTransport {
     }
 }
 
+/// The response that ends a [`AgentTransport::call_streaming`] call.
+pub struct StreamingResponse {
+    rx: oneshot::Receiver<Result<serde_json::Value, TransportError>>,
+    call: InFlightCall,
+}
+
+impl StreamingResponse {
+    /// Wait for the response, or give up on the call once `cancel` fires.
+    ///
+    /// There is no timeout — a large stream may take arbitrarily long, and
+    /// the call fails anyway if the agent channel closes.
+    pub async fn response(
+        self,
+        cancel: &CancellationToken,
+    ) -> Result<serde_json::Value, TransportError> {
+        let Self { rx, call } = self;
+        let response = tokio::select! {
+            response = rx => response,
+            _ = cancel.cancelled() => return Err(TransportError::Cancelled),
+        };
+        call.finish();
+        response.unwrap_or(Err(TransportError::ChannelClosed))
+    }
+}
+
 impl Drop for AgentTransport {
     fn drop(&mut self) {
         self.alive
             .store(false, std::sync::atomic::Ordering::Relaxed);
     }
 }
+
+/// Watch events carried by a notification: one for `watch/event`, all of
+/// them for `watch/event-batch`; `None` for any other notification.
+fn watch_notification_events(method: &str, params: serde_json::Value) -> Option<Vec<WatchEvent>> {
+    let events = match method {
+        "watch/event" => serde_json::from_value::<WatchEvent>(params).map(|event| vec![event]),
+        "watch/event-batch" => {
+            serde_json::from_value::<WatchEventBatch>(params).map(|batch| batch.events)
+        }
+        _ => return None,
+    };
+    Some(events.unwrap_or_else(|e| {
+        warn!("[agent-transport] Malformed {} notification: {}", method, e);
+        Vec::new()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rpc_error(code: i32, data: Option<serde_json::Value>) -> AgentRpcError {
+        AgentRpcError {
+            code,
+            message: "CONFLICT: File modified externally".to_string(),
+            data,
+        }
+    }
+
+    /// A transport with no agent behind it; the receiver sees every line
+    /// it writes.
+    fn mock_transport() -> (AgentTransport, mpsc::Receiver<String>) {
+        let (write_tx, write_rx) = mpsc::channel(16);
+        let (watch_tx, watch_rx) = mpsc::channel(1);
+        let (shutdown_tx, _) = mpsc::channel(1);
+        let (_, closed_rx) = watch::channel(());
+        let transport = AgentTransport {
+            write_tx,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            streams: Arc::new(Mutex::new(HashMap::new())),
+            watch_rx: Mutex::new(watch_rx),
+            watch_taken: std::sync::atomic::AtomicBool::new(false),
+            _watch_tx: watch_tx,
+            shutdown_tx,
+            alive: Arc::new(std::sync::atomic::AtomicBool::new(true)),
+            closed_rx,
+            compress: std::sync::atomic::AtomicBool::new(false),
+        };
+        (transport, write_rx)
+    }
+
+    async fn next_line(write_rx: &mut mpsc::Receiver<String>) -> serde_json::Value {
+        serde_json::from_str(&write_rx.recv().await.unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_timeout_sends_sys_cancel() {
+        let (transport, mut write_rx) = mock_transport();
+
+        let err = transport
+            .call_with_timeout("fs/listTree", serde_json::json!({ "path": "/" }), 0)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, TransportError::Timeout(0)));
+
+        let request = next_line(&mut write_rx).await;
+        assert_eq!(request["method"], "fs/listTree");
+        let cancel = next_line(&mut write_rx).await;
+        assert_eq!(cancel["method"], "sys/cancel");
+        assert_eq!(cancel["params"]["id"], request["id"]);
+
+        // Only the (unawaited) sys/cancel reply is still expected
+        let pending = transport.pending.lock().await;
+        let request_id = request["id"].as_u64().unwrap();
+        assert!(!pending.contains_key(&request_id));
+        assert!(pending.contains_key(&cancel["id"].as_u64().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_explicit_cancel_sends_sys_cancel() {
+        let (transport, mut write_rx) = mock_transport();
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+
+        let err = transport
+            .call_cancellable("search/grep", serde_json::json!({}), 30, &cancel)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, TransportError::Cancelled));
+
+        let request = next_line(&mut write_rx).await;
+        let sent = next_line(&mut write_rx).await;
+        assert_eq!(sent["method"], "sys/cancel");
+        assert_eq!(sent["params"]["id"], request["id"]);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_stream_cancels_and_forgets_chunks() {
+        let (transport, mut write_rx) = mock_transport();
+
+        let (chunks, response) = transport
+            .call_streaming("fs/readFileStream", serde_json::json!({ "path": "/var/log/big.log" }))
+            .await
+            .unwrap();
+        let request = next_line(&mut write_rx).await;
+        let id = request["id"].as_u64().unwrap();
+        assert!(transport.streams.lock().await.contains_key(&id));
+
+        drop(response);
+        let cancel = next_line(&mut write_rx).await;
+        assert_eq!(cancel["method"], "sys/cancel");
+        assert_eq!(cancel["params"]["id"], id);
+
+        // Chunks arriving after this have nowhere to go
+        assert!(!transport.streams.lock().await.contains_key(&id));
+        drop(chunks);
+    }
+
+    #[tokio::test]
+    async fn test_closed_channel_only_marks_idempotent_calls_replayable() {
+        let mut pending = HashMap::new();
+        let (read_tx, read_rx) = oneshot::channel();
+        let (write_tx, write_rx) = oneshot::channel();
+        pending.insert(
+            1,
+            PendingCall {
+                tx: read_tx,
+                idempotent: is_idempotent("fs/readFile"),
+            },
+        );
+        pending.insert(
+            2,
+            PendingCall {
+                tx: write_tx,
+                idempotent: is_idempotent("fs/writeFile"),
+            },
+        );
+
+        fail_pending(&mut pending);
+
+        assert!(pending.is_empty());
+        assert!(matches!(read_rx.await.unwrap(), Err(TransportError::Interrupted)));
+        // A write may already have landed on the old agent: never replayed
+        assert!(matches!(write_rx.await.unwrap(), Err(TransportError::ChannelClosed)));
+    }
+
+    #[test]
+    fn test_file_chunk_decodes() {
+        let (id, data) = decode_file_chunk(serde_json::json!({ "id": 7, "data": "aGVsbG8=" })).unwrap();
+        assert_eq!(id, 7);
+        assert_eq!(&data[..], b"hello");
+
+        assert!(decode_file_chunk(serde_json::json!({ "id": 7, "data": "%%" })).is_err());
+        assert!(decode_file_chunk(serde_json::json!({ "data": "aGVsbG8=" })).is_err());
+    }
+
+    #[test]
+    fn test_stale_hash_conflict_is_typed() {
+        let err: TransportError = rpc_error(
+            ERR_CONFLICT,
+            Some(serde_json::json!({ "expected_hash": "aaa", "current_hash": "bbb" })),
+        )
+        .into();
+
+        match err {
+            TransportError::WriteConflict { current_hash } => assert_eq!(current_hash, "bbb"),
+            other => panic!("expected WriteConflict, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_large_compressed_result_round_trips() {
+        use base64::Engine;
+        use std::io::Write;
+
+        let entries: Vec<_> = (0..5000)
+            .map(|i| serde_json::json!({ "path": format!("/srv/app/src/file_{}.rs", i) }))
+            .collect();
+        let original = serde_json::json!({ "entries": entries });
+        let raw = serde_json::to_vec(&original).unwrap();
+        let mut encoder =
+            flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&raw).unwrap();
+        let compressed = encoder.finish().unwrap();
+        assert!(compressed.len() < raw.len() / 4);
+        let packed = base64::engine::general_purpose::STANDARD.encode(&compressed);
+
+        let restored = inflate_result(
+            serde_json::Value::String(packed.clone()),
+            "gzip+base64",
+            MAX_INFLATED_RESULT_BYTES,
+        )
+        .unwrap();
+        assert_eq!(restored, original);
+
+        // Same payload against a cap smaller than its inflated size
+        let err = inflate_result(
+            serde_json::Value::String(packed),
+            "gzip+base64",
+            (raw.len() - 1) as u64,
+        )
+        .unwrap_err();
+        assert_eq!(err.code, ERR_INTERNAL);
+    }
+
+    #[test]
+    fn test_watch_batch_notifications_fan_out() {
+        let events = watch_notification_events(
+            "watch/event-batch",
+            serde_json::json!({ "events": [
+                { "path": "/srv/app/src/lib.rs", "kind": "modify" },
+                { "path": "/srv/app/src/new.rs", "kind": "create" },
+            ] }),
+        )
+        .unwrap();
+        let paths: Vec<_> = events.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, ["/srv/app/src/lib.rs", "/srv/app/src/new.rs"]);
+
+        let single = watch_notification_events(
+            "watch/event",
+            serde_json::json!({ "path": "/srv/app/Cargo.toml", "kind": "delete" }),
+        )
+        .unwrap();
+        assert_eq!(single[0].kind, "delete");
+
+        assert!(
+            watch_notification_events("watch/event-batch", serde_json::json!({}))
+                .unwrap()
+                .is_empty()
+        );
+        assert!(watch_notification_events("fs/progress", serde_json::json!({})).is_none());
+    }
+
+    #[test]
+    fn test_unknown_result_encoding_is_rejected() {
+        let err = inflate_result(serde_json::json!("x"), "brotli", 1024).unwrap_err();
+        assert!(err.message.contains("brotli"));
+    }
+
+    #[test]
+    fn test_conflict_without_data_stays_generic() {
+        // Agents predating typed conflicts send no `data`
+        let err: TransportError = rpc_error(ERR_CONFLICT, None).into();
+        assert!(matches!(err, TransportError::RpcError(e) if e.code == ERR_CONFLICT));
+
+        let err: TransportError = rpc_error(ERR_IO, None).into();
+        assert!(matches!(err, TransportError::RpcError(_)));
+    }
+}
@@ -0,0 +1,469 @@
+//! Connection admission for the WebSocket bridge
+//!
+//! The bridge listens on localhost, where any local process or browser page
+//! can reach it. Before a connection is attached to a session it must:
+//! 1. Come from an allowed `Origin` (when the client sends one)
+//! 2. Send the session token as its first message
+//!
+//! Connections are admitted in parallel, up to `max_connections` at a time,
+//! so a stray client holding a socket open cannot lock the frontend out.
+//! Rejected clients get a close frame with a specific code:
+//! - `1008` (policy violation): origin not allowed, bad or missing token
+//! - `1013` (try again later): too many concurrent connections
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinSet;
+use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{accept_hdr_async, WebSocketStream};
+use tracing::{debug, warn};
+
+use super::paste::PasteGuard;
+use super::server::{validate_token, DisconnectReason, HEARTBEAT_TIMEOUT_SECS, REPLAY_LINE_COUNT};
+
+/// Default cap on connections being admitted at once
+pub const DEFAULT_MAX_CONNECTIONS: usize = 4;
+
+/// Default time a detached session's PTY is kept alive for reattach
+pub const DEFAULT_DETACH_GRACE: Duration = Duration::from_secs(300);
+
+/// Lowest heartbeat timeout accepted from settings; anything shorter drops
+/// clients whose webview is merely busy
+pub const MIN_HEARTBEAT_TIMEOUT_SECS: u64 = 10;
+
+/// Time a client has to send its token after the handshake
+const AUTH_TIMEOUT_SECS: u64 = 5;
+
+/// Time allowed for handshaking a client that is being turned away
+const REJECT_TIMEOUT_SECS: u64 = 2;
+
+/// Origins the app's webview loads from (production builds and `tauri dev`)
+const DEFAULT_ALLOWED_ORIGINS: &[&str] = &[
+    "tauri://localhost",
+    "http://tauri.localhost",
+    "https://tauri.localhost",
+    "http://localhost:1420",
+];
+
+pub type WsStream = WebSocketStream<TcpStream>;
+
+/// Admission, liveness and reattach rules for a bridge
+#[derive(Debug, Clone)]
+pub struct BridgePolicy {
+    /// Allowed `Origin` header values. Clients that send no `Origin` (not a
+    /// browser) are let through to the token check.
+    pub allowed_origins: Vec<String>,
+    /// Connections that may be handshaking/authenticating at once
+    pub max_connections: usize,
+    /// Silence from an attached client after which the bridge detaches with
+    /// `DisconnectReason::HeartbeatTimeout`. The SSH session is kept for
+    /// reconnection, as for any recoverable disconnect.
+    pub heartbeat_timeout: Duration,
+    /// How long the SSH PTY survives a recoverable disconnect. A new bridge
+    /// started within this window (`recreate_terminal_pty`) reattaches to
+    /// the running shell instead of opening a new one.
+    pub detach_grace: Duration,
+    /// Scroll buffer lines replayed to a client when it (re)attaches
+    pub replay_lines: usize,
+    /// Holds multi-line pastes for confirmation (v2 bridges only)
+    pub paste_guard: Option<Arc<PasteGuard>>,
+}
+
+impl Default for BridgePolicy {
+    fn default() -> Self {
+        Self {
+            allowed_origins: DEFAULT_ALLOWED_ORIGINS
+                .iter()
+                .map(|origin| origin.to_string())
+                .collect(),
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            heartbeat_timeout: Duration::from_secs(HEARTBEAT_TIMEOUT_SECS),
+            detach_grace: DEFAULT_DETACH_GRACE,
+            replay_lines: REPLAY_LINE_COUNT,
+            paste_guard: None,
+        }
+    }
+}
+
+impl BridgePolicy {
+    /// Policy for new bridges under `config` (no paste guard)
+    pub fn from_config(config: &BridgeConfig) -> Self {
+        Self {
+            allowed_origins: config.allowed_origins.clone(),
+            max_connections: config.max_connections.max(1),
+            heartbeat_timeout: Duration::from_secs(
+                config
+                    .heartbeat_timeout_secs
+                    .max(MIN_HEARTBEAT_TIMEOUT_SECS),
+            ),
+            detach_grace: Duration::from_secs(config.detach_grace_secs),
+            replay_lines: config.replay_lines,
+            paste_guard: None,
+        }
+    }
+
+    fn allows_origin(&self, origin: Option<&str>) -> bool {
+        match origin {
+            None => true,
+            Some(origin) => self
+                .allowed_origins
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(origin)),
+        }
+    }
+}
+
+/// User-adjustable bridge settings, applied to bridges started afterwards
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BridgeConfig {
+    pub allowed_origins: Vec<String>,
+    pub max_connections: usize,
+    pub heartbeat_timeout_secs: u64,
+    pub detach_grace_secs: u64,
+    pub replay_lines: usize,
+}
+
+impl Default for BridgeConfig {
+    fn default() -> Self {
+        let policy = BridgePolicy::default();
+        Self {
+            allowed_origins: policy.allowed_origins,
+            max_connections: policy.max_connections,
+            heartbeat_timeout_secs: policy.heartbeat_timeout.as_secs(),
+            detach_grace_secs: policy.detach_grace.as_secs(),
+            replay_lines: policy.replay_lines,
+        }
+    }
+}
+
+/// Counts connections being admitted and refuses new ones past the cap
+struct ConnectionGate {
+    in_flight: Arc<AtomicUsize>,
+    max: usize,
+}
+
+/// Held while one connection is admitted; releases its slot on drop
+struct ConnectionPermit {
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl ConnectionGate {
+    fn new(max: usize) -> Self {
+        Self {
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            max,
+        }
+    }
+
+    fn try_acquire(&self) -> Option<ConnectionPermit> {
+        let previous = self.in_flight.fetch_add(1, Ordering::SeqCst);
+        if previous >= self.max {
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            return None;
+        }
+        Some(ConnectionPermit {
+            in_flight: Arc::clone(&self.in_flight),
+        })
+    }
+}
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Accept connections until one passes the origin and token checks
+///
+/// Runs until `accept_timeout` elapses. Returns `AuthFailed` rather than
+/// `AcceptTimeout` if at least one client was turned away for its token.
+pub async fn accept_authenticated(
+    listener: &TcpListener,
+    expected_token: &str,
+    policy: &BridgePolicy,
+    accept_timeout: Duration,
+) -> Result<(WsStream, SocketAddr), DisconnectReason> {
+    let deadline = tokio::time::Instant::now() + accept_timeout;
+    let gate = ConnectionGate::new(policy.max_connections);
+    // Dropping the set on return aborts the remaining admissions
+    let mut pending = JoinSet::new();
+    let mut auth_failed = false;
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, addr) = accepted.map_err(|e| {
+                    warn!("Failed to accept WebSocket connection: {}", e);
+                    DisconnectReason::NetworkError(e.to_string())
+                })?;
+                // Disable Nagle's algorithm for low-latency interactive terminal
+                if let Err(e) = stream.set_nodelay(true) {
+                    warn!("Failed to set TCP_NODELAY: {}", e);
+                }
+
+                let Some(permit) = gate.try_acquire() else {
+                    warn!(
+                        "WebSocket connection from {} rejected: {} already pending",
+                        addr, policy.max_connections
+                    );
+                    tokio::spawn(reject(stream, CloseCode::Again, "Too many connections"));
+                    continue;
+                };
+                let token = expected_token.to_string();
+                let policy = policy.clone();
+                pending.spawn(async move {
+                    let _permit = permit;
+                    authenticate(stream, &token, &policy)
+                        .await
+                        .map(|ws_stream| (ws_stream, addr))
+                });
+            }
+            Some(joined) = pending.join_next() => match joined {
+                Ok(Ok(admitted)) => return Ok(admitted),
+                Ok(Err(e)) => {
+                    warn!("WebSocket connection rejected: {}", e);
+                    auth_failed |= e.contains("Authentication");
+                }
+                Err(e) => warn!("WebSocket admission task failed: {}", e),
+            },
+            _ = tokio::time::sleep_until(deadline) => {
+                return Err(if auth_failed {
+                    DisconnectReason::AuthFailed
+                } else {
+                    DisconnectReason::AcceptTimeout
+                });
+            }
+        }
+    }
+}
+
+/// Handshake with one client, then check its origin and token
+// The handshake callback's error type is fixed by tungstenite
+#[allow(clippy::result_large_err)]
+async fn authenticate(
+    stream: TcpStream,
+    expected_token: &str,
+    policy: &BridgePolicy,
+) -> Result<WsStream, String> {
+    let mut origin = None;
+    let mut ws_stream = accept_hdr_async(stream, |request: &Request, response: Response| {
+        origin = request
+            .headers()
+            .get("origin")
+            .map(|value| String::from_utf8_lossy(value.as_bytes()).into_owned());
+        Ok(response)
+    })
+    .await
+    .map_err(|e| format!("WebSocket handshake failed: {}", e))?;
+
+    if !policy.allows_origin(origin.as_deref()) {
+        close(&mut ws_stream, CloseCode::Policy, "Origin not allowed").await;
+        return Err(format!(
+            "Origin not allowed: {}",
+            origin.unwrap_or_default()
+        ));
+    }
+
+    // Authenticate: expect first message to contain token
+    let auth_result =
+        tokio::time::timeout(Duration::from_secs(AUTH_TIMEOUT_SECS), ws_stream.next()).await;
+    let failure = match auth_result {
+        Ok(Some(Ok(Message::Text(token)))) if validate_token(&token, expected_token) => {
+            debug!("WebSocket token authentication successful");
+            return Ok(ws_stream);
+        }
+        Ok(Some(Ok(Message::Binary(data))))
+            if validate_token(&String::from_utf8_lossy(&data), expected_token) =>
+        {
+            debug!("WebSocket token authentication successful (binary)");
+            return Ok(ws_stream);
+        }
+        Ok(Some(Ok(Message::Text(_)))) | Ok(Some(Ok(Message::Binary(_)))) => {
+            "invalid or expired token".to_string()
+        }
+        Ok(Some(Ok(_))) => "unexpected message".to_string(),
+        Ok(Some(Err(e))) => e.to_string(),
+        Ok(None) => return Err("Authentication failed: connection closed".to_string()),
+        Err(_) => "timeout".to_string(),
+    };
+
+    close(&mut ws_stream, CloseCode::Policy, "Authentication failed").await;
+    Err(format!("Authentication failed: {}", failure))
+}
+
+/// Turn a client away with a close code it can show to the user
+async fn reject(stream: TcpStream, code: CloseCode, reason: &'static str) {
+    let _ = tokio::time::timeout(Duration::from_secs(REJECT_TIMEOUT_SECS), async {
+        if let Ok(mut ws_stream) = tokio_tungstenite::accept_async(stream).await {
+            close(&mut ws_stream, code, reason).await;
+        }
+    })
+    .await;
+}
+
+async fn close(ws_stream: &mut WsStream, code: CloseCode, reason: &'static str) {
+    let _ = ws_stream
+        .close(Some(CloseFrame {
+            code,
+            reason: reason.into(),
+        }))
+        .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge::server::generate_token;
+    use futures_util::SinkExt;
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+    use tokio_tungstenite::{connect_async, MaybeTlsStream};
+
+    type Server = tokio::task::JoinHandle<Result<(WsStream, SocketAddr), DisconnectReason>>;
+
+    /// Start admitting connections on a fresh port; returns (url, token, server)
+    async fn serve(policy: BridgePolicy, accept_timeout: Duration) -> (String, String, Server) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let token = generate_token();
+        let expected = token.clone();
+        let server = tokio::spawn(async move {
+            accept_authenticated(&listener, &expected, &policy, accept_timeout).await
+        });
+        (url, token, server)
+    }
+
+    /// Close code the server ends the connection with
+    async fn close_code(
+        client: &mut WebSocketStream<MaybeTlsStream<TcpStream>>,
+    ) -> Option<CloseCode> {
+        while let Some(message) = client.next().await {
+            if let Ok(Message::Close(frame)) = message {
+                return frame.map(|frame| frame.code);
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn test_origin_policy() {
+        let policy = BridgePolicy::default();
+        assert!(policy.allows_origin(None));
+        assert!(policy.allows_origin(Some("tauri://localhost")));
+        assert!(policy.allows_origin(Some("HTTP://TAURI.LOCALHOST")));
+        assert!(!policy.allows_origin(Some("https://evil.example")));
+        assert!(!policy.allows_origin(Some("null")));
+    }
+
+    #[tokio::test]
+    async fn test_bad_token_is_rejected_and_server_keeps_listening() {
+        let (url, token, server) = serve(BridgePolicy::default(), Duration::from_secs(10)).await;
+
+        let (mut intruder, _) = connect_async(url.as_str()).await.unwrap();
+        intruder
+            .send(Message::Text("not-the-token".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(close_code(&mut intruder).await, Some(CloseCode::Policy));
+
+        // The legitimate client can still attach afterwards
+        let (mut client, _) = connect_async(url.as_str()).await.unwrap();
+        client.send(Message::Text(token.clone())).await.unwrap();
+        assert!(server.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_disallowed_origin_is_rejected() {
+        let (url, token, server) = serve(BridgePolicy::default(), Duration::from_secs(10)).await;
+
+        let mut request = url.as_str().into_client_request().unwrap();
+        request
+            .headers_mut()
+            .insert("Origin", "https://evil.example".parse().unwrap());
+        let (mut client, _) = connect_async(request).await.unwrap();
+        client.send(Message::Text(token.clone())).await.unwrap();
+        assert_eq!(close_code(&mut client).await, Some(CloseCode::Policy));
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_connections_are_capped() {
+        let policy = BridgePolicy {
+            max_connections: 1,
+            ..BridgePolicy::default()
+        };
+        let (url, token, server) = serve(policy, Duration::from_secs(10)).await;
+
+        // Holds the only slot without authenticating
+        let (mut idle, _) = connect_async(url.as_str()).await.unwrap();
+        let (mut extra, _) = connect_async(url.as_str()).await.unwrap();
+        assert_eq!(close_code(&mut extra).await, Some(CloseCode::Again));
+
+        // Once the slot frees up, a new client is admitted
+        idle.close(None).await.unwrap();
+        drop(idle);
+        let mut admitted = false;
+        for _ in 0..20 {
+            let (mut client, _) = connect_async(url.as_str()).await.unwrap();
+            client.send(Message::Text(token.clone())).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            if server.is_finished() {
+                admitted = true;
+                break;
+            }
+        }
+        assert!(admitted);
+        assert!(server.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_token_failure_reported_on_timeout() {
+        let (url, _token, server) =
+            serve(BridgePolicy::default(), Duration::from_millis(500)).await;
+
+        let (mut client, _) = connect_async(url.as_str()).await.unwrap();
+        client
+            .send(Message::Text("expired".to_string()))
+            .await
+            .unwrap();
+        assert!(matches!(
+            server.await.unwrap(),
+            Err(DisconnectReason::AuthFailed)
+        ));
+    }
+
+    #[test]
+    fn test_policy_from_partial_config() {
+        let config: BridgeConfig =
+            serde_json::from_str(r#"{"heartbeatTimeoutSecs": 30, "maxConnections": 0}"#).unwrap();
+        let policy = BridgePolicy::from_config(&config);
+        assert_eq!(policy.heartbeat_timeout, Duration::from_secs(30));
+        // A zero cap would lock every client out
+        assert_eq!(policy.max_connections, 1);
+        assert_eq!(policy.detach_grace, DEFAULT_DETACH_GRACE);
+        assert_eq!(
+            policy.allowed_origins,
+            BridgePolicy::default().allowed_origins
+        );
+    }
+
+    #[test]
+    fn test_policy_clamps_heartbeat_timeout() {
+        let config: BridgeConfig = serde_json::from_str(r#"{"heartbeatTimeoutSecs": 0}"#).unwrap();
+        let policy = BridgePolicy::from_config(&config);
+        // A zero timeout would detach every client on the first tick
+        assert_eq!(
+            policy.heartbeat_timeout,
+            Duration::from_secs(MIN_HEARTBEAT_TIMEOUT_SECS)
+        );
+    }
+}
@@ -7,6 +7,7 @@ use std::time::Instant;
 use tokio::sync::mpsc;
 use tracing::{info, warn};
 
+use super::handshake::{BridgeConfig, BridgePolicy};
 use crate::ssh::SessionCommand;
 
 /// Information about an active bridge
@@ -30,15 +31,32 @@ pub struct BridgeHandle {
 /// Manages all active WebSocket bridges
 pub struct BridgeManager {
     bridges: Arc<RwLock<HashMap<String, BridgeHandle>>>,
+    config: RwLock<BridgeConfig>,
 }
 
 impl BridgeManager {
     pub fn new() -> Self {
         Self {
             bridges: Arc::new(RwLock::new(HashMap::new())),
+            config: RwLock::new(BridgeConfig::default()),
         }
     }
 
+    /// Current bridge settings
+    pub fn config(&self) -> BridgeConfig {
+        self.config.read().clone()
+    }
+
+    /// Replace the bridge settings; running bridges keep their policy
+    pub fn set_config(&self, config: BridgeConfig) {
+        *self.config.write() = config;
+    }
+
+    /// Policy for a bridge started now
+    pub fn policy(&self) -> BridgePolicy {
+        BridgePolicy::from_config(&self.config.read())
+    }
+
     /// Register a new bridge (legacy mode)
     pub fn register(&self, session_id: String, port: u16, token: String) {
         let now = Instant::now();
@@ -3,12 +3,18 @@
 //! This module provides WebSocket server functionality for bridging
 //! SSH sessions to the frontend xterm.js terminal.
 
+mod handshake;
 mod manager;
+mod paste;
 mod protocol;
+mod resize;
 mod server;
 
+pub use handshake::{BridgeConfig, BridgePolicy};
 pub use manager::BridgeManager;
+pub use paste::{HeldPaste, PasteGuard, PasteGuardPolicy, DEFAULT_PASTE_MIN_BYTES};
 pub use protocol::{
-    data_frame, error_frame, heartbeat_frame, resize_frame, Frame, FrameCodec, MessageType,
+    data_frame, error_frame, heartbeat_frame, is_oversized_frame, resize_frame, Frame, FrameCodec,
+    MessageType, OversizedFrame,
 };
 pub use server::{DisconnectReason, WsBridge};
@@ -0,0 +1,326 @@
+//! Multi-line paste guard
+//!
+//! Pasting several lines into a shell that is not in bracketed-paste mode runs
+//! each line as soon as its newline arrives. When enabled, the guard holds
+//! such pastes instead of forwarding them and announces them on a broadcast
+//! channel; the frontend asks the user, then sends or discards the paste by ID.
+//!
+//! A Data frame is held when it is at least `min_bytes` long, spans more than
+//! one line, and is not safely bracketed: framed by `ESC[200~ … ESC[201~`
+//! while the remote has bracketed-paste mode (DECSET 2004) switched on.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tracing::warn;
+
+/// Default size below which input is never treated as a paste
+pub const DEFAULT_PASTE_MIN_BYTES: usize = 8;
+
+/// Held pastes kept per session; the oldest is dropped beyond this
+const MAX_PENDING_PASTES: usize = 16;
+
+/// Characters of a held paste included in its preview
+const PREVIEW_CHARS: usize = 200;
+
+const ESC: u8 = 0x1b;
+const PASTE_START: &[u8] = b"\x1b[200~";
+const PASTE_END: &[u8] = b"\x1b[201~";
+const MODE_ON: &[u8] = b"\x1b[?2004h";
+const MODE_OFF: &[u8] = b"\x1b[?2004l";
+
+/// When to ask before sending a paste
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasteGuardPolicy {
+    /// Hold multi-line pastes until confirmed
+    #[serde(default)]
+    pub confirm_multiline: bool,
+    /// Input shorter than this is never held
+    #[serde(default = "default_min_bytes")]
+    pub min_bytes: usize,
+}
+
+fn default_min_bytes() -> usize {
+    DEFAULT_PASTE_MIN_BYTES
+}
+
+impl Default for PasteGuardPolicy {
+    fn default() -> Self {
+        Self {
+            confirm_multiline: false,
+            min_bytes: DEFAULT_PASTE_MIN_BYTES,
+        }
+    }
+}
+
+/// A paste waiting for confirmation
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeldPaste {
+    pub paste_id: u64,
+    pub byte_len: usize,
+    pub line_count: usize,
+    /// Start of the pasted text (lossy UTF-8, markers stripped)
+    pub preview: String,
+}
+
+/// Tracks whether the remote has bracketed-paste mode switched on
+///
+/// Mode switches split across output chunks are recognised.
+#[derive(Debug, Default)]
+pub struct BracketedPasteTracker {
+    enabled: bool,
+    /// Possible start of a mode switch at the end of the last chunk
+    tail: Vec<u8>,
+}
+
+impl BracketedPasteTracker {
+    /// Whether the last mode switch seen enabled bracketed paste
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Scan a chunk of remote output for mode switches
+    pub fn observe(&mut self, data: &[u8]) {
+        if self.tail.is_empty() && !data.contains(&ESC) {
+            return;
+        }
+
+        let mut buf = std::mem::take(&mut self.tail);
+        buf.extend_from_slice(data);
+
+        for (i, _) in buf.iter().enumerate().filter(|(_, b)| **b == ESC) {
+            let rest = &buf[i..];
+            if rest.starts_with(MODE_ON) {
+                self.enabled = true;
+            } else if rest.starts_with(MODE_OFF) {
+                self.enabled = false;
+            } else if rest.len() < MODE_ON.len()
+                && (MODE_ON.starts_with(rest) || MODE_OFF.starts_with(rest))
+            {
+                self.tail = rest.to_vec();
+            }
+        }
+    }
+}
+
+/// Whether `payload` must be confirmed before it is sent
+pub fn needs_confirmation(
+    payload: &[u8],
+    policy: &PasteGuardPolicy,
+    remote_bracketed: bool,
+) -> bool {
+    if !policy.confirm_multiline || payload.len() < policy.min_bytes {
+        return false;
+    }
+    match strip_paste_markers(payload) {
+        Some(_) if remote_bracketed => false,
+        Some(body) => is_multiline(body),
+        None => is_multiline(payload),
+    }
+}
+
+/// Body of a paste framed by bracketed-paste markers
+fn strip_paste_markers(payload: &[u8]) -> Option<&[u8]> {
+    payload.strip_prefix(PASTE_START)?.strip_suffix(PASTE_END)
+}
+
+fn is_line_break(b: &u8) -> bool {
+    *b == b'\r' || *b == b'\n'
+}
+
+/// More than one line: a line break followed by further content
+fn is_multiline(body: &[u8]) -> bool {
+    let end = body
+        .iter()
+        .rposition(|b| !is_line_break(b))
+        .map_or(0, |i| i + 1);
+    body[..end].iter().any(is_line_break)
+}
+
+/// Per-session paste guard shared by the bridge and the confirm command
+pub struct PasteGuard {
+    policy: Mutex<PasteGuardPolicy>,
+    tracker: Mutex<BracketedPasteTracker>,
+    pending: Mutex<BTreeMap<u64, Vec<u8>>>,
+    next_id: AtomicU64,
+    held_tx: broadcast::Sender<HeldPaste>,
+}
+
+impl PasteGuard {
+    pub fn new(policy: PasteGuardPolicy) -> Self {
+        let (held_tx, _) = broadcast::channel(MAX_PENDING_PASTES);
+        Self {
+            policy: Mutex::new(policy),
+            tracker: Mutex::new(BracketedPasteTracker::default()),
+            pending: Mutex::new(BTreeMap::new()),
+            next_id: AtomicU64::new(1),
+            held_tx,
+        }
+    }
+
+    pub fn policy(&self) -> PasteGuardPolicy {
+        *self.policy.lock()
+    }
+
+    pub fn set_policy(&self, policy: PasteGuardPolicy) {
+        *self.policy.lock() = policy;
+    }
+
+    /// Receive each paste as it is held
+    pub fn subscribe(&self) -> broadcast::Receiver<HeldPaste> {
+        self.held_tx.subscribe()
+    }
+
+    /// Feed remote output (for bracketed-paste mode tracking)
+    pub fn observe_output(&self, data: &[u8]) {
+        self.tracker.lock().observe(data);
+    }
+
+    /// Hold `payload` if it needs confirmation
+    ///
+    /// Returns true when the payload was held; the caller must not forward it.
+    /// Input typed while a paste is held is not delayed.
+    pub fn intercept(&self, payload: &[u8]) -> bool {
+        let remote_bracketed = self.tracker.lock().is_enabled();
+        if !needs_confirmation(payload, &self.policy(), remote_bracketed) {
+            return false;
+        }
+
+        let paste_id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let body = strip_paste_markers(payload).unwrap_or(payload);
+        let held = HeldPaste {
+            paste_id,
+            byte_len: payload.len(),
+            line_count: body
+                .split(is_line_break)
+                .filter(|line| !line.is_empty())
+                .count(),
+            preview: String::from_utf8_lossy(body)
+                .chars()
+                .take(PREVIEW_CHARS)
+                .collect(),
+        };
+
+        {
+            let mut pending = self.pending.lock();
+            if pending.len() >= MAX_PENDING_PASTES {
+                if let Some((dropped, _)) = pending.pop_first() {
+                    warn!("Dropping unconfirmed paste {}", dropped);
+                }
+            }
+            pending.insert(paste_id, payload.to_vec());
+        }
+
+        // No subscriber: nobody can confirm, but the paste stays held
+        let _ = self.held_tx.send(held);
+        true
+    }
+
+    /// Remove a held paste, returning its bytes (as received) to send
+    pub fn take(&self, paste_id: u64) -> Option<Vec<u8>> {
+        self.pending.lock().remove(&paste_id)
+    }
+}
+
+impl Default for PasteGuard {
+    fn default() -> Self {
+        Self::new(PasteGuardPolicy::default())
+    }
+}
+
+impl std::fmt::Debug for PasteGuard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PasteGuard")
+            .field("policy", &self.policy())
+            .field("pending", &self.pending.lock().len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn confirm_policy() -> PasteGuardPolicy {
+        PasteGuardPolicy {
+            confirm_multiline: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_multiline_without_bracketed_paste_needs_confirmation() {
+        let policy = confirm_policy();
+        let paste = b"cd /tmp\rrm -rf build\r";
+
+        assert!(needs_confirmation(paste, &policy, false));
+        // Remote in bracketed mode but the client did not frame the paste
+        assert!(needs_confirmation(paste, &policy, true));
+        // Framed, but the remote would not strip the markers
+        assert!(needs_confirmation(
+            b"\x1b[200~cd /tmp\rrm -rf build\x1b[201~",
+            &policy,
+            false
+        ));
+
+        // Safely bracketed
+        assert!(!needs_confirmation(
+            b"\x1b[200~cd /tmp\rrm -rf build\x1b[201~",
+            &policy,
+            true
+        ));
+        // Single line with a trailing newline, typing, short input
+        assert!(!needs_confirmation(b"make install\r", &policy, false));
+        assert!(!needs_confirmation(b"l", &policy, false));
+        assert!(!needs_confirmation(b"a\rb\r", &policy, false));
+        // Guard off
+        assert!(!needs_confirmation(
+            paste,
+            &PasteGuardPolicy::default(),
+            false
+        ));
+    }
+
+    #[test]
+    fn test_tracker_follows_split_mode_switches() {
+        let mut tracker = BracketedPasteTracker::default();
+        assert!(!tracker.is_enabled());
+
+        tracker.observe(b"prompt$ \x1b[?20");
+        assert!(!tracker.is_enabled());
+        tracker.observe(b"04h");
+        assert!(tracker.is_enabled());
+
+        tracker.observe(b"\x1b[?2004l\x1b[?2004h\x1b[?2004");
+        assert!(tracker.is_enabled());
+        tracker.observe(b"l vim output");
+        assert!(!tracker.is_enabled());
+    }
+
+    #[test]
+    fn test_guard_holds_and_releases_paste() {
+        let guard = PasteGuard::new(confirm_policy());
+        let mut held_rx = guard.subscribe();
+
+        assert!(!guard.intercept(b"ls\r"));
+        assert!(guard.intercept(b"echo one\recho two\r"));
+
+        let held = held_rx.try_recv().unwrap();
+        assert_eq!(held.line_count, 2);
+        assert_eq!(held.preview, "echo one\recho two\r");
+        assert_eq!(
+            guard.take(held.paste_id).as_deref(),
+            Some(&b"echo one\recho two\r"[..])
+        );
+        assert!(guard.take(held.paste_id).is_none());
+
+        // Bracketed shells get the paste straight away
+        guard.observe_output(b"\x1b[?2004h$ ");
+        assert!(!guard.intercept(b"\x1b[200~echo one\recho two\r\x1b[201~"));
+    }
+}
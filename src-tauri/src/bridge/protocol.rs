@@ -12,8 +12,18 @@
 //! - 0x01: Resize    - Window size change (cols: u16, rows: u16)
 //! - 0x02: Heartbeat - Keep-alive ping/pong
 //! - 0x03: Error     - Error notification
+//! - 0x04: CompressedData - zstd-compressed terminal data, decoded as Data
+//! - 0x05: Batch     - Several complete frames (each with its own header);
+//!   batches do not nest
+//! - 0x06: DataSeq   - Terminal data prefixed with a u32 sequence number
+//!   (seq: u32, data), for gap detection; see [`FrameCodec::detect_gap`]
+//!
+//! CompressedData is only sent by a [`FrameCodec`] built with
+//! [`FrameCodec::with_compression`], so peers that predate it never see it.
 
 use bytes::{Buf, BufMut, Bytes, BytesMut};
+use std::collections::VecDeque;
+use std::fmt;
 use std::io::{self, ErrorKind};
 
 /// Protocol version
@@ -26,6 +36,46 @@ pub const HEADER_SIZE: usize = 5;
 /// Maximum payload size (16 MB)
 pub const MAX_PAYLOAD_SIZE: usize = 16 * 1024 * 1024;
 
+/// Data payloads shorter than this are sent uncompressed even when
+/// compression is enabled; the zstd frame overhead outweighs the gain
+const MIN_COMPRESS_SIZE: usize = 256;
+
+/// Maximum number of sub-frames in one Batch frame
+pub const MAX_BATCH_FRAMES: usize = 4096;
+
+/// Decode error for a frame header that declares a payload larger than
+/// [`MAX_PAYLOAD_SIZE`] (or the smaller limit of a
+/// [`FrameCodec::with_max_buffer`] codec).
+///
+/// Length-prefixed streams cannot be resynchronized once a header is corrupt,
+/// so this is always fatal for the connection. It is carried inside an
+/// `io::Error` of kind `InvalidData`; use [`is_oversized_frame`] to detect it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OversizedFrame {
+    /// Payload length declared by the header
+    pub declared: usize,
+    /// Largest payload the decoder accepts
+    pub max: usize,
+}
+
+impl fmt::Display for OversizedFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Payload too large: {} bytes (max {})",
+            self.declared, self.max
+        )
+    }
+}
+
+impl std::error::Error for OversizedFrame {}
+
+/// Check whether a decode error is an [`OversizedFrame`] protocol violation
+pub fn is_oversized_frame(err: &io::Error) -> bool {
+    err.get_ref()
+        .is_some_and(|inner| inner.downcast_ref::<OversizedFrame>().is_some())
+}
+
 /// Message types for the wire protocol
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
@@ -38,6 +88,12 @@ pub enum MessageType {
     Heartbeat = 0x02,
     /// Error message
     Error = 0x03,
+    /// zstd-compressed terminal I/O data
+    CompressedData = 0x04,
+    /// Several frames sent as one
+    Batch = 0x05,
+    /// Sequence-numbered terminal I/O data
+    DataSeq = 0x06,
 }
 
 impl MessageType {
@@ -48,6 +104,9 @@ impl MessageType {
             0x01 => Some(Self::Resize),
             0x02 => Some(Self::Heartbeat),
             0x03 => Some(Self::Error),
+            0x04 => Some(Self::CompressedData),
+            0x05 => Some(Self::Batch),
+            0x06 => Some(Self::DataSeq),
             _ => None,
         }
     }
@@ -69,6 +128,10 @@ pub enum Frame {
     Heartbeat(u32),
     /// Error message
     Error(String),
+    /// Several frames sent as one (never nested)
+    Batch(Vec<Frame>),
+    /// Terminal I/O data with a sequence number
+    DataSeq { seq: u32, data: Bytes },
 }
 
 impl Frame {
@@ -99,6 +162,20 @@ impl Frame {
                 buf.put_u32(msg_bytes.len() as u32);
                 buf.extend_from_slice(msg_bytes);
             }
+            Frame::Batch(frames) => {
+                let encoded: Vec<Bytes> = frames.iter().map(Frame::encode).collect();
+                buf.put_u8(MessageType::Batch.as_byte());
+                buf.put_u32(encoded.iter().map(Bytes::len).sum::<usize>() as u32);
+                for frame in encoded {
+                    buf.extend_from_slice(&frame);
+                }
+            }
+            Frame::DataSeq { seq, data } => {
+                buf.put_u8(MessageType::DataSeq.as_byte());
+                buf.put_u32(4 + data.len() as u32);
+                buf.put_u32(*seq);
+                buf.extend_from_slice(data);
+            }
         }
 
         buf.freeze()
@@ -107,6 +184,11 @@ impl Frame {
     /// Try to decode a frame from bytes
     /// Returns None if not enough data, Err if invalid
     pub fn decode(buf: &mut BytesMut) -> io::Result<Option<Self>> {
+        Self::decode_with_limit(buf, MAX_PAYLOAD_SIZE)
+    }
+
+    /// [`decode`](Self::decode), rejecting payloads over `max_payload` bytes
+    fn decode_with_limit(buf: &mut BytesMut, max_payload: usize) -> io::Result<Option<Self>> {
         // Check if we have enough for the header
         if buf.len() < HEADER_SIZE {
             return Ok(None);
@@ -117,13 +199,24 @@ impl Frame {
         let length = u32::from_be_bytes([buf[1], buf[2], buf[3], buf[4]]) as usize;
 
         // Validate length
-        if length > MAX_PAYLOAD_SIZE {
+        if length > max_payload {
             return Err(io::Error::new(
                 ErrorKind::InvalidData,
-                format!("Payload too large: {} bytes", length),
+                OversizedFrame {
+                    declared: length,
+                    max: max_payload,
+                },
             ));
         }
 
+        // Validate type before consuming anything
+        let msg_type = MessageType::from_byte(msg_type).ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Unknown message type: {}", msg_type),
+            )
+        })?;
+
         // Check if we have the full frame
         if buf.len() < HEADER_SIZE + length {
             return Ok(None);
@@ -132,14 +225,6 @@ impl Frame {
         // Consume header
         buf.advance(HEADER_SIZE);
 
-        // Parse payload based on type
-        let msg_type = MessageType::from_byte(msg_type).ok_or_else(|| {
-            io::Error::new(
-                ErrorKind::InvalidData,
-                format!("Unknown message type: {}", msg_type),
-            )
-        })?;
-
         let frame = match msg_type {
             MessageType::Data => {
                 let data = buf.split_to(length).freeze();
@@ -171,21 +256,154 @@ impl Frame {
                 let msg = String::from_utf8_lossy(&data).to_string();
                 Frame::Error(msg)
             }
+            MessageType::CompressedData => {
+                let data = buf.split_to(length);
+                Frame::Data(Bytes::from(decompress_payload(&data)?))
+            }
+            MessageType::Batch => {
+                let mut payload = buf.split_to(length);
+                let mut frames = Vec::new();
+                while !payload.is_empty() {
+                    if payload[0] == MessageType::Batch.as_byte() {
+                        return Err(io::Error::new(
+                            ErrorKind::InvalidData,
+                            "Batch frame must not contain another batch",
+                        ));
+                    }
+                    if frames.len() == MAX_BATCH_FRAMES {
+                        return Err(io::Error::new(
+                            ErrorKind::InvalidData,
+                            format!("Batch frame holds more than {} frames", MAX_BATCH_FRAMES),
+                        ));
+                    }
+                    match Frame::decode(&mut payload)? {
+                        Some(frame) => frames.push(frame),
+                        None => {
+                            return Err(io::Error::new(
+                                ErrorKind::InvalidData,
+                                "Batch frame ends inside a sub-frame",
+                            ))
+                        }
+                    }
+                }
+                Frame::Batch(frames)
+            }
+            MessageType::DataSeq => {
+                if length < 4 {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidData,
+                        "DataSeq frame must have at least 4 bytes payload",
+                    ));
+                }
+                let seq = buf.get_u32();
+                let data = buf.split_to(length - 4).freeze();
+                Frame::DataSeq { seq, data }
+            }
         };
 
         Ok(Some(frame))
     }
 }
 
+/// Decompress a CompressedData payload. The output is capped like any other
+/// payload; memory grows with the actual output rather than the cap.
+fn decompress_payload(data: &[u8]) -> io::Result<Vec<u8>> {
+    use std::io::Read;
+
+    let invalid = |e: io::Error| {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!("Invalid compressed data frame: {}", e),
+        )
+    };
+    let decoder = zstd::stream::Decoder::new(data).map_err(invalid)?;
+    let mut out = Vec::new();
+    decoder
+        .take(MAX_PAYLOAD_SIZE as u64 + 1)
+        .read_to_end(&mut out)
+        .map_err(invalid)?;
+    if out.len() > MAX_PAYLOAD_SIZE {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Compressed data frame expands beyond {} bytes",
+                MAX_PAYLOAD_SIZE
+            ),
+        ));
+    }
+    Ok(out)
+}
+
 /// Frame encoder/decoder for streaming
 pub struct FrameCodec {
     buffer: BytesMut,
+    /// Sub-frames of a decoded batch not yet returned
+    pending: VecDeque<Frame>,
+    /// Sequence number of the last DataSeq frame decoded
+    last_seq: Option<u32>,
+    /// Unreported discontinuity as (expected, received)
+    gap: Option<(u32, u32)>,
+    /// Most bytes buffered for one incomplete frame, header included
+    max_buffer: usize,
+    /// zstd level for outgoing Data frames; `None` sends them uncompressed
+    compression: Option<i32>,
 }
 
 impl FrameCodec {
     pub fn new() -> Self {
         Self {
             buffer: BytesMut::with_capacity(8192),
+            pending: VecDeque::new(),
+            last_seq: None,
+            gap: None,
+            max_buffer: HEADER_SIZE + MAX_PAYLOAD_SIZE,
+            compression: None,
+        }
+    }
+
+    /// A codec that buffers at most `bytes` for an incomplete frame
+    ///
+    /// A header declaring a larger frame fails in [`decode_next`](Self::decode_next)
+    /// with an [`OversizedFrame`] error instead of buffering its payload. The
+    /// limit never exceeds the default (a maximum-size frame).
+    pub fn with_max_buffer(bytes: usize) -> Self {
+        Self {
+            max_buffer: bytes.clamp(HEADER_SIZE, HEADER_SIZE + MAX_PAYLOAD_SIZE),
+            ..Self::new()
+        }
+    }
+
+    /// A codec whose [`encode`](Self::encode) sends Data frames as
+    /// CompressedData at the given zstd level
+    ///
+    /// Only use this with peers known to decode CompressedData frames.
+    pub fn with_compression(level: i32) -> Self {
+        Self {
+            compression: Some(level),
+            ..Self::new()
+        }
+    }
+
+    /// Encode a frame for sending
+    ///
+    /// With compression enabled, Data frames are compressed unless they are
+    /// small or would not shrink; other frames are encoded as usual.
+    pub fn encode(&self, frame: &Frame) -> Bytes {
+        let (Some(level), Frame::Data(data)) = (self.compression, frame) else {
+            return frame.encode();
+        };
+        if data.len() < MIN_COMPRESS_SIZE {
+            return frame.encode();
+        }
+        match zstd::bulk::compress(data, level) {
+            Ok(compressed) if compressed.len() < data.len() => {
+                let mut buf = BytesMut::with_capacity(HEADER_SIZE + compressed.len());
+                buf.put_u8(MessageType::CompressedData.as_byte());
+                buf.put_u32(compressed.len() as u32);
+                buf.extend_from_slice(&compressed);
+                buf.freeze()
+            }
+            _ => frame.encode(),
         }
     }
 
@@ -195,18 +413,66 @@ impl FrameCodec {
     }
 
     /// Try to decode the next frame
+    ///
+    /// Batch frames are flattened: their sub-frames are returned one by one,
+    /// so callers never see a `Frame::Batch`. DataSeq frames are returned as
+    /// `Frame::Data` once their sequence number has been checked.
+    ///
+    /// A header declaring a frame larger than the buffer limit is an
+    /// [`OversizedFrame`] error as soon as the header arrives.
+    ///
+    /// On a decode error the buffered bytes are discarded: a corrupt header
+    /// means frame boundaries are lost, and keeping the bytes would make every
+    /// later call fail (or wait forever for a payload that never arrives).
     pub fn decode_next(&mut self) -> io::Result<Option<Frame>> {
-        Frame::decode(&mut self.buffer)
+        loop {
+            if let Some(frame) = self.pending.pop_front() {
+                return Ok(Some(self.track_sequence(frame)));
+            }
+            match Frame::decode_with_limit(&mut self.buffer, self.max_buffer - HEADER_SIZE) {
+                Ok(Some(Frame::Batch(frames))) => self.pending.extend(frames),
+                Ok(frame) => return Ok(frame.map(|frame| self.track_sequence(frame))),
+                Err(e) => {
+                    self.buffer.clear();
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    /// Record a DataSeq frame's sequence number and unwrap it into Data
+    fn track_sequence(&mut self, frame: Frame) -> Frame {
+        let Frame::DataSeq { seq, data } = frame else {
+            return frame;
+        };
+        if let Some(last) = self.last_seq {
+            let expected = last.wrapping_add(1);
+            if seq != expected {
+                self.gap = Some((expected, seq));
+            }
+        }
+        self.last_seq = Some(seq);
+        Frame::Data(data)
+    }
+
+    /// Take the latest sequence discontinuity as (expected, received)
+    ///
+    /// The first DataSeq frame sets the baseline; after that each frame must
+    /// carry the previous sequence number plus one (wrapping). A gap is
+    /// reported once; later frames are checked against the received number.
+    pub fn detect_gap(&mut self) -> Option<(u32, u32)> {
+        self.gap.take()
     }
 
     /// Clear internal buffer
     pub fn clear(&mut self) {
         self.buffer.clear();
+        self.pending.clear();
     }
 
     /// Check if buffer is too large (possible malformed data)
     pub fn is_overflow(&self) -> bool {
-        self.buffer.len() > MAX_PAYLOAD_SIZE
+        self.buffer.len() > self.max_buffer
     }
 
     /// Get buffer length
@@ -304,6 +570,143 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_compressed_data_frame_roundtrip() {
+        let payload: Vec<u8> = b"drwxr-xr-x  2 root root 4096 Jan  1 00:00 bin\r\n"
+            .iter()
+            .copied()
+            .cycle()
+            .take(64 * 1024)
+            .collect();
+
+        let encoded = FrameCodec::with_compression(3).encode(&data_frame(payload.clone()));
+        assert_eq!(encoded[0], MessageType::CompressedData.as_byte());
+        assert!(encoded.len() < payload.len() / 10);
+
+        let mut codec = FrameCodec::new();
+        codec.feed(&encoded);
+        match codec.decode_next().unwrap().unwrap() {
+            Frame::Data(data) => assert_eq!(data, payload),
+            _ => panic!("Expected Data frame"),
+        }
+
+        // Without compression, or for short input, plain Data frames are sent
+        let plain = FrameCodec::new().encode(&data_frame(payload));
+        assert_eq!(plain[0], MessageType::Data.as_byte());
+        let short = FrameCodec::with_compression(3).encode(&data_frame(&b"ls\r"[..]));
+        assert_eq!(short[0], MessageType::Data.as_byte());
+    }
+
+    #[test]
+    fn test_corrupt_compressed_frame_errors() {
+        let mut buf = BytesMut::new();
+        buf.put_u8(MessageType::CompressedData.as_byte());
+        buf.put_u32(4);
+        buf.put_slice(b"junk");
+
+        let mut codec = FrameCodec::new();
+        codec.feed(&buf);
+        let err = codec.decode_next().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(!is_oversized_frame(&err));
+    }
+
+    #[test]
+    fn test_compressed_frame_expanding_past_limit_errors() {
+        let compressed = zstd::bulk::compress(&vec![0u8; MAX_PAYLOAD_SIZE + 1], 3).unwrap();
+        let mut buf = BytesMut::new();
+        buf.put_u8(MessageType::CompressedData.as_byte());
+        buf.put_u32(compressed.len() as u32);
+        buf.put_slice(&compressed);
+
+        let mut codec = FrameCodec::new();
+        codec.feed(&buf);
+        let err = codec.decode_next().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_batch_frame_is_flattened() {
+        let batch = Frame::Batch(vec![
+            data_frame(Bytes::from_static(b"ls\r")),
+            resize_frame(132, 43),
+        ]);
+
+        let mut codec = FrameCodec::new();
+        codec.feed(&batch.encode());
+        codec.feed(&heartbeat_frame(7).encode());
+
+        match codec.decode_next().unwrap().unwrap() {
+            Frame::Data(d) => assert_eq!(d, &b"ls\r"[..]),
+            _ => panic!("Expected Data frame"),
+        }
+        match codec.decode_next().unwrap().unwrap() {
+            Frame::Resize { cols, rows } => assert_eq!((cols, rows), (132, 43)),
+            _ => panic!("Expected Resize frame"),
+        }
+        match codec.decode_next().unwrap().unwrap() {
+            Frame::Heartbeat(seq) => assert_eq!(seq, 7),
+            _ => panic!("Expected Heartbeat frame"),
+        }
+        assert!(codec.decode_next().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_invalid_batch_frames_error() {
+        let decode = |frame: Frame| Frame::decode(&mut BytesMut::from(&frame.encode()[..]));
+
+        let nested = Frame::Batch(vec![Frame::Batch(vec![heartbeat_frame(1)])]);
+        assert_eq!(decode(nested).unwrap_err().kind(), ErrorKind::InvalidData);
+
+        let too_many = Frame::Batch(vec![heartbeat_frame(1); MAX_BATCH_FRAMES + 1]);
+        assert_eq!(decode(too_many).unwrap_err().kind(), ErrorKind::InvalidData);
+        let at_cap = Frame::Batch(vec![heartbeat_frame(1); MAX_BATCH_FRAMES]);
+        assert!(matches!(decode(at_cap), Ok(Some(Frame::Batch(f))) if f.len() == MAX_BATCH_FRAMES));
+
+        // Sub-frame cut short by the batch length
+        let mut buf = BytesMut::new();
+        buf.put_u8(MessageType::Batch.as_byte());
+        buf.put_u32(3);
+        buf.put_slice(&heartbeat_frame(1).encode()[..3]);
+        assert_eq!(
+            Frame::decode(&mut buf).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn test_data_seq_gaps_are_detected() {
+        let seq_frame = |seq| Frame::DataSeq {
+            seq,
+            data: Bytes::from_static(b"out"),
+        };
+        let mut codec = FrameCodec::new();
+
+        for seq in [7, 8, 9] {
+            codec.feed(&seq_frame(seq).encode());
+            match codec.decode_next().unwrap().unwrap() {
+                Frame::Data(d) => assert_eq!(d, &b"out"[..]),
+                _ => panic!("Expected Data frame"),
+            }
+        }
+        assert_eq!(codec.detect_gap(), None);
+
+        // 10 was dropped, then arrives late
+        codec.feed(&seq_frame(11).encode());
+        codec.feed(&seq_frame(10).encode());
+        codec.decode_next().unwrap().unwrap();
+        assert_eq!(codec.detect_gap(), Some((10, 11)));
+        assert_eq!(codec.detect_gap(), None);
+        codec.decode_next().unwrap().unwrap();
+        assert_eq!(codec.detect_gap(), Some((12, 10)));
+
+        // Sequence numbers wrap, including inside a batch
+        codec.feed(&Frame::Batch(vec![seq_frame(11), seq_frame(u32::MAX)]).encode());
+        codec.feed(&seq_frame(0).encode());
+        while codec.decode_next().unwrap().is_some() {}
+        assert_eq!(codec.detect_gap(), Some((12, u32::MAX)));
+    }
+
     #[test]
     fn test_partial_frame() {
         let frame = data_frame(Bytes::from_static(b"hello"));
@@ -347,4 +750,77 @@ mod tests {
         // No more frames
         assert!(codec.decode_next().unwrap().is_none());
     }
+
+    #[test]
+    fn test_oversized_length_header_errors() {
+        let mut codec = FrameCodec::new();
+
+        // Data frame header declaring 4 GiB of payload, followed by garbage
+        let mut bogus = vec![MessageType::Data.as_byte(), 0xFF, 0xFF, 0xFF, 0xFF];
+        bogus.extend_from_slice(b"garbage");
+        codec.feed(&bogus);
+
+        let err = codec.decode_next().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(is_oversized_frame(&err));
+
+        // Garbage is dropped instead of accumulating
+        assert_eq!(codec.buffer_len(), 0);
+        assert!(codec.decode_next().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_oversized_header_errors_before_payload_arrives() {
+        // Just the header: must error immediately rather than wait for 16MB+
+        let mut buf = BytesMut::new();
+        buf.put_u8(MessageType::Data.as_byte());
+        buf.put_u32((MAX_PAYLOAD_SIZE + 1) as u32);
+
+        let err = Frame::decode(&mut buf).unwrap_err();
+        assert!(is_oversized_frame(&err));
+    }
+
+    #[test]
+    fn test_codec_rejects_frames_over_buffer_limit() {
+        let header = |declared: usize| {
+            let mut buf = BytesMut::new();
+            buf.put_u8(MessageType::Data.as_byte());
+            buf.put_u32(declared as u32);
+            buf
+        };
+
+        // Valid header claiming 20MB: errors at once instead of buffering
+        let mut codec = FrameCodec::new();
+        codec.feed(&header(20 * 1024 * 1024));
+        assert!(is_oversized_frame(&codec.decode_next().unwrap_err()));
+        assert_eq!(codec.buffer_len(), 0);
+
+        // A smaller configured limit applies to frames under MAX_PAYLOAD_SIZE
+        let mut codec = FrameCodec::with_max_buffer(1024 * 1024);
+        codec.feed(&header(2 * 1024 * 1024));
+        codec.feed(&[0; 4096]);
+        let err = codec.decode_next().unwrap_err();
+        assert_eq!(
+            err.get_ref().unwrap().downcast_ref::<OversizedFrame>(),
+            Some(&OversizedFrame {
+                declared: 2 * 1024 * 1024,
+                max: 1024 * 1024 - HEADER_SIZE,
+            })
+        );
+        assert_eq!(codec.buffer_len(), 0);
+
+        // Frames within the limit still decode
+        codec.feed(&data_frame(vec![b'x'; 1024 * 1024 - HEADER_SIZE]).encode());
+        assert!(matches!(codec.decode_next(), Ok(Some(Frame::Data(_)))));
+    }
+
+    #[test]
+    fn test_unknown_type_is_not_oversized() {
+        let mut codec = FrameCodec::new();
+        codec.feed(&[0x7F, 0, 0, 0, 1, 0]);
+
+        let err = codec.decode_next().unwrap_err();
+        assert!(!is_oversized_frame(&err));
+        assert_eq!(codec.buffer_len(), 0);
+    }
 }
@@ -0,0 +1,182 @@
+//! Resize Coalescing
+//!
+//! Dragging a window edge makes xterm.js emit a burst of resize frames. Sent
+//! one by one, each becomes a remote `window-change` request, which can flood
+//! slow links and makes full-screen programs redraw repeatedly (flicker).
+//!
+//! The coalescer sits between the WebSocket input task and the PTY: it keeps
+//! only the most recent dimensions and forwards them once no further resize
+//! has arrived for the debounce interval. A drag that never pauses still
+//! gets its latest size forwarded every max-wait interval, so the remote
+//! side keeps up while the user is resizing.
+
+use std::future::Future;
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Quiet period before the latest resize is forwarded (ms)
+pub const RESIZE_DEBOUNCE_MS: u64 = 50;
+
+/// Longest a resize is held back during a continuous burst (ms)
+pub const RESIZE_MAX_WAIT_MS: u64 = 250;
+
+/// Capacity of the coalescer input channel
+const RESIZE_CHANNEL_CAPACITY: usize = 64;
+
+/// Spawn a resize coalescer.
+///
+/// Returns the sender the input task should push `(cols, rows)` into, and the
+/// task handle. `apply` forwards a single resize to the PTY and returns
+/// `false` once the downstream side is gone, which stops the coalescer.
+///
+/// A resize is forwarded after `debounce` of quiet, or `max_wait` after the
+/// first resize of a burst, whichever comes first. When the sender is
+/// dropped, any pending resize is flushed before exit.
+pub(crate) fn spawn_resize_coalescer<F, Fut>(
+    debounce: Duration,
+    max_wait: Duration,
+    apply: F,
+) -> (mpsc::Sender<(u16, u16)>, JoinHandle<()>)
+where
+    F: FnMut(u16, u16) -> Fut + Send + 'static,
+    Fut: Future<Output = bool> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel(RESIZE_CHANNEL_CAPACITY);
+    let handle = tokio::spawn(coalesce_resizes(rx, debounce, max_wait, apply));
+    (tx, handle)
+}
+
+async fn coalesce_resizes<F, Fut>(
+    mut rx: mpsc::Receiver<(u16, u16)>,
+    debounce: Duration,
+    max_wait: Duration,
+    mut apply: F,
+) where
+    F: FnMut(u16, u16) -> Fut,
+    Fut: Future<Output = bool>,
+{
+    while let Some(mut latest) = rx.recv().await {
+        let deadline = tokio::time::Instant::now() + max_wait;
+
+        // Absorb the rest of the burst, keeping only the newest size. Stops
+        // when the quiet period elapses, the burst has been held for
+        // `max_wait`, or the channel closes.
+        loop {
+            let now = tokio::time::Instant::now();
+            if now >= deadline {
+                break;
+            }
+            let quiet_until = (now + debounce).min(deadline);
+            match tokio::time::timeout_at(quiet_until, rx.recv()).await {
+                Ok(Some(next)) => latest = next,
+                _ => break,
+            }
+        }
+
+        let (cols, rows) = latest;
+        if !apply(cols, rows).await {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Coalescer whose "PTY" records every resize it receives
+    fn spawn_with_mock(
+        debounce: Duration,
+    ) -> (
+        mpsc::Sender<(u16, u16)>,
+        JoinHandle<()>,
+        mpsc::UnboundedReceiver<(u16, u16)>,
+    ) {
+        spawn_with_max_wait(debounce, Duration::from_secs(60))
+    }
+
+    fn spawn_with_max_wait(
+        debounce: Duration,
+        max_wait: Duration,
+    ) -> (
+        mpsc::Sender<(u16, u16)>,
+        JoinHandle<()>,
+        mpsc::UnboundedReceiver<(u16, u16)>,
+    ) {
+        let (pty_tx, pty_rx) = mpsc::unbounded_channel();
+        let (tx, handle) = spawn_resize_coalescer(debounce, max_wait, move |cols, rows| {
+            let ok = pty_tx.send((cols, rows)).is_ok();
+            async move { ok }
+        });
+        (tx, handle, pty_rx)
+    }
+
+    #[tokio::test]
+    async fn test_burst_collapses_to_last_size() {
+        let (tx, handle, mut pty_rx) = spawn_with_mock(Duration::from_millis(30));
+
+        for (cols, rows) in [(80, 24), (90, 25), (100, 30), (110, 32), (120, 40)] {
+            tx.send((cols, rows)).await.unwrap();
+        }
+
+        let first = tokio::time::timeout(Duration::from_secs(2), pty_rx.recv())
+            .await
+            .expect("resize was never flushed");
+        assert_eq!(first, Some((120, 40)));
+
+        drop(tx);
+        handle.await.unwrap();
+        assert!(pty_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_separate_resizes_are_both_forwarded() {
+        let (tx, handle, mut pty_rx) = spawn_with_mock(Duration::from_millis(10));
+
+        tx.send((80, 24)).await.unwrap();
+        assert_eq!(pty_rx.recv().await, Some((80, 24)));
+
+        tx.send((132, 43)).await.unwrap();
+        assert_eq!(pty_rx.recv().await, Some((132, 43)));
+
+        drop(tx);
+        handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_pending_resize_flushed_on_close() {
+        let (tx, handle, mut pty_rx) = spawn_with_mock(Duration::from_secs(60));
+
+        tx.send((80, 24)).await.unwrap();
+        tx.send((100, 50)).await.unwrap();
+        drop(tx);
+
+        handle.await.unwrap();
+        assert_eq!(pty_rx.recv().await, Some((100, 50)));
+        assert_eq!(pty_rx.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_continuous_burst_flushes_at_max_wait() {
+        // Resizes keep arriving faster than the quiet period
+        let (tx, handle, mut pty_rx) =
+            spawn_with_max_wait(Duration::from_millis(60), Duration::from_millis(100));
+
+        for cols in 80..100 {
+            tx.send((cols, 24)).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        drop(tx);
+        handle.await.unwrap();
+
+        let mut forwarded = Vec::new();
+        while let Ok(size) = pty_rx.try_recv() {
+            forwarded.push(size);
+        }
+        // Intermediate sizes went out during the drag, and the last one won
+        assert!(forwarded.len() >= 2, "forwarded {:?}", forwarded);
+        assert_eq!(forwarded.last(), Some(&(99, 24)));
+    }
+}
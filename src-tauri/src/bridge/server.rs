@@ -9,12 +9,16 @@ use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use subtle::ConstantTimeEq;
-use tokio::net::{TcpListener, TcpStream};
+use tokio::net::TcpListener;
 use tokio::sync::{mpsc, oneshot};
-use tokio_tungstenite::{accept_async, tungstenite::Message};
+use tokio_tungstenite::tungstenite::Message;
 use tracing::{debug, error, info, warn};
 
-use super::protocol::{data_frame, error_frame, heartbeat_frame, Frame, FrameCodec};
+use super::handshake::{accept_authenticated, BridgePolicy, WsStream};
+use super::protocol::{
+    data_frame, error_frame, heartbeat_frame, is_oversized_frame, Frame, FrameCodec,
+};
+use super::resize::{spawn_resize_coalescer, RESIZE_DEBOUNCE_MS, RESIZE_MAX_WAIT_MS};
 use crate::session::{parse_terminal_output, ScrollBuffer};
 use crate::ssh::{
     ExtendedSessionHandle as SshExtendedSessionHandle, SessionCommand, SessionHandle,
@@ -22,20 +26,24 @@ use crate::ssh::{
 
 /// Heartbeat interval (seconds)
 const HEARTBEAT_INTERVAL_SECS: u64 = 30;
-/// Heartbeat timeout - consider connection dead if no response (seconds)
+/// Default heartbeat timeout - consider connection dead if no frame arrives (seconds)
 /// This is a LOCAL WebSocket (localhost), not over the network.
 /// Raised from 90s to 300s (5 min) to tolerate macOS App Nap, system sleep,
 /// and background tab throttling that can pause JS execution.
 /// Real connection liveness is monitored by SSH heartbeat (15s interval).
-const HEARTBEAT_TIMEOUT_SECS: u64 = 300;
+/// Overridable per bridge via `BridgePolicy::heartbeat_timeout`.
+pub(super) const HEARTBEAT_TIMEOUT_SECS: u64 = 300;
 /// WebSocket send timeout - disconnect if a single frame cannot be delivered (seconds)
 /// Raised from 5s to tolerate mobile/VPN network jitter
 const WS_SEND_TIMEOUT_SECS: u64 = 15;
 /// WebSocket accept timeout (seconds)
 /// Extended to 60s to handle font loading and multiple concurrent terminals
 const WS_ACCEPT_TIMEOUT_SECS: u64 = 60;
-/// Number of lines to replay after reconnect
-const REPLAY_LINE_COUNT: usize = 50;
+/// Default number of scroll buffer lines replayed to a (re)attaching client
+/// Overridable per bridge via `BridgePolicy::replay_lines`.
+pub(super) const REPLAY_LINE_COUNT: usize = 50;
+/// Grace period for delivering the final Error frame after a fatal protocol error (ms)
+const PROTOCOL_ERROR_FLUSH_MS: u64 = 500;
 /// Token validity window (seconds) - tokens older than this are rejected
 /// Extended to 300s (5 min) to handle high-latency networks and system load
 const TOKEN_VALIDITY_SECS: u64 = 300;
@@ -48,8 +56,24 @@ const FRAME_CHANNEL_CAPACITY: usize = 16384;
 #[cfg(not(target_os = "windows"))]
 const FRAME_CHANNEL_CAPACITY: usize = 4096;
 
-async fn build_replay_frame(scroll_buffer: Arc<ScrollBuffer>) -> Result<Vec<u8>, String> {
-    let lines = scroll_buffer.tail_lines(REPLAY_LINE_COUNT).await;
+/// Give the sender task a short window to flush a final Error frame.
+///
+/// Callers must abort every other task holding a frame sender first so the
+/// sender task can observe the closed channel and finish on its own.
+async fn flush_sender<T>(sender_task: &mut tokio::task::JoinHandle<T>) {
+    let _ = tokio::time::timeout(Duration::from_millis(PROTOCOL_ERROR_FLUSH_MS), sender_task).await;
+}
+
+/// Encode the Error frame sent to the client before a protocol teardown
+fn protocol_error_frame(err: &std::io::Error) -> Bytes {
+    error_frame(format!("Protocol error: {}", err)).encode()
+}
+
+async fn build_replay_frame(
+    scroll_buffer: Arc<ScrollBuffer>,
+    line_count: usize,
+) -> Result<Vec<u8>, String> {
+    let lines = scroll_buffer.tail_lines(line_count).await;
     if lines.is_empty() {
         return Ok(Vec::new());
     }
@@ -88,7 +112,7 @@ const TOKEN_TOTAL_LEN: usize = TOKEN_RANDOM_LEN + TOKEN_TIMESTAMP_LEN; // 40 byt
 /// - Output: 54 character URL-safe Base64 string
 ///
 /// The token appears opaque to external observers - no visible structure.
-fn generate_token() -> String {
+pub(super) fn generate_token() -> String {
     let mut data = [0u8; TOKEN_TOTAL_LEN];
     // Fill first 32 bytes with cryptographically secure random data
     rand::rngs::OsRng.fill_bytes(&mut data[..TOKEN_RANDOM_LEN]);
@@ -103,7 +127,7 @@ fn generate_token() -> String {
 /// portion, then checks timestamp expiration from the expected token.
 ///
 /// Returns true if token matches and has not expired.
-fn validate_token(received: &str, expected: &str) -> bool {
+pub(super) fn validate_token(received: &str, expected: &str) -> bool {
     let received_trimmed = received.trim();
 
     // Quick length check (doesn't leak token content)
@@ -170,6 +194,8 @@ pub enum DisconnectReason {
     AcceptTimeout,
     /// Authentication failed
     AuthFailed,
+    /// Client sent a frame that violates the wire protocol
+    ProtocolError(String),
 }
 
 impl DisconnectReason {
@@ -192,6 +218,7 @@ impl DisconnectReason {
             DisconnectReason::NetworkError(e) => format!("Network error: {}", e),
             DisconnectReason::AcceptTimeout => "Connection accept timeout".to_string(),
             DisconnectReason::AuthFailed => "Authentication failed".to_string(),
+            DisconnectReason::ProtocolError(e) => format!("Protocol error: {}", e),
         }
     }
 }
@@ -236,6 +263,54 @@ impl ConnectionState {
     }
 }
 
+/// Send heartbeats until the client goes quiet for `timeout`
+///
+/// Any frame from the client counts as a sign of life (see
+/// `ConnectionState::touch`). Heartbeats go out every `HEARTBEAT_INTERVAL_SECS`,
+/// or more often when the timeout is shorter, so a client that echoes them
+/// never times out. Returns `"heartbeat_timeout"`, or `"channel_full"` when
+/// the frame channel can't take a heartbeat.
+async fn heartbeat_loop(
+    state: Arc<ConnectionState>,
+    frame_tx: mpsc::Sender<Bytes>,
+    timeout: Duration,
+    session_id: String,
+) -> &'static str {
+    let period = Duration::from_secs(HEARTBEAT_INTERVAL_SECS).min(timeout / 4);
+    let mut interval = tokio::time::interval(period.max(Duration::from_millis(1)));
+    loop {
+        interval.tick().await;
+
+        let now_millis = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let elapsed = Duration::from_millis(now_millis.saturating_sub(state.last_seen_millis()));
+        if elapsed > timeout {
+            warn!(
+                "Heartbeat timeout for session {} ({:?} since last activity)",
+                session_id, elapsed
+            );
+            // Best effort: a frontend that is merely stalled learns why it was dropped
+            let err = error_frame("Connection timeout - no heartbeat response");
+            let _ = frame_tx.try_send(err.encode());
+            return "heartbeat_timeout";
+        }
+
+        // Send heartbeat (non-blocking to avoid backpressure)
+        let seq = state.next_seq();
+        if frame_tx.try_send(heartbeat_frame(seq).encode()).is_err() {
+            // Channel full means frontend is overloaded - abort heartbeat
+            debug!(
+                "Heartbeat channel full, terminating heartbeat task for session {}",
+                session_id
+            );
+            return "channel_full";
+        }
+        debug!("Sent heartbeat seq={} for session {}", seq, session_id);
+    }
+}
+
 /// Channel for sending resize events back to SSH
 pub type ResizeTx = mpsc::Sender<(u16, u16)>;
 pub type ResizeRx = mpsc::Receiver<(u16, u16)>;
@@ -255,6 +330,7 @@ impl WsBridge {
     pub async fn start(
         session_handle: SessionHandle,
         scroll_buffer: Arc<ScrollBuffer>,
+        policy: BridgePolicy,
     ) -> Result<(String, u16, String), String> {
         // Generate time-bound authentication token to prevent local process hijacking
         let token = generate_token();
@@ -288,6 +364,7 @@ impl WsBridge {
             ready_tx,
             token_clone,
             scroll_buffer,
+            policy,
         ));
 
         // Wait for server to be ready (with timeout)
@@ -300,6 +377,7 @@ impl WsBridge {
     pub async fn start_with_resize(
         session_handle: SessionHandle,
         scroll_buffer: Arc<ScrollBuffer>,
+        policy: BridgePolicy,
     ) -> Result<(String, u16, String, ResizeRx), String> {
         // Generate time-bound authentication token to prevent local process hijacking
         let token = generate_token();
@@ -336,6 +414,7 @@ impl WsBridge {
             ready_tx,
             token_clone,
             scroll_buffer,
+            policy,
         ));
 
         let _ = tokio::time::timeout(Duration::from_millis(500), ready_rx).await;
@@ -349,6 +428,7 @@ impl WsBridge {
         session_handle: SshExtendedSessionHandle,
         scroll_buffer: Arc<ScrollBuffer>,
         replay_on_connect: bool,
+        policy: BridgePolicy,
     ) -> Result<(String, u16, String), String> {
         // Generate time-bound authentication token to prevent local process hijacking
         let token = generate_token();
@@ -379,6 +459,7 @@ impl WsBridge {
             token_clone,
             scroll_buffer,
             replay_on_connect,
+            policy,
         ));
 
         let _ = tokio::time::timeout(Duration::from_millis(500), ready_rx).await;
@@ -390,10 +471,11 @@ impl WsBridge {
     /// This is the v2 API that works with SessionRegistry
     /// Returns: (session_id, port, token, disconnect_rx)
     /// The disconnect_rx will receive the reason when the WebSocket connection ends
-    pub async fn start_extended_with_disconnect(
+    pub async fn start_extended_with_policy(
         session_handle: SshExtendedSessionHandle,
         scroll_buffer: Arc<ScrollBuffer>,
         replay_on_connect: bool,
+        policy: BridgePolicy,
     ) -> Result<(String, u16, String, oneshot::Receiver<DisconnectReason>), String> {
         // Generate time-bound authentication token to prevent local process hijacking
         let token = generate_token();
@@ -426,6 +508,7 @@ impl WsBridge {
             disconnect_tx,
             scroll_buffer,
             replay_on_connect,
+            policy,
         ));
 
         let _ = tokio::time::timeout(Duration::from_millis(500), ready_rx).await;
@@ -433,6 +516,29 @@ impl WsBridge {
         Ok((session_id, port, token, disconnect_rx))
     }
 
+    /// Wait for the frontend to connect and authenticate
+    ///
+    /// Other clients may connect in the meantime; they are turned away
+    /// without ending the wait (see `handshake`).
+    async fn accept(
+        listener: &TcpListener,
+        expected_token: &str,
+        policy: &BridgePolicy,
+        session_id: &str,
+    ) -> Result<(WsStream, std::net::SocketAddr), DisconnectReason> {
+        let result = accept_authenticated(
+            listener,
+            expected_token,
+            policy,
+            Duration::from_secs(WS_ACCEPT_TIMEOUT_SECS),
+        )
+        .await;
+        if let Err(DisconnectReason::AcceptTimeout) = result {
+            warn!("WebSocket accept timeout for session {}", session_id);
+        }
+        result
+    }
+
     /// Run the WebSocket server (legacy mode - backward compatible)
     async fn run_server(
         listener: TcpListener,
@@ -440,46 +546,37 @@ impl WsBridge {
         ready_tx: oneshot::Sender<()>,
         expected_token: String,
         scroll_buffer: Arc<ScrollBuffer>,
+        policy: BridgePolicy,
     ) {
         let session_id = session_handle.id.clone();
 
         // Signal that we're ready to accept connections
         let _ = ready_tx.send(());
 
-        // Accept only one connection per session (with timeout)
-        let accept_result = tokio::time::timeout(
-            Duration::from_secs(WS_ACCEPT_TIMEOUT_SECS),
-            listener.accept(),
-        )
-        .await;
-
-        match accept_result {
-            Ok(Ok((stream, addr))) => {
-                // Disable Nagle's algorithm for low-latency interactive terminal
-                if let Err(e) = stream.set_nodelay(true) {
-                    warn!("Failed to set TCP_NODELAY: {}", e);
-                }
+        // Attach only one authenticated connection per session (with timeout)
+        match Self::accept(&listener, &expected_token, &policy, &session_id).await {
+            Ok((ws_stream, addr)) => {
                 info!(
                     "WebSocket connection from {} for session {}",
                     addr, session_id
                 );
                 if let Err(e) = Self::handle_connection_v1(
-                    stream,
+                    ws_stream,
                     session_handle,
                     None,
-                    expected_token,
                     scroll_buffer,
+                    &policy,
                 )
                 .await
                 {
                     error!("WebSocket connection error: {}", e);
                 }
             }
-            Ok(Err(e)) => {
-                error!("Failed to accept WebSocket connection: {}", e);
-            }
-            Err(_) => {
-                warn!("WebSocket accept timeout for session {}", session_id);
+            Err(reason) => {
+                warn!(
+                    "WebSocket bridge for session {} not attached: {:?}",
+                    session_id, reason
+                );
             }
         }
 
@@ -493,44 +590,35 @@ impl WsBridge {
         ready_tx: oneshot::Sender<()>,
         expected_token: String,
         scroll_buffer: Arc<ScrollBuffer>,
+        policy: BridgePolicy,
     ) {
         let session_id = extended.handle.id.clone();
 
         let _ = ready_tx.send(());
 
-        let accept_result = tokio::time::timeout(
-            Duration::from_secs(WS_ACCEPT_TIMEOUT_SECS),
-            listener.accept(),
-        )
-        .await;
-
-        match accept_result {
-            Ok(Ok((stream, addr))) => {
-                // Disable Nagle's algorithm for low-latency interactive terminal
-                if let Err(e) = stream.set_nodelay(true) {
-                    warn!("Failed to set TCP_NODELAY: {}", e);
-                }
+        match Self::accept(&listener, &expected_token, &policy, &session_id).await {
+            Ok((ws_stream, addr)) => {
                 info!(
                     "WebSocket connection (extended) from {} for session {}",
                     addr, session_id
                 );
                 if let Err(e) = Self::handle_connection_v1(
-                    stream,
+                    ws_stream,
                     extended.handle,
                     Some(extended.resize_tx),
-                    expected_token,
                     scroll_buffer,
+                    &policy,
                 )
                 .await
                 {
                     error!("WebSocket connection error: {}", e);
                 }
             }
-            Ok(Err(e)) => {
-                error!("Failed to accept WebSocket connection: {}", e);
-            }
-            Err(_) => {
-                warn!("WebSocket accept timeout for session {}", session_id);
+            Err(reason) => {
+                warn!(
+                    "WebSocket bridge (extended) for session {} not attached: {:?}",
+                    session_id, reason
+                );
             }
         }
 
@@ -542,73 +630,22 @@ impl WsBridge {
 
     /// Handle a single WebSocket connection with v1 protocol
     async fn handle_connection_v1(
-        stream: TcpStream,
+        ws_stream: WsStream,
         session_handle: SessionHandle,
         resize_tx: Option<ResizeTx>,
-        expected_token: String,
         scroll_buffer: Arc<ScrollBuffer>,
+        policy: &BridgePolicy,
     ) -> Result<(), String> {
-        // Perform WebSocket handshake (no auth yet)
-        let ws_stream = accept_async(stream)
-            .await
-            .map_err(|e| format!("WebSocket handshake failed: {}", e))?;
-
-        let (ws_sender, mut ws_receiver) = ws_stream.split();
-
-        // Authenticate: expect first message to contain token
-        let auth_result = tokio::time::timeout(Duration::from_secs(5), ws_receiver.next()).await;
-
-        match auth_result {
-            Ok(Some(Ok(Message::Text(token)))) => {
-                if validate_token(&token, &expected_token) {
-                    debug!("WebSocket token authentication successful");
-                } else {
-                    error!("WebSocket token authentication failed: invalid or expired token");
-                    return Err("Authentication failed: invalid or expired token".to_string());
-                }
-            }
-            Ok(Some(Ok(Message::Binary(data)))) => {
-                let token = String::from_utf8_lossy(&data);
-                if validate_token(&token, &expected_token) {
-                    debug!("WebSocket token authentication successful (binary)");
-                } else {
-                    error!("WebSocket token authentication failed: invalid or expired token");
-                    return Err("Authentication failed: invalid or expired token".to_string());
-                }
-            }
-            Ok(Some(Err(e))) => {
-                error!("WebSocket error during authentication: {}", e);
-                return Err(format!("Authentication failed: {}", e));
-            }
-            Ok(None) => {
-                error!("WebSocket closed before authentication");
-                return Err("Authentication failed: connection closed".to_string());
-            }
-            Err(_) => {
-                error!("WebSocket authentication timeout");
-                return Err("Authentication failed: timeout".to_string());
-            }
-            _ => {
-                error!("WebSocket authentication failed: unexpected message type");
-                return Err("Authentication failed: unexpected message".to_string());
-            }
-        }
-
-        // Reunite the split stream for further processing
-        let ws_stream = ws_sender
-            .reunite(ws_receiver)
-            .map_err(|e| format!("Failed to reunite WebSocket stream: {}", e))?;
-
         debug!(
-            "WebSocket handshake and authentication completed for session {}",
+            "WebSocket connection attached for session {}",
             session_handle.id
         );
 
         let (mut ws_sender, mut ws_receiver) = ws_stream.split();
 
         // 发送 scroll_buffer 中最近的历史行给新连接的客户端
-        // 只发送最近 REPLAY_LINE_COUNT 行，避免克隆整个缓冲区浪费内存
-        let history_lines = scroll_buffer.tail_lines(REPLAY_LINE_COUNT).await;
+        // 只发送最近 policy.replay_lines 行，避免克隆整个缓冲区浪费内存
+        let history_lines = scroll_buffer.tail_lines(policy.replay_lines).await;
         if !history_lines.is_empty() {
             debug!(
                 "Sending {} history lines to reconnected client for session {}",
@@ -692,45 +729,25 @@ impl WsBridge {
         });
 
         // Task: Heartbeat sender
-        let sid_hb = id.clone();
-        let mut heartbeat_task = tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_secs(HEARTBEAT_INTERVAL_SECS));
-            loop {
-                interval.tick().await;
-
-                // Check for timeout
-                let now_millis = std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis() as u64;
-                let last = state_hb.last_seen_millis();
-                let elapsed_secs = (now_millis - last) / 1000;
-
-                if elapsed_secs > HEARTBEAT_TIMEOUT_SECS {
-                    warn!(
-                        "Heartbeat timeout for session {} ({}s since last activity)",
-                        sid_hb, elapsed_secs
-                    );
-                    // Send error frame before closing
-                    let err = error_frame("Connection timeout - no heartbeat response");
-                    let _ = frame_tx_hb.send(err.encode()).await;
-                    break;
-                }
+        let mut heartbeat_task = tokio::spawn(heartbeat_loop(
+            state_hb,
+            frame_tx_hb,
+            policy.heartbeat_timeout,
+            id.clone(),
+        ));
 
-                // Send heartbeat (non-blocking to avoid backpressure)
-                let seq = state_hb.next_seq();
-                let frame = heartbeat_frame(seq);
-                if frame_tx_hb.try_send(frame.encode()).is_err() {
-                    // Channel full means frontend is overloaded - abort heartbeat
-                    debug!(
-                        "Heartbeat channel full, terminating heartbeat task for session {}",
-                        sid_hb
-                    );
-                    break;
-                }
-                debug!("Sent heartbeat seq={} for session {}", seq, sid_hb);
-            }
-            debug!("Heartbeat task stopped for session {}", sid_hb);
+        // Coalesce resize bursts before they reach the PTY. The coalescer exits
+        // (after flushing the last size) once the input task drops its sender.
+        let resize_tx = resize_tx.map(|tx| {
+            let (coalesced_tx, _) = spawn_resize_coalescer(
+                Duration::from_millis(RESIZE_DEBOUNCE_MS),
+                Duration::from_millis(RESIZE_MAX_WAIT_MS),
+                move |cols, rows| {
+                    let tx = tx.clone();
+                    async move { tx.send((cols, rows)).await.is_ok() }
+                },
+            );
+            coalesced_tx
         });
 
         // Task: Process incoming WebSocket messages
@@ -751,12 +768,18 @@ impl WsBridge {
                         loop {
                             match codec.decode_next() {
                                 Ok(Some(frame)) => {
+                                    if let Some((expected, received)) = codec.detect_gap() {
+                                        warn!(
+                                            "Input sequence gap for session {}: expected {}, got {}",
+                                            sid_in, expected, received
+                                        );
+                                    }
                                     match frame {
                                         Frame::Data(payload) => {
                                             // Forward to SSH stdin
                                             if stdin_tx.send(payload.to_vec()).await.is_err() {
                                                 debug!("SSH stdin channel closed");
-                                                return;
+                                                return false;
                                             }
                                         }
                                         Frame::Resize { cols, rows } => {
@@ -775,6 +798,8 @@ impl WsBridge {
                                             );
                                             // Heartbeat response received - connection is alive
                                         }
+                                        // Flattened / unwrapped into Data by FrameCodec::decode_next
+                                        Frame::Batch(_) | Frame::DataSeq { .. } => {}
                                         Frame::Error(msg) => {
                                             warn!(
                                                 "Received error frame from client: {} for session {}",
@@ -787,6 +812,11 @@ impl WsBridge {
                                     // Need more data
                                     break;
                                 }
+                                Err(e) if is_oversized_frame(&e) => {
+                                    error!("Fatal protocol error: {} for session {}", e, sid_in);
+                                    let _ = frame_tx.send(protocol_error_frame(&e)).await;
+                                    return true;
+                                }
                                 Err(e) => {
                                     warn!("Protocol decode error: {} for session {}", e, sid_in);
                                     // For backward compatibility, treat as raw data
@@ -795,7 +825,7 @@ impl WsBridge {
                                         // Early in connection, might be legacy client
                                         debug!("Falling back to raw mode for legacy client");
                                         if stdin_tx.send(data.clone()).await.is_err() {
-                                            return;
+                                            return false;
                                         }
                                     }
                                     codec.clear();
@@ -834,6 +864,7 @@ impl WsBridge {
                 }
             }
             debug!("WS -> SSH forwarder stopped for session {}", sid_in);
+            false
         });
 
         // Wait for any task to complete
@@ -847,8 +878,13 @@ impl WsBridge {
             _ = &mut heartbeat_task => {
                 debug!("Heartbeat task completed for session {}", id);
             }
-            _ = &mut input_task => {
+            result = &mut input_task => {
                 debug!("Input task completed for session {}", id);
+                if matches!(result, Ok(true)) {
+                    ssh_out_task.abort();
+                    heartbeat_task.abort();
+                    flush_sender(&mut sender_task).await;
+                }
             }
         }
 
@@ -870,44 +906,35 @@ impl WsBridge {
         expected_token: String,
         scroll_buffer: Arc<ScrollBuffer>,
         replay_on_connect: bool,
+        policy: BridgePolicy,
     ) {
         let session_id = session_handle.id.clone();
 
         let _ = ready_tx.send(());
 
-        let accept_result = tokio::time::timeout(
-            Duration::from_secs(WS_ACCEPT_TIMEOUT_SECS),
-            listener.accept(),
-        )
-        .await;
-
-        match accept_result {
-            Ok(Ok((stream, addr))) => {
-                // Disable Nagle's algorithm for low-latency interactive terminal
-                if let Err(e) = stream.set_nodelay(true) {
-                    warn!("Failed to set TCP_NODELAY: {}", e);
-                }
+        match Self::accept(&listener, &expected_token, &policy, &session_id).await {
+            Ok((ws_stream, addr)) => {
                 info!(
                     "WebSocket connection (v2) from {} for session {}",
                     addr, session_id
                 );
                 if let Err(e) = Self::handle_connection_v2(
-                    stream,
+                    ws_stream,
                     session_handle,
-                    expected_token,
                     scroll_buffer,
                     replay_on_connect,
+                    &policy,
                 )
                 .await
                 {
                     error!("WebSocket connection error: {}", e);
                 }
             }
-            Ok(Err(e)) => {
-                error!("Failed to accept WebSocket connection: {}", e);
-            }
-            Err(_) => {
-                warn!("WebSocket accept timeout for session {}", session_id);
+            Err(reason) => {
+                warn!(
+                    "WebSocket bridge (v2) for session {} not attached: {:?}",
+                    session_id, reason
+                );
             }
         }
 
@@ -923,56 +950,37 @@ impl WsBridge {
         disconnect_tx: oneshot::Sender<DisconnectReason>,
         scroll_buffer: Arc<ScrollBuffer>,
         replay_on_connect: bool,
+        policy: BridgePolicy,
     ) {
         let session_id = session_handle.id.clone();
 
         let _ = ready_tx.send(());
 
-        let accept_result = tokio::time::timeout(
-            Duration::from_secs(WS_ACCEPT_TIMEOUT_SECS),
-            listener.accept(),
-        )
-        .await;
-
-        let disconnect_reason = match accept_result {
-            Ok(Ok((stream, addr))) => {
-                // Disable Nagle's algorithm for low-latency interactive terminal
-                if let Err(e) = stream.set_nodelay(true) {
-                    warn!("Failed to set TCP_NODELAY: {}", e);
-                }
-                info!(
-                    "WebSocket connection (v2+disconnect) from {} for session {}",
-                    addr, session_id
-                );
-                match Self::handle_connection_v2_with_disconnect(
-                    stream,
-                    session_handle,
-                    expected_token,
-                    scroll_buffer,
-                    replay_on_connect,
-                )
-                .await
-                {
-                    Ok(reason) => reason,
-                    Err(e) => {
-                        error!("WebSocket connection error: {}", e);
-                        if e.contains("Authentication") {
-                            DisconnectReason::AuthFailed
-                        } else {
+        let disconnect_reason =
+            match Self::accept(&listener, &expected_token, &policy, &session_id).await {
+                Ok((ws_stream, addr)) => {
+                    info!(
+                        "WebSocket connection (v2+disconnect) from {} for session {}",
+                        addr, session_id
+                    );
+                    match Self::handle_connection_v2_with_disconnect(
+                        ws_stream,
+                        session_handle,
+                        scroll_buffer,
+                        replay_on_connect,
+                        &policy,
+                    )
+                    .await
+                    {
+                        Ok(reason) => reason,
+                        Err(e) => {
+                            error!("WebSocket connection error: {}", e);
                             DisconnectReason::NetworkError(e)
                         }
                     }
                 }
-            }
-            Ok(Err(e)) => {
-                error!("Failed to accept WebSocket connection: {}", e);
-                DisconnectReason::NetworkError(e.to_string())
-            }
-            Err(_) => {
-                warn!("WebSocket accept timeout for session {}", session_id);
-                DisconnectReason::AcceptTimeout
-            }
-        };
+                Err(reason) => reason,
+            };
 
         info!(
             "WebSocket server (v2+disconnect) stopped for session {}: {:?}",
@@ -985,65 +993,14 @@ impl WsBridge {
 
     /// Handle connection with v2 protocol (uses SessionCommand)
     async fn handle_connection_v2(
-        stream: TcpStream,
+        ws_stream: WsStream,
         session_handle: SshExtendedSessionHandle,
-        expected_token: String,
         scroll_buffer: Arc<ScrollBuffer>,
         replay_on_connect: bool,
+        policy: &BridgePolicy,
     ) -> Result<(), String> {
-        // Perform WebSocket handshake (no auth yet)
-        let ws_stream = accept_async(stream)
-            .await
-            .map_err(|e| format!("WebSocket handshake failed: {}", e))?;
-
-        let (ws_sender, mut ws_receiver) = ws_stream.split();
-
-        // Authenticate: expect first message to contain token
-        let auth_result = tokio::time::timeout(Duration::from_secs(5), ws_receiver.next()).await;
-
-        match auth_result {
-            Ok(Some(Ok(Message::Text(token)))) => {
-                if validate_token(&token, &expected_token) {
-                    debug!("WebSocket token authentication successful (v2))");
-                } else {
-                    error!("WebSocket token authentication failed (v2): invalid or expired token");
-                    return Err("Authentication failed: invalid or expired token".to_string());
-                }
-            }
-            Ok(Some(Ok(Message::Binary(data)))) => {
-                let token = String::from_utf8_lossy(&data);
-                if validate_token(&token, &expected_token) {
-                    debug!("WebSocket token authentication successful (v2, binary)");
-                } else {
-                    error!("WebSocket token authentication failed (v2): invalid or expired token");
-                    return Err("Authentication failed: invalid or expired token".to_string());
-                }
-            }
-            Ok(Some(Err(e))) => {
-                error!("WebSocket error during authentication (v2): {}", e);
-                return Err(format!("Authentication failed: {}", e));
-            }
-            Ok(None) => {
-                error!("WebSocket closed before authentication (v2)");
-                return Err("Authentication failed: connection closed".to_string());
-            }
-            Err(_) => {
-                error!("WebSocket authentication timeout (v2)");
-                return Err("Authentication failed: timeout".to_string());
-            }
-            _ => {
-                error!("WebSocket authentication failed (v2): unexpected message type");
-                return Err("Authentication failed: unexpected message".to_string());
-            }
-        }
-
-        // Reunite the split stream for further processing
-        let ws_stream = ws_sender
-            .reunite(ws_receiver)
-            .map_err(|e| format!("Failed to reunite WebSocket stream: {}", e))?;
-
         debug!(
-            "WebSocket handshake (v2) completed for session {}",
+            "WebSocket connection (v2) attached for session {}",
             session_handle.id
         );
 
@@ -1052,7 +1009,8 @@ impl WsBridge {
         let (id, cmd_tx, mut stdout_rx) = session_handle.into_parts();
 
         if replay_on_connect {
-            if let Ok(replay) = build_replay_frame(scroll_buffer.clone()).await {
+            if let Ok(replay) = build_replay_frame(scroll_buffer.clone(), policy.replay_lines).await
+            {
                 if !replay.is_empty() {
                     let _ = ws_sender.send(Message::Binary(replay)).await;
                 }
@@ -1100,10 +1058,16 @@ impl WsBridge {
             debug!("WebSocket sender task stopped");
         });
 
+        let paste_guard_out = policy.paste_guard.clone();
+        let paste_guard_in = policy.paste_guard.clone();
+
         // Task: SSH stdout -> WebSocket
         let mut ssh_out_task = tokio::spawn(async move {
             while let Ok(data) = stdout_rx.recv().await {
                 state_out.touch();
+                if let Some(guard) = &paste_guard_out {
+                    guard.observe_output(&data);
+                }
 
                 // Forward to WebSocket
                 let frame = data_frame(Bytes::from(data)).encode();
@@ -1115,32 +1079,28 @@ impl WsBridge {
         });
 
         // Task: Heartbeat sender
-        let mut heartbeat_task = tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_secs(HEARTBEAT_INTERVAL_SECS));
-            loop {
-                interval.tick().await;
-
-                // Check if connection is dead
-                let now = std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis() as u64;
-                let last = state_hb.last_seen_millis();
-                if now.saturating_sub(last) > HEARTBEAT_TIMEOUT_SECS * 1000 {
-                    warn!("Heartbeat timeout detected");
-                    break;
-                }
+        let mut heartbeat_task = tokio::spawn(heartbeat_loop(
+            state_hb,
+            frame_tx_hb,
+            policy.heartbeat_timeout,
+            id.clone(),
+        ));
 
-                let seq = state_hb.next_seq();
-                let frame = heartbeat_frame(seq).encode();
-                if frame_tx_hb.try_send(frame).is_err() {
-                    // Channel full means frontend is overloaded - abort heartbeat
-                    debug!("Heartbeat channel full, terminating heartbeat task");
-                    break;
+        // Coalesce resize bursts into a single window-change
+        let cmd_tx_resize = cmd_tx.clone();
+        let (resize_tx, _) = spawn_resize_coalescer(
+            Duration::from_millis(RESIZE_DEBOUNCE_MS),
+            Duration::from_millis(RESIZE_MAX_WAIT_MS),
+            move |cols, rows| {
+                let cmd_tx = cmd_tx_resize.clone();
+                async move {
+                    cmd_tx
+                        .send(SessionCommand::Resize(cols, rows))
+                        .await
+                        .is_ok()
                 }
-            }
-            debug!("Heartbeat task stopped");
-        });
+            },
+        );
 
         // Task: WebSocket -> SSH (uses cmd_tx with SessionCommand)
         let cmd_tx_clone = cmd_tx.clone();
@@ -1154,16 +1114,42 @@ impl WsBridge {
                         state.touch();
                         codec.feed(&data);
 
-                        while let Ok(Some(frame)) = codec.decode_next() {
+                        loop {
+                            let frame = match codec.decode_next() {
+                                Ok(Some(frame)) => frame,
+                                Ok(None) => break,
+                                Err(e) if is_oversized_frame(&e) => {
+                                    error!("Fatal protocol error: {} for session {}", e, sid_in);
+                                    let _ = frame_tx.send(protocol_error_frame(&e)).await;
+                                    return true;
+                                }
+                                Err(e) => {
+                                    warn!("Protocol decode error: {} for session {}", e, sid_in);
+                                    break;
+                                }
+                            };
+                            if let Some((expected, received)) = codec.detect_gap() {
+                                warn!(
+                                    "Input sequence gap for session {}: expected {}, got {}",
+                                    sid_in, expected, received
+                                );
+                            }
                             match frame {
                                 Frame::Data(payload) => {
+                                    if paste_guard_in
+                                        .as_ref()
+                                        .is_some_and(|guard| guard.intercept(&payload))
+                                    {
+                                        debug!("Holding multi-line paste for session {}", sid_in);
+                                        continue;
+                                    }
                                     if cmd_tx_clone
                                         .send(SessionCommand::Data(payload.to_vec()))
                                         .await
                                         .is_err()
                                     {
                                         debug!("SSH cmd channel closed");
-                                        return;
+                                        return false;
                                     }
                                 }
                                 Frame::Resize { cols, rows } => {
@@ -1171,18 +1157,16 @@ impl WsBridge {
                                         "Resize request: {}x{} for session {}",
                                         cols, rows, sid_in
                                     );
-                                    if cmd_tx_clone
-                                        .send(SessionCommand::Resize(cols, rows))
-                                        .await
-                                        .is_err()
-                                    {
+                                    if resize_tx.send((cols, rows)).await.is_err() {
                                         debug!("SSH cmd channel closed");
-                                        return;
+                                        return false;
                                     }
                                 }
                                 Frame::Heartbeat(seq) => {
                                     debug!("Received heartbeat echo: seq={}", seq);
                                 }
+                                // Flattened / unwrapped into Data by FrameCodec::decode_next
+                                Frame::Batch(_) | Frame::DataSeq { .. } => {}
                                 Frame::Error(msg) => {
                                     error!("Error frame from client: {}", msg);
                                 }
@@ -1197,7 +1181,7 @@ impl WsBridge {
                                     .await
                                     .is_err()
                                 {
-                                    return;
+                                    return false;
                                 }
                             }
                             codec.clear();
@@ -1231,6 +1215,7 @@ impl WsBridge {
                 }
             }
             debug!("WS -> SSH forwarder (v2) stopped for session {}", sid_in);
+            false
         });
 
         // Wait for any task to complete
@@ -1244,8 +1229,13 @@ impl WsBridge {
             _ = &mut heartbeat_task => {
                 debug!("Heartbeat task completed for session {}", id);
             }
-            _ = &mut input_task => {
+            result = &mut input_task => {
                 debug!("Input task completed for session {}", id);
+                if matches!(result, Ok(true)) {
+                    ssh_out_task.abort();
+                    heartbeat_task.abort();
+                    flush_sender(&mut sender_task).await;
+                }
             }
         }
 
@@ -1261,65 +1251,14 @@ impl WsBridge {
 
     /// Handle connection with v2 protocol and return disconnect reason
     async fn handle_connection_v2_with_disconnect(
-        stream: TcpStream,
+        ws_stream: WsStream,
         session_handle: SshExtendedSessionHandle,
-        expected_token: String,
         scroll_buffer: Arc<ScrollBuffer>,
         replay_on_connect: bool,
+        policy: &BridgePolicy,
     ) -> Result<DisconnectReason, String> {
-        // Perform WebSocket handshake (no auth yet)
-        let ws_stream = accept_async(stream)
-            .await
-            .map_err(|e| format!("WebSocket handshake failed: {}", e))?;
-
-        let (ws_sender, mut ws_receiver) = ws_stream.split();
-
-        // Authenticate: expect first message to contain token
-        let auth_result = tokio::time::timeout(Duration::from_secs(5), ws_receiver.next()).await;
-
-        match auth_result {
-            Ok(Some(Ok(Message::Text(token)))) => {
-                if validate_token(&token, &expected_token) {
-                    debug!("WebSocket token authentication successful (v2+disconnect)");
-                } else {
-                    error!("WebSocket token authentication failed: invalid or expired token");
-                    return Err("Authentication failed: invalid or expired token".to_string());
-                }
-            }
-            Ok(Some(Ok(Message::Binary(data)))) => {
-                let token = String::from_utf8_lossy(&data);
-                if validate_token(&token, &expected_token) {
-                    debug!("WebSocket token authentication successful (v2+disconnect, binary)");
-                } else {
-                    error!("WebSocket token authentication failed: invalid or expired token");
-                    return Err("Authentication failed: invalid or expired token".to_string());
-                }
-            }
-            Ok(Some(Err(e))) => {
-                error!("WebSocket error during authentication: {}", e);
-                return Err(format!("Authentication failed: {}", e));
-            }
-            Ok(None) => {
-                error!("WebSocket closed before authentication");
-                return Err("Authentication failed: connection closed".to_string());
-            }
-            Err(_) => {
-                error!("WebSocket authentication timeout");
-                return Err("Authentication failed: timeout".to_string());
-            }
-            _ => {
-                error!("WebSocket authentication failed: unexpected message type");
-                return Err("Authentication failed: unexpected message".to_string());
-            }
-        }
-
-        // Reunite the split stream for further processing
-        let ws_stream = ws_sender
-            .reunite(ws_receiver)
-            .map_err(|e| format!("Failed to reunite WebSocket stream: {}", e))?;
-
         debug!(
-            "WebSocket handshake (v2+disconnect) completed for session {}",
+            "WebSocket connection (v2+disconnect) attached for session {}",
             session_handle.id
         );
 
@@ -1327,7 +1266,8 @@ impl WsBridge {
         let (id, cmd_tx, mut stdout_rx) = session_handle.into_parts();
 
         if replay_on_connect {
-            if let Ok(replay) = build_replay_frame(scroll_buffer.clone()).await {
+            if let Ok(replay) = build_replay_frame(scroll_buffer.clone(), policy.replay_lines).await
+            {
                 if !replay.is_empty() {
                     let _ = ws_sender.send(Message::Binary(replay)).await;
                 }
@@ -1369,10 +1309,16 @@ impl WsBridge {
             "channel_closed"
         });
 
+        let paste_guard_out = policy.paste_guard.clone();
+        let paste_guard_in = policy.paste_guard.clone();
+
         // Task: SSH stdout -> WebSocket
         let mut ssh_out_task = tokio::spawn(async move {
             while let Ok(data) = stdout_rx.recv().await {
                 state_out.touch();
+                if let Some(guard) = &paste_guard_out {
+                    guard.observe_output(&data);
+                }
 
                 // Forward to WebSocket
                 let frame = data_frame(Bytes::from(data)).encode();
@@ -1385,29 +1331,28 @@ impl WsBridge {
         });
 
         // Task: Heartbeat sender - returns reason if timeout
-        let mut heartbeat_task = tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_secs(HEARTBEAT_INTERVAL_SECS));
-            loop {
-                interval.tick().await;
-
-                let now = std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis() as u64;
-                let last = state_hb.last_seen_millis();
-                if now.saturating_sub(last) > HEARTBEAT_TIMEOUT_SECS * 1000 {
-                    warn!("Heartbeat timeout detected for session");
-                    return "heartbeat_timeout";
-                }
+        let mut heartbeat_task = tokio::spawn(heartbeat_loop(
+            state_hb,
+            frame_tx_hb,
+            policy.heartbeat_timeout,
+            id.clone(),
+        ));
 
-                let seq = state_hb.next_seq();
-                let frame = heartbeat_frame(seq).encode();
-                if frame_tx_hb.try_send(frame).is_err() {
-                    debug!("Heartbeat channel full");
-                    return "channel_full";
+        // Coalesce resize bursts into a single window-change
+        let cmd_tx_resize = cmd_tx.clone();
+        let (resize_tx, _) = spawn_resize_coalescer(
+            Duration::from_millis(RESIZE_DEBOUNCE_MS),
+            Duration::from_millis(RESIZE_MAX_WAIT_MS),
+            move |cols, rows| {
+                let cmd_tx = cmd_tx_resize.clone();
+                async move {
+                    cmd_tx
+                        .send(SessionCommand::Resize(cols, rows))
+                        .await
+                        .is_ok()
                 }
-            }
-        });
+            },
+        );
 
         // Task: WebSocket -> SSH
         let cmd_tx_clone = cmd_tx.clone();
@@ -1421,9 +1366,35 @@ impl WsBridge {
                         state.touch();
                         codec.feed(&data);
 
-                        while let Ok(Some(frame)) = codec.decode_next() {
+                        loop {
+                            let frame = match codec.decode_next() {
+                                Ok(Some(frame)) => frame,
+                                Ok(None) => break,
+                                Err(e) if is_oversized_frame(&e) => {
+                                    error!("Fatal protocol error: {} for session {}", e, sid_in);
+                                    let _ = frame_tx.send(protocol_error_frame(&e)).await;
+                                    return "protocol_error";
+                                }
+                                Err(e) => {
+                                    warn!("Protocol decode error: {} for session {}", e, sid_in);
+                                    break;
+                                }
+                            };
+                            if let Some((expected, received)) = codec.detect_gap() {
+                                warn!(
+                                    "Input sequence gap for session {}: expected {}, got {}",
+                                    sid_in, expected, received
+                                );
+                            }
                             match frame {
                                 Frame::Data(payload) => {
+                                    if paste_guard_in
+                                        .as_ref()
+                                        .is_some_and(|guard| guard.intercept(&payload))
+                                    {
+                                        debug!("Holding multi-line paste for session {}", sid_in);
+                                        continue;
+                                    }
                                     if cmd_tx_clone
                                         .send(SessionCommand::Data(payload.to_vec()))
                                         .await
@@ -1434,17 +1405,15 @@ impl WsBridge {
                                 }
                                 Frame::Resize { cols, rows } => {
                                     info!("Resize: {}x{} for session {}", cols, rows, sid_in);
-                                    if cmd_tx_clone
-                                        .send(SessionCommand::Resize(cols, rows))
-                                        .await
-                                        .is_err()
-                                    {
+                                    if resize_tx.send((cols, rows)).await.is_err() {
                                         return "ssh_closed";
                                     }
                                 }
                                 Frame::Heartbeat(seq) => {
                                     debug!("Received heartbeat echo: seq={}", seq);
                                 }
+                                // Flattened / unwrapped into Data by FrameCodec::decode_next
+                                Frame::Batch(_) | Frame::DataSeq { .. } => {}
                                 Frame::Error(msg) => {
                                     error!("Error frame from client: {}", msg);
                                 }
@@ -1500,6 +1469,12 @@ impl WsBridge {
             result = &mut input_task => result.unwrap_or("client_closed"),
         };
 
+        if reason_str == "protocol_error" {
+            ssh_out_task.abort();
+            heartbeat_task.abort();
+            flush_sender(&mut sender_task).await;
+        }
+
         // Abort remaining tasks to prevent zombie tokio tasks
         sender_task.abort();
         ssh_out_task.abort();
@@ -1513,6 +1488,7 @@ impl WsBridge {
             "network_error" | "send_timeout" => {
                 DisconnectReason::NetworkError(reason_str.to_string())
             }
+            "protocol_error" => DisconnectReason::ProtocolError("oversized frame".to_string()),
             "channel_full" => {
                 // Channel full means server is overloaded, not client disconnect
                 DisconnectReason::NetworkError("server_overloaded".to_string())
@@ -1532,3 +1508,71 @@ impl WsBridge {
         Ok(disconnect_reason)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::BytesMut;
+
+    fn decode(frame: Bytes) -> Frame {
+        Frame::decode(&mut BytesMut::from(&frame[..]))
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_times_out_when_peer_stops_responding() {
+        let state = Arc::new(ConnectionState::new());
+        let (frame_tx, mut frame_rx) = mpsc::channel::<Bytes>(64);
+        let timeout = Duration::from_millis(200);
+        let heartbeat = tokio::spawn(heartbeat_loop(
+            state.clone(),
+            frame_tx,
+            timeout,
+            "test".to_string(),
+        ));
+
+        // Mock peer: echoes heartbeats for a while, well past the timeout...
+        let started = Instant::now();
+        let mut seq = 0;
+        while started.elapsed() < timeout * 3 {
+            match decode(frame_rx.recv().await.unwrap()) {
+                Frame::Heartbeat(received) => seq = received,
+                other => panic!("unexpected frame {:?}", other),
+            }
+            state.touch();
+        }
+        assert!(!heartbeat.is_finished());
+        assert!(seq > 0);
+
+        // ...then goes quiet
+        let went_quiet = Instant::now();
+        let reason = tokio::time::timeout(Duration::from_secs(5), heartbeat)
+            .await
+            .expect("heartbeat timeout should fire")
+            .unwrap();
+        assert_eq!(reason, "heartbeat_timeout");
+        assert!(went_quiet.elapsed() >= timeout);
+
+        // The last frame tells a stalled frontend why it was dropped
+        let mut last = None;
+        while let Ok(frame) = frame_rx.try_recv() {
+            last = Some(decode(frame));
+        }
+        assert!(matches!(last, Some(Frame::Error(_))));
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_stops_when_channel_is_full() {
+        let state = Arc::new(ConnectionState::new());
+        let (frame_tx, _frame_rx) = mpsc::channel::<Bytes>(1);
+        let reason = heartbeat_loop(
+            state,
+            frame_tx,
+            Duration::from_millis(200),
+            "test".to_string(),
+        )
+        .await;
+        assert_eq!(reason, "channel_full");
+    }
+}
@@ -1,25 +1,42 @@
 //! Configuration Commands
 //!
-//! Tauri commands for managing saved connections and SSH config import.
+//! Tauri commands for managing saved connections and SSH config import/export.
 
+use super::session_tree::SessionTreeState;
+use super::{CommandError, ErrorCode};
 use crate::config::{
-    default_ssh_config_path, parse_ssh_config, AiProviderVault, ConfigFile, ConfigStorage,
-    Keychain, KeychainError, ProxyHopConfig, SavedAuth, SavedConnection, SshConfigHost,
+    config_dir, consolidate_credentials, default_ssh_config_path, filter_importable_hosts,
+    parse_ssh_config_blocks, resolve_host, to_ssh_config, validate_connection, AiProviderVault,
+    CheckKind, ConfigFile, ConfigStorage, ConnectionCheck, ConnectionOptions, ConnectionSnippet,
+    ConsolidationReport, CredentialStore, FileSecretBackend, Keychain, KeychainError, ProbeRoute,
+    ProxyHopConfig, RotationReport, SavedAuth, SavedConnection, SnippetCredential, SshConfigHost,
+    AI_KEYCHAIN_SERVICE, SSH_KEYCHAIN_SERVICE,
 };
+use crate::session::SessionConfig;
+use crate::ssh::certificate::{check_certificate_file, CertStatus, DEFAULT_EXPIRY_WARNING_SECS};
+use crate::ssh::{AuthMethod, ServerBanner, SshConnectionRegistry};
+use crate::state::StateStore;
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use std::time::Instant;
 use tauri::{Manager, State};
 
-/// Service name for AI provider API keys in system keychain
-const AI_KEYCHAIN_SERVICE: &str = "com.oxideterm.ai";
-
 /// Shared config state
 pub struct ConfigState {
     storage: ConfigStorage,
     config: RwLock<ConfigFile>,
-    keychain: Keychain,
-    ai_keychain: Keychain,
+    /// SSH passwords and passphrases
+    keychain: Arc<dyn CredentialStore>,
+    /// AI provider API keys
+    ai_keychain: Arc<dyn CredentialStore>,
+    /// OS keychain handle for switching to the file fallback
+    /// (`None` when the stores were injected)
+    system_keychain: Option<Keychain>,
+    /// AI keychain handle for rotation (`None` when the stores were injected)
+    system_ai_keychain: Option<Keychain>,
+    /// Result of the startup probe of the OS keychain
+    system_keychain_available: bool,
 }
 
 impl ConfigState {
@@ -28,14 +45,75 @@ impl ConfigState {
         let storage = ConfigStorage::new().map_err(|e| e.to_string())?;
         let config = storage.load().await.map_err(|e| e.to_string())?;
 
+        // Both keychains share a backend so the file fallback covers AI keys too
+        let keychain = Keychain::with_service(
+            config
+                .ssh_keychain_service
+                .as_deref()
+                .unwrap_or(SSH_KEYCHAIN_SERVICE),
+        );
+        let ai_keychain = keychain.for_service(
+            config
+                .ai_keychain_service
+                .as_deref()
+                .unwrap_or(AI_KEYCHAIN_SERVICE),
+        );
+
+        let system_keychain_available = keychain.is_available();
+        if !system_keychain_available {
+            // Only reuse the file fallback if the user enabled it before
+            let fallback_dir = config_dir()
+                .ok()
+                .filter(|dir| FileSecretBackend::exists(dir));
+            match fallback_dir.map(|dir| FileSecretBackend::open(&dir)) {
+                Some(Ok(backend)) => {
+                    keychain.set_backend(Arc::new(backend));
+                    tracing::warn!("System keychain unavailable, using encrypted file storage");
+                }
+                Some(Err(e)) => {
+                    tracing::error!(
+                        "System keychain unavailable and file storage failed to open: {}",
+                        e
+                    );
+                }
+                None => {
+                    tracing::warn!(
+                        "System keychain unavailable: saved passwords and API keys cannot be \
+                         stored until file-based storage is enabled"
+                    );
+                }
+            }
+        }
+
         Ok(Self {
             storage,
             config: RwLock::new(config),
-            keychain: Keychain::new(),
-            ai_keychain: Keychain::with_service(AI_KEYCHAIN_SERVICE),
+            keychain: Arc::new(keychain.clone()),
+            ai_keychain: Arc::new(ai_keychain.clone()),
+            system_keychain: Some(keychain),
+            system_ai_keychain: Some(ai_keychain),
+            system_keychain_available,
         })
     }
 
+    /// Create config state with injected credential stores (for tests)
+    pub fn with_credential_stores(
+        storage: ConfigStorage,
+        config: ConfigFile,
+        keychain: Arc<dyn CredentialStore>,
+        ai_keychain: Arc<dyn CredentialStore>,
+    ) -> Self {
+        Self {
+            storage,
+            config: RwLock::new(config),
+            keychain,
+            ai_keychain,
+            system_keychain: None,
+            system_ai_keychain: None,
+            system_keychain_available: true,
+        }
+    }
+
     /// Save config to disk
     async fn save(&self) -> Result<(), String> {
         let config = self.config.read().clone();
@@ -62,6 +140,15 @@ impl ConfigState {
         self.keychain.get(key).map_err(|e| e.to_string())
     }
 
+    /// Public API: Get value from keychain, `None` if nothing is stored
+    pub fn lookup_keychain_value(&self, key: &str) -> Result<Option<String>, String> {
+        match self.keychain.get(key) {
+            Ok(value) => Ok(Some(value)),
+            Err(KeychainError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
     /// Public API: Store value in keychain
     pub fn set_keychain_value(&self, key: &str, value: &str) -> Result<(), String> {
         self.keychain.store(key, value).map_err(|e| e.to_string())
@@ -105,6 +192,35 @@ pub struct ConnectionInfo {
     pub tags: Vec<String>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub proxy_chain: Vec<ProxyHopInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_command: Option<String>,
+    #[serde(default)]
+    pub idle_timeout_secs: u64,
+}
+
+/// Treat blank or `none` ProxyCommand values (OpenSSH's opt-out) as unset
+fn normalize_proxy_command(command: Option<String>) -> Option<String> {
+    command
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty() && !c.eq_ignore_ascii_case("none"))
+}
+
+/// Apply the optional fields of an update to a saved connection's options
+///
+/// Fields the client left out keep their saved value, so older callers that
+/// don't know about them can't erase them. An empty (or `none`)
+/// ProxyCommand clears it.
+fn update_connection_options(
+    options: &mut ConnectionOptions,
+    proxy_command: Option<String>,
+    idle_timeout_secs: Option<u64>,
+) {
+    if let Some(command) = proxy_command {
+        options.proxy_command = normalize_proxy_command(Some(command));
+    }
+    if let Some(secs) = idle_timeout_secs {
+        options.idle_timeout_secs = secs;
+    }
 }
 
 /// Helper to convert SavedAuth to (auth_type, key_path) tuple
@@ -153,6 +269,8 @@ impl From<&SavedConnection> for ConnectionInfo {
             color: conn.color.clone(),
             tags: conn.tags.clone(),
             proxy_chain,
+            proxy_command: conn.options.proxy_command.clone(),
+            idle_timeout_secs: conn.options.idle_timeout_secs,
         }
     }
 }
@@ -173,6 +291,22 @@ pub struct SaveConnectionRequest {
     pub tags: Vec<String>,
     pub jump_host: Option<String>, // Legacy jump host for backward compatibility
     pub proxy_chain: Option<Vec<ProxyHopRequest>>, // Multi-hop proxy chain
+    #[serde(default)]
+    pub proxy_command: Option<String>, // None = keep existing, "" = clear
+    #[serde(default)]
+    pub idle_timeout_secs: Option<u64>, // None = keep existing, 0 = disabled
+}
+
+/// Request for `config_import_connection_snippet`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImportSnippetRequest {
+    pub snippet: String,
+    /// Fresh credentials for the target (agent auth needs none)
+    #[serde(default)]
+    pub credentials: SnippetCredential,
+    /// Fresh credentials for each proxy hop, in chain order
+    #[serde(default)]
+    pub hop_credentials: Vec<SnippetCredential>,
 }
 
 /// Request for a single proxy hop in the chain
@@ -187,6 +321,28 @@ pub struct ProxyHopRequest {
     pub passphrase: Option<String>, // Passphrase for encrypted keys
 }
 
+/// Request for `config_validate_connection` (never carries credentials)
+#[derive(Debug, Clone, Deserialize)]
+pub struct ValidateConnectionRequest {
+    pub host: String,
+    pub port: u16,
+    pub auth_type: String,        // "password", "key", "certificate", "agent"
+    pub key_path: Option<String>, // Key and certificate auth
+    #[serde(default)]
+    pub cert_path: Option<String>, // Certificate auth only
+    #[serde(default)]
+    pub proxy_command: Option<String>, // Nothing is probed behind a ProxyCommand
+    #[serde(default)]
+    pub proxy_chain: Vec<ValidateJumpHost>, // Jump hosts, first hop first
+}
+
+/// Address of a jump host in `ValidateConnectionRequest`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ValidateJumpHost {
+    pub host: String,
+    pub port: u16,
+}
+
 /// SSH config host info for frontend
 #[derive(Debug, Clone, Serialize)]
 pub struct SshHostInfo {
@@ -269,6 +425,49 @@ pub async fn search_connections(
         .collect())
 }
 
+/// Get connections carrying all (`match_all`) or any (default) of `tags`
+#[tauri::command]
+pub async fn config_filter_by_tags(
+    state: State<'_, Arc<ConfigState>>,
+    tags: Vec<String>,
+    match_all: Option<bool>,
+) -> Result<Vec<ConnectionInfo>, String> {
+    let config = state.config.read();
+    Ok(config
+        .get_by_tags(&tags, match_all.unwrap_or(false))
+        .into_iter()
+        .map(ConnectionInfo::from)
+        .collect())
+}
+
+/// Add a tag to several connections, returning how many changed
+#[tauri::command]
+pub async fn config_add_tag_to_many(
+    state: State<'_, Arc<ConfigState>>,
+    ids: Vec<String>,
+    tag: String,
+) -> Result<usize, String> {
+    let changed = state.config.write().add_tag_to_many(&ids, &tag);
+    if changed > 0 {
+        state.save().await?;
+    }
+    Ok(changed)
+}
+
+/// Remove a tag from several connections, returning how many changed
+#[tauri::command]
+pub async fn config_remove_tag_from_many(
+    state: State<'_, Arc<ConfigState>>,
+    ids: Vec<String>,
+    tag: String,
+) -> Result<usize, String> {
+    let changed = state.config.write().remove_tag_from_many(&ids, &tag);
+    if changed > 0 {
+        state.save().await?;
+    }
+    Ok(changed)
+}
+
 /// Get all groups
 #[tauri::command]
 pub async fn get_groups(state: State<'_, Arc<ConfigState>>) -> Result<Vec<String>, String> {
@@ -412,6 +611,11 @@ pub async fn save_connection(
             conn.username = request.username;
             conn.color = request.color;
             conn.tags = request.tags;
+            update_connection_options(
+                &mut conn.options,
+                request.proxy_command,
+                request.idle_timeout_secs,
+            );
 
             if let Some(ref password) = request.password {
                 let keychain_id = format!("oxide_conn_{}", uuid::Uuid::new_v4());
@@ -527,7 +731,11 @@ pub async fn save_connection(
                 port: request.port,
                 username: request.username,
                 auth,
-                options: Default::default(),
+                options: ConnectionOptions {
+                    proxy_command: normalize_proxy_command(request.proxy_command),
+                    idle_timeout_secs: request.idle_timeout_secs.unwrap_or(0),
+                    ..Default::default()
+                },
                 created_at: chrono::Utc::now(),
                 last_used_at: None,
                 color: request.color,
@@ -555,6 +763,7 @@ pub async fn save_connection(
 #[tauri::command]
 pub async fn delete_connection(
     state: State<'_, Arc<ConfigState>>,
+    state_store: State<'_, Arc<StateStore>>,
     id: String,
 ) -> Result<(), String> {
     {
@@ -574,6 +783,16 @@ pub async fn delete_connection(
 
     state.save().await?;
 
+    // Ignored ports are keyed by connection id and would outlive it
+    let store = state_store.inner().clone();
+    let result = tokio::task::spawn_blocking(move || store.delete_ignored_ports(&id)).await;
+    if let Ok(Err(e)) = result {
+        tracing::warn!(
+            "Failed to delete ignored ports of removed connection: {}",
+            e
+        );
+    }
+
     Ok(())
 }
 
@@ -608,27 +827,283 @@ pub async fn get_connection_password(
     }
 }
 
-/// Import hosts from SSH config
+/// Credential storage status
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeychainStatus {
+    /// Whether the OS keychain answered the startup probe
+    pub system_available: bool,
+    /// Whether credentials currently go to the encrypted file fallback
+    pub file_fallback: bool,
+    /// Whether credentials can be stored at all right now
+    pub usable: bool,
+}
+
+impl ConfigState {
+    fn keychain_status(&self) -> KeychainStatus {
+        let file_fallback = self
+            .system_keychain
+            .as_ref()
+            .is_some_and(|keychain| !keychain.is_system());
+        KeychainStatus {
+            system_available: self.system_keychain_available,
+            file_fallback,
+            usable: self.system_keychain_available || file_fallback,
+        }
+    }
+}
+
+/// Get credential storage status (warn the user when it is not usable)
 #[tauri::command]
-pub async fn list_ssh_config_hosts() -> Result<Vec<SshHostInfo>, String> {
-    let hosts = parse_ssh_config(None).await.map_err(|e| e.to_string())?;
-    Ok(hosts.iter().map(SshHostInfo::from).collect())
+pub async fn get_keychain_status(
+    state: State<'_, Arc<ConfigState>>,
+) -> Result<KeychainStatus, String> {
+    Ok(state.keychain_status())
 }
 
-/// Import a single SSH config host as a saved connection
+/// Store credentials in the encrypted file fallback
+///
+/// Only call after the user explicitly agreed: the key is kept next to the
+/// vault in the config directory. Refused while the OS keychain works.
 #[tauri::command]
-pub async fn import_ssh_host(
+pub async fn enable_keychain_file_fallback(
     state: State<'_, Arc<ConfigState>>,
-    alias: String,
-) -> Result<ConnectionInfo, String> {
-    // Parse SSH config
-    let hosts = parse_ssh_config(None).await.map_err(|e| e.to_string())?;
-    let host = hosts
+) -> Result<KeychainStatus, String> {
+    if state.system_keychain_available {
+        return Err("System keychain is available; file storage is not needed".to_string());
+    }
+    let keychain = state
+        .system_keychain
+        .as_ref()
+        .ok_or("Credential storage cannot be switched")?;
+    if keychain.is_system() {
+        let dir = config_dir().map_err(|e| e.to_string())?;
+        let backend = FileSecretBackend::open(&dir).map_err(|e| e.to_string())?;
+        keychain.set_backend(Arc::new(backend));
+        tracing::warn!("Enabled encrypted file storage for credentials");
+    }
+    Ok(state.keychain_status())
+}
+
+/// Check the validity window of a connection's OpenSSH certificate
+///
+/// `warn_within_secs` overrides the "expires soon" threshold (default 15 minutes).
+#[tauri::command]
+pub async fn config_check_certificate(
+    state: State<'_, Arc<ConfigState>>,
+    connection_id: String,
+    warn_within_secs: Option<u64>,
+) -> Result<CertStatus, String> {
+    let cert_path = {
+        let config = state.config.read();
+        let conn = config
+            .get_connection(&connection_id)
+            .ok_or("Connection not found")?;
+        match &conn.auth {
+            SavedAuth::Certificate { cert_path, .. } => cert_path.clone(),
+            _ => return Err("Connection does not use certificate auth".to_string()),
+        }
+    };
+
+    check_certificate_file(
+        &cert_path,
+        warn_within_secs.unwrap_or(DEFAULT_EXPIRY_WARNING_SECS),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Dry-run a connection before saving it
+///
+/// Resolves the host, probes the port over TCP and checks that key/cert
+/// files exist. No SSH handshake or authentication is attempted. Proxied
+/// hosts are probed at the first jump host, or not at all behind a ProxyCommand.
+#[tauri::command]
+pub async fn config_validate_connection(
+    request: ValidateConnectionRequest,
+) -> Result<Vec<ConnectionCheck>, String> {
+    let key_path = request.key_path.as_deref();
+    let files = match request.auth_type.as_str() {
+        "key" => vec![(CheckKind::KeyFile, key_path)],
+        "certificate" => vec![
+            (CheckKind::KeyFile, key_path),
+            (CheckKind::CertFile, request.cert_path.as_deref()),
+        ],
+        _ => Vec::new(),
+    };
+
+    let route = match (&request.proxy_command, request.proxy_chain.first()) {
+        (Some(_), _) => ProbeRoute::ProxyCommand,
+        (None, Some(hop)) => ProbeRoute::JumpHost {
+            host: &hop.host,
+            port: hop.port,
+        },
+        (None, None) => ProbeRoute::Direct,
+    };
+
+    Ok(validate_connection(&request.host, request.port, route, &files).await)
+}
+
+/// Why a connection test failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestFailure {
+    /// DNS, TCP, timeout or SSH transport problem
+    Network,
+    /// Server key does not match known_hosts
+    HostKey,
+    /// Rejected credentials, unusable key/certificate or ssh-agent problem
+    Auth,
+}
+
+impl TestFailure {
+    fn from_code(code: ErrorCode) -> Self {
+        match code {
+            ErrorCode::AuthFailed | ErrorCode::InvalidKey | ErrorCode::SshAgentUnavailable => {
+                TestFailure::Auth
+            }
+            ErrorCode::HostKeyChanged => TestFailure::HostKey,
+            _ => TestFailure::Network,
+        }
+    }
+}
+
+/// Result of `config_test_connection`
+#[derive(Debug, Serialize)]
+pub struct TestResult {
+    pub ok: bool,
+    /// Detected remote OS (same values as the profiler's `os_type`)
+    pub os_type: Option<String>,
+    /// Server identification string, e.g. `SSH-2.0-OpenSSH_9.6`
+    pub banner: Option<String>,
+    pub auth_method_used: String, // "password", "key", "certificate", "agent"
+    pub duration_ms: u64,
+    pub failure: Option<TestFailure>,
+    pub error: Option<CommandError>,
+}
+
+/// Build the session config for testing a saved connection, with
+/// credentials from the keychain. Returns the config and the auth type.
+fn session_config_for_test(
+    state: &ConfigState,
+    id: &str,
+) -> Result<(SessionConfig, String), CommandError> {
+    let config = state.config.read();
+    let conn = config
+        .get_connection(id)
+        .ok_or_else(|| CommandError::not_found("Connection not found"))?;
+    if !conn.proxy_chain.is_empty() {
+        return Err(CommandError::invalid_input(
+            "Testing connections with a proxy chain is not supported",
+        ));
+    }
+
+    let passphrase = |kc_id: &Option<String>| {
+        kc_id
+            .as_ref()
+            .and_then(|kc_id| state.keychain.get(kc_id).ok())
+    };
+    let auth = match &conn.auth {
+        SavedAuth::Password { keychain_id } => {
+            let password = state
+                .keychain
+                .get(keychain_id)
+                .map_err(|e| CommandError::new(ErrorCode::Keychain, e.to_string()))?;
+            AuthMethod::password(password)
+        }
+        SavedAuth::Key {
+            key_path,
+            passphrase_keychain_id,
+            ..
+        } => AuthMethod::key(key_path, passphrase(passphrase_keychain_id)),
+        SavedAuth::Certificate {
+            key_path,
+            cert_path,
+            passphrase_keychain_id,
+            ..
+        } => AuthMethod::certificate(key_path, cert_path, passphrase(passphrase_keychain_id)),
+        SavedAuth::Agent => AuthMethod::Agent,
+    };
+    let (auth_type, _) = auth_to_info(&conn.auth);
+
+    let session_config = SessionConfig {
+        host: conn.host.clone(),
+        port: conn.port,
+        username: conn.username.clone(),
+        auth,
+        name: Some(conn.name.clone()),
+        color: None,
+        cols: 80,
+        rows: 24,
+        proxy_command: conn.options.proxy_command.clone(),
+    };
+    Ok((session_config, auth_type))
+}
+
+/// Test a saved connection: authenticate, detect the remote OS, disconnect
+///
+/// The connection never enters the registry or the session tree. Connection
+/// and auth failures are reported in the result (`ok = false`); only a
+/// missing connection or keychain failure rejects the command.
+#[tauri::command]
+pub async fn config_test_connection(
+    state: State<'_, Arc<ConfigState>>,
+    connection_registry: State<'_, Arc<SshConnectionRegistry>>,
+    id: String,
+) -> Result<TestResult, CommandError> {
+    let (session_config, auth_method_used) = session_config_for_test(&state, &id)?;
+    let banner = ServerBanner::default();
+
+    let started = Instant::now();
+    let outcome = connection_registry
+        .test_connection(session_config, banner.clone())
+        .await;
+    let duration_ms = started.elapsed().as_millis() as u64;
+
+    let (os_type, error) = match outcome {
+        Ok(env) => (Some(env.os_type), None),
+        Err(e) => (None, Some(CommandError::from(e))),
+    };
+    let failure = error.as_ref().map(|e| TestFailure::from_code(e.code));
+
+    Ok(TestResult {
+        ok: error.is_none(),
+        os_type,
+        banner: banner.get(),
+        auth_method_used,
+        duration_ms,
+        failure,
+        error,
+    })
+}
+
+/// Import hosts from SSH config
+#[tauri::command]
+pub async fn list_ssh_config_hosts() -> Result<Vec<SshHostInfo>, String> {
+    let hosts = parse_ssh_config_blocks(None)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(filter_importable_hosts(hosts.clone())
         .iter()
-        .find(|h| h.alias == alias)
-        .ok_or_else(|| format!("Host '{}' not found in SSH config", alias))?;
+        .map(|host| {
+            // Show what `Host *`-style blocks contribute too
+            let mut info = SshHostInfo::from(&resolve_host(&hosts, &host.alias));
+            info.alias = host.alias.clone();
+            info
+        })
+        .collect())
+}
 
-    // Create connection
+/// Build the saved connection for an SSH config host.
+///
+/// Options from every matching Host block apply (see [`resolve_host`]).
+/// ProxyJump aliases resolve against the other `hosts`; hops with no Host
+/// entry are kept as written and returned alongside the connection (see
+/// [`SshConfigHost::resolve_proxy_chain`]).
+fn ssh_host_to_connection(
+    host: &SshConfigHost,
+    hosts: &[SshConfigHost],
+) -> (SavedConnection, Vec<String>) {
+    let host = &resolve_host(hosts, &host.alias);
     let auth = if let Some(ref key_path) = host.identity_file {
         SavedAuth::Key {
             key_path: key_path.clone(),
@@ -641,22 +1116,62 @@ pub async fn import_ssh_host(
 
     let username = host.user.clone().unwrap_or_else(whoami::username);
 
+    let proxy_chain = host.resolve_proxy_chain(hosts);
+    for missing in &proxy_chain.undefined {
+        tracing::warn!(
+            "SSH config host '{}' jumps through '{}', which has no Host entry; using it as written",
+            host.alias,
+            missing
+        );
+    }
+
     let conn = SavedConnection {
         id: uuid::Uuid::new_v4().to_string(),
         version: crate::config::CONFIG_VERSION,
-        name: alias.clone(),
+        name: host.alias.clone(),
         group: Some("Imported".to_string()),
         host: host.effective_hostname().to_string(),
         port: host.effective_port(),
         username,
         auth,
-        options: Default::default(),
+        options: ConnectionOptions {
+            proxy_command: normalize_proxy_command(host.proxy_command.clone()),
+            ..Default::default()
+        },
         created_at: chrono::Utc::now(),
         last_used_at: None,
         color: None,
         tags: vec!["ssh-config".to_string()],
-        proxy_chain: Vec::new(),
+        proxy_chain: proxy_chain.hops,
     };
+    (conn, proxy_chain.undefined)
+}
+
+/// Result of `import_ssh_host`
+#[derive(Debug, Serialize)]
+pub struct ImportedSshHost {
+    #[serde(flatten)]
+    pub connection: ConnectionInfo,
+    /// ProxyJump hosts with no Host entry, imported as written
+    pub undefined_jump_hosts: Vec<String>,
+}
+
+/// Import a single SSH config host as a saved connection
+#[tauri::command]
+pub async fn import_ssh_host(
+    state: State<'_, Arc<ConfigState>>,
+    alias: String,
+) -> Result<ImportedSshHost, String> {
+    // Parse SSH config
+    let hosts = parse_ssh_config_blocks(None)
+        .await
+        .map_err(|e| e.to_string())?;
+    let host = hosts
+        .iter()
+        .find(|h| h.alias == alias && !h.is_wildcard())
+        .ok_or_else(|| format!("Host '{}' not found in SSH config", alias))?;
+
+    let (conn, undefined_jump_hosts) = ssh_host_to_connection(host, &hosts);
 
     {
         let mut config = state.config.write();
@@ -669,7 +1184,10 @@ pub async fn import_ssh_host(
 
     state.save().await?;
 
-    Ok(ConnectionInfo::from(&conn))
+    Ok(ImportedSshHost {
+        connection: ConnectionInfo::from(&conn),
+        undefined_jump_hosts,
+    })
 }
 
 /// Get SSH config file path
@@ -680,6 +1198,83 @@ pub async fn get_ssh_config_path() -> Result<String, String> {
         .map_err(|e| e.to_string())
 }
 
+/// Export one connection as a shareable JSON snippet.
+///
+/// Unlike `.oxide` exports the snippet is not encrypted, so it carries no
+/// credentials or key paths, only the auth type of each hop.
+#[tauri::command]
+pub async fn config_export_connection_snippet(
+    state: State<'_, Arc<ConfigState>>,
+    id: String,
+) -> Result<String, String> {
+    let config = state.config.read();
+    let conn = config.get_connection(&id).ok_or("Connection not found")?;
+    Ok(ConnectionSnippet::from(conn).to_json())
+}
+
+/// Export connections as an OpenSSH config fragment (`~/.ssh/config` syntax).
+///
+/// Connections are written in the order given; credentials are never
+/// included.
+#[tauri::command]
+pub async fn export_to_ssh_config(
+    state: State<'_, Arc<ConfigState>>,
+    connection_ids: Vec<String>,
+) -> Result<String, String> {
+    let config = state.config.read();
+    let connections = connection_ids
+        .iter()
+        .map(|id| {
+            config
+                .get_connection(id)
+                .cloned()
+                .ok_or_else(|| format!("Connection not found: {}", id))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(to_ssh_config(&connections))
+}
+
+/// Recreate a connection from a pasted snippet with fresh credentials.
+///
+/// Fails with a message naming the hop and credential when one is missing,
+/// so the frontend can prompt for it and retry.
+#[tauri::command]
+pub async fn config_import_connection_snippet(
+    state: State<'_, Arc<ConfigState>>,
+    request: ImportSnippetRequest,
+) -> Result<ConnectionInfo, String> {
+    let snippet = ConnectionSnippet::parse(&request.snippet).map_err(|e| e.to_string())?;
+    let mut imported = snippet
+        .into_connection(request.credentials, request.hop_credentials)
+        .map_err(|e| e.to_string())?;
+
+    for (keychain_id, secret) in &imported.keychain_entries {
+        state
+            .keychain
+            .store(keychain_id, secret)
+            .map_err(|e| e.to_string())?;
+    }
+
+    let conn = {
+        let mut config = state.config.write();
+        let existing_names = config.connections.iter().map(|c| c.name.clone()).collect();
+        imported.connection.name =
+            super::oxide_import::resolve_name_conflict(&imported.connection.name, &existing_names);
+
+        if let Some(ref group) = imported.connection.group {
+            if !config.groups.contains(group) {
+                config.groups.push(group.clone());
+            }
+        }
+        config.add_connection(imported.connection.clone());
+        imported.connection
+    }; // config lock dropped here
+
+    state.save().await?;
+
+    Ok(ConnectionInfo::from(&conn))
+}
+
 /// Create groups
 #[tauri::command]
 pub async fn create_group(state: State<'_, Arc<ConfigState>>, name: String) -> Result<(), String> {
@@ -724,6 +1319,8 @@ pub struct SavedConnectionForConnect {
     pub passphrase: Option<String>,
     pub name: String,
     pub proxy_chain: Vec<ProxyHopForConnect>,
+    pub proxy_command: Option<String>,
+    pub idle_timeout_secs: u64,
 }
 
 #[derive(Debug, Serialize)]
@@ -868,6 +1465,8 @@ pub async fn get_saved_connection_for_connect(
         passphrase,
         name: conn.name.clone(),
         proxy_chain,
+        proxy_command: conn.options.proxy_command.clone(),
+        idle_timeout_secs: conn.options.idle_timeout_secs,
     })
 }
 
@@ -936,7 +1535,7 @@ pub async fn delete_ai_api_key(state: State<'_, Arc<ConfigState>>) -> Result<(),
 /// Called lazily on first access. Returns the key if migration succeeded.
 fn try_migrate_vault_to_keychain(
     app_handle: &tauri::AppHandle,
-    ai_keychain: &Keychain,
+    ai_keychain: &dyn CredentialStore,
     provider_id: &str,
 ) -> Option<String> {
     let app_data_dir = match app_handle.path().app_data_dir() {
@@ -1114,6 +1713,138 @@ pub async fn delete_ai_provider_api_key(
     Ok(())
 }
 
+/// Move all stored secrets to their canonical locations.
+///
+/// Connection passwords and passphrases are re-stored under consistent
+/// keychain IDs and legacy `ai.vault` / `ai_keys/*.vault` files move into the
+/// AI keychain. Old copies are only removed after the new ones read back
+/// (and, for keychain entries, after the updated config is saved and the
+/// persisted session tree points at the new IDs).
+#[tauri::command]
+pub async fn config_consolidate_credentials(
+    app_handle: tauri::AppHandle,
+    state: State<'_, Arc<ConfigState>>,
+    tree_state: State<'_, Arc<SessionTreeState>>,
+) -> Result<ConsolidationReport, String> {
+    let legacy_dir = app_handle.path().app_data_dir().ok();
+    let consolidation = {
+        let mut config = state.config.write();
+        consolidate_credentials(
+            &mut config,
+            state.keychain.as_ref(),
+            state.ai_keychain.as_ref(),
+            legacy_dir.as_deref(),
+            LEGACY_PROVIDER_ID,
+        )
+    }; // config lock dropped here
+
+    state.save().await?;
+
+    // Restored tree nodes still name the old IDs until they reconnect
+    let report = match tree_state
+        .rename_keychain_refs(consolidation.renamed_ids())
+        .await
+    {
+        Ok(()) => consolidation.delete_stale(state.keychain.as_ref()),
+        Err(e) => {
+            tracing::warn!("Failed to update session tree keychain references: {}", e);
+            consolidation.keep_stale("the saved session tree still refers to it")
+        }
+    };
+    tracing::info!(
+        "Credential consolidation: {} moved, {} unchanged, {} failed",
+        report.moved.len(),
+        report.unchanged,
+        report.failures.len()
+    );
+    Ok(report)
+}
+
+/// Built-in AI provider IDs, probed because the keychain cannot be listed
+const BUILTIN_AI_PROVIDER_IDS: [&str; 4] = [
+    "builtin-openai",
+    "builtin-anthropic",
+    "builtin-gemini",
+    "builtin-ollama",
+];
+
+/// Outcome of `rotate_keychain`, per keychain service
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeychainRotationReport {
+    pub ssh: RotationReport,
+    pub ai: RotationReport,
+}
+
+/// Re-key all stored credentials under new keychain service names.
+///
+/// The keychain cannot be listed on every platform, so only IDs known to
+/// OxideTerm move: connection and jump host secrets from the config file,
+/// the built-in AI providers and any `ai_provider_ids` (custom providers from
+/// settings). Secrets are copied and read back first; if any copy fails,
+/// nothing switches and the copies are removed. Old entries are deleted only
+/// after the config naming the new services is saved. `dry_run` just reports
+/// what would move.
+#[tauri::command]
+pub async fn rotate_keychain(
+    state: State<'_, Arc<ConfigState>>,
+    dry_run: bool,
+    ai_provider_ids: Option<Vec<String>>,
+) -> Result<KeychainRotationReport, String> {
+    let (Some(keychain), Some(ai_keychain)) = (&state.system_keychain, &state.system_ai_keychain)
+    else {
+        return Err("Credential storage cannot be rotated".to_string());
+    };
+
+    let ssh_ids: Vec<String> = state.config.read().keychain_ids().into_iter().collect();
+    let mut ai_ids: Vec<String> = BUILTIN_AI_PROVIDER_IDS
+        .iter()
+        .map(|id| id.to_string())
+        .chain(ai_provider_ids.unwrap_or_default())
+        .collect();
+    ai_ids.sort();
+    ai_ids.dedup();
+
+    let ssh_service = Keychain::rotated_service(SSH_KEYCHAIN_SERVICE);
+    let ai_service = Keychain::rotated_service(AI_KEYCHAIN_SERVICE);
+    let ssh = keychain.rotate(&ssh_service, &ssh_ids, dry_run);
+    let ai = ai_keychain.rotate(&ai_service, &ai_ids, dry_run);
+
+    if dry_run || !ssh.is_complete() || !ai.is_complete() {
+        return Ok(KeychainRotationReport {
+            ssh: ssh.abort(),
+            ai: ai.abort(),
+        });
+    }
+
+    let previous = {
+        let mut config = state.config.write();
+        (
+            config.ssh_keychain_service.replace(ssh_service),
+            config.ai_keychain_service.replace(ai_service),
+        )
+    }; // config lock dropped here
+
+    if let Err(e) = state.save().await {
+        // Still on the old services: point the config back at them
+        {
+            let mut config = state.config.write();
+            (config.ssh_keychain_service, config.ai_keychain_service) = previous;
+        }
+        ssh.abort();
+        ai.abort();
+        return Err(format!(
+            "Failed to save config, keychain left unchanged: {}",
+            e
+        ));
+    }
+
+    Ok(KeychainRotationReport {
+        ssh: ssh.commit(),
+        ai: ai.commit(),
+    })
+}
+
 /// List all provider IDs that have stored API keys
 /// Note: This checks both keychain and legacy vault files
 #[tauri::command]
@@ -1137,13 +1868,7 @@ pub async fn list_ai_provider_keys(
 
     // Check known provider IDs in keychain
     // Since keychain doesn't support enumeration, we probe known provider IDs
-    let known_ids = [
-        "builtin-openai",
-        "builtin-anthropic",
-        "builtin-gemini",
-        "builtin-ollama",
-    ];
-    for id in &known_ids {
+    for id in BUILTIN_AI_PROVIDER_IDS {
         if state.ai_keychain.get(id).is_ok() {
             providers.insert(id.to_string());
         }
@@ -1151,3 +1876,171 @@ pub async fn list_ai_provider_keys(
 
     Ok(providers.into_iter().collect())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{memory_config_state, MemoryCredentialStore};
+
+    fn state_with_memory_stores() -> (ConfigState, Arc<MemoryCredentialStore>) {
+        let keychain = Arc::new(MemoryCredentialStore::new());
+        let state = memory_config_state(ConfigFile::default(), keychain.clone());
+        (state, keychain)
+    }
+
+    #[test]
+    fn test_keychain_access_goes_through_store() {
+        let (state, keychain) = state_with_memory_stores();
+
+        state.set_keychain_value("oxide_conn_1", "hunter2").unwrap();
+        assert_eq!(keychain.ids(), vec!["oxide_conn_1".to_string()]);
+        assert_eq!(state.get_keychain_value("oxide_conn_1").unwrap(), "hunter2");
+
+        state.delete_keychain_value("oxide_conn_1").unwrap();
+        assert!(keychain.is_empty());
+        assert!(state.get_keychain_value("oxide_conn_1").is_err());
+    }
+
+    #[test]
+    fn test_injected_stores_report_usable() {
+        let (state, keychain) = state_with_memory_stores();
+        let status = state.keychain_status();
+        assert!(status.usable && !status.file_fallback);
+
+        keychain.set_unavailable(true);
+        let err = state.set_keychain_value("id", "secret").unwrap_err();
+        assert!(err.contains("unavailable"), "{}", err);
+    }
+
+    #[test]
+    fn test_session_config_for_test_resolves_password() {
+        let (state, _) = state_with_memory_stores();
+        state.set_keychain_value("oxide_conn_1", "hunter2").unwrap();
+        let conn =
+            SavedConnection::new_password("web", "example.com", 2222, "root", "oxide_conn_1");
+        let id = conn.id.clone();
+        state.config.write().add_connection(conn);
+
+        let (config, auth_type) = session_config_for_test(&state, &id).unwrap();
+        assert_eq!(auth_type, "password");
+        assert_eq!(config.port, 2222);
+        assert!(
+            matches!(config.auth, AuthMethod::Password { ref password } if password == "hunter2")
+        );
+
+        let err = session_config_for_test(&state, "missing").unwrap_err();
+        assert_eq!(err.code, ErrorCode::NotFound);
+    }
+
+    #[test]
+    fn test_update_without_proxy_command_keeps_it() {
+        let mut options = ConnectionOptions {
+            proxy_command: Some("cloudflared access ssh --hostname %h".to_string()),
+            idle_timeout_secs: 600,
+            ..ConnectionOptions::default()
+        };
+
+        // Older clients don't send either field
+        update_connection_options(&mut options, None, None);
+        assert_eq!(
+            options.proxy_command.as_deref(),
+            Some("cloudflared access ssh --hostname %h")
+        );
+        assert_eq!(options.idle_timeout_secs, 600);
+
+        update_connection_options(&mut options, Some("nc -X 5 %h %p".to_string()), Some(0));
+        assert_eq!(options.proxy_command.as_deref(), Some("nc -X 5 %h %p"));
+        assert_eq!(options.idle_timeout_secs, 0);
+
+        update_connection_options(&mut options, Some(String::new()), None);
+        assert_eq!(options.proxy_command, None);
+    }
+
+    #[test]
+    fn test_session_config_for_test_rejects_proxy_chain() {
+        let (state, _) = state_with_memory_stores();
+        let mut conn = SavedConnection::new_password("db", "10.0.0.5", 22, "root", "kc");
+        conn.proxy_chain.push(ProxyHopConfig {
+            host: "bastion".to_string(),
+            port: 22,
+            username: "jump".to_string(),
+            auth: SavedAuth::Agent,
+        });
+        let id = conn.id.clone();
+        state.config.write().add_connection(conn);
+
+        let err = session_config_for_test(&state, &id).unwrap_err();
+        assert_eq!(err.code, ErrorCode::InvalidInput);
+    }
+
+    #[test]
+    fn test_ssh_host_import_builds_three_hop_chain() {
+        let hosts = crate::config::ssh_config::parse_ssh_config_content(
+            r#"
+Host compute
+    HostName node07.cluster
+    User alice
+    ProxyJump bastion,gw.example.com:2200,login
+
+Host bastion
+    HostName bastion.example.com
+    User jump
+    IdentityFile /keys/bastion
+
+Host login
+    HostName login.cluster
+    Port 2222
+"#,
+        )
+        .unwrap();
+
+        let (conn, undefined) = ssh_host_to_connection(&hosts[0], &hosts);
+        assert_eq!(undefined, vec!["gw.example.com".to_string()]);
+        assert_eq!(conn.host, "node07.cluster");
+        assert_eq!(conn.username, "alice");
+
+        let chain: Vec<(&str, u16)> = conn
+            .proxy_chain
+            .iter()
+            .map(|hop| (hop.host.as_str(), hop.port))
+            .collect();
+        assert_eq!(
+            chain,
+            vec![
+                ("bastion.example.com", 22),
+                // Not a Host alias: kept as written with the default username
+                ("gw.example.com", 2200),
+                // Port comes from the jump host's own Host block
+                ("login.cluster", 2222),
+            ]
+        );
+        assert_eq!(conn.proxy_chain[0].username, "jump");
+        assert!(matches!(conn.proxy_chain[0].auth, SavedAuth::Key { .. }));
+        assert_eq!(conn.proxy_chain[1].username, whoami::username());
+        assert_eq!(conn.proxy_chain[1].auth, SavedAuth::Agent);
+    }
+
+    #[test]
+    fn test_failure_kind_separates_auth_from_network() {
+        assert_eq!(
+            TestFailure::from_code(ErrorCode::AuthFailed),
+            TestFailure::Auth
+        );
+        assert_eq!(
+            TestFailure::from_code(ErrorCode::InvalidKey),
+            TestFailure::Auth
+        );
+        assert_eq!(
+            TestFailure::from_code(ErrorCode::ConnectionRefused),
+            TestFailure::Network
+        );
+        assert_eq!(
+            TestFailure::from_code(ErrorCode::Timeout),
+            TestFailure::Network
+        );
+        assert_eq!(
+            TestFailure::from_code(ErrorCode::HostKeyChanged),
+            TestFailure::HostKey
+        );
+    }
+}
@@ -23,7 +23,7 @@ use std::sync::Arc;
 use tauri::State;
 use tracing::{info, warn};
 
-use super::{ForwardingRegistry, HealthRegistry, ProfilerRegistry};
+use super::{CommandError, ErrorCode, ForwardingRegistry, HealthRegistry, ProfilerRegistry};
 use crate::bridge::BridgeManager;
 use crate::session::{
     AuthMethod, KeyAuth, SessionConfig, SessionInfo, SessionRegistry, SessionStats,
@@ -290,7 +290,7 @@ pub struct EstablishConnectionResponse {
 pub async fn establish_connection(
     request: ConnectRequest,
     connection_registry: State<'_, Arc<SshConnectionRegistry>>,
-) -> Result<EstablishConnectionResponse, String> {
+) -> Result<EstablishConnectionResponse, CommandError> {
     info!(
         "Establish connection request: {}@{}:{}",
         request.username, request.host, request.port
@@ -307,8 +307,10 @@ pub async fn establish_connection(
             passphrase,
         },
         AuthRequest::DefaultKey { passphrase } => {
-            let key_auth = KeyAuth::from_default_locations(passphrase.as_deref())
-                .map_err(|e| format!("No SSH key found: {}", e))?;
+            let key_auth = KeyAuth::from_default_locations(passphrase.as_deref()).map_err(|e| {
+                CommandError::new(ErrorCode::InvalidKey, "No SSH key found")
+                    .with_detail(e.to_string())
+            })?;
             AuthMethod::Key {
                 key_path: key_auth.key_path.to_string_lossy().to_string(),
                 passphrase,
@@ -326,6 +328,7 @@ pub async fn establish_connection(
         color: None,
         cols: request.cols,
         rows: request.rows,
+        proxy_command: None,
     };
 
     // 检查是否有可复用的连接
@@ -335,7 +338,7 @@ pub async fn establish_connection(
         let connection_info = connection_registry
             .get_info(&existing_id)
             .await
-            .ok_or_else(|| "Connection disappeared".to_string())?;
+            .ok_or_else(|| CommandError::not_found("Connection disappeared"))?;
 
         return Ok(EstablishConnectionResponse {
             connection_id: existing_id,
@@ -347,18 +350,17 @@ pub async fn establish_connection(
     // 建立新连接
     // TODO: 支持 proxy_chain
     if request.proxy_chain.is_some() {
-        return Err("Proxy chain not yet supported in establish_connection. Use connect_v2 for proxy connections.".to_string());
+        return Err(CommandError::invalid_input(
+            "Proxy chain not yet supported in establish_connection. Use connect_v2 for proxy connections.",
+        ));
     }
 
-    let connection_id = connection_registry
-        .connect(config)
-        .await
-        .map_err(|e| format!("Connection failed: {}", e))?;
+    let connection_id = connection_registry.connect(config).await?;
 
     let connection_info = connection_registry
         .get_info(&connection_id)
         .await
-        .ok_or_else(|| "Connection disappeared after creation".to_string())?;
+        .ok_or_else(|| CommandError::not_found("Connection disappeared after creation"))?;
 
     info!("New connection established: {}", connection_id);
 
@@ -373,7 +375,7 @@ pub async fn establish_connection(
 #[tauri::command]
 pub async fn list_connections(
     connection_registry: State<'_, Arc<SshConnectionRegistry>>,
-) -> Result<Vec<crate::ssh::ConnectionInfo>, String> {
+) -> Result<Vec<crate::ssh::ConnectionInfo>, CommandError> {
     Ok(connection_registry.inner().list_connections().await)
 }
 
@@ -382,10 +384,10 @@ pub async fn list_connections(
 pub async fn disconnect_connection(
     connection_id: String,
     connection_registry: State<'_, Arc<SshConnectionRegistry>>,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     connection_registry
         .inner()
         .disconnect(&connection_id)
         .await
-        .map_err(|e| format!("Failed to disconnect: {}", e))
+        .map_err(|e| CommandError::from(e).context("Failed to disconnect"))
 }
@@ -0,0 +1,359 @@
+//! Structured error payload for Tauri commands
+//!
+//! Commands used to return `Result<T, String>`, leaving the frontend to
+//! pattern-match on (sometimes localized) sentences. `CommandError` carries a
+//! stable `code` next to the message so the UI can branch on the failure kind
+//! and localize the text itself:
+//!
+//! ```json
+//! { "code": "auth_failed", "message": "Authentication failed", "detail": "..." }
+//! ```
+//!
+//! `From` impls cover the module error types, so `?` keeps working in
+//! commands; plain `String`s map to `ErrorCode::Internal`.
+
+use serde::Serialize;
+
+use super::messages::{Locale, Message};
+use crate::agent::{TransportError, ERR_INVALID_PARAMS, ERR_IO, ERR_NOT_FOUND, ERR_PERMISSION};
+use crate::forwarding::ForwardError;
+use crate::oxide_file::OxideFileError;
+use crate::router::RouteError;
+use crate::session::TreeError;
+use crate::ssh::{ConnectionRegistryError, SshError};
+
+/// Machine-readable failure kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// Node, connection, session or file does not exist
+    NotFound,
+    /// Rejected argument or operation not allowed in the current state
+    InvalidInput,
+    /// Remote side denied access
+    PermissionDenied,
+    /// TCP connection refused by the remote host
+    ConnectionRefused,
+    /// SSH connection could not be established for another reason
+    ConnectionFailed,
+    /// No live connection for the target
+    NotConnected,
+    /// Server rejected the credentials
+    AuthFailed,
+    /// Server key does not match known_hosts (possible MITM)
+    HostKeyChanged,
+    /// Private key or certificate could not be loaded
+    InvalidKey,
+    /// ssh-agent is missing or failed
+    SshAgentUnavailable,
+    /// Operation timed out
+    Timeout,
+    /// Connection pool limit reached
+    LimitReached,
+    /// Not a valid .oxide file, or an unsupported version
+    InvalidFile,
+    /// Wrong .oxide password or corrupted file
+    DecryptionFailed,
+    /// .oxide payload failed its integrity check
+    ChecksumMismatch,
+    /// Remote agent is not deployed on the node
+    AgentNotDeployed,
+    /// Agent write rejected because the file changed since it was read,
+    /// or a requested remote port is already taken
+    Conflict,
+    /// Agent transport or RPC failure
+    AgentFailed,
+    /// System keychain access failed
+    Keychain,
+    /// Filesystem error
+    Io,
+    /// Anything not covered above
+    Internal,
+}
+
+/// Error returned by Tauri commands
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, thiserror::Error)]
+#[serde(rename_all = "camelCase")]
+#[error("{message}")]
+pub struct CommandError {
+    pub code: ErrorCode,
+    /// Human-readable summary (English unless the command takes a `Locale`)
+    pub message: String,
+    /// Underlying cause, for logs and "details" disclosure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl CommandError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            detail: None,
+        }
+    }
+
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Replace the message with `message`, keeping the code and moving the
+    /// previous message into `detail`
+    pub fn context(self, message: impl Into<String>) -> Self {
+        let detail = match self.detail {
+            Some(detail) => format!("{}: {}", self.message, detail),
+            None => self.message,
+        };
+        Self {
+            code: self.code,
+            message: message.into(),
+            detail: Some(detail),
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidInput, message)
+    }
+
+    pub fn agent_not_deployed() -> Self {
+        Self::new(ErrorCode::AgentNotDeployed, "Agent not deployed")
+    }
+}
+
+/// Classify a connection failure that only survived as text.
+///
+/// The SSH handler and the connection registry flatten their errors into
+/// strings, so this looks for the phrases they produce.
+fn classify_connection_failure(message: &str) -> ErrorCode {
+    let lower = message.to_lowercase();
+    if lower.contains("host key verification failed") || lower.contains("host key changed") {
+        ErrorCode::HostKeyChanged
+    } else if lower.contains("authentication failed") {
+        ErrorCode::AuthFailed
+    } else if lower.contains("connection refused") {
+        ErrorCode::ConnectionRefused
+    } else if lower.contains("timed out") || lower.contains("timeout") {
+        ErrorCode::Timeout
+    } else {
+        ErrorCode::ConnectionFailed
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+}
+
+impl From<&str> for CommandError {
+    fn from(message: &str) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+}
+
+impl From<std::io::Error> for CommandError {
+    fn from(e: std::io::Error) -> Self {
+        let code = match e.kind() {
+            std::io::ErrorKind::NotFound => ErrorCode::NotFound,
+            std::io::ErrorKind::PermissionDenied => ErrorCode::PermissionDenied,
+            _ => ErrorCode::Io,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<SshError> for CommandError {
+    fn from(e: SshError) -> Self {
+        let code = match &e {
+            SshError::ConnectionFailed(msg) => classify_connection_failure(msg),
+            SshError::AuthenticationFailed(_) => ErrorCode::AuthFailed,
+            SshError::IoError(io) => match io.kind() {
+                std::io::ErrorKind::ConnectionRefused => ErrorCode::ConnectionRefused,
+                std::io::ErrorKind::TimedOut => ErrorCode::Timeout,
+                _ => ErrorCode::ConnectionFailed,
+            },
+            SshError::ProtocolError(_) => ErrorCode::ConnectionFailed,
+            SshError::KeyError(_)
+            | SshError::CertificateLoadError(_)
+            | SshError::CertificateParseError(_) => ErrorCode::InvalidKey,
+            SshError::Timeout(_) => ErrorCode::Timeout,
+            SshError::SessionNotFound(_) => ErrorCode::NotFound,
+            SshError::Disconnected => ErrorCode::NotConnected,
+            SshError::AgentNotAvailable(_) | SshError::AgentError(_) => {
+                ErrorCode::SshAgentUnavailable
+            }
+            SshError::SessionError(_) | SshError::ChannelError(_) => ErrorCode::Internal,
+            SshError::Forward(ForwardError::RemotePortInUse { .. }) => ErrorCode::Conflict,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<ConnectionRegistryError> for CommandError {
+    fn from(e: ConnectionRegistryError) -> Self {
+        let code = match &e {
+            ConnectionRegistryError::NotFound(_) => ErrorCode::NotFound,
+            ConnectionRegistryError::LimitReached { .. } => ErrorCode::LimitReached,
+            ConnectionRegistryError::ConnectionFailed(msg) => classify_connection_failure(msg),
+            ConnectionRegistryError::AlreadyDisconnected => ErrorCode::NotConnected,
+            ConnectionRegistryError::InvalidState(_) => ErrorCode::InvalidInput,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<TreeError> for CommandError {
+    fn from(e: TreeError) -> Self {
+        let code = match &e {
+            TreeError::NodeNotFound(_) => ErrorCode::NotFound,
+            TreeError::ParentNotConnected(_) => ErrorCode::NotConnected,
+            TreeError::InvalidOperation(_) | TreeError::DepthLimitExceeded(_) => {
+                ErrorCode::InvalidInput
+            }
+            TreeError::ConnectionFailed(msg) => classify_connection_failure(msg),
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<RouteError> for CommandError {
+    fn from(e: RouteError) -> Self {
+        let code = match &e {
+            RouteError::NodeNotFound(_) => ErrorCode::NotFound,
+            RouteError::NotConnected(_) | RouteError::CapabilityUnavailable(_) => {
+                ErrorCode::NotConnected
+            }
+            RouteError::ConnectionError(msg) => classify_connection_failure(msg),
+            RouteError::SftpOperationError(_) => ErrorCode::Io,
+            RouteError::ConnectionTimeout(_) => ErrorCode::Timeout,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<TransportError> for CommandError {
+    fn from(e: TransportError) -> Self {
+        match &e {
+            TransportError::WriteConflict { current_hash } => {
+                Self::new(ErrorCode::Conflict, e.to_string()).with_detail(current_hash.clone())
+            }
+            TransportError::Timeout(_) => Self::new(ErrorCode::Timeout, e.to_string()),
+            TransportError::LocalIo(_) => Self::new(ErrorCode::Io, e.to_string()),
+            TransportError::RpcError(rpc) => {
+                let code = match rpc.code {
+                    ERR_NOT_FOUND => ErrorCode::NotFound,
+                    ERR_PERMISSION => ErrorCode::PermissionDenied,
+                    ERR_INVALID_PARAMS => ErrorCode::InvalidInput,
+                    ERR_IO => ErrorCode::Io,
+                    _ => ErrorCode::AgentFailed,
+                };
+                Self::new(code, rpc.message.clone()).with_detail(e.to_string())
+            }
+            _ => Self::new(ErrorCode::AgentFailed, e.to_string()),
+        }
+    }
+}
+
+impl CommandError {
+    /// `.oxide` failure, with the message in the caller's language
+    pub fn oxide(e: OxideFileError, locale: Locale) -> Self {
+        let (code, message) = match &e {
+            OxideFileError::InvalidMagic
+            | OxideFileError::UnsupportedVersion(_)
+            | OxideFileError::UnsupportedKdfVersion(_)
+            | OxideFileError::InvalidFormat(_) => {
+                (ErrorCode::InvalidFile, Message::OxideInvalidFile)
+            }
+            OxideFileError::DecryptionFailed => {
+                (ErrorCode::DecryptionFailed, Message::OxideDecryptionFailed)
+            }
+            OxideFileError::ChecksumMismatch => {
+                (ErrorCode::ChecksumMismatch, Message::OxideChecksumMismatch)
+            }
+            OxideFileError::Io(_) => (ErrorCode::Io, Message::OxideIoFailed),
+            _ => (ErrorCode::Internal, Message::OxideProcessingFailed),
+        };
+        Self::new(code, message.text(locale)).with_detail(e.to_string())
+    }
+}
+
+impl From<OxideFileError> for CommandError {
+    fn from(e: OxideFileError) -> Self {
+        Self::oxide(e, Locale::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializes_code_message_detail() {
+        let err = CommandError::new(ErrorCode::AuthFailed, "Authentication failed")
+            .with_detail("publickey rejected");
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            serde_json::json!({
+                "code": "auth_failed",
+                "message": "Authentication failed",
+                "detail": "publickey rejected",
+            })
+        );
+
+        let bare = serde_json::to_value(CommandError::from("boom")).unwrap();
+        assert_eq!(
+            bare,
+            serde_json::json!({ "code": "internal", "message": "boom" })
+        );
+    }
+
+    #[test]
+    fn test_connection_failures_are_classified() {
+        let host_key = SshError::ConnectionFailed(
+            "HOST KEY VERIFICATION FAILED: Key for example.com:22 has changed!".into(),
+        );
+        assert_eq!(CommandError::from(host_key).code, ErrorCode::HostKeyChanged);
+
+        let refused = ConnectionRegistryError::ConnectionFailed(
+            "Connection failed: Connection refused (os error 111)".into(),
+        );
+        assert_eq!(
+            CommandError::from(refused).code,
+            ErrorCode::ConnectionRefused
+        );
+
+        let auth = ConnectionRegistryError::ConnectionFailed(
+            "Authentication failed: all methods rejected".into(),
+        );
+        assert_eq!(CommandError::from(auth).code, ErrorCode::AuthFailed);
+
+        let other = ConnectionRegistryError::ConnectionFailed("No route to host".into());
+        assert_eq!(CommandError::from(other).code, ErrorCode::ConnectionFailed);
+    }
+
+    #[test]
+    fn test_context_keeps_code_and_moves_cause_to_detail() {
+        let err = CommandError::from(std::io::Error::from(std::io::ErrorKind::NotFound))
+            .context("Cannot read key file");
+        assert_eq!(err.code, ErrorCode::NotFound);
+        assert_eq!(err.message, "Cannot read key file");
+        assert_eq!(err.to_string(), "Cannot read key file");
+        assert!(err.detail.is_some());
+    }
+
+    #[test]
+    fn test_oxide_decryption_failure_is_localized() {
+        let zh = CommandError::oxide(OxideFileError::DecryptionFailed, Locale::Zh);
+        assert_eq!(zh.code, ErrorCode::DecryptionFailed);
+        assert_eq!(zh.message, "密码错误或文件已损坏");
+
+        let en = CommandError::from(OxideFileError::DecryptionFailed);
+        assert_eq!(en.code, ErrorCode::DecryptionFailed);
+        assert_eq!(en.message, "Wrong password or corrupted file");
+    }
+}
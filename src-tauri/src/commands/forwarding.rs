@@ -10,15 +10,25 @@ use tauri::State;
 use tokio::sync::RwLock;
 use tracing::{error, info, warn};
 
+use crate::commands::health::ProfilerRegistry;
 use crate::forwarding::{
-    ForwardRule, ForwardRuleUpdate, ForwardStats, ForwardStatus, ForwardType, ForwardingManager,
+    ConnectionEvent, ForwardError, ForwardRule, ForwardRuleUpdate, ForwardStats, ForwardStatus,
+    ForwardType, ForwardingManager,
 };
-use crate::state::{forwarding::ForwardPersistence, PersistedForward, StateStore};
+use crate::session::SessionRegistry;
+use crate::ssh::SshError;
+use crate::state::forwarding::{ForwardEventLog, ForwardPersistence};
+use crate::state::{PersistedForward, StateStore};
+
+/// Default number of events returned by `forward_get_log`
+const DEFAULT_FORWARD_LOG_LIMIT: usize = 200;
 
 /// Global registry of forwarding managers (one per session)
 pub struct ForwardingRegistry {
     managers: RwLock<HashMap<String, Arc<ForwardingManager>>>,
     persistence: Option<ForwardPersistence>,
+    /// Connection event log shared by all managers (requires persistence)
+    event_log: Option<Arc<ForwardEventLog>>,
 }
 
 impl ForwardingRegistry {
@@ -27,6 +37,7 @@ impl ForwardingRegistry {
         Self {
             managers: RwLock::new(HashMap::new()),
             persistence: None,
+            event_log: None,
         }
     }
 
@@ -34,12 +45,21 @@ impl ForwardingRegistry {
     pub fn new_with_state(state_store: Arc<StateStore>) -> Self {
         Self {
             managers: RwLock::new(HashMap::new()),
-            persistence: Some(ForwardPersistence::new(state_store)),
+            persistence: Some(ForwardPersistence::new(state_store.clone())),
+            event_log: Some(Arc::new(ForwardEventLog::new(state_store))),
+        }
+    }
+
+    /// Hook a manager up to the connection event log
+    fn attach_event_log(&self, manager: &mut ForwardingManager) {
+        if let Some(event_log) = &self.event_log {
+            manager.set_connection_log(event_log.clone());
         }
     }
 
     /// Register a forwarding manager for a session
-    pub async fn register(&self, session_id: String, manager: ForwardingManager) {
+    pub async fn register(&self, session_id: String, mut manager: ForwardingManager) {
+        self.attach_event_log(&mut manager);
         self.managers
             .write()
             .await
@@ -105,7 +125,8 @@ impl ForwardingRegistry {
         );
 
         // Create a new manager with the new HandleController
-        let new_manager = ForwardingManager::new(new_handle_controller, session_id);
+        let mut new_manager = ForwardingManager::new(new_handle_controller, session_id);
+        self.attach_event_log(&mut new_manager);
 
         // Restore each forward rule
         let mut restored_rules = Vec::new();
@@ -157,6 +178,9 @@ impl ForwardingRegistry {
 
     /// Persist a forward rule
     pub async fn persist_forward(&self, forward: PersistedForward) -> Result<(), String> {
+        if let Some(event_log) = &self.event_log {
+            event_log.set_enabled(&forward.id, forward.logging);
+        }
         if let Some(persistence) = &self.persistence {
             persistence
                 .save_async(forward)
@@ -169,6 +193,9 @@ impl ForwardingRegistry {
 
     /// Delete a persisted forward
     pub async fn delete_persisted_forward(&self, forward_id: String) -> Result<(), String> {
+        if let Some(event_log) = &self.event_log {
+            event_log.set_enabled(&forward_id, false);
+        }
         if let Some(persistence) = &self.persistence {
             persistence
                 .delete_async(forward_id)
@@ -179,12 +206,32 @@ impl ForwardingRegistry {
         Ok(())
     }
 
+    /// Merge duplicate persisted forward rules (e.g. left behind by a crash)
+    ///
+    /// Rules of different persisted sessions are kept apart.
+    /// Returns the number of rows removed.
+    pub async fn dedupe_persisted_forwards(&self) -> Result<usize, String> {
+        if let Some(persistence) = &self.persistence {
+            persistence
+                .dedupe_async()
+                .await
+                .map_err(|e| format!("Failed to merge duplicate forwards: {:?}", e))
+        } else {
+            Ok(0)
+        }
+    }
+
     /// Load persisted forwards for a session (uses async internally where possible)
     pub async fn load_persisted_forwards(
         &self,
         session_id: &str,
     ) -> Result<Vec<PersistedForward>, String> {
         if let Some(persistence) = &self.persistence {
+            // Drop duplicates first so restore never starts the same bind twice
+            if let Err(e) = self.dedupe_persisted_forwards().await {
+                warn!("{}", e);
+            }
+
             // Use async load_all and filter (more efficient than loading all synchronously)
             let all_forwards = persistence
                 .load_all_async()
@@ -217,6 +264,34 @@ impl ForwardingRegistry {
         }
         Ok(())
     }
+
+    /// Enable or disable connection event logging for a forward
+    pub async fn update_logging(&self, forward_id: &str, logging: bool) -> Result<(), String> {
+        let (Some(persistence), Some(event_log)) = (&self.persistence, &self.event_log) else {
+            return Err("Forward logging requires state persistence".to_string());
+        };
+        persistence
+            .update_logging(forward_id, logging)
+            .map_err(|e| format!("Failed to update logging: {:?}", e))?;
+        event_log.set_enabled(forward_id, logging);
+        info!("Updated logging for forward {}: {}", forward_id, logging);
+        Ok(())
+    }
+
+    /// Load the newest `limit` connection events of a forward, oldest first
+    pub async fn load_forward_log(
+        &self,
+        forward_id: &str,
+        limit: usize,
+    ) -> Result<Vec<ConnectionEvent>, String> {
+        if let Some(persistence) = &self.persistence {
+            persistence
+                .load_events(forward_id, limit)
+                .map_err(|e| format!("Failed to load forward log: {:?}", e))
+        } else {
+            Ok(Vec::new())
+        }
+    }
 }
 
 impl Default for ForwardingRegistry {
@@ -305,6 +380,8 @@ impl From<ForwardRule> for ForwardRuleDto {
 pub async fn create_port_forward(
     registry: State<'_, Arc<ForwardingRegistry>>,
     connection_registry: State<'_, Arc<crate::ssh::SshConnectionRegistry>>,
+    session_registry: State<'_, Arc<SessionRegistry>>,
+    profiler_registry: State<'_, ProfilerRegistry>,
     request: CreateForwardRequest,
 ) -> Result<ForwardResponse, String> {
     info!(
@@ -403,6 +480,10 @@ pub async fn create_port_forward(
             })
         }
         Err(e) => {
+            let connection_id = session_registry
+                .get(&request.session_id)
+                .and_then(|s| s.connection_id);
+            let e = with_remote_port_holder(e, &profiler_registry, connection_id.as_deref());
             error!("Failed to create port forward: {}", e);
             Ok(ForwardResponse {
                 success: false,
@@ -413,6 +494,29 @@ pub async fn create_port_forward(
     }
 }
 
+/// Name the process holding a refused remote port, when the connection's
+/// resource profiler has seen it listening
+pub(crate) fn with_remote_port_holder(
+    err: SshError,
+    profiler_registry: &ProfilerRegistry,
+    connection_id: Option<&str>,
+) -> SshError {
+    match (err, connection_id) {
+        (
+            SshError::Forward(ForwardError::RemotePortInUse {
+                port,
+                process: None,
+            }),
+            Some(connection_id),
+        ) => ForwardError::RemotePortInUse {
+            port,
+            process: profiler_registry.port_holder(connection_id, port),
+        }
+        .into(),
+        (err, _) => err,
+    }
+}
+
 /// Stop a port forward
 #[tauri::command]
 pub async fn stop_port_forward(
@@ -847,6 +951,7 @@ pub async fn list_saved_forwards(
             target_host: f.rule.target_host,
             target_port: f.rule.target_port,
             auto_start: f.auto_start,
+            logging: f.logging,
             created_at: f.created_at.to_rfc3339(),
         })
         .collect())
@@ -866,6 +971,32 @@ pub async fn set_forward_auto_start(
     registry.update_auto_start(&forward_id, auto_start).await
 }
 
+/// Enable or disable connection event logging for a saved forward
+#[tauri::command]
+pub async fn set_forward_logging(
+    registry: State<'_, Arc<ForwardingRegistry>>,
+    forward_id: String,
+    logging: bool,
+) -> Result<(), String> {
+    info!("Setting logging={} for forward {}", logging, forward_id);
+    registry.update_logging(&forward_id, logging).await
+}
+
+/// Get the newest connection events (accept/close) recorded for a forward
+///
+/// Events are only recorded while the forward's `logging` flag is set.
+/// Returned oldest first; `limit` defaults to 200.
+#[tauri::command]
+pub async fn forward_get_log(
+    registry: State<'_, Arc<ForwardingRegistry>>,
+    forward_id: String,
+    limit: Option<usize>,
+) -> Result<Vec<ConnectionEvent>, String> {
+    registry
+        .load_forward_log(&forward_id, limit.unwrap_or(DEFAULT_FORWARD_LOG_LIMIT))
+        .await
+}
+
 /// Delete a persisted forward rule
 #[tauri::command]
 pub async fn delete_saved_forward(
@@ -887,5 +1018,6 @@ pub struct PersistedForwardDto {
     pub target_host: String,
     pub target_port: u16,
     pub auto_start: bool,
+    pub logging: bool,
     pub created_at: String,
 }
@@ -1,18 +1,25 @@
 //! Health Check & Resource Profiler Tauri Commands
 //!
 //! Provides commands for monitoring connection health and remote resource metrics.
-//! Also includes smart port detection commands (detected_ports, ignore_port).
+//! Also includes smart port detection commands (detected_ports, get_ports, rescan_ports,
+//! ignore_port, unignore_port, clear_ignored).
 
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 use dashmap::DashMap;
 use tauri::State;
 
+use super::config::ConfigState;
 use crate::session::health::ResourceMetrics;
-use crate::session::profiler::{DetectedPort, ProfilerState, ResourceProfiler};
+use crate::session::profiler::{
+    custom_sample_command, DetectedPort, HistoryResolution, IgnoredPortsStore, ProfilerState,
+    ResourceProfiler, DEFAULT_EMIT_INTERVAL, DEFAULT_INTERVAL, MAX_INTERVAL, MIN_INTERVAL,
+};
 use crate::session::{HealthMetrics, HealthStatus, HealthTracker, QuickHealthCheck};
 use crate::ssh::SshConnectionRegistry;
+use crate::state::StateStore;
 
 /// Registry for health trackers
 pub struct HealthRegistry {
@@ -223,6 +230,22 @@ impl ProfilerRegistry {
             profiler.stop();
         }
     }
+
+    /// Describe the process the last port scan saw listening on `port`,
+    /// e.g. "node (pid 4242)". `None` if the port or its owner is unknown.
+    pub fn port_holder(&self, connection_id: &str, port: u16) -> Option<String> {
+        let entry = self.profilers.get(connection_id)?;
+        entry
+            .detected_ports()
+            .into_iter()
+            .filter(|p| p.port == port)
+            .find_map(|p| match (p.process_name, p.pid) {
+                (Some(name), Some(pid)) => Some(format!("{} (pid {})", name, pid)),
+                (Some(name), None) => Some(name),
+                (None, Some(pid)) => Some(format!("pid {}", pid)),
+                (None, None) => None,
+            })
+    }
 }
 
 impl Default for ProfilerRegistry {
@@ -235,13 +258,44 @@ impl Default for ProfilerRegistry {
 ///
 /// Idempotent: if a profiler is already running for this connection, returns Ok.
 /// This prevents React StrictMode double-mount from spawning duplicate profilers.
+///
+/// `emit_interval_ms` caps how often this connection's profiler events reach the
+/// frontend (default: `DEFAULT_EMIT_INTERVAL`).
+///
+/// `scan_udp` adds bound UDP sockets to port detection (default: TCP only).
+///
+/// `interval_ms` sets the sampling interval (1000–300000, default 10000).
+///
+/// `saved_connection_id` names the saved connection this runtime connection was
+/// opened from; ignored ports are persisted under it and restored on the next
+/// start. Without it they are kept in memory only.
+///
+/// `custom_command` replaces the built-in sampling command for this host; it
+/// must print the profiler's section markers and end with `===END===` (see
+/// `custom_sample_command`).
 #[tauri::command]
 pub async fn start_resource_profiler(
     connection_id: String,
+    emit_interval_ms: Option<u64>,
+    scan_udp: Option<bool>,
+    interval_ms: Option<u64>,
+    saved_connection_id: Option<String>,
+    custom_command: Option<String>,
     profiler_registry: State<'_, ProfilerRegistry>,
+    state_store: State<'_, Arc<StateStore>>,
     connection_registry: State<'_, Arc<SshConnectionRegistry>>,
+    config_state: State<'_, Arc<ConfigState>>,
     app_handle: tauri::AppHandle,
 ) -> Result<(), String> {
+    let interval = interval_ms
+        .map(sample_interval)
+        .transpose()?
+        .unwrap_or(DEFAULT_INTERVAL);
+    let custom_command = custom_command
+        .as_deref()
+        .map(custom_sample_command)
+        .transpose()?;
+
     // Idempotent: if already running, just return Ok
     if let Some(entry) = profiler_registry.profilers.get(&connection_id) {
         let state = entry.state().await;
@@ -258,15 +312,44 @@ pub async fn start_resource_profiler(
         .get_handle_controller(&connection_id)
         .ok_or_else(|| format!("Connection not found: {}", connection_id))?;
 
-    // Fetch remote OS type for platform-dispatched port detection commands
-    let os_type = connection_registry
+    // Fetch remote OS family for platform-dispatched port detection commands
+    // (Unknown falls back to the Linux commands)
+    let os_family = connection_registry
         .get_connection(&connection_id)
         .and_then(|entry| entry.remote_env())
-        .map(|env| env.os_type)
-        .unwrap_or_else(|| "Linux".to_string());
-
-    let profiler =
-        ResourceProfiler::spawn(connection_id.clone(), controller, app_handle, os_type);
+        .map(|env| env.os_family)
+        .unwrap_or_default();
+
+    let emit_interval = emit_interval_ms
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_EMIT_INTERVAL);
+
+    // Ignored ports persist per saved connection; find it by endpoint when
+    // the caller doesn't know it
+    let saved_connection_id = match saved_connection_id {
+        Some(id) => Some(id),
+        None => connection_registry
+            .get_info(&connection_id)
+            .await
+            .and_then(|info| {
+                config_state
+                    .get_config_snapshot()
+                    .find_by_endpoint(&info.host, info.port, &info.username)
+                    .map(|conn| conn.id.clone())
+            }),
+    };
+
+    let profiler = ResourceProfiler::spawn_with_interval(
+        connection_id.clone(),
+        controller,
+        app_handle,
+        os_family,
+        scan_udp.unwrap_or(false),
+        custom_command,
+        saved_connection_id.map(|id| IgnoredPortsStore::new(state_store.inner().clone(), id)),
+        emit_interval,
+        interval,
+    );
     profiler_registry.profilers.insert(connection_id, profiler);
 
     Ok(())
@@ -287,6 +370,51 @@ pub async fn stop_resource_profiler(
     }
 }
 
+/// Change the sampling interval of a running profiler (1000–300000 ms).
+/// Takes effect immediately; the next sample follows one new interval later.
+#[tauri::command]
+pub async fn set_profiler_interval(
+    connection_id: String,
+    millis: u64,
+    profiler_registry: State<'_, ProfilerRegistry>,
+) -> Result<(), String> {
+    let interval = sample_interval(millis)?;
+    let entry = profiler_registry
+        .profilers
+        .get(&connection_id)
+        .ok_or_else(|| format!("No resource profiler running for {}", connection_id))?;
+    entry.set_interval(interval);
+    Ok(())
+}
+
+/// Validate a sampling interval given in milliseconds
+fn sample_interval(millis: u64) -> Result<Duration, String> {
+    let interval = Duration::from_millis(millis);
+    if interval < MIN_INTERVAL || interval > MAX_INTERVAL {
+        return Err(format!(
+            "Sampling interval must be between {} and {} ms, got {}",
+            MIN_INTERVAL.as_millis(),
+            MAX_INTERVAL.as_millis(),
+            millis
+        ));
+    }
+    Ok(interval)
+}
+
+/// Get the profiler state for a connection (`None` if no profiler was started).
+/// A degraded profiler reports when it retries sampling.
+#[tauri::command]
+pub async fn get_profiler_state(
+    connection_id: String,
+    profiler_registry: State<'_, ProfilerRegistry>,
+) -> Result<Option<ProfilerState>, String> {
+    if let Some(entry) = profiler_registry.profilers.get(&connection_id) {
+        Ok(Some(entry.state().await))
+    } else {
+        Ok(None)
+    }
+}
+
 /// Get latest resource metrics for a connection
 #[tauri::command]
 pub async fn get_resource_metrics(
@@ -300,14 +428,16 @@ pub async fn get_resource_metrics(
     }
 }
 
-/// Get resource metrics history for sparkline rendering
+/// Get resource metrics history for sparkline rendering.
+/// `resolution` defaults to raw samples; coarser tiers cover hours instead of minutes.
 #[tauri::command]
 pub async fn get_resource_history(
     connection_id: String,
+    resolution: Option<HistoryResolution>,
     profiler_registry: State<'_, ProfilerRegistry>,
 ) -> Result<Vec<ResourceMetrics>, String> {
     if let Some(entry) = profiler_registry.profilers.get(&connection_id) {
-        Ok(entry.history().await)
+        Ok(entry.history(resolution.unwrap_or_default()).await)
     } else {
         Ok(Vec::new())
     }
@@ -329,8 +459,36 @@ pub async fn get_detected_ports(
     }
 }
 
-/// Ignore a port so it won't trigger notifications again (until profiler restart).
-/// Used when the user dismisses a port detection notification.
+/// Get the listening ports of the last complete scan, including the silent
+/// first one, so a freshly opened UI does not wait for a `port-detected` change.
+#[tauri::command]
+pub async fn profiler_get_ports(
+    connection_id: String,
+    profiler_registry: State<'_, ProfilerRegistry>,
+) -> Result<Vec<DetectedPort>, String> {
+    get_detected_ports(connection_id, profiler_registry).await
+}
+
+/// Force an immediate port scan (outside the sampling interval) and return its result.
+/// Lets the forwarding UI populate without waiting for the next interval or a change.
+#[tauri::command]
+pub async fn profiler_rescan_ports(
+    connection_id: String,
+    profiler_registry: State<'_, ProfilerRegistry>,
+) -> Result<Vec<DetectedPort>, String> {
+    // Take the future out so the map entry is not held across the await
+    let rescan = match profiler_registry.profilers.get(&connection_id) {
+        Some(entry) => entry.rescan_ports(),
+        None => return Ok(Vec::new()),
+    };
+    rescan
+        .await
+        .ok_or_else(|| format!("Port rescan for {} did not complete", connection_id))
+}
+
+/// Ignore a port so it won't trigger notifications again (until un-ignored).
+/// Used when the user dismisses a port detection notification. Persisted if the
+/// profiler was started with a saved connection ID.
 #[tauri::command]
 pub async fn ignore_detected_port(
     connection_id: String,
@@ -343,6 +501,33 @@ pub async fn ignore_detected_port(
     Ok(())
 }
 
+/// Un-ignore a port. If it is still listening, the next scan reports it as new again.
+/// Returns whether the port was ignored.
+#[tauri::command]
+pub async fn profiler_unignore_port(
+    connection_id: String,
+    port: u16,
+    profiler_registry: State<'_, ProfilerRegistry>,
+) -> Result<bool, String> {
+    Ok(profiler_registry
+        .profilers
+        .get(&connection_id)
+        .is_some_and(|entry| entry.unignore_port(port)))
+}
+
+/// Clear all ignored ports for a connection (e.g. after dismissing one by accident).
+/// Returns how many ports were un-ignored.
+#[tauri::command]
+pub async fn profiler_clear_ignored(
+    connection_id: String,
+    profiler_registry: State<'_, ProfilerRegistry>,
+) -> Result<usize, String> {
+    Ok(profiler_registry
+        .profilers
+        .get(&connection_id)
+        .map_or(0, |entry| entry.clear_ignored_ports()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -355,6 +540,15 @@ mod tests {
         assert_eq!(format_uptime(90061), "1d 1h");
     }
 
+    #[test]
+    fn test_sample_interval_range() {
+        assert_eq!(sample_interval(1000), Ok(Duration::from_secs(1)));
+        assert_eq!(sample_interval(300_000), Ok(Duration::from_secs(300)));
+        assert!(sample_interval(999).is_err());
+        assert!(sample_interval(300_001).is_err());
+        assert!(sample_interval(0).is_err());
+    }
+
     #[test]
     fn test_profiler_registry_new() {
         let registry = ProfilerRegistry::new();
@@ -2,10 +2,17 @@
 //!
 //! Commands for the lightweight IDE mode feature.
 
+use parking_lot::Mutex;
 use russh::ChannelMsg;
 use serde::Serialize;
-use std::sync::Arc;
-use tauri::State;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, State};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 
 use crate::sftp::session::SftpRegistry;
 use crate::sftp::types::{FileType, PreviewContent};
@@ -54,6 +61,37 @@ pub struct ExecResult {
     pub exit_code: Option<u32>,
 }
 
+/// Output event of a streamed remote command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecEvent {
+    /// Raw stdout bytes (chunks may split UTF-8 sequences)
+    Stdout(Vec<u8>),
+    /// Raw stderr bytes
+    Stderr(Vec<u8>),
+    /// Always the last event. `None` if the server never sent an exit
+    /// status (e.g. killed by a signal, or Close without ExitStatus).
+    Exit(Option<u32>),
+}
+
+/// Streamed exec output sent to the frontend as `ide:exec:{exec_id}`
+///
+/// Output is decoded as UTF-8 across chunk boundaries. A cancelled exec
+/// stops without an `exit` event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ExecStreamEvent {
+    Stdout {
+        data: String,
+    },
+    Stderr {
+        data: String,
+    },
+    Exit {
+        #[serde(rename = "exitCode")]
+        exit_code: Option<u32>,
+    },
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // Constants
 // ═══════════════════════════════════════════════════════════════════════════
@@ -222,11 +260,13 @@ pub(crate) async fn get_git_branch_inner(
 /// Execute a command on the remote server via SSH exec channel
 ///
 /// This is used for running commands like `grep` for search and `git status` for file status.
+/// `cwd` and `env` are applied with shell quoting (see `build_exec_command`).
 #[tauri::command]
 pub async fn ide_exec_command(
     connection_id: String,
     command: String,
     cwd: Option<String>,
+    env: Option<Vec<(String, String)>>,
     timeout_secs: Option<u64>,
     connection_registry: State<'_, Arc<SshConnectionRegistry>>,
 ) -> Result<ExecResult, String> {
@@ -234,7 +274,14 @@ pub async fn ide_exec_command(
         .get_handle_controller(&connection_id)
         .ok_or_else(|| format!("Connection not found: {}", connection_id))?;
 
-    exec_command_inner(controller, command, cwd, timeout_secs).await
+    exec_command_inner(
+        controller,
+        command,
+        cwd,
+        env.unwrap_or_default(),
+        timeout_secs,
+    )
+    .await
 }
 
 /// 执行远程命令的内部实现（供 node_ide_exec_command 复用）
@@ -242,37 +289,21 @@ pub(crate) async fn exec_command_inner(
     controller: crate::ssh::HandleController,
     command: String,
     cwd: Option<String>,
+    env: Vec<(String, String)>,
     timeout_secs: Option<u64>,
 ) -> Result<ExecResult, String> {
     use tokio::time::{timeout, Duration};
     use tracing::{debug, warn};
 
+    // Validate before opening a channel
+    let full_command = build_exec_command(&command, cwd, &env)?;
+
     // Open a new session channel
     let mut channel = controller
         .open_session_channel()
         .await
         .map_err(|e| format!("Failed to open exec channel: {}", e))?;
 
-    // Build command with optional cwd
-    let full_command = match cwd {
-        Some(dir) => {
-            // Handle ~ prefix: keep it outside quotes so the shell expands it
-            let cd_target = if dir == "~" {
-                "~".to_string()
-            } else if let Some(rest) = dir.strip_prefix("~/") {
-                if rest.is_empty() {
-                    "~".to_string()
-                } else {
-                    format!("~/{}", shell_escape(rest))
-                }
-            } else {
-                shell_escape(&dir)
-            };
-            format!("cd {} && {}", cd_target, command)
-        }
-        None => command.clone(),
-    };
-
     debug!("IDE exec: {}", full_command);
 
     // Execute the command
@@ -349,8 +380,508 @@ pub(crate) async fn exec_command_inner(
     })
 }
 
+/// 流式执行远程命令
+///
+/// stdout/stderr 分块按到达顺序推送，最后一个事件总是 `ExecEvent::Exit`。
+/// 没有整体超时：调用方丢弃 receiver 即取消，后台任务会立即关闭 exec 通道
+/// （即使此时远端没有任何输出）。
+pub async fn exec_command_streamed(
+    controller: crate::ssh::HandleController,
+    command: String,
+    cwd: Option<String>,
+    env: Vec<(String, String)>,
+) -> Result<mpsc::Receiver<ExecEvent>, String> {
+    use tracing::debug;
+
+    let full_command = build_exec_command(&command, cwd, &env)?;
+
+    let mut channel = controller
+        .open_session_channel()
+        .await
+        .map_err(|e| format!("Failed to open exec channel: {}", e))?;
+
+    debug!("IDE exec (streamed): {}", full_command);
+
+    channel
+        .exec(true, full_command)
+        .await
+        .map_err(|e| format!("Failed to execute command: {}", e))?;
+
+    let (tx, rx) = mpsc::channel(EXEC_STREAM_CAPACITY);
+    tokio::spawn(async move {
+        if !pump_exec_output(&mut channel, &tx).await {
+            debug!("IDE exec (streamed) cancelled: {}", command);
+        }
+        let _ = channel.close().await;
+    });
+
+    Ok(rx)
+}
+
+/// Cancel a streamed exec started with `node_ide_exec_streamed`
+///
+/// Returns false if it already finished (or never existed).
+#[tauri::command]
+pub async fn ide_exec_cancel(exec_id: String) -> Result<bool, String> {
+    Ok(match RUNNING_EXECS.lock().remove(&exec_id) {
+        Some(cancel) => {
+            cancel.cancel();
+            true
+        }
+        None => false,
+    })
+}
+
+/// Streamed execs in flight, by exec ID
+static RUNNING_EXECS: LazyLock<Mutex<HashMap<String, CancellationToken>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// 流式执行远程命令，并把输出作为 `ide:exec:{exec_id}` 事件推送给前端
+///
+/// `exec_id` 由前端生成，这样它能在命令启动前订阅事件，不会漏掉早期输出。
+pub(crate) async fn start_streamed_exec(
+    app_handle: AppHandle,
+    exec_id: String,
+    controller: crate::ssh::HandleController,
+    command: String,
+    cwd: Option<String>,
+    env: Vec<(String, String)>,
+) -> Result<(), String> {
+    let cancel = register_exec(&exec_id)?;
+    let mut rx = match exec_command_streamed(controller, command, cwd, env).await {
+        Ok(rx) => rx,
+        Err(e) => {
+            RUNNING_EXECS.lock().remove(&exec_id);
+            return Err(e);
+        }
+    };
+
+    tokio::spawn(async move {
+        let event_name = format!("ide:exec:{}", exec_id);
+        let mut stdout = Utf8Chunks::default();
+        let mut stderr = Utf8Chunks::default();
+        loop {
+            // Dropping `rx` on cancel makes the pump close the channel
+            let event = tokio::select! {
+                event = rx.recv() => event,
+                _ = cancel.cancelled() => break,
+            };
+            match event {
+                Some(ExecEvent::Stdout(data)) => {
+                    emit_output(&app_handle, &event_name, stdout.decode(&data), false)
+                }
+                Some(ExecEvent::Stderr(data)) => {
+                    emit_output(&app_handle, &event_name, stderr.decode(&data), true)
+                }
+                Some(ExecEvent::Exit(exit_code)) => {
+                    emit_output(&app_handle, &event_name, stdout.finish(), false);
+                    emit_output(&app_handle, &event_name, stderr.finish(), true);
+                    let _ = app_handle.emit(&event_name, ExecStreamEvent::Exit { exit_code });
+                }
+                None => break,
+            }
+        }
+        RUNNING_EXECS.lock().remove(&exec_id);
+    });
+
+    Ok(())
+}
+
+/// Claim `exec_id` in `RUNNING_EXECS`, failing if it is already taken
+fn register_exec(exec_id: &str) -> Result<CancellationToken, String> {
+    match RUNNING_EXECS.lock().entry(exec_id.to_string()) {
+        Entry::Occupied(_) => Err(format!("Exec {} is already running", exec_id)),
+        Entry::Vacant(slot) => Ok(slot.insert(CancellationToken::new()).clone()),
+    }
+}
+
+/// Emit decoded output, skipping chunks that decoded to nothing
+fn emit_output(app_handle: &AppHandle, event_name: &str, data: String, stderr: bool) {
+    if data.is_empty() {
+        return;
+    }
+    let event = if stderr {
+        ExecStreamEvent::Stderr { data }
+    } else {
+        ExecStreamEvent::Stdout { data }
+    };
+    let _ = app_handle.emit(event_name, event);
+}
+
+/// Decodes a byte stream as UTF-8, holding back a character split across
+/// chunks until the rest of it arrives
+#[derive(Debug, Default)]
+struct Utf8Chunks {
+    pending: Vec<u8>,
+}
+
+impl Utf8Chunks {
+    fn decode(&mut self, data: &[u8]) -> String {
+        self.pending.extend_from_slice(data);
+        let complete = match std::str::from_utf8(&self.pending) {
+            // Incomplete sequence at the end: keep it for the next chunk
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            // Valid, or really invalid (decoded lossily)
+            _ => self.pending.len(),
+        };
+        let text = String::from_utf8_lossy(&self.pending[..complete]).into_owned();
+        self.pending.drain(..complete);
+        text
+    }
+
+    /// Whatever is still held back, decoded lossily
+    fn finish(&mut self) -> String {
+        let text = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending.clear();
+        text
+    }
+}
+
+/// Buffered events per streamed exec before the reader applies backpressure
+const EXEC_STREAM_CAPACITY: usize = 64;
+
+/// How long to wait for ExitStatus once Eof has been seen
+const EXIT_STATUS_GRACE: Duration = Duration::from_secs(2);
+
+/// Exec channel message, reduced to what the output pump cares about
+#[derive(Debug)]
+enum ExecStep {
+    Stdout(Vec<u8>),
+    Stderr(Vec<u8>),
+    ExitStatus(u32),
+    Eof,
+}
+
+/// Source of exec channel messages (`None` = channel closed)
+trait ExecSource: Send {
+    fn next_step(&mut self) -> impl Future<Output = Option<ExecStep>> + Send;
+}
+
+impl ExecSource for russh::Channel<russh::client::Msg> {
+    fn next_step(&mut self) -> impl Future<Output = Option<ExecStep>> + Send {
+        async move {
+            loop {
+                match self.wait().await? {
+                    ChannelMsg::Data { data } => return Some(ExecStep::Stdout(data.to_vec())),
+                    // ext=1 is stderr
+                    ChannelMsg::ExtendedData { data, ext: 1 } => {
+                        return Some(ExecStep::Stderr(data.to_vec()))
+                    }
+                    ChannelMsg::ExitStatus { exit_status } => {
+                        return Some(ExecStep::ExitStatus(exit_status))
+                    }
+                    ChannelMsg::Eof => return Some(ExecStep::Eof),
+                    ChannelMsg::Close => return None,
+                    // Ignore other messages (WindowAdjusted, Success, etc.)
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Forward exec output to `tx` until the command finishes.
+///
+/// Servers differ in ordering: ExitStatus may come before Eof, after Eof, or
+/// not at all before Close. The pump finishes on Close, on Eof once an exit
+/// status is known, or `EXIT_STATUS_GRACE` after Eof otherwise.
+///
+/// Returns `false` if the receiver was dropped (cancelled).
+async fn pump_exec_output<S: ExecSource>(source: &mut S, tx: &mpsc::Sender<ExecEvent>) -> bool {
+    let mut exit_code = None;
+    let mut eof = false;
+
+    loop {
+        let next = async {
+            if eof {
+                tokio::time::timeout(EXIT_STATUS_GRACE, source.next_step())
+                    .await
+                    .unwrap_or(None)
+            } else {
+                source.next_step().await
+            }
+        };
+        let step = tokio::select! {
+            // Receiver dropped while the remote side is silent
+            _ = tx.closed() => return false,
+            step = next => step,
+        };
+
+        let event = match step {
+            Some(ExecStep::Stdout(data)) => ExecEvent::Stdout(data),
+            Some(ExecStep::Stderr(data)) => ExecEvent::Stderr(data),
+            Some(ExecStep::ExitStatus(code)) => {
+                exit_code = Some(code);
+                if eof {
+                    break;
+                }
+                continue;
+            }
+            Some(ExecStep::Eof) => {
+                if exit_code.is_some() {
+                    break;
+                }
+                eof = true;
+                continue;
+            }
+            None => break,
+        };
+        if tx.send(event).await.is_err() {
+            return false;
+        }
+    }
+
+    tx.send(ExecEvent::Exit(exit_code)).await.is_ok()
+}
+
+/// Build the remote command line with optional working directory and
+/// environment.
+///
+/// Produces `cd -- <dir> && export K='v' ... && <command>`. Directory and
+/// values are single-quoted (a leading `~` stays unquoted so the remote shell
+/// expands it). Newlines, carriage returns and NUL are rejected in `cwd` and
+/// `env`, and variable names must be valid shell identifiers. `command`
+/// itself is passed through untouched.
+fn build_exec_command(
+    command: &str,
+    cwd: Option<String>,
+    env: &[(String, String)],
+) -> Result<String, String> {
+    let mut prefix = Vec::new();
+
+    if let Some(dir) = cwd {
+        reject_line_breaks("cwd", &dir)?;
+        // Handle ~ prefix: keep it outside quotes so the shell expands it
+        let cd_target = if dir == "~" {
+            "~".to_string()
+        } else if let Some(rest) = dir.strip_prefix("~/") {
+            if rest.is_empty() {
+                "~".to_string()
+            } else {
+                format!("~/{}", shell_escape(rest))
+            }
+        } else {
+            shell_escape(&dir)
+        };
+        prefix.push(format!("cd -- {}", cd_target));
+    }
+
+    if !env.is_empty() {
+        let mut assignments = Vec::with_capacity(env.len());
+        for (key, value) in env {
+            if !is_shell_identifier(key) {
+                return Err(format!("Invalid environment variable name: {:?}", key));
+            }
+            reject_line_breaks(key, value)?;
+            assignments.push(format!("{}={}", key, shell_escape(value)));
+        }
+        prefix.push(format!("export {}", assignments.join(" ")));
+    }
+
+    prefix.push(command.to_string());
+    Ok(prefix.join(" && "))
+}
+
+/// Reject values that would break out of a single-line shell command
+fn reject_line_breaks(what: &str, value: &str) -> Result<(), String> {
+    if value.contains(['\n', '\r', '\0']) {
+        return Err(format!("{} must not contain line breaks or NUL", what));
+    }
+    Ok(())
+}
+
+/// `[A-Za-z_][A-Za-z0-9_]*`
+fn is_shell_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
 /// Escape a string for use in shell command
 fn shell_escape(s: &str) -> String {
     // Simple escaping - wrap in single quotes and escape single quotes
     format!("'{}'", s.replace('\'', "'\\''"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Scripted channel: each sent step is delivered in order; dropping the
+    /// sender acts as Close.
+    impl ExecSource for mpsc::UnboundedReceiver<ExecStep> {
+        fn next_step(&mut self) -> impl Future<Output = Option<ExecStep>> + Send {
+            self.recv()
+        }
+    }
+
+    /// Run the pump over `steps`; unless `close`, the channel stays open
+    /// afterwards (no Close ever arrives).
+    async fn run_pump(steps: Vec<ExecStep>, close: bool) -> (bool, Vec<ExecEvent>) {
+        let (step_tx, mut source) = mpsc::unbounded_channel();
+        for step in steps {
+            step_tx.send(step).unwrap();
+        }
+        let _open = (!close).then_some(step_tx);
+
+        let (tx, mut rx) = mpsc::channel(16);
+        let completed =
+            tokio::time::timeout(Duration::from_secs(1), pump_exec_output(&mut source, &tx))
+                .await
+                .expect("pump did not finish");
+        drop(tx);
+
+        let mut events = Vec::new();
+        while let Some(event) = rx.recv().await {
+            events.push(event);
+        }
+        (completed, events)
+    }
+
+    #[test]
+    fn test_utf8_chunks_hold_back_split_characters() {
+        let bytes = "日志 ok".as_bytes();
+        let mut chunks = Utf8Chunks::default();
+        assert_eq!(chunks.decode(&bytes[..2]), "");
+        assert_eq!(chunks.decode(&bytes[2..4]), "日");
+        assert_eq!(chunks.decode(&bytes[4..]), "志 ok");
+        assert_eq!(chunks.finish(), "");
+
+        // A truncated trailing character is flushed lossily at the end
+        assert_eq!(chunks.decode(&bytes[..1]), "");
+        assert_eq!(chunks.finish(), "\u{FFFD}");
+    }
+
+    #[tokio::test]
+    async fn test_exit_status_after_eof() {
+        // Channel stays open: the pump must not wait for Close
+        let (completed, events) = run_pump(
+            vec![
+                ExecStep::Stdout(b"building".to_vec()),
+                ExecStep::Stderr(b"warning".to_vec()),
+                ExecStep::Eof,
+                ExecStep::ExitStatus(3),
+            ],
+            false,
+        )
+        .await;
+
+        assert!(completed);
+        assert_eq!(
+            events,
+            vec![
+                ExecEvent::Stdout(b"building".to_vec()),
+                ExecEvent::Stderr(b"warning".to_vec()),
+                ExecEvent::Exit(Some(3)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_exit_status_before_eof_keeps_trailing_output() {
+        let (completed, events) = run_pump(
+            vec![
+                ExecStep::ExitStatus(0),
+                ExecStep::Stdout(b"tail".to_vec()),
+                ExecStep::Eof,
+            ],
+            false,
+        )
+        .await;
+
+        assert!(completed);
+        assert_eq!(
+            events,
+            vec![
+                ExecEvent::Stdout(b"tail".to_vec()),
+                ExecEvent::Exit(Some(0))
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_close_without_exit_status() {
+        let (completed, events) =
+            run_pump(vec![ExecStep::Stdout(b"x".to_vec()), ExecStep::Eof], true).await;
+
+        assert!(completed);
+        assert_eq!(
+            events,
+            vec![ExecEvent::Stdout(b"x".to_vec()), ExecEvent::Exit(None)]
+        );
+    }
+
+    #[test]
+    fn test_exec_id_cannot_be_claimed_twice() {
+        let cancel = register_exec("exec-dup").unwrap();
+        assert!(register_exec("exec-dup").is_err());
+        // The first claim is untouched
+        RUNNING_EXECS.lock().remove("exec-dup").unwrap().cancel();
+        assert!(cancel.is_cancelled());
+        assert!(register_exec("exec-dup").is_ok());
+        RUNNING_EXECS.lock().remove("exec-dup");
+    }
+
+    #[test]
+    fn test_cwd_with_spaces_is_quoted() {
+        assert_eq!(
+            build_exec_command("ls", Some("/opt/my app".to_string()), &[]).unwrap(),
+            "cd -- '/opt/my app' && ls"
+        );
+        // ~ stays outside the quotes so the remote shell expands it
+        assert_eq!(
+            build_exec_command("ls", Some("~/my dir".to_string()), &[]).unwrap(),
+            "cd -- ~/'my dir' && ls"
+        );
+        assert_eq!(build_exec_command("ls", None, &[]).unwrap(), "ls");
+    }
+
+    #[test]
+    fn test_cwd_special_characters_are_inert() {
+        assert_eq!(
+            build_exec_command("ls", Some("/srv/it's $HOME;`id`".to_string()), &[]).unwrap(),
+            r"cd -- '/srv/it'\''s $HOME;`id`' && ls"
+        );
+    }
+
+    #[test]
+    fn test_env_is_exported_with_quoting() {
+        let env = vec![
+            ("NODE_ENV".to_string(), "production".to_string()),
+            ("GREETING".to_string(), r#"it's a "test" $USER"#.to_string()),
+        ];
+        assert_eq!(
+            build_exec_command("npm start", Some("/opt/app".to_string()), &env).unwrap(),
+            r#"cd -- '/opt/app' && export NODE_ENV='production' GREETING='it'\''s a "test" $USER' && npm start"#
+        );
+    }
+
+    #[test]
+    fn test_rejects_line_breaks_and_bad_names() {
+        let var = |k: &str, v: &str| vec![(k.to_string(), v.to_string())];
+
+        assert!(build_exec_command("ls", Some("/tmp/a\nrm -rf ~".to_string()), &[]).is_err());
+        assert!(build_exec_command("ls", None, &var("X", "a\r\nb")).is_err());
+        assert!(build_exec_command("ls", None, &var("X", "a\0b")).is_err());
+        assert!(build_exec_command("ls", None, &var("1X", "a")).is_err());
+        assert!(build_exec_command("ls", None, &var("A=B", "a")).is_err());
+        assert!(build_exec_command("ls", None, &var("", "a")).is_err());
+        assert!(build_exec_command("ls", None, &var("_PATH2", "a")).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_dropping_receiver_cancels_silent_command() {
+        // Remote side is alive but produces nothing
+        let (_step_tx, mut source) = mpsc::unbounded_channel::<ExecStep>();
+        let (tx, rx) = mpsc::channel(16);
+
+        let pump = tokio::spawn(async move { pump_exec_output(&mut source, &tx).await });
+        drop(rx);
+
+        let completed = tokio::time::timeout(Duration::from_secs(1), pump)
+            .await
+            .expect("cancellation was not prompt")
+            .unwrap();
+        assert!(!completed);
+    }
+}
@@ -9,7 +9,7 @@
 //! - Immediate cleanup on failure/cancel
 //! - Direct connection only (no proxy chain support in MVP)
 
-use crate::bridge::WsBridge;
+use crate::bridge::{BridgeManager, BridgePolicy, WsBridge};
 use crate::session::{SessionConfig, SessionRegistry};
 use crate::ssh::{
     keyboard_interactive::{
@@ -44,6 +44,7 @@ const KBI_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
 pub async fn ssh_connect_kbi(
     app: AppHandle,
     registry: State<'_, Arc<SessionRegistry>>,
+    bridge_manager: State<'_, BridgeManager>,
     host: String,
     port: u16,
     username: String,
@@ -68,6 +69,7 @@ pub async fn ssh_connect_kbi(
         cols,
         rows,
         display_name,
+        bridge_manager.policy(),
     )
     .await;
 
@@ -144,6 +146,7 @@ async fn run_kbi_flow(
     cols: u32,
     rows: u32,
     display_name: Option<String>,
+    bridge_policy: BridgePolicy,
 ) -> Result<(String, u16, String), String> {
     // 1. Establish TCP connection and SSH handshake (with timeout)
     let addr = format!("{}:{}", host, port);
@@ -286,6 +289,7 @@ async fn run_kbi_flow(
         color: None,
         cols,
         rows,
+        proxy_command: None,
     };
 
     // Create session in registry
@@ -309,18 +313,24 @@ async fn run_kbi_flow(
             format!("Failed to open shell: {}", e)
         })?;
 
-    // Get command sender and scroll buffer
+    // Get command sender, scroll buffer and paste guard
     let cmd_tx = session_handle.cmd_tx.clone();
-    let scroll_buffer = registry
-        .with_session(&sid, |entry| entry.scroll_buffer.clone())
+    let (scroll_buffer, paste_guard) = registry
+        .with_session(&sid, |entry| {
+            (entry.scroll_buffer.clone(), entry.paste_guard.clone())
+        })
         .ok_or_else(|| {
             registry.remove(&sid);
             "Session not found in registry".to_string()
         })?;
+    let bridge_policy = BridgePolicy {
+        paste_guard: Some(paste_guard),
+        ..bridge_policy
+    };
 
     // Start WebSocket bridge with disconnect tracking
     let (_, ws_port, ws_token, _disconnect_rx) =
-        WsBridge::start_extended_with_disconnect(session_handle, scroll_buffer, false)
+        WsBridge::start_extended_with_policy(session_handle, scroll_buffer, false, bridge_policy)
             .await
             .map_err(|e| {
                 registry.remove(&sid);
@@ -335,6 +345,9 @@ async fn run_kbi_flow(
             format!("Failed to update session state: {}", e)
         })?;
 
+    // Held multi-line pastes reach the frontend as `session:paste_confirm`
+    super::ssh::spawn_paste_confirm_forwarder(app, &registry, sid.clone(), None);
+
     info!(
         "KBI flow {}: session {} created, ws://127.0.0.1:{}",
         auth_flow_id, sid, ws_port
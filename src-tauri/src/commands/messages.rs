@@ -0,0 +1,158 @@
+//! User-facing backend messages in the frontend's language
+//!
+//! Most command errors stay in English and are localized by the frontend
+//! from `CommandError::code`. The few messages shown verbatim to the user
+//! (.oxide import/export and password validation) are looked up here with
+//! the `Locale` the frontend passes along, instead of the backend picking a
+//! language on its own.
+
+use serde::{Deserialize, Deserializer};
+
+/// UI language of the caller
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    /// English, also the fallback for languages without a catalog
+    #[default]
+    En,
+    /// Simplified Chinese
+    Zh,
+    /// Traditional Chinese
+    ZhHant,
+}
+
+impl Locale {
+    /// Parse an i18next / BCP 47 tag such as `zh-CN` or `en-US`.
+    ///
+    /// Chinese picks the script from a `Hant` / `Hans` subtag, else from the
+    /// region (`TW`, `HK` and `MO` write Traditional). Unknown tags fall back
+    /// to English.
+    pub fn from_tag(tag: &str) -> Self {
+        let tag = tag.to_ascii_lowercase();
+        let mut subtags = tag.split(['-', '_']);
+        if subtags.next() != Some("zh") {
+            return Locale::En;
+        }
+        for subtag in subtags {
+            match subtag {
+                "hant" | "tw" | "hk" | "mo" => return Locale::ZhHant,
+                "hans" => return Locale::Zh,
+                _ => {}
+            }
+        }
+        Locale::Zh
+    }
+}
+
+impl<'de> Deserialize<'de> for Locale {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let tag = String::deserialize(deserializer)?;
+        Ok(Locale::from_tag(&tag))
+    }
+}
+
+/// Catalog keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Message {
+    OxideInvalidFile,
+    OxideDecryptionFailed,
+    OxideChecksumMismatch,
+    OxideIoFailed,
+    OxideProcessingFailed,
+    PasswordTooShort,
+    PasswordTooWeak,
+}
+
+impl Message {
+    pub fn text(self, locale: Locale) -> &'static str {
+        match locale {
+            Locale::En => self.en(),
+            Locale::Zh => self.zh(),
+            Locale::ZhHant => self.zh_hant(),
+        }
+    }
+
+    fn en(self) -> &'static str {
+        match self {
+            Message::OxideInvalidFile => "Invalid .oxide file",
+            Message::OxideDecryptionFailed => "Wrong password or corrupted file",
+            Message::OxideChecksumMismatch => {
+                "File checksum mismatch, data may have been tampered with"
+            }
+            Message::OxideIoFailed => "File I/O failed",
+            Message::OxideProcessingFailed => "Failed to process .oxide file",
+            Message::PasswordTooShort => "Password must be at least 12 characters",
+            Message::PasswordTooWeak => {
+                "Password must contain uppercase, lowercase, digit and special characters"
+            }
+        }
+    }
+
+    fn zh(self) -> &'static str {
+        match self {
+            Message::OxideInvalidFile => "无效的 .oxide 文件",
+            Message::OxideDecryptionFailed => "密码错误或文件已损坏",
+            Message::OxideChecksumMismatch => "文件校验失败，数据可能被篡改",
+            Message::OxideIoFailed => "文件读写失败",
+            Message::OxideProcessingFailed => "处理 .oxide 文件失败",
+            Message::PasswordTooShort => "密码长度至少 12 个字符",
+            Message::PasswordTooWeak => "密码必须包含大写、小写、数字和特殊字符",
+        }
+    }
+
+    fn zh_hant(self) -> &'static str {
+        match self {
+            Message::OxideInvalidFile => "無效的 .oxide 檔案",
+            Message::OxideDecryptionFailed => "密碼錯誤或檔案已損壞",
+            Message::OxideChecksumMismatch => "檔案驗證失敗，資料可能已被竄改",
+            Message::OxideIoFailed => "檔案讀寫失敗",
+            Message::OxideProcessingFailed => "處理 .oxide 檔案失敗",
+            Message::PasswordTooShort => "密碼長度至少需要 12 個字元",
+            Message::PasswordTooWeak => "密碼必須包含大小寫字母、數字和特殊字元",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decryption_failure_per_locale() {
+        let msg = Message::OxideDecryptionFailed;
+        assert_eq!(
+            msg.text(Locale::from_tag("en")),
+            "Wrong password or corrupted file"
+        );
+        assert_eq!(msg.text(Locale::from_tag("zh-CN")), "密码错误或文件已损坏");
+        assert_eq!(msg.text(Locale::from_tag("zh_TW")), "密碼錯誤或檔案已損壞");
+        // No Japanese catalog yet: English fallback
+        assert_eq!(
+            msg.text(Locale::from_tag("ja")),
+            "Wrong password or corrupted file"
+        );
+        assert_eq!(msg.text(Locale::default()), msg.text(Locale::En));
+    }
+
+    #[test]
+    fn test_chinese_script_from_tag() {
+        assert_eq!(Locale::from_tag("zh"), Locale::Zh);
+        assert_eq!(Locale::from_tag("zh-SG"), Locale::Zh);
+        assert_eq!(Locale::from_tag("zh-Hans-HK"), Locale::Zh);
+        assert_eq!(Locale::from_tag("zh-HK"), Locale::ZhHant);
+        assert_eq!(Locale::from_tag("zh-Hant"), Locale::ZhHant);
+        assert_eq!(Locale::from_tag("zh-hant-cn"), Locale::ZhHant);
+    }
+
+    #[test]
+    fn test_locale_deserializes_from_tag() {
+        let locale: Locale = serde_json::from_str("\"zh-CN\"").unwrap();
+        assert_eq!(locale, Locale::Zh);
+        let locale: Locale = serde_json::from_str("\"zh-TW\"").unwrap();
+        assert_eq!(locale, Locale::ZhHant);
+        let locale: Locale = serde_json::from_str("\"pt-BR\"").unwrap();
+        assert_eq!(locale, Locale::En);
+    }
+}
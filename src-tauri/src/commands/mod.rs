@@ -6,12 +6,14 @@ pub mod ai_chat;
 pub mod archive;
 pub mod config;
 mod connect_v2;
+pub mod error;
 pub mod forwarding;
 pub mod health;
 pub mod ide;
 pub mod kbi;
 #[cfg(feature = "local-terminal")]
 pub mod local;
+pub mod messages;
 pub mod network;
 
 // WSL Graphics commands — the actual module lives at src/graphics/
@@ -28,16 +30,19 @@ pub mod scroll;
 pub mod session_tree;
 pub mod sftp;
 pub mod ssh;
+pub mod state;
 
 pub use ai_chat::*;
 pub use archive::*;
 pub use connect_v2::*;
+pub use error::{CommandError, ErrorCode};
 pub use forwarding::*;
 pub use health::*;
 pub use ide::*;
 pub use kbi::*;
 #[cfg(feature = "local-terminal")]
 pub use local::*;
+pub use messages::Locale;
 pub use network::*;
 pub use node_agent::*;
 pub use node_forwarding::*;
@@ -49,3 +54,4 @@ pub use scroll::*;
 pub use session_tree::*;
 pub use sftp::*;
 pub use ssh::*;
+pub use state::*;
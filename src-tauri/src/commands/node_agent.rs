@@ -8,8 +8,12 @@
 //!
 //! - `node_agent_deploy` — deploy and start the agent
 //! - `node_agent_status` — check agent status
+//! - `node_agent_operation_mode` — which backend (agent or SFTP) serves file ops
+//! - `node_agent_usage` — agent memory use and uptime (diagnostics)
 //! - `node_agent_read_file` — read file via agent (with hash)
 //! - `node_agent_write_file` — atomic write via agent (with optimistic lock)
+//! - `node_agent_download_file` — stream a file of any size to disk (hash-verified)
+//! - `node_agent_stat` — file metadata, optionally with binary detection
 //! - `node_agent_list_tree` — recursive directory listing
 //! - `node_agent_grep` — full-text search
 //! - `node_agent_git_status` — git status
@@ -17,17 +21,21 @@
 //! - `node_agent_watch_stop` — stop file watching
 //! - `node_agent_remove` — remove agent binary from remote host
 
+use std::path::Path;
 use std::sync::Arc;
 
-use tauri::{AppHandle, Emitter, State};
+use tauri::{AppHandle, State};
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, warn};
 
 use crate::agent::{
-    AgentDeployer, AgentRegistry, AgentSession, AgentStatus, DeployError, GitStatusResult,
-    GrepMatch, ListTreeResult, ReadFileResult, SymbolIndexResult, SymbolInfo,
-    WriteFileResult,
+    AgentDeployer, AgentDeployment, AgentRegistry, AgentSession, AgentStatus, AgentUsage, DeployError, GitStatusResult,
+    GrepMatch, ListTreeResult, OperationMode, ReadFileResult, ReadFileStreamResult, SymbolIndexResult,
+    StatResult, SymbolInfo, WriteFileResult,
 };
+use crate::commands::{CommandError, ErrorCode};
 use crate::router::NodeRouter;
+use crate::ssh::HandleController;
 
 // ═══════════════════════════════════════════════════════════════════════════
 // Deploy & Status
@@ -40,43 +48,48 @@ pub async fn node_agent_deploy(
     router: State<'_, Arc<NodeRouter>>,
     agent_registry: State<'_, Arc<AgentRegistry>>,
     app_handle: AppHandle,
-) -> Result<AgentStatus, String> {
+) -> Result<AgentStatus, CommandError> {
     info!("[node_agent_deploy] Deploying agent for node {}", node_id);
 
     // Resolve connection
-    let resolved = router
-        .resolve_connection(&node_id)
-        .await
-        .map_err(|e| e.to_string())?;
+    let resolved = router.resolve_connection(&node_id).await?;
 
-    // Check if already deployed
-    if agent_registry.has_agent(&resolved.connection_id) {
-        let session = agent_registry.get(&resolved.connection_id).unwrap();
-        return Ok(session.status());
+    // Check if already deployed (or being restarted after a dropped channel)
+    if let Some(session) = agent_registry.get(&resolved.connection_id) {
+        if session.is_alive() || session.is_reconnecting() {
+            return Ok(session.status());
+        }
     }
 
     // Need SFTP for binary upload
-    let sftp_arc = router
-        .acquire_sftp(&node_id)
-        .await
-        .map_err(|e| e.to_string())?;
+    let sftp_arc = router.acquire_sftp(&node_id).await?;
     let sftp = sftp_arc.lock().await;
 
-    // Deploy
+    // Deploy, trying the directory that worked last time first
+    let preferred_dir = agent_registry.install_dir(&resolved.connection_id);
     match AgentDeployer::deploy_and_start(
         &resolved.handle_controller,
         &sftp,
         &app_handle,
+        preferred_dir.as_deref(),
     )
     .await
     {
-        Ok((transport, info)) => {
+        Ok(AgentDeployment {
+            transport,
+            info,
+            remote_dir,
+        }) => {
             let status = AgentStatus::Ready {
                 version: info.version.clone(),
                 arch: info.arch.clone(),
                 pid: info.pid,
             };
-            let session = AgentSession::new(transport, info);
+            let session = AgentSession::new(transport, info).with_restart(
+                resolved.handle_controller.clone(),
+                AgentDeployer::binary_path(&remote_dir),
+            );
+            agent_registry.set_install_dir(&resolved.connection_id, remote_dir);
             agent_registry.register(resolved.connection_id.clone(), session);
             Ok(status)
         }
@@ -103,46 +116,29 @@ pub async fn node_agent_deploy(
 ///
 /// 1. Shuts down the running agent process (if any)
 /// 2. Removes the registry entry
-/// 3. Resolves `$HOME` on the remote host, then deletes `$HOME/.oxideterm/oxideterm-agent`
+/// 3. Deletes the binary from the directory the last deploy used, or, if
+///    unknown, resolves `$HOME` and deletes `$HOME/.oxideterm/oxideterm-agent`
 ///
 /// Safety: uses `$HOME` (not `~`) for reliable expansion, validates the resolved
-/// path is non-empty, and only deletes the exact agent binary.
+/// path is absolute, and only deletes the exact agent binary.
 #[tauri::command]
 pub async fn node_agent_remove(
     node_id: String,
     router: State<'_, Arc<NodeRouter>>,
     agent_registry: State<'_, Arc<AgentRegistry>>,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     info!("[node_agent_remove] Removing agent for node {}", node_id);
 
-    let resolved = router
-        .resolve_connection(&node_id)
-        .await
-        .map_err(|e| e.to_string())?;
+    let resolved = router.resolve_connection(&node_id).await?;
 
     // Step 1: Shutdown agent session (sends sys/shutdown RPC + removes entry)
     agent_registry.remove(&resolved.connection_id).await;
 
-    // Step 2: Resolve $HOME on the remote host for reliable path expansion
-    let home_result = crate::commands::ide::exec_command_inner(
-        resolved.handle_controller.clone(),
-        "echo \"$HOME\"".to_string(),
-        None,
-        Some(10),
-    )
-    .await
-    .map_err(|e| format!("Failed to resolve HOME: {}", e))?;
-
-    let home = home_result.stdout.trim().to_string();
-    if home.is_empty() || !home.starts_with('/') {
-        return Err(format!(
-            "Cannot resolve HOME directory on remote host (got: {:?})",
-            home
-        ));
-    }
-
-    // Construct the exact path — only the agent binary, nothing else.
-    let agent_path = format!("{}/.oxideterm/oxideterm-agent", home);
+    // Step 2: Find the binary — the recorded install dir, else under $HOME
+    let agent_path = match agent_registry.install_dir(&resolved.connection_id) {
+        Some(dir) if dir.starts_with('/') => AgentDeployer::binary_path(&dir),
+        _ => default_agent_path(&resolved.handle_controller).await?,
+    };
 
     // Step 3: Delete the agent binary via SSH exec
     // Use -- to prevent argument injection, and single-quote the path
@@ -153,10 +149,13 @@ pub async fn node_agent_remove(
         resolved.handle_controller.clone(),
         rm_cmd,
         None,
+        Vec::new(),
         Some(15),
     )
     .await
-    .map_err(|e| format!("Failed to remove agent binary: {}", e))?;
+    .map_err(|e| {
+        CommandError::new(ErrorCode::AgentFailed, "Failed to remove agent binary").with_detail(e)
+    })?;
 
     if let Some(code) = result.exit_code {
         if code != 0 {
@@ -167,6 +166,7 @@ pub async fn node_agent_remove(
         }
     }
 
+    agent_registry.forget_install_dir(&resolved.connection_id);
     info!(
         "[node_agent_remove] Agent removed for node {} (path: {})",
         node_id, agent_path
@@ -174,17 +174,47 @@ pub async fn node_agent_remove(
     Ok(())
 }
 
+/// `$HOME/.oxideterm/oxideterm-agent` on the remote host, for agents
+/// deployed before install directories were recorded.
+async fn default_agent_path(
+    controller: &HandleController,
+) -> Result<String, CommandError> {
+    // Resolve $HOME on the remote host for reliable path expansion
+    let home_result = crate::commands::ide::exec_command_inner(
+        controller.clone(),
+        "echo \"$HOME\"".to_string(),
+        None,
+        Vec::new(),
+        Some(10),
+    )
+    .await
+    .map_err(|e| {
+        CommandError::new(ErrorCode::AgentFailed, "Failed to resolve HOME").with_detail(e)
+    })?;
+
+    let home = home_result.stdout.trim().to_string();
+    if home.is_empty() || !home.starts_with('/') {
+        return Err(CommandError::new(
+            ErrorCode::AgentFailed,
+            format!(
+                "Cannot resolve HOME directory on remote host (got: {:?})",
+                home
+            ),
+        ));
+    }
+
+    // Construct the exact path — only the agent binary, nothing else.
+    Ok(format!("{}/.oxideterm/oxideterm-agent", home))
+}
+
 /// Get agent status for a node.
 #[tauri::command]
 pub async fn node_agent_status(
     node_id: String,
     router: State<'_, Arc<NodeRouter>>,
     agent_registry: State<'_, Arc<AgentRegistry>>,
-) -> Result<AgentStatus, String> {
-    let resolved = router
-        .resolve_connection(&node_id)
-        .await
-        .map_err(|e| e.to_string())?;
+) -> Result<AgentStatus, CommandError> {
+    let resolved = router.resolve_connection(&node_id).await?;
 
     match agent_registry.get(&resolved.connection_id) {
         Some(session) => Ok(session.status()),
@@ -192,6 +222,36 @@ pub async fn node_agent_status(
     }
 }
 
+/// Get the backend currently serving file operations for a node.
+///
+/// Transitions are also pushed as `fs:backend_changed` events.
+#[tauri::command]
+pub async fn node_agent_operation_mode(
+    node_id: String,
+    router: State<'_, Arc<NodeRouter>>,
+    agent_registry: State<'_, Arc<AgentRegistry>>,
+) -> Result<OperationMode, CommandError> {
+    let resolved = router.resolve_connection(&node_id).await?;
+
+    Ok(agent_registry.mode_for(&resolved.connection_id))
+}
+
+/// Get the agent's self-reported memory use and uptime for a node.
+#[tauri::command]
+pub async fn node_agent_usage(
+    node_id: String,
+    router: State<'_, Arc<NodeRouter>>,
+    agent_registry: State<'_, Arc<AgentRegistry>>,
+) -> Result<AgentUsage, CommandError> {
+    let resolved = router.resolve_connection(&node_id).await?;
+
+    agent_registry
+        .usage(&resolved.connection_id)
+        .await
+        .ok_or_else(CommandError::agent_not_deployed)?
+        .map_err(CommandError::from)
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // File Operations (Agent-first with SFTP fallback)
 // ═══════════════════════════════════════════════════════════════════════════
@@ -203,20 +263,17 @@ pub async fn node_agent_read_file(
     path: String,
     router: State<'_, Arc<NodeRouter>>,
     agent_registry: State<'_, Arc<AgentRegistry>>,
-) -> Result<ReadFileResult, String> {
-    let resolved = router
-        .resolve_connection(&node_id)
-        .await
-        .map_err(|e| e.to_string())?;
+) -> Result<ReadFileResult, CommandError> {
+    let resolved = router.resolve_connection(&node_id).await?;
 
     let session = agent_registry
         .get(&resolved.connection_id)
-        .ok_or_else(|| "Agent not deployed".to_string())?;
+        .ok_or_else(CommandError::agent_not_deployed)?;
 
     session
         .read_file(&path)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
 }
 
 /// Write a file via agent (atomic write with optional optimistic lock).
@@ -228,45 +285,100 @@ pub async fn node_agent_write_file(
     expect_hash: Option<String>,
     router: State<'_, Arc<NodeRouter>>,
     agent_registry: State<'_, Arc<AgentRegistry>>,
-) -> Result<WriteFileResult, String> {
-    let resolved = router
-        .resolve_connection(&node_id)
-        .await
-        .map_err(|e| e.to_string())?;
+) -> Result<WriteFileResult, CommandError> {
+    let resolved = router.resolve_connection(&node_id).await?;
 
     let session = agent_registry
         .get(&resolved.connection_id)
-        .ok_or_else(|| "Agent not deployed".to_string())?;
+        .ok_or_else(CommandError::agent_not_deployed)?;
 
     session
         .write_file(&path, &content, expect_hash.as_deref())
         .await
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
+}
+
+/// Download a remote file of any size via agent, streaming it to `local_path`.
+///
+/// The content never sits in memory whole; it is verified against the
+/// agent's size and SHA-256 before `local_path` is replaced.
+#[tauri::command]
+pub async fn node_agent_download_file(
+    node_id: String,
+    remote_path: String,
+    local_path: String,
+    router: State<'_, Arc<NodeRouter>>,
+    agent_registry: State<'_, Arc<AgentRegistry>>,
+) -> Result<ReadFileStreamResult, CommandError> {
+    let resolved = router.resolve_connection(&node_id).await?;
+
+    let session = agent_registry
+        .get(&resolved.connection_id)
+        .ok_or_else(CommandError::agent_not_deployed)?;
+
+    session
+        .read_file_stream(
+            &remote_path,
+            Path::new(&local_path),
+            &CancellationToken::new(),
+        )
+        .await
+        .map_err(CommandError::from)
+}
+
+/// Get file/directory metadata via agent.
+///
+/// `detect_binary` makes the agent read the start of a regular file to fill
+/// in `is_binary`; leave it off when only metadata is needed.
+#[tauri::command]
+pub async fn node_agent_stat(
+    node_id: String,
+    path: String,
+    detect_binary: Option<bool>,
+    router: State<'_, Arc<NodeRouter>>,
+    agent_registry: State<'_, Arc<AgentRegistry>>,
+) -> Result<StatResult, CommandError> {
+    let resolved = router.resolve_connection(&node_id).await?;
+
+    let session = agent_registry
+        .get(&resolved.connection_id)
+        .ok_or_else(CommandError::agent_not_deployed)?;
+
+    session
+        .stat(&path, detect_binary.unwrap_or(false))
+        .await
+        .map_err(CommandError::from)
 }
 
 /// List directory tree (recursive) via agent — returns entries + truncation metadata.
+/// Pass `cursor` (a previous `next_cursor`) to fetch the next page.
 #[tauri::command]
 pub async fn node_agent_list_tree(
     node_id: String,
     path: String,
     max_depth: Option<u32>,
     max_entries: Option<u32>,
+    cursor: Option<String>,
+    respect_gitignore: Option<bool>,
     router: State<'_, Arc<NodeRouter>>,
     agent_registry: State<'_, Arc<AgentRegistry>>,
-) -> Result<ListTreeResult, String> {
-    let resolved = router
-        .resolve_connection(&node_id)
-        .await
-        .map_err(|e| e.to_string())?;
+) -> Result<ListTreeResult, CommandError> {
+    let resolved = router.resolve_connection(&node_id).await?;
 
     let session = agent_registry
         .get(&resolved.connection_id)
-        .ok_or_else(|| "Agent not deployed".to_string())?;
+        .ok_or_else(CommandError::agent_not_deployed)?;
 
     session
-        .list_tree(&path, max_depth, max_entries)
+        .list_tree(
+            &path,
+            max_depth,
+            max_entries,
+            cursor.as_deref(),
+            respect_gitignore.unwrap_or(false),
+        )
         .await
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
 }
 
 /// Search files for a pattern via agent.
@@ -277,22 +389,26 @@ pub async fn node_agent_grep(
     path: String,
     case_sensitive: Option<bool>,
     max_results: Option<u32>,
+    respect_gitignore: Option<bool>,
     router: State<'_, Arc<NodeRouter>>,
     agent_registry: State<'_, Arc<AgentRegistry>>,
-) -> Result<Vec<GrepMatch>, String> {
-    let resolved = router
-        .resolve_connection(&node_id)
-        .await
-        .map_err(|e| e.to_string())?;
+) -> Result<Vec<GrepMatch>, CommandError> {
+    let resolved = router.resolve_connection(&node_id).await?;
 
     let session = agent_registry
         .get(&resolved.connection_id)
-        .ok_or_else(|| "Agent not deployed".to_string())?;
+        .ok_or_else(CommandError::agent_not_deployed)?;
 
     session
-        .grep(&pattern, &path, case_sensitive.unwrap_or(false), max_results)
+        .grep(
+            &pattern,
+            &path,
+            case_sensitive.unwrap_or(false),
+            max_results,
+            respect_gitignore.unwrap_or(false),
+        )
         .await
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
 }
 
 /// Get git status via agent.
@@ -302,20 +418,17 @@ pub async fn node_agent_git_status(
     path: String,
     router: State<'_, Arc<NodeRouter>>,
     agent_registry: State<'_, Arc<AgentRegistry>>,
-) -> Result<GitStatusResult, String> {
-    let resolved = router
-        .resolve_connection(&node_id)
-        .await
-        .map_err(|e| e.to_string())?;
+) -> Result<GitStatusResult, CommandError> {
+    let resolved = router.resolve_connection(&node_id).await?;
 
     let session = agent_registry
         .get(&resolved.connection_id)
-        .ok_or_else(|| "Agent not deployed".to_string())?;
+        .ok_or_else(CommandError::agent_not_deployed)?;
 
     session
         .git_status(&path)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -323,27 +436,37 @@ pub async fn node_agent_git_status(
 // ═══════════════════════════════════════════════════════════════════════════
 
 /// Start watching a directory for changes via agent.
+///
+/// Also starts the connection's watch pump (once), which emits every change
+/// as `agent:watch:{connectionId}`. Returns the connection ID so the frontend
+/// can subscribe to that event.
+///
+/// `debounce_ms` is how long a path must be quiet before its change is
+/// reported (agent default: 100ms).
 #[tauri::command]
 pub async fn node_agent_watch_start(
     node_id: String,
     path: String,
     ignore: Option<Vec<String>>,
+    debounce_ms: Option<u64>,
     router: State<'_, Arc<NodeRouter>>,
     agent_registry: State<'_, Arc<AgentRegistry>>,
-) -> Result<(), String> {
-    let resolved = router
-        .resolve_connection(&node_id)
-        .await
-        .map_err(|e| e.to_string())?;
+) -> Result<String, CommandError> {
+    let resolved = router.resolve_connection(&node_id).await?;
 
     let session = agent_registry
         .get(&resolved.connection_id)
-        .ok_or_else(|| "Agent not deployed".to_string())?;
+        .ok_or_else(CommandError::agent_not_deployed)?;
 
     session
-        .watch_start(&path, ignore.unwrap_or_default())
+        .watch_start(&path, ignore.unwrap_or_default(), debounce_ms)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)?;
+
+    agent_registry
+        .start_watch_pump(&resolved.connection_id)
+        .await;
+    Ok(resolved.connection_id)
 }
 
 /// Stop watching a directory.
@@ -353,67 +476,17 @@ pub async fn node_agent_watch_stop(
     path: String,
     router: State<'_, Arc<NodeRouter>>,
     agent_registry: State<'_, Arc<AgentRegistry>>,
-) -> Result<(), String> {
-    let resolved = router
-        .resolve_connection(&node_id)
-        .await
-        .map_err(|e| e.to_string())?;
+) -> Result<(), CommandError> {
+    let resolved = router.resolve_connection(&node_id).await?;
 
     let session = agent_registry
         .get(&resolved.connection_id)
-        .ok_or_else(|| "Agent not deployed".to_string())?;
+        .ok_or_else(CommandError::agent_not_deployed)?;
 
     session
         .watch_stop(&path)
         .await
-        .map_err(|e| e.to_string())
-}
-
-/// Start relaying watch events from the agent to Tauri frontend events.
-///
-/// Spawns a background task that reads from the agent's watch channel
-/// and emits `agent:watch-event:{nodeId}` events to the frontend.
-/// The task automatically stops when the agent channel closes.
-#[tauri::command]
-pub async fn node_agent_start_watch_relay(
-    node_id: String,
-    router: State<'_, Arc<NodeRouter>>,
-    agent_registry: State<'_, Arc<AgentRegistry>>,
-    app_handle: AppHandle,
-) -> Result<(), String> {
-    let resolved = router
-        .resolve_connection(&node_id)
-        .await
-        .map_err(|e| e.to_string())?;
-
-    let session = agent_registry
-        .get(&resolved.connection_id)
-        .ok_or_else(|| "Agent not deployed".to_string())?;
-
-    let mut watch_rx = session
-        .take_watch_rx()
-        .await
-        .ok_or_else(|| "Watch relay already started".to_string())?;
-
-    let node_id_clone = node_id.clone();
-    let event_name = format!("agent:watch-event:{}", node_id);
-
-    tokio::spawn(async move {
-        info!("[agent-watch-relay] Started for node {}", node_id_clone);
-        while let Some(event) = watch_rx.recv().await {
-            debug!(
-                "[agent-watch-relay] {} — {:?} {}",
-                node_id_clone, event.kind, event.path
-            );
-            if let Err(e) = app_handle.emit(&event_name, &event) {
-                warn!("[agent-watch-relay] Failed to emit: {}", e);
-                break;
-            }
-        }
-        info!("[agent-watch-relay] Ended for node {}", node_id_clone);
-    });
-
-    Ok(())
+        .map_err(CommandError::from)
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -428,20 +501,17 @@ pub async fn node_agent_symbol_index(
     max_files: Option<u32>,
     router: State<'_, Arc<NodeRouter>>,
     agent_registry: State<'_, Arc<AgentRegistry>>,
-) -> Result<SymbolIndexResult, String> {
-    let resolved = router
-        .resolve_connection(&node_id)
-        .await
-        .map_err(|e| e.to_string())?;
+) -> Result<SymbolIndexResult, CommandError> {
+    let resolved = router.resolve_connection(&node_id).await?;
 
     let session = agent_registry
         .get(&resolved.connection_id)
-        .ok_or_else(|| "Agent not deployed".to_string())?;
+        .ok_or_else(CommandError::agent_not_deployed)?;
 
     let result: SymbolIndexResult = session
         .symbol_index(&path, max_files)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
     Ok(result)
 }
 
@@ -454,20 +524,17 @@ pub async fn node_agent_symbol_complete(
     limit: Option<u32>,
     router: State<'_, Arc<NodeRouter>>,
     agent_registry: State<'_, Arc<AgentRegistry>>,
-) -> Result<Vec<SymbolInfo>, String> {
-    let resolved = router
-        .resolve_connection(&node_id)
-        .await
-        .map_err(|e| e.to_string())?;
+) -> Result<Vec<SymbolInfo>, CommandError> {
+    let resolved = router.resolve_connection(&node_id).await?;
 
     let session = agent_registry
         .get(&resolved.connection_id)
-        .ok_or_else(|| "Agent not deployed".to_string())?;
+        .ok_or_else(CommandError::agent_not_deployed)?;
 
     let result: Vec<SymbolInfo> = session
         .symbol_complete(&path, &prefix, limit)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
     Ok(result)
 }
 
@@ -479,19 +546,16 @@ pub async fn node_agent_symbol_definitions(
     name: String,
     router: State<'_, Arc<NodeRouter>>,
     agent_registry: State<'_, Arc<AgentRegistry>>,
-) -> Result<Vec<SymbolInfo>, String> {
-    let resolved = router
-        .resolve_connection(&node_id)
-        .await
-        .map_err(|e| e.to_string())?;
+) -> Result<Vec<SymbolInfo>, CommandError> {
+    let resolved = router.resolve_connection(&node_id).await?;
 
     let session = agent_registry
         .get(&resolved.connection_id)
-        .ok_or_else(|| "Agent not deployed".to_string())?;
+        .ok_or_else(CommandError::agent_not_deployed)?;
 
     let result: Vec<SymbolInfo> = session
         .symbol_definitions(&path, &name)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(CommandError::from)?;
     Ok(result)
 }
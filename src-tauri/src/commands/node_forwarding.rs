@@ -12,8 +12,10 @@ use tauri::State;
 use tracing::{error, info, warn};
 
 use crate::commands::forwarding::{
-    ForwardResponse, ForwardRuleDto, ForwardStatsDto, ForwardingRegistry, PersistedForwardDto,
+    with_remote_port_holder, ForwardResponse, ForwardRuleDto, ForwardStatsDto, ForwardingRegistry,
+    PersistedForwardDto,
 };
+use crate::commands::health::ProfilerRegistry;
 use crate::forwarding::{ForwardRule, ForwardRuleUpdate, ForwardStatus, ForwardType};
 use crate::router::{NodeRouter, RouteError};
 
@@ -63,6 +65,7 @@ pub async fn node_create_forward(
     router: State<'_, Arc<NodeRouter>>,
     registry: State<'_, Arc<ForwardingRegistry>>,
     connection_registry: State<'_, Arc<crate::ssh::SshConnectionRegistry>>,
+    profiler_registry: State<'_, ProfilerRegistry>,
 ) -> Result<ForwardResponse, RouteError> {
     let session_id = resolve_terminal_session_id(&router, &node_id).await?;
     info!(
@@ -162,11 +165,19 @@ pub async fn node_create_forward(
                 error: None,
             })
         }
-        Err(e) => Ok(ForwardResponse {
-            success: false,
-            forward: None,
-            error: Some(e.to_string()),
-        }),
+        Err(e) => {
+            let connection_id = router
+                .resolve_connection(&node_id)
+                .await
+                .ok()
+                .map(|r| r.connection_id);
+            let e = with_remote_port_holder(e, &profiler_registry, connection_id.as_deref());
+            Ok(ForwardResponse {
+                success: false,
+                forward: None,
+                error: Some(e.to_string()),
+            })
+        }
     }
 }
 
@@ -439,6 +450,7 @@ pub async fn node_list_saved_forwards(
             target_host: f.rule.target_host,
             target_port: f.rule.target_port,
             auto_start: f.auto_start,
+            logging: f.logging,
             created_at: f.created_at.to_rfc3339(),
         })
         .collect())
@@ -662,6 +662,7 @@ pub async fn node_ide_exec_command(
     node_id: String,
     command: String,
     cwd: Option<String>,
+    env: Option<Vec<(String, String)>>,
     timeout_secs: Option<u64>,
     router: State<'_, Arc<NodeRouter>>,
     connection_registry: State<'_, Arc<SshConnectionRegistry>>,
@@ -676,9 +677,50 @@ pub async fn node_ide_exec_command(
             RouteError::NotConnected(format!("Connection {} not found", resolved.connection_id))
         })?;
 
-    crate::commands::ide::exec_command_inner(controller, command, cwd, timeout_secs)
-        .await
-        .map_err(|e| RouteError::SftpOperationError(e))
+    crate::commands::ide::exec_command_inner(
+        controller,
+        command,
+        cwd,
+        env.unwrap_or_default(),
+        timeout_secs,
+    )
+    .await
+    .map_err(|e| RouteError::SftpOperationError(e))
+}
+
+/// IDE: 通过 nodeId 流式执行远程命令
+///
+/// 输出以 `ide:exec:{exec_id}` 事件推送（见 `ExecStreamEvent`），
+/// 用 `ide_exec_cancel` 取消。
+#[tauri::command]
+pub async fn node_ide_exec_streamed(
+    node_id: String,
+    exec_id: String,
+    command: String,
+    cwd: Option<String>,
+    env: Option<Vec<(String, String)>>,
+    app_handle: AppHandle,
+    router: State<'_, Arc<NodeRouter>>,
+    connection_registry: State<'_, Arc<SshConnectionRegistry>>,
+) -> Result<(), RouteError> {
+    let resolved = router.resolve_connection(&node_id).await?;
+
+    let controller = connection_registry
+        .get_handle_controller(&resolved.connection_id)
+        .ok_or_else(|| {
+            RouteError::NotConnected(format!("Connection {} not found", resolved.connection_id))
+        })?;
+
+    crate::commands::ide::start_streamed_exec(
+        app_handle,
+        exec_id,
+        controller,
+        command,
+        cwd,
+        env.unwrap_or_default(),
+    )
+    .await
+    .map_err(RouteError::SftpOperationError)
 }
 
 /// Check if a file is editable (node-first)
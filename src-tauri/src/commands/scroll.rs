@@ -1,11 +1,15 @@
 //! Tauri commands for scroll buffer management
 
+use std::collections::HashMap;
 use std::sync::Arc;
 use tauri::State;
 
 use serde::Serialize;
 
-use crate::session::{BufferStats, SearchOptions, SearchResult, SessionRegistry, TerminalLine};
+use crate::session::{
+    render_search_report, search_buffers, BufferStats, ReportFormat, SearchOptions, SearchResult,
+    SessionRegistry, TerminalLine,
+};
 
 /// Response for get_all_buffer_lines with truncation metadata
 #[derive(Debug, Clone, Serialize)]
@@ -100,6 +104,42 @@ pub async fn search_terminal(
     Ok(scroll_buffer.search(options).await)
 }
 
+/// Search the scroll buffers of all sessions at once
+///
+/// Results are keyed by session ID. `options.max_matches` is shared across
+/// sessions so a single noisy session can't use up the whole budget.
+#[tauri::command]
+pub async fn search_all_sessions(
+    options: SearchOptions,
+    registry: State<'_, Arc<SessionRegistry>>,
+) -> Result<HashMap<String, SearchResult>, String> {
+    let mut buffers = Vec::new();
+    for (session_id, scroll_buffer) in registry.scroll_buffers() {
+        buffers.push((session_id, scroll_buffer.get_all().await));
+    }
+
+    Ok(search_buffers(buffers, options).await)
+}
+
+/// Render search results as a shareable report
+///
+/// Returns the report; when `path` is given it is also written there.
+#[tauri::command]
+pub async fn search_export_results(
+    query: String,
+    result: SearchResult,
+    format: ReportFormat,
+    path: Option<String>,
+) -> Result<String, String> {
+    let report = render_search_report(&query, &result, format);
+    if let Some(path) = path {
+        tokio::fs::write(&path, &report)
+            .await
+            .map_err(|e| format!("Failed to write report to {}: {}", path, e))?;
+    }
+    Ok(report)
+}
+
 /// Scroll to specific line and get context
 #[tauri::command]
 pub async fn scroll_to_line(
@@ -2,20 +2,33 @@
 //!
 //! Tauri commands for managing the dynamic jump host session tree.
 
-use std::sync::Arc;
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
 
 use serde::{Deserialize, Serialize};
-use tauri::State;
-use tokio::sync::RwLock;
-
-use crate::session::tree::{FlatNode, NodeConnection, NodeOrigin, NodeState, SessionTree};
+use tauri::{AppHandle, Emitter, State};
+use tokio::sync::{broadcast, RwLock};
+
+use super::{CommandError, ErrorCode};
+use crate::session::event_names::TREE_NODE_STATE;
+use crate::session::tree::{
+    FlatNode, LostConnection, NodeConnection, NodeOrigin, NodeState, PersistedAuth,
+    PersistedSessionTree, SessionNode, SessionTree, TreeError,
+};
 use crate::session::types::SessionConfig;
-use crate::session::AuthMethod;
+use crate::session::{AuthMethod, TreeNodeStatePayload};
 use crate::ssh::SshConnectionRegistry;
+use crate::state::StateStore;
 
 /// Session Tree 状态（全局单例）
 pub struct SessionTreeState {
     pub tree: RwLock<SessionTree>,
+    /// 树结构快照的持久化目标（None = 不持久化）
+    store: Option<Arc<StateStore>>,
+    /// 保存的连接，用于查找节点凭据的钥匙串引用（启动后由 `attach_config` 设置）
+    config: OnceLock<Arc<ConfigState>>,
+    /// 恢复节点尚未解析的钥匙串引用（节点 ID → 认证方式），首次连接时解析
+    restored_auth: parking_lot::Mutex<HashMap<String, PersistedAuth>>,
 }
 
 impl Default for SessionTreeState {
@@ -28,7 +41,158 @@ impl SessionTreeState {
     pub fn new() -> Self {
         Self {
             tree: RwLock::new(SessionTree::new()),
+            store: None,
+            config: OnceLock::new(),
+            restored_auth: parking_lot::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 从 `store` 恢复上次保存的树结构（节点均为 Disconnected），并在之后的
+    /// 每次结构变化时写回
+    ///
+    /// 凭据只以钥匙串引用的形式恢复，见 [`Self::resolve_restored_auth`]。
+    pub fn with_store(store: Arc<StateStore>) -> Self {
+        let persisted = match store.load_session_tree() {
+            Ok(Some(data)) => match PersistedSessionTree::from_bytes(&data) {
+                Ok(persisted) => persisted,
+                Err(e) => {
+                    tracing::warn!("Discarding unreadable session tree snapshot: {}", e);
+                    PersistedSessionTree::default()
+                }
+            },
+            Ok(None) => PersistedSessionTree::default(),
+            Err(e) => {
+                tracing::warn!("Failed to load session tree snapshot: {}", e);
+                PersistedSessionTree::default()
+            }
+        };
+
+        let mut restored_auth: HashMap<String, PersistedAuth> = persisted
+            .nodes
+            .iter()
+            .filter(|node| node.auth.has_keychain_ref())
+            .map(|node| (node.id.clone(), node.auth.clone()))
+            .collect();
+        let tree = SessionTree::from_persisted(persisted);
+        restored_auth.retain(|id, _| tree.get_node(id).is_some());
+
+        if !tree.is_empty() {
+            tracing::info!("Restored session tree with {} nodes", tree.len());
+        }
+
+        Self {
+            tree: RwLock::new(tree),
+            store: Some(store),
+            config: OnceLock::new(),
+            restored_auth: parking_lot::Mutex::new(restored_auth),
+        }
+    }
+
+    /// 设置用于查找钥匙串引用的配置（只生效一次）
+    pub fn attach_config(&self, config: Arc<ConfigState>) {
+        if self.config.set(config).is_err() {
+            tracing::warn!("Session tree config already attached");
+        }
+    }
+
+    /// 保存树结构快照（节点增删后调用，调用方持有写锁）
+    ///
+    /// 节点凭据只保存钥匙串引用：尚未解析的恢复节点沿用原引用，其余节点
+    /// 从对应的保存连接查找。失败只记录警告：持久化不应阻断树操作本身。
+    pub fn persist(&self, tree: &SessionTree) {
+        if let Err(e) = self.try_persist(tree) {
+            tracing::warn!("Failed to persist session tree: {}", e);
+        }
+    }
+
+    /// 同 [`Self::persist`]，但返回失败
+    fn try_persist(&self, tree: &SessionTree) -> Result<(), String> {
+        let Some(store) = &self.store else {
+            return Ok(());
+        };
+        let config = self.config.get().map(|c| c.get_config_snapshot());
+        let persisted = {
+            let mut restored_auth = self.restored_auth.lock();
+            restored_auth.retain(|id, _| tree.get_node(id).is_some());
+            tree.to_persisted_with(|node| {
+                if let Some(auth) = restored_auth.get(&node.id) {
+                    return auth.clone();
+                }
+                let auth = PersistedAuth::from(&node.connection.auth);
+                match config.as_ref().and_then(|c| saved_auth_for_node(c, node)) {
+                    Some(saved) => with_keychain_refs(auth, saved),
+                    None => auth,
+                }
+            })
+        };
+        persisted
+            .to_bytes()
+            .map_err(|e| e.to_string())
+            .and_then(|data| store.save_session_tree(&data).map_err(|e| e.to_string()))
+    }
+
+    /// 凭据整理后把钥匙串引用改为新 ID（旧 ID → 新 ID），并重写快照
+    ///
+    /// 须在删除旧钥匙串条目之前调用。尚未解析的恢复节点沿用的是旧引用；
+    /// 其余节点保存时从连接配置查找，配置已指向新 ID。
+    pub async fn rename_keychain_refs(
+        &self,
+        renamed: &HashMap<String, String>,
+    ) -> Result<(), String> {
+        if renamed.is_empty() {
+            return Ok(());
+        }
+        for auth in self.restored_auth.lock().values_mut() {
+            auth.rename_keychain_refs(renamed);
         }
+        self.try_persist(&*self.tree.read().await)
+    }
+
+    /// 经 `ConfigState` 解析恢复节点的钥匙串引用，把凭据写回节点（连接前调用）
+    ///
+    /// 没有待解析引用的节点不做任何事；配置尚未就绪时保留引用，下次再试。
+    pub async fn resolve_restored_auth(&self, node_ids: &[String]) -> Result<(), String> {
+        let Some(config) = self.config.get() else {
+            return Ok(());
+        };
+        for node_id in node_ids {
+            let Some(auth_ref) = self.restored_auth.lock().get(node_id).cloned() else {
+                continue;
+            };
+            let auth = auth_ref.resolve(|id| config.lookup_keychain_value(id))?;
+            if let Some(node) = self.tree.write().await.get_node_mut(node_id) {
+                node.connection.auth = auth;
+            }
+            self.restored_auth.lock().remove(node_id);
+        }
+        Ok(())
+    }
+
+    /// 等待节点的 SSH 连接断开，然后将其子树标记为 Disconnected
+    ///
+    /// 见 `SessionTree::mark_connection_lost`
+    pub async fn wait_connection_lost(
+        &self,
+        node_id: &str,
+        ssh_connection_id: &str,
+        mut disconnect_rx: broadcast::Receiver<()>,
+    ) -> LostConnection {
+        // Closed（发送端已释放）同样表示连接已结束
+        let _ = disconnect_rx.recv().await;
+        self.tree
+            .write()
+            .await
+            .mark_connection_lost(node_id, ssh_connection_id)
+    }
+
+    /// 搜索节点，可选按状态过滤（见 `SessionTree::search_nodes`）
+    pub async fn search_tree_nodes(
+        &self,
+        query: &str,
+        state_filter: Option<NodeState>,
+    ) -> Vec<FlatNode> {
+        let tree = self.tree.read().await;
+        tree.search_nodes(query, state_filter.as_ref())
     }
 }
 
@@ -49,6 +213,9 @@ pub struct ConnectServerRequest {
     pub key_path: Option<String>,
     pub passphrase: Option<String>,
     pub display_name: Option<String>,
+    /// OpenSSH-style ProxyCommand for this direct connection
+    #[serde(default)]
+    pub proxy_command: Option<String>,
 }
 
 fn default_auth_type() -> String {
@@ -71,6 +238,16 @@ pub struct DrillDownRequest {
     pub display_name: Option<String>,
 }
 
+/// 钻入响应
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DrillDownResponse {
+    /// 新建子节点 ID
+    pub node_id: String,
+    /// 新节点的端点已出现在祖先链上时，该祖先节点的 ID（仅提示，不阻止钻入）
+    pub cycle_ancestor: Option<String>,
+}
+
 /// 预设链连接请求
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -92,6 +269,9 @@ pub struct HopInfo {
     pub password: Option<String>,
     pub key_path: Option<String>,
     pub passphrase: Option<String>,
+    /// ProxyCommand（仅第一跳直连时使用）
+    #[serde(default)]
+    pub proxy_command: Option<String>,
 }
 
 /// 会话树摘要信息
@@ -144,6 +324,98 @@ fn build_connection(
     conn
 }
 
+/// 树节点对应的保存凭据
+///
+/// 预设链节点按来源中的保存连接和跳数查找；其余节点按端点匹配保存连接，
+/// 再匹配各连接的代理链跳板。
+fn saved_auth_for_node<'a>(config: &'a ConfigFile, node: &SessionNode) -> Option<&'a SavedAuth> {
+    match &node.origin {
+        NodeOrigin::ManualPreset {
+            saved_connection_id,
+            hop_index,
+        } => {
+            let conn = config.get_connection(saved_connection_id)?;
+            match conn.proxy_chain.get(*hop_index as usize) {
+                Some(hop) => Some(&hop.auth),
+                None => Some(&conn.auth),
+            }
+        }
+        NodeOrigin::Restored {
+            saved_connection_id,
+        } => config
+            .get_connection(saved_connection_id)
+            .map(|conn| &conn.auth),
+        _ => {
+            let target = &node.connection;
+            config
+                .connections
+                .iter()
+                .find(|conn| {
+                    conn.host == target.host
+                        && conn.port == target.port
+                        && conn.username == target.username
+                })
+                .map(|conn| &conn.auth)
+                .or_else(|| {
+                    config
+                        .connections
+                        .iter()
+                        .flat_map(|conn| &conn.proxy_chain)
+                        .find(|hop| {
+                            hop.host == target.host
+                                && hop.port == target.port
+                                && hop.username == target.username
+                        })
+                        .map(|hop| &hop.auth)
+                })
+        }
+    }
+}
+
+/// 给持久化的认证方式附上保存凭据中的钥匙串引用（认证类型一致时）
+fn with_keychain_refs(auth: PersistedAuth, saved: &SavedAuth) -> PersistedAuth {
+    match (auth, saved) {
+        (PersistedAuth::Password { .. }, SavedAuth::Password { keychain_id }) => {
+            PersistedAuth::Password {
+                keychain_id: Some(keychain_id.clone()),
+            }
+        }
+        (
+            PersistedAuth::Key {
+                key_path,
+                has_passphrase,
+                ..
+            },
+            SavedAuth::Key {
+                passphrase_keychain_id,
+                ..
+            },
+        ) => PersistedAuth::Key {
+            key_path,
+            has_passphrase,
+            passphrase_keychain_id: passphrase_keychain_id.clone(),
+        },
+        (
+            PersistedAuth::Certificate {
+                key_path,
+                cert_path,
+                has_passphrase,
+                ..
+            },
+            SavedAuth::Certificate {
+                passphrase_keychain_id,
+                ..
+            },
+        ) => PersistedAuth::Certificate {
+            key_path,
+            cert_path,
+            has_passphrase,
+            passphrase_keychain_id: passphrase_keychain_id.clone(),
+        },
+        (auth, _) => auth,
+    }
+}
+
 // ============================================================================
 // Tauri Commands
 // ============================================================================
@@ -157,6 +429,16 @@ pub async fn get_session_tree(
     Ok(tree.flatten())
 }
 
+/// 搜索会话树节点（host / display_name / username，大小写不敏感）
+#[tauri::command]
+pub async fn search_session_tree(
+    state: State<'_, Arc<SessionTreeState>>,
+    query: String,
+    state_filter: Option<NodeState>,
+) -> Result<Vec<FlatNode>, String> {
+    Ok(state.search_tree_nodes(&query, state_filter).await)
+}
+
 /// 获取会话树摘要信息
 #[tauri::command]
 pub async fn get_session_tree_summary(
@@ -196,16 +478,18 @@ pub async fn add_root_node(
         request.passphrase,
     )?;
 
-    let connection = build_connection(
+    let mut connection = build_connection(
         request.host,
         request.port,
         request.username,
         auth,
         request.display_name,
     );
+    connection.proxy_command = request.proxy_command;
 
     let mut tree = state.tree.write().await;
     let node_id = tree.add_root_node(connection, NodeOrigin::Direct);
+    state.persist(&tree);
 
     tracing::info!("Added root node: {}", node_id);
     Ok(node_id)
@@ -219,7 +503,7 @@ pub async fn add_root_node(
 pub async fn tree_drill_down(
     state: State<'_, Arc<SessionTreeState>>,
     request: DrillDownRequest,
-) -> Result<String, String> {
+) -> Result<DrillDownResponse, String> {
     let auth = build_auth(
         &request.auth_type,
         request.password,
@@ -236,16 +520,29 @@ pub async fn tree_drill_down(
     );
 
     let mut tree = state.tree.write().await;
-    let node_id = tree
-        .drill_down(&request.parent_node_id, connection)
+    let outcome = tree
+        .drill_down_flagged(&request.parent_node_id, connection)
         .map_err(|e| e.to_string())?;
+    state.persist(&tree);
+
+    // 回到祖先端点只会形成多余的嵌套隧道，但经端口转发等场景下是合理的：交由前端提示
+    if let Some(ancestor) = &outcome.cycle_ancestor {
+        tracing::warn!(
+            "Node {} revisits the endpoint of ancestor {}",
+            outcome.node_id,
+            ancestor
+        );
+    }
 
     tracing::info!(
         "Drilled down from {} to new node {}",
         request.parent_node_id,
-        node_id
+        outcome.node_id
     );
-    Ok(node_id)
+    Ok(DrillDownResponse {
+        node_id: outcome.node_id,
+        cycle_ancestor: outcome.cycle_ancestor,
+    })
 }
 
 /// 展开手工预设链响应
@@ -282,13 +579,15 @@ pub async fn expand_manual_preset(
             hop.key_path.clone(),
             hop.passphrase.clone(),
         )?;
-        hops.push(build_connection(
+        let mut connection = build_connection(
             hop.host.clone(),
             hop.port,
             hop.username.clone(),
             auth,
             None,
-        ));
+        );
+        connection.proxy_command = hop.proxy_command.clone();
+        hops.push(connection);
     }
 
     let target_auth = build_auth(
@@ -297,19 +596,23 @@ pub async fn expand_manual_preset(
         request.target.key_path.clone(),
         request.target.passphrase.clone(),
     )?;
-    let target = build_connection(
+    let mut target = build_connection(
         request.target.host.clone(),
         request.target.port,
         request.target.username.clone(),
         target_auth,
         None,
     );
+    target.proxy_command = request.target.proxy_command.clone();
 
     // 展开为树节点
     let target_node_id = {
         let mut tree = state.tree.write().await;
-        tree.expand_manual_preset(&request.saved_connection_id, hops, target)
-            .map_err(|e| e.to_string())?
+        let target_node_id = tree
+            .expand_manual_preset(&request.saved_connection_id, hops, target)
+            .map_err(|e| e.to_string())?;
+        state.persist(&tree);
+        target_node_id
     };
 
     // 收集从根到目标的路径
@@ -474,6 +777,7 @@ pub async fn remove_tree_node(
     // 3. 从树中移除节点
     let mut tree = state.tree.write().await;
     let removed = tree.remove_node(&node_id).map_err(|e| e.to_string())?;
+    state.persist(&tree);
 
     // 4. 清理 sequencer 中对应节点的 generation 计数器（防止 DashMap 泄漏）
     let sequencer = emitter.sequencer();
@@ -576,6 +880,7 @@ pub async fn clear_session_tree(
     // 4. 清空树
     let mut tree = state.tree.write().await;
     *tree = SessionTree::new();
+    state.persist(&tree);
     tracing::info!(
         "Session tree cleared ({} nodes, connections disconnected, sequencer cleaned)",
         nodes_to_cleanup.len()
@@ -619,31 +924,44 @@ pub struct ConnectTreeNodeResponse {
 /// - 对于子节点（depth>0），通过父节点的隧道建立连接
 #[tauri::command]
 pub async fn connect_tree_node(
+    app_handle: AppHandle,
     state: State<'_, Arc<SessionTreeState>>,
     connection_registry: State<'_, Arc<SshConnectionRegistry>>,
     request: ConnectTreeNodeRequest,
-) -> Result<ConnectTreeNodeResponse, String> {
+) -> Result<ConnectTreeNodeResponse, CommandError> {
     let node_id = request.node_id.clone();
 
+    // 恢复的节点只有钥匙串引用：连接前取回凭据
+    state.resolve_restored_auth(&[node_id.clone()]).await?;
+
     // 1. 获取节点信息并构建 SessionConfig
     let (session_config, parent_node_id) = {
         let tree = state.tree.read().await;
         let node = tree
             .get_node(&node_id)
-            .ok_or_else(|| format!("Node not found: {}", node_id))?;
+            .ok_or_else(|| CommandError::not_found(format!("Node not found: {}", node_id)))?;
 
         // 确保节点状态允许连接
         match &node.state {
             NodeState::Pending | NodeState::Disconnected => {}
             NodeState::Failed { .. } => {}
             NodeState::Connecting => {
-                return Err(format!("Node {} is already connecting", node_id));
+                return Err(CommandError::invalid_input(format!(
+                    "Node {} is already connecting",
+                    node_id
+                )));
             }
             NodeState::Connected => {
-                return Err(format!("Node {} is already connected", node_id));
+                return Err(CommandError::invalid_input(format!(
+                    "Node {} is already connected",
+                    node_id
+                )));
             }
         }
 
+        // 超出深度限制的节点不做任何 SSH 操作
+        SessionTree::check_depth(node.depth)?;
+
         // 构建 SessionConfig
         let config = SessionConfig {
             host: node.connection.host.clone(),
@@ -654,6 +972,7 @@ pub async fn connect_tree_node(
             color: None,
             cols: request.cols,
             rows: request.rows,
+            proxy_command: node.connection.proxy_command.clone(),
         };
 
         (config, node.parent_id.clone())
@@ -662,8 +981,7 @@ pub async fn connect_tree_node(
     // 2. 更新节点状态为 Connecting
     {
         let mut tree = state.tree.write().await;
-        tree.update_state(&node_id, NodeState::Connecting)
-            .map_err(|e| e.to_string())?;
+        tree.update_state(&node_id, NodeState::Connecting)?;
     }
 
     // 3. 根据是否有父节点决定连接方式
@@ -671,14 +989,16 @@ pub async fn connect_tree_node(
         // 有父节点 - 先获取父节点的 SSH 连接 ID
         let parent_ssh_id = {
             let tree = state.tree.read().await;
-            let parent_node = tree
-                .get_node(parent_id)
-                .ok_or_else(|| format!("Parent node not found: {}", parent_id))?;
-
-            parent_node
-                .ssh_connection_id
-                .clone()
-                .ok_or_else(|| format!("Parent node {} has no SSH connection", parent_id))?
+            let parent_node = tree.get_node(parent_id).ok_or_else(|| {
+                CommandError::not_found(format!("Parent node not found: {}", parent_id))
+            })?;
+
+            parent_node.ssh_connection_id.clone().ok_or_else(|| {
+                CommandError::new(
+                    ErrorCode::NotConnected,
+                    format!("Parent node {} has no SSH connection", parent_id),
+                )
+            })?
         };
 
         // 通过父连接建立隧道连接
@@ -693,7 +1013,7 @@ pub async fn connect_tree_node(
             .establish_tunneled_connection(&parent_ssh_id, session_config)
             .await
             .map(|id| (id, Some(parent_ssh_id)))
-            .map_err(|e| e.to_string())
+            .map_err(CommandError::from)
     } else {
         // 无父节点 - 直接连接
         tracing::info!("Connecting root node {} directly", node_id);
@@ -702,7 +1022,7 @@ pub async fn connect_tree_node(
             .connect(session_config)
             .await
             .map(|id| (id, None))
-            .map_err(|e| e.to_string())
+            .map_err(CommandError::from)
     };
 
     // 4. 根据连接结果更新节点状态
@@ -711,12 +1031,10 @@ pub async fn connect_tree_node(
             let mut tree = state.tree.write().await;
 
             // 更新状态为已连接
-            tree.update_state(&node_id, NodeState::Connected)
-                .map_err(|e| e.to_string())?;
+            tree.update_state(&node_id, NodeState::Connected)?;
 
             // 关联 SSH 连接 ID
-            tree.set_ssh_connection_id(&node_id, ssh_connection_id.clone())
-                .map_err(|e| e.to_string())?;
+            tree.set_ssh_connection_id(&node_id, ssh_connection_id.clone())?;
 
             tracing::info!(
                 "Node {} connected with ssh_id: {}, parent_ssh_id: {:?}",
@@ -725,6 +1043,19 @@ pub async fn connect_tree_node(
                 parent_connection_id
             );
 
+            // 监听连接断开，父隧道失效时级联标记子树
+            if let Some(controller) = connection_registry.get_handle_controller(&ssh_connection_id)
+            {
+                spawn_disconnect_watcher(
+                    app_handle,
+                    state.inner().clone(),
+                    connection_registry.inner().clone(),
+                    node_id.clone(),
+                    ssh_connection_id.clone(),
+                    controller.subscribe_disconnect(),
+                );
+            }
+
             Ok(ConnectTreeNodeResponse {
                 node_id,
                 ssh_connection_id,
@@ -735,8 +1066,12 @@ pub async fn connect_tree_node(
             let mut tree = state.tree.write().await;
 
             // 更新状态为失败
-            tree.update_state(&node_id, NodeState::Failed { error: e.clone() })
-                .map_err(|err| err.to_string())?;
+            tree.update_state(
+                &node_id,
+                NodeState::Failed {
+                    error: e.to_string(),
+                },
+            )?;
 
             tracing::error!("Failed to connect node {}: {}", node_id, e);
             Err(e)
@@ -744,48 +1079,68 @@ pub async fn connect_tree_node(
     }
 }
 
-/// 断开会话树节点
+/// 后台监听节点的 SSH 连接断开
 ///
-/// 断开节点的 SSH 连接，并递归断开所有子节点
-#[tauri::command]
-pub async fn disconnect_tree_node(
-    state: State<'_, Arc<SessionTreeState>>,
-    connection_registry: State<'_, Arc<SshConnectionRegistry>>,
+/// 断开后节点及其子树被标记为 Disconnected，每个变化的节点发送一次 `tree:node_state`。
+fn spawn_disconnect_watcher(
+    app_handle: AppHandle,
+    state: Arc<SessionTreeState>,
+    connection_registry: Arc<SshConnectionRegistry>,
     node_id: String,
-) -> Result<Vec<String>, String> {
-    // 1. 收集需要断开的节点（自底向上的顺序）
-    let nodes_to_disconnect: Vec<(String, Option<String>)> = {
-        let tree = state.tree.read().await;
+    ssh_connection_id: String,
+    disconnect_rx: broadcast::Receiver<()>,
+) {
+    tokio::spawn(async move {
+        let lost = state
+            .wait_connection_lost(&node_id, &ssh_connection_id, disconnect_rx)
+            .await;
+        disconnect_orphaned(&connection_registry, &lost.orphaned).await;
+        if lost.changed.is_empty() {
+            return;
+        }
 
-        // 获取从此节点开始的所有子树节点
-        fn collect_subtree(
-            tree: &SessionTree,
-            node_id: &str,
-            result: &mut Vec<(String, Option<String>)>,
-        ) {
-            if let Some(node) = tree.get_node(node_id) {
-                // 先处理所有子节点
-                for child_id in &node.children_ids {
-                    collect_subtree(tree, child_id, result);
-                }
-                // 最后处理自己
-                result.push((node_id.to_string(), node.ssh_connection_id.clone()));
+        tracing::info!(
+            "SSH connection {} of node {} lost, {} tree node(s) marked disconnected",
+            ssh_connection_id,
+            node_id,
+            lost.changed.len()
+        );
+        for nid in lost.changed {
+            let payload = TreeNodeStatePayload {
+                node_id: nid,
+                state: NodeState::Disconnected,
+            };
+            if let Err(e) = app_handle.emit(TREE_NODE_STATE, &payload) {
+                tracing::warn!("Failed to emit {}: {}", TREE_NODE_STATE, e);
             }
         }
+    });
+}
 
-        let mut nodes = Vec::new();
-        collect_subtree(&tree, &node_id, &mut nodes);
-        nodes
-    };
-
-    if nodes_to_disconnect.is_empty() {
-        return Err(format!("Node not found: {}", node_id));
+/// 断开父隧道失效后残留在注册表中的子孙连接
+async fn disconnect_orphaned(connection_registry: &SshConnectionRegistry, ssh_ids: &[String]) {
+    for ssh_connection_id in ssh_ids {
+        if let Err(e) = connection_registry.disconnect(ssh_connection_id).await {
+            tracing::warn!(
+                "Failed to disconnect SSH connection {}: {}",
+                ssh_connection_id,
+                e
+            );
+        }
     }
+}
 
+/// 依次断开节点的 SSH 连接并标记为 Disconnected
+///
+/// 单个节点失败只记录警告，不中断整体操作。
+async fn disconnect_nodes_in_order(
+    state: &SessionTreeState,
+    connection_registry: &SshConnectionRegistry,
+    nodes: Vec<(String, Option<String>)>,
+) -> Vec<String> {
     let mut disconnected_ids = Vec::new();
 
-    // 2. 按顺序断开连接（先子节点，后父节点）
-    for (nid, ssh_id) in nodes_to_disconnect {
+    for (nid, ssh_id) in nodes {
         if let Some(ssh_connection_id) = ssh_id {
             // 断开 SSH 连接
             if let Err(e) = connection_registry.disconnect(&ssh_connection_id).await {
@@ -799,20 +1154,42 @@ pub async fn disconnect_tree_node(
 
         // 更新节点状态和清除所有会话元数据
         let mut tree = state.tree.write().await;
-        if let Err(e) = tree.update_state(&nid, NodeState::Disconnected) {
+        if let Err(e) = tree.mark_disconnected(&nid) {
             tracing::warn!("Failed to update node {} state: {}", nid, e);
         }
 
-        // 清除所有关联的会话 ID
-        if let Some(node) = tree.get_node_mut(&nid) {
-            node.ssh_connection_id = None;
-            node.terminal_session_id = None;
-            node.sftp_session_id = None;
-        }
-
         disconnected_ids.push(nid);
     }
 
+    disconnected_ids
+}
+
+/// 断开会话树节点
+///
+/// 断开节点的 SSH 连接，并递归断开所有子节点
+#[tauri::command]
+pub async fn disconnect_tree_node(
+    state: State<'_, Arc<SessionTreeState>>,
+    connection_registry: State<'_, Arc<SshConnectionRegistry>>,
+    node_id: String,
+) -> Result<Vec<String>, String> {
+    // 1. 收集需要断开的节点（自底向上的顺序）
+    let nodes_to_disconnect: Vec<(String, Option<String>)> = {
+        let tree = state.tree.read().await;
+        tree.subtree_bottom_up(&node_id)
+            .into_iter()
+            .map(|node| (node.id.clone(), node.ssh_connection_id.clone()))
+            .collect()
+    };
+
+    if nodes_to_disconnect.is_empty() {
+        return Err(format!("Node not found: {}", node_id));
+    }
+
+    // 2. 按顺序断开连接（先子节点，后父节点）
+    let disconnected_ids =
+        disconnect_nodes_in_order(&state, &connection_registry, nodes_to_disconnect).await;
+
     tracing::info!(
         "Disconnected {} nodes starting from {}",
         disconnected_ids.len(),
@@ -821,6 +1198,30 @@ pub async fn disconnect_tree_node(
     Ok(disconnected_ids)
 }
 
+/// 断开整个会话树
+///
+/// 跨所有根节点自底向上断开（叶子先于根），返回断开的节点 ID。
+/// 空树返回空列表。
+#[tauri::command]
+pub async fn disconnect_all_tree_nodes(
+    state: State<'_, Arc<SessionTreeState>>,
+    connection_registry: State<'_, Arc<SshConnectionRegistry>>,
+) -> Result<Vec<String>, String> {
+    let nodes_to_disconnect: Vec<(String, Option<String>)> = {
+        let tree = state.tree.read().await;
+        tree.all_nodes_bottom_up()
+            .into_iter()
+            .map(|node| (node.id.clone(), node.ssh_connection_id.clone()))
+            .collect()
+    };
+
+    let disconnected_ids =
+        disconnect_nodes_in_order(&state, &connection_registry, nodes_to_disconnect).await;
+
+    tracing::info!("Disconnected all {} tree nodes", disconnected_ids.len());
+    Ok(disconnected_ids)
+}
+
 /// 连接预设的手工跳板链（模式1: 静态全手工）
 ///
 /// 此命令会：
@@ -847,13 +1248,15 @@ pub async fn connect_manual_preset(
             hop.key_path.clone(),
             hop.passphrase.clone(),
         )?;
-        hops.push(build_connection(
+        let mut connection = build_connection(
             hop.host.clone(),
             hop.port,
             hop.username.clone(),
             auth,
             None,
-        ));
+        );
+        connection.proxy_command = hop.proxy_command.clone();
+        hops.push(connection);
     }
 
     let target_auth = build_auth(
@@ -862,19 +1265,23 @@ pub async fn connect_manual_preset(
         request.target.key_path.clone(),
         request.target.passphrase.clone(),
     )?;
-    let target = build_connection(
+    let mut target = build_connection(
         request.target.host.clone(),
         request.target.port,
         request.target.username.clone(),
         target_auth,
         None,
     );
+    target.proxy_command = request.target.proxy_command.clone();
 
     // 2. 展开为树节点
     let target_node_id = {
         let mut tree = state.tree.write().await;
-        tree.expand_manual_preset(&request.saved_connection_id, hops, target)
-            .map_err(|e| e.to_string())?
+        let target_node_id = tree
+            .expand_manual_preset(&request.saved_connection_id, hops, target)
+            .map_err(|e| e.to_string())?;
+        state.persist(&tree);
+        target_node_id
     };
 
     tracing::info!(
@@ -923,6 +1330,7 @@ pub async fn connect_manual_preset(
                 color: None,
                 cols,
                 rows,
+                proxy_command: node.connection.proxy_command.clone(),
             };
 
             // 获取父节点的 SSH 连接 ID（如果有）
@@ -1054,69 +1462,492 @@ pub struct ConnectManualPresetResponse {
     pub chain_depth: u32,
 }
 
-// ============================================================================
-// Auto-Route Commands (Mode 2: Static Auto-Route)
-// ============================================================================
-
-use super::config::ConfigState;
-use crate::session::topology_graph::{
-    NetworkTopology, TopologyEdge, TopologyEdgesConfig, TopologyNodeInfo,
-};
-
-/// Get topology nodes (auto-generated from saved connections)
-#[tauri::command]
-pub async fn get_topology_nodes(
-    config_state: State<'_, Arc<ConfigState>>,
-) -> Result<Vec<TopologyNodeInfo>, String> {
-    // Load saved connections from config snapshot
-    let config = config_state.get_config_snapshot();
-    let connections = &config.connections;
-
-    // Build topology from connections
-    let topology = NetworkTopology::build_from_connections(connections);
-
-    Ok(topology.get_all_nodes())
-}
-
-/// Get topology edges
-#[tauri::command]
-pub async fn get_topology_edges(
-    config_state: State<'_, Arc<ConfigState>>,
-) -> Result<Vec<TopologyEdge>, String> {
-    let config = config_state.get_config_snapshot();
-    let connections = &config.connections;
-    let topology = NetworkTopology::build_from_connections(connections);
-    Ok(topology.get_all_edges())
+/// 重连链路区段响应
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconnectChainSegmentResponse {
+    /// 目标节点 ID
+    pub target_node_id: String,
+    /// 目标节点的 SSH 连接 ID
+    pub target_ssh_connection_id: String,
+    /// 重新建立连接的节点 ID（从断点到目标）
+    pub reconnected_node_ids: Vec<String>,
+    /// 仍然存活、未被触碰的上游节点 ID
+    pub untouched_node_ids: Vec<String>,
 }
 
-/// Get custom edges overlay config
-#[tauri::command]
-pub async fn get_topology_edges_overlay() -> Result<TopologyEdgesConfig, String> {
-    Ok(NetworkTopology::get_edges_overlay())
+/// 重连子树响应
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconnectSubtreeResponse {
+    /// 子树根节点 ID
+    pub node_id: String,
+    /// 重新建立连接的节点 ID（自顶向下，可能包含断开的祖先）
+    pub reconnected_node_ids: Vec<String>,
+    /// 已连接、被跳过的子树节点 ID
+    pub skipped_node_ids: Vec<String>,
 }
 
-/// Add a custom edge to topology
-#[tauri::command]
-pub async fn add_topology_edge(from: String, to: String, cost: Option<i32>) -> Result<(), String> {
-    NetworkTopology::add_custom_edge(from, to, cost.unwrap_or(1))
+/// 发送单个节点的 `tree:node_state` 事件
+fn emit_node_state(app_handle: &AppHandle, node_id: &str, state: NodeState) {
+    let payload = TreeNodeStatePayload {
+        node_id: node_id.to_string(),
+        state,
+    };
+    if let Err(e) = app_handle.emit(TREE_NODE_STATE, &payload) {
+        tracing::warn!("Failed to emit {}: {}", TREE_NODE_STATE, e);
+    }
 }
 
-/// Remove a custom edge from topology
-#[tauri::command]
-pub async fn remove_topology_edge(from: String, to: String) -> Result<(), String> {
-    NetworkTopology::remove_custom_edge(&from, &to)
+/// 以注册表为准，把 `node_ids` 中已失效的连接同步到会话树
+///
+/// 断开通知可能尚未到达；受影响的子树标记为 Disconnected 并发送事件。
+async fn sync_lost_connections(
+    app_handle: &AppHandle,
+    state: &SessionTreeState,
+    connection_registry: &SshConnectionRegistry,
+    node_ids: &[String],
+) {
+    let stale: Vec<(String, String)> = {
+        let tree = state.tree.read().await;
+        node_ids
+            .iter()
+            .filter_map(|id| tree.get_node(id))
+            .filter_map(|node| {
+                let ssh_id = node.ssh_connection_id.as_ref()?;
+                (!connection_registry.is_alive(ssh_id)).then(|| (node.id.clone(), ssh_id.clone()))
+            })
+            .collect()
+    };
+    for (node_id, ssh_id) in stale {
+        let lost = state
+            .tree
+            .write()
+            .await
+            .mark_connection_lost(&node_id, &ssh_id);
+        disconnect_orphaned(connection_registry, &lost.orphaned).await;
+        for nid in lost.changed {
+            emit_node_state(app_handle, &nid, NodeState::Disconnected);
+        }
+    }
 }
 
-/// Exclude an auto-generated edge
-#[tauri::command]
-pub async fn exclude_topology_edge(from: String, to: String) -> Result<(), String> {
-    NetworkTopology::exclude_edge(from, to)
+/// `node_ids` 中有节点正在连接时拒绝重连，避免与进行中的连接竞争
+fn ensure_none_connecting(tree: &SessionTree, node_ids: &[String]) -> Result<(), CommandError> {
+    match node_ids
+        .iter()
+        .filter_map(|id| tree.get_node(id))
+        .find(|node| node.state == NodeState::Connecting)
+    {
+        Some(node) => Err(CommandError::invalid_input(format!(
+            "Node {} is already connecting",
+            node.id
+        ))),
+        None => Ok(()),
+    }
 }
 
-/// Auto-route expand request
-#[derive(Debug, Clone, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct ExpandAutoRouteRequest {
+/// 经父节点隧道（根节点直连）重连单个节点，返回新的 SSH 连接 ID
+///
+/// 父节点须已连接。状态变化会发送 `tree:node_state`；失败时节点标记为 Failed。
+async fn reconnect_node(
+    app_handle: &AppHandle,
+    state: &Arc<SessionTreeState>,
+    connection_registry: &Arc<SshConnectionRegistry>,
+    node_id: &str,
+    cols: u32,
+    rows: u32,
+) -> Result<String, CommandError> {
+    let (session_config, parent_ssh_id) = {
+        let tree = state.tree.read().await;
+        let node = tree
+            .get_node(node_id)
+            .ok_or_else(|| CommandError::not_found(format!("Node not found: {}", node_id)))?;
+        let config = SessionConfig {
+            host: node.connection.host.clone(),
+            port: node.connection.port,
+            username: node.connection.username.clone(),
+            auth: node.connection.auth.clone(),
+            name: node.connection.display_name.clone(),
+            color: None,
+            cols,
+            rows,
+            proxy_command: node.connection.proxy_command.clone(),
+        };
+        let parent_ssh_id = node
+            .parent_id
+            .as_ref()
+            .and_then(|parent_id| tree.get_node(parent_id))
+            .and_then(|parent| parent.ssh_connection_id.clone());
+        (config, parent_ssh_id)
+    };
+
+    state
+        .tree
+        .write()
+        .await
+        .update_state(node_id, NodeState::Connecting)?;
+    emit_node_state(app_handle, node_id, NodeState::Connecting);
+
+    let connect_result = match parent_ssh_id {
+        Some(parent_ssh_id) => {
+            connection_registry
+                .establish_tunneled_connection(&parent_ssh_id, session_config)
+                .await
+        }
+        None => connection_registry.connect(session_config).await,
+    }
+    .map_err(CommandError::from);
+
+    match connect_result {
+        Ok(ssh_connection_id) => {
+            {
+                let mut tree = state.tree.write().await;
+                tree.update_state(node_id, NodeState::Connected)?;
+                tree.set_ssh_connection_id(node_id, ssh_connection_id.clone())?;
+            }
+            emit_node_state(app_handle, node_id, NodeState::Connected);
+
+            if let Some(controller) = connection_registry.get_handle_controller(&ssh_connection_id)
+            {
+                spawn_disconnect_watcher(
+                    app_handle.clone(),
+                    state.clone(),
+                    connection_registry.clone(),
+                    node_id.to_string(),
+                    ssh_connection_id.clone(),
+                    controller.subscribe_disconnect(),
+                );
+            }
+
+            tracing::info!(
+                "Node {} reconnected with ssh_id: {}",
+                node_id,
+                ssh_connection_id
+            );
+            Ok(ssh_connection_id)
+        }
+        Err(e) => {
+            let failed = NodeState::Failed {
+                error: e.to_string(),
+            };
+            state
+                .tree
+                .write()
+                .await
+                .update_state(node_id, failed.clone())?;
+            emit_node_state(app_handle, node_id, failed);
+
+            tracing::error!("Failed to reconnect node {}: {}", node_id, e);
+            Err(e)
+        }
+    }
+}
+
+/// 重连单个节点的方式（测试中可替换为不建立真实 SSH 连接的实现）
+trait NodeReconnector: Sync {
+    /// 经父节点隧道重连 `node_id`，返回新的 SSH 连接 ID
+    fn reconnect(&self, node_id: &str)
+        -> impl Future<Output = Result<String, CommandError>> + Send;
+}
+
+/// 经 SSH 连接注册表重连（见 `reconnect_node`）
+struct RegistryReconnector<'a> {
+    app_handle: &'a AppHandle,
+    state: &'a Arc<SessionTreeState>,
+    connection_registry: &'a Arc<SshConnectionRegistry>,
+    cols: u32,
+    rows: u32,
+}
+
+impl NodeReconnector for RegistryReconnector<'_> {
+    fn reconnect(
+        &self,
+        node_id: &str,
+    ) -> impl Future<Output = Result<String, CommandError>> + Send {
+        reconnect_node(
+            self.app_handle,
+            self.state,
+            self.connection_registry,
+            node_id,
+            self.cols,
+            self.rows,
+        )
+    }
+}
+
+/// 只重连链路中断开的区段
+///
+/// 与 `connect_manual_preset` 的全有或全无不同：
+/// 1. 先把注册表中已失效的连接同步到会话树（级联标记子树）
+/// 2. 找到根到目标路径上第一个断开的节点
+/// 3. 从该节点起逐个经父隧道重连到目标，仍存活的上游跳板保持不动
+///
+/// 每个节点的状态变化都会发送 `tree:node_state`。中途失败时已重连的节点保持连接，
+/// 失败节点标记为 Failed，下一次调用会从那里继续。
+#[tauri::command]
+pub async fn reconnect_chain_segment(
+    app_handle: AppHandle,
+    state: State<'_, Arc<SessionTreeState>>,
+    connection_registry: State<'_, Arc<SshConnectionRegistry>>,
+    target_node_id: String,
+    cols: Option<u32>,
+    rows: Option<u32>,
+) -> Result<ReconnectChainSegmentResponse, CommandError> {
+    // 1. 断开通知可能尚未到达：以注册表为准同步路径上的节点
+    let path_ids: Vec<String> = {
+        let tree = state.tree.read().await;
+        tree.get_path_to_node(&target_node_id)
+            .iter()
+            .map(|node| node.id.clone())
+            .collect()
+    };
+    sync_lost_connections(&app_handle, &state, &connection_registry, &path_ids).await;
+
+    let reconnector = RegistryReconnector {
+        app_handle: &app_handle,
+        state: state.inner(),
+        connection_registry: connection_registry.inner(),
+        cols: cols.unwrap_or(80),
+        rows: rows.unwrap_or(24),
+    };
+    rebuild_broken_segment(&state, &reconnector, target_node_id).await
+}
+
+/// 重连到 `target_node_id` 的路径上断开的区段（步骤 2、3，见 `reconnect_chain_segment`）
+async fn rebuild_broken_segment(
+    state: &SessionTreeState,
+    reconnector: &impl NodeReconnector,
+    target_node_id: String,
+) -> Result<ReconnectChainSegmentResponse, CommandError> {
+    // 2. 计算需要重连的区段
+    let (segment, untouched_node_ids) = {
+        let tree = state.tree.read().await;
+        let segment = tree.broken_segment(&target_node_id)?;
+        ensure_none_connecting(&tree, &segment)?;
+        let untouched: Vec<String> = tree
+            .get_path_to_node(&target_node_id)
+            .iter()
+            .map(|node| node.id.clone())
+            .take_while(|id| !segment.contains(id))
+            .collect();
+        (segment, untouched)
+    };
+
+    tracing::info!(
+        "Reconnecting chain segment to {}: {} node(s), {} upstream hop(s) kept",
+        target_node_id,
+        segment.len(),
+        untouched_node_ids.len()
+    );
+
+    // 3. 从断点逐个重连（恢复的节点先取回凭据）
+    state.resolve_restored_auth(&segment).await?;
+    let mut reconnected_node_ids = Vec::new();
+    for node_id in &segment {
+        reconnector
+            .reconnect(node_id)
+            .await
+            .map_err(|e| e.context(format!("Reconnecting chain node {} failed", node_id)))?;
+        reconnected_node_ids.push(node_id.clone());
+    }
+
+    let target_ssh_connection_id = {
+        let tree = state.tree.read().await;
+        tree.get_node(&target_node_id)
+            .and_then(|n| n.ssh_connection_id.clone())
+            .ok_or_else(|| {
+                CommandError::new(ErrorCode::NotConnected, "Target node has no SSH connection")
+            })?
+    };
+
+    Ok(ReconnectChainSegmentResponse {
+        target_node_id,
+        target_ssh_connection_id,
+        reconnected_node_ids,
+        untouched_node_ids,
+    })
+}
+
+/// 重建以 `node_id` 为根的子树
+///
+/// 自顶向下经父隧道逐个重连（见 `SessionTree::subtree_reconnect_plan`）：
+/// 父节点断开时先重连断开的祖先，已连接的节点保持不动。重启后恢复的节点
+/// 先经钥匙串引用取回凭据。
+///
+/// 任一节点失败即停止，并像 `connect_manual_preset` 一样逆序断开本次已重连的节点；
+/// 失败节点标记为 Failed，其余回到 Disconnected。
+#[tauri::command]
+pub async fn reconnect_tree_subtree(
+    app_handle: AppHandle,
+    state: State<'_, Arc<SessionTreeState>>,
+    connection_registry: State<'_, Arc<SshConnectionRegistry>>,
+    node_id: String,
+    cols: Option<u32>,
+    rows: Option<u32>,
+) -> Result<ReconnectSubtreeResponse, CommandError> {
+    let cols = cols.unwrap_or(80);
+    let rows = rows.unwrap_or(24);
+
+    // 1. 以注册表为准同步路径与子树上的节点
+    let known_ids: Vec<String> = {
+        let tree = state.tree.read().await;
+        if tree.get_node(&node_id).is_none() {
+            return Err(TreeError::NodeNotFound(node_id).into());
+        }
+        tree.get_path_to_node(&node_id)
+            .into_iter()
+            .chain(tree.get_descendants(&node_id))
+            .map(|node| node.id.clone())
+            .collect()
+    };
+    sync_lost_connections(&app_handle, &state, &connection_registry, &known_ids).await;
+
+    // 2. 计算重连顺序
+    let (plan, skipped_node_ids) = {
+        let tree = state.tree.read().await;
+        let plan = tree.subtree_reconnect_plan(&node_id)?;
+        ensure_none_connecting(&tree, &plan)?;
+        let skipped: Vec<String> = std::iter::once(node_id.clone())
+            .chain(
+                tree.get_descendants(&node_id)
+                    .iter()
+                    .map(|node| node.id.clone()),
+            )
+            .filter(|id| !plan.contains(id))
+            .collect();
+        (plan, skipped)
+    };
+
+    tracing::info!(
+        "Reconnecting subtree {}: {} node(s), {} already connected",
+        node_id,
+        plan.len(),
+        skipped_node_ids.len()
+    );
+
+    // 3. 恢复的节点只有钥匙串引用：先取回整个计划的凭据
+    state.resolve_restored_auth(&plan).await?;
+
+    // 4. 自顶向下重连，失败时回滚
+    let mut reconnected_node_ids: Vec<String> = Vec::new();
+    for nid in &plan {
+        let result = reconnect_node(
+            &app_handle,
+            state.inner(),
+            &connection_registry,
+            nid,
+            cols,
+            rows,
+        )
+        .await;
+
+        let Err(e) = result else {
+            reconnected_node_ids.push(nid.clone());
+            continue;
+        };
+
+        for done in reconnected_node_ids.iter().rev() {
+            let ssh_id = {
+                let tree = state.tree.read().await;
+                tree.get_node(done)
+                    .and_then(|n| n.ssh_connection_id.clone())
+            };
+            if let Some(ssh_connection_id) = ssh_id {
+                if let Err(err) = connection_registry.disconnect(&ssh_connection_id).await {
+                    tracing::warn!(
+                        "Failed to rollback connection {}: {}",
+                        ssh_connection_id,
+                        err
+                    );
+                }
+            }
+            if state.tree.write().await.mark_disconnected(done).is_ok() {
+                emit_node_state(&app_handle, done, NodeState::Disconnected);
+            }
+        }
+
+        return Err(e.context(format!(
+            "Reconnecting subtree node {} failed ({} reconnected node(s) rolled back)",
+            nid,
+            reconnected_node_ids.len()
+        )));
+    }
+
+    Ok(ReconnectSubtreeResponse {
+        node_id,
+        reconnected_node_ids,
+        skipped_node_ids,
+    })
+}
+
+// ============================================================================
+// Auto-Route Commands (Mode 2: Static Auto-Route)
+// ============================================================================
+
+use super::config::ConfigState;
+use super::HealthRegistry;
+use crate::config::{ConfigFile, SavedAuth};
+use crate::session::topology_graph::{
+    NetworkTopology, RoutePreview, TopologyEdge, TopologyEdgesConfig, TopologyNodeConfig,
+    TopologyNodeInfo,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Get topology nodes (auto-generated from saved connections)
+#[tauri::command]
+pub async fn get_topology_nodes(
+    config_state: State<'_, Arc<ConfigState>>,
+) -> Result<Vec<TopologyNodeInfo>, String> {
+    // Load saved connections from config snapshot
+    let config = config_state.get_config_snapshot();
+    let connections = &config.connections;
+
+    // Build topology from connections
+    let topology = NetworkTopology::build_from_connections(connections);
+
+    Ok(topology.get_all_nodes())
+}
+
+/// Get topology edges
+#[tauri::command]
+pub async fn get_topology_edges(
+    config_state: State<'_, Arc<ConfigState>>,
+) -> Result<Vec<TopologyEdge>, String> {
+    let config = config_state.get_config_snapshot();
+    let connections = &config.connections;
+    let topology = NetworkTopology::build_from_connections(connections);
+    Ok(topology.get_all_edges())
+}
+
+/// Get custom edges overlay config
+#[tauri::command]
+pub async fn get_topology_edges_overlay() -> Result<TopologyEdgesConfig, String> {
+    Ok(NetworkTopology::get_edges_overlay())
+}
+
+/// Add a custom edge to topology
+#[tauri::command]
+pub async fn add_topology_edge(from: String, to: String, cost: Option<i32>) -> Result<(), String> {
+    NetworkTopology::add_custom_edge(from, to, cost.unwrap_or(1))
+}
+
+/// Remove a custom edge from topology
+#[tauri::command]
+pub async fn remove_topology_edge(from: String, to: String) -> Result<(), String> {
+    NetworkTopology::remove_custom_edge(&from, &to)
+}
+
+/// Exclude an auto-generated edge
+#[tauri::command]
+pub async fn exclude_topology_edge(from: String, to: String) -> Result<(), String> {
+    NetworkTopology::exclude_edge(from, to)
+}
+
+/// Auto-route expand request
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpandAutoRouteRequest {
     /// Target node ID (topology node id, same as saved connection id)
     pub target_id: String,
     /// Optional display name override
@@ -1135,6 +1966,147 @@ pub struct ExpandAutoRouteResponse {
     pub total_cost: i32,
     /// All expanded node IDs (from root to target)
     pub all_node_ids: Vec<String>,
+    /// Next-best routes (intermediate hop node IDs), cheapest first, for
+    /// failover if `route` doesn't work out
+    pub alternatives: Vec<Vec<String>>,
+}
+
+/// How many fallback routes `expand_auto_route` suggests
+const AUTO_ROUTE_ALTERNATIVES: usize = 2;
+
+/// Preview the auto-route to a target without expanding it
+///
+/// Same topology build and route computation as `expand_auto_route`, but
+/// side-effect free: no SessionTree nodes and no route ID are created.
+#[tauri::command]
+pub async fn preview_auto_route(
+    state: State<'_, Arc<SessionTreeState>>,
+    config_state: State<'_, Arc<ConfigState>>,
+    health_registry: State<'_, HealthRegistry>,
+    target_id: String,
+) -> Result<RoutePreview, String> {
+    let config = config_state.get_config_snapshot();
+    let mut topology = NetworkTopology::build_from_connections(&config.connections);
+    apply_measured_rtts(&mut topology, &state, &health_registry).await;
+    let unhealthy = unhealthy_topology_nodes(&topology, &state, &target_id).await;
+    topology.preview_route_excluding(&target_id, &unhealthy)
+}
+
+/// How long a failed connect keeps its host out of auto-routes
+const FAILED_NODE_TTL_SECS: i64 = 300;
+
+/// Topology nodes whose session tree node recently failed to connect
+///
+/// Auto-routes avoid them rather than failing mid-chain. A failure is
+/// forgotten after `FAILED_NODE_TTL_SECS`, or as soon as another tree node
+/// to the same host is connected. The target itself is never excluded, so
+/// connecting to a failed host directly can be retried.
+async fn unhealthy_topology_nodes(
+    topology: &NetworkTopology,
+    state: &SessionTreeState,
+    target_id: &str,
+) -> HashSet<String> {
+    let tree = state.tree.read().await;
+    let nodes = tree.all_nodes_bottom_up();
+    let cutoff = chrono::Utc::now() - chrono::Duration::seconds(FAILED_NODE_TTL_SECS);
+    let topology_id = |node: &SessionNode| {
+        let conn = &node.connection;
+        topology.find_node_by_endpoint(&conn.host, conn.port, &conn.username)
+    };
+
+    let connected: HashSet<&str> = nodes
+        .iter()
+        .filter(|node| node.state.is_connected())
+        .filter_map(|node| topology_id(node))
+        .collect();
+
+    nodes
+        .iter()
+        .filter(|node| matches!(node.state, NodeState::Failed { .. }))
+        .filter(|node| node.failed_at.is_some_and(|at| at > cutoff))
+        .filter_map(|node| topology_id(node))
+        .filter(|id| *id != target_id && !connected.contains(id))
+        .map(String::from)
+        .collect()
+}
+
+/// Weight topology edges with the RTTs measured on open sessions
+///
+/// A tree node's keepalive RTT covers its whole tunnel, so the link from its
+/// parent is credited with the difference to the parent's RTT. Links whose
+/// parent has no measurement are left at their static cost.
+async fn apply_measured_rtts(
+    topology: &mut NetworkTopology,
+    state: &SessionTreeState,
+    health_registry: &HealthRegistry,
+) {
+    // (node id, parent id, endpoint, terminal session) of connected nodes
+    let connected: Vec<(String, Option<String>, NodeConnection, String)> = {
+        let tree = state.tree.read().await;
+        tree.all_nodes_bottom_up()
+            .into_iter()
+            .filter(|node| node.state.is_connected())
+            .filter_map(|node| {
+                let session_id = node.terminal_session_id.clone()?;
+                Some((
+                    node.id.clone(),
+                    node.parent_id.clone(),
+                    node.connection.clone(),
+                    session_id,
+                ))
+            })
+            .collect()
+    };
+
+    let mut rtts: HashMap<String, u64> = HashMap::new();
+    for (node_id, _, _, session_id) in &connected {
+        if let Some(tracker) = health_registry.get(session_id) {
+            if let Some(rtt) = tracker.metrics().await.avg_latency_ms {
+                rtts.insert(node_id.clone(), rtt);
+            }
+        }
+    }
+
+    let topology_id = |conn: &NodeConnection| {
+        topology
+            .find_node_by_endpoint(&conn.host, conn.port, &conn.username)
+            .map(String::from)
+    };
+    let endpoints: HashMap<&str, &NodeConnection> = connected
+        .iter()
+        .map(|(node_id, _, conn, _)| (node_id.as_str(), conn))
+        .collect();
+
+    let mut links: Vec<(String, String, u64)> = Vec::new();
+    for (node_id, parent_id, conn, _) in &connected {
+        let Some(&rtt) = rtts.get(node_id) else {
+            continue;
+        };
+        let Some(to) = topology_id(conn) else {
+            continue;
+        };
+        let (from, link_rtt) = match parent_id {
+            None => ("local".to_string(), rtt),
+            Some(parent_id) => {
+                let parent = rtts
+                    .get(parent_id)
+                    .zip(endpoints.get(parent_id.as_str()))
+                    .and_then(|(&parent_rtt, parent_conn)| {
+                        Some((topology_id(parent_conn)?, parent_rtt))
+                    });
+                let Some((from, parent_rtt)) = parent else {
+                    continue;
+                };
+                (from, rtt.saturating_sub(parent_rtt))
+            }
+        };
+        links.push((from, to, link_rtt));
+    }
+
+    for (from, to, rtt) in links {
+        let rtt = u32::try_from(rtt).unwrap_or(u32::MAX);
+        topology.update_edge_rtt(&from, &to, rtt);
+    }
 }
 
 /// Expand auto-route node chain (Mode 2: Static Auto-Route)
@@ -1142,23 +2114,33 @@ pub struct ExpandAutoRouteResponse {
 /// Auto-computes optimal path to target node and expands SessionTree nodes.
 ///
 /// # Workflow
-/// 1. Build topology from saved connections
-/// 2. Use Dijkstra to compute shortest path
+/// 1. Build topology from saved connections (edges weighted by measured RTT where known)
+/// 2. Use Dijkstra to compute shortest path, avoiding hosts that recently failed
 /// 3. Convert path to SessionTree nodes
 /// 4. Return expanded node info
 #[tauri::command]
 pub async fn expand_auto_route(
     state: State<'_, Arc<SessionTreeState>>,
     config_state: State<'_, Arc<ConfigState>>,
+    health_registry: State<'_, HealthRegistry>,
     request: ExpandAutoRouteRequest,
 ) -> Result<ExpandAutoRouteResponse, String> {
-    // 1. Build topology from saved connections
+    // 1. Build topology from saved connections, weighted by measured RTTs
     let config = config_state.get_config_snapshot();
     let connections = &config.connections;
-    let topology = NetworkTopology::build_from_connections(connections);
-
-    // 2. Compute route
-    let route_result = topology.compute_route(&request.target_id)?;
+    let mut topology = NetworkTopology::build_from_connections(connections);
+    apply_measured_rtts(&mut topology, &state, &health_registry).await;
+
+    // 2. Compute route, avoiding hosts that recently failed
+    let unhealthy = unhealthy_topology_nodes(&topology, &state, &request.target_id).await;
+    let route_result = topology.compute_route_excluding(&request.target_id, &unhealthy)?;
+    let alternatives: Vec<Vec<String>> = topology
+        .compute_k_routes_excluding(&request.target_id, AUTO_ROUTE_ALTERNATIVES + 1, &unhealthy)
+        .into_iter()
+        .map(|route| route.path)
+        .filter(|path| *path != route_result.path)
+        .take(AUTO_ROUTE_ALTERNATIVES)
+        .collect();
     tracing::info!(
         "Auto-route computed: local -> {} -> {} (cost: {})",
         route_result.path.join(" -> "),
@@ -1178,7 +2160,7 @@ pub async fn expand_auto_route(
             .get_node(hop_id)
             .ok_or_else(|| format!("Hop node '{}' not found", hop_id))?;
 
-        let auth = topology_auth_to_session_auth(&hop_config.auth_type, &hop_config.key_path)?;
+        let auth = resolve_topology_auth(&config_state, &config, hop_config)?;
         let mut conn = NodeConnection::new(
             hop_config.host.clone(),
             hop_config.port,
@@ -1190,8 +2172,7 @@ pub async fn expand_auto_route(
     }
 
     // 5. Build target NodeConnection
-    let target_auth =
-        topology_auth_to_session_auth(&target_config.auth_type, &target_config.key_path)?;
+    let target_auth = resolve_topology_auth(&config_state, &config, target_config)?;
     let mut target_conn = NodeConnection::new(
         target_config.host.clone(),
         target_config.port,
@@ -1208,6 +2189,7 @@ pub async fn expand_auto_route(
     let target_node_id = tree
         .expand_auto_route(&target_config.host, &route_id, hop_connections, target_conn)
         .map_err(|e| e.to_string())?;
+    state.persist(&tree);
 
     // 8. Collect all node IDs (backtrack from target to root)
     let mut all_node_ids = Vec::new();
@@ -1229,18 +2211,76 @@ pub async fn expand_auto_route(
         route: route_result.path,
         total_cost: route_result.total_cost,
         all_node_ids,
+        alternatives,
     })
 }
 
+/// Saved credentials behind a topology node
+///
+/// Nodes built from a saved connection carry its ID; auto-generated hop
+/// nodes are matched against the proxy chains they were created from.
+fn find_saved_auth<'a>(config: &'a ConfigFile, node: &TopologyNodeConfig) -> Option<&'a SavedAuth> {
+    if let Some(id) = &node.saved_connection_id {
+        return config.get_connection(id).map(|conn| &conn.auth);
+    }
+    config
+        .connections
+        .iter()
+        .flat_map(|conn| &conn.proxy_chain)
+        .find(|hop| hop.host == node.host && hop.port == node.port && hop.username == node.username)
+        .map(|hop| &hop.auth)
+}
+
+/// Build the SessionTree auth method for a topology node
+///
+/// Passwords and key passphrases are looked up in the keychain; a password
+/// connection without a stored secret falls back to agent auth.
+fn resolve_topology_auth(
+    config_state: &ConfigState,
+    config: &ConfigFile,
+    node: &TopologyNodeConfig,
+) -> Result<AuthMethod, String> {
+    match find_saved_auth(config, node) {
+        Some(SavedAuth::Password { keychain_id }) => {
+            match config_state.lookup_keychain_value(keychain_id)? {
+                Some(password) => Ok(AuthMethod::Password { password }),
+                None => {
+                    tracing::warn!(
+                        "No stored password for topology node {}, falling back to agent auth",
+                        node.id
+                    );
+                    Ok(AuthMethod::Agent)
+                }
+            }
+        }
+        Some(SavedAuth::Key {
+            key_path,
+            passphrase_keychain_id,
+            ..
+        }) => {
+            let passphrase = match passphrase_keychain_id {
+                Some(kc_id) => config_state.lookup_keychain_value(kc_id)?,
+                None => None,
+            };
+            Ok(AuthMethod::Key {
+                key_path: key_path.clone(),
+                passphrase,
+            })
+        }
+        _ => topology_auth_to_session_auth(&node.auth_type, &node.key_path),
+    }
+}
+
 /// Convert topology auth type to SessionTree auth method
+///
+/// Used for nodes without saved credentials (e.g. custom topology entries).
 fn topology_auth_to_session_auth(
     auth_type: &str,
     key_path: &Option<String>,
 ) -> Result<AuthMethod, String> {
     match auth_type {
-        "password" => Err(
-            "Password authentication requires password which is not stored in topology".to_string(),
-        ),
+        // No stored secret to look up: default to the agent
+        "password" => Ok(AuthMethod::Agent),
         "key" => {
             let path = key_path
                 .clone()
@@ -1457,3 +2497,528 @@ pub async fn destroy_node_sessions(
         sftp_closed,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{
+        memory_config_state, CredentialStore, MemoryCredentialStore, ProxyHopConfig,
+        SavedConnection,
+    };
+
+    fn topology_node(conn_id: Option<&str>, host: &str, username: &str) -> TopologyNodeConfig {
+        TopologyNodeConfig {
+            id: conn_id.unwrap_or(host).to_string(),
+            host: host.to_string(),
+            port: 22,
+            username: username.to_string(),
+            auth_type: "password".to_string(),
+            key_path: None,
+            display_name: None,
+            is_local: false,
+            tags: Vec::new(),
+            saved_connection_id: conn_id.map(str::to_string),
+        }
+    }
+
+    fn config_state(keychain: Arc<MemoryCredentialStore>) -> ConfigState {
+        memory_config_state(ConfigFile::default(), keychain)
+    }
+
+    #[test]
+    fn test_password_hops_resolve_from_keychain() {
+        let keychain = Arc::new(MemoryCredentialStore::new());
+        keychain.store("kc-target", "target-pw").unwrap();
+        keychain.store("kc-bastion", "bastion-pw").unwrap();
+        let state = config_state(keychain);
+
+        let mut target = SavedConnection::new_password("db", "db.internal", 22, "app", "kc-target");
+        target.proxy_chain.push(ProxyHopConfig {
+            host: "bastion".to_string(),
+            port: 22,
+            username: "jump".to_string(),
+            auth: SavedAuth::Password {
+                keychain_id: "kc-bastion".to_string(),
+            },
+        });
+        let config = ConfigFile {
+            connections: vec![target.clone()],
+            ..ConfigFile::default()
+        };
+
+        let auth = resolve_topology_auth(
+            &state,
+            &config,
+            &topology_node(Some(&target.id), "db.internal", "app"),
+        )
+        .unwrap();
+        assert!(matches!(auth, AuthMethod::Password { password } if password == "target-pw"));
+
+        // Auto-generated hop node: matched through the proxy chain
+        let auth = resolve_topology_auth(&state, &config, &topology_node(None, "bastion", "jump"))
+            .unwrap();
+        assert!(matches!(auth, AuthMethod::Password { password } if password == "bastion-pw"));
+    }
+
+    #[test]
+    fn test_password_without_stored_secret_uses_agent() {
+        let state = config_state(Arc::new(MemoryCredentialStore::new()));
+        let conn = SavedConnection::new_password("web", "web", 22, "root", "kc-missing");
+        let config = ConfigFile {
+            connections: vec![conn.clone()],
+            ..ConfigFile::default()
+        };
+
+        let auth = resolve_topology_auth(
+            &state,
+            &config,
+            &topology_node(Some(&conn.id), "web", "root"),
+        )
+        .unwrap();
+        assert!(matches!(auth, AuthMethod::Agent));
+
+        // Unknown node with password auth type: same default
+        let auth =
+            resolve_topology_auth(&state, &config, &topology_node(None, "other", "x")).unwrap();
+        assert!(matches!(auth, AuthMethod::Agent));
+    }
+
+    #[test]
+    fn test_keychain_failure_is_reported() {
+        let keychain = Arc::new(MemoryCredentialStore::new());
+        keychain.set_unavailable(true);
+        let state = config_state(keychain);
+        let conn = SavedConnection::new_password("web", "web", 22, "root", "kc-1");
+        let config = ConfigFile {
+            connections: vec![conn.clone()],
+            ..ConfigFile::default()
+        };
+
+        assert!(resolve_topology_auth(
+            &state,
+            &config,
+            &topology_node(Some(&conn.id), "web", "root")
+        )
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_failed_hosts_age_out_of_routes() {
+        let bastion = SavedConnection::new_password("bastion", "bastion", 22, "ops", "kc-1");
+        let db = SavedConnection::new_password("db", "db", 22, "ops", "kc-2");
+        let topology = NetworkTopology::build_from_connections(&[bastion.clone(), db.clone()]);
+        let state = SessionTreeState::new();
+
+        let failed = {
+            let mut tree = state.tree.write().await;
+            let failed = tree.add_root_node(
+                NodeConnection::new("bastion", 22, "ops"),
+                NodeOrigin::Direct,
+            );
+            tree.update_state(
+                &failed,
+                NodeState::Failed {
+                    error: "timed out".to_string(),
+                },
+            )
+            .unwrap();
+            failed
+        };
+        let unhealthy = unhealthy_topology_nodes(&topology, &state, &db.id).await;
+        assert!(unhealthy.contains(&bastion.id));
+        // The target is never excluded
+        let unhealthy = unhealthy_topology_nodes(&topology, &state, &bastion.id).await;
+        assert!(unhealthy.is_empty());
+
+        // Old failures are forgotten
+        let expired = chrono::Utc::now() - chrono::Duration::seconds(FAILED_NODE_TTL_SECS + 1);
+        {
+            let mut tree = state.tree.write().await;
+            tree.get_node_mut(&failed).unwrap().failed_at = Some(expired);
+        }
+        assert!(unhealthy_topology_nodes(&topology, &state, &db.id)
+            .await
+            .is_empty());
+
+        // So are failures of hosts that have since been connected
+        {
+            let mut tree = state.tree.write().await;
+            tree.get_node_mut(&failed).unwrap().failed_at = Some(chrono::Utc::now());
+            let retried = tree.add_root_node(
+                NodeConnection::new("bastion", 22, "ops"),
+                NodeOrigin::Direct,
+            );
+            tree.update_state(&retried, NodeState::Connected).unwrap();
+        }
+        assert!(unhealthy_topology_nodes(&topology, &state, &db.id)
+            .await
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_parent_disconnect_marks_descendants() {
+        let state = Arc::new(SessionTreeState::new());
+        let (parent, child, grandchild) = {
+            let mut tree = state.tree.write().await;
+            let parent = tree.add_root_node(
+                NodeConnection::new("bastion", 22, "ops"),
+                NodeOrigin::Direct,
+            );
+            tree.update_state(&parent, NodeState::Connected).unwrap();
+            tree.set_ssh_connection_id(&parent, "ssh-bastion".to_string())
+                .unwrap();
+            let child = tree
+                .drill_down(&parent, NodeConnection::new("app", 22, "ops"))
+                .unwrap();
+            tree.update_state(&child, NodeState::Connected).unwrap();
+            tree.set_ssh_connection_id(&child, "ssh-app".to_string())
+                .unwrap();
+            let grandchild = tree
+                .drill_down(&child, NodeConnection::new("db", 22, "ops"))
+                .unwrap();
+            tree.update_state(&grandchild, NodeState::Connecting)
+                .unwrap();
+            (parent, child, grandchild)
+        };
+
+        let (disconnect_tx, disconnect_rx) = broadcast::channel(1);
+        let watcher = tokio::spawn({
+            let state = state.clone();
+            let parent = parent.clone();
+            async move {
+                state
+                    .wait_connection_lost(&parent, "ssh-bastion", disconnect_rx)
+                    .await
+            }
+        });
+
+        // Simulate the bastion's SSH connection dropping
+        disconnect_tx.send(()).unwrap();
+        let lost = watcher.await.unwrap();
+        assert_eq!(
+            lost.changed,
+            vec![grandchild.clone(), child.clone(), parent.clone()]
+        );
+        assert_eq!(lost.orphaned, vec!["ssh-app".to_string()]);
+
+        let tree = state.tree.read().await;
+        for id in [&parent, &child, &grandchild] {
+            let node = tree.get_node(id).unwrap();
+            assert_eq!(node.state, NodeState::Disconnected);
+            assert!(node.ssh_connection_id.is_none());
+        }
+    }
+
+    /// 不建立 SSH 连接，只在树中把节点标记为已连接
+    struct FakeReconnector<'a> {
+        state: &'a SessionTreeState,
+        calls: parking_lot::Mutex<Vec<String>>,
+    }
+
+    impl NodeReconnector for FakeReconnector<'_> {
+        fn reconnect(
+            &self,
+            node_id: &str,
+        ) -> impl Future<Output = Result<String, CommandError>> + Send {
+            self.calls.lock().push(node_id.to_string());
+            let node_id = node_id.to_string();
+            async move {
+                let ssh_id = format!("ssh-{}-new", node_id);
+                let mut tree = self.state.tree.write().await;
+                tree.update_state(&node_id, NodeState::Connected)?;
+                tree.set_ssh_connection_id(&node_id, ssh_id.clone())?;
+                Ok(ssh_id)
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chain_segment_reconnect_keeps_upstream_hops() {
+        let state = SessionTreeState::new();
+        let (path, target) = {
+            let mut tree = state.tree.write().await;
+            let target = tree
+                .expand_manual_preset(
+                    "saved-db",
+                    vec![
+                        NodeConnection::new("jump", 22, "ops"),
+                        NodeConnection::new("bastion", 22, "ops"),
+                    ],
+                    NodeConnection::new("db", 22, "ops"),
+                )
+                .unwrap();
+            let path: Vec<String> = tree
+                .get_path_to_node(&target)
+                .iter()
+                .map(|node| node.id.clone())
+                .collect();
+            for (i, id) in path.iter().enumerate() {
+                tree.update_state(id, NodeState::Connected).unwrap();
+                tree.set_ssh_connection_id(id, format!("ssh-{}", i))
+                    .unwrap();
+            }
+            // The middle hop drops, taking the target with it
+            tree.mark_connection_lost(&path[1], "ssh-1");
+            (path, target)
+        };
+
+        let reconnector = FakeReconnector {
+            state: &state,
+            calls: parking_lot::Mutex::new(Vec::new()),
+        };
+        let response = rebuild_broken_segment(&state, &reconnector, target.clone())
+            .await
+            .unwrap();
+
+        assert_eq!(response.untouched_node_ids, vec![path[0].clone()]);
+        assert_eq!(response.reconnected_node_ids, path[1..].to_vec());
+        assert_eq!(
+            response.target_ssh_connection_id,
+            format!("ssh-{}-new", target)
+        );
+        // Only the broken segment was connected (and authenticated) again
+        assert_eq!(*reconnector.calls.lock(), path[1..].to_vec());
+
+        let tree = state.tree.read().await;
+        let jump = tree.get_node(&path[0]).unwrap();
+        assert_eq!(jump.state, NodeState::Connected);
+        assert_eq!(jump.ssh_connection_id.as_deref(), Some("ssh-0"));
+        for id in &path[1..] {
+            let node = tree.get_node(id).unwrap();
+            assert_eq!(node.state, NodeState::Connected);
+            assert_eq!(
+                node.ssh_connection_id.as_deref(),
+                Some(format!("ssh-{}-new", id).as_str())
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_disconnect_all_tears_down_both_roots() {
+        let state = SessionTreeState::new();
+        let registry = SshConnectionRegistry::new();
+        let mut connected = Vec::new();
+        {
+            let mut tree = state.tree.write().await;
+            for root_host in ["alpha", "beta"] {
+                let mut current = tree.add_root_node(
+                    NodeConnection::new(root_host, 22, "ops"),
+                    NodeOrigin::Direct,
+                );
+                for level in 0..3 {
+                    tree.update_state(&current, NodeState::Connected).unwrap();
+                    tree.set_ssh_connection_id(&current, format!("ssh-{}", current))
+                        .unwrap();
+                    tree.set_terminal_session_id(&current, format!("term-{}", current))
+                        .unwrap();
+                    connected.push(current.clone());
+                    if level < 2 {
+                        let host = format!("{}-{}", root_host, level);
+                        current = tree
+                            .drill_down(&current, NodeConnection::new(&host, 22, "ops"))
+                            .unwrap();
+                    }
+                }
+            }
+        }
+
+        // A second pass over already-disconnected nodes must be harmless
+        for _ in 0..2 {
+            let nodes: Vec<(String, Option<String>)> = {
+                let tree = state.tree.read().await;
+                tree.all_nodes_bottom_up()
+                    .into_iter()
+                    .map(|node| (node.id.clone(), node.ssh_connection_id.clone()))
+                    .collect()
+            };
+            let expected: Vec<String> = nodes.iter().map(|(id, _)| id.clone()).collect();
+            // The registry knows none of these connections: every disconnect
+            // fails, and the teardown carries on regardless
+            let disconnected = disconnect_nodes_in_order(&state, &registry, nodes).await;
+            assert_eq!(disconnected, expected);
+            assert_eq!(disconnected.len(), 6);
+        }
+
+        let tree = state.tree.read().await;
+        for id in &connected {
+            let node = tree.get_node(id).unwrap();
+            assert_eq!(node.state, NodeState::Disconnected);
+            assert!(node.ssh_connection_id.is_none());
+            assert!(node.terminal_session_id.is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_restored_password_node_resolves_keychain_secret() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let store = Arc::new(StateStore::new(temp_dir.path().join("state.redb")).unwrap());
+        let keychain = Arc::new(MemoryCredentialStore::new());
+        keychain.store("kc-bastion", "bastion-pw").unwrap();
+        keychain.store("kc-db", "db-pw").unwrap();
+
+        let mut saved = SavedConnection::new_password("db", "db.internal", 22, "app", "kc-db");
+        saved.proxy_chain.push(ProxyHopConfig {
+            host: "bastion".to_string(),
+            port: 22,
+            username: "jump".to_string(),
+            auth: SavedAuth::Password {
+                keychain_id: "kc-bastion".to_string(),
+            },
+        });
+        let config = Arc::new(memory_config_state(
+            ConfigFile {
+                connections: vec![saved.clone()],
+                ..ConfigFile::default()
+            },
+            keychain,
+        ));
+
+        let (bastion, db) = {
+            let state = SessionTreeState::with_store(store.clone());
+            state.attach_config(config.clone());
+            let mut tree = state.tree.write().await;
+            let db = tree
+                .expand_manual_preset(
+                    &saved.id,
+                    vec![NodeConnection::new("bastion", 22, "jump").with_password("bastion-pw")],
+                    NodeConnection::new("db.internal", 22, "app").with_password("db-pw"),
+                )
+                .unwrap();
+            let bastion = tree.get_node(&db).unwrap().parent_id.clone().unwrap();
+            state.persist(&tree);
+            (bastion, db)
+        };
+
+        // Only the keychain references are stored
+        let raw = store.load_session_tree().unwrap().unwrap();
+        let raw = String::from_utf8_lossy(&raw);
+        assert!(raw.contains("kc-bastion") && raw.contains("kc-db"));
+        assert!(!raw.contains("bastion-pw") && !raw.contains("db-pw"));
+
+        let restored = SessionTreeState::with_store(store.clone());
+        restored.attach_config(config);
+        assert!(matches!(
+            &restored.tree.read().await.get_node(&db).unwrap().connection.auth,
+            AuthMethod::Password { password } if password.is_empty()
+        ));
+
+        // Snapshots taken before reconnecting keep the references
+        restored.persist(&*restored.tree.read().await);
+        let restored = {
+            let config = restored.config.get().unwrap().clone();
+            let state = SessionTreeState::with_store(store);
+            state.attach_config(config);
+            state
+        };
+
+        restored
+            .resolve_restored_auth(&[bastion.clone(), db.clone()])
+            .await
+            .unwrap();
+        let tree = restored.tree.read().await;
+        assert!(matches!(
+            &tree.get_node(&bastion).unwrap().connection.auth,
+            AuthMethod::Password { password } if password == "bastion-pw"
+        ));
+        assert!(matches!(
+            &tree.get_node(&db).unwrap().connection.auth,
+            AuthMethod::Password { password } if password == "db-pw"
+        ));
+        assert!(restored.restored_auth.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_consolidated_keychain_refs_rewrite_snapshot() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let store = Arc::new(StateStore::new(temp_dir.path().join("state.redb")).unwrap());
+        let keychain = Arc::new(MemoryCredentialStore::new());
+        let saved = SavedConnection::new_password("db", "db.internal", 22, "app", "kc-legacy");
+        let config = Arc::new(memory_config_state(
+            ConfigFile {
+                connections: vec![saved.clone()],
+                ..ConfigFile::default()
+            },
+            keychain.clone(),
+        ));
+
+        let db = {
+            let state = SessionTreeState::with_store(store.clone());
+            state.attach_config(config.clone());
+            let mut tree = state.tree.write().await;
+            let db = tree
+                .expand_manual_preset(
+                    &saved.id,
+                    vec![NodeConnection::new("bastion", 22, "jump")],
+                    NodeConnection::new("db.internal", 22, "app").with_password("db-pw"),
+                )
+                .unwrap();
+            state.persist(&tree);
+            db
+        };
+
+        // Consolidation moves the secret and repoints the saved connection
+        let canonical = format!("oxide_conn_{}", saved.id);
+        keychain.store(&canonical, "db-pw").unwrap();
+        config
+            .update_config(|config| {
+                config.connections[0].auth = SavedAuth::Password {
+                    keychain_id: canonical.clone(),
+                }
+            })
+            .unwrap();
+        let restored = SessionTreeState::with_store(store.clone());
+        restored.attach_config(config);
+        let renamed = HashMap::from([("kc-legacy".to_string(), canonical.clone())]);
+        restored.rename_keychain_refs(&renamed).await.unwrap();
+
+        // Only now may the old entry go
+        let raw = store.load_session_tree().unwrap().unwrap();
+        let raw = String::from_utf8_lossy(&raw);
+        assert!(raw.contains(&canonical) && !raw.contains("kc-legacy"));
+        keychain.delete("kc-legacy").unwrap();
+
+        restored
+            .resolve_restored_auth(std::slice::from_ref(&db))
+            .await
+            .unwrap();
+        assert!(matches!(
+            &restored.tree.read().await.get_node(&db).unwrap().connection.auth,
+            AuthMethod::Password { password } if password == "db-pw"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_tree_restored_from_store() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let store = Arc::new(StateStore::new(temp_dir.path().join("state.redb")).unwrap());
+
+        let (root, child) = {
+            let state = SessionTreeState::with_store(store.clone());
+            let mut tree = state.tree.write().await;
+            let root = tree.add_root_node(
+                NodeConnection::new("bastion", 22, "ops"),
+                NodeOrigin::Direct,
+            );
+            tree.update_state(&root, NodeState::Connected).unwrap();
+            let child = tree
+                .drill_down(&root, NodeConnection::new("app", 22, "ops"))
+                .unwrap();
+            tree.update_state(&child, NodeState::Connected).unwrap();
+            state.persist(&tree);
+            (root, child)
+        };
+
+        let restored = SessionTreeState::with_store(store);
+        let tree = restored.tree.read().await;
+        assert_eq!(tree.len(), 2);
+        assert_eq!(
+            tree.get_node(&child).unwrap().parent_id.as_deref(),
+            Some(root.as_str())
+        );
+        assert_eq!(tree.get_node(&root).unwrap().state, NodeState::Disconnected);
+        assert_eq!(
+            tree.get_node(&child).unwrap().state,
+            NodeState::Disconnected
+        );
+    }
+}
@@ -9,18 +9,28 @@
 //! - `ssh_set_keep_alive` - 设置连接保持
 //! - `create_terminal` - 为已有连接创建终端
 //! - `close_terminal` - 关闭终端（不断开连接）
+//! - `set_session_notify` - 设置终端活动通知
+//! - `set_paste_guard` / `confirm_paste` - 多行粘贴确认
+//! - `session_start_recording` / `session_stop_recording` - 终端录制（asciicast v2）
 
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 use tauri::{AppHandle, State};
 use tracing::{info, warn};
 
+use super::config::ConfigState;
 use super::{ForwardingRegistry, HealthRegistry, ProfilerRegistry};
-use crate::bridge::{BridgeManager, WsBridge};
+use crate::bridge::{
+    BridgeConfig, BridgeManager, BridgePolicy, DisconnectReason, PasteGuardPolicy, WsBridge,
+};
+use crate::config::storage::recordings_dir;
 use crate::forwarding::ForwardingManager;
 use crate::session::{
-    parse_terminal_output, AuthMethod, SessionConfig, SessionInfo, SessionRegistry,
+    event_names, idle, parse_terminal_output, resolve_cast_path, AuthMethod, IdleDisconnectPayload,
+    IdleTracker, NotifyOn, PasteConfirmPayload, SessionConfig, SessionInfo, SessionNotifyPayload,
+    SessionRegistry, DEFAULT_MAX_CAST_BYTES,
 };
 use crate::agent::AgentRegistry;
 use crate::sftp::session::SftpRegistry;
@@ -128,6 +138,24 @@ pub async fn ssh_set_pool_config(
     Ok(())
 }
 
+/// 获取 WebSocket bridge 配置
+#[tauri::command]
+pub async fn bridge_get_config(
+    bridge_manager: State<'_, BridgeManager>,
+) -> Result<BridgeConfig, String> {
+    Ok(bridge_manager.config())
+}
+
+/// 设置 WebSocket bridge 配置（对之后启动的 bridge 生效）
+#[tauri::command]
+pub async fn bridge_set_config(
+    config: BridgeConfig,
+    bridge_manager: State<'_, BridgeManager>,
+) -> Result<(), String> {
+    bridge_manager.set_config(config);
+    Ok(())
+}
+
 /// 获取连接池统计信息
 ///
 /// 返回连接池实时状态，用于监控面板
@@ -156,6 +184,15 @@ pub struct CreateTerminalRequest {
     pub rows: u32,
     /// 缓冲区最大行数
     pub max_buffer_lines: Option<usize>,
+    /// 空闲超时（秒），无 PTY 读写超过该时长自动断开；0 表示沿用保存连接的设置
+    #[serde(default)]
+    pub idle_timeout_secs: u64,
+    /// 活动通知（响铃 / 空闲后输出 / 模式匹配）
+    #[serde(default)]
+    pub notify_on: Option<NotifyOn>,
+    /// 多行粘贴确认策略
+    #[serde(default)]
+    pub paste_guard: Option<PasteGuardPolicy>,
 }
 
 fn default_cols() -> u32 {
@@ -184,11 +221,13 @@ pub struct CreateTerminalResponse {
 /// 为已有 SSH 连接创建终端
 #[tauri::command]
 pub async fn create_terminal(
-    _app_handle: AppHandle,
+    app_handle: AppHandle,
     request: CreateTerminalRequest,
     connection_registry: State<'_, Arc<SshConnectionRegistry>>,
     session_registry: State<'_, Arc<SessionRegistry>>,
     forwarding_registry: State<'_, Arc<ForwardingRegistry>>,
+    config_state: State<'_, Arc<ConfigState>>,
+    bridge_manager: State<'_, BridgeManager>,
 ) -> Result<CreateTerminalResponse, String> {
     info!(
         "Create terminal request for connection: {}",
@@ -238,6 +277,7 @@ pub async fn create_terminal(
         color: None,
         cols: request.cols,
         rows: request.rows,
+        proxy_command: None,
     };
 
     // 在 SessionRegistry 创建 session
@@ -375,8 +415,21 @@ pub async fn create_terminal(
         .with_session(&session_id, |entry| entry.output_tx.clone())
         .ok_or_else(|| "Session output channel not found".to_string())?;
 
+    let idle_tracker = session_registry
+        .with_session(&session_id, |entry| entry.idle_tracker.clone())
+        .ok_or_else(|| "Session not found in registry".to_string())?;
+
+    let recorder = session_registry
+        .with_session(&session_id, |entry| entry.recorder.clone())
+        .ok_or_else(|| "Session not found in registry".to_string())?;
+    // 录制文件头使用的终端尺寸
+    let _ = recorder
+        .lock()
+        .resize(request.cols as u16, request.rows as u16);
+
     let output_rx = output_tx.subscribe();
     let scroll_buffer_clone = scroll_buffer.clone();
+    let idle_tracker_clone = idle_tracker.clone();
 
     // 启动 channel 处理任务
     let sid = session_id.clone();
@@ -388,6 +441,7 @@ pub async fn create_terminal(
                 Some(cmd) = cmd_rx.recv() => {
                     match cmd {
                         SessionCommand::Data(data) => {
+                            idle_tracker_clone.touch();
                             if let Err(e) = channel.data(&data[..]).await {
                                 tracing::error!("Failed to send data to SSH channel: {}", e);
                                 break;
@@ -398,6 +452,9 @@ pub async fn create_terminal(
                             if let Err(e) = channel.window_change(cols as u32, rows as u32, 0, 0).await {
                                 tracing::error!("Failed to resize PTY: {}", e);
                             }
+                            if let Err(e) = recorder.lock().resize(cols, rows) {
+                                warn!("Failed to record resize: {}", e);
+                            }
                         }
                         SessionCommand::Close => {
                             info!("Close command received for session {}", sid);
@@ -410,6 +467,7 @@ pub async fn create_terminal(
                 Some(msg) = channel.wait() => {
                     match msg {
                         ChannelMsg::Data { data } => {
+                            idle_tracker_clone.touch();
                             let bytes = data.to_vec();
                             let lines = parse_terminal_output(&bytes);
                             if !lines.is_empty() {
@@ -419,6 +477,7 @@ pub async fn create_terminal(
                         }
                         ChannelMsg::ExtendedData { data, ext } => {
                             if ext == 1 {
+                                idle_tracker_clone.touch();
                                 let bytes = data.to_vec();
                                 let lines = parse_terminal_output(&bytes);
                                 if !lines.is_empty() {
@@ -449,93 +508,37 @@ pub async fn create_terminal(
     };
 
     // 启动 WebSocket bridge
-    let (_, port, token, disconnect_rx) =
-        WsBridge::start_extended_with_disconnect(extended_handle, scroll_buffer.clone(), false)
-            .await
-            .map_err(|e| {
-                session_registry.remove(&session_id);
-                let conn_reg = connection_registry.inner().clone();
-                let conn_id = request.connection_id.clone();
-                tokio::spawn(async move {
-                    let _ = conn_reg.release(&conn_id).await;
-                });
-                format!("Failed to start WebSocket bridge: {}", e)
-            })?;
+    let bridge_policy = BridgePolicy {
+        paste_guard: session_registry.with_session(&session_id, |entry| entry.paste_guard.clone()),
+        ..bridge_manager.policy()
+    };
+    let (_, port, token, disconnect_rx) = WsBridge::start_extended_with_policy(
+        extended_handle,
+        scroll_buffer.clone(),
+        false,
+        bridge_policy.clone(),
+    )
+    .await
+    .map_err(|e| {
+        session_registry.remove(&session_id);
+        let conn_reg = connection_registry.inner().clone();
+        let conn_id = request.connection_id.clone();
+        tokio::spawn(async move {
+            let _ = conn_reg.release(&conn_id).await;
+        });
+        format!("Failed to start WebSocket bridge: {}", e)
+    })?;
 
     // 处理 WebSocket 断开事件
-    // Note: connection_status_changed events are emitted by heartbeat monitoring
-    // Important: 不要在这里移除 terminal_id 或释放连接，因为重连时需要这些信息
-    let session_id_clone = session_id.clone();
-    let registry_clone = session_registry.inner().clone();
-    let conn_registry_clone = connection_registry.inner().clone();
-    let conn_id_clone = request.connection_id.clone();
-    let node_emitter_ct = conn_registry_clone.node_emitter(); // Oxide-Next Phase 2
-    tokio::spawn(async move {
-        if let Ok(reason) = disconnect_rx.await {
-            warn!(
-                "Session {} WebSocket bridge disconnected: {:?}",
-                session_id_clone, reason
-            );
-            if reason.is_recoverable() {
-                // 🔧 修复 ref_count 泄漏：超时后释放连接引用
-                let conn_reg_for_cleanup = conn_registry_clone.clone();
-                let session_id_for_cleanup = session_id_clone.clone();
-                let _ = registry_clone.mark_ws_detached_with_cleanup(
-                    &session_id_clone,
-                    Duration::from_secs(300),
-                    Some(move |conn_id: String| {
-                        // 在 TTL 过期时释放连接池引用
-                        let conn_reg = conn_reg_for_cleanup;
-                        let sid = session_id_for_cleanup;
-                        tokio::spawn(async move {
-                            info!(
-                                "Releasing connection {} ref after WS detach timeout (session: {})",
-                                conn_id, sid
-                            );
-                            let _ = conn_reg.remove_terminal(&conn_id, &sid).await;
-                            let _ = conn_reg.release(&conn_id).await;
-                        });
-                    }),
-                );
-            } else {
-                // AcceptTimeout 或其他不可恢复的断开：清理会话
-                // 这是因为如果前端从未连接，保留这个会话没有意义
-                if matches!(reason, crate::bridge::DisconnectReason::AcceptTimeout) {
-                    warn!(
-                        "Session {} WS accept timeout, removing from registries",
-                        session_id_clone
-                    );
-                    // 🔴 关键修复：发送 disconnected 事件通知前端
-                    // 这样前端可以清理掉对这个已失效 session 的引用
-                    conn_registry_clone
-                        .emit_connection_status_changed(&conn_id_clone, "disconnected")
-                        .await;
-
-                    // Oxide-Next Phase 2: node:state 事件
-                    if let Some(ref emitter) = node_emitter_ct {
-                        emitter.emit_state_from_connection(
-                            &conn_id_clone,
-                            &crate::ssh::ConnectionState::Disconnected,
-                            "WS accept timeout",
-                        );
-                    }
-
-                    // 从连接的终端列表中移除
-                    let _ = conn_registry_clone
-                        .remove_terminal(&conn_id_clone, &session_id_clone)
-                        .await;
-                    // 释放连接引用
-                    let _ = conn_registry_clone.release(&conn_id_clone).await;
-                    // 完全移除会话
-                    let _ = registry_clone.disconnect_complete(&session_id_clone, true);
-                } else {
-                    // 其他不可恢复的断开：只更新状态，不移除
-                    // 终端关联由 close_terminal 命令显式移除
-                    let _ = registry_clone.disconnect_complete(&session_id_clone, false);
-                }
-            }
-        }
-    });
+    spawn_ws_disconnect_handler(
+        disconnect_rx,
+        session_id.clone(),
+        request.connection_id.clone(),
+        session_registry.inner().clone(),
+        connection_registry.inner().clone(),
+        bridge_policy.detach_grace,
+        "Session",
+    );
 
     // 克隆 HandleController 用于 ForwardingManager
     let forwarding_controller = handle_controller.clone();
@@ -571,6 +574,44 @@ pub async fn create_terminal(
         .register(session_id.clone(), forwarding_manager)
         .await;
 
+    // 活动通知
+    spawn_activity_notifier(
+        app_handle.clone(),
+        &session_registry,
+        session_id.clone(),
+        request.notify_on.clone(),
+    );
+
+    // 多行粘贴确认
+    spawn_paste_confirm_forwarder(
+        app_handle.clone(),
+        &session_registry,
+        session_id.clone(),
+        request.paste_guard,
+    );
+
+    // 空闲超时监控（请求未指定时沿用保存连接的设置）
+    let idle_timeout_secs = match request.idle_timeout_secs {
+        0 => config_state
+            .get_config_snapshot()
+            .find_by_endpoint(
+                &connection_info.host,
+                connection_info.port,
+                &connection_info.username,
+            )
+            .map_or(0, |conn| conn.options.idle_timeout_secs),
+        secs => secs,
+    };
+    if idle_timeout_secs > 0 {
+        spawn_idle_watchdog(
+            app_handle,
+            session_registry.inner().clone(),
+            session_id.clone(),
+            idle_tracker,
+            idle_timeout_secs,
+        );
+    }
+
     let ws_url = format!("ws://localhost:{}", port);
     let session_info = session_registry
         .get(&session_id)
@@ -590,6 +631,388 @@ pub async fn create_terminal(
     })
 }
 
+/// 处理 WebSocket bridge 断开事件
+///
+/// - 可恢复的断开（心跳超时、网络错误等）：保留 PTY `detach_grace`，期间
+///   `recreate_terminal_pty` 可重新挂载；超时后释放连接引用
+/// - AcceptTimeout：前端从未连接，通知前端并完全移除会话
+/// - 其他：只更新状态，终端关联由 `close_terminal` 显式移除
+///
+/// Note: connection_status_changed events are emitted by heartbeat monitoring
+/// Important: 可恢复断开时不要移除 terminal_id 或释放连接，因为重连时需要这些信息
+fn spawn_ws_disconnect_handler(
+    disconnect_rx: tokio::sync::oneshot::Receiver<DisconnectReason>,
+    session_id: String,
+    connection_id: String,
+    session_registry: Arc<SessionRegistry>,
+    connection_registry: Arc<SshConnectionRegistry>,
+    detach_grace: Duration,
+    label: &'static str,
+) {
+    let node_emitter = connection_registry.node_emitter(); // Oxide-Next Phase 2
+    tokio::spawn(async move {
+        let Ok(reason) = disconnect_rx.await else {
+            return;
+        };
+        warn!(
+            "{} {} WebSocket bridge disconnected: {:?}",
+            label, session_id, reason
+        );
+
+        if reason.is_recoverable() {
+            // 🔧 修复 ref_count 泄漏：超时后释放连接引用
+            let conn_reg_for_cleanup = connection_registry.clone();
+            let session_id_for_cleanup = session_id.clone();
+            let _ = session_registry.mark_ws_detached_with_cleanup(
+                &session_id,
+                detach_grace,
+                Some(move |conn_id: String| {
+                    // 在 TTL 过期时释放连接池引用
+                    let conn_reg = conn_reg_for_cleanup;
+                    let sid = session_id_for_cleanup;
+                    tokio::spawn(async move {
+                        info!(
+                            "Releasing connection {} ref after WS detach timeout (session: {})",
+                            conn_id, sid
+                        );
+                        let _ = conn_reg.remove_terminal(&conn_id, &sid).await;
+                        let _ = conn_reg.release(&conn_id).await;
+                    });
+                }),
+            );
+        } else if matches!(reason, DisconnectReason::AcceptTimeout) {
+            // 前端从未连接，保留这个会话没有意义
+            warn!(
+                "{} {} WS accept timeout, removing from registries",
+                label, session_id
+            );
+            // 🔴 关键修复：发送 disconnected 事件通知前端
+            // 这样前端可以清理掉对这个已失效 session 的引用
+            connection_registry
+                .emit_connection_status_changed(&connection_id, "disconnected")
+                .await;
+
+            // Oxide-Next Phase 2: node:state 事件
+            if let Some(ref emitter) = node_emitter {
+                emitter.emit_state_from_connection(
+                    &connection_id,
+                    &crate::ssh::ConnectionState::Disconnected,
+                    "WS accept timeout",
+                );
+            }
+
+            // 从连接的终端列表中移除
+            let _ = connection_registry
+                .remove_terminal(&connection_id, &session_id)
+                .await;
+            // 释放连接引用
+            let _ = connection_registry.release(&connection_id).await;
+            // 完全移除会话
+            let _ = session_registry.disconnect_complete(&session_id, true);
+        } else {
+            // 其他不可恢复的断开：只更新状态，不移除
+            // 终端关联由 close_terminal 命令显式移除
+            let _ = session_registry.disconnect_complete(&session_id, false);
+        }
+    });
+}
+
+/// 空闲超时监控：超过 `timeout_secs` 无 PTY 读写时优雅断开并通知前端
+///
+/// 断开走状态机（`close_session` → `start_disconnecting`），
+/// 连接引用的释放仍由前端收到事件后调用 `close_terminal` 完成。
+fn spawn_idle_watchdog(
+    app_handle: AppHandle,
+    session_registry: Arc<SessionRegistry>,
+    session_id: String,
+    idle_tracker: Arc<IdleTracker>,
+    timeout_secs: u64,
+) {
+    tokio::spawn(async move {
+        let registry = session_registry.clone();
+        let sid = session_id.clone();
+        let idle = idle::wait_for_idle(
+            idle_tracker,
+            Duration::from_secs(timeout_secs),
+            idle::IDLE_POLL_INTERVAL,
+            move || {
+                registry
+                    .with_session(&sid, |entry| entry.state_machine.is_active())
+                    .unwrap_or(false)
+            },
+        )
+        .await;
+
+        let Some(idle_for) = idle else {
+            return;
+        };
+
+        info!(
+            "Session {} idle for {}s (timeout {}s), disconnecting",
+            session_id,
+            idle_for.as_secs(),
+            timeout_secs
+        );
+
+        if let Err(e) = session_registry.close_session(&session_id).await {
+            warn!("Failed to close idle session {}: {}", session_id, e);
+        }
+
+        use tauri::Emitter;
+        let payload = IdleDisconnectPayload {
+            session_id: session_id.clone(),
+            idle_secs: idle_for.as_secs(),
+            timeout_secs,
+        };
+        if let Err(e) = app_handle.emit(event_names::SESSION_IDLE_DISCONNECT, &payload) {
+            warn!("Failed to emit idle disconnect event: {}", e);
+        }
+    });
+}
+
+/// 活动通知：订阅终端输出，命中响铃 / 空闲后输出 / 模式时发出 `session:notify:{id}`
+///
+/// 任务随 session 的输出广播关闭而结束；配置可随时通过 `set_session_notify` 修改。
+fn spawn_activity_notifier(
+    app_handle: AppHandle,
+    session_registry: &SessionRegistry,
+    session_id: String,
+    notify_on: Option<NotifyOn>,
+) {
+    let Some((notifier, mut output_rx)) = session_registry.with_session(&session_id, |entry| {
+        (entry.activity_notifier.clone(), entry.output_tx.subscribe())
+    }) else {
+        return;
+    };
+
+    if let Some(notify_on) = notify_on {
+        if let Err(e) = notifier.lock().set_config(notify_on) {
+            warn!("Invalid notify pattern for session {}: {}", session_id, e);
+        }
+    }
+
+    tokio::spawn(async move {
+        use tauri::Emitter;
+        use tokio::sync::broadcast::error::RecvError;
+
+        let event = format!("{}{}", event_names::SESSION_NOTIFY_PREFIX, session_id);
+        loop {
+            let data = match output_rx.recv().await {
+                Ok(data) => data,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            let triggers = notifier.lock().feed(&data, std::time::Instant::now());
+            for trigger in triggers {
+                let payload = SessionNotifyPayload {
+                    session_id: session_id.clone(),
+                    trigger,
+                };
+                if let Err(e) = app_handle.emit(&event, &payload) {
+                    warn!("Failed to emit session notify event: {}", e);
+                }
+            }
+        }
+    });
+}
+
+/// 设置终端活动通知（替换当前配置，`pattern` 为正则且区分大小写）
+#[tauri::command]
+pub async fn set_session_notify(
+    session_id: String,
+    notify_on: NotifyOn,
+    session_registry: State<'_, Arc<SessionRegistry>>,
+) -> Result<(), String> {
+    let notifier = session_registry
+        .with_session(&session_id, |entry| entry.activity_notifier.clone())
+        .ok_or_else(|| "Session not found".to_string())?;
+    notifier
+        .lock()
+        .set_config(notify_on)
+        .map_err(|e| format!("Invalid regex: {}", e))
+}
+
+/// 多行粘贴确认：把 bridge 扣留的粘贴转发为 `session:paste_confirm` 事件
+///
+/// 扣留状态在 session 上，跨 bridge 重建保留；任务随 session 移除而结束。
+pub(crate) fn spawn_paste_confirm_forwarder(
+    app_handle: AppHandle,
+    session_registry: &SessionRegistry,
+    session_id: String,
+    policy: Option<PasteGuardPolicy>,
+) {
+    let Some(guard) = session_registry.with_session(&session_id, |entry| entry.paste_guard.clone())
+    else {
+        return;
+    };
+    if let Some(policy) = policy {
+        guard.set_policy(policy);
+    }
+    let mut held_rx = guard.subscribe();
+    // Hold only the receiver: the task must not keep the guard alive
+    drop(guard);
+
+    tokio::spawn(async move {
+        use tauri::Emitter;
+        use tokio::sync::broadcast::error::RecvError;
+
+        loop {
+            let paste = match held_rx.recv().await {
+                Ok(paste) => paste,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            let payload = PasteConfirmPayload {
+                session_id: session_id.clone(),
+                paste,
+            };
+            if let Err(e) = app_handle.emit(event_names::SESSION_PASTE_CONFIRM, &payload) {
+                warn!("Failed to emit paste confirm event: {}", e);
+            }
+        }
+    });
+}
+
+/// 设置多行粘贴确认策略
+#[tauri::command]
+pub async fn set_paste_guard(
+    session_id: String,
+    policy: PasteGuardPolicy,
+    session_registry: State<'_, Arc<SessionRegistry>>,
+) -> Result<(), String> {
+    let guard = session_registry
+        .with_session(&session_id, |entry| entry.paste_guard.clone())
+        .ok_or_else(|| "Session not found".to_string())?;
+    guard.set_policy(policy);
+    Ok(())
+}
+
+/// 发送（`send = true`）或丢弃一条等待确认的粘贴
+#[tauri::command]
+pub async fn confirm_paste(
+    session_id: String,
+    paste_id: u64,
+    send: bool,
+    session_registry: State<'_, Arc<SessionRegistry>>,
+) -> Result<(), String> {
+    let (guard, cmd_tx) = session_registry
+        .with_session(&session_id, |entry| {
+            (entry.paste_guard.clone(), entry.cmd_tx.clone())
+        })
+        .ok_or_else(|| "Session not found".to_string())?;
+    let data = guard
+        .take(paste_id)
+        .ok_or_else(|| format!("Paste {} is no longer pending", paste_id))?;
+    if !send {
+        return Ok(());
+    }
+    let cmd_tx = cmd_tx.ok_or_else(|| "Session is not attached".to_string())?;
+    cmd_tx
+        .send(crate::ssh::SessionCommand::Data(data))
+        .await
+        .map_err(|e| format!("Failed to send paste: {}", e))
+}
+
+/// 开始录制终端输出为 asciicast v2 文件，返回文件路径
+///
+/// 未指定 `path` 时写入 `~/.oxideterm/recordings/{session_id}-{时间}.cast`。
+/// 指定的 `path` 须为已存在目录下的绝对 `.cast` 路径；已有文件仅在 `overwrite` 时覆盖。
+/// 文件超过 64 MiB 后不再追加事件；session 关闭时自动结束录制。
+#[tauri::command]
+pub async fn session_start_recording(
+    session_id: String,
+    path: Option<String>,
+    overwrite: Option<bool>,
+    session_registry: State<'_, Arc<SessionRegistry>>,
+) -> Result<String, String> {
+    let (recorder, mut output_rx, title) = session_registry
+        .with_session(&session_id, |entry| {
+            (
+                entry.recorder.clone(),
+                entry.output_tx.subscribe(),
+                entry.config.name.clone(),
+            )
+        })
+        .ok_or_else(|| "Session not found".to_string())?;
+
+    let path = match path {
+        Some(path) => resolve_cast_path(Path::new(&path))
+            .map_err(|e| format!("Invalid recording path: {}", e))?,
+        None => {
+            let dir = recordings_dir().map_err(|e| e.to_string())?;
+            tokio::fs::create_dir_all(&dir)
+                .await
+                .map_err(|e| format!("Failed to create recordings directory: {}", e))?;
+            let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+            dir.join(format!("{}-{}.cast", session_id, stamp))
+        }
+    };
+
+    let mut stop_rx = recorder
+        .lock()
+        .start(
+            path.clone(),
+            title.as_deref(),
+            DEFAULT_MAX_CAST_BYTES,
+            overwrite.unwrap_or(false),
+        )
+        .map_err(|e| format!("Failed to start recording: {}", e))?;
+    info!("Recording session {} to {}", session_id, path.display());
+
+    tokio::spawn(async move {
+        use tokio::sync::broadcast::error::RecvError;
+
+        loop {
+            tokio::select! {
+                _ = &mut stop_rx => break,
+                result = output_rx.recv() => match result {
+                    Ok(data) => {
+                        let mut recorder = recorder.lock();
+                        if let Err(e) = recorder.output(&data) {
+                            warn!("Recording of session {} failed: {}", session_id, e);
+                            recorder.stop();
+                            break;
+                        }
+                    }
+                    Err(RecvError::Lagged(n)) => {
+                        warn!("Recording of session {} skipped {} output chunks", session_id, n);
+                    }
+                    Err(RecvError::Closed) => {
+                        recorder.lock().stop();
+                        break;
+                    }
+                },
+            }
+        }
+    });
+
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// 结束录制，返回录制文件路径
+#[tauri::command]
+pub async fn session_stop_recording(
+    session_id: String,
+    session_registry: State<'_, Arc<SessionRegistry>>,
+) -> Result<String, String> {
+    let recorder = session_registry
+        .with_session(&session_id, |entry| entry.recorder.clone())
+        .ok_or_else(|| "Session not found".to_string())?;
+    let done_rx = recorder
+        .lock()
+        .stop()
+        .ok_or_else(|| "Session is not being recorded".to_string())?;
+    let summary = done_rx
+        .await
+        .map_err(|_| "Recording writer exited unexpectedly".to_string())?
+        .map_err(|e| format!("Failed to finish recording: {}", e))?;
+    info!(
+        "Recording of session {} finished: {} bytes, {} ms, truncated={}",
+        session_id, summary.bytes, summary.duration_ms, summary.truncated
+    );
+    Ok(summary.path.to_string_lossy().into_owned())
+}
+
 /// 关闭终端（不断开 SSH 连接）
 #[tauri::command]
 pub async fn close_terminal(
@@ -662,6 +1085,7 @@ pub async fn recreate_terminal_pty(
     connection_registry: State<'_, Arc<SshConnectionRegistry>>,
     session_registry: State<'_, Arc<SessionRegistry>>,
     _forwarding_registry: State<'_, Arc<ForwardingRegistry>>,
+    bridge_manager: State<'_, BridgeManager>,
 ) -> Result<RecreateTerminalResponse, String> {
     info!("Recreate terminal PTY request: {}", session_id);
 
@@ -685,15 +1109,42 @@ pub async fn recreate_terminal_pty(
                 stdout_rx: output_tx.subscribe(),
             };
 
-            let (_, port, token, _disconnect_rx) =
-                WsBridge::start_extended_with_disconnect(extended_handle, scroll_buffer, true)
-                    .await
-                    .map_err(|e| format!("Failed to start WebSocket bridge: {}", e))?;
+            // Replay the buffered tail so the reattached terminal isn't blank
+            let bridge_policy = BridgePolicy {
+                paste_guard: session_registry
+                    .with_session(&session_id, |entry| entry.paste_guard.clone()),
+                ..bridge_manager.policy()
+            };
+            let (_, port, token, disconnect_rx) = WsBridge::start_extended_with_policy(
+                extended_handle,
+                scroll_buffer,
+                true,
+                bridge_policy.clone(),
+            )
+            .await
+            .map_err(|e| format!("Failed to start WebSocket bridge: {}", e))?;
 
+            // Cancels the pending detach cleanup: the PTY is attached again
             session_registry
                 .update_ws_info(&session_id, port, token.clone(), cmd_tx, handle_controller)
                 .map_err(|e| format!("Failed to update session: {}", e))?;
 
+            // A later drop detaches again instead of leaving the PTY unmanaged
+            if let Some(connection_id) = session_registry
+                .get(&session_id)
+                .and_then(|info| info.connection_id)
+            {
+                spawn_ws_disconnect_handler(
+                    disconnect_rx,
+                    session_id.clone(),
+                    connection_id,
+                    session_registry.inner().clone(),
+                    connection_registry.inner().clone(),
+                    bridge_policy.detach_grace,
+                    "Reattached session",
+                );
+            }
+
             let ws_url = format!("ws://localhost:{}", port);
 
             info!(
@@ -762,8 +1213,20 @@ pub async fn recreate_terminal_pty(
         .with_session(&session_id, |entry| entry.output_tx.clone())
         .ok_or_else(|| "Session output channel not found".to_string())?;
 
+    let idle_tracker = session_registry
+        .with_session(&session_id, |entry| entry.idle_tracker.clone())
+        .ok_or_else(|| "Session not found in registry".to_string())?;
+
+    let recorder = session_registry
+        .with_session(&session_id, |entry| entry.recorder.clone())
+        .ok_or_else(|| "Session not found in registry".to_string())?;
+    let _ = recorder
+        .lock()
+        .resize(config.cols as u16, config.rows as u16);
+
     let output_rx = output_tx.subscribe();
     let scroll_buffer_clone = scroll_buffer.clone();
+    let idle_tracker_clone = idle_tracker.clone();
 
     let sid = session_id.clone();
     tokio::spawn(async move {
@@ -774,6 +1237,7 @@ pub async fn recreate_terminal_pty(
                 Some(cmd) = cmd_rx.recv() => {
                     match cmd {
                         SessionCommand::Data(data) => {
+                            idle_tracker_clone.touch();
                             if let Err(e) = channel.data(&data[..]).await {
                                 tracing::error!("Failed to send data to SSH channel: {}", e);
                                 break;
@@ -783,6 +1247,9 @@ pub async fn recreate_terminal_pty(
                             if let Err(e) = channel.window_change(cols as u32, rows as u32, 0, 0).await {
                                 tracing::error!("Failed to resize PTY: {}", e);
                             }
+                            if let Err(e) = recorder.lock().resize(cols, rows) {
+                                warn!("Failed to record resize: {}", e);
+                            }
                         }
                         SessionCommand::Close => {
                             let _ = channel.eof().await;
@@ -794,6 +1261,7 @@ pub async fn recreate_terminal_pty(
                 Some(msg) = channel.wait() => {
                     match msg {
                         ChannelMsg::Data { data } => {
+                            idle_tracker_clone.touch();
                             let bytes = data.to_vec();
                             let lines = parse_terminal_output(&bytes);
                             if !lines.is_empty() {
@@ -803,6 +1271,7 @@ pub async fn recreate_terminal_pty(
                         }
                         ChannelMsg::ExtendedData { data, ext } => {
                             if ext == 1 {
+                                idle_tracker_clone.touch();
                                 let bytes = data.to_vec();
                                 let lines = parse_terminal_output(&bytes);
                                 if !lines.is_empty() {
@@ -832,73 +1301,18 @@ pub async fn recreate_terminal_pty(
     };
 
     // 启动新的 WebSocket bridge
-    let (_, port, token, disconnect_rx) =
-        WsBridge::start_extended_with_disconnect(extended_handle, scroll_buffer, false)
-            .await
-            .map_err(|e| format!("Failed to start WebSocket bridge: {}", e))?;
-
-    // 处理 WebSocket 断开事件
-    // Note: connection_status_changed events are emitted by heartbeat monitoring
-    // Important: 不要在这里移除 terminal_id 或释放连接，因为重连时需要这些信息
-    let session_id_clone = session_id.clone();
-    let registry_clone = session_registry.inner().clone();
-    let conn_registry_clone = connection_registry.inner().clone();
-    let conn_id_clone = connection_id.clone();
-    let node_emitter_clone = conn_registry_clone.node_emitter(); // Oxide-Next Phase 2
-    tokio::spawn(async move {
-        if let Ok(reason) = disconnect_rx.await {
-            warn!(
-                "Recreated session {} WebSocket bridge disconnected: {:?}",
-                session_id_clone, reason
-            );
-            if reason.is_recoverable() {
-                // 🔧 修复 ref_count 泄漏：超时后释放连接引用
-                let conn_reg_for_cleanup = conn_registry_clone.clone();
-                let session_id_for_cleanup = session_id_clone.clone();
-                let _ = registry_clone.mark_ws_detached_with_cleanup(
-                    &session_id_clone,
-                    Duration::from_secs(300),
-                    Some(move |conn_id: String| {
-                        let conn_reg = conn_reg_for_cleanup;
-                        let sid = session_id_for_cleanup;
-                        tokio::spawn(async move {
-                            info!("Releasing connection {} ref after recreated WS detach timeout (session: {})", conn_id, sid);
-                            let _ = conn_reg.remove_terminal(&conn_id, &sid).await;
-                            let _ = conn_reg.release(&conn_id).await;
-                        });
-                    }),
-                );
-            } else {
-                // AcceptTimeout: 前端没有连接，清理会话
-                if matches!(reason, crate::bridge::DisconnectReason::AcceptTimeout) {
-                    warn!(
-                        "Recreated session {} WS accept timeout, removing from registries",
-                        session_id_clone
-                    );
-                    // 🔴 关键修复：发送 disconnected 事件通知前端
-                    conn_registry_clone
-                        .emit_connection_status_changed(&conn_id_clone, "disconnected")
-                        .await;
-                    // Oxide-Next Phase 2: node:state 事件
-                    if let Some(ref emitter) = node_emitter_clone {
-                        emitter.emit_state_from_connection(
-                            &conn_id_clone,
-                            &crate::ssh::ConnectionState::Disconnected,
-                            "WS accept timeout (recreate)",
-                        );
-                    }
-                    let _ = conn_registry_clone
-                        .remove_terminal(&conn_id_clone, &session_id_clone)
-                        .await;
-                    let _ = conn_registry_clone.release(&conn_id_clone).await;
-                    let _ = registry_clone.disconnect_complete(&session_id_clone, true);
-                } else {
-                    // 其他不可恢复的断开：只更新状态
-                    let _ = registry_clone.disconnect_complete(&session_id_clone, false);
-                }
-            }
-        }
-    });
+    let bridge_policy = BridgePolicy {
+        paste_guard: session_registry.with_session(&session_id, |entry| entry.paste_guard.clone()),
+        ..bridge_manager.policy()
+    };
+    let (_, port, token, disconnect_rx) = WsBridge::start_extended_with_policy(
+        extended_handle,
+        scroll_buffer,
+        false,
+        bridge_policy.clone(),
+    )
+    .await
+    .map_err(|e| format!("Failed to start WebSocket bridge: {}", e))?;
 
     // 更新 session registry 的 ws_port 和 ws_token
     session_registry
@@ -911,6 +1325,17 @@ pub async fn recreate_terminal_pty(
         )
         .map_err(|e| format!("Failed to update session: {}", e))?;
 
+    // 处理 WebSocket 断开事件（在 update_ws_info 之后注册，避免被其清除 detach 状态）
+    spawn_ws_disconnect_handler(
+        disconnect_rx,
+        session_id.clone(),
+        connection_id.clone(),
+        session_registry.inner().clone(),
+        connection_registry.inner().clone(),
+        bridge_policy.detach_grace,
+        "Recreated session",
+    );
+
     let ws_url = format!("ws://localhost:{}", port);
 
     info!(
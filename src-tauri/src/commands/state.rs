@@ -0,0 +1,41 @@
+//! State Store Commands
+//!
+//! Read-only diagnostics for the persisted session/forward state database.
+
+use crate::state::StateStore;
+use serde::Serialize;
+use std::sync::Arc;
+use tauri::State;
+
+/// State database statistics
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateStatsResponse {
+    pub session_count: usize,
+    pub forward_count: usize,
+    /// Size of the database file on disk
+    pub db_bytes: u64,
+    pub db_path: String,
+}
+
+/// Get state database statistics
+#[tauri::command]
+pub async fn state_get_stats(
+    store: State<'_, Arc<StateStore>>,
+) -> Result<StateStatsResponse, String> {
+    let store = store.inner().clone();
+    let db_path = store.path().display().to_string();
+
+    // Counting iterates both tables; keep it off the async runtime
+    let stats = tokio::task::spawn_blocking(move || store.stats())
+        .await
+        .map_err(|e| format!("State stats task failed: {}", e))?
+        .map_err(|e| e.to_string())?;
+
+    Ok(StateStatsResponse {
+        session_count: stats.session_count,
+        forward_count: stats.forward_count,
+        db_bytes: stats.db_bytes,
+        db_path,
+    })
+}
@@ -0,0 +1,490 @@
+//! Credential consolidation
+//!
+//! Older versions left secrets under a mix of keychain IDs (`oxide_hop_<uuid>`,
+//! `oxideterm-<uuid>`, IDs shared between connections, ...) and AI keys in
+//! the legacy XOR vault files. Consolidation re-stores every connection
+//! secret under the IDs `.oxide` import uses (`oxide_conn_<id>`,
+//! `oxide_key_<id>`, `oxide_cert_<id>`, with `_hop<n>` for jump hosts) and
+//! moves vault files into the AI keychain.
+//!
+//! Nothing is removed until its replacement has been read back: legacy
+//! files are deleted right after their key verifies, old keychain entries
+//! only by [`Consolidation::delete_stale`] once the updated config is saved
+//! and everything else naming the old IDs (the persisted session tree, see
+//! [`Consolidation::renamed_ids`]) has been updated.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use serde::Serialize;
+
+use super::keychain::CredentialStore;
+use super::types::{ConfigFile, SavedAuth};
+use super::vault::{AiProviderVault, AiVault, VaultError};
+
+/// A secret that now lives under a new name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MovedCredential {
+    pub from: String,
+    pub to: String,
+}
+
+/// A secret that could not be moved (its old copy is left in place)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConsolidationFailure {
+    pub source: String,
+    pub error: String,
+}
+
+/// What `config_consolidate_credentials` did
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsolidationReport {
+    pub moved: Vec<MovedCredential>,
+    /// Secrets already stored under their canonical ID
+    pub unchanged: usize,
+    pub failures: Vec<ConsolidationFailure>,
+}
+
+/// Result of the first phase, before old keychain entries are removed
+#[derive(Debug)]
+pub struct Consolidation {
+    pub report: ConsolidationReport,
+    /// Old keychain IDs no connection refers to anymore
+    stale_ids: Vec<String>,
+    /// Old keychain ID → new ID, for every secret that moved
+    renamed: HashMap<String, String>,
+}
+
+impl Consolidation {
+    /// Keychain IDs that moved (old → new). An ID shared by several
+    /// connections maps to one of its copies.
+    pub fn renamed_ids(&self) -> &HashMap<String, String> {
+        &self.renamed
+    }
+
+    /// Delete the old keychain entries. Call only after the config holding
+    /// the new IDs has been saved.
+    pub fn delete_stale(mut self, keychain: &dyn CredentialStore) -> ConsolidationReport {
+        for id in self.stale_ids {
+            if let Err(e) = keychain.delete(&id) {
+                self.report.failures.push(ConsolidationFailure {
+                    source: format!("keychain:{}", id),
+                    error: format!("moved, but the old entry could not be deleted: {}", e),
+                });
+            }
+        }
+        self.report
+    }
+
+    /// Leave the old keychain entries in place because something may still
+    /// name them; each is reported as a failure with `reason`
+    pub fn keep_stale(mut self, reason: &str) -> ConsolidationReport {
+        for id in self.stale_ids {
+            self.report.failures.push(ConsolidationFailure {
+                source: format!("keychain:{}", id),
+                error: format!("moved, but the old entry was kept: {}", reason),
+            });
+        }
+        self.report
+    }
+}
+
+/// Re-store every connection secret under its canonical ID and move legacy
+/// AI vault files found in `legacy_dir` into `ai_keychain`.
+///
+/// `config` is updated in place to point at the new IDs; the single-key
+/// `ai.vault` is stored under `legacy_provider_id`.
+pub fn consolidate_credentials(
+    config: &mut ConfigFile,
+    keychain: &dyn CredentialStore,
+    ai_keychain: &dyn CredentialStore,
+    legacy_dir: Option<&Path>,
+    legacy_provider_id: &str,
+) -> Consolidation {
+    let mut report = ConsolidationReport::default();
+    let mut replaced = Vec::new();
+
+    for conn in config.connections.iter_mut() {
+        let base = conn.id.clone();
+        let label = conn.name.clone();
+        migrate_auth(
+            &mut conn.auth,
+            &base,
+            &label,
+            keychain,
+            &mut report,
+            &mut replaced,
+        );
+        for (index, hop) in conn.proxy_chain.iter_mut().enumerate() {
+            let label = format!("{} (jump host {})", label, index + 1);
+            migrate_auth(
+                &mut hop.auth,
+                &format!("{}_hop{}", base, index),
+                &label,
+                keychain,
+                &mut report,
+                &mut replaced,
+            );
+        }
+    }
+
+    // An ID can be shared (jump hosts copied from another connection), so
+    // only drop the ones nothing refers to anymore
+    let referenced = config.keychain_ids();
+    let mut stale_ids: Vec<String> = replaced
+        .iter()
+        .map(|(old, _)| old.clone())
+        .filter(|id| !referenced.contains(id))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    stale_ids.sort();
+
+    if let Some(dir) = legacy_dir {
+        migrate_legacy_vaults(dir, ai_keychain, legacy_provider_id, &mut report);
+    }
+
+    Consolidation {
+        report,
+        stale_ids,
+        renamed: replaced.into_iter().collect(),
+    }
+}
+
+fn migrate_auth(
+    auth: &mut SavedAuth,
+    base: &str,
+    label: &str,
+    keychain: &dyn CredentialStore,
+    report: &mut ConsolidationReport,
+    replaced: &mut Vec<(String, String)>,
+) {
+    let (current, canonical) = match auth {
+        SavedAuth::Password { keychain_id } => (keychain_id, format!("oxide_conn_{}", base)),
+        SavedAuth::Key {
+            passphrase_keychain_id: Some(id),
+            ..
+        } => (id, format!("oxide_key_{}", base)),
+        SavedAuth::Certificate {
+            passphrase_keychain_id: Some(id),
+            ..
+        } => (id, format!("oxide_cert_{}", base)),
+        _ => return,
+    };
+
+    if *current == canonical {
+        report.unchanged += 1;
+        return;
+    }
+
+    let source = format!("keychain:{}", current);
+    match move_secret(keychain, current, keychain, &canonical) {
+        Ok(()) => {
+            report.moved.push(MovedCredential {
+                from: format!("{}: {}", label, source),
+                to: format!("keychain:{}", canonical),
+            });
+            let old = std::mem::replace(current, canonical.clone());
+            replaced.push((old, canonical));
+        }
+        Err(error) => report.failures.push(ConsolidationFailure {
+            source: format!("{}: {}", label, source),
+            error,
+        }),
+    }
+}
+
+/// Copy a secret and confirm the copy reads back identically
+fn move_secret(
+    from: &dyn CredentialStore,
+    from_id: &str,
+    to: &dyn CredentialStore,
+    to_id: &str,
+) -> Result<(), String> {
+    let secret = from.get(from_id).map_err(|e| e.to_string())?;
+    write_verified(to, to_id, &secret)
+}
+
+fn write_verified(store: &dyn CredentialStore, id: &str, secret: &str) -> Result<(), String> {
+    store.store(id, secret).map_err(|e| e.to_string())?;
+    match store.get(id) {
+        Ok(read_back) if read_back == secret => Ok(()),
+        Ok(_) => Err("read-back did not match the stored value".to_string()),
+        Err(e) => Err(format!("read-back failed: {}", e)),
+    }
+}
+
+fn migrate_legacy_vaults(
+    dir: &Path,
+    ai_keychain: &dyn CredentialStore,
+    legacy_provider_id: &str,
+    report: &mut ConsolidationReport,
+) {
+    let single = AiVault::new(dir.to_path_buf());
+    if single.exists() {
+        migrate_vault_key(
+            "file:ai.vault",
+            single.load(),
+            || single.delete(),
+            ai_keychain,
+            legacy_provider_id,
+            report,
+        );
+    }
+
+    let providers = AiProviderVault::new(dir.to_path_buf());
+    let provider_ids = match providers.list_providers() {
+        Ok(ids) => ids,
+        Err(e) => {
+            report.failures.push(ConsolidationFailure {
+                source: "file:ai_keys".to_string(),
+                error: e.to_string(),
+            });
+            return;
+        }
+    };
+    for provider_id in provider_ids {
+        migrate_vault_key(
+            &format!("file:ai_keys/{}.vault", provider_id),
+            providers.load(&provider_id),
+            || providers.delete(&provider_id),
+            ai_keychain,
+            &provider_id,
+            report,
+        );
+    }
+}
+
+fn migrate_vault_key(
+    source: &str,
+    loaded: Result<String, VaultError>,
+    delete_file: impl FnOnce() -> Result<(), VaultError>,
+    ai_keychain: &dyn CredentialStore,
+    provider_id: &str,
+    report: &mut ConsolidationReport,
+) {
+    let fail = |report: &mut ConsolidationReport, error: String| {
+        report.failures.push(ConsolidationFailure {
+            source: source.to_string(),
+            error,
+        })
+    };
+
+    let key = match loaded {
+        Ok(key) => key,
+        Err(e) => return fail(report, e.to_string()),
+    };
+
+    // The keychain copy is what the app reads; never overwrite a newer key
+    match ai_keychain.get(provider_id) {
+        Ok(existing) if existing != key => {
+            return fail(
+                report,
+                format!(
+                    "keychain already holds a different key for {}; file left in place",
+                    provider_id
+                ),
+            )
+        }
+        Ok(_) => {}
+        Err(_) => {
+            if let Err(e) = write_verified(ai_keychain, provider_id, &key) {
+                return fail(report, e);
+            }
+        }
+    }
+
+    if let Err(e) = delete_file() {
+        return fail(
+            report,
+            format!("moved, but the file could not be deleted: {}", e),
+        );
+    }
+    report.moved.push(MovedCredential {
+        from: source.to_string(),
+        to: format!("ai keychain:{}", provider_id),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::keychain::MemoryCredentialStore;
+    use crate::config::types::{ProxyHopConfig, SavedConnection};
+    use tempfile::TempDir;
+
+    /// A target with a legacy password ID, a jump host sharing a passphrase
+    /// ID with another connection, and one connection already consolidated
+    fn seeded() -> (ConfigFile, MemoryCredentialStore) {
+        let keychain = MemoryCredentialStore::new();
+        keychain.store("oxideterm-legacy-pw", "hunter2").unwrap();
+        keychain.store("oxide_hop_key_shared", "jump-pass").unwrap();
+
+        let mut jump = SavedConnection::new_key("jump", "bastion", 22, "ops", "/k/jump");
+        jump.auth = SavedAuth::Key {
+            key_path: "/k/jump".into(),
+            has_passphrase: true,
+            passphrase_keychain_id: Some("oxide_hop_key_shared".into()),
+        };
+
+        let mut target =
+            SavedConnection::new_password("db", "db.internal", 22, "dba", "oxideterm-legacy-pw");
+        target.proxy_chain.push(ProxyHopConfig {
+            host: "bastion".into(),
+            port: 22,
+            username: "ops".into(),
+            auth: jump.auth.clone(),
+        });
+
+        let mut tidy = SavedConnection::new_password("tidy", "tidy", 22, "me", "");
+        let tidy_id = format!("oxide_conn_{}", tidy.id);
+        keychain.store(&tidy_id, "ok").unwrap();
+        tidy.auth = SavedAuth::Password {
+            keychain_id: tidy_id,
+        };
+
+        let mut config = ConfigFile::default();
+        config.add_connection(jump);
+        config.add_connection(target);
+        config.add_connection(tidy);
+        (config, keychain)
+    }
+
+    #[test]
+    fn test_connection_secrets_move_to_canonical_ids() {
+        let (mut config, keychain) = seeded();
+        let ai_keychain = MemoryCredentialStore::new();
+
+        let consolidation =
+            consolidate_credentials(&mut config, &keychain, &ai_keychain, None, "builtin-openai");
+        let renamed = consolidation.renamed_ids().clone();
+        let report = consolidation.delete_stale(&keychain);
+
+        assert_eq!(report.moved.len(), 3);
+        assert_eq!(report.unchanged, 1);
+        assert!(report.failures.is_empty(), "{:?}", report.failures);
+
+        let jump = &config.connections[0];
+        let target = &config.connections[1];
+        let password_id = format!("oxide_conn_{}", target.id);
+        let hop_id = format!("oxide_key_{}_hop0", target.id);
+        assert_eq!(
+            target.auth,
+            SavedAuth::Password {
+                keychain_id: password_id.clone()
+            }
+        );
+        assert!(matches!(
+            &target.proxy_chain[0].auth,
+            SavedAuth::Key { passphrase_keychain_id: Some(id), .. } if *id == hop_id
+        ));
+        assert_eq!(keychain.get(&password_id).unwrap(), "hunter2");
+        assert_eq!(keychain.get(&hop_id).unwrap(), "jump-pass");
+        assert_eq!(renamed.len(), 2);
+        assert_eq!(renamed["oxideterm-legacy-pw"], password_id);
+        assert!(renamed["oxide_hop_key_shared"].starts_with("oxide_key_"));
+        assert_eq!(
+            keychain.get(&format!("oxide_key_{}", jump.id)).unwrap(),
+            "jump-pass"
+        );
+
+        // Old entries are gone once nothing refers to them
+        assert!(!keychain.exists("oxideterm-legacy-pw").unwrap());
+        assert!(!keychain.exists("oxide_hop_key_shared").unwrap());
+        assert_eq!(keychain.len(), 4);
+
+        // A second run has nothing left to do
+        let again = consolidate_credentials(&mut config, &keychain, &ai_keychain, None, "x")
+            .delete_stale(&keychain);
+        assert!(again.moved.is_empty());
+        assert_eq!(again.unchanged, 4);
+    }
+
+    #[test]
+    fn test_missing_secret_is_reported_and_reference_kept() {
+        let (mut config, keychain) = seeded();
+        keychain.delete("oxideterm-legacy-pw").unwrap();
+
+        let report = consolidate_credentials(
+            &mut config,
+            &keychain,
+            &MemoryCredentialStore::new(),
+            None,
+            "builtin-openai",
+        )
+        .delete_stale(&keychain);
+
+        assert_eq!(report.failures.len(), 1);
+        assert!(report.failures[0].source.contains("oxideterm-legacy-pw"));
+        assert_eq!(
+            config.connections[1].auth,
+            SavedAuth::Password {
+                keychain_id: "oxideterm-legacy-pw".into()
+            }
+        );
+    }
+
+    #[test]
+    fn test_legacy_vaults_move_into_ai_keychain() {
+        let dir = TempDir::new().unwrap();
+        AiVault::new(dir.path().to_path_buf())
+            .save("sk-legacy")
+            .unwrap();
+        let providers = AiProviderVault::new(dir.path().to_path_buf());
+        providers.save("builtin-anthropic", "sk-ant").unwrap();
+        providers.save("builtin-gemini", "sk-file").unwrap();
+
+        let ai_keychain = MemoryCredentialStore::new();
+        ai_keychain.store("builtin-gemini", "sk-newer").unwrap();
+
+        let mut config = ConfigFile::default();
+        let keychain = MemoryCredentialStore::new();
+        let report = consolidate_credentials(
+            &mut config,
+            &keychain,
+            &ai_keychain,
+            Some(dir.path()),
+            "builtin-openai",
+        )
+        .delete_stale(&keychain);
+
+        assert_eq!(ai_keychain.get("builtin-openai").unwrap(), "sk-legacy");
+        assert_eq!(ai_keychain.get("builtin-anthropic").unwrap(), "sk-ant");
+        assert!(!AiVault::new(dir.path().to_path_buf()).exists());
+        assert!(!providers.exists("builtin-anthropic"));
+        assert_eq!(report.moved.len(), 2);
+
+        // A conflicting keychain key wins and the file stays for the user
+        assert_eq!(ai_keychain.get("builtin-gemini").unwrap(), "sk-newer");
+        assert!(providers.exists("builtin-gemini"));
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(
+            report.failures[0].source,
+            "file:ai_keys/builtin-gemini.vault"
+        );
+    }
+
+    #[test]
+    fn test_unavailable_keychain_keeps_legacy_files() {
+        let dir = TempDir::new().unwrap();
+        let providers = AiProviderVault::new(dir.path().to_path_buf());
+        providers.save("builtin-openai", "sk-file").unwrap();
+
+        let ai_keychain = MemoryCredentialStore::new();
+        ai_keychain.set_unavailable(true);
+        let keychain = MemoryCredentialStore::new();
+        let report = consolidate_credentials(
+            &mut ConfigFile::default(),
+            &keychain,
+            &ai_keychain,
+            Some(dir.path()),
+            "builtin-openai",
+        )
+        .delete_stale(&keychain);
+
+        assert!(report.moved.is_empty());
+        assert_eq!(report.failures.len(), 1);
+        assert!(providers.exists("builtin-openai"));
+    }
+}
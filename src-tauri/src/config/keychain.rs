@@ -2,60 +2,389 @@
 //!
 //! Securely stores passwords and passphrases in the system keychain.
 //! Uses the `keyring` crate for cross-platform keychain access.
+//!
+//! Callers work against the [`CredentialStore`] trait; [`Keychain`] is the
+//! real implementation and `MemoryCredentialStore`, compiled only for
+//! tests, stands in for it there.
+//!
+//! Storage goes through a [`SecretBackend`] so that a box without a usable
+//! keychain (e.g. Linux without a running secret-service) can be detected
+//! with [`Keychain::is_available`] and, with the user's consent, switched to
+//! the file fallback in [`super::secret_file`].
+//!
+//! [`Keychain::rotate`] moves every secret to a fresh service name. It cannot
+//! discover entries by itself: `keyring` has no listing API, the Windows
+//! Credential Manager and macOS Keychain only support filtered searches
+//! through native calls, and secret-service collections may be locked. The
+//! caller therefore passes the IDs it knows about (connection secrets from
+//! the config file, AI provider IDs from settings); anything else stored
+//! under the old service is left behind.
 
+#[cfg(test)]
+use std::collections::HashMap;
+#[cfg(test)]
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use chrono::Utc;
 use keyring::Entry;
+#[cfg(test)]
+use parking_lot::Mutex;
+use parking_lot::RwLock;
+use serde::Serialize;
 use uuid::Uuid;
 
-/// Service name for keychain entries
-const SERVICE_NAME: &str = "com.oxideterm.ssh";
+/// Default service for SSH passwords and passphrases
+pub const SSH_KEYCHAIN_SERVICE: &str = "com.oxideterm.ssh";
+
+/// Default service for AI provider API keys
+pub const AI_KEYCHAIN_SERVICE: &str = "com.oxideterm.ai";
+
+/// Account used by the availability probe (never written)
+const PROBE_ACCOUNT: &str = "oxideterm-availability-probe";
+
+/// Credential storage keyed by ID (one store per service)
+pub trait CredentialStore: Send + Sync {
+    /// Store a secret under `id`, overwriting any previous value
+    fn store(&self, id: &str, secret: &str) -> Result<(), KeychainError>;
+
+    /// Retrieve a secret (`NotFound` if there is none)
+    fn get(&self, id: &str) -> Result<String, KeychainError>;
+
+    /// Delete a secret (deleting a missing secret succeeds)
+    fn delete(&self, id: &str) -> Result<(), KeychainError>;
+
+    /// Check if a secret exists
+    fn exists(&self, id: &str) -> Result<bool, KeychainError>;
+
+    /// Store a new secret and return its generated ID
+    fn store_new(&self, secret: &str) -> Result<String, KeychainError> {
+        let id = Keychain::generate_id();
+        self.store(&id, secret)?;
+        Ok(id)
+    }
+
+    /// Update an existing secret
+    fn update(&self, id: &str, new_secret: &str) -> Result<(), KeychainError> {
+        self.store(id, new_secret)
+    }
+}
+
+/// Storage behind a [`Keychain`], keyed by service + account
+pub trait SecretBackend: Send + Sync {
+    fn set_password(&self, service: &str, account: &str, secret: &str) -> keyring::Result<()>;
+    fn get_password(&self, service: &str, account: &str) -> keyring::Result<String>;
+    fn delete_password(&self, service: &str, account: &str) -> keyring::Result<()>;
+
+    /// Whether this is the OS keychain (as opposed to a fallback store)
+    fn is_system(&self) -> bool {
+        false
+    }
+}
+
+/// The platform keychain via the `keyring` crate
+pub struct SystemKeyring;
+
+impl SecretBackend for SystemKeyring {
+    fn set_password(&self, service: &str, account: &str, secret: &str) -> keyring::Result<()> {
+        Entry::new(service, account)?.set_password(secret)
+    }
+
+    fn get_password(&self, service: &str, account: &str) -> keyring::Result<String> {
+        Entry::new(service, account)?.get_password()
+    }
+
+    fn delete_password(&self, service: &str, account: &str) -> keyring::Result<()> {
+        Entry::new(service, account)?.delete_credential()
+    }
+
+    fn is_system(&self) -> bool {
+        true
+    }
+}
 
 /// Keychain errors
 #[derive(Debug, thiserror::Error)]
 pub enum KeychainError {
     #[error("Keychain error: {0}")]
-    Keyring(#[from] keyring::Error),
+    Keyring(keyring::Error),
+
+    #[error("System keychain is unavailable ({0}); enable file-based password storage in settings to save credentials")]
+    Unavailable(keyring::Error),
 
     #[error("Secret not found for ID: {0}")]
     NotFound(String),
 }
 
+impl From<keyring::Error> for KeychainError {
+    fn from(e: keyring::Error) -> Self {
+        match e {
+            // The store itself could not be reached (no secret-service, ...)
+            keyring::Error::NoStorageAccess(_) | keyring::Error::PlatformFailure(_) => {
+                KeychainError::Unavailable(e)
+            }
+            e => KeychainError::Keyring(e),
+        }
+    }
+}
+
 /// Keychain manager for storing SSH credentials
+#[derive(Clone)]
 pub struct Keychain {
-    service: String,
+    /// Shared with clones, so a committed [`Rotation`] moves all of them
+    service: Arc<RwLock<String>>,
+    /// Shared with keychains created via `for_service`, so switching the
+    /// backend applies to all of them
+    backend: Arc<RwLock<Arc<dyn SecretBackend>>>,
 }
 
 impl Keychain {
     /// Create a new keychain manager
     pub fn new() -> Self {
-        Self {
-            service: SERVICE_NAME.to_string(),
-        }
+        Self::with_service(SSH_KEYCHAIN_SERVICE)
     }
 
     /// Create with custom service name (for testing)
     pub fn with_service(service: impl Into<String>) -> Self {
+        Self::with_backend(service, Arc::new(SystemKeyring))
+    }
+
+    /// Create with a custom storage backend
+    pub fn with_backend(service: impl Into<String>, backend: Arc<dyn SecretBackend>) -> Self {
         Self {
-            service: service.into(),
+            service: Arc::new(RwLock::new(service.into())),
+            backend: Arc::new(RwLock::new(backend)),
         }
     }
 
+    /// Keychain for another service that shares this one's backend
+    pub fn for_service(&self, service: impl Into<String>) -> Self {
+        Self {
+            service: Arc::new(RwLock::new(service.into())),
+            backend: self.backend.clone(),
+        }
+    }
+
+    /// Service name entries are currently stored under
+    pub fn service(&self) -> String {
+        self.service.read().clone()
+    }
+
+    /// Replace the storage backend (for this keychain and all sharing it)
+    pub fn set_backend(&self, backend: Arc<dyn SecretBackend>) {
+        *self.backend.write() = backend;
+    }
+
+    /// Whether the OS keychain (rather than a fallback) is in use
+    pub fn is_system(&self) -> bool {
+        self.backend().is_system()
+    }
+
+    /// Probe whether the backend can be reached at all.
+    ///
+    /// Reads an entry that never exists: "not found" means the store
+    /// answered, anything else (no secret-service, locked platform store,
+    /// ...) means credential access would fail.
+    pub fn is_available(&self) -> bool {
+        let service = self.service();
+        match self.backend().get_password(&service, PROBE_ACCOUNT) {
+            Ok(_) | Err(keyring::Error::NoEntry) => true,
+            Err(e) => {
+                tracing::warn!("Keychain unavailable: service={}, error={:?}", service, e);
+                false
+            }
+        }
+    }
+
+    fn backend(&self) -> Arc<dyn SecretBackend> {
+        self.backend.read().clone()
+    }
+
+    /// Account name for an ID
+    /// Uses explicit username to ensure stable keychain identity on macOS
+    fn account(id: &str) -> String {
+        format!("{}@{}", whoami::username(), id)
+    }
+
     /// Generate a new unique keychain ID
     pub fn generate_id() -> String {
         format!("oxideterm-{}", Uuid::new_v4())
     }
 
+    /// A fresh service name derived from `base` (one of the defaults)
+    pub fn rotated_service(base: &str) -> String {
+        format!("{}.{}", base, Utc::now().format("%Y%m%d%H%M%S"))
+    }
+
+    /// Copy the secrets stored under `ids` to `new_service`.
+    ///
+    /// Each copy is read back before it counts as migrated; IDs with nothing
+    /// stored are reported as missing. Old entries stay in place and this
+    /// keychain keeps its service until [`Rotation::commit`]. With `dry_run`
+    /// the secrets are only read.
+    pub fn rotate(&self, new_service: &str, ids: &[String], dry_run: bool) -> Rotation {
+        let backend = self.backend();
+        let old_service = self.service();
+        let mut report = RotationReport {
+            from_service: old_service.clone(),
+            to_service: new_service.to_string(),
+            dry_run,
+            ..RotationReport::default()
+        };
+
+        for id in ids {
+            let account = Self::account(id);
+            let secret = match backend.get_password(&old_service, &account) {
+                Ok(secret) => secret,
+                Err(keyring::Error::NoEntry) => {
+                    report.missing.push(id.clone());
+                    continue;
+                }
+                Err(e) => {
+                    report.failed.push(RotationFailure::new(id, e));
+                    continue;
+                }
+            };
+            if dry_run {
+                report.migrated.push(id.clone());
+                continue;
+            }
+            let copied = backend
+                .set_password(new_service, &account, &secret)
+                .and_then(|()| backend.get_password(new_service, &account));
+            match copied {
+                Ok(read_back) if read_back == secret => report.migrated.push(id.clone()),
+                Ok(_) => report.failed.push(RotationFailure {
+                    id: id.clone(),
+                    error: "read-back did not match the stored value".to_string(),
+                }),
+                Err(e) => report.failed.push(RotationFailure::new(id, e)),
+            }
+        }
+
+        tracing::info!(
+            "Keychain rotation {} -> {}: {} migrated, {} missing, {} failed{}",
+            report.from_service,
+            report.to_service,
+            report.migrated.len(),
+            report.missing.len(),
+            report.failed.len(),
+            if dry_run { " (dry run)" } else { "" }
+        );
+        Rotation {
+            keychain: self.clone(),
+            report,
+        }
+    }
+}
+
+/// An ID that could not be moved to the new service
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RotationFailure {
+    pub id: String,
+    pub error: String,
+}
+
+impl RotationFailure {
+    fn new(id: &str, error: keyring::Error) -> Self {
+        Self {
+            id: id.to_string(),
+            error: KeychainError::from(error).to_string(),
+        }
+    }
+}
+
+/// What [`Keychain::rotate`] did (or, in a dry run, would do) for one service
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RotationReport {
+    pub from_service: String,
+    pub to_service: String,
+    pub dry_run: bool,
+    /// IDs copied to the new service
+    pub migrated: Vec<String>,
+    /// Known IDs with no secret stored
+    pub missing: Vec<String>,
+    pub failed: Vec<RotationFailure>,
+    /// Whether the keychain now uses `to_service`
+    pub committed: bool,
+}
+
+/// Secrets copied by [`Keychain::rotate`], with the old entries still in place
+#[must_use = "a rotation must be committed or aborted"]
+pub struct Rotation {
+    keychain: Keychain,
+    report: RotationReport,
+}
+
+impl Rotation {
+    pub fn report(&self) -> &RotationReport {
+        &self.report
+    }
+
+    /// Whether every known secret was copied
+    pub fn is_complete(&self) -> bool {
+        self.report.failed.is_empty()
+    }
+
+    /// Switch the keychain (and its clones) to the new service and delete
+    /// the old entries. Call only once the new service name is persisted.
+    pub fn commit(mut self) -> RotationReport {
+        if self.report.dry_run {
+            return self.report;
+        }
+        let backend = self.keychain.backend();
+        *self.keychain.service.write() = self.report.to_service.clone();
+        self.report.committed = true;
+
+        for id in &self.report.migrated {
+            match backend.delete_password(&self.report.from_service, &Keychain::account(id)) {
+                Ok(()) | Err(keyring::Error::NoEntry) => {}
+                Err(e) => self.report.failed.push(RotationFailure {
+                    id: id.clone(),
+                    error: format!(
+                        "moved, but the old entry could not be deleted: {}",
+                        KeychainError::from(e)
+                    ),
+                }),
+            }
+        }
+        self.report
+    }
+
+    /// Delete the copies and stay on the old service
+    pub fn abort(mut self) -> RotationReport {
+        if self.report.dry_run {
+            return self.report;
+        }
+        let backend = self.keychain.backend();
+        for id in std::mem::take(&mut self.report.migrated) {
+            if let Err(e) =
+                backend.delete_password(&self.report.to_service, &Keychain::account(&id))
+            {
+                tracing::warn!(
+                    "Keychain rotation rollback: could not delete copy of {}: {:?}",
+                    id,
+                    e
+                );
+            }
+        }
+        self.report
+    }
+}
+
+impl CredentialStore for Keychain {
     /// Store a secret in the keychain
-    /// Returns the keychain ID used
-    pub fn store(&self, id: &str, secret: &str) -> Result<(), KeychainError> {
-        tracing::info!("Keychain store: service={}, id={}", self.service, id);
-        // Use explicit username to ensure stable keychain identity on macOS
-        let username = whoami::username();
-        let entry = Entry::new(&self.service, &format!("{}@{}", username, id))?;
-        match entry.set_password(secret) {
+    fn store(&self, id: &str, secret: &str) -> Result<(), KeychainError> {
+        let service = self.service();
+        tracing::info!("Keychain store: service={}, id={}", service, id);
+        let backend = self.backend();
+        let account = Self::account(id);
+        match backend.set_password(&service, &account, secret) {
             Ok(()) => {
                 tracing::info!("Keychain store called successfully, verifying...");
                 // Verify the store actually worked by reading it back
-                match entry.get_password() {
+                match backend.get_password(&service, &account) {
                     Ok(read_back) => {
                         if read_back == secret {
                             tracing::info!("Keychain store verified: id={}", id);
@@ -67,31 +396,23 @@ impl Keychain {
                     }
                     Err(e) => {
                         tracing::error!("Keychain store verification failed: {:?}", e);
-                        Err(KeychainError::Keyring(e))
+                        Err(e.into())
                     }
                 }
             }
             Err(e) => {
                 tracing::error!("Keychain store failed: id={}, error={:?}", id, e);
-                Err(KeychainError::Keyring(e))
+                Err(e.into())
             }
         }
     }
 
-    /// Store a new secret and return its generated ID
-    pub fn store_new(&self, secret: &str) -> Result<String, KeychainError> {
-        let id = Self::generate_id();
-        self.store(&id, secret)?;
-        Ok(id)
-    }
-
     /// Retrieve a secret from the keychain
-    pub fn get(&self, id: &str) -> Result<String, KeychainError> {
-        tracing::info!("Keychain get: service={}, id={}", self.service, id);
+    fn get(&self, id: &str) -> Result<String, KeychainError> {
+        let service = self.service();
+        tracing::info!("Keychain get: service={}, id={}", service, id);
         // Use same username-prefixed account as store()
-        let username = whoami::username();
-        let entry = Entry::new(&self.service, &format!("{}@{}", username, id))?;
-        match entry.get_password() {
+        match self.backend().get_password(&service, &Self::account(id)) {
             Ok(secret) => {
                 tracing::info!("Keychain get success: id={}, len={}", id, secret.len());
                 Ok(secret)
@@ -102,40 +423,36 @@ impl Keychain {
             }
             Err(e) => {
                 tracing::error!("Keychain get failed: id={}, error={:?}", id, e);
-                Err(KeychainError::Keyring(e))
+                Err(e.into())
             }
         }
     }
 
     /// Delete a secret from the keychain
-    pub fn delete(&self, id: &str) -> Result<(), KeychainError> {
+    fn delete(&self, id: &str) -> Result<(), KeychainError> {
         // Use same username-prefixed account
-        let username = whoami::username();
-        let entry = Entry::new(&self.service, &format!("{}@{}", username, id))?;
-        match entry.delete_credential() {
+        match self
+            .backend()
+            .delete_password(&self.service(), &Self::account(id))
+        {
             Ok(()) => Ok(()),
             Err(keyring::Error::NoEntry) => Ok(()), // Already deleted
-            Err(e) => Err(KeychainError::Keyring(e)),
+            Err(e) => Err(e.into()),
         }
     }
 
     /// Check if a secret exists
-    pub fn exists(&self, id: &str) -> Result<bool, KeychainError> {
+    fn exists(&self, id: &str) -> Result<bool, KeychainError> {
         // Use same username-prefixed account as store()/get()/delete()
-        let username = whoami::username();
-        let entry = Entry::new(&self.service, &format!("{}@{}", username, id))?;
-        match entry.get_password() {
+        match self
+            .backend()
+            .get_password(&self.service(), &Self::account(id))
+        {
             Ok(_) => Ok(true),
             Err(keyring::Error::NoEntry) => Ok(false),
-            Err(e) => Err(KeychainError::Keyring(e)),
+            Err(e) => Err(e.into()),
         }
     }
-
-    /// Update an existing secret
-    pub fn update(&self, id: &str, new_secret: &str) -> Result<(), KeychainError> {
-        // keyring will overwrite existing entry
-        self.store(id, new_secret)
-    }
 }
 
 impl Default for Keychain {
@@ -144,6 +461,100 @@ impl Default for Keychain {
     }
 }
 
+/// In-memory credential store for tests
+///
+/// `set_unavailable(true)` makes every call fail like an unreachable OS
+/// keychain, for exercising error and rollback paths.
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemoryCredentialStore {
+    secrets: Mutex<HashMap<String, String>>,
+    unavailable: AtomicBool,
+}
+
+#[cfg(test)]
+impl MemoryCredentialStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Simulate the backing store going away (or coming back)
+    pub fn set_unavailable(&self, unavailable: bool) {
+        self.unavailable.store(unavailable, Ordering::SeqCst);
+    }
+
+    /// Number of stored secrets
+    pub fn len(&self) -> usize {
+        self.secrets.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// IDs of all stored secrets, sorted
+    pub fn ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.secrets.lock().keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    fn check(&self) -> Result<(), KeychainError> {
+        if self.unavailable.load(Ordering::SeqCst) {
+            return Err(KeychainError::Unavailable(keyring::Error::NoStorageAccess(
+                "in-memory store marked unavailable".into(),
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+impl CredentialStore for MemoryCredentialStore {
+    fn store(&self, id: &str, secret: &str) -> Result<(), KeychainError> {
+        self.check()?;
+        self.secrets
+            .lock()
+            .insert(id.to_string(), secret.to_string());
+        Ok(())
+    }
+
+    fn get(&self, id: &str) -> Result<String, KeychainError> {
+        self.check()?;
+        self.secrets
+            .lock()
+            .get(id)
+            .cloned()
+            .ok_or_else(|| KeychainError::NotFound(id.to_string()))
+    }
+
+    fn delete(&self, id: &str) -> Result<(), KeychainError> {
+        self.check()?;
+        self.secrets.lock().remove(id);
+        Ok(())
+    }
+
+    fn exists(&self, id: &str) -> Result<bool, KeychainError> {
+        self.check()?;
+        Ok(self.secrets.lock().contains_key(id))
+    }
+}
+
+/// Config state over `config` whose SSH secrets live in `keychain`, with an
+/// empty AI keychain and a config path nothing is saved to
+#[cfg(test)]
+pub fn memory_config_state(
+    config: super::ConfigFile,
+    keychain: Arc<MemoryCredentialStore>,
+) -> crate::commands::config::ConfigState {
+    crate::commands::config::ConfigState::with_credential_stores(
+        super::ConfigStorage::with_path(std::env::temp_dir().join("oxideterm-test-unused.json")),
+        config,
+        keychain,
+        Arc::new(MemoryCredentialStore::new()),
+    )
+}
+
 /// Helper function to create a keychain entry label
 pub fn make_label(host: &str, username: &str) -> String {
     format!("OxideTerm: {}@{}", username, host)
@@ -153,6 +564,161 @@ pub fn make_label(host: &str, username: &str) -> String {
 mod tests {
     use super::*;
 
+    /// Keychain that cannot be reached (e.g. no secret-service running)
+    struct UnavailableBackend;
+
+    impl SecretBackend for UnavailableBackend {
+        fn set_password(&self, _: &str, _: &str, _: &str) -> keyring::Result<()> {
+            Err(no_storage())
+        }
+        fn get_password(&self, _: &str, _: &str) -> keyring::Result<String> {
+            Err(no_storage())
+        }
+        fn delete_password(&self, _: &str, _: &str) -> keyring::Result<()> {
+            Err(no_storage())
+        }
+        fn is_system(&self) -> bool {
+            true
+        }
+    }
+
+    fn no_storage() -> keyring::Error {
+        keyring::Error::NoStorageAccess("org.freedesktop.secrets not provided".into())
+    }
+
+    #[derive(Default)]
+    struct MemoryBackend {
+        entries: Mutex<HashMap<(String, String), String>>,
+    }
+
+    impl SecretBackend for MemoryBackend {
+        fn set_password(&self, service: &str, account: &str, secret: &str) -> keyring::Result<()> {
+            self.entries
+                .lock()
+                .insert((service.into(), account.into()), secret.into());
+            Ok(())
+        }
+        fn get_password(&self, service: &str, account: &str) -> keyring::Result<String> {
+            self.entries
+                .lock()
+                .get(&(service.into(), account.into()))
+                .cloned()
+                .ok_or(keyring::Error::NoEntry)
+        }
+        fn delete_password(&self, service: &str, account: &str) -> keyring::Result<()> {
+            self.entries
+                .lock()
+                .remove(&(service.into(), account.into()))
+                .map(|_| ())
+                .ok_or(keyring::Error::NoEntry)
+        }
+    }
+
+    #[test]
+    fn test_unavailable_backend_is_detected() {
+        let keychain = Keychain::with_backend("com.oxideterm.test", Arc::new(UnavailableBackend));
+
+        assert!(!keychain.is_available());
+        assert!(matches!(
+            keychain.store("id", "secret"),
+            Err(KeychainError::Unavailable(_))
+        ));
+    }
+
+    #[test]
+    fn test_empty_backend_is_available() {
+        let keychain =
+            Keychain::with_backend("com.oxideterm.test", Arc::new(MemoryBackend::default()));
+
+        // The probe entry does not exist, which still proves the store answers
+        assert!(keychain.is_available());
+        assert!(matches!(
+            keychain.get("missing"),
+            Err(KeychainError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_switching_backend_applies_to_shared_keychains() {
+        let ssh = Keychain::with_backend("com.oxideterm.test", Arc::new(UnavailableBackend));
+        let ai = ssh.for_service("com.oxideterm.test.ai");
+        assert!(!ai.is_available());
+        assert!(ssh.is_system());
+
+        ssh.set_backend(Arc::new(MemoryBackend::default()));
+        assert!(ssh.is_available() && ai.is_available());
+        assert!(!ai.is_system());
+
+        ssh.store("id", "ssh-secret").unwrap();
+        ai.store("id", "ai-secret").unwrap();
+        assert_eq!(ssh.get("id").unwrap(), "ssh-secret");
+        assert_eq!(ai.get("id").unwrap(), "ai-secret");
+
+        ai.delete("id").unwrap();
+        assert!(!ai.exists("id").unwrap());
+        assert!(ssh.exists("id").unwrap());
+    }
+
+    #[test]
+    fn test_rotate_moves_known_secrets() {
+        let backend = Arc::new(MemoryBackend::default());
+        let keychain = Keychain::with_backend("com.oxideterm.test", backend.clone());
+        let shared = keychain.clone();
+        keychain.store("a", "secret-a").unwrap();
+        keychain.store("b", "secret-b").unwrap();
+        let ids = vec!["a".to_string(), "b".to_string(), "gone".to_string()];
+
+        // Dry run only reads
+        let dry = keychain.rotate("com.oxideterm.test.2", &ids, true).commit();
+        assert_eq!(dry.migrated, vec!["a", "b"]);
+        assert_eq!(dry.missing, vec!["gone"]);
+        assert!(!dry.committed);
+        assert_eq!(backend.entries.lock().len(), 2);
+
+        let rotation = keychain.rotate("com.oxideterm.test.2", &ids, false);
+        assert!(rotation.is_complete());
+        // Nothing switches or disappears before the commit
+        assert_eq!(keychain.service(), "com.oxideterm.test");
+        assert_eq!(backend.entries.lock().len(), 4);
+
+        let report = rotation.commit();
+        assert!(report.committed && report.failed.is_empty());
+        assert_eq!(shared.service(), "com.oxideterm.test.2");
+        assert_eq!(shared.get("a").unwrap(), "secret-a");
+        assert_eq!(shared.get("b").unwrap(), "secret-b");
+        assert_eq!(backend.entries.lock().len(), 2);
+    }
+
+    #[test]
+    fn test_rotate_abort_keeps_old_service() {
+        let backend = Arc::new(MemoryBackend::default());
+        let keychain = Keychain::with_backend("com.oxideterm.test", backend.clone());
+        keychain.store("a", "secret-a").unwrap();
+
+        let report = keychain
+            .rotate("com.oxideterm.test.2", &["a".to_string()], false)
+            .abort();
+        assert!(report.migrated.is_empty() && !report.committed);
+        assert_eq!(keychain.service(), "com.oxideterm.test");
+        assert_eq!(keychain.get("a").unwrap(), "secret-a");
+        assert_eq!(backend.entries.lock().len(), 1);
+    }
+
+    #[test]
+    fn test_rotate_reports_unreachable_store() {
+        let keychain = Keychain::with_backend("com.oxideterm.test", Arc::new(UnavailableBackend));
+        let rotation = keychain.rotate("com.oxideterm.test.2", &["a".to_string()], false);
+        assert!(!rotation.is_complete());
+        assert_eq!(rotation.report().failed[0].id, "a");
+    }
+
+    #[test]
+    fn test_rotated_service_keeps_base() {
+        let service = Keychain::rotated_service(SSH_KEYCHAIN_SERVICE);
+        assert!(service.starts_with("com.oxideterm.ssh."));
+        assert_ne!(service, SSH_KEYCHAIN_SERVICE);
+    }
+
     // Note: These tests interact with the real system keychain
     // They use a unique service name to avoid conflicts
 
@@ -182,6 +748,31 @@ mod tests {
         assert!(!keychain.exists(&id).unwrap());
     }
 
+    #[test]
+    fn test_memory_store_matches_keychain_semantics() {
+        let store = MemoryCredentialStore::new();
+
+        let id = store.store_new("secret").unwrap();
+        assert!(id.starts_with("oxideterm-"));
+        assert_eq!(store.get(&id).unwrap(), "secret");
+
+        store.update(&id, "rotated").unwrap();
+        assert_eq!(store.get(&id).unwrap(), "rotated");
+        assert_eq!(store.ids(), vec![id.clone()]);
+
+        store.delete(&id).unwrap();
+        store.delete(&id).unwrap(); // Already deleted
+        assert!(!store.exists(&id).unwrap());
+        assert!(matches!(store.get(&id), Err(KeychainError::NotFound(_))));
+
+        store.set_unavailable(true);
+        assert!(matches!(
+            store.store("id", "secret"),
+            Err(KeychainError::Unavailable(_))
+        ));
+        assert!(store.is_empty());
+    }
+
     #[test]
     fn test_generate_id() {
         let id1 = Keychain::generate_id();
@@ -7,17 +7,38 @@
 //! - SSH passwords & passphrases: `com.oxideterm.ssh` keychain service
 //! - AI provider API keys: `com.oxideterm.ai` keychain service (since v1.6.0)
 //! - Legacy XOR vault files (`ai_keys/*.vault`) are auto-migrated on first access
+//! - Both services can be re-keyed under new names (`rotate_keychain`); the
+//!   names in use are recorded in the config file
+//! - Without a usable keychain (headless Linux), both services can be moved to
+//!   the opt-in encrypted `secrets.vault` file
 
+pub mod consolidate;
 pub mod keychain;
+pub mod secret_file;
+pub mod snippet;
 pub mod ssh_config;
 pub mod storage;
 pub mod types;
+pub mod validate;
 pub mod vault;
 
-pub use keychain::{Keychain, KeychainError};
-pub use ssh_config::{default_ssh_config_path, parse_ssh_config, SshConfigError, SshConfigHost};
+pub use consolidate::{consolidate_credentials, ConsolidationReport};
+#[cfg(test)]
+pub use keychain::{memory_config_state, MemoryCredentialStore};
+pub use keychain::{
+    CredentialStore, Keychain, KeychainError, Rotation, RotationFailure, RotationReport,
+    SecretBackend, SystemKeyring, AI_KEYCHAIN_SERVICE, SSH_KEYCHAIN_SERVICE,
+};
+pub use secret_file::{FileSecretBackend, SecretFileError};
+pub use snippet::{ConnectionSnippet, SnippetCredential, SnippetError};
+pub use ssh_config::{
+    default_ssh_config_path, filter_importable_hosts, parse_ssh_config, parse_ssh_config_blocks,
+    parse_ssh_config_file, resolve_host, to_ssh_config, ResolvedProxyChain, SshConfigError,
+    SshConfigHost,
+};
 pub use storage::{config_dir, connections_file, ConfigStorage, StorageError};
 pub use types::{
     ConfigFile, ConnectionOptions, ProxyHopConfig, SavedAuth, SavedConnection, CONFIG_VERSION,
 };
+pub use validate::{validate_connection, CheckKind, CheckStatus, ConnectionCheck, ProbeRoute};
 pub use vault::{AiProviderVault, AiVault, VaultError};
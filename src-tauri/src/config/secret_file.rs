@@ -0,0 +1,272 @@
+//! File-based secret storage (keychain fallback)
+//!
+//! Used only when the system keychain is unavailable (headless Linux without
+//! a secret-service, containers, CI) and the user has explicitly agreed to
+//! store passwords on disk.
+//!
+//! Layout in the config directory:
+//! - `secrets.key`: 32 random bytes, mode 0600
+//! - `secrets.vault`: `OXSECRT1` + nonce + ChaCha20-Poly1305 ciphertext of a
+//!   JSON map `"{service}\n{account}" -> secret`, mode 0600
+//!
+//! The key sits next to the vault, so this protects against the vault alone
+//! leaking (backups, synced config folders) but not against someone who can
+//! read the whole config directory. That trade-off is why it is opt-in.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chacha20poly1305::{aead::Aead, ChaCha20Poly1305, KeyInit, Nonce};
+use parking_lot::Mutex;
+use rand::RngCore;
+use zeroize::Zeroizing;
+
+use super::keychain::SecretBackend;
+
+/// Encrypted secrets file name
+pub const SECRETS_FILE: &str = "secrets.vault";
+
+/// Key file name
+const KEY_FILE: &str = "secrets.key";
+
+/// Magic header to identify secrets files
+const SECRETS_MAGIC: &[u8; 8] = b"OXSECRT1";
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+/// Secret file errors
+#[derive(Debug, thiserror::Error)]
+pub enum SecretFileError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid secrets file format")]
+    InvalidFormat,
+
+    #[error("Failed to decrypt secrets file")]
+    DecryptionFailed,
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Encrypted on-disk secret store
+pub struct FileSecretBackend {
+    path: PathBuf,
+    key: Zeroizing<[u8; KEY_LEN]>,
+    entries: Mutex<HashMap<String, String>>,
+}
+
+impl FileSecretBackend {
+    /// Whether the fallback was enabled before (the user already consented)
+    pub fn exists(dir: &Path) -> bool {
+        dir.join(SECRETS_FILE).is_file()
+    }
+
+    /// Open the store in `dir`, creating the key and an empty vault if needed
+    pub fn open(dir: &Path) -> Result<Self, SecretFileError> {
+        fs::create_dir_all(dir)?;
+        let key = load_or_create_key(&dir.join(KEY_FILE))?;
+        let path = dir.join(SECRETS_FILE);
+
+        let entries = match fs::read(&path) {
+            Ok(data) => decrypt(&key, &data)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let backend = Self {
+            path,
+            key,
+            entries: Mutex::new(entries),
+        };
+        if !backend.path.exists() {
+            backend.persist(&backend.entries.lock())?;
+        }
+        Ok(backend)
+    }
+
+    /// Encrypt and atomically replace the vault file
+    fn persist(&self, entries: &HashMap<String, String>) -> Result<(), SecretFileError> {
+        let plaintext = Zeroizing::new(serde_json::to_vec(entries)?);
+
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::rngs::OsRng.fill_bytes(&mut nonce);
+        let cipher = ChaCha20Poly1305::new_from_slice(&*self.key)
+            .map_err(|_| SecretFileError::InvalidFormat)?;
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_ref())
+            .map_err(|_| SecretFileError::InvalidFormat)?;
+
+        let mut data = Vec::with_capacity(SECRETS_MAGIC.len() + NONCE_LEN + ciphertext.len());
+        data.extend_from_slice(SECRETS_MAGIC);
+        data.extend_from_slice(&nonce);
+        data.extend_from_slice(&ciphertext);
+
+        let temp_path = self.path.with_extension("vault.tmp");
+        write_private(&temp_path, &data)?;
+        fs::rename(&temp_path, &self.path)?;
+        Ok(())
+    }
+
+    fn update(
+        &self,
+        f: impl FnOnce(&mut HashMap<String, String>) -> keyring::Result<()>,
+    ) -> keyring::Result<()> {
+        let mut entries = self.entries.lock();
+        let mut next = entries.clone();
+        f(&mut next)?;
+        self.persist(&next)
+            .map_err(|e| keyring::Error::PlatformFailure(Box::new(e)))?;
+        *entries = next;
+        Ok(())
+    }
+}
+
+impl SecretBackend for FileSecretBackend {
+    fn set_password(&self, service: &str, account: &str, secret: &str) -> keyring::Result<()> {
+        self.update(|entries| {
+            entries.insert(entry_key(service, account), secret.to_string());
+            Ok(())
+        })
+    }
+
+    fn get_password(&self, service: &str, account: &str) -> keyring::Result<String> {
+        self.entries
+            .lock()
+            .get(&entry_key(service, account))
+            .cloned()
+            .ok_or(keyring::Error::NoEntry)
+    }
+
+    fn delete_password(&self, service: &str, account: &str) -> keyring::Result<()> {
+        self.update(|entries| {
+            entries
+                .remove(&entry_key(service, account))
+                .map(|_| ())
+                .ok_or(keyring::Error::NoEntry)
+        })
+    }
+}
+
+fn entry_key(service: &str, account: &str) -> String {
+    format!("{}\n{}", service, account)
+}
+
+fn load_or_create_key(path: &Path) -> Result<Zeroizing<[u8; KEY_LEN]>, SecretFileError> {
+    let mut key = Zeroizing::new([0u8; KEY_LEN]);
+    match fs::read(path) {
+        Ok(data) => {
+            let data = Zeroizing::new(data);
+            if data.len() != KEY_LEN {
+                return Err(SecretFileError::InvalidFormat);
+            }
+            key.copy_from_slice(&data);
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            rand::rngs::OsRng.fill_bytes(&mut *key);
+            write_private(path, &*key)?;
+        }
+        Err(e) => return Err(e.into()),
+    }
+    Ok(key)
+}
+
+fn decrypt(key: &[u8; KEY_LEN], data: &[u8]) -> Result<HashMap<String, String>, SecretFileError> {
+    let rest = data
+        .strip_prefix(SECRETS_MAGIC.as_slice())
+        .filter(|rest| rest.len() >= NONCE_LEN)
+        .ok_or(SecretFileError::InvalidFormat)?;
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+    let cipher =
+        ChaCha20Poly1305::new_from_slice(key).map_err(|_| SecretFileError::InvalidFormat)?;
+    let plaintext = Zeroizing::new(
+        cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| SecretFileError::DecryptionFailed)?,
+    );
+    Ok(serde_json::from_slice(&plaintext)?)
+}
+
+/// Write a file readable only by the current user
+fn write_private(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(data)?;
+    file.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(tag: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("oxideterm-secrets-{}-{}", tag, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_round_trip_survives_reopen() {
+        let dir = temp_dir("reopen");
+        assert!(!FileSecretBackend::exists(&dir));
+
+        let backend = FileSecretBackend::open(&dir).unwrap();
+        assert!(FileSecretBackend::exists(&dir));
+        backend.set_password("svc", "alice@id1", "hunter2").unwrap();
+        backend
+            .set_password("svc", "alice@id2", "swordfish")
+            .unwrap();
+        backend.delete_password("svc", "alice@id2").unwrap();
+        drop(backend);
+
+        let reopened = FileSecretBackend::open(&dir).unwrap();
+        assert_eq!(
+            reopened.get_password("svc", "alice@id1").unwrap(),
+            "hunter2"
+        );
+        assert!(matches!(
+            reopened.get_password("svc", "alice@id2"),
+            Err(keyring::Error::NoEntry)
+        ));
+        assert!(matches!(
+            reopened.get_password("other", "alice@id1"),
+            Err(keyring::Error::NoEntry)
+        ));
+
+        // Secrets are not stored in clear text
+        let raw = fs::read(dir.join(SECRETS_FILE)).unwrap();
+        assert!(raw.starts_with(SECRETS_MAGIC));
+        assert!(!raw.windows(7).any(|w| w == b"hunter2"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_wrong_key_fails_to_open() {
+        let dir = temp_dir("wrongkey");
+        FileSecretBackend::open(&dir)
+            .unwrap()
+            .set_password("svc", "acct", "secret")
+            .unwrap();
+
+        fs::write(dir.join(KEY_FILE), [7u8; KEY_LEN]).unwrap();
+        assert!(matches!(
+            FileSecretBackend::open(&dir),
+            Err(SecretFileError::DecryptionFailed)
+        ));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,457 @@
+//! Connection snippets
+//!
+//! A snippet is a compact, unencrypted JSON form of one saved connection,
+//! meant for pasting into a ticket or chat. It carries the host, port, user
+//! and proxy chain, but no secret references: no keychain IDs, no key or
+//! certificate paths, and auth reduced to its type. Importing a snippet
+//! therefore needs fresh credentials for every hop that isn't agent auth.
+//!
+//! ProxyCommand is left out on purpose (as in `.oxide` exports): importing it
+//! would run a command taken from pasted text.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::types::{ConnectionOptions, ProxyHopConfig, SavedAuth, SavedConnection, CONFIG_VERSION};
+
+/// Current snippet format version
+pub const SNIPPET_VERSION: u32 = 1;
+
+/// Snippet errors
+#[derive(Debug, thiserror::Error)]
+pub enum SnippetError {
+    #[error("Not a connection snippet: {0}")]
+    Invalid(String),
+
+    #[error("Unsupported snippet version {0} (expected {expected})", expected = SNIPPET_VERSION)]
+    UnsupportedVersion(u32),
+
+    #[error("{target} needs a {what} for {auth} authentication")]
+    MissingCredential {
+        target: String,
+        auth: SnippetAuth,
+        what: &'static str,
+    },
+}
+
+/// Authentication type, without any of its data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnippetAuth {
+    Password,
+    Key,
+    Agent,
+    Certificate,
+}
+
+impl std::fmt::Display for SnippetAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SnippetAuth::Password => "password",
+            SnippetAuth::Key => "key",
+            SnippetAuth::Agent => "agent",
+            SnippetAuth::Certificate => "certificate",
+        })
+    }
+}
+
+impl From<&SavedAuth> for SnippetAuth {
+    fn from(auth: &SavedAuth) -> Self {
+        match auth {
+            SavedAuth::Password { .. } => SnippetAuth::Password,
+            SavedAuth::Key { .. } => SnippetAuth::Key,
+            SavedAuth::Agent => SnippetAuth::Agent,
+            SavedAuth::Certificate { .. } => SnippetAuth::Certificate,
+        }
+    }
+}
+
+/// One jump host in a snippet's proxy chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnippetHop {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub auth: SnippetAuth,
+}
+
+/// A single connection, safe to share
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionSnippet {
+    /// Format marker and version
+    pub oxide_snippet: u32,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub auth: SnippetAuth,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub proxy_chain: Vec<SnippetHop>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub keep_alive_interval: u32,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub compression: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub term_type: Option<String>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub idle_timeout_secs: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+fn is_zero<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+/// Fresh credentials for one hop of an imported snippet
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SnippetCredential {
+    pub password: Option<String>,
+    pub key_path: Option<String>,
+    pub cert_path: Option<String>,
+    pub passphrase: Option<String>,
+}
+
+/// A connection rebuilt from a snippet, with the secrets it needs stored
+#[derive(Debug)]
+pub struct ImportedSnippet {
+    pub connection: SavedConnection,
+    /// `(keychain_id, secret)` pairs the connection's auth refers to
+    pub keychain_entries: Vec<(String, String)>,
+}
+
+impl From<&SavedConnection> for ConnectionSnippet {
+    fn from(conn: &SavedConnection) -> Self {
+        Self {
+            oxide_snippet: SNIPPET_VERSION,
+            name: conn.name.clone(),
+            group: conn.group.clone(),
+            host: conn.host.clone(),
+            port: conn.port,
+            username: conn.username.clone(),
+            auth: SnippetAuth::from(&conn.auth),
+            proxy_chain: conn
+                .proxy_chain
+                .iter()
+                .map(|hop| SnippetHop {
+                    host: hop.host.clone(),
+                    port: hop.port,
+                    username: hop.username.clone(),
+                    auth: SnippetAuth::from(&hop.auth),
+                })
+                .collect(),
+            keep_alive_interval: conn.options.keep_alive_interval,
+            compression: conn.options.compression,
+            term_type: conn.options.term_type.clone(),
+            idle_timeout_secs: conn.options.idle_timeout_secs,
+            color: conn.color.clone(),
+            tags: conn.tags.clone(),
+        }
+    }
+}
+
+impl ConnectionSnippet {
+    /// Serialize to compact JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("snippet serialization cannot fail")
+    }
+
+    /// Parse a pasted snippet, tolerating surrounding whitespace
+    pub fn parse(text: &str) -> Result<Self, SnippetError> {
+        let snippet: Self =
+            serde_json::from_str(text.trim()).map_err(|e| SnippetError::Invalid(e.to_string()))?;
+        if snippet.oxide_snippet != SNIPPET_VERSION {
+            return Err(SnippetError::UnsupportedVersion(snippet.oxide_snippet));
+        }
+        if snippet.host.trim().is_empty() || snippet.username.trim().is_empty() {
+            return Err(SnippetError::Invalid(
+                "host and username are required".into(),
+            ));
+        }
+        Ok(snippet)
+    }
+
+    /// Rebuild a saved connection under a new ID.
+    ///
+    /// `credentials` is for the target, `hop_credentials` for each proxy hop
+    /// in order; agent hops need none. Passwords and passphrases come back as
+    /// keychain entries for the caller to store.
+    pub fn into_connection(
+        self,
+        credentials: SnippetCredential,
+        mut hop_credentials: Vec<SnippetCredential>,
+    ) -> Result<ImportedSnippet, SnippetError> {
+        let id = Uuid::new_v4().to_string();
+        let mut keychain_entries = Vec::new();
+
+        hop_credentials.resize_with(self.proxy_chain.len(), Default::default);
+        let mut proxy_chain = Vec::with_capacity(self.proxy_chain.len());
+        for (index, (hop, credential)) in self
+            .proxy_chain
+            .into_iter()
+            .zip(hop_credentials)
+            .enumerate()
+        {
+            let target = format!("Jump host {} ({})", index + 1, hop.host);
+            let auth = build_auth(
+                hop.auth,
+                credential,
+                &format!("{}_hop{}", id, index),
+                &target,
+                &mut keychain_entries,
+            )?;
+            proxy_chain.push(ProxyHopConfig {
+                host: hop.host,
+                port: hop.port,
+                username: hop.username,
+                auth,
+            });
+        }
+
+        let target = format!("{}@{}", self.username, self.host);
+        let auth = build_auth(self.auth, credentials, &id, &target, &mut keychain_entries)?;
+
+        let connection = SavedConnection {
+            id,
+            version: CONFIG_VERSION,
+            name: self.name,
+            group: self.group,
+            host: self.host,
+            port: self.port,
+            username: self.username,
+            auth,
+            options: ConnectionOptions {
+                keep_alive_interval: self.keep_alive_interval,
+                compression: self.compression,
+                term_type: self.term_type,
+                idle_timeout_secs: self.idle_timeout_secs,
+                ..Default::default()
+            },
+            created_at: Utc::now(),
+            last_used_at: None,
+            color: self.color,
+            tags: self.tags,
+            proxy_chain,
+        };
+
+        Ok(ImportedSnippet {
+            connection,
+            keychain_entries,
+        })
+    }
+}
+
+fn build_auth(
+    kind: SnippetAuth,
+    credential: SnippetCredential,
+    id: &str,
+    target: &str,
+    keychain_entries: &mut Vec<(String, String)>,
+) -> Result<SavedAuth, SnippetError> {
+    let missing = |what| SnippetError::MissingCredential {
+        target: target.to_string(),
+        auth: kind,
+        what,
+    };
+    let passphrase_keychain_id =
+        |passphrase: Option<String>, keychain_entries: &mut Vec<(String, String)>| {
+            passphrase.filter(|p| !p.is_empty()).map(|p| {
+                let kc_id = format!("oxide_key_{}", id);
+                keychain_entries.push((kc_id.clone(), p));
+                kc_id
+            })
+        };
+
+    Ok(match kind {
+        SnippetAuth::Password => {
+            let password = credential
+                .password
+                .filter(|p| !p.is_empty())
+                .ok_or_else(|| missing("password"))?;
+            let keychain_id = format!("oxide_conn_{}", id);
+            keychain_entries.push((keychain_id.clone(), password));
+            SavedAuth::Password { keychain_id }
+        }
+        SnippetAuth::Key => {
+            let key_path = credential.key_path.ok_or_else(|| missing("key path"))?;
+            let passphrase_keychain_id =
+                passphrase_keychain_id(credential.passphrase, keychain_entries);
+            SavedAuth::Key {
+                key_path,
+                has_passphrase: passphrase_keychain_id.is_some(),
+                passphrase_keychain_id,
+            }
+        }
+        SnippetAuth::Certificate => {
+            let key_path = credential.key_path.ok_or_else(|| missing("key path"))?;
+            let cert_path = credential
+                .cert_path
+                .ok_or_else(|| missing("certificate path"))?;
+            let passphrase_keychain_id =
+                passphrase_keychain_id(credential.passphrase, keychain_entries);
+            SavedAuth::Certificate {
+                key_path,
+                cert_path,
+                has_passphrase: passphrase_keychain_id.is_some(),
+                passphrase_keychain_id,
+            }
+        }
+        SnippetAuth::Agent => SavedAuth::Agent,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secret_connection() -> SavedConnection {
+        let mut conn = SavedConnection::new_key(
+            "Build box",
+            "build.example.com",
+            2222,
+            "ci",
+            "/home/alice/.ssh/id_build_secret",
+        );
+        conn.auth = SavedAuth::Certificate {
+            key_path: "/home/alice/.ssh/id_build_secret".into(),
+            cert_path: "/home/alice/.ssh/id_build_secret-cert.pub".into(),
+            has_passphrase: true,
+            passphrase_keychain_id: Some("oxide_key_cafebabe".into()),
+        };
+        conn.group = Some("CI".into());
+        conn.tags = vec!["prod".into()];
+        conn.options.keep_alive_interval = 30;
+        conn.options.proxy_command = Some("nc -X connect -x proxy:8080 %h %p".into());
+        conn.proxy_chain = vec![
+            ProxyHopConfig {
+                host: "bastion.example.com".into(),
+                port: 22,
+                username: "jump".into(),
+                auth: SavedAuth::Password {
+                    keychain_id: "oxide_hop_deadbeef".into(),
+                },
+            },
+            ProxyHopConfig {
+                host: "inner.example.com".into(),
+                port: 22,
+                username: "jump".into(),
+                auth: SavedAuth::Agent,
+            },
+        ];
+        conn
+    }
+
+    #[test]
+    fn test_snippet_round_trip_without_secrets() {
+        let conn = secret_connection();
+        let json = ConnectionSnippet::from(&conn).to_json();
+
+        for secret in [
+            "oxide_key_cafebabe",
+            "oxide_hop_deadbeef",
+            "id_build_secret",
+            "keychain",
+            "key_path",
+            "proxy_command",
+            &conn.id,
+        ] {
+            assert!(!json.contains(secret), "snippet leaks {secret:?}: {json}");
+        }
+
+        let snippet = ConnectionSnippet::parse(&format!("\n  {json}\n")).unwrap();
+        assert_eq!(snippet.auth, SnippetAuth::Certificate);
+        assert_eq!(snippet.proxy_chain[0].auth, SnippetAuth::Password);
+        assert_eq!(snippet.proxy_chain[1].auth, SnippetAuth::Agent);
+
+        let imported = snippet
+            .into_connection(
+                SnippetCredential {
+                    key_path: Some("/keys/new".into()),
+                    cert_path: Some("/keys/new-cert.pub".into()),
+                    ..Default::default()
+                },
+                vec![SnippetCredential {
+                    password: Some("hunter2".into()),
+                    ..Default::default()
+                }],
+            )
+            .unwrap();
+        let restored = imported.connection;
+
+        assert_ne!(restored.id, conn.id);
+        assert_eq!(restored.name, conn.name);
+        assert_eq!(restored.group, conn.group);
+        assert_eq!(restored.display_string(), conn.display_string());
+        assert_eq!(restored.tags, conn.tags);
+        assert_eq!(restored.options.keep_alive_interval, 30);
+        assert_eq!(restored.options.proxy_command, None);
+        assert_eq!(restored.proxy_chain.len(), 2);
+        assert_eq!(restored.proxy_chain[0].host, "bastion.example.com");
+        assert_eq!(restored.proxy_chain[1].auth, SavedAuth::Agent);
+        assert_eq!(
+            restored.auth,
+            SavedAuth::Certificate {
+                key_path: "/keys/new".into(),
+                cert_path: "/keys/new-cert.pub".into(),
+                has_passphrase: false,
+                passphrase_keychain_id: None,
+            }
+        );
+
+        // Only the fresh hop password is handed back for the keychain
+        assert_eq!(imported.keychain_entries.len(), 1);
+        let (kc_id, secret) = &imported.keychain_entries[0];
+        assert_eq!(secret, "hunter2");
+        assert_eq!(
+            restored.proxy_chain[0].auth,
+            SavedAuth::Password {
+                keychain_id: kc_id.clone()
+            }
+        );
+    }
+
+    #[test]
+    fn test_import_requires_fresh_credentials() {
+        let json = ConnectionSnippet::from(&secret_connection()).to_json();
+        let snippet = ConnectionSnippet::parse(&json).unwrap();
+
+        let err = snippet
+            .into_connection(
+                SnippetCredential {
+                    key_path: Some("/keys/new".into()),
+                    cert_path: Some("/keys/new-cert.pub".into()),
+                    ..Default::default()
+                },
+                Vec::new(),
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            SnippetError::MissingCredential {
+                auth: SnippetAuth::Password,
+                what: "password",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_parse_rejects_other_json() {
+        assert!(matches!(
+            ConnectionSnippet::parse(r#"{"host": "x"}"#),
+            Err(SnippetError::Invalid(_))
+        ));
+
+        let mut snippet = ConnectionSnippet::from(&secret_connection());
+        snippet.oxide_snippet = 99;
+        assert!(matches!(
+            ConnectionSnippet::parse(&snippet.to_json()),
+            Err(SnippetError::UnsupportedVersion(99))
+        ));
+    }
+}
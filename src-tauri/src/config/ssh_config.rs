@@ -5,11 +5,14 @@
 //! - Basic: Host, HostName, User, Port, IdentityFile
 //! - ProxyJump: Multi-hop jump hosts
 //! - Port Forwarding: LocalForward, RemoteForward, DynamicForward
+//! - Include: globbed, relative to the config's directory, cycle-safe
+//! - Host patterns: `*`/`?` globs and `!` negation, composed by [`resolve_host`]
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
-use tokio::fs;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use super::types::{ProxyHopConfig, SavedAuth, SavedConnection};
 
 /// Port forwarding rule
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -68,6 +71,10 @@ pub struct ProxyJumpHost {
     pub host: String,
     /// Port (default: 22)
     pub port: u16,
+    /// Whether the port was written out (`host:22`), which overrides the
+    /// jump host's own `Port`
+    #[serde(default)]
+    pub explicit_port: bool,
 }
 
 impl ProxyJumpHost {
@@ -82,20 +89,29 @@ impl ProxyJumpHost {
 
         let (host, port) = if host_port.contains(':') {
             let parts: Vec<&str> = host_port.rsplitn(2, ':').collect();
-            (parts[1].to_string(), parts[0].parse().unwrap_or(22))
+            (parts[1].to_string(), parts[0].parse().ok())
         } else {
-            (host_port.to_string(), 22)
+            (host_port.to_string(), None)
         };
 
-        Some(ProxyJumpHost { user, host, port })
+        Some(ProxyJumpHost {
+            user,
+            host,
+            port: port.unwrap_or(22),
+            explicit_port: port.is_some(),
+        })
     }
 }
 
 /// A parsed SSH config host entry (Enhanced)
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SshConfigHost {
-    /// Host alias (the pattern after "Host")
+    /// Host alias: the first plain name after "Host", or the first pattern
+    /// if the line has none (e.g. `*`)
     pub alias: String,
+    /// Every pattern on the "Host" line, as written (`*.example.com`, `!bastion`)
+    #[serde(default)]
+    pub patterns: Vec<String>,
     /// Actual hostname (HostName directive)
     pub hostname: Option<String>,
     /// Username (User directive)
@@ -144,7 +160,53 @@ impl SshConfigHost {
 
     /// Check if this is a wildcard pattern
     pub fn is_wildcard(&self) -> bool {
-        self.alias.contains('*') || self.alias.contains('?')
+        self.alias.contains('*') || self.alias.contains('?') || self.alias.starts_with('!')
+    }
+
+    /// Whether this block applies to `hostname`, with OpenSSH semantics:
+    /// `*` and `?` globs, case-insensitive, and a matching `!pattern`
+    /// excludes the host no matter where it appears on the line.
+    pub fn matches(&self, hostname: &str) -> bool {
+        let hostname = hostname.to_lowercase();
+        let mut matched = false;
+        for pattern in &self.patterns {
+            let pattern = pattern.to_lowercase();
+            match pattern.strip_prefix('!') {
+                Some(negated) if glob_match(negated, &hostname) => return false,
+                Some(_) => {}
+                None => matched |= glob_match(&pattern, &hostname),
+            }
+        }
+        matched
+    }
+
+    /// Fill options not set yet from `block` (ssh's first-value-wins rule);
+    /// forwards accumulate across blocks
+    fn inherit(&mut self, block: &SshConfigHost) {
+        fn first<T: Clone>(slot: &mut Option<T>, value: &Option<T>) {
+            if slot.is_none() {
+                slot.clone_from(value);
+            }
+        }
+        first(&mut self.hostname, &block.hostname);
+        first(&mut self.user, &block.user);
+        first(&mut self.port, &block.port);
+        first(&mut self.identity_file, &block.identity_file);
+        first(&mut self.certificate_file, &block.certificate_file);
+        first(&mut self.proxy_command, &block.proxy_command);
+        first(&mut self.dynamic_forward, &block.dynamic_forward);
+        if self.proxy_jump.is_empty() {
+            self.proxy_jump.clone_from(&block.proxy_jump);
+        }
+        self.local_forwards
+            .extend(block.local_forwards.iter().cloned());
+        self.remote_forwards
+            .extend(block.remote_forwards.iter().cloned());
+        for (key, value) in &block.other {
+            self.other
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
     }
 
     /// Check if this host requires a proxy jump
@@ -179,6 +241,89 @@ impl SshConfigHost {
 
         Some(hops.join(" → "))
     }
+
+    /// Build the saved proxy chain for this host's ProxyJump hops, in order.
+    ///
+    /// Each hop takes HostName, User, Port and IdentityFile from the Host
+    /// blocks matching it (see [`resolve_host`]), wildcard blocks included,
+    /// with `user@` and an explicit `:port` in the ProxyJump value taking
+    /// precedence. Hops no plain Host entry names are listed in `undefined`;
+    /// without a wildcard match they are used as written (user defaults to
+    /// the local user).
+    ///
+    /// Like OpenSSH, the first hop is reached through its own ProxyJump, so
+    /// that chain is resolved recursively and comes first; later hops are
+    /// reached through the previous one. A hop that cycles back to a host
+    /// on the path is dropped.
+    pub fn resolve_proxy_chain(&self, hosts: &[SshConfigHost]) -> ResolvedProxyChain {
+        let mut resolved = ResolvedProxyChain::default();
+        self.push_proxy_hops(hosts, &mut vec![self.alias.clone()], &mut resolved);
+        resolved
+    }
+
+    fn push_proxy_hops(
+        &self,
+        hosts: &[SshConfigHost],
+        path: &mut Vec<String>,
+        resolved: &mut ResolvedProxyChain,
+    ) {
+        for (i, hop) in self.proxy_jump.iter().enumerate() {
+            // A cycle back to a host on the path: that host is reached directly
+            if path.contains(&hop.host) {
+                continue;
+            }
+            if !hosts
+                .iter()
+                .any(|h| !h.is_wildcard() && h.matches(&hop.host))
+                && !resolved.undefined.contains(&hop.host)
+            {
+                resolved.undefined.push(hop.host.clone());
+            }
+
+            let entry = resolve_host(hosts, &hop.host);
+            if i == 0 && entry.has_proxy_jump() {
+                path.push(hop.host.clone());
+                entry.push_proxy_hops(hosts, path, resolved);
+                path.pop();
+            }
+
+            let host = entry.effective_hostname();
+            let port = if hop.explicit_port {
+                hop.port
+            } else {
+                entry.effective_port()
+            };
+            let username = hop
+                .user
+                .clone()
+                .or_else(|| entry.user.clone())
+                .unwrap_or_else(whoami::username);
+            let auth = match entry.identity_file.clone() {
+                Some(key_path) => SavedAuth::Key {
+                    key_path,
+                    has_passphrase: false,
+                    passphrase_keychain_id: None,
+                },
+                None => SavedAuth::Agent,
+            };
+
+            resolved.hops.push(ProxyHopConfig {
+                host: host.to_string(),
+                port,
+                username,
+                auth,
+            });
+        }
+    }
+}
+
+/// A ProxyJump chain resolved against the other hosts in the same config
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedProxyChain {
+    /// Jump hosts, first hop first
+    pub hops: Vec<ProxyHopConfig>,
+    /// ProxyJump names with no `Host` entry of their own
+    pub undefined: Vec<String>,
 }
 
 /// SSH config parser errors
@@ -201,154 +346,430 @@ pub fn default_ssh_config_path() -> Result<PathBuf, SshConfigError> {
         .ok_or(SshConfigError::NoHomeDir)
 }
 
-/// Parse SSH config file
+/// Parse SSH config file (and any files it `Include`s)
+///
+/// Wildcard Host blocks are left out; see [`parse_ssh_config_blocks`].
 pub async fn parse_ssh_config(path: Option<PathBuf>) -> Result<Vec<SshConfigHost>, SshConfigError> {
+    Ok(filter_importable_hosts(
+        parse_ssh_config_blocks(path).await?,
+    ))
+}
+
+/// Parse SSH config like [`parse_ssh_config`], but keep every Host block,
+/// wildcard and negated patterns included, in file order for [`resolve_host`]
+pub async fn parse_ssh_config_blocks(
+    path: Option<PathBuf>,
+) -> Result<Vec<SshConfigHost>, SshConfigError> {
     let path = match path {
         Some(p) => p,
         None => default_ssh_config_path()?,
     };
 
-    let content = match fs::read_to_string(&path).await {
-        Ok(c) => c,
-        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-            return Ok(Vec::new());
-        }
-        Err(e) => return Err(SshConfigError::Io(e)),
-    };
+    tokio::task::spawn_blocking(move || read_config_blocks(&path))
+        .await
+        .map_err(|e| SshConfigError::Io(std::io::Error::other(e)))?
+}
+
+/// Parse an SSH config file, following `Include` directives.
+///
+/// Relative include paths and globs resolve against the directory of
+/// `path`, as OpenSSH does for `~/.ssh/config`. Included files are parsed
+/// in place, so their hosts appear in file order; a file is only read once,
+/// which also breaks include cycles. Missing files are skipped.
+pub fn parse_ssh_config_file(path: &Path) -> Result<Vec<SshConfigHost>, SshConfigError> {
+    Ok(filter_importable_hosts(read_config_blocks(path)?))
+}
 
-    parse_ssh_config_content(&content)
+fn read_config_blocks(path: &Path) -> Result<Vec<SshConfigHost>, SshConfigError> {
+    let mut parser = ConfigParser {
+        base_dir: path.parent().map(Path::to_path_buf),
+        ..Default::default()
+    };
+    parser.read_file(path)?;
+    Ok(parser.finish())
 }
 
 /// Parse SSH config content string
+///
+/// `Include` directives are ignored here: there is no file to resolve them
+/// against. Use [`parse_ssh_config_file`] to follow them.
 pub fn parse_ssh_config_content(content: &str) -> Result<Vec<SshConfigHost>, SshConfigError> {
-    let mut hosts = Vec::new();
-    let mut current_host: Option<SshConfigHost> = None;
+    Ok(filter_importable_hosts(parse_config_blocks(content)?))
+}
+
+fn parse_config_blocks(content: &str) -> Result<Vec<SshConfigHost>, SshConfigError> {
+    let mut parser = ConfigParser::default();
+    parser.parse(content)?;
+    Ok(parser.finish())
+}
 
-    for line in content.lines() {
-        let line = line.trim();
+/// Expand a leading `~/` to the home directory
+fn expand_home(value: &str) -> String {
+    match (value.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(stripped), Some(home)) => home.join(stripped).to_string_lossy().into_owned(),
+        _ => value.to_string(),
+    }
+}
+
+/// Line-by-line parser state, shared across included files
+#[derive(Default)]
+struct ConfigParser {
+    hosts: Vec<SshConfigHost>,
+    current_host: Option<SshConfigHost>,
+    /// Where relative `Include` paths resolve (`None`: includes are ignored)
+    base_dir: Option<PathBuf>,
+    /// Canonical paths of files already read
+    visited: HashSet<PathBuf>,
+}
 
-        // Skip empty lines and comments
-        if line.is_empty() || line.starts_with('#') {
-            continue;
+impl ConfigParser {
+    fn read_file(&mut self, path: &Path) -> Result<(), SshConfigError> {
+        let canonical = match std::fs::canonicalize(path) {
+            Ok(p) => p,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(SshConfigError::Io(e)),
+        };
+        if !self.visited.insert(canonical.clone()) {
+            tracing::warn!(
+                "SSH config {} included more than once (cycle?); skipping",
+                canonical.display()
+            );
+            return Ok(());
         }
 
-        // Parse "Key Value" or "Key=Value"
-        let (key, value) = if let Some(eq_pos) = line.find('=') {
-            let key = line[..eq_pos].trim();
-            let value = line[eq_pos + 1..].trim();
-            (key, value)
-        } else {
-            let parts: Vec<&str> = line.splitn(2, char::is_whitespace).collect();
-            if parts.len() < 2 {
-                continue; // Skip malformed lines
-            }
-            (parts[0], parts[1].trim())
+        let content = match std::fs::read_to_string(&canonical) {
+            Ok(c) => c,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(SshConfigError::Io(e)),
         };
+        self.parse(&content)
+    }
 
-        let key_lower = key.to_lowercase();
+    /// Read every file matched by the patterns of an `Include` line
+    fn include(&mut self, value: &str) -> Result<(), SshConfigError> {
+        let Some(base_dir) = self.base_dir.clone() else {
+            return Ok(());
+        };
 
-        if key_lower == "host" {
-            // Save previous host if exists
-            if let Some(host) = current_host.take() {
-                if !host.is_wildcard() {
-                    hosts.push(host);
+        for pattern in value.split_whitespace() {
+            let pattern = expand_home(pattern.trim_matches('"'));
+            let pattern = if Path::new(&pattern).is_absolute() {
+                pattern
+            } else {
+                base_dir.join(&pattern).to_string_lossy().into_owned()
+            };
+
+            let paths = match glob::glob(&pattern) {
+                Ok(paths) => paths,
+                Err(e) => {
+                    tracing::warn!("Invalid SSH config Include pattern {}: {}", pattern, e);
+                    continue;
+                }
+            };
+            // glob yields matches in alphabetical order, like OpenSSH
+            for path in paths.flatten() {
+                if path.is_file() {
+                    self.read_file(&path)?;
                 }
             }
+        }
+        Ok(())
+    }
 
-            // Handle multiple hosts on same line (e.g., "Host foo bar")
-            for alias in value.split_whitespace() {
-                // For now, we only take the first non-wildcard host
-                if !alias.contains('*') && !alias.contains('?') {
-                    current_host = Some(SshConfigHost {
-                        alias: alias.to_string(),
-                        ..Default::default()
-                    });
-                    break;
-                }
+    fn parse(&mut self, content: &str) -> Result<(), SshConfigError> {
+        for line in content.lines() {
+            let line = line.trim();
+
+            // Skip empty lines and comments
+            if line.is_empty() || line.starts_with('#') {
+                continue;
             }
-        } else if let Some(ref mut host) = current_host {
-            match key_lower.as_str() {
-                "hostname" => host.hostname = Some(value.to_string()),
-                "user" => host.user = Some(value.to_string()),
-                "port" => {
-                    host.port = value.parse().ok();
+
+            // Parse "Key Value" or "Key=Value"
+            let (key, value) = if let Some(eq_pos) = line.find('=') {
+                let key = line[..eq_pos].trim();
+                let value = line[eq_pos + 1..].trim();
+                (key, value)
+            } else {
+                let parts: Vec<&str> = line.splitn(2, char::is_whitespace).collect();
+                if parts.len() < 2 {
+                    continue; // Skip malformed lines
                 }
-                "identityfile" => {
+                (parts[0], parts[1].trim())
+            };
+
+            let key_lower = key.to_lowercase();
+
+            if key_lower == "include" {
+                self.include(value)?;
+            } else if key_lower == "host" {
+                // Save previous host if exists
+                if let Some(host) = self.current_host.take() {
+                    self.hosts.push(host);
+                }
+
+                // Handle multiple patterns on one line (e.g., "Host foo *.example.com !bar")
+                let patterns: Vec<String> = value.split_whitespace().map(String::from).collect();
+                let alias = patterns
+                    .iter()
+                    .find(|p| !p.contains(['*', '?', '!']))
+                    .or(patterns.first())
+                    .cloned()
+                    .unwrap_or_default();
+                self.current_host = Some(SshConfigHost {
+                    alias,
+                    patterns,
+                    ..Default::default()
+                });
+            } else if let Some(ref mut host) = self.current_host {
+                match key_lower.as_str() {
+                    "hostname" => host.hostname = Some(value.to_string()),
+                    "user" => host.user = Some(value.to_string()),
+                    "port" => {
+                        host.port = value.parse().ok();
+                    }
                     // Expand ~ to home directory
-                    let expanded = if let Some(stripped) = value.strip_prefix("~/") {
-                        if let Some(home) = dirs::home_dir() {
-                            home.join(stripped).to_string_lossy().into_owned()
-                        } else {
-                            value.to_string()
+                    "identityfile" => {
+                        host.identity_file = Some(expand_home(value.trim_matches('"')))
+                    }
+                    "certificatefile" => {
+                        host.certificate_file = Some(expand_home(value.trim_matches('"')))
+                    }
+                    // ProxyJump: can be comma-separated for multi-hop
+                    "proxyjump" => {
+                        if value.to_lowercase() != "none" {
+                            for jump in value.split(',') {
+                                if let Some(proxy_host) = ProxyJumpHost::parse(jump.trim()) {
+                                    host.proxy_jump.push(proxy_host);
+                                }
+                            }
                         }
-                    } else {
-                        value.to_string()
-                    };
-                    host.identity_file = Some(expanded);
-                }
-                "certificatefile" => {
-                    // Expand ~ to home directory (same logic as IdentityFile)
-                    let expanded = if let Some(stripped) = value.strip_prefix("~/") {
-                        if let Some(home) = dirs::home_dir() {
-                            home.join(stripped).to_string_lossy().into_owned()
-                        } else {
-                            value.to_string()
+                    }
+                    // ProxyCommand (alternative to ProxyJump)
+                    "proxycommand" => {
+                        if value.to_lowercase() != "none" {
+                            host.proxy_command = Some(value.to_string());
                         }
-                    } else {
-                        value.to_string()
-                    };
-                    host.certificate_file = Some(expanded);
-                }
-                // ProxyJump: can be comma-separated for multi-hop
-                "proxyjump" => {
-                    if value.to_lowercase() != "none" {
-                        for jump in value.split(',') {
-                            if let Some(proxy_host) = ProxyJumpHost::parse(jump.trim()) {
-                                host.proxy_jump.push(proxy_host);
-                            }
+                    }
+                    // LocalForward: [bind_address:]port host:hostport
+                    "localforward" => {
+                        if let Some(rule) = PortForwardRule::parse(value) {
+                            host.local_forwards.push(rule);
                         }
                     }
-                }
-                // ProxyCommand (alternative to ProxyJump)
-                "proxycommand" => {
-                    if value.to_lowercase() != "none" {
-                        host.proxy_command = Some(value.to_string());
+                    // RemoteForward: [bind_address:]port host:hostport
+                    "remoteforward" => {
+                        if let Some(rule) = PortForwardRule::parse(value) {
+                            host.remote_forwards.push(rule);
+                        }
                     }
-                }
-                // LocalForward: [bind_address:]port host:hostport
-                "localforward" => {
-                    if let Some(rule) = PortForwardRule::parse(value) {
-                        host.local_forwards.push(rule);
+                    // DynamicForward: [bind_address:]port
+                    "dynamicforward" => {
+                        let port_str = if value.contains(':') {
+                            value.rsplit(':').next().unwrap_or(value)
+                        } else {
+                            value
+                        };
+                        host.dynamic_forward = port_str.parse().ok();
                     }
-                }
-                // RemoteForward: [bind_address:]port host:hostport
-                "remoteforward" => {
-                    if let Some(rule) = PortForwardRule::parse(value) {
-                        host.remote_forwards.push(rule);
+                    // Kept for wildcard blocks to pass on (first value wins)
+                    _ => {
+                        host.other
+                            .entry(key_lower)
+                            .or_insert_with(|| value.to_string());
                     }
                 }
-                // DynamicForward: [bind_address:]port
-                "dynamicforward" => {
-                    let port_str = if value.contains(':') {
-                        value.rsplit(':').next().unwrap_or(value)
-                    } else {
-                        value
-                    };
-                    host.dynamic_forward = port_str.parse().ok();
-                }
-                _ => {} // Ignore other directives
             }
         }
+        Ok(())
     }
 
-    // Don't forget the last host
-    if let Some(host) = current_host {
-        if !host.is_wildcard() {
-            hosts.push(host);
+    fn finish(mut self) -> Vec<SshConfigHost> {
+        // Don't forget the last host
+        if let Some(host) = self.current_host.take() {
+            self.hosts.push(host);
         }
+        self.hosts
+    }
+}
+
+/// Effective options for connecting to `name`, composed the way ssh does:
+/// every Host block whose patterns match is applied top to bottom, and the
+/// first value seen for an option wins.
+pub fn resolve_host(hosts: &[SshConfigHost], name: &str) -> SshConfigHost {
+    let mut resolved = SshConfigHost {
+        alias: name.to_string(),
+        ..Default::default()
+    };
+    for block in hosts.iter().filter(|h| h.matches(name)) {
+        resolved.inherit(block);
     }
+    resolved
+}
 
-    Ok(hosts)
+/// OpenSSH-style glob: `*` matches any run of characters, `?` exactly one
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` and the text index it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                p += 1;
+                backtrack = Some((p, t));
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` swallow one more character
+                Some((star_p, star_t)) => {
+                    p = star_p;
+                    t = star_t + 1;
+                    backtrack = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Render saved connections as an OpenSSH config fragment.
+///
+/// Each connection becomes a `Host` block named after it. Jump hosts get
+/// blocks of their own (`<alias>-jump<n>`) so their keys carry over, and
+/// `ProxyJump` lists them in order. No secrets are written: password logins
+/// only get a comment saying so.
+pub fn to_ssh_config(connections: &[SavedConnection]) -> String {
+    let mut out = String::new();
+    let mut used = HashSet::new();
+
+    for conn in connections {
+        let alias = unique_alias(&conn.name, &mut used);
+        let hop_aliases: Vec<String> = (1..=conn.proxy_chain.len())
+            .map(|n| unique_alias(&format!("{}-jump{}", alias, n), &mut used))
+            .collect();
+
+        let proxy_jump = (!hop_aliases.is_empty()).then(|| hop_aliases.join(","));
+        let proxy_command = conn
+            .options
+            .proxy_command
+            .as_deref()
+            .filter(|_| proxy_jump.is_none());
+        write_host_block(
+            &mut out,
+            &alias,
+            &conn.host,
+            conn.port,
+            &conn.username,
+            &conn.auth,
+        );
+        if let Some(jumps) = proxy_jump {
+            out.push_str(&format!("    ProxyJump {}\n", jumps));
+        }
+        if let Some(command) = proxy_command {
+            out.push_str(&format!("    ProxyCommand {}\n", command));
+        }
+        out.push('\n');
+
+        for (hop, hop_alias) in conn.proxy_chain.iter().zip(&hop_aliases) {
+            write_host_block(
+                &mut out,
+                hop_alias,
+                &hop.host,
+                hop.port,
+                &hop.username,
+                &hop.auth,
+            );
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn write_host_block(
+    out: &mut String,
+    alias: &str,
+    host: &str,
+    port: u16,
+    user: &str,
+    auth: &SavedAuth,
+) {
+    out.push_str(&format!("Host {}\n    HostName {}\n", alias, host));
+    if port != 22 {
+        out.push_str(&format!("    Port {}\n", port));
+    }
+    out.push_str(&format!("    User {}\n", user));
+    match auth {
+        SavedAuth::Password { .. } => {
+            out.push_str("    # Password authentication: credentials are not exported\n")
+        }
+        SavedAuth::Key { key_path, .. } => {
+            out.push_str(&format!("    IdentityFile {}\n", export_path(key_path)))
+        }
+        SavedAuth::Certificate {
+            key_path,
+            cert_path,
+            ..
+        } => {
+            out.push_str(&format!("    IdentityFile {}\n", export_path(key_path)));
+            out.push_str(&format!("    CertificateFile {}\n", export_path(cert_path)));
+        }
+        SavedAuth::Agent => {}
+    }
+}
+
+/// A `Host` alias for `name` not in `used`: characters ssh treats as
+/// pattern syntax or separators become `-`
+fn unique_alias(name: &str, used: &mut HashSet<String>) -> String {
+    let base: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_whitespace() || "*?!,#=\"".contains(c) {
+                '-'
+            } else {
+                c
+            }
+        })
+        .collect();
+    let base = if base.is_empty() {
+        "host".to_string()
+    } else {
+        base
+    };
+
+    let mut alias = base.clone();
+    let mut n = 2;
+    while !used.insert(alias.clone()) {
+        alias = format!("{}-{}", base, n);
+        n += 1;
+    }
+    alias
+}
+
+/// Key path as written to the fragment: `~/` for the home directory (so it
+/// works for whoever receives it), quoted if it contains spaces
+fn export_path(path: &str) -> String {
+    let path = match dirs::home_dir().and_then(|home| {
+        Path::new(path)
+            .strip_prefix(home)
+            .ok()
+            .map(Path::to_path_buf)
+    }) {
+        Some(relative) => format!("~/{}", relative.to_string_lossy()),
+        None => path.to_string(),
+    };
+    if path.contains(char::is_whitespace) {
+        format!("\"{}\"", path)
+    } else {
+        path
+    }
 }
 
 /// Filter hosts suitable for import (non-wildcard, has hostname or is valid)
@@ -406,6 +827,128 @@ Host prod
         assert_eq!(hosts[0].alias, "prod");
     }
 
+    #[test]
+    fn test_blocks_keep_wildcards() {
+        let content = r#"
+Host *
+    ServerAliveInterval 60
+
+Host dev-*
+    User developer
+
+Host prod
+    HostName prod.example.com
+"#;
+
+        let blocks = parse_config_blocks(content).unwrap();
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[1].patterns, vec!["dev-*"]);
+        assert_eq!(
+            resolve_host(&blocks, "dev-api").user.as_deref(),
+            Some("developer")
+        );
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match(
+            "*.internal.example.com",
+            "db.internal.example.com"
+        ));
+        assert!(!glob_match(
+            "*.internal.example.com",
+            "internal.example.com"
+        ));
+        assert!(glob_match("web-??", "web-01"));
+        assert!(!glob_match("web-??", "web-1"));
+        assert!(glob_match("a*b*c", "axxbyybc"));
+        assert!(!glob_match("a*b*c", "axxbyyb"));
+    }
+
+    #[test]
+    fn test_negated_pattern_excludes_host() {
+        let content = r#"
+Host * !bastion
+    ProxyJump bastion
+"#;
+        let hosts = parse_config_blocks(content).unwrap();
+        let block = &hosts[0];
+        assert_eq!(block.patterns, vec!["*", "!bastion"]);
+        assert!(block.is_wildcard());
+
+        assert!(block.matches("db.example.com"));
+        assert!(!block.matches("bastion"));
+        assert!(!block.matches("BASTION"));
+
+        // Order on the line doesn't matter: a matching negation always wins
+        let reversed = SshConfigHost {
+            patterns: vec!["!bastion".to_string(), "*".to_string()],
+            ..Default::default()
+        };
+        assert!(!reversed.matches("bastion"));
+        // A line of only negations matches nothing
+        let only_negated = SshConfigHost {
+            patterns: vec!["!bastion".to_string()],
+            ..Default::default()
+        };
+        assert!(!only_negated.matches("web"));
+    }
+
+    #[test]
+    fn test_multiple_patterns_on_one_line() {
+        let content = r#"
+Host web *.staging.example.com db?
+    User deploy
+"#;
+        let hosts = parse_ssh_config_content(content).unwrap();
+        let block = &hosts[0];
+        assert_eq!(block.alias, "web");
+        assert!(!block.is_wildcard());
+        assert!(block.matches("web"));
+        assert!(block.matches("api.staging.example.com"));
+        assert!(block.matches("db1"));
+        assert!(!block.matches("db10"));
+        assert!(!block.matches("api.example.com"));
+    }
+
+    #[test]
+    fn test_resolve_host_first_value_wins() {
+        let content = r#"
+Host db.internal.example.com
+    User dba
+
+Host *.internal.example.com !gw.internal.example.com
+    User ops
+    Port 2200
+    ProxyJump gw.internal.example.com
+    LocalForward 5432 localhost:5432
+
+Host *
+    User nobody
+    ServerAliveInterval 60
+    LocalForward 8080 localhost:80
+"#;
+        let hosts = parse_config_blocks(content).unwrap();
+
+        let db = resolve_host(&hosts, "db.internal.example.com");
+        assert_eq!(db.user.as_deref(), Some("dba"));
+        assert_eq!(db.port, Some(2200));
+        assert_eq!(db.proxy_jump[0].host, "gw.internal.example.com");
+        assert_eq!(db.local_forwards.len(), 2);
+        assert_eq!(
+            db.other.get("serveraliveinterval").map(String::as_str),
+            Some("60")
+        );
+        assert_eq!(db.effective_hostname(), "db.internal.example.com");
+
+        // The jump host itself is excluded from the ProxyJump block
+        let gw = resolve_host(&hosts, "gw.internal.example.com");
+        assert_eq!(gw.user.as_deref(), Some("nobody"));
+        assert!(gw.proxy_jump.is_empty());
+        assert_eq!(gw.effective_port(), 22);
+    }
+
     #[test]
     fn test_effective_values() {
         let host = SshConfigHost {
@@ -512,11 +1055,13 @@ Host hpc
                     user: Some("admin".to_string()),
                     host: "jump1".to_string(),
                     port: 22,
+                    explicit_port: false,
                 },
                 ProxyJumpHost {
                     user: None,
                     host: "jump2".to_string(),
                     port: 2222,
+                    explicit_port: true,
                 },
             ],
             ..Default::default()
@@ -525,4 +1070,297 @@ Host hpc
         let desc = host.proxy_jump_description().unwrap();
         assert_eq!(desc, "admin@jump1:22 → jump2:2222");
     }
+
+    #[test]
+    fn test_resolve_two_hop_proxy_chain() {
+        let content = r#"
+Host compute
+    HostName node001.internal
+    User admin
+    ProxyJump bastion,ops@hpc:2200
+
+Host bastion
+    HostName jump.example.com
+    User jumper
+    Port 2022
+    IdentityFile /keys/bastion
+
+Host hpc
+    HostName login.hpc.example.com
+    User hpcuser
+"#;
+
+        let hosts = parse_ssh_config_content(content).unwrap();
+        let resolved = hosts[0].resolve_proxy_chain(&hosts);
+
+        assert!(resolved.undefined.is_empty());
+        assert_eq!(resolved.hops.len(), 2);
+
+        let first = &resolved.hops[0];
+        assert_eq!(first.host, "jump.example.com");
+        assert_eq!(first.port, 2022);
+        assert_eq!(first.username, "jumper");
+        assert_eq!(
+            first.auth,
+            SavedAuth::Key {
+                key_path: "/keys/bastion".to_string(),
+                has_passphrase: false,
+                passphrase_keychain_id: None,
+            }
+        );
+
+        // The ProxyJump value's user and port win over the Host entry
+        let second = &resolved.hops[1];
+        assert_eq!(second.host, "login.hpc.example.com");
+        assert_eq!(second.port, 2200);
+        assert_eq!(second.username, "ops");
+        assert_eq!(second.auth, SavedAuth::Agent);
+    }
+
+    #[test]
+    fn test_include_merges_hosts_in_file_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let sub = dir.path().join("config.d");
+        std::fs::create_dir(&sub).unwrap();
+        std::fs::write(
+            sub.join("10-work"),
+            "Host work\n    HostName work.example.com\n",
+        )
+        .unwrap();
+        std::fs::write(
+            sub.join("20-lab"),
+            "Host lab\n    HostName lab.example.com\n    User researcher\n",
+        )
+        .unwrap();
+        let main = dir.path().join("config");
+        std::fs::write(
+            &main,
+            "Host first\n    HostName first.example.com\n\nInclude config.d/*\n\nHost last\n    HostName last.example.com\n",
+        )
+        .unwrap();
+
+        let hosts = parse_ssh_config_file(&main).unwrap();
+        let aliases: Vec<&str> = hosts.iter().map(|h| h.alias.as_str()).collect();
+        assert_eq!(aliases, vec!["first", "work", "lab", "last"]);
+        assert_eq!(hosts[2].user.as_deref(), Some("researcher"));
+    }
+
+    #[test]
+    fn test_include_cycle_is_read_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("config");
+        std::fs::write(
+            &main,
+            "Include config\nHost loop\n    HostName loop.example.com\n",
+        )
+        .unwrap();
+
+        let hosts = parse_ssh_config_file(&main).unwrap();
+        assert_eq!(hosts.len(), 1);
+        assert_eq!(hosts[0].alias, "loop");
+    }
+
+    #[test]
+    fn test_missing_config_and_includes_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(parse_ssh_config_file(&dir.path().join("absent"))
+            .unwrap()
+            .is_empty());
+
+        let main = dir.path().join("config");
+        std::fs::write(&main, "Include nothing/* missing\nHost a\n").unwrap();
+        assert_eq!(parse_ssh_config_file(&main).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_export_round_trips_through_parser() {
+        let mut web = SavedConnection::new_key(
+            "web server",
+            "web.example.com",
+            2222,
+            "deploy",
+            "/keys/my key",
+        );
+        web.options.proxy_command = Some("nc -X 5 -x proxy:1080 %h %p".to_string());
+
+        let mut db = SavedConnection::new_password("db", "10.0.0.5", 22, "postgres", "kc-db");
+        db.proxy_chain = vec![
+            ProxyHopConfig {
+                host: "bastion.example.com".to_string(),
+                port: 2022,
+                username: "jump".to_string(),
+                auth: SavedAuth::Key {
+                    key_path: "/keys/bastion".to_string(),
+                    has_passphrase: true,
+                    passphrase_keychain_id: Some("kc-hop".to_string()),
+                },
+            },
+            ProxyHopConfig {
+                host: "10.0.0.1".to_string(),
+                port: 22,
+                username: "ops".to_string(),
+                auth: SavedAuth::Agent,
+            },
+        ];
+
+        let mut hpc = SavedConnection::new_key("db", "login.hpc.edu", 22, "alice", "/keys/hpc");
+        hpc.auth = SavedAuth::Certificate {
+            key_path: "/keys/hpc".to_string(),
+            cert_path: "/keys/hpc-cert.pub".to_string(),
+            has_passphrase: false,
+            passphrase_keychain_id: None,
+        };
+
+        let fragment = to_ssh_config(&[web.clone(), db.clone(), hpc.clone()]);
+        assert!(fragment.contains("credentials are not exported"));
+        assert!(!fragment.contains("kc-db") && !fragment.contains("kc-hop"));
+
+        let hosts = parse_ssh_config_content(&fragment).unwrap();
+        let find = |alias: &str| hosts.iter().find(|h| h.alias == alias).unwrap();
+
+        let parsed = find("web-server");
+        assert_eq!(parsed.effective_hostname(), "web.example.com");
+        assert_eq!(parsed.effective_port(), 2222);
+        assert_eq!(parsed.user.as_deref(), Some("deploy"));
+        assert_eq!(parsed.identity_file.as_deref(), Some("/keys/my key"));
+        assert_eq!(parsed.proxy_command, web.options.proxy_command);
+
+        let parsed = find("db");
+        assert_eq!(parsed.effective_hostname(), "10.0.0.5");
+        assert_eq!(parsed.effective_port(), 22);
+        assert!(parsed.identity_file.is_none());
+        let chain = parsed.resolve_proxy_chain(&hosts);
+        assert!(chain.undefined.is_empty());
+        assert_eq!(chain.hops.len(), 2);
+        for (got, want) in chain.hops.iter().zip(&db.proxy_chain) {
+            assert_eq!(
+                (&got.host, got.port, &got.username),
+                (&want.host, want.port, &want.username)
+            );
+        }
+        assert!(
+            matches!(&chain.hops[0].auth, SavedAuth::Key { key_path, .. } if key_path == "/keys/bastion")
+        );
+        assert_eq!(chain.hops[1].auth, SavedAuth::Agent);
+
+        // Second connection named "db" gets a distinct alias
+        let parsed = find("db-2");
+        assert_eq!(parsed.effective_hostname(), "login.hpc.edu");
+        assert_eq!(parsed.identity_file.as_deref(), Some("/keys/hpc"));
+        assert_eq!(
+            parsed.certificate_file.as_deref(),
+            Some("/keys/hpc-cert.pub")
+        );
+
+        // Connections plus one block per jump host, nothing else
+        assert_eq!(hosts.len(), 5);
+        assert!(hosts.iter().all(|h| !h.is_wildcard()));
+    }
+
+    #[test]
+    fn test_resolve_undefined_jump_host() {
+        let content = r#"
+Host target
+    HostName target.example.com
+    ProxyJump admin@jump.example.com:2222
+"#;
+
+        let hosts = parse_ssh_config_content(content).unwrap();
+        let resolved = hosts[0].resolve_proxy_chain(&hosts);
+
+        assert_eq!(resolved.undefined, vec!["jump.example.com".to_string()]);
+        assert_eq!(resolved.hops[0].host, "jump.example.com");
+        assert_eq!(resolved.hops[0].port, 2222);
+        assert_eq!(resolved.hops[0].username, "admin");
+    }
+
+    #[test]
+    fn test_proxy_hop_takes_wildcard_options() {
+        let content = r#"
+Host app
+    HostName app.internal.example.com
+    ProxyJump gw.internal.example.com,bastion
+
+Host bastion
+    HostName bastion.example.com
+
+Host *.internal.example.com
+    User ops
+    Port 2200
+    IdentityFile /keys/internal
+
+Host *
+    User fallback
+"#;
+
+        let hosts = parse_config_blocks(content).unwrap();
+        let resolved = hosts[0].resolve_proxy_chain(&hosts);
+
+        // Only a wildcard block covers the gateway
+        assert_eq!(
+            resolved.undefined,
+            vec!["gw.internal.example.com".to_string()]
+        );
+        let gw = &resolved.hops[0];
+        assert_eq!(gw.host, "gw.internal.example.com");
+        assert_eq!(gw.port, 2200);
+        assert_eq!(gw.username, "ops");
+        assert!(matches!(gw.auth, SavedAuth::Key { .. }));
+
+        let bastion = &resolved.hops[1];
+        assert_eq!(bastion.host, "bastion.example.com");
+        assert_eq!(bastion.port, 22);
+        assert_eq!(bastion.username, "fallback");
+        assert_eq!(bastion.auth, SavedAuth::Agent);
+    }
+
+    #[test]
+    fn test_proxy_hop_explicit_port_overrides() {
+        let content = r#"
+Host app
+    ProxyJump bastion:22
+
+Host bastion
+    HostName bastion.example.com
+    Port 2022
+"#;
+
+        let hosts = parse_ssh_config_content(content).unwrap();
+        assert!(hosts[0].proxy_jump[0].explicit_port);
+        let resolved = hosts[0].resolve_proxy_chain(&hosts);
+        assert_eq!(resolved.hops[0].port, 22);
+    }
+
+    #[test]
+    fn test_resolve_nested_proxy_jump() {
+        let content = r#"
+Host app
+    HostName app.internal
+    ProxyJump inner,edge
+
+Host inner
+    HostName inner.example.com
+    ProxyJump outer
+
+Host outer
+    HostName outer.example.com
+    ProxyJump inner
+
+Host edge
+    HostName edge.example.com
+    ProxyJump unrelated
+"#;
+
+        let hosts = parse_ssh_config_content(content).unwrap();
+        let resolved = hosts[0].resolve_proxy_chain(&hosts);
+        let chain: Vec<&str> = resolved.hops.iter().map(|h| h.host.as_str()).collect();
+
+        // inner is reached through outer; the outer ↔ inner cycle is cut and
+        // edge's own ProxyJump is overridden by the chain
+        assert_eq!(
+            chain,
+            vec!["outer.example.com", "inner.example.com", "edge.example.com"]
+        );
+        assert!(resolved.undefined.is_empty());
+    }
 }
@@ -53,6 +53,11 @@ pub fn log_dir() -> Result<PathBuf, StorageError> {
     Ok(config_dir()?.join("logs"))
 }
 
+/// Get the directory for terminal session recordings
+pub fn recordings_dir() -> Result<PathBuf, StorageError> {
+    Ok(config_dir()?.join("recordings"))
+}
+
 /// Get the connections file path
 pub fn connections_file() -> Result<PathBuf, StorageError> {
     Ok(config_dir()?.join("connections.json"))
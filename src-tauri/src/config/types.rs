@@ -2,6 +2,8 @@
 //!
 //! Data structures for saved connections with version support for migrations.
 
+use std::collections::BTreeSet;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -69,6 +71,15 @@ pub struct ConnectionOptions {
     /// Custom terminal type (default: xterm-256color)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub term_type: Option<String>,
+
+    /// ProxyCommand used as the transport instead of a direct TCP socket.
+    /// Runs an arbitrary local command — see `ssh::proxy_command`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_command: Option<String>,
+
+    /// Disconnect terminals after this many seconds without PTY I/O (0 = disabled)
+    #[serde(default)]
+    pub idle_timeout_secs: u64,
 }
 
 /// A saved connection configuration
@@ -193,6 +204,12 @@ impl SavedConnection {
         self.last_used_at = Some(Utc::now());
     }
 
+    /// Whether the connection carries `tag` (case-insensitive)
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = tag.to_lowercase();
+        self.tags.iter().any(|t| t.to_lowercase() == tag)
+    }
+
     /// Get display string (user@host:port)
     pub fn display_string(&self) -> String {
         if self.port == 22 {
@@ -219,6 +236,14 @@ pub struct ConfigFile {
     /// Recently used connection IDs (most recent first)
     #[serde(default)]
     pub recent: Vec<String>,
+
+    /// Keychain service for SSH secrets, when rotated away from the default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_keychain_service: Option<String>,
+
+    /// Keychain service for AI provider keys, when rotated away from the default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ai_keychain_service: Option<String>,
 }
 
 impl Default for ConfigFile {
@@ -228,6 +253,8 @@ impl Default for ConfigFile {
             connections: Vec::new(),
             groups: Vec::new(),
             recent: Vec::new(),
+            ssh_keychain_service: None,
+            ai_keychain_service: None,
         }
     }
 }
@@ -250,11 +277,44 @@ impl ConfigFile {
         }
     }
 
+    /// Every keychain ID a connection or jump host refers to, sorted
+    pub fn keychain_ids(&self) -> BTreeSet<String> {
+        self.connections
+            .iter()
+            .flat_map(|c| std::iter::once(&c.auth).chain(c.proxy_chain.iter().map(|h| &h.auth)))
+            .filter_map(|auth| match auth {
+                SavedAuth::Password { keychain_id } => Some(keychain_id.clone()),
+                SavedAuth::Key {
+                    passphrase_keychain_id,
+                    ..
+                }
+                | SavedAuth::Certificate {
+                    passphrase_keychain_id,
+                    ..
+                } => passphrase_keychain_id.clone(),
+                SavedAuth::Agent => None,
+            })
+            .collect()
+    }
+
     /// Get connection by ID
     pub fn get_connection(&self, id: &str) -> Option<&SavedConnection> {
         self.connections.iter().find(|c| c.id == id)
     }
 
+    /// Find the saved connection for a live endpoint
+    /// (host case-insensitive, port and username exact)
+    pub fn find_by_endpoint(
+        &self,
+        host: &str,
+        port: u16,
+        username: &str,
+    ) -> Option<&SavedConnection> {
+        self.connections
+            .iter()
+            .find(|c| c.host.eq_ignore_ascii_case(host) && c.port == port && c.username == username)
+    }
+
     /// Get mutable connection by ID
     pub fn get_connection_mut(&mut self, id: &str) -> Option<&mut SavedConnection> {
         self.connections.iter_mut().find(|c| c.id == id)
@@ -292,7 +352,7 @@ impl ConfigFile {
             .collect()
     }
 
-    /// Search connections by name or host
+    /// Search connections by name, host, username or tag
     pub fn search(&self, query: &str) -> Vec<&SavedConnection> {
         let query_lower = query.to_lowercase();
         self.connections
@@ -301,9 +361,71 @@ impl ConfigFile {
                 c.name.to_lowercase().contains(&query_lower)
                     || c.host.to_lowercase().contains(&query_lower)
                     || c.username.to_lowercase().contains(&query_lower)
+                    || c.tags
+                        .iter()
+                        .any(|t| t.to_lowercase().contains(&query_lower))
             })
             .collect()
     }
+
+    /// Get connections carrying a tag (case-insensitive)
+    pub fn get_by_tag(&self, tag: &str) -> Vec<&SavedConnection> {
+        self.get_by_tags(&[tag], false)
+    }
+
+    /// Get connections carrying all (`match_all`) or any of `tags`.
+    /// Matching is case-insensitive; an empty tag list matches nothing.
+    pub fn get_by_tags<S: AsRef<str>>(&self, tags: &[S], match_all: bool) -> Vec<&SavedConnection> {
+        if tags.is_empty() {
+            return Vec::new();
+        }
+        self.connections
+            .iter()
+            .filter(|c| {
+                let mut matches = tags.iter().map(|t| c.has_tag(t.as_ref()));
+                if match_all {
+                    matches.all(|m| m)
+                } else {
+                    matches.any(|m| m)
+                }
+            })
+            .collect()
+    }
+
+    /// Add a tag to each listed connection that doesn't already carry it.
+    /// Returns how many connections changed.
+    pub fn add_tag_to_many(&mut self, ids: &[String], tag: &str) -> usize {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return 0;
+        }
+        let mut changed = 0;
+        for conn in self.connections.iter_mut() {
+            if ids.contains(&conn.id) && !conn.has_tag(tag) {
+                conn.tags.push(tag.to_string());
+                changed += 1;
+            }
+        }
+        changed
+    }
+
+    /// Remove a tag (case-insensitive) from each listed connection.
+    /// Returns how many connections changed.
+    pub fn remove_tag_from_many(&mut self, ids: &[String], tag: &str) -> usize {
+        let tag = tag.trim().to_lowercase();
+        let mut changed = 0;
+        for conn in self.connections.iter_mut() {
+            if !ids.contains(&conn.id) {
+                continue;
+            }
+            let before = conn.tags.len();
+            conn.tags.retain(|t| t.to_lowercase() != tag);
+            if conn.tags.len() != before {
+                changed += 1;
+            }
+        }
+        changed
+    }
 }
 
 #[cfg(test)]
@@ -338,4 +460,70 @@ mod tests {
         assert_eq!(config.connections.len(), 0);
         assert_eq!(config.recent.len(), 0);
     }
+
+    #[test]
+    fn test_find_by_endpoint() {
+        let mut config = ConfigFile::default();
+        let conn = SavedConnection::new_password("Test", "Example.com", 2222, "user", "kc-123");
+        let id = conn.id.clone();
+        config.add_connection(conn);
+
+        let found = config.find_by_endpoint("example.com", 2222, "user");
+        assert_eq!(found.map(|c| c.id.as_str()), Some(id.as_str()));
+        assert!(config.find_by_endpoint("example.com", 22, "user").is_none());
+        assert!(config
+            .find_by_endpoint("example.com", 2222, "root")
+            .is_none());
+    }
+
+    fn tagged(name: &str, tags: &[&str]) -> SavedConnection {
+        let mut conn = SavedConnection::new_password(name, "example.com", 22, "user", "kc");
+        conn.tags = tags.iter().map(|t| t.to_string()).collect();
+        conn
+    }
+
+    fn names(conns: Vec<&SavedConnection>) -> Vec<&str> {
+        conns.into_iter().map(|c| c.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_filter_by_tags_all_vs_any() {
+        let mut config = ConfigFile::default();
+        config.add_connection(tagged("web", &["Prod", "eu"]));
+        config.add_connection(tagged("db", &["prod", "US"]));
+        config.add_connection(tagged("dev", &["staging"]));
+
+        assert_eq!(names(config.get_by_tag("PROD")), ["web", "db"]);
+        assert_eq!(names(config.get_by_tags(&["prod", "eu"], true)), ["web"]);
+        assert_eq!(
+            names(config.get_by_tags(&["eu", "us"], false)),
+            ["web", "db"]
+        );
+        assert!(config.get_by_tags(&["eu", "us"], true).is_empty());
+        assert!(config.get_by_tags::<&str>(&[], false).is_empty());
+        assert_eq!(names(config.search("stag")), ["dev"]);
+    }
+
+    #[test]
+    fn test_bulk_tag_mutations() {
+        let mut config = ConfigFile::default();
+        let web = tagged("web", &["Prod"]);
+        let db = tagged("db", &[]);
+        let dev = tagged("dev", &[]);
+        let ids = vec![web.id.clone(), db.id.clone()];
+        config.add_connection(web);
+        config.add_connection(db);
+        config.add_connection(dev);
+
+        // "web" already has it under different case
+        assert_eq!(config.add_tag_to_many(&ids, " prod "), 1);
+        assert_eq!(config.connections[0].tags, ["Prod"]);
+        assert_eq!(config.connections[1].tags, ["prod"]);
+        assert!(config.connections[2].tags.is_empty());
+        assert_eq!(config.add_tag_to_many(&ids, "  "), 0);
+
+        assert_eq!(config.remove_tag_from_many(&ids, "PROD"), 2);
+        assert!(config.get_by_tag("prod").is_empty());
+        assert_eq!(config.remove_tag_from_many(&ids, "prod"), 0);
+    }
 }
@@ -0,0 +1,290 @@
+//! Dry-run checks for a connection before it is saved
+//!
+//! Nothing here authenticates or touches credentials: the host name is
+//! resolved, the port gets a bare TCP connect that is dropped right away,
+//! and key/certificate paths are looked up on the local filesystem. Network
+//! steps are bounded by short timeouts so the form stays responsive.
+//!
+//! Only the first network hop is probed: the jump host of a proxy chain, or
+//! nothing at all behind a ProxyCommand, since the target may not be
+//! reachable (or even resolvable) from this machine.
+
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Serialize;
+use tokio::net::{lookup_host, TcpStream};
+use tokio::time::timeout;
+
+use crate::session::auth::expand_tilde;
+
+/// Upper bound for DNS resolution
+pub const DNS_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Upper bound for the TCP reachability probe (all resolved addresses)
+pub const TCP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Which check a result belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckKind {
+    Dns,
+    Tcp,
+    KeyFile,
+    CertFile,
+}
+
+/// Outcome of a single check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    Failed,
+    /// Not run because an earlier check failed
+    Skipped,
+}
+
+/// How the client reaches the target host
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeRoute<'a> {
+    Direct,
+    /// Through a proxy chain whose first hop is `host:port`
+    JumpHost {
+        host: &'a str,
+        port: u16,
+    },
+    /// Through a local ProxyCommand, which cannot be probed without running it
+    ProxyCommand,
+}
+
+/// One entry of the validation report
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionCheck {
+    pub kind: CheckKind,
+    pub status: CheckStatus,
+    pub message: String,
+}
+
+impl ConnectionCheck {
+    fn new(kind: CheckKind, status: CheckStatus, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+/// Run every check for `host:port` plus the given key/cert files.
+///
+/// The DNS and TCP checks go to the first hop of `route`.
+pub async fn validate_connection(
+    host: &str,
+    port: u16,
+    route: ProbeRoute<'_>,
+    files: &[(CheckKind, Option<&str>)],
+) -> Vec<ConnectionCheck> {
+    let mut checks = Vec::with_capacity(2 + files.len());
+
+    match route {
+        ProbeRoute::Direct => checks.extend(check_network(host, port).await),
+        ProbeRoute::JumpHost { host, port } => {
+            for mut check in check_network(host, port).await {
+                check.message = format!("Jump host {}: {}", host, check.message);
+                checks.push(check);
+            }
+        }
+        ProbeRoute::ProxyCommand => {
+            for kind in [CheckKind::Dns, CheckKind::Tcp] {
+                checks.push(ConnectionCheck::new(
+                    kind,
+                    CheckStatus::Skipped,
+                    "Reached through ProxyCommand",
+                ));
+            }
+        }
+    }
+
+    for (kind, path) in files {
+        checks.push(check_file(*kind, *path));
+    }
+    checks
+}
+
+/// DNS and TCP checks for `host:port`; the probe is skipped when the host
+/// does not resolve
+async fn check_network(host: &str, port: u16) -> [ConnectionCheck; 2] {
+    let (dns, addrs) = resolve(host, port, DNS_TIMEOUT).await;
+    let tcp = if addrs.is_empty() {
+        ConnectionCheck::new(CheckKind::Tcp, CheckStatus::Skipped, "Host did not resolve")
+    } else {
+        probe_tcp(&addrs, TCP_TIMEOUT).await
+    };
+    [dns, tcp]
+}
+
+/// Resolve `host`; returns the check and the addresses found
+async fn resolve(host: &str, port: u16, limit: Duration) -> (ConnectionCheck, Vec<SocketAddr>) {
+    let host = host.trim();
+    if host.is_empty() {
+        let check = ConnectionCheck::new(CheckKind::Dns, CheckStatus::Failed, "Host is empty");
+        return (check, Vec::new());
+    }
+
+    match timeout(limit, lookup_host((host, port))).await {
+        Ok(Ok(addrs)) => {
+            let addrs: Vec<SocketAddr> = addrs.collect();
+            match addrs.first() {
+                Some(first) => {
+                    let message = format!("Resolved to {}", first.ip());
+                    let check = ConnectionCheck::new(CheckKind::Dns, CheckStatus::Ok, message);
+                    (check, addrs)
+                }
+                None => {
+                    let message = format!("No addresses found for {}", host);
+                    let check = ConnectionCheck::new(CheckKind::Dns, CheckStatus::Failed, message);
+                    (check, addrs)
+                }
+            }
+        }
+        Ok(Err(e)) => {
+            let message = format!("Cannot resolve {}: {}", host, e);
+            let check = ConnectionCheck::new(CheckKind::Dns, CheckStatus::Failed, message);
+            (check, Vec::new())
+        }
+        Err(_) => {
+            let message = format!("Resolving {} timed out", host);
+            let check = ConnectionCheck::new(CheckKind::Dns, CheckStatus::Failed, message);
+            (check, Vec::new())
+        }
+    }
+}
+
+/// Open (and immediately drop) a TCP connection to the first reachable address
+async fn probe_tcp(addrs: &[SocketAddr], limit: Duration) -> ConnectionCheck {
+    let attempt = async {
+        let mut last_error = None;
+        for addr in addrs {
+            match TcpStream::connect(addr).await {
+                Ok(_) => return Ok(*addr),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error)
+    };
+
+    match timeout(limit, attempt).await {
+        Ok(Ok(addr)) => ConnectionCheck::new(
+            CheckKind::Tcp,
+            CheckStatus::Ok,
+            format!("Port {} reachable at {}", addr.port(), addr),
+        ),
+        Ok(Err(Some(e))) => ConnectionCheck::new(
+            CheckKind::Tcp,
+            CheckStatus::Failed,
+            format!("Port unreachable: {}", e),
+        ),
+        Ok(Err(None)) => ConnectionCheck::new(
+            CheckKind::Tcp,
+            CheckStatus::Failed,
+            "No address to connect to",
+        ),
+        Err(_) => ConnectionCheck::new(
+            CheckKind::Tcp,
+            CheckStatus::Failed,
+            format!("Connection timed out after {}s", limit.as_secs()),
+        ),
+    }
+}
+
+/// Check that a key or certificate path points at a file
+fn check_file(kind: CheckKind, path: Option<&str>) -> ConnectionCheck {
+    let Some(path) = path.map(str::trim).filter(|p| !p.is_empty()) else {
+        return ConnectionCheck::new(kind, CheckStatus::Failed, "No file specified");
+    };
+
+    let expanded = expand_tilde(Path::new(path));
+    match std::fs::metadata(&expanded) {
+        Ok(meta) if meta.is_file() => ConnectionCheck::new(kind, CheckStatus::Ok, path),
+        Ok(_) => ConnectionCheck::new(kind, CheckStatus::Failed, format!("{} is not a file", path)),
+        Err(e) => ConnectionCheck::new(kind, CheckStatus::Failed, format!("{}: {}", path, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_file_existence() {
+        let dir = std::env::temp_dir().join(format!("oxideterm-validate-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let key = dir.join("id_test");
+        std::fs::write(&key, b"key").unwrap();
+
+        let found = check_file(CheckKind::KeyFile, key.to_str());
+        assert_eq!(found.status, CheckStatus::Ok);
+
+        let missing = dir.join("id_missing");
+        let check = check_file(CheckKind::CertFile, missing.to_str());
+        assert_eq!(check.kind, CheckKind::CertFile);
+        assert_eq!(check.status, CheckStatus::Failed);
+
+        // A directory is not a usable key
+        assert_eq!(
+            check_file(CheckKind::KeyFile, dir.to_str()).status,
+            CheckStatus::Failed
+        );
+        assert_eq!(
+            check_file(CheckKind::KeyFile, Some("  ")).status,
+            CheckStatus::Failed
+        );
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_unresolvable_host_skips_tcp() {
+        // `.invalid` is reserved and never resolves (RFC 6761)
+        let checks =
+            validate_connection("oxideterm-test.invalid", 22, ProbeRoute::Direct, &[]).await;
+        assert_eq!(checks.len(), 2);
+        assert_eq!(checks[0].kind, CheckKind::Dns);
+        assert_eq!(checks[0].status, CheckStatus::Failed);
+        assert_eq!(checks[1].kind, CheckKind::Tcp);
+        assert_eq!(checks[1].status, CheckStatus::Skipped);
+    }
+
+    #[tokio::test]
+    async fn test_proxied_hosts_probe_first_hop_only() {
+        // The target only resolves on the far side of the proxy
+        let target = "oxideterm-test.invalid";
+        let checks = validate_connection(target, 22, ProbeRoute::ProxyCommand, &[]).await;
+        assert_eq!(checks.len(), 2);
+        assert!(checks.iter().all(|c| c.status == CheckStatus::Skipped));
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let jump = ProbeRoute::JumpHost {
+            host: "127.0.0.1",
+            port: listener.local_addr().unwrap().port(),
+        };
+        let checks = validate_connection(target, 22, jump, &[]).await;
+        assert_eq!(checks[0].status, CheckStatus::Ok);
+        assert_eq!(checks[1].status, CheckStatus::Ok);
+        assert!(checks[1].message.starts_with("Jump host 127.0.0.1: "));
+    }
+
+    #[tokio::test]
+    async fn test_reachable_and_closed_ports() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let open = listener.local_addr().unwrap();
+        let check = probe_tcp(&[open], TCP_TIMEOUT).await;
+        assert_eq!(check.status, CheckStatus::Ok);
+
+        drop(listener);
+        let check = probe_tcp(&[open], TCP_TIMEOUT).await;
+        assert_eq!(check.status, CheckStatus::Failed);
+    }
+}
@@ -12,7 +12,7 @@ use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::{broadcast, mpsc};
 use tracing::{debug, error, info, warn};
 
-use super::events::ForwardEventEmitter;
+use super::events::{ForwardConnectionLog, ForwardEventEmitter};
 use super::manager::ForwardStatus;
 use crate::ssh::{HandleController, SshError};
 
@@ -166,8 +166,15 @@ pub async fn start_dynamic_forward(
 ) -> Result<DynamicForwardHandle, SshError> {
     // Subscribe to disconnect notifications
     let disconnect_rx = handle_controller.subscribe_disconnect();
-    start_dynamic_forward_with_disconnect(handle_controller, config, disconnect_rx, None, None)
-        .await
+    start_dynamic_forward_with_disconnect(
+        handle_controller,
+        config,
+        disconnect_rx,
+        None,
+        None,
+        None,
+    )
+    .await
 }
 
 /// Start dynamic forward with explicit disconnect receiver
@@ -177,6 +184,7 @@ pub async fn start_dynamic_forward_with_disconnect(
     mut disconnect_rx: broadcast::Receiver<()>,
     forward_id: Option<String>,
     event_emitter: Option<ForwardEventEmitter>,
+    connection_log: Option<ForwardConnectionLog>,
 ) -> Result<DynamicForwardHandle, SshError> {
     // Bind to local address
     let listener = TcpListener::bind(&config.local_addr)
@@ -255,6 +263,9 @@ pub async fn start_dynamic_forward_with_disconnect(
                             }
 
                             debug!("SOCKS5: Accepted connection from {}", peer_addr);
+                            if let Some(ref log) = connection_log {
+                                log.accepted(&peer_addr.to_string());
+                            }
 
                             // Update stats
                             stats_clone.connection_count.fetch_add(1, Ordering::Relaxed);
@@ -262,6 +273,7 @@ pub async fn start_dynamic_forward_with_disconnect(
 
                             let controller = handle_controller.clone();
                             let stats_for_conn = stats_clone.clone();
+                            let log_for_conn = connection_log.clone();
                             // Subscribe to shutdown signal for this child task
                             let mut child_shutdown_rx = child_shutdown_tx_clone.subscribe();
 
@@ -281,8 +293,12 @@ pub async fn start_dynamic_forward_with_disconnect(
                                     |n| n.checked_sub(1),
                                 );
 
-                                if let Err(e) = result {
+                                let (bytes_sent, bytes_received) = result.unwrap_or_else(|e| {
                                     warn!("SOCKS5 connection error from {}: {}", peer_addr, e);
+                                    (0, 0)
+                                });
+                                if let Some(log) = log_for_conn {
+                                    log.closed(&peer_addr.to_string(), bytes_sent, bytes_received);
                                 }
                             });
                         }
@@ -337,12 +353,14 @@ pub async fn start_dynamic_forward_with_disconnect(
 }
 
 /// Handle a single SOCKS5 connection
+///
+/// Returns the bytes sent and received once the tunnel closes.
 async fn handle_socks5_connection(
     handle_controller: HandleController,
     mut stream: TcpStream,
     stats: Arc<ForwardStatsAtomic>,
     shutdown_rx: &mut broadcast::Receiver<()>,
-) -> Result<(), SshError> {
+) -> Result<(u64, u64), SshError> {
     // Phase 1: Authentication negotiation
     let mut buf = [0u8; 258];
 
@@ -527,7 +545,7 @@ async fn bridge_socks5_connection(
     mut channel: russh::Channel<russh::client::Msg>,
     stats: Arc<ForwardStatsAtomic>,
     shutdown_rx: &mut broadcast::Receiver<()>,
-) -> Result<(), SshError> {
+) -> Result<(u64, u64), SshError> {
     let (mut local_read, mut local_write) = local_stream.split();
 
     // Create internal channels for lock-free data flow
@@ -542,6 +560,11 @@ async fn bridge_socks5_connection(
     let stats_for_send = stats.clone();
     let stats_for_recv = stats.clone();
 
+    // Per-connection totals, borrowed by the tasks below
+    let sent = AtomicU64::new(0);
+    let received = AtomicU64::new(0);
+    let (sent_ref, received_ref) = (&sent, &received);
+
     // Task 1: Read from local socket, send to mpsc channel
     let local_reader = async move {
         let mut buf = vec![0u8; 32768];
@@ -562,6 +585,7 @@ async fn bridge_socks5_connection(
                         }
                         Ok(Ok(n)) => {
                             stats_for_send.bytes_sent.fetch_add(n as u64, Ordering::Relaxed);
+                            sent_ref.fetch_add(n as u64, Ordering::Relaxed);
                             if local_to_ssh_tx.send(buf[..n].to_vec()).await.is_err() {
                                 debug!("SOCKS5 local reader: channel closed");
                                 break;
@@ -639,6 +663,7 @@ async fn bridge_socks5_connection(
                         Ok(Some(russh::ChannelMsg::Data { data })) => {
                             let data_len = data.len();
                             stats_for_recv.bytes_received.fetch_add(data_len as u64, Ordering::Relaxed);
+                            received_ref.fetch_add(data_len as u64, Ordering::Relaxed);
                             if ssh_to_local_tx.send(data.to_vec()).await.is_err() {
                                 debug!("SOCKS5 SSH I/O: local writer closed");
                                 break;
@@ -687,7 +712,10 @@ async fn bridge_socks5_connection(
     let _ = close_tx.send(());
 
     debug!("SOCKS5 connection closed");
-    Ok(())
+    Ok((
+        sent.load(Ordering::Relaxed),
+        received.load(Ordering::Relaxed),
+    ))
 }
 
 #[cfg(test)]
@@ -0,0 +1,55 @@
+//! Forwarding Errors
+//!
+//! Typed errors for failures the user can act on. Surfaced through
+//! `SshError::Forward` so the forwarding start paths keep their signatures.
+
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ForwardError {
+    /// The SSH server refused to listen on the requested remote port.
+    ///
+    /// The protocol reply carries no reason, but for a specific port the
+    /// usual cause is another process already bound to it. `process` names
+    /// it when the resource profiler has seen the port listening.
+    #[error("{}", describe_remote_port_in_use(*port, process.as_deref()))]
+    RemotePortInUse { port: u16, process: Option<String> },
+}
+
+fn describe_remote_port_in_use(port: u16, process: Option<&str>) -> String {
+    match process {
+        Some(process) => format!(
+            "Remote port {} is already in use on the server by {}",
+            port, process
+        ),
+        None => format!(
+            "Remote port {} is already in use on the server, or the server does not allow forwarding it",
+            port
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_port_in_use_message() {
+        let known = ForwardError::RemotePortInUse {
+            port: 8080,
+            process: Some("nginx (pid 42)".into()),
+        };
+        assert_eq!(
+            known.to_string(),
+            "Remote port 8080 is already in use on the server by nginx (pid 42)"
+        );
+
+        let unknown = ForwardError::RemotePortInUse {
+            port: 8080,
+            process: None,
+        };
+        assert!(unknown
+            .to_string()
+            .starts_with("Remote port 8080 is already in use"));
+    }
+}
@@ -2,7 +2,13 @@
 //!
 //! Provides event emission for port forward status changes.
 //! Events are emitted through Tauri's event system to the frontend.
+//!
+//! Connection accept/close events for the per-forward audit log go through
+//! [`ConnectionLogSink`] instead, so forwards never depend on the state store.
+
+use std::sync::Arc;
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use tauri::Emitter;
 
@@ -116,6 +122,96 @@ impl std::fmt::Debug for ForwardEventEmitter {
     }
 }
 
+/// Kind of connection event recorded in a forward's audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectionEventKind {
+    /// A client connection was accepted
+    Accept,
+    /// A client connection was closed
+    Close,
+}
+
+/// One entry of a forward's connection audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionEvent {
+    pub kind: ConnectionEventKind,
+    pub timestamp: DateTime<Utc>,
+    /// Client address (for remote forwards, the originator reported by the server)
+    pub peer_addr: String,
+    /// Bytes sent client -> target over the connection (0 for `Accept`)
+    pub bytes_sent: u64,
+    /// Bytes received target -> client over the connection (0 for `Accept`)
+    pub bytes_received: u64,
+}
+
+/// Destination for connection audit events
+///
+/// Logging is opt-in per forward, so `is_enabled` is checked before an
+/// event is built; it must be cheap.
+pub trait ConnectionLogSink: Send + Sync {
+    /// Whether events for `forward_id` should be recorded
+    fn is_enabled(&self, forward_id: &str) -> bool;
+
+    /// Record an event for `forward_id`. Must not block the caller.
+    fn record(&self, forward_id: &str, event: ConnectionEvent);
+}
+
+/// Connection audit log handle for a single forward
+#[derive(Clone)]
+pub struct ForwardConnectionLog {
+    forward_id: String,
+    sink: Arc<dyn ConnectionLogSink>,
+}
+
+impl ForwardConnectionLog {
+    pub fn new(forward_id: impl Into<String>, sink: Arc<dyn ConnectionLogSink>) -> Self {
+        Self {
+            forward_id: forward_id.into(),
+            sink,
+        }
+    }
+
+    /// Record an accepted connection
+    pub fn accepted(&self, peer_addr: &str) {
+        self.record(ConnectionEventKind::Accept, peer_addr, 0, 0);
+    }
+
+    /// Record a closed connection with the bytes it carried
+    pub fn closed(&self, peer_addr: &str, bytes_sent: u64, bytes_received: u64) {
+        self.record(
+            ConnectionEventKind::Close,
+            peer_addr,
+            bytes_sent,
+            bytes_received,
+        );
+    }
+
+    fn record(&self, kind: ConnectionEventKind, peer_addr: &str, sent: u64, received: u64) {
+        if !self.sink.is_enabled(&self.forward_id) {
+            return;
+        }
+        self.sink.record(
+            &self.forward_id,
+            ConnectionEvent {
+                kind,
+                timestamp: Utc::now(),
+                peer_addr: peer_addr.to_string(),
+                bytes_sent: sent,
+                bytes_received: received,
+            },
+        );
+    }
+}
+
+impl std::fmt::Debug for ForwardConnectionLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ForwardConnectionLog")
+            .field("forward_id", &self.forward_id)
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
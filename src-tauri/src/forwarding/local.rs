@@ -12,7 +12,7 @@ use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::{broadcast, mpsc};
 use tracing::{debug, error, info, warn};
 
-use super::events::ForwardEventEmitter;
+use super::events::{ForwardConnectionLog, ForwardEventEmitter};
 use super::manager::ForwardStatus;
 use crate::ssh::{HandleController, SshError};
 
@@ -175,7 +175,8 @@ pub async fn start_local_forward(
 ) -> Result<LocalForwardHandle, SshError> {
     // Subscribe to disconnect notifications
     let disconnect_rx = handle_controller.subscribe_disconnect();
-    start_local_forward_with_disconnect(handle_controller, config, disconnect_rx, None, None).await
+    start_local_forward_with_disconnect(handle_controller, config, disconnect_rx, None, None, None)
+        .await
 }
 
 /// Start local port forwarding with explicit disconnect receiver
@@ -185,6 +186,7 @@ pub async fn start_local_forward_with_disconnect(
     mut disconnect_rx: broadcast::Receiver<()>,
     forward_id: Option<String>,
     event_emitter: Option<ForwardEventEmitter>,
+    connection_log: Option<ForwardConnectionLog>,
 ) -> Result<LocalForwardHandle, SshError> {
     // Bind to local address
     let listener = TcpListener::bind(&config.local_addr)
@@ -269,6 +271,9 @@ pub async fn start_local_forward_with_disconnect(
                             }
 
                             debug!("Accepted connection from {} for forward", peer_addr);
+                            if let Some(ref log) = connection_log {
+                                log.accepted(&peer_addr.to_string());
+                            }
 
                             // Update stats
                             stats_clone.connection_count.fetch_add(1, Ordering::Relaxed);
@@ -278,6 +283,7 @@ pub async fn start_local_forward_with_disconnect(
                             let controller = handle_controller.clone();
                             let remote_host_clone = remote_host.clone();
                             let stats_for_conn = stats_clone.clone();
+                            let log_for_conn = connection_log.clone();
                             // Subscribe to shutdown signal for this child task
                             let mut child_shutdown_rx = child_shutdown_tx_clone.subscribe();
 
@@ -299,8 +305,12 @@ pub async fn start_local_forward_with_disconnect(
                                     |n| n.checked_sub(1),
                                 );
 
-                                if let Err(e) = result {
+                                let (bytes_sent, bytes_received) = result.unwrap_or_else(|e| {
                                     warn!("Forward connection error: {}", e);
+                                    (0, 0)
+                                });
+                                if let Some(log) = log_for_conn {
+                                    log.closed(&peer_addr.to_string(), bytes_sent, bytes_received);
                                 }
                             });
                         }
@@ -373,6 +383,8 @@ const FORWARD_IDLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs
 /// - Lock contention between read/write paths
 /// - Potential deadlocks from holding locks across `.await`
 /// - The need to manually manage lock ordering
+///
+/// Returns the bytes sent and received over this connection.
 async fn handle_forward_connection(
     handle_controller: HandleController,
     mut local_stream: TcpStream,
@@ -380,7 +392,7 @@ async fn handle_forward_connection(
     remote_port: u16,
     stats: Arc<ForwardStatsAtomic>,
     shutdown_rx: &mut broadcast::Receiver<()>,
-) -> Result<(), SshError> {
+) -> Result<(u64, u64), SshError> {
     // Open direct-tcpip channel to remote via Handle Owner Task
     let mut channel = handle_controller
         .open_direct_tcpip(remote_host, remote_port as u32, "127.0.0.1", 0)
@@ -409,6 +421,11 @@ async fn handle_forward_connection(
     let stats_for_send = stats.clone();
     let stats_for_recv = stats.clone();
 
+    // Per-connection totals, borrowed by the tasks below
+    let sent = AtomicU64::new(0);
+    let received = AtomicU64::new(0);
+    let (sent_ref, received_ref) = (&sent, &received);
+
     // Task 1: Read from local socket, send to mpsc channel
     let local_reader = async move {
         let mut buf = vec![0u8; 32768];
@@ -429,6 +446,7 @@ async fn handle_forward_connection(
                         }
                         Ok(Ok(n)) => {
                             stats_for_send.bytes_sent.fetch_add(n as u64, Ordering::Relaxed);
+                            sent_ref.fetch_add(n as u64, Ordering::Relaxed);
                             if local_to_ssh_tx.send(buf[..n].to_vec()).await.is_err() {
                                 debug!("Local reader: channel closed");
                                 break;
@@ -512,6 +530,7 @@ async fn handle_forward_connection(
                         Ok(Some(russh::ChannelMsg::Data { data })) => {
                             let data_len = data.len();
                             stats_for_recv.bytes_received.fetch_add(data_len as u64, Ordering::Relaxed);
+                            received_ref.fetch_add(data_len as u64, Ordering::Relaxed);
                             if ssh_to_local_tx.send(data.to_vec()).await.is_err() {
                                 debug!("SSH I/O: local writer closed");
                                 break;
@@ -554,7 +573,10 @@ async fn handle_forward_connection(
     let _ = close_tx.send(());
 
     debug!("Forward connection closed");
-    Ok(())
+    Ok((
+        sent.load(Ordering::Relaxed),
+        received.load(Ordering::Relaxed),
+    ))
 }
 
 #[cfg(test)]
@@ -4,6 +4,7 @@
 //! Provides lifecycle management, status tracking, and cleanup.
 
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
@@ -14,7 +15,7 @@ use super::dynamic::{
     start_dynamic_forward_with_disconnect, DynamicForward, DynamicForwardHandle,
     ForwardStats as DynamicForwardStats,
 };
-use super::events::ForwardEventEmitter;
+use super::events::{ConnectionLogSink, ForwardConnectionLog, ForwardEventEmitter};
 use super::local::{
     start_local_forward_with_disconnect, ForwardStats as LocalForwardStats, LocalForward,
     LocalForwardHandle,
@@ -231,6 +232,8 @@ pub struct ForwardingManager {
     handle_controller: HandleController,
     /// Event emitter for frontend notifications (optional)
     event_emitter: Option<ForwardEventEmitter>,
+    /// Destination for per-forward connection audit events (optional)
+    connection_log: Option<Arc<dyn ConnectionLogSink>>,
     /// Active local forwards
     local_forwards: RwLock<HashMap<String, LocalForwardEntry>>,
     /// Active remote forwards
@@ -250,6 +253,7 @@ impl ForwardingManager {
         Self {
             handle_controller,
             event_emitter: None,
+            connection_log: None,
             local_forwards: RwLock::new(HashMap::new()),
             remote_forwards: RwLock::new(HashMap::new()),
             dynamic_forwards: RwLock::new(HashMap::new()),
@@ -268,6 +272,7 @@ impl ForwardingManager {
         Self {
             handle_controller,
             event_emitter: Some(event_emitter),
+            connection_log: None,
             local_forwards: RwLock::new(HashMap::new()),
             remote_forwards: RwLock::new(HashMap::new()),
             dynamic_forwards: RwLock::new(HashMap::new()),
@@ -281,6 +286,21 @@ impl ForwardingManager {
         self.event_emitter = Some(event_emitter);
     }
 
+    /// Set the connection audit log sink after construction
+    ///
+    /// Forwards created afterwards report accept/close events to it; the sink
+    /// decides per forward whether they are recorded.
+    pub fn set_connection_log(&mut self, sink: Arc<dyn ConnectionLogSink>) {
+        self.connection_log = Some(sink);
+    }
+
+    /// Connection audit log handle for a forward, if a sink is configured
+    fn connection_log_for(&self, forward_id: &str) -> Option<ForwardConnectionLog> {
+        self.connection_log
+            .as_ref()
+            .map(|sink| ForwardConnectionLog::new(forward_id, sink.clone()))
+    }
+
     /// Emit status changed event if emitter is configured
     fn emit_status_changed(&self, forward_id: &str, status: ForwardStatus, error: Option<String>) {
         if let Some(ref emitter) = self.event_emitter {
@@ -327,6 +347,7 @@ impl ForwardingManager {
             disconnect_rx,
             Some(rule.id.clone()),
             self.event_emitter.clone(),
+            self.connection_log_for(&rule.id),
         )
         .await?;
 
@@ -382,8 +403,12 @@ impl ForwardingManager {
             disconnect_rx,
             Some(rule.id.clone()),
             self.event_emitter.clone(),
+            self.connection_log_for(&rule.id),
         )
         .await?;
+
+        // Report the server-assigned port when 0 was requested
+        rule.bind_port = handle.bound_port;
         rule.status = ForwardStatus::Active;
 
         let entry = RemoteForwardEntry {
@@ -427,6 +452,7 @@ impl ForwardingManager {
             disconnect_rx,
             Some(rule.id.clone()),
             self.event_emitter.clone(),
+            self.connection_log_for(&rule.id),
         )
         .await?;
 
@@ -873,6 +899,8 @@ impl ForwardingManager {
 mod tests {
     use super::*;
 
+    use crate::forwarding::ForwardError;
+
     #[test]
     fn test_forward_rule_local() {
         let rule = ForwardRule::local("127.0.0.1", 8888, "localhost", 8888);
@@ -895,4 +923,64 @@ mod tests {
         let rule = ForwardRule::local("127.0.0.1", 8888, "localhost", 8888).with_id("my-jupyter");
         assert_eq!(rule.id, "my-jupyter");
     }
+
+    /// Answer remote-forward commands the way a server would, binding
+    /// port 0 to `assigned` and refusing `taken`.
+    fn mock_handle(assigned: u32, taken: u32) -> HandleController {
+        use crate::ssh::HandleCommand;
+
+        let (cmd_tx, mut cmd_rx) = tokio::sync::mpsc::channel(8);
+        tokio::spawn(async move {
+            while let Some(cmd) = cmd_rx.recv().await {
+                match cmd {
+                    HandleCommand::TcpipForward { port, reply_tx, .. } => {
+                        let reply = match port {
+                            0 => Ok(assigned),
+                            p if p == taken => Err(russh::Error::RequestDenied),
+                            p => Ok(p),
+                        };
+                        let _ = reply_tx.send(reply);
+                    }
+                    HandleCommand::CancelTcpipForward { reply_tx, .. } => {
+                        let _ = reply_tx.send(Ok(()));
+                    }
+                    _ => {}
+                }
+            }
+        });
+        HandleController::new(cmd_tx)
+    }
+
+    #[tokio::test]
+    async fn test_remote_forward_port_zero_reports_assigned_port() {
+        use super::super::remote::REMOTE_FORWARD_REGISTRY;
+
+        let manager = ForwardingManager::new(mock_handle(43210, 2222), "session-port-zero");
+        let rule = ForwardRule::remote("127.0.0.42", 0, "localhost", 3000);
+
+        let created = manager.create_forward(rule).await.unwrap();
+        assert_eq!(created.bind_port, 43210);
+        assert_eq!(created.status, ForwardStatus::Active);
+        assert!(REMOTE_FORWARD_REGISTRY
+            .lookup("127.0.0.42", 43210)
+            .await
+            .is_some());
+
+        manager.stop_forward(&created.id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_remote_forward_refused_port_is_in_use() {
+        let manager = ForwardingManager::new(mock_handle(43210, 2222), "session-port-taken");
+        let rule = ForwardRule::remote("127.0.0.43", 2222, "localhost", 3000);
+
+        let err = manager.create_forward(rule).await.unwrap_err();
+        assert!(matches!(
+            err,
+            SshError::Forward(ForwardError::RemotePortInUse {
+                port: 2222,
+                process: None
+            })
+        ));
+    }
 }
@@ -4,13 +4,18 @@
 //! Designed for HPC/supercomputing workflows (Jupyter, TensorBoard, etc.)
 
 mod dynamic;
+mod error;
 mod events;
 mod local;
 pub mod manager;
 pub mod remote;
 
 pub use dynamic::{start_dynamic_forward, DynamicForward, DynamicForwardHandle};
-pub use events::{ForwardEvent, ForwardEventEmitter};
+pub use error::ForwardError;
+pub use events::{
+    ConnectionEvent, ConnectionEventKind, ConnectionLogSink, ForwardConnectionLog, ForwardEvent,
+    ForwardEventEmitter,
+};
 pub use local::{start_local_forward, LocalForward, LocalForwardHandle};
 pub use manager::{
     ForwardRule, ForwardRuleUpdate, ForwardStats, ForwardStatus, ForwardType, ForwardingManager,
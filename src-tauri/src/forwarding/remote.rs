@@ -23,7 +23,7 @@ use tokio::net::TcpStream;
 use tokio::sync::{broadcast, mpsc, RwLock};
 use tracing::{debug, info, warn};
 
-use super::events::ForwardEventEmitter;
+use super::events::{ForwardConnectionLog, ForwardEventEmitter};
 use super::manager::ForwardStatus;
 use crate::ssh::{HandleController, SshError};
 
@@ -97,6 +97,8 @@ pub struct RemoteForwardTarget {
     pub local_port: u16,
     /// Stats tracking using atomics for lock-free updates from async handlers
     pub stats: Arc<RemoteForwardStatsAtomic>,
+    /// Connection audit log, if the forward has one
+    pub connection_log: Option<ForwardConnectionLog>,
 }
 
 /// Atomic stats for remote forwards (used for thread-safe updates from callbacks)
@@ -148,6 +150,7 @@ impl RemoteForwardRegistry {
         remote_port: u16,
         local_host: String,
         local_port: u16,
+        connection_log: Option<ForwardConnectionLog>,
     ) -> Arc<RemoteForwardStatsAtomic> {
         let key = (remote_addr.clone(), remote_port);
         let stats = Arc::new(RemoteForwardStatsAtomic::new());
@@ -155,6 +158,7 @@ impl RemoteForwardRegistry {
             local_host,
             local_port,
             stats: stats.clone(),
+            connection_log,
         };
         self.forwards.write().await.insert(key, target);
         debug!(
@@ -282,7 +286,8 @@ pub async fn start_remote_forward(
 ) -> Result<RemoteForwardHandle, SshError> {
     // Subscribe to disconnect notifications
     let disconnect_rx = handle_controller.subscribe_disconnect();
-    start_remote_forward_with_disconnect(handle_controller, config, disconnect_rx, None, None).await
+    start_remote_forward_with_disconnect(handle_controller, config, disconnect_rx, None, None, None)
+        .await
 }
 
 /// Start remote forward with explicit disconnect receiver and optional event emitter
@@ -292,6 +297,7 @@ pub async fn start_remote_forward_with_disconnect(
     mut disconnect_rx: broadcast::Receiver<()>,
     forward_id: Option<String>,
     event_emitter: Option<ForwardEventEmitter>,
+    connection_log: Option<ForwardConnectionLog>,
 ) -> Result<RemoteForwardHandle, SshError> {
     info!(
         "Requesting remote port forward: {}:{} -> {}:{}",
@@ -317,6 +323,7 @@ pub async fn start_remote_forward_with_disconnect(
             actual_port as u16,
             config.local_host.clone(),
             config.local_port,
+            connection_log,
         )
         .await;
 
@@ -417,11 +424,19 @@ pub async fn handle_forwarded_connection(
         .fetch_add(1, Ordering::Relaxed);
     let stats = target.stats.clone();
 
+    let peer_addr = format!("{}:{}", originator_address, originator_port);
+    if let Some(ref log) = target.connection_log {
+        log.accepted(&peer_addr);
+    }
+
     // Connect to local service
     let local_addr = format!("{}:{}", target.local_host, target.local_port);
     let local_stream = TcpStream::connect(&local_addr).await.map_err(|e| {
         // Decrement active connections on connection failure
         stats.active_connections.fetch_sub(1, Ordering::Relaxed);
+        if let Some(ref log) = target.connection_log {
+            log.closed(&peer_addr, 0, 0);
+        }
         SshError::ConnectionFailed(format!("Failed to connect to {}: {}", local_addr, e))
     })?;
 
@@ -436,12 +451,17 @@ pub async fn handle_forwarded_connection(
     );
 
     // Bridge the connection
-    let result = bridge_forwarded_connection(local_stream, channel, stats.clone()).await;
+    let (bytes_sent, bytes_received) =
+        bridge_forwarded_connection(local_stream, channel, stats.clone()).await;
 
     // Decrement active connections when done
     stats.active_connections.fetch_sub(1, Ordering::Relaxed);
 
-    result
+    if let Some(ref log) = target.connection_log {
+        log.closed(&peer_addr, bytes_sent, bytes_received);
+    }
+
+    Ok(())
 }
 
 /// Idle timeout for remote forwarded connections (5 minutes)
@@ -458,11 +478,13 @@ const REMOTE_FORWARD_IDLE_TIMEOUT: std::time::Duration = std::time::Duration::fr
 /// 1. No lock contention between concurrent read/write operations
 /// 2. Explicit timeout on all I/O operations (protects against zombie connections)
 /// 3. Clean shutdown propagation via broadcast channel
+///
+/// Returns the bytes sent and received over this connection.
 async fn bridge_forwarded_connection(
     mut local_stream: TcpStream,
     mut channel: russh::Channel<russh::client::Msg>,
     stats: Arc<RemoteForwardStatsAtomic>,
-) -> Result<(), SshError> {
+) -> (u64, u64) {
     let (mut local_read, mut local_write) = local_stream.split();
 
     // Create internal channels for lock-free data flow
@@ -477,6 +499,11 @@ async fn bridge_forwarded_connection(
     let stats_for_send = stats.clone();
     let stats_for_recv = stats.clone();
 
+    // Per-connection totals, borrowed by the tasks below
+    let sent = AtomicU64::new(0);
+    let received = AtomicU64::new(0);
+    let (sent_ref, received_ref) = (&sent, &received);
+
     // Task 1: Read from local socket, send to mpsc channel
     let local_reader = async move {
         let mut buf = vec![0u8; 32768];
@@ -497,6 +524,7 @@ async fn bridge_forwarded_connection(
                         }
                         Ok(Ok(n)) => {
                             stats_for_send.bytes_sent.fetch_add(n as u64, Ordering::Relaxed);
+                            sent_ref.fetch_add(n as u64, Ordering::Relaxed);
                             if local_to_ssh_tx.send(buf[..n].to_vec()).await.is_err() {
                                 debug!("Remote forward local reader: channel closed");
                                 break;
@@ -574,6 +602,7 @@ async fn bridge_forwarded_connection(
                         Ok(Some(russh::ChannelMsg::Data { data })) => {
                             let data_len = data.len();
                             stats_for_recv.bytes_received.fetch_add(data_len as u64, Ordering::Relaxed);
+                            received_ref.fetch_add(data_len as u64, Ordering::Relaxed);
                             if ssh_to_local_tx.send(data.to_vec()).await.is_err() {
                                 debug!("Remote forward SSH I/O: local writer closed");
                                 break;
@@ -616,7 +645,10 @@ async fn bridge_forwarded_connection(
     let _ = close_tx.send(());
 
     debug!("Remote forward connection closed");
-    Ok(())
+    (
+        sent.load(Ordering::Relaxed),
+        received.load(Ordering::Relaxed),
+    )
 }
 
 #[cfg(test)]
@@ -643,7 +675,13 @@ mod tests {
 
         // Register
         registry
-            .register("0.0.0.0".to_string(), 9000, "localhost".to_string(), 3000)
+            .register(
+                "0.0.0.0".to_string(),
+                9000,
+                "localhost".to_string(),
+                3000,
+                None,
+            )
             .await;
 
         // Lookup
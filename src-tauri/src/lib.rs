@@ -265,8 +265,8 @@ pub fn run() {
     // Create transfer manager for concurrent transfer control
     let transfer_manager = Arc::new(TransferManager::new());
 
-    // Create session tree state for dynamic jump host support
-    let session_tree_state = Arc::new(SessionTreeState::new());
+    // Create session tree state for dynamic jump host support (restored from the last snapshot)
+    let session_tree_state = Arc::new(SessionTreeState::with_store(state_store.clone()));
 
     // Oxide-Next Phase 2: 创建 NodeEventEmitter（共享实例）
     let node_event_emitter = Arc::new(router::NodeEventEmitter::new());
@@ -324,6 +324,7 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .manage(BridgeManager::new())
         .manage(registry.clone())
+        .manage(state_store.clone())
         .manage(forwarding_registry.clone())
         .manage(health_registry)
         .manage(commands::ProfilerRegistry::new())
@@ -359,7 +360,32 @@ pub fn run() {
 
         match tauri::async_runtime::block_on(ConfigState::new()) {
             Ok(config_state) => {
-                app.manage(Arc::new(config_state));
+                // Drop ignored ports of connections deleted outside the app
+                let connection_ids: std::collections::HashSet<String> = config_state
+                    .get_config_snapshot()
+                    .connections
+                    .into_iter()
+                    .map(|conn| conn.id)
+                    .collect();
+                let store = state_store.clone();
+                tauri::async_runtime::spawn_blocking(move || {
+                    match store.retain_ignored_ports(&connection_ids) {
+                        Ok(0) => {}
+                        Ok(removed) => {
+                            tracing::info!(
+                                "Removed ignored ports of {} deleted connections",
+                                removed
+                            )
+                        }
+                        Err(e) => tracing::warn!("Failed to prune ignored ports: {}", e),
+                    }
+                });
+
+                let config_state = Arc::new(config_state);
+                // Restored session tree nodes resolve their keychain references through it
+                app.state::<Arc<SessionTreeState>>()
+                    .attach_config(config_state.clone());
+                app.manage(config_state);
                 tracing::info!("Config state initialized successfully");
                 write_startup_log("Config state initialized successfully");
             }
@@ -384,6 +410,9 @@ pub fn run() {
         // Oxide-Next Phase 2: Set AppHandle for NodeEventEmitter
         node_event_emitter.set_app_handle(app.handle().clone());
 
+        // Agent registry emits fs:backend_changed
+        agent_registry.set_app_handle(app.handle().clone());
+
         // Initialize auto reconnect service
         let reconnect_service = Arc::new(AutoReconnectService::new(
             registry.clone(),
@@ -448,9 +477,16 @@ pub fn run() {
         commands::ssh_set_keep_alive,
         commands::ssh_get_pool_config,
         commands::ssh_set_pool_config,
+        commands::bridge_get_config,
+        commands::bridge_set_config,
         commands::ssh_get_pool_stats,
         commands::create_terminal,
         commands::close_terminal,
+        commands::set_session_notify,
+        commands::set_paste_guard,
+        commands::confirm_paste,
+        commands::session_start_recording,
+        commands::session_stop_recording,
         commands::recreate_terminal_pty,
         // SSH host key preflight (TOFU)
         commands::ssh_preflight,
@@ -465,10 +501,13 @@ pub fn run() {
         commands::get_all_buffer_lines,
         // Search commands
         commands::search_terminal,
+        commands::search_all_sessions,
+        commands::search_export_results,
         commands::scroll_to_line,
         // Session tree commands (dynamic jump host)
         commands::get_session_tree,
         commands::get_session_tree_summary,
+        commands::search_session_tree,
         commands::add_root_node,
         commands::tree_drill_down,
         commands::expand_manual_preset,
@@ -480,6 +519,7 @@ pub fn run() {
         commands::remove_topology_edge,
         commands::exclude_topology_edge,
         commands::expand_auto_route,
+        commands::preview_auto_route,
         // Session tree node management
         commands::update_tree_node_state,
         commands::set_tree_node_connection,
@@ -491,22 +531,36 @@ pub fn run() {
         commands::clear_session_tree,
         commands::connect_tree_node,
         commands::disconnect_tree_node,
+        commands::disconnect_all_tree_nodes,
         commands::connect_manual_preset,
+        commands::reconnect_chain_segment,
+        commands::reconnect_tree_subtree,
         commands::destroy_node_sessions,
         // Config commands
         commands::config::get_connections,
         commands::config::get_recent_connections,
         commands::config::get_connections_by_group,
         commands::config::search_connections,
+        commands::config::config_filter_by_tags,
+        commands::config::config_add_tag_to_many,
+        commands::config::config_remove_tag_from_many,
         commands::config::get_groups,
         commands::config::save_connection,
         commands::config::delete_connection,
         commands::config::mark_connection_used,
         commands::config::get_connection_password,
+        commands::config::get_keychain_status,
+        commands::config::enable_keychain_file_fallback,
         commands::config::get_saved_connection_for_connect,
+        commands::config::config_check_certificate,
+        commands::config::config_validate_connection,
+        commands::config::config_test_connection,
         commands::config::list_ssh_config_hosts,
         commands::config::import_ssh_host,
         commands::config::get_ssh_config_path,
+        commands::config::config_export_connection_snippet,
+        commands::config::export_to_ssh_config,
+        commands::config::config_import_connection_snippet,
         commands::config::create_group,
         commands::config::delete_group,
         // AI API key commands
@@ -520,8 +574,11 @@ pub fn run() {
         commands::config::has_ai_provider_api_key,
         commands::config::delete_ai_provider_api_key,
         commands::config::list_ai_provider_keys,
+        commands::config::config_consolidate_credentials,
+        commands::config::rotate_keychain,
         // Oxide file export/import commands
         commands::oxide_export::export_to_oxide,
+        commands::oxide_export::append_to_oxide,
         commands::oxide_export::preflight_export,
         commands::oxide_import::validate_oxide_file,
         commands::oxide_import::preview_oxide_import,
@@ -542,6 +599,8 @@ pub fn run() {
         commands::get_port_forward_stats,
         commands::list_saved_forwards,
         commands::set_forward_auto_start,
+        commands::set_forward_logging,
+        commands::forward_get_log,
         commands::delete_saved_forward,
         // Health check commands
         commands::get_connection_health,
@@ -552,11 +611,17 @@ pub fn run() {
         // Resource profiler commands
         commands::start_resource_profiler,
         commands::stop_resource_profiler,
+        commands::set_profiler_interval,
+        commands::get_profiler_state,
         commands::get_resource_metrics,
         commands::get_resource_history,
         // Smart port detection commands
         commands::get_detected_ports,
+        commands::profiler_get_ports,
+        commands::profiler_rescan_ports,
         commands::ignore_detected_port,
+        commands::profiler_unignore_port,
+        commands::profiler_clear_ignored,
         // IDE mode commands
         commands::ide_open_project,
         commands::ide_check_file,
@@ -596,6 +661,8 @@ pub fn run() {
         commands::ai_chat_clear_all,
         commands::ai_chat_replace_conversation_messages,
         commands::ai_chat_get_stats,
+        // State store diagnostics
+        commands::state_get_stats,
         // Plugin system commands
         commands::list_plugins,
         commands::read_plugin_file,
@@ -636,6 +703,8 @@ pub fn run() {
         commands::node_sftp_resume_transfer,
         commands::node_ide_open_project,
         commands::node_ide_exec_command,
+        commands::node_ide_exec_streamed,
+        commands::ide_exec_cancel,
         commands::node_ide_check_file,
         commands::node_ide_batch_stat,
         // Oxide-Next: node-first forwarding commands
@@ -655,14 +724,17 @@ pub fn run() {
         commands::node_agent_deploy,
         commands::node_agent_remove,
         commands::node_agent_status,
+        commands::node_agent_operation_mode,
+        commands::node_agent_usage,
         commands::node_agent_read_file,
         commands::node_agent_write_file,
+        commands::node_agent_download_file,
+        commands::node_agent_stat,
         commands::node_agent_list_tree,
         commands::node_agent_grep,
         commands::node_agent_git_status,
         commands::node_agent_watch_start,
         commands::node_agent_watch_stop,
-        commands::node_agent_start_watch_relay,
         commands::node_agent_symbol_index,
         commands::node_agent_symbol_complete,
         commands::node_agent_symbol_definitions,
@@ -709,9 +781,16 @@ pub fn run() {
         commands::ssh_set_keep_alive,
         commands::ssh_get_pool_config,
         commands::ssh_set_pool_config,
+        commands::bridge_get_config,
+        commands::bridge_set_config,
         commands::ssh_get_pool_stats,
         commands::create_terminal,
         commands::close_terminal,
+        commands::set_session_notify,
+        commands::set_paste_guard,
+        commands::confirm_paste,
+        commands::session_start_recording,
+        commands::session_stop_recording,
         commands::recreate_terminal_pty,
         // SSH host key preflight (TOFU)
         commands::ssh_preflight,
@@ -726,10 +805,13 @@ pub fn run() {
         commands::get_all_buffer_lines,
         // Search commands
         commands::search_terminal,
+        commands::search_all_sessions,
+        commands::search_export_results,
         commands::scroll_to_line,
         // Session tree commands (dynamic jump host)
         commands::get_session_tree,
         commands::get_session_tree_summary,
+        commands::search_session_tree,
         commands::add_root_node,
         commands::tree_drill_down,
         commands::expand_manual_preset,
@@ -741,6 +823,7 @@ pub fn run() {
         commands::remove_topology_edge,
         commands::exclude_topology_edge,
         commands::expand_auto_route,
+        commands::preview_auto_route,
         // Session tree node management
         commands::update_tree_node_state,
         commands::set_tree_node_connection,
@@ -752,22 +835,36 @@ pub fn run() {
         commands::clear_session_tree,
         commands::connect_tree_node,
         commands::disconnect_tree_node,
+        commands::disconnect_all_tree_nodes,
         commands::connect_manual_preset,
+        commands::reconnect_chain_segment,
+        commands::reconnect_tree_subtree,
         commands::destroy_node_sessions,
         // Config commands
         commands::config::get_connections,
         commands::config::get_recent_connections,
         commands::config::get_connections_by_group,
         commands::config::search_connections,
+        commands::config::config_filter_by_tags,
+        commands::config::config_add_tag_to_many,
+        commands::config::config_remove_tag_from_many,
         commands::config::get_groups,
         commands::config::save_connection,
         commands::config::delete_connection,
         commands::config::mark_connection_used,
         commands::config::get_connection_password,
+        commands::config::get_keychain_status,
+        commands::config::enable_keychain_file_fallback,
         commands::config::get_saved_connection_for_connect,
+        commands::config::config_check_certificate,
+        commands::config::config_validate_connection,
+        commands::config::config_test_connection,
         commands::config::list_ssh_config_hosts,
         commands::config::import_ssh_host,
         commands::config::get_ssh_config_path,
+        commands::config::config_export_connection_snippet,
+        commands::config::export_to_ssh_config,
+        commands::config::config_import_connection_snippet,
         commands::config::create_group,
         commands::config::delete_group,
         // AI API key commands
@@ -781,8 +878,11 @@ pub fn run() {
         commands::config::has_ai_provider_api_key,
         commands::config::delete_ai_provider_api_key,
         commands::config::list_ai_provider_keys,
+        commands::config::config_consolidate_credentials,
+        commands::config::rotate_keychain,
         // Oxide file export/import commands
         commands::oxide_export::export_to_oxide,
+        commands::oxide_export::append_to_oxide,
         commands::oxide_export::preflight_export,
         commands::oxide_import::validate_oxide_file,
         commands::oxide_import::preview_oxide_import,
@@ -803,6 +903,8 @@ pub fn run() {
         commands::get_port_forward_stats,
         commands::list_saved_forwards,
         commands::set_forward_auto_start,
+        commands::set_forward_logging,
+        commands::forward_get_log,
         commands::delete_saved_forward,
         // Health check commands
         commands::get_connection_health,
@@ -813,11 +915,17 @@ pub fn run() {
         // Resource profiler commands
         commands::start_resource_profiler,
         commands::stop_resource_profiler,
+        commands::set_profiler_interval,
+        commands::get_profiler_state,
         commands::get_resource_metrics,
         commands::get_resource_history,
         // Smart port detection commands
         commands::get_detected_ports,
+        commands::profiler_get_ports,
+        commands::profiler_rescan_ports,
         commands::ignore_detected_port,
+        commands::profiler_unignore_port,
+        commands::profiler_clear_ignored,
         // IDE mode commands
         commands::ide_open_project,
         commands::ide_check_file,
@@ -856,6 +964,8 @@ pub fn run() {
         commands::ai_chat_clear_all,
         commands::ai_chat_replace_conversation_messages,
         commands::ai_chat_get_stats,
+        // State store diagnostics
+        commands::state_get_stats,
         // Plugin system commands
         commands::list_plugins,
         commands::read_plugin_file,
@@ -896,6 +1006,8 @@ pub fn run() {
         commands::node_sftp_resume_transfer,
         commands::node_ide_open_project,
         commands::node_ide_exec_command,
+        commands::node_ide_exec_streamed,
+        commands::ide_exec_cancel,
         commands::node_ide_check_file,
         commands::node_ide_batch_stat,
         // Oxide-Next: node-first forwarding commands
@@ -915,14 +1027,17 @@ pub fn run() {
         commands::node_agent_deploy,
         commands::node_agent_remove,
         commands::node_agent_status,
+        commands::node_agent_operation_mode,
+        commands::node_agent_usage,
         commands::node_agent_read_file,
         commands::node_agent_write_file,
+        commands::node_agent_download_file,
+        commands::node_agent_stat,
         commands::node_agent_list_tree,
         commands::node_agent_grep,
         commands::node_agent_git_status,
         commands::node_agent_watch_start,
         commands::node_agent_watch_stop,
-        commands::node_agent_start_watch_relay,
         commands::node_agent_symbol_index,
         commands::node_agent_symbol_complete,
         commands::node_agent_symbol_definitions,
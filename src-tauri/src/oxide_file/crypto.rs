@@ -8,8 +8,8 @@ use zeroize::Zeroizing;
 
 use super::error::OxideFileError;
 use super::format::{
-    kdf_flags, EncryptedConnection, EncryptedPayload, OxideFile, OxideMetadata, NONCE_LEN,
-    SALT_LEN, TAG_LEN,
+    kdf_flags, EncryptedConnection, EncryptedPayload, KdfStrength, OxideFile, OxideMetadata,
+    NONCE_LEN, SALT_LEN, TAG_LEN,
 };
 
 /// KDF parameters for different versions
@@ -20,11 +20,11 @@ struct KdfParams {
 }
 
 impl KdfParams {
-    /// Get KDF parameters for a specific version
-    fn for_version(version: u32) -> Result<Self, OxideFileError> {
+    /// Get KDF parameters for a specific version (`strength` applies to v3 only)
+    fn for_version(version: u32, strength: KdfStrength) -> Result<Self, OxideFileError> {
         match version {
             kdf_flags::KDF_V1 | 0 => {
-                // v1 (default): 256MB, 4 iterations, parallelism=4
+                // v1: 256MB, 4 iterations, parallelism=4
                 // Also handle legacy files with flags=0
                 Ok(KdfParams {
                     memory_cost: 262144, // 256 MB
@@ -32,6 +32,19 @@ impl KdfParams {
                     parallelism: 4,
                 })
             }
+            kdf_flags::KDF_V3 => {
+                // v3: parallelism=4, cost chosen at export
+                let (memory_cost, iterations) = match strength {
+                    KdfStrength::Interactive => (65536, 2), // 64 MB
+                    KdfStrength::Moderate => (131072, 3),   // 128 MB
+                    KdfStrength::Sensitive => (262144, 4),  // 256 MB
+                };
+                Ok(KdfParams {
+                    memory_cost,
+                    iterations,
+                    parallelism: 4,
+                })
+            }
             kdf_flags::KDF_V2 => {
                 // v2 (future): 512MB, 6 iterations, parallelism=4
                 Ok(KdfParams {
@@ -47,14 +60,15 @@ impl KdfParams {
 
 /// Derive encryption key from password using Argon2id with specified KDF version
 ///
-/// Default (v1): 4 iterations, 256MB memory, parallelism=4 (~2 seconds on modern CPU)
+/// Default (v1, Sensitive): 4 iterations, 256MB memory, parallelism=4 (~2 seconds on modern CPU)
 /// Provides strong protection against GPU brute-force attacks
 pub fn derive_key(
     password: &str,
     salt: &[u8],
     kdf_version: u32,
+    strength: KdfStrength,
 ) -> Result<Zeroizing<[u8; 32]>, OxideFileError> {
-    let kdf_params = KdfParams::for_version(kdf_version)?;
+    let kdf_params = KdfParams::for_version(kdf_version, strength)?;
 
     let params = Params::new(
         kdf_params.memory_cost,
@@ -75,6 +89,8 @@ pub fn derive_key(
 }
 
 /// Encrypt payload and create .oxide file structure
+///
+/// The key is derived at `metadata.kdf_strength`.
 pub fn encrypt_oxide_file(
     payload: &EncryptedPayload,
     password: &str,
@@ -86,8 +102,9 @@ pub fn encrypt_oxide_file(
     rand::rngs::OsRng.fill_bytes(&mut salt);
     rand::rngs::OsRng.fill_bytes(&mut nonce);
 
-    // 2. Derive encryption key from password using current KDF version
-    let key = derive_key(password, &salt, kdf_flags::CURRENT_KDF)?;
+    // 2. Derive encryption key from password; the KDF version records the strength
+    let kdf_version = kdf_flags::for_strength(metadata.kdf_strength);
+    let key = derive_key(password, &salt, kdf_version, metadata.kdf_strength)?;
 
     // 3. Serialize payload with MessagePack (supports tagged enums)
     let plaintext = rmp_serde::to_vec_named(payload)?;
@@ -118,7 +135,7 @@ pub fn encrypt_oxide_file(
         nonce,
         encrypted_data: encrypted_data.to_vec(),
         tag,
-        kdf_version: kdf_flags::CURRENT_KDF,
+        kdf_version,
     })
 }
 
@@ -129,8 +146,13 @@ pub fn decrypt_oxide_file(
     oxide_file: &OxideFile,
     password: &str,
 ) -> Result<EncryptedPayload, OxideFileError> {
-    // 1. Derive key from password and salt using file's KDF version
-    let key = derive_key(password, &oxide_file.salt, oxide_file.kdf_version)?;
+    // 1. Derive key from password and salt using file's KDF version and strength
+    let key = derive_key(
+        password,
+        &oxide_file.salt,
+        oxide_file.kdf_version,
+        oxide_file.metadata.kdf_strength,
+    )?;
 
     // 2. Prepare cipher
     let cipher =
@@ -221,6 +243,7 @@ mod tests {
             description: Some("Test export".to_string()),
             num_connections: 1,
             connection_names: vec!["Test Server".to_string()],
+            kdf_strength: KdfStrength::Sensitive,
         }
     }
 
@@ -229,8 +252,8 @@ mod tests {
         let password = "TestPassword123!";
         let salt = [0u8; 32];
 
-        let key1 = derive_key(password, &salt, kdf_flags::KDF_V1).unwrap();
-        let key2 = derive_key(password, &salt, kdf_flags::KDF_V1).unwrap();
+        let key1 = derive_key(password, &salt, kdf_flags::KDF_V1, KdfStrength::Sensitive).unwrap();
+        let key2 = derive_key(password, &salt, kdf_flags::KDF_V1, KdfStrength::Sensitive).unwrap();
 
         // Same password and salt should produce same key
         assert_eq!(&*key1, &*key2);
@@ -238,8 +261,24 @@ mod tests {
         // Different salt should produce different key
         let mut different_salt = [0u8; 32];
         different_salt[0] = 1;
-        let key3 = derive_key(password, &different_salt, kdf_flags::KDF_V1).unwrap();
+        let key3 = derive_key(
+            password,
+            &different_salt,
+            kdf_flags::KDF_V1,
+            KdfStrength::Sensitive,
+        )
+        .unwrap();
         assert_ne!(&*key1, &*key3);
+
+        // Different strength should produce different key
+        let key4 =
+            derive_key(password, &salt, kdf_flags::KDF_V3, KdfStrength::Interactive).unwrap();
+        assert_ne!(&*key1, &*key4);
+
+        // v1 always uses the full cost, whatever the metadata says
+        let key5 =
+            derive_key(password, &salt, kdf_flags::KDF_V1, KdfStrength::Interactive).unwrap();
+        assert_eq!(&*key1, &*key5);
     }
 
     #[test]
@@ -287,6 +326,40 @@ mod tests {
         assert!(result.is_err()); // Should fail AEAD verification
     }
 
+    #[test]
+    fn test_interactive_strength_roundtrip() {
+        let payload = create_test_payload();
+        let mut metadata = create_test_metadata();
+        metadata.kdf_strength = KdfStrength::Interactive;
+
+        let bytes = encrypt_oxide_file(&payload, "test123!", metadata)
+            .unwrap()
+            .to_bytes()
+            .unwrap();
+
+        // Strength survives the trip through the unencrypted metadata, and
+        // the header marks the file so older readers refuse it
+        let oxide_file = OxideFile::from_bytes(&bytes).unwrap();
+        assert_eq!(oxide_file.metadata.kdf_strength, KdfStrength::Interactive);
+        assert_eq!(oxide_file.kdf_version, kdf_flags::KDF_V3);
+
+        let decrypted = decrypt_oxide_file(&oxide_file, "test123!").unwrap();
+        assert_eq!(decrypted.connections[0].name, "Test Server");
+    }
+
+    #[test]
+    fn test_tampered_strength_rejected() {
+        let payload = create_test_payload();
+        let mut metadata = create_test_metadata();
+        metadata.kdf_strength = KdfStrength::Interactive;
+
+        let mut oxide_file = encrypt_oxide_file(&payload, "test123!", metadata).unwrap();
+        oxide_file.metadata.kdf_strength = KdfStrength::Moderate;
+
+        let result = decrypt_oxide_file(&oxide_file, "test123!");
+        assert!(matches!(result, Err(OxideFileError::DecryptionFailed)));
+    }
+
     #[test]
     fn test_checksum_computation() {
         let conn = create_test_connection();
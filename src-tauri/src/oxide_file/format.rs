@@ -27,11 +27,42 @@ pub mod kdf_flags {
     /// KDF v2: Reserved for future use (e.g., higher memory cost)
     pub const KDF_V2: u32 = 0x0002;
 
+    /// KDF v3: Argon2id at the cost named by the metadata's `kdf_strength`,
+    /// parallelism=4. Readers that predate it refuse the file with
+    /// `UnsupportedKdfVersion` instead of deriving a v1 key.
+    pub const KDF_V3: u32 = 0x0003;
+
     /// Mask to extract KDF version from flags
     pub const KDF_VERSION_MASK: u32 = 0x00FF;
 
     /// Current KDF version used for new files
     pub const CURRENT_KDF: u32 = KDF_V1;
+
+    /// KDF version for a new file at `strength`: the default cost stays on
+    /// v1 so older readers can still open it
+    pub fn for_strength(strength: super::KdfStrength) -> u32 {
+        match strength {
+            super::KdfStrength::Sensitive => CURRENT_KDF,
+            super::KdfStrength::Interactive | super::KdfStrength::Moderate => KDF_V3,
+        }
+    }
+}
+
+/// Argon2id cost level chosen at export time
+///
+/// Stored in the unencrypted metadata so import can derive the same key;
+/// files below the default cost are marked KDF v3 in the header flags.
+/// Editing it only yields a different key, which the AEAD tag rejects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum KdfStrength {
+    /// 64MB memory, 2 iterations: fast on low-end machines
+    Interactive,
+    /// 128MB memory, 3 iterations
+    Moderate,
+    /// 256MB memory, 4 iterations (files without the field use this)
+    #[default]
+    Sensitive,
 }
 
 /// File header structure (21 bytes fixed)
@@ -46,10 +77,23 @@ pub struct FileHeader {
 
 impl FileHeader {
     pub fn new(metadata_length: u32, encrypted_data_length: u32) -> Self {
+        Self::with_kdf_version(
+            metadata_length,
+            encrypted_data_length,
+            kdf_flags::CURRENT_KDF,
+        )
+    }
+
+    /// Header whose flags select `kdf_version`
+    pub fn with_kdf_version(
+        metadata_length: u32,
+        encrypted_data_length: u32,
+        kdf_version: u32,
+    ) -> Self {
         Self {
             magic: *MAGIC,
             version: VERSION,
-            flags: kdf_flags::CURRENT_KDF, // Set current KDF version in flags
+            flags: kdf_version & kdf_flags::KDF_VERSION_MASK,
             metadata_length,
             encrypted_data_length,
         }
@@ -111,6 +155,9 @@ pub struct OxideMetadata {
     pub description: Option<String>,
     pub num_connections: usize,
     pub connection_names: Vec<String>,
+    /// Argon2id cost used for this file
+    #[serde(default)]
+    pub kdf_strength: KdfStrength,
 }
 
 /// Encrypted payload structure
@@ -197,7 +244,7 @@ impl OxideFile {
         let encrypted_len = self.encrypted_data.len() as u32;
 
         // Build header
-        let header = FileHeader::new(metadata_len, encrypted_len);
+        let header = FileHeader::with_kdf_version(metadata_len, encrypted_len, self.kdf_version);
         let header_bytes = header.to_bytes();
 
         // Combine all parts
@@ -2,7 +2,7 @@
 //!
 //! Provides encrypted configuration file export/import with:
 //! - ChaCha20-Poly1305 AEAD encryption
-//! - Argon2id key derivation (high strength)
+//! - Argon2id key derivation (high strength by default, selectable per export)
 //! - Binary file format with unencrypted metadata
 //! - Git-friendly and offline-decryptable
 
@@ -14,6 +14,6 @@ pub mod format;
 pub use crypto::{compute_checksum, decrypt_oxide_file, encrypt_oxide_file};
 pub use error::OxideFileError;
 pub use format::{
-    EncryptedAuth, EncryptedConnection, EncryptedPayload, EncryptedProxyHop, OxideFile,
-    OxideMetadata,
+    EncryptedAuth, EncryptedConnection, EncryptedPayload, EncryptedProxyHop, KdfStrength,
+    OxideFile, OxideMetadata,
 };
@@ -145,7 +145,7 @@ pub fn default_key_paths() -> Vec<PathBuf> {
 }
 
 /// Expand ~ to home directory
-fn expand_tilde(path: &Path) -> PathBuf {
+pub(crate) fn expand_tilde(path: &Path) -> PathBuf {
     let path_str = path.to_string_lossy();
 
     if let Some(stripped) = path_str.strip_prefix("~/") {
@@ -9,6 +9,8 @@
 //! - Two-phase detection: Phase A identifies Windows vs Unix; Phase B collects details
 //! - Handles "disguised" Windows environments (Git Bash/MinGW, MSYS, Cygwin, WSL)
 //! - Total timeout: 8s. Failure → `os_type = "Unknown"`, logged but non-fatal
+//! - `os_type` is the display string; platform-specific code dispatches on the
+//!   typed [`OsFamily`] stored alongside it
 //!
 //! # Invariants
 //! - E1: Detection channel is closed after use — no lingering MaxSessions consumption
@@ -53,6 +55,51 @@ const PHASE_B_WINDOWS_CMD: &str =
 // Types
 // ═══════════════════════════════════════════════════════════════════════════
 
+/// OS family used to pick platform-specific commands and parsers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OsFamily {
+    Linux,
+    MacOs,
+    /// FreeBSD, OpenBSD, NetBSD, DragonFly BSD
+    Bsd,
+    /// Native Windows (PowerShell / cmd)
+    Windows,
+    /// POSIX layer on Windows: Git Bash / MinGW, MSYS2, Cygwin
+    WindowsPosix,
+    #[default]
+    Unknown,
+}
+
+impl OsFamily {
+    /// Classify a `uname -s` value.
+    pub fn from_uname(sysname: &str) -> Self {
+        let upper = sysname.trim().to_uppercase();
+
+        if upper.starts_with("MINGW") || upper.starts_with("MSYS") || upper.starts_with("CYGWIN") {
+            return Self::WindowsPosix;
+        }
+
+        match upper.as_str() {
+            "LINUX" => Self::Linux,
+            "DARWIN" => Self::MacOs,
+            "FREEBSD" | "OPENBSD" | "NETBSD" | "DRAGONFLY" => Self::Bsd,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// Typed summary of the remote OS.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OsInfo {
+    pub family: OsFamily,
+    /// Distribution or OS version (e.g., "Ubuntu 22.04.3 LTS")
+    pub distro: Option<String>,
+    /// Kernel release (uname -r)
+    pub kernel: Option<String>,
+}
+
 /// Detected remote environment information.
 ///
 /// `os_type` special values for Windows variants:
@@ -66,6 +113,10 @@ pub struct RemoteEnvInfo {
     /// OS type: "Linux", "macOS", "Windows", "FreeBSD", "Windows_MinGW", "Unknown", etc.
     pub os_type: String,
 
+    /// Typed OS family, for dispatch
+    #[serde(default)]
+    pub os_family: OsFamily,
+
     /// Human-readable OS version (e.g., "Ubuntu 22.04.3 LTS", "Microsoft Windows NT 10.0.22631")
     pub os_version: Option<String>,
 
@@ -87,6 +138,7 @@ impl RemoteEnvInfo {
     pub fn unknown() -> Self {
         Self {
             os_type: "Unknown".to_string(),
+            os_family: OsFamily::Unknown,
             os_version: None,
             kernel: None,
             arch: None,
@@ -94,6 +146,15 @@ impl RemoteEnvInfo {
             detected_at: Utc::now().timestamp(),
         }
     }
+
+    /// Typed OS summary.
+    pub fn os_info(&self) -> OsInfo {
+        OsInfo {
+            family: self.os_family,
+            distro: self.os_version.clone(),
+            kernel: self.kernel.clone(),
+        }
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -184,13 +245,17 @@ async fn run_detection(channel: &mut Channel<Msg>, connection_id: &str) -> Remot
             Err(e) => {
                 warn!("[EnvDetector] Phase B failed for {}: {}", connection_id, e);
                 // We at least know the platform from Phase A
-                let os_type = if is_windows {
-                    "Windows".to_string()
+                let (os_type, os_family) = if is_windows {
+                    ("Windows".to_string(), OsFamily::Windows)
                 } else {
-                    classify_unix_os(&raw_platform)
+                    (
+                        classify_unix_os(&raw_platform),
+                        OsFamily::from_uname(&raw_platform),
+                    )
                 };
                 return RemoteEnvInfo {
                     os_type,
+                    os_family,
                     os_version: None,
                     kernel: None,
                     arch: None,
@@ -331,6 +396,7 @@ fn classify_unix_os(uname_s: &str) -> String {
 /// Parse Phase B output for Unix hosts.
 fn parse_unix_env(output: &str, raw_platform: &str) -> RemoteEnvInfo {
     let os_type = classify_unix_os(raw_platform);
+    let os_family = OsFamily::from_uname(raw_platform);
 
     let env_val = extract_section(output, "===ENV===", "===ARCH===")
         .map(|s| s.trim().to_string())
@@ -362,6 +428,7 @@ fn parse_unix_env(output: &str, raw_platform: &str) -> RemoteEnvInfo {
 
     RemoteEnvInfo {
         os_type,
+        os_family,
         os_version,
         kernel,
         arch,
@@ -391,6 +458,7 @@ fn parse_windows_env(output: &str) -> RemoteEnvInfo {
 
     RemoteEnvInfo {
         os_type: "Windows".to_string(),
+        os_family: OsFamily::Windows,
         os_version,
         kernel: None,
         arch,
@@ -460,6 +528,34 @@ mod tests {
         assert_eq!(classify_unix_os("SunOS"), "SunOS");
     }
 
+    #[test]
+    fn test_os_family_from_uname() {
+        let cases = [
+            ("Linux", OsFamily::Linux),
+            ("linux\n", OsFamily::Linux),
+            ("Darwin", OsFamily::MacOs),
+            ("FreeBSD", OsFamily::Bsd),
+            ("OpenBSD", OsFamily::Bsd),
+            ("NetBSD", OsFamily::Bsd),
+            ("DragonFly", OsFamily::Bsd),
+            ("MINGW64_NT-10.0-19045", OsFamily::WindowsPosix),
+            ("MINGW32_NT-6.1", OsFamily::WindowsPosix),
+            ("MSYS_NT-10.0-22631", OsFamily::WindowsPosix),
+            ("CYGWIN_NT-10.0-19045", OsFamily::WindowsPosix),
+            ("SunOS", OsFamily::Unknown),
+            ("unknown", OsFamily::Unknown),
+            ("", OsFamily::Unknown),
+        ];
+        for (uname, family) in cases {
+            assert_eq!(
+                OsFamily::from_uname(uname),
+                family,
+                "uname -s = {:?}",
+                uname
+            );
+        }
+    }
+
     #[test]
     fn test_parse_unix_env() {
         let output = r#"===ENV===
@@ -477,6 +573,14 @@ ID=ubuntu
 "#;
         let result = parse_unix_env(output, "Linux");
         assert_eq!(result.os_type, "Linux");
+        assert_eq!(
+            result.os_info(),
+            OsInfo {
+                family: OsFamily::Linux,
+                distro: Some("Ubuntu 22.04.3 LTS".to_string()),
+                kernel: Some("5.15.0-91-generic".to_string()),
+            }
+        );
         assert_eq!(result.os_version.as_deref(), Some("Ubuntu 22.04.3 LTS"));
         assert_eq!(result.arch.as_deref(), Some("x86_64"));
         assert_eq!(result.kernel.as_deref(), Some("5.15.0-91-generic"));
@@ -498,6 +602,7 @@ arm64
 "#;
         let result = parse_unix_env(output, "Darwin");
         assert_eq!(result.os_type, "macOS");
+        assert_eq!(result.os_family, OsFamily::MacOs);
         assert_eq!(result.arch.as_deref(), Some("arm64"));
         assert_eq!(result.shell.as_deref(), Some("/bin/zsh"));
         // No /etc/os-release on macOS, falls back to uname -s value
@@ -519,6 +624,7 @@ x86_64
 "#;
         let result = parse_unix_env(output, "MINGW64_NT-10.0-19045");
         assert_eq!(result.os_type, "Windows_MinGW");
+        assert_eq!(result.os_family, OsFamily::WindowsPosix);
         assert_eq!(result.arch.as_deref(), Some("x86_64"));
     }
 
@@ -534,6 +640,7 @@ PowerShell 7.4.1
 "#;
         let result = parse_windows_env(output);
         assert_eq!(result.os_type, "Windows");
+        assert_eq!(result.os_family, OsFamily::Windows);
         assert_eq!(
             result.os_version.as_deref(),
             Some("Microsoft Windows NT 10.0.22631.0")
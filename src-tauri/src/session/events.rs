@@ -6,10 +6,22 @@
 
 use serde::{Deserialize, Serialize};
 
+use super::notify::NotifyTrigger;
+use super::tree::NodeState;
+use crate::bridge::HeldPaste;
+
 /// Event names as constants
 pub mod event_names {
     /// Network status changed
     pub const NETWORK_STATUS_CHANGED: &str = "network:status_changed";
+    /// Terminal session closed after exceeding its idle timeout
+    pub const SESSION_IDLE_DISCONNECT: &str = "session:idle_disconnect";
+    /// Session tree node state changed outside of a command (e.g. parent tunnel died)
+    pub const TREE_NODE_STATE: &str = "tree:node_state";
+    /// Prefix of the per-session activity notification event (`session:notify:{id}`)
+    pub const SESSION_NOTIFY_PREFIX: &str = "session:notify:";
+    /// A multi-line paste is held until the user confirms it
+    pub const SESSION_PASTE_CONFIRM: &str = "session:paste_confirm";
 }
 
 /// Network status payload
@@ -17,3 +29,40 @@ pub mod event_names {
 pub struct NetworkStatusPayload {
     pub online: bool,
 }
+
+/// Idle-timeout disconnect payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IdleDisconnectPayload {
+    pub session_id: String,
+    /// Observed time without PTY I/O
+    pub idle_secs: u64,
+    /// Configured idle timeout
+    pub timeout_secs: u64,
+}
+
+/// Session activity notification payload
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionNotifyPayload {
+    pub session_id: String,
+    #[serde(flatten)]
+    pub trigger: NotifyTrigger,
+}
+
+/// Held paste payload
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasteConfirmPayload {
+    pub session_id: String,
+    #[serde(flatten)]
+    pub paste: HeldPaste,
+}
+
+/// Session tree node state payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeNodeStatePayload {
+    pub node_id: String,
+    pub state: NodeState,
+}
@@ -77,6 +77,12 @@ pub struct ResourceMetrics {
     pub memory_total: Option<u64>,
     /// Memory usage percentage (0-100)
     pub memory_percent: Option<f64>,
+    /// Bytes used on the root filesystem
+    pub disk_used: Option<u64>,
+    /// Size of the root filesystem in bytes
+    pub disk_total: Option<u64>,
+    /// Root filesystem usage percentage (0-100)
+    pub disk_percent: Option<f64>,
     /// 1-minute load average
     pub load_avg_1: Option<f64>,
     /// 5-minute load average
@@ -89,6 +95,10 @@ pub struct ResourceMetrics {
     pub net_rx_bytes_per_sec: Option<u64>,
     /// Network TX bytes per second
     pub net_tx_bytes_per_sec: Option<u64>,
+    /// Disk read bytes per second (all physical devices)
+    pub disk_read_bytes_per_sec: Option<u64>,
+    /// Disk write bytes per second (all physical devices)
+    pub disk_write_bytes_per_sec: Option<u64>,
     /// SSH RTT in milliseconds (from HealthTracker)
     pub ssh_rtt_ms: Option<u64>,
     /// Source quality of the metrics
@@ -0,0 +1,212 @@
+//! Session Idle Tracking
+//!
+//! Records the time of the last PTY data in either direction and decides when
+//! a session has been idle long enough to be disconnected automatically.
+//! Heartbeats, pings and resizes do not count as activity.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Default poll interval of the idle watchdog
+pub const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Monotonic time source (injectable for tests)
+pub trait IdleClock: Send + Sync {
+    /// Time elapsed since an arbitrary fixed origin
+    fn now(&self) -> Duration;
+}
+
+/// Real clock backed by `Instant`
+struct MonotonicClock {
+    origin: Instant,
+}
+
+impl IdleClock for MonotonicClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+}
+
+/// Per-session PTY activity tracker
+pub struct IdleTracker {
+    clock: Arc<dyn IdleClock>,
+    /// Clock reading (ms) of the last Data frame
+    last_activity_ms: AtomicU64,
+}
+
+impl IdleTracker {
+    /// Create a tracker using the system monotonic clock
+    pub fn new() -> Self {
+        Self::with_clock(Arc::new(MonotonicClock {
+            origin: Instant::now(),
+        }))
+    }
+
+    /// Create a tracker with a custom clock
+    pub fn with_clock(clock: Arc<dyn IdleClock>) -> Self {
+        let now = clock.now().as_millis() as u64;
+        Self {
+            clock,
+            last_activity_ms: AtomicU64::new(now),
+        }
+    }
+
+    /// Record PTY activity (input or output)
+    pub fn touch(&self) {
+        let now = self.clock.now().as_millis() as u64;
+        self.last_activity_ms.fetch_max(now, Ordering::Relaxed);
+    }
+
+    /// Time since the last recorded activity
+    pub fn idle_for(&self) -> Duration {
+        let now = self.clock.now().as_millis() as u64;
+        let last = self.last_activity_ms.load(Ordering::Relaxed);
+        Duration::from_millis(now.saturating_sub(last))
+    }
+
+    /// Whether the idle window has been exceeded (`timeout` of zero disables)
+    pub fn is_idle(&self, timeout: Duration) -> bool {
+        !timeout.is_zero() && self.idle_for() >= timeout
+    }
+}
+
+impl Default for IdleTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Wait until the session has been idle for `timeout`.
+///
+/// Returns the observed idle duration when the window is exceeded, or `None`
+/// if the timeout is disabled or `is_alive` reports the session has ended.
+pub async fn wait_for_idle(
+    tracker: Arc<IdleTracker>,
+    timeout: Duration,
+    poll_interval: Duration,
+    is_alive: impl Fn() -> bool,
+) -> Option<Duration> {
+    if timeout.is_zero() {
+        return None;
+    }
+
+    let mut interval = tokio::time::interval(poll_interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        interval.tick().await;
+
+        if !is_alive() {
+            return None;
+        }
+        if tracker.is_idle(timeout) {
+            return Some(tracker.idle_for());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+
+    /// Clock that only moves when told to
+    #[derive(Default)]
+    struct ManualClock {
+        now_ms: AtomicU64,
+    }
+
+    impl ManualClock {
+        fn advance(&self, by: Duration) {
+            self.now_ms
+                .fetch_add(by.as_millis() as u64, Ordering::SeqCst);
+        }
+    }
+
+    impl IdleClock for ManualClock {
+        fn now(&self) -> Duration {
+            Duration::from_millis(self.now_ms.load(Ordering::SeqCst))
+        }
+    }
+
+    fn tracker_with_clock() -> (Arc<ManualClock>, Arc<IdleTracker>) {
+        let clock = Arc::new(ManualClock::default());
+        let tracker = Arc::new(IdleTracker::with_clock(clock.clone()));
+        (clock, tracker)
+    }
+
+    #[test]
+    fn test_touch_resets_idle_time() {
+        let (clock, tracker) = tracker_with_clock();
+
+        clock.advance(Duration::from_secs(45));
+        assert_eq!(tracker.idle_for(), Duration::from_secs(45));
+
+        tracker.touch();
+        assert_eq!(tracker.idle_for(), Duration::ZERO);
+
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(tracker.idle_for(), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_zero_timeout_disables() {
+        let (clock, tracker) = tracker_with_clock();
+        clock.advance(Duration::from_secs(86_400));
+        assert!(!tracker.is_idle(Duration::ZERO));
+    }
+
+    #[tokio::test]
+    async fn test_fires_only_after_idle_window() {
+        let (clock, tracker) = tracker_with_clock();
+        let timeout = Duration::from_secs(60);
+
+        let watchdog = tokio::spawn(wait_for_idle(
+            tracker.clone(),
+            timeout,
+            Duration::from_millis(5),
+            || true,
+        ));
+
+        // Just under the window: keeps waiting
+        clock.advance(Duration::from_secs(59));
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(!watchdog.is_finished());
+
+        // Activity resets the window
+        tracker.touch();
+        clock.advance(Duration::from_secs(30));
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert!(!watchdog.is_finished());
+
+        // Full window without activity: fires
+        clock.advance(Duration::from_secs(30));
+        let idle = tokio::time::timeout(Duration::from_secs(2), watchdog)
+            .await
+            .expect("watchdog did not fire")
+            .unwrap();
+        assert_eq!(idle, Some(Duration::from_secs(60)));
+    }
+
+    #[tokio::test]
+    async fn test_stops_when_session_ends() {
+        let (_clock, tracker) = tracker_with_clock();
+        let alive = Arc::new(AtomicBool::new(true));
+        let alive_check = alive.clone();
+
+        let watchdog = tokio::spawn(wait_for_idle(
+            tracker,
+            Duration::from_secs(60),
+            Duration::from_millis(5),
+            move || alive_check.load(Ordering::SeqCst),
+        ));
+
+        alive.store(false, Ordering::SeqCst);
+        let result = tokio::time::timeout(Duration::from_secs(2), watchdog)
+            .await
+            .expect("watchdog did not stop")
+            .unwrap();
+        assert_eq!(result, None);
+    }
+}
@@ -6,6 +6,9 @@
 //! - Thread-safe session access via DashMap
 //! - Silent reconnection with exponential backoff
 //! - Connection health monitoring
+//! - Idle-timeout tracking for automatic disconnect
+//! - Activity notifications (bell, output after idle, pattern match)
+//! - asciinema-compatible session recording
 //! - Tauri event emission for frontend state sync
 //! - **Session Tree** for dynamic jump host support (三种跳板机模式)
 //! - **Topology Graph** for auto-route calculation (静态自动路由)
@@ -15,12 +18,17 @@ pub mod auto_reconnect;
 pub mod env_detector;
 pub mod events;
 pub mod health;
+pub mod idle;
+pub mod notify;
 pub mod parser;
 pub mod profiler;
 mod reconnect;
+pub mod recording;
 mod registry;
 pub mod scroll_buffer;
 pub mod search;
+pub mod search_index;
+pub mod search_report;
 mod state;
 pub mod topology_graph;
 pub mod tree;
@@ -28,23 +36,35 @@ pub mod types;
 
 pub use auth::{load_private_key, KeyAuth};
 pub use auto_reconnect::AutoReconnectService;
-pub use env_detector::RemoteEnvInfo;
-pub use events::{event_names, NetworkStatusPayload};
+pub use env_detector::{OsFamily, OsInfo, RemoteEnvInfo};
+pub use events::{
+    event_names, IdleDisconnectPayload, NetworkStatusPayload, PasteConfirmPayload,
+    SessionNotifyPayload, TreeNodeStatePayload,
+};
 pub use health::{
     HealthMetrics, HealthStatus, HealthThresholds, HealthTracker, MetricsSource, QuickHealthCheck,
     ResourceMetrics,
 };
+pub use idle::IdleTracker;
+pub use notify::{ActivityNotifier, NotifyOn, NotifyTrigger};
 pub use parser::{parse_terminal_output, parse_terminal_output_simple, BatchParser};
-pub use profiler::{ProfilerState, ResourceProfiler};
+pub use profiler::{
+    HistoryResolution, IgnoredPortsStore, PortProtocol, ProfilerState, ResourceProfiler,
+};
 pub use reconnect::{
     ReconnectConfig, ReconnectError, ReconnectEvent, ReconnectState, SessionReconnector,
 };
+pub use recording::{resolve_cast_path, RecordingSummary, SessionRecorder, DEFAULT_MAX_CAST_BYTES};
 pub use registry::{RegistryError, SessionRegistry};
-pub use scroll_buffer::{BufferStats, ScrollBuffer, SerializedBuffer, TerminalLine};
-pub use search::{search_lines, SearchMatch, SearchOptions, SearchResult};
+pub use scroll_buffer::{
+    BufferStats, SavedBuffer, ScrollBuffer, ScrollBufferError, SerializedBuffer, TerminalLine,
+};
+pub use search::{search_buffers, search_lines, SearchMatch, SearchOptions, SearchResult};
+pub use search_report::{render_search_report, ReportFormat};
 pub use state::{SessionState, SessionStateMachine};
 pub use topology_graph::{
-    NetworkTopology, RouteResult, TopologyEdge, TopologyNodeConfig, TopologyNodeInfo,
+    NetworkTopology, RouteHopDetail, RoutePreview, RouteResult, TopologyEdge, TopologyNodeConfig,
+    TopologyNodeInfo,
 };
 pub use tree::{FlatNode, NodeConnection, NodeOrigin, NodeState, SessionTree, TreeError};
 pub use types::{AuthMethod, SessionConfig, SessionEntry, SessionInfo, SessionStats};
@@ -0,0 +1,313 @@
+//! Session Activity Notifications
+//!
+//! Watches a session's PTY output for the things a user wants to hear about
+//! while looking at another tab: a terminal bell, output resuming after a
+//! quiet period, or a pattern appearing in the stream ("tell me when my build
+//! is done").
+//!
+//! Output runs through a persistent VTE parser, so bells that terminate OSC
+//! sequences (shell prompts setting the window title) are not counted and the
+//! pattern is matched against text with escape codes stripped, even when a
+//! sequence or line is split across chunks.
+
+use std::time::{Duration, Instant};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use vte::{Parser, Perform};
+
+use super::search::{build_search_regex, SearchOptions};
+
+/// Default quiet period before output counts as resumed
+pub const DEFAULT_NOTIFY_IDLE_SECS: u64 = 30;
+
+/// Longest partial line kept for pattern matching (bytes)
+const MAX_PENDING_LINE: usize = 4096;
+
+/// Which output events raise a notification
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotifyOn {
+    /// The PTY emitted a BEL
+    #[serde(default)]
+    pub bell: bool,
+    /// Output arrived after at least `idle_secs` without any
+    #[serde(default)]
+    pub idle_then_output: bool,
+    /// Regex matched against each output line (case-sensitive)
+    #[serde(default)]
+    pub pattern: Option<String>,
+    /// Quiet period for `idle_then_output`
+    #[serde(default = "default_idle_secs")]
+    pub idle_secs: u64,
+}
+
+fn default_idle_secs() -> u64 {
+    DEFAULT_NOTIFY_IDLE_SECS
+}
+
+impl Default for NotifyOn {
+    fn default() -> Self {
+        Self {
+            bell: false,
+            idle_then_output: false,
+            pattern: None,
+            idle_secs: DEFAULT_NOTIFY_IDLE_SECS,
+        }
+    }
+}
+
+impl NotifyOn {
+    /// Whether any trigger is switched on
+    pub fn is_enabled(&self) -> bool {
+        self.bell || self.idle_then_output || self.pattern.is_some()
+    }
+}
+
+/// Why a notification was raised
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "trigger", rename_all = "camelCase")]
+pub enum NotifyTrigger {
+    Bell,
+    #[serde(rename_all = "camelCase")]
+    OutputAfterIdle {
+        idle_secs: u64,
+    },
+    /// `line` is the (escape-stripped) line the pattern matched
+    Pattern {
+        line: String,
+    },
+}
+
+/// VTE performer collecting bells and plain-text lines
+#[derive(Default)]
+struct Scanner {
+    bells: usize,
+    /// Line being built
+    line: String,
+    /// The pattern already fired on `line` while it was incomplete
+    line_reported: bool,
+    /// Lines completed during the current chunk
+    lines: Vec<String>,
+}
+
+impl Scanner {
+    fn end_line(&mut self) {
+        let line = std::mem::take(&mut self.line);
+        if !std::mem::take(&mut self.line_reported) && !line.is_empty() {
+            self.lines.push(line);
+        }
+    }
+}
+
+impl Perform for Scanner {
+    fn print(&mut self, c: char) {
+        self.line.push(c);
+        if self.line.len() >= MAX_PENDING_LINE {
+            self.end_line();
+        }
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            0x07 => self.bells += 1,
+            // Progress output redraws with \r; treat each redraw as a line
+            b'\n' | b'\r' => self.end_line(),
+            b'\t' => self.line.push(' '),
+            _ => {}
+        }
+    }
+}
+
+/// Per-session output watcher
+pub struct ActivityNotifier {
+    config: NotifyOn,
+    pattern: Option<Regex>,
+    parser: Parser,
+    scanner: Scanner,
+    last_output: Option<Instant>,
+}
+
+impl ActivityNotifier {
+    /// Create a notifier; fails if `config.pattern` is not a valid regex
+    pub fn new(config: NotifyOn) -> Result<Self, regex::Error> {
+        let pattern = compile_pattern(&config)?;
+        Ok(Self {
+            config,
+            pattern,
+            parser: Parser::new(),
+            scanner: Scanner::default(),
+            last_output: None,
+        })
+    }
+
+    /// Current configuration
+    pub fn config(&self) -> &NotifyOn {
+        &self.config
+    }
+
+    /// Replace the configuration, keeping stream state
+    pub fn set_config(&mut self, config: NotifyOn) -> Result<(), regex::Error> {
+        self.pattern = compile_pattern(&config)?;
+        self.config = config;
+        Ok(())
+    }
+
+    /// Process one chunk of PTY output received at `now`
+    ///
+    /// Returns at most one trigger of each kind per chunk, so a burst of
+    /// bells or matching lines raises a single notification.
+    pub fn feed(&mut self, data: &[u8], now: Instant) -> Vec<NotifyTrigger> {
+        let mut triggers = Vec::new();
+
+        let previous = self.last_output.replace(now);
+        if !self.config.is_enabled() {
+            return triggers;
+        }
+
+        if self.config.idle_then_output {
+            if let Some(previous) = previous {
+                let idle = now.saturating_duration_since(previous);
+                if idle >= Duration::from_secs(self.config.idle_secs) {
+                    triggers.push(NotifyTrigger::OutputAfterIdle {
+                        idle_secs: idle.as_secs(),
+                    });
+                }
+            }
+        }
+
+        self.parser.advance(&mut self.scanner, data);
+
+        let bells = std::mem::take(&mut self.scanner.bells);
+        if self.config.bell && bells > 0 {
+            triggers.push(NotifyTrigger::Bell);
+        }
+
+        let lines = std::mem::take(&mut self.scanner.lines);
+        if let Some(pattern) = &self.pattern {
+            let matched = lines.into_iter().find(|line| pattern.is_match(line));
+            if let Some(line) = matched {
+                triggers.push(NotifyTrigger::Pattern { line });
+            } else if !self.scanner.line_reported && pattern.is_match(&self.scanner.line) {
+                // Prompts and "done" messages often arrive without a newline
+                self.scanner.line_reported = true;
+                triggers.push(NotifyTrigger::Pattern {
+                    line: self.scanner.line.clone(),
+                });
+            }
+        }
+
+        triggers
+    }
+}
+
+impl Default for ActivityNotifier {
+    fn default() -> Self {
+        Self {
+            config: NotifyOn::default(),
+            pattern: None,
+            parser: Parser::new(),
+            scanner: Scanner::default(),
+            last_output: None,
+        }
+    }
+}
+
+/// Compile the configured pattern with the buffer search rules (regex,
+/// case-sensitive); an empty pattern is treated as none
+fn compile_pattern(config: &NotifyOn) -> Result<Option<Regex>, regex::Error> {
+    let Some(query) = config.pattern.as_deref().filter(|p| !p.is_empty()) else {
+        return Ok(None);
+    };
+    build_search_regex(&SearchOptions {
+        query: query.to_string(),
+        case_sensitive: true,
+        regex: true,
+        whole_word: false,
+        max_matches: 0,
+        context_before: 0,
+        context_after: 0,
+    })
+    .map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notifier(config: NotifyOn) -> ActivityNotifier {
+        ActivityNotifier::new(config).unwrap()
+    }
+
+    #[test]
+    fn test_bell_trigger_ignores_osc_terminator() {
+        let mut n = notifier(NotifyOn {
+            bell: true,
+            ..Default::default()
+        });
+        let now = Instant::now();
+
+        // Title update terminated by BEL, split across chunks
+        assert!(n.feed(b"\x1b]0;user@host: ~", now).is_empty());
+        assert!(n.feed(b"\x07$ ", now).is_empty());
+
+        assert_eq!(n.feed(b"make\r\n\x07", now), vec![NotifyTrigger::Bell]);
+        assert_eq!(n.feed(b"\x07\x07", now), vec![NotifyTrigger::Bell]);
+    }
+
+    #[test]
+    fn test_pattern_trigger_across_chunks() {
+        let mut n = notifier(NotifyOn {
+            pattern: Some(r"Build (succeeded|failed)".to_string()),
+            ..Default::default()
+        });
+        let now = Instant::now();
+
+        assert!(n.feed(b"compiling...\r\n\x1b[32mBuild suc", now).is_empty());
+        assert_eq!(
+            n.feed(b"ceeded\x1b[0m in 12s", now),
+            vec![NotifyTrigger::Pattern {
+                line: "Build succeeded in 12s".to_string()
+            }]
+        );
+        // Completing the already-reported line does not fire again
+        assert!(n.feed(b"\r\n", now).is_empty());
+
+        assert_eq!(
+            n.feed(b"Build failed\r\nBuild failed\r\n", now),
+            vec![NotifyTrigger::Pattern {
+                line: "Build failed".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_output_after_idle() {
+        let mut n = notifier(NotifyOn {
+            idle_then_output: true,
+            idle_secs: 10,
+            ..Default::default()
+        });
+        let start = Instant::now();
+
+        assert!(n.feed(b"first", start).is_empty());
+        assert!(n.feed(b"busy", start + Duration::from_secs(5)).is_empty());
+        assert_eq!(
+            n.feed(b"done", start + Duration::from_secs(20)),
+            vec![NotifyTrigger::OutputAfterIdle { idle_secs: 15 }]
+        );
+    }
+
+    #[test]
+    fn test_invalid_pattern_rejected() {
+        let config = NotifyOn {
+            pattern: Some("(".to_string()),
+            ..Default::default()
+        };
+        assert!(ActivityNotifier::new(config.clone()).is_err());
+
+        let mut n = ActivityNotifier::default();
+        assert!(n.set_config(config).is_err());
+        assert!(!n.config().is_enabled());
+    }
+}
@@ -12,15 +12,25 @@
 //! - Opens ONE shell channel at startup, reuses it for all sampling cycles
 //! - Collects `/proc/stat`, `/proc/meminfo`, `/proc/loadavg`, `/proc/net/dev` via stdin commands
 //! - CPU% and network rates require delta between two samples (first sample returns None)
+//! - The sampling interval can be changed at runtime via `set_interval()`
 //! - Non-Linux hosts gracefully degrade to `MetricsSource::RttOnly`
-//! - Port detection commands are platform-dispatched based on `os_type`
+//! - Port detection commands are platform-dispatched on the detected `OsFamily`
+//! - Port detection is TCP-only unless UDP scanning is requested; ports are
+//!   diffed by `(port, protocol)` so a TCP and a UDP socket on one port stay apart
+//! - Ignored ports survive restarts when the profiler is given an
+//!   `IgnoredPortsStore`; changes are written through to the state database
 //!
 //! # Invariants
 //! - P1: Profiler does not hold strong references to the connection
 //! - P2: SSH disconnect → profiler auto-stops via `disconnect_rx`
 //! - P3: Only 1 shell channel held for the entire profiler lifetime
-//! - P5: First sample returns None for CPU/network (no delta baseline)
+//! - P5: First sample returns None for CPU/network (no delta baseline); the same
+//!   holds for the first sample after an interval change
 //! - P6: First port scan is silent (establishes baseline, no event emitted)
+//! - P7: At most one `profiler:update` and one `port-detected` event per connection per
+//!   emit interval; updates inside the window are coalesced and the latest value wins
+//! - P8: Failed samples back off exponentially (capped at `MAX_BACKOFF`); the first
+//!   success restores the regular interval and leaves `Degraded`
 
 use std::collections::{HashSet, VecDeque};
 use std::sync::{Arc, RwLock};
@@ -30,16 +40,24 @@ use russh::client::Msg;
 use russh::{Channel, ChannelMsg};
 use serde::{Deserialize, Serialize};
 use tauri::Emitter;
-use tokio::sync::broadcast;
-use tokio::time::{timeout, Duration};
+use tokio::sync::{broadcast, mpsc, oneshot, watch};
+use tokio::time::{timeout, Duration, Instant};
 use tracing::{debug, trace, warn};
 
+use crate::session::env_detector::OsFamily;
 use crate::session::health::{MetricsSource, ResourceMetrics};
 use crate::ssh::HandleController;
+use crate::state::StateStore;
 
-/// Maximum number of history points kept (ring buffer)
+/// Maximum number of full-resolution history points kept (ring buffer, ~10 min)
 const HISTORY_CAPACITY: usize = 60;
 
+/// 1-minute averages kept (~2 h)
+const MINUTE_HISTORY_CAPACITY: usize = 120;
+
+/// 5-minute averages kept (~12 h)
+const FIVE_MINUTE_HISTORY_CAPACITY: usize = 144;
+
 /// Maximum output size from a single sample (64KB — includes ss/netstat + docker ps)
 const MAX_OUTPUT_SIZE: usize = 65_536;
 
@@ -47,69 +65,169 @@ const MAX_OUTPUT_SIZE: usize = 65_536;
 const SAMPLE_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Default sampling interval (10s to minimise SSH bandwidth contention with PTY)
-const DEFAULT_INTERVAL: Duration = Duration::from_secs(10);
+pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Shortest sampling interval accepted by `set_interval()`
+pub const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Longest sampling interval accepted by `set_interval()`
+pub const MAX_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Default minimum gap between two emits of the same event kind for one connection
+pub const DEFAULT_EMIT_INTERVAL: Duration = Duration::from_millis(1000);
 
 /// Number of consecutive failures before degrading to RttOnly
 const MAX_CONSECUTIVE_FAILURES: u32 = 3;
 
+/// Longest delay between sampling attempts while samples keep failing
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Consecutive incomplete samples (timed out or truncated) before the shell
+/// channel is considered wedged and force-reopened
+const MAX_INCOMPLETE_SAMPLES: u32 = 2;
+
+/// Upper bound for a forced port rescan (one sample plus a possible shell channel reopen)
+const RESCAN_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Reply channel for a forced port rescan
+type RescanReply = oneshot::Sender<Vec<DetectedPort>>;
+
 /// Timeout for opening the initial shell channel
 const CHANNEL_OPEN_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// Slimmed sampling command (Linux only) — reads /proc pseudo-files for metrics.
-/// The full command is now built dynamically by `build_sample_command()` based on `os_type`,
+/// The full command is now built dynamically by `build_sample_command()` based on `OsFamily`,
 /// appending a platform-specific port scan after the metrics section.
-const METRICS_COMMAND_LINUX: &str = "echo '===STAT==='; head -1 /proc/stat 2>/dev/null; echo '===MEMINFO==='; grep -E '^(MemTotal|MemAvailable):' /proc/meminfo 2>/dev/null; echo '===LOADAVG==='; cat /proc/loadavg 2>/dev/null; echo '===NETDEV==='; cat /proc/net/dev 2>/dev/null; echo '===NPROC==='; nproc 2>/dev/null";
+/// `df -kP /` is POSIX, so the disk section also fills in on macOS and the BSDs.
+const METRICS_COMMAND_LINUX: &str = "echo '===STAT==='; head -1 /proc/stat 2>/dev/null; echo '===MEMINFO==='; grep -E '^(MemTotal|MemAvailable):' /proc/meminfo 2>/dev/null; echo '===LOADAVG==='; cat /proc/loadavg 2>/dev/null; echo '===NETDEV==='; cat /proc/net/dev 2>/dev/null; echo '===DISKSTATS==='; cat /proc/diskstats 2>/dev/null; echo '===NPROC==='; nproc 2>/dev/null; echo '===CPUINFO==='; grep -c ^processor /proc/cpuinfo 2>/dev/null; echo '===DISK==='; df -kP / 2>/dev/null";
 
 // ─── Port Detection: Platform-Dispatched Commands ─────────────────────────
 
 /// Linux: Use `ss` (modern) with `netstat` fallback.
 /// Output: one line per listening socket with addr:port and optional process info.
-const PORT_CMD_LINUX: &str = "echo '===PORTS==='; ((ss -tlnp 2>/dev/null || netstat -tlnp 2>/dev/null) | grep -i listen || true); echo '===PORTS_END==='; echo '===DOCKER==='; ((docker ps --format '{{.ID}}\t{{.Names}}\t{{.Ports}}' 2>/dev/null || sudo -n docker ps --format '{{.ID}}\t{{.Names}}\t{{.Ports}}' 2>/dev/null) || true); echo '===DOCKER_END==='";
+const PORT_CMD_LINUX: &str = "echo '===PORTS==='; ((ss -tlnp 2>/dev/null || netstat -tlnp 2>/dev/null) | grep -iE 'listen|users:' || true); echo '===PORTS_END==='; echo '===DOCKER==='; ((docker ps --format '{{.ID}}\t{{.Names}}\t{{.Ports}}' 2>/dev/null || sudo -n docker ps --format '{{.ID}}\t{{.Names}}\t{{.Ports}}' 2>/dev/null) || true); echo '===DOCKER_END==='";
 
 /// macOS: Use `lsof` to list listening TCP sockets.
 const PORT_CMD_MACOS: &str = "echo '===PORTS==='; ((lsof -iTCP -sTCP:LISTEN -nP 2>/dev/null | tail -n +2) || true); echo '===PORTS_END==='; echo '===DOCKER==='; ((docker ps --format '{{.ID}}\t{{.Names}}\t{{.Ports}}' 2>/dev/null || sudo -n docker ps --format '{{.ID}}\t{{.Names}}\t{{.Ports}}' 2>/dev/null) || true); echo '===DOCKER_END==='";
 
 /// Windows (PowerShell): `Get-NetTCPConnection` → CSV-like output.
-const PORT_CMD_WINDOWS: &str = "echo '===PORTS==='; powershell -NoProfile -Command \"Get-NetTCPConnection -State Listen 2>$null | Select-Object LocalAddress,LocalPort,OwningProcess | Format-Table -HideTableHeaders\" 2>/dev/null; echo '===PORTS_END==='";
+/// `netstat -ano` always runs too and the two are merged: it covers hosts without
+/// the cmdlet (older / Server Core Windows) and listeners the cmdlet does not list.
+/// Only its `LISTENING` rows are kept so busy hosts stay within `MAX_OUTPUT_SIZE`.
+const PORT_CMD_WINDOWS: &str = "echo '===PORTS==='; powershell -NoProfile -Command \"Get-NetTCPConnection -State Listen 2>$null | Select-Object LocalAddress,LocalPort,OwningProcess | Format-Table -HideTableHeaders\" 2>/dev/null; netstat -ano 2>/dev/null | findstr LISTENING; echo '===PORTS_END==='";
 
 /// FreeBSD: Use `sockstat` to list listening TCP sockets.
 const PORT_CMD_FREEBSD: &str = "echo '===PORTS==='; sockstat -4 -6 -l -P tcp 2>/dev/null | tail -n +2; echo '===PORTS_END==='";
 
+// UDP has no LISTEN state: these list bound sockets (`UNCONN` in ss).
+
+/// Linux: `ss -ulnp` with `netstat -ulnp` fallback.
+const UDP_CMD_LINUX: &str = "echo '===UDP==='; ((ss -ulnp 2>/dev/null || netstat -ulnp 2>/dev/null) || true); echo '===UDP_END==='";
+
+/// macOS: `lsof -iUDP` (connected sockets are skipped by the parser).
+const UDP_CMD_MACOS: &str = "echo '===UDP==='; ((lsof -iUDP -nP 2>/dev/null | tail -n +2) || true); echo '===UDP_END==='";
+
+/// Windows (PowerShell): `Get-NetUDPEndpoint`, falling back to `netstat -ano`.
+const UDP_CMD_WINDOWS: &str = "echo '===UDP==='; powershell -NoProfile -Command \"Get-NetUDPEndpoint 2>$null | Select-Object LocalAddress,LocalPort,OwningProcess | Format-Table -HideTableHeaders\" 2>/dev/null || netstat -ano 2>/dev/null; echo '===UDP_END==='";
+
+/// FreeBSD: `sockstat` restricted to UDP.
+const UDP_CMD_FREEBSD: &str = "echo '===UDP==='; sockstat -4 -6 -l -P udp 2>/dev/null | tail -n +2; echo '===UDP_END==='";
+
 /// Build the complete sampling command including port scan for the given OS.
+/// The UDP scan is appended only when `include_udp` is set.
 /// Returns a String with a trailing newline, ready to send to the shell channel.
-fn build_sample_command(os_type: &str) -> String {
-    let metrics = match os_type {
-        "Linux" | "linux" | "Windows_MinGW" | "Windows_MSYS" | "Windows_Cygwin" => {
-            METRICS_COMMAND_LINUX
-        }
-        // Non-Linux: metrics will degrade to RttOnly, but port scan still runs
-        _ => METRICS_COMMAND_LINUX,
+fn build_sample_command(os_family: OsFamily, include_udp: bool) -> String {
+    // Non-Linux hosts: metrics will degrade to RttOnly, but port scan still runs
+    let metrics = METRICS_COMMAND_LINUX;
+
+    let (port_cmd, udp_cmd) = match os_family {
+        OsFamily::Linux | OsFamily::WindowsPosix => (PORT_CMD_LINUX, UDP_CMD_LINUX),
+        OsFamily::MacOs => (PORT_CMD_MACOS, UDP_CMD_MACOS),
+        OsFamily::Windows => (PORT_CMD_WINDOWS, UDP_CMD_WINDOWS),
+        OsFamily::Bsd => (PORT_CMD_FREEBSD, UDP_CMD_FREEBSD),
+        OsFamily::Unknown => (PORT_CMD_LINUX, UDP_CMD_LINUX), // Fallback to Linux commands
     };
 
-    let port_cmd = match os_type {
-        "Linux" | "linux" | "Windows_MinGW" | "Windows_MSYS" | "Windows_Cygwin" => PORT_CMD_LINUX,
-        "macOS" | "macos" | "Darwin" => PORT_CMD_MACOS,
-        "Windows" | "windows" => PORT_CMD_WINDOWS,
-        "FreeBSD" | "freebsd" | "OpenBSD" | "NetBSD" => PORT_CMD_FREEBSD,
-        _ => PORT_CMD_LINUX, // Fallback to Linux commands
-    };
+    if include_udp {
+        format!("{}; {}; {}; echo '===END==='\n", metrics, port_cmd, udp_cmd)
+    } else {
+        format!("{}; {}; echo '===END==='\n", metrics, port_cmd)
+    }
+}
 
-    format!("{}; {}; echo '===END==='\n", metrics, port_cmd)
+/// Check a user-supplied sampling command and prepare it for the shell channel.
+///
+/// A custom command replaces the built-in one entirely (metrics and port scan)
+/// and must print the same sections, each a `===NAME===` line followed by the
+/// raw output the built-in command would collect:
+///
+/// - `===STAT===`: first line of `/proc/stat`; anything printed before it is dropped
+/// - `===MEMINFO===`: `MemTotal:` and `MemAvailable:` lines of `/proc/meminfo`
+/// - `===LOADAVG===`: `/proc/loadavg`
+/// - `===NETDEV===`, `===DISKSTATS===`: `/proc/net/dev`, `/proc/diskstats`
+/// - `===NPROC===`, `===CPUINFO===`: `nproc`, `grep -c ^processor /proc/cpuinfo`
+/// - `===DISK===`: `df -kP /`
+/// - `===PORTS===` … `===PORTS_END===` (and `===UDP===` … `===UDP_END===`):
+///   the port scan in the format of the host's OS family
+/// - `===END===`: always last; the sampler reads until it sees this line
+///
+/// Missing sections leave their metrics empty. Since the sampler cannot
+/// finish without `===END===`, the command itself must contain it (e.g.
+/// `/opt/metrics.sh; echo '===END==='`).
+pub fn custom_sample_command(command: &str) -> Result<String, String> {
+    let command = command.trim();
+    if command.is_empty() {
+        return Err("Custom sampling command is empty".to_string());
+    }
+    if !command.contains("===END===") {
+        return Err("Custom sampling command must print an ===END=== marker".to_string());
+    }
+    Ok(format!("{}\n", command))
 }
 
 // ─── Port Detection Data Structures ───────────────────────────────────────
 
+/// Transport protocol of a detected port
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum PortProtocol {
+    #[default]
+    Tcp,
+    Udp,
+}
+
+/// Identity of a port in the scan diff
+type PortKey = (u16, PortProtocol);
+
 /// A detected listening port on the remote host.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct DetectedPort {
     /// The port number
     pub port: u16,
+    /// TCP listener or bound UDP socket
+    #[serde(default)]
+    pub protocol: PortProtocol,
     /// Bind address (e.g. "0.0.0.0", "127.0.0.1", "::")
     pub bind_addr: String,
     /// Process name if available (e.g. "node", "python3")
     pub process_name: Option<String>,
     /// Process ID if available
     pub pid: Option<u32>,
+    /// More than one process holds the port (forked workers sharing the
+    /// socket, or `SO_REUSEPORT`); `process_name` and `pid` name the first
+    #[serde(default)]
+    pub shared: bool,
+}
+
+impl DetectedPort {
+    fn key(&self) -> PortKey {
+        (self.port, self.protocol)
+    }
+
+    /// Whether the bind address carries a `%zone` scope (IPv6 link-local or a device-bound socket)
+    fn is_zone_scoped(&self) -> bool {
+        self.bind_addr.contains('%')
+    }
 }
 
 /// Event emitted when new listening ports are detected on the remote host.
@@ -162,21 +280,430 @@ struct NetSnapshot {
     tx_bytes: u64,
 }
 
+/// Raw disk counters from /proc/diskstats
+#[derive(Debug, Clone, Default)]
+struct DiskSnapshot {
+    read_bytes: u64,
+    write_bytes: u64,
+}
+
+/// /proc/diskstats counts 512-byte sectors regardless of the device's sector size
+const DISKSTATS_SECTOR_SIZE: u64 = 512;
+
+/// Block devices left out of disk I/O: RAM-backed devices, plus device-mapper
+/// and md arrays whose I/O is already counted on the disks beneath them
+const DISKSTATS_EXCLUDED_PREFIXES: &[&str] = &["loop", "ram", "zram", "dm-", "md"];
+
 /// Previous sample state for delta calculations
 #[derive(Debug, Clone)]
 struct PreviousSample {
     cpu: CpuSnapshot,
     net: NetSnapshot,
+    disk: DiskSnapshot,
     timestamp_ms: u64,
 }
 
+/// Port keys of the last complete scan, which `port-detected` diffs are
+/// computed against
+///
+/// Every complete scan goes through [`observe`](Self::observe), scheduled
+/// or forced by a rescan, so the baseline always reflects the latest scan
+/// and a change is reported exactly once.
+#[derive(Debug, Default)]
+struct PortBaseline {
+    keys: HashSet<PortKey>,
+    established: bool,
+}
+
+/// Change between two complete port scans
+#[derive(Debug, Default, PartialEq)]
+struct PortDiff {
+    /// P6: the first scan only establishes the baseline and is never reported
+    initial: bool,
+    opened: Vec<PortKey>,
+    closed: Vec<PortKey>,
+}
+
+impl PortBaseline {
+    /// Record a complete scan and return what changed since the previous one
+    fn observe(&mut self, current: HashSet<PortKey>) -> PortDiff {
+        let diff = if self.established {
+            PortDiff {
+                initial: false,
+                opened: current.difference(&self.keys).copied().collect(),
+                closed: self.keys.difference(&current).copied().collect(),
+            }
+        } else {
+            PortDiff {
+                initial: true,
+                ..Default::default()
+            }
+        };
+        self.keys = current;
+        self.established = true;
+        diff
+    }
+}
+
+/// Ports the user dismissed, plus un-ignored ports awaiting re-announcement
+///
+/// An ignored port that keeps listening still enters the diff baseline, so
+/// simply removing it from the ignore set would never report it again.
+/// Un-ignoring therefore also resets the baseline for that port: the next
+/// scan reports it as new if it is still listening.
+///
+/// Ports are ignored by number, covering both TCP and UDP.
+#[derive(Debug, Default)]
+struct PortIgnoreSet {
+    ignored: HashSet<u16>,
+    /// Un-ignored ports to drop from the baseline on the next scan
+    resurface: HashSet<u16>,
+}
+
+impl PortIgnoreSet {
+    /// Start from previously persisted ports
+    fn with_ports(ports: impl IntoIterator<Item = u16>) -> Self {
+        Self {
+            ignored: ports.into_iter().collect(),
+            resurface: HashSet::new(),
+        }
+    }
+
+    /// Ignored ports, sorted
+    fn ports(&self) -> Vec<u16> {
+        let mut ports: Vec<u16> = self.ignored.iter().copied().collect();
+        ports.sort_unstable();
+        ports
+    }
+
+    fn ignore(&mut self, port: u16) {
+        self.resurface.remove(&port);
+        self.ignored.insert(port);
+    }
+
+    /// Returns whether the port was ignored
+    fn unignore(&mut self, port: u16) -> bool {
+        let removed = self.ignored.remove(&port);
+        if removed {
+            self.resurface.insert(port);
+        }
+        removed
+    }
+
+    /// Returns how many ports were ignored
+    fn clear(&mut self) -> usize {
+        let count = self.ignored.len();
+        self.resurface.extend(self.ignored.drain());
+        count
+    }
+
+    fn contains(&self, port: u16) -> bool {
+        self.ignored.contains(&port)
+    }
+
+    /// Drop un-ignored ports that are still listening from the diff baseline
+    /// so the current scan reports them as new.
+    fn reset_baseline(&mut self, prev_ports: &mut HashSet<PortKey>, current: &HashSet<PortKey>) {
+        for port in self.resurface.drain() {
+            prev_ports.retain(|key| key.0 != port || !current.contains(key));
+        }
+    }
+}
+
+/// Persists a profiler's ignored ports in the state database
+///
+/// Entries are keyed by saved connection ID: runtime connection IDs change on
+/// every connect, so they cannot carry the set across restarts.
+#[derive(Clone)]
+pub struct IgnoredPortsStore {
+    store: Arc<StateStore>,
+    connection_id: String,
+    /// Serializes write-through saves so the last one stores the latest set
+    write_lock: Arc<std::sync::Mutex<()>>,
+}
+
+impl IgnoredPortsStore {
+    pub fn new(store: Arc<StateStore>, connection_id: String) -> Self {
+        Self {
+            store,
+            connection_id,
+            write_lock: Arc::new(std::sync::Mutex::new(())),
+        }
+    }
+
+    fn load(&self) -> Vec<u16> {
+        self.store
+            .load_ignored_ports(&self.connection_id)
+            .unwrap_or_else(|e| {
+                warn!(
+                    "Failed to load ignored ports for {}: {}",
+                    self.connection_id, e
+                );
+                Vec::new()
+            })
+    }
+
+    /// Save the current contents of `ignored_ports` in the background
+    fn persist(&self, ignored_ports: Arc<RwLock<PortIgnoreSet>>) {
+        let this = self.clone();
+        // redb commits are blocking; keep them off the async runtime
+        tokio::task::spawn_blocking(move || {
+            let _guard = this.write_lock.lock().unwrap();
+            let ports = ignored_ports.read().unwrap().ports();
+            if let Err(e) = this.store.save_ignored_ports(&this.connection_id, &ports) {
+                warn!(
+                    "Failed to save ignored ports for {}: {}",
+                    this.connection_id, e
+                );
+            }
+        });
+    }
+}
+
+/// Why a sample produced no usable output
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SampleError {
+    /// No `===END===` within `SAMPLE_TIMEOUT`; the channel may be wedged
+    TimedOut,
+    /// The channel failed or closed
+    Channel(String),
+}
+
+impl std::fmt::Display for SampleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SampleError::TimedOut => write!(f, "Sample command timed out"),
+            SampleError::Channel(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Detects a shell channel that keeps returning partial output without closing
+///
+/// Samples that end without `===END===` (timeout or truncation) are counted;
+/// after `MAX_INCOMPLETE_SAMPLES` in a row the channel should be reopened.
+#[derive(Debug, Default)]
+struct WedgeDetector {
+    consecutive_incomplete: u32,
+}
+
+impl WedgeDetector {
+    /// Record a sample; returns true when the channel should be reopened
+    fn record(&mut self, complete: bool) -> bool {
+        if complete {
+            self.consecutive_incomplete = 0;
+            return false;
+        }
+        self.consecutive_incomplete += 1;
+        if self.consecutive_incomplete >= MAX_INCOMPLETE_SAMPLES {
+            self.consecutive_incomplete = 0;
+            return true;
+        }
+        false
+    }
+}
+
+/// Resolution of the metrics history returned by `ResourceProfiler::history`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryResolution {
+    /// Every sample (~10 min)
+    #[default]
+    Raw,
+    /// 1-minute averages (~2 h)
+    OneMinute,
+    /// 5-minute averages (~12 h)
+    FiveMinutes,
+}
+
+/// One downsampled history tier: finished buckets plus the samples of the
+/// bucket in progress
+#[derive(Debug)]
+struct AggregateTier {
+    bucket_ms: u64,
+    capacity: usize,
+    buckets: VecDeque<ResourceMetrics>,
+    current: Vec<ResourceMetrics>,
+}
+
+impl AggregateTier {
+    fn new(bucket_ms: u64, capacity: usize) -> Self {
+        Self {
+            bucket_ms,
+            capacity,
+            buckets: VecDeque::with_capacity(capacity),
+            current: Vec::new(),
+        }
+    }
+
+    fn bucket_of(&self, metrics: &ResourceMetrics) -> u64 {
+        metrics.timestamp_ms / self.bucket_ms
+    }
+
+    fn push(&mut self, metrics: &ResourceMetrics) {
+        let bucket = self.bucket_of(metrics);
+        if let Some(first) = self.current.first() {
+            if self.bucket_of(first) != bucket {
+                let finished = self.average_current();
+                self.current.clear();
+                if self.buckets.len() >= self.capacity {
+                    self.buckets.pop_front();
+                }
+                self.buckets.push_back(finished);
+            }
+        }
+        self.current.push(metrics.clone());
+    }
+
+    /// Average of the bucket in progress, stamped with the bucket start
+    fn average_current(&self) -> ResourceMetrics {
+        let bucket_start = self.bucket_of(&self.current[0]) * self.bucket_ms;
+        average_metrics(&self.current, bucket_start)
+    }
+
+    /// Finished buckets followed by the (partial) bucket in progress
+    fn series(&self) -> Vec<ResourceMetrics> {
+        let mut series: Vec<ResourceMetrics> = self.buckets.iter().cloned().collect();
+        if !self.current.is_empty() {
+            series.push(self.average_current());
+        }
+        series
+    }
+}
+
+/// Tiered metrics history: recent samples at full resolution plus 1-minute
+/// and 5-minute averages, so long sessions keep hours of data in bounded memory.
+#[derive(Debug)]
+struct MetricsHistory {
+    raw: VecDeque<ResourceMetrics>,
+    one_minute: AggregateTier,
+    five_minutes: AggregateTier,
+}
+
+impl MetricsHistory {
+    fn new() -> Self {
+        Self {
+            raw: VecDeque::with_capacity(HISTORY_CAPACITY),
+            one_minute: AggregateTier::new(60_000, MINUTE_HISTORY_CAPACITY),
+            five_minutes: AggregateTier::new(300_000, FIVE_MINUTE_HISTORY_CAPACITY),
+        }
+    }
+
+    fn push(&mut self, metrics: &ResourceMetrics) {
+        if self.raw.len() >= HISTORY_CAPACITY {
+            self.raw.pop_front();
+        }
+        self.raw.push_back(metrics.clone());
+        self.one_minute.push(metrics);
+        self.five_minutes.push(metrics);
+    }
+
+    fn series(&self, resolution: HistoryResolution) -> Vec<ResourceMetrics> {
+        match resolution {
+            HistoryResolution::Raw => self.raw.iter().cloned().collect(),
+            HistoryResolution::OneMinute => self.one_minute.series(),
+            HistoryResolution::FiveMinutes => self.five_minutes.series(),
+        }
+    }
+}
+
+/// Average a bucket of samples.
+///
+/// Optional fields average only the samples that have a value (`None` is
+/// skipped, not counted as zero); a field that is `None` in every sample stays
+/// `None`. `cpu_cores` takes the latest known value. The source is kept when
+/// all samples agree, otherwise it is `Partial` if any sample had data.
+fn average_metrics(samples: &[ResourceMetrics], timestamp_ms: u64) -> ResourceMetrics {
+    fn mean_f64(values: impl Iterator<Item = Option<f64>>) -> Option<f64> {
+        let (sum, count) = values
+            .flatten()
+            .fold((0.0, 0u32), |(sum, count), v| (sum + v, count + 1));
+        (count > 0).then(|| sum / count as f64)
+    }
+
+    fn mean_u64(values: impl Iterator<Item = Option<u64>>) -> Option<u64> {
+        let (sum, count) = values.flatten().fold((0u128, 0u128), |(sum, count), v| {
+            (sum + v as u128, count + 1)
+        });
+        (count > 0).then(|| ((sum + count / 2) / count) as u64)
+    }
+
+    let first_source = samples.first().map(|m| m.source);
+    let source = match first_source {
+        Some(source) if samples.iter().all(|m| m.source == source) => source,
+        _ if samples
+            .iter()
+            .any(|m| matches!(m.source, MetricsSource::Full | MetricsSource::Partial)) =>
+        {
+            MetricsSource::Partial
+        }
+        _ => samples.last().map_or(MetricsSource::Failed, |m| m.source),
+    };
+
+    ResourceMetrics {
+        timestamp_ms,
+        cpu_percent: mean_f64(samples.iter().map(|m| m.cpu_percent)),
+        memory_used: mean_u64(samples.iter().map(|m| m.memory_used)),
+        memory_total: mean_u64(samples.iter().map(|m| m.memory_total)),
+        memory_percent: mean_f64(samples.iter().map(|m| m.memory_percent)),
+        disk_used: mean_u64(samples.iter().map(|m| m.disk_used)),
+        disk_total: mean_u64(samples.iter().map(|m| m.disk_total)),
+        disk_percent: mean_f64(samples.iter().map(|m| m.disk_percent)),
+        load_avg_1: mean_f64(samples.iter().map(|m| m.load_avg_1)),
+        load_avg_5: mean_f64(samples.iter().map(|m| m.load_avg_5)),
+        load_avg_15: mean_f64(samples.iter().map(|m| m.load_avg_15)),
+        cpu_cores: samples.iter().rev().find_map(|m| m.cpu_cores),
+        net_rx_bytes_per_sec: mean_u64(samples.iter().map(|m| m.net_rx_bytes_per_sec)),
+        net_tx_bytes_per_sec: mean_u64(samples.iter().map(|m| m.net_tx_bytes_per_sec)),
+        disk_read_bytes_per_sec: mean_u64(samples.iter().map(|m| m.disk_read_bytes_per_sec)),
+        disk_write_bytes_per_sec: mean_u64(samples.iter().map(|m| m.disk_write_bytes_per_sec)),
+        ssh_rtt_ms: mean_u64(samples.iter().map(|m| m.ssh_rtt_ms)),
+        source,
+    }
+}
+
 /// Profiler running state
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
+#[serde(tag = "state", rename_all = "snake_case")]
 pub enum ProfilerState {
     Running,
     Stopped,
-    Degraded,
+    /// Sampling keeps failing; only RTT-only metrics are reported
+    Degraded {
+        /// Delay before the next sampling attempt, as of the last failure
+        /// (`None` when the profiler gave up)
+        retry_in_secs: Option<u64>,
+    },
+}
+
+/// Event emitted on `profiler:state:{connectionId}` when the profiler
+/// changes state (a new `Degraded` retry delay is not a change)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfilerStateEvent {
+    pub connection_id: String,
+    pub old_state: ProfilerState,
+    pub new_state: ProfilerState,
+    /// Why the state changed, e.g. "3 consecutive failures"
+    pub reason: String,
+}
+
+/// Store `new` as the profiler state
+///
+/// Returns the transition event when the state kind changed; rewriting the
+/// same kind (a `Degraded` state with a new retry delay) returns `None`.
+fn set_state(
+    state: &RwLock<ProfilerState>,
+    connection_id: &str,
+    new: ProfilerState,
+    reason: impl FnOnce() -> String,
+) -> Option<ProfilerStateEvent> {
+    let old = std::mem::replace(&mut *state.write().unwrap(), new);
+    (std::mem::discriminant(&old) != std::mem::discriminant(&new)).then(|| ProfilerStateEvent {
+        connection_id: connection_id.to_string(),
+        old_state: old,
+        new_state: new,
+        reason: reason(),
+    })
 }
 
 /// Resource profiler for a single SSH connection
@@ -184,18 +711,30 @@ pub struct ResourceProfiler {
     connection_id: String,
     state: Arc<RwLock<ProfilerState>>,
     latest: Arc<RwLock<Option<ResourceMetrics>>>,
-    history: Arc<RwLock<VecDeque<ResourceMetrics>>>,
+    history: Arc<RwLock<MetricsHistory>>,
     /// Sender to signal the sampling loop to stop
     stop_tx: Option<tokio::sync::oneshot::Sender<()>>,
-    /// Ports the user has dismissed / ignored (not shown again until restart)
-    ignored_ports: Arc<RwLock<HashSet<u16>>>,
+    /// Ports the user has dismissed / ignored (not shown again until un-ignored)
+    ignored_ports: Arc<RwLock<PortIgnoreSet>>,
+    /// Where ignored ports are persisted; without one they last until the profiler stops
+    ignored_ports_store: Option<IgnoredPortsStore>,
     /// Latest detected listening ports
     detected_ports: Arc<RwLock<Vec<DetectedPort>>>,
+    /// Requests for an immediate sample outside the interval
+    rescan_tx: mpsc::Sender<RescanReply>,
+    /// Current sampling interval, watched by the sampling loop
+    interval_tx: watch::Sender<Duration>,
 }
 
 impl ResourceProfiler {
     /// Spawn a new profiler that samples the remote host via the given controller.
     ///
+    /// Frontend events are coalesced to at most one per `emit_interval` (P7).
+    /// Bound UDP sockets are reported alongside TCP listeners when `include_udp` is set.
+    /// Ignored ports are loaded from `ignored_ports_store` and written back on every change.
+    /// A `custom_command` (checked by [`custom_sample_command`]) replaces the
+    /// built-in sampling command.
+    ///
     /// The profiler automatically stops when:
     /// 1. `stop()` is called
     /// 2. The SSH connection disconnects (via `disconnect_rx`)
@@ -203,14 +742,54 @@ impl ResourceProfiler {
         connection_id: String,
         controller: HandleController,
         app_handle: tauri::AppHandle,
-        os_type: String,
+        os_family: OsFamily,
+        include_udp: bool,
+        custom_command: Option<String>,
+        ignored_ports_store: Option<IgnoredPortsStore>,
+        emit_interval: Duration,
+    ) -> Self {
+        Self::spawn_with_interval(
+            connection_id,
+            controller,
+            app_handle,
+            os_family,
+            include_udp,
+            custom_command,
+            ignored_ports_store,
+            emit_interval,
+            DEFAULT_INTERVAL,
+        )
+    }
+
+    /// Like [`spawn`](Self::spawn), sampling every `interval` instead of
+    /// `DEFAULT_INTERVAL` (clamped to `MIN_INTERVAL..=MAX_INTERVAL`).
+    pub fn spawn_with_interval(
+        connection_id: String,
+        controller: HandleController,
+        app_handle: tauri::AppHandle,
+        os_family: OsFamily,
+        include_udp: bool,
+        custom_command: Option<String>,
+        ignored_ports_store: Option<IgnoredPortsStore>,
+        emit_interval: Duration,
+        interval: Duration,
     ) -> Self {
+        // Build the sample command once (includes port scan for this OS)
+        let sample_command =
+            custom_command.unwrap_or_else(|| build_sample_command(os_family, include_udp));
         let state = Arc::new(RwLock::new(ProfilerState::Running));
         let latest = Arc::new(RwLock::new(None));
-        let history = Arc::new(RwLock::new(VecDeque::with_capacity(HISTORY_CAPACITY)));
-        let ignored_ports = Arc::new(RwLock::new(HashSet::new()));
+        let history = Arc::new(RwLock::new(MetricsHistory::new()));
+        let ignored_ports = Arc::new(RwLock::new(PortIgnoreSet::with_ports(
+            ignored_ports_store
+                .as_ref()
+                .map(IgnoredPortsStore::load)
+                .unwrap_or_default(),
+        )));
         let detected_ports = Arc::new(RwLock::new(Vec::new()));
         let (stop_tx, stop_rx) = tokio::sync::oneshot::channel();
+        let (rescan_tx, rescan_rx) = mpsc::channel(8);
+        let (interval_tx, interval_rx) = watch::channel(interval.clamp(MIN_INTERVAL, MAX_INTERVAL));
 
         let profiler = Self {
             connection_id: connection_id.clone(),
@@ -219,7 +798,10 @@ impl ResourceProfiler {
             history: history.clone(),
             stop_tx: Some(stop_tx),
             ignored_ports: ignored_ports.clone(),
+            ignored_ports_store,
             detected_ports: detected_ports.clone(),
+            rescan_tx,
+            interval_tx,
         };
 
         // Subscribe to SSH disconnect
@@ -239,11 +821,16 @@ impl ResourceProfiler {
                 latest_clone,
                 history_clone,
                 stop_rx,
+                rescan_rx,
+                interval_rx,
                 &mut disconnect_rx,
                 app_handle,
-                os_type,
+                os_family,
+                include_udp,
+                sample_command,
                 ignored_ports,
                 detected_ports,
+                emit_interval,
             )
             .await;
         });
@@ -256,9 +843,9 @@ impl ResourceProfiler {
         self.latest.read().unwrap().clone()
     }
 
-    /// Get metrics history for sparkline rendering
-    pub async fn history(&self) -> Vec<ResourceMetrics> {
-        self.history.read().unwrap().iter().cloned().collect()
+    /// Get metrics history for sparkline rendering, oldest first
+    pub async fn history(&self, resolution: HistoryResolution) -> Vec<ResourceMetrics> {
+        self.history.read().unwrap().series(resolution)
     }
 
     /// Get current profiler state
@@ -278,14 +865,76 @@ impl ResourceProfiler {
         &self.connection_id
     }
 
+    /// Current sampling interval
+    pub fn interval(&self) -> Duration {
+        *self.interval_tx.borrow()
+    }
+
+    /// Change the sampling interval (clamped to `MIN_INTERVAL..=MAX_INTERVAL`).
+    ///
+    /// The next sample is taken one new interval from now. CPU% and network
+    /// rates restart from a fresh baseline, so that sample reports them as None.
+    pub fn set_interval(&self, interval: Duration) {
+        let interval = interval.clamp(MIN_INTERVAL, MAX_INTERVAL);
+        self.interval_tx.send_if_modified(|current| {
+            let changed = *current != interval;
+            *current = interval;
+            changed
+        });
+    }
+
     /// Get the latest detected listening ports
     pub fn detected_ports(&self) -> Vec<DetectedPort> {
         self.detected_ports.read().unwrap().clone()
     }
 
+    /// Force an immediate sample and return the listening ports it found.
+    ///
+    /// The forced sample runs through the regular diff path, so the diff
+    /// baseline and `port-detected` events stay consistent, and the interval
+    /// restarts from it. Returns `None` if the profiler stopped or the sample
+    /// did not finish in time. The returned future does not borrow `self`.
+    pub fn rescan_ports(&self) -> impl std::future::Future<Output = Option<Vec<DetectedPort>>> {
+        let rescan_tx = self.rescan_tx.clone();
+        async move {
+            let (reply_tx, reply_rx) = oneshot::channel();
+            rescan_tx.send(reply_tx).await.ok()?;
+            timeout(RESCAN_TIMEOUT, reply_rx).await.ok()?.ok()
+        }
+    }
+
     /// Add a port to the ignore list (user dismissed the notification)
     pub fn ignore_port(&self, port: u16) {
-        self.ignored_ports.write().unwrap().insert(port);
+        self.ignored_ports.write().unwrap().ignore(port);
+        self.persist_ignored_ports();
+    }
+
+    /// Remove a port from the ignore list.
+    ///
+    /// If the port is still listening, the next scan reports it as new again.
+    /// Returns whether the port was ignored.
+    pub fn unignore_port(&self, port: u16) -> bool {
+        let removed = self.ignored_ports.write().unwrap().unignore(port);
+        if removed {
+            self.persist_ignored_ports();
+        }
+        removed
+    }
+
+    /// Clear the ignore list; still-listening ports are reported as new on the next scan.
+    /// Returns how many ports were ignored.
+    pub fn clear_ignored_ports(&self) -> usize {
+        let count = self.ignored_ports.write().unwrap().clear();
+        if count > 0 {
+            self.persist_ignored_ports();
+        }
+        count
+    }
+
+    fn persist_ignored_ports(&self) {
+        if let Some(store) = &self.ignored_ports_store {
+            store.persist(self.ignored_ports.clone());
+        }
     }
 }
 
@@ -296,46 +945,75 @@ impl ResourceProfiler {
 ///
 /// Also performs port detection: parses `===PORTS===...===PORTS_END===` from each sample,
 /// diffs against previous scan, and emits `port-detected:{connectionId}` on changes.
+///
+/// A rescan request makes the next tick fire immediately; it is answered with
+/// the port snapshot once that tick has been processed.
+///
+/// An interval change restarts the ticker at the new period and drops the
+/// delta baseline (P5).
+///
+/// Emits go through an [`EmitThrottle`] per event kind, so forced rescans cannot
+/// flood the frontend; a coalesced update is flushed when its window ends, or
+/// when the loop stops.
 async fn sampling_loop(
     connection_id: String,
     controller: HandleController,
     state: Arc<RwLock<ProfilerState>>,
     latest: Arc<RwLock<Option<ResourceMetrics>>>,
-    history: Arc<RwLock<VecDeque<ResourceMetrics>>>,
+    history: Arc<RwLock<MetricsHistory>>,
     mut stop_rx: tokio::sync::oneshot::Receiver<()>,
+    mut rescan_rx: mpsc::Receiver<RescanReply>,
+    mut interval_rx: watch::Receiver<Duration>,
     disconnect_rx: &mut broadcast::Receiver<()>,
     app_handle: tauri::AppHandle,
-    os_type: String,
-    ignored_ports: Arc<RwLock<HashSet<u16>>>,
+    os_family: OsFamily,
+    include_udp: bool,
+    sample_command: String,
+    ignored_ports: Arc<RwLock<PortIgnoreSet>>,
     detected_ports: Arc<RwLock<Vec<DetectedPort>>>,
+    emit_interval: Duration,
 ) {
     let mut prev_sample: Option<PreviousSample> = None;
     let mut consecutive_failures: u32 = 0;
-    let mut interval = tokio::time::interval(DEFAULT_INTERVAL);
+    let mut wedge_detector = WedgeDetector::default();
+    let mut interval = tokio::time::interval(*interval_rx.borrow_and_update());
     // Skip the immediate first tick
     interval.tick().await;
 
     // Port detection state
-    let mut prev_ports: HashSet<u16> = HashSet::new();
-    let mut is_initial_scan = true;
-
-    // Build the sample command once (includes port scan for this OS)
-    let sample_command = build_sample_command(&os_type);
+    let mut port_baseline = PortBaseline::default();
+    let mut pending_rescans: Vec<RescanReply> = Vec::new();
+
+    let mut metrics_throttle: EmitThrottle<ResourceMetrics> = EmitThrottle::new(emit_interval);
+    let mut ports_throttle: EmitThrottle<PortDetectionEvent> = EmitThrottle::new(emit_interval);
+    let metrics_event = format!("profiler:update:{}", connection_id);
+    let ports_event = format!("port-detected:{}", connection_id);
+    let state_event = format!("profiler:state:{}", connection_id);
+    let emit_state = |new: ProfilerState, reason: &dyn Fn() -> String| {
+        if let Some(event) = set_state(&state, &connection_id, new, reason) {
+            emit_event(&app_handle, &state_event, &event);
+        }
+    };
 
     debug!(
-        "Resource profiler started for connection {} (os_type={})",
-        connection_id, os_type
+        "Resource profiler started for connection {} (os_family={:?}, udp={})",
+        connection_id, os_family, include_udp
     );
 
     // Open persistent shell channel
-    let mut shell_channel = match open_shell_channel(&controller, &os_type).await {
+    let mut shell_channel = match open_shell_channel(&controller, os_family).await {
         Ok(ch) => ch,
         Err(e) => {
             warn!(
                 "Profiler failed to open shell channel for {}: {}",
                 connection_id, e
             );
-            *state.write().unwrap() = ProfilerState::Degraded;
+            emit_state(
+                ProfilerState::Degraded {
+                    retry_in_secs: None,
+                },
+                &|| format!("channel open failed: {}", e),
+            );
             // Emit degraded metrics so frontend knows
             let metrics = make_empty_metrics(MetricsSource::RttOnly);
             store_metrics(&latest, &history, &metrics);
@@ -344,93 +1022,102 @@ async fn sampling_loop(
         }
     };
 
-    loop {
+    let stop_reason = loop {
+        let next_flush = [metrics_throttle.deadline(), ports_throttle.deadline()]
+            .into_iter()
+            .flatten()
+            .min();
+
         tokio::select! {
-            _ = interval.tick() => {
-                // Degraded mode: only emit RTT-only metrics
-                if consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
-                    let current_state = *state.read().unwrap();
-                    if current_state != ProfilerState::Degraded {
-                        *state.write().unwrap() = ProfilerState::Degraded;
-                        warn!(
-                            "Resource profiler degraded for {} after {} consecutive failures",
-                            connection_id, consecutive_failures
-                        );
-                    }
-                    let metrics = make_empty_metrics(MetricsSource::RttOnly);
-                    store_metrics(&latest, &history, &metrics);
-                    emit_metrics(&app_handle, &connection_id, &metrics);
-                    continue;
+            _ = tokio::time::sleep_until(next_flush.unwrap_or_else(Instant::now)), if next_flush.is_some() => {
+                let now = Instant::now();
+                if let Some(metrics) = metrics_throttle.flush(now) {
+                    emit_event(&app_handle, &metrics_event, &metrics);
                 }
-
+                if let Some(event) = ports_throttle.flush(now) {
+                    emit_port_event(&app_handle, &ports_event, &event);
+                }
+                // Not a sample: leave pending rescans for the next tick
+                continue;
+            }
+            _ = interval.tick() => {
                 // Execute sampling command on persistent shell
                 match shell_sample(&mut shell_channel, &sample_command).await {
                     Ok(output) => {
+                        // P8: the backoff ends; `interval` already ticks at the base period
+                        if consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
+                            debug!("Resource profiler recovered for {}", connection_id);
+                        }
                         consecutive_failures = 0;
+                        emit_state(ProfilerState::Running, &|| "sample succeeded".to_string());
                         let metrics = parse_metrics(&output, &prev_sample);
 
                         let cpu = parse_cpu_snapshot(&output);
                         let net = parse_net_snapshot(&output);
+                        let disk = parse_disk_snapshot(&output);
                         prev_sample = Some(PreviousSample {
                             cpu: cpu.unwrap_or_default(),
                             net: net.unwrap_or_default(),
+                            disk: disk.unwrap_or_default(),
                             timestamp_ms: metrics.timestamp_ms,
                         });
 
                         store_metrics(&latest, &history, &metrics);
-                        emit_metrics(&app_handle, &connection_id, &metrics);
                         trace!("Profiler sample for {}: source={:?}", connection_id, metrics.source);
+                        if let Some(metrics) = metrics_throttle.offer(Instant::now(), metrics, |_, latest| latest) {
+                            emit_event(&app_handle, &metrics_event, &metrics);
+                        }
 
                         // ── Port Detection ──
                         // Skip port diff if sample was truncated (no ===END=== marker),
                         // as partial output would produce false closed/new diffs.
-                        let sample_complete = output.contains("===END===");
+                        let sample_complete = find_marker(&output, "===END===").is_some();
                         if !sample_complete {
                             warn!("Profiler sample for {} was truncated, skipping port diff", connection_id);
                         }
 
                         if sample_complete {
-                        let current_ports = parse_listening_ports(&output, &os_type);
-                        let current_port_numbers: HashSet<u16> =
-                            current_ports.iter().map(|p| p.port).collect();
-
-                        if is_initial_scan {
+                        let current_ports = parse_listening_ports(&output, os_family, include_udp);
+                        let current_port_keys: HashSet<PortKey> =
+                            current_ports.iter().map(DetectedPort::key).collect();
+
+                        // Un-ignored ports that are still listening re-surface as new
+                        ignored_ports
+                            .write()
+                            .unwrap()
+                            .reset_baseline(&mut port_baseline.keys, &current_port_keys);
+
+                        let diff = port_baseline.observe(current_port_keys);
+                        if diff.initial {
                             // P6: first scan is silent — establish baseline
-                            prev_ports = current_port_numbers;
                             *detected_ports.write().unwrap() = current_ports;
-                            is_initial_scan = false;
-                            trace!("Port detection baseline for {}: {} ports", connection_id, prev_ports.len());
+                            trace!("Port detection baseline for {}: {} ports", connection_id, port_baseline.keys.len());
                         } else {
                             // Diff: find new and closed ports
-                            let new_port_numbers: Vec<u16> = current_port_numbers
-                                .difference(&prev_ports)
-                                .copied()
-                                .collect();
-                            let closed_port_numbers: Vec<u16> = prev_ports
-                                .difference(&current_port_numbers)
-                                .copied()
-                                .collect();
-
-                            if !new_port_numbers.is_empty() || !closed_port_numbers.is_empty() {
+                            let new_port_keys = diff.opened;
+                            let closed_port_keys = diff.closed;
+
+                            if !new_port_keys.is_empty() || !closed_port_keys.is_empty() {
                                 // Filter out ignored ports and port 22 (SSH)
                                 let ignored = ignored_ports.read().unwrap();
                                 let new_ports: Vec<DetectedPort> = current_ports
                                     .iter()
                                     .filter(|p| {
-                                        new_port_numbers.contains(&p.port)
+                                        new_port_keys.contains(&p.key())
                                             && p.port != 22
-                                            && !ignored.contains(&p.port)
+                                            && !ignored.contains(p.port)
                                     })
                                     .cloned()
                                     .collect();
-                                let closed_ports: Vec<DetectedPort> = prev_ports
+                                let closed_ports: Vec<DetectedPort> = closed_port_keys
                                     .iter()
-                                    .filter(|port| closed_port_numbers.contains(port))
-                                    .map(|&port| DetectedPort {
+                                    .map(|&(port, protocol)| DetectedPort {
                                         port,
+                                        protocol,
                                         bind_addr: String::new(),
                                         process_name: None,
                                         pid: None,
+                                        shared: false,
                                     })
                                     .collect();
                                 drop(ignored);
@@ -443,27 +1130,30 @@ async fn sampling_loop(
                                         closed_ports,
                                         all_ports: current_ports.clone(),
                                     };
-                                    let event_name =
-                                        format!("port-detected:{}", connection_id);
-                                    if let Err(e) = app_handle.emit(&event_name, &event) {
-                                        warn!("Failed to emit port detection event: {}", e);
-                                    }
                                     debug!(
                                         "Port detection for {}: {} new, {} closed",
                                         connection_id,
                                         event.new_ports.len(),
                                         event.closed_ports.len()
                                     );
+                                    if let Some(event) = ports_throttle.offer(Instant::now(), event, merge_port_events) {
+                                        emit_port_event(&app_handle, &ports_event, &event);
+                                    }
                                 }
                             }
 
-                            // Always update snapshot: port numbers for diff baseline,
-                            // and full DetectedPort data for metadata freshness
+                            // Always update snapshot: full DetectedPort data for metadata freshness
                             // (bind_addr / process_name / pid may change even if port set is stable)
-                            prev_ports = current_port_numbers;
                             *detected_ports.write().unwrap() = current_ports;
                         }
                         } // end if sample_complete
+
+                        if wedge_detector.record(sample_complete) {
+                            warn!("Profiler shell for {} keeps returning truncated output, reopening", connection_id);
+                            reopen_shell_channel(&mut shell_channel, &controller, os_family, &connection_id).await;
+                            // Counters from the old channel's last sample are not a valid delta base
+                            prev_sample = None;
+                        }
                     }
                     Err(e) => {
                         consecutive_failures += 1;
@@ -472,56 +1162,148 @@ async fn sampling_loop(
                             connection_id, consecutive_failures, MAX_CONSECUTIVE_FAILURES, e
                         );
 
-                        // Try to reopen the shell channel once
-                        if let Ok(new_ch) = open_shell_channel(&controller, &os_type).await {
-                            shell_channel = new_ch;
-                            debug!("Profiler reopened shell channel for {}", connection_id);
+                        // A closed channel is reopened right away; a timeout may be a
+                        // slow host, so only a run of them counts as a wedged channel
+                        let reopen = match e {
+                            SampleError::TimedOut => wedge_detector.record(false),
+                            SampleError::Channel(_) => true,
+                        };
+                        if reopen {
+                            reopen_shell_channel(&mut shell_channel, &controller, os_family, &connection_id).await;
+                            prev_sample = None;
                         }
 
-                        let failed_metrics = make_empty_metrics(MetricsSource::Failed);
+                        // P8: the next attempt waits out the backoff, even if the
+                        // reopen failed and the channel is known to be dead
+                        let period = interval.period();
+                        let backoff = failure_backoff(period, consecutive_failures);
+                        interval = tokio::time::interval_at(Instant::now() + backoff, period);
+
+                        // Degraded mode: only emit RTT-only metrics
+                        let source = if consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
+                            if !matches!(*state.read().unwrap(), ProfilerState::Degraded { .. }) {
+                                warn!(
+                                    "Resource profiler degraded for {} after {} consecutive failures",
+                                    connection_id, consecutive_failures
+                                );
+                            }
+                            emit_state(
+                                ProfilerState::Degraded {
+                                    retry_in_secs: Some(backoff.as_secs()),
+                                },
+                                &|| format!("{} consecutive failures", consecutive_failures),
+                            );
+                            MetricsSource::RttOnly
+                        } else {
+                            MetricsSource::Failed
+                        };
+                        let failed_metrics = make_empty_metrics(source);
                         store_metrics(&latest, &history, &failed_metrics);
-                        emit_metrics(&app_handle, &connection_id, &failed_metrics);
+                        if let Some(metrics) = metrics_throttle.offer(Instant::now(), failed_metrics, |_, latest| latest) {
+                            emit_event(&app_handle, &metrics_event, &metrics);
+                        }
                     }
                 }
             }
-            _ = disconnect_rx.recv() => {
+            Ok(()) = interval_rx.changed() => {
+                let period = *interval_rx.borrow_and_update();
+                debug!("Profiler interval for {} set to {:?}", connection_id, period);
+                let first_tick = if consecutive_failures > 0 {
+                    failure_backoff(period, consecutive_failures)
+                } else {
+                    period
+                };
+                interval = tokio::time::interval_at(Instant::now() + first_tick, period);
+                // Counters sampled on the old schedule are not a valid delta base
+                prev_sample = None;
+                if !pending_rescans.is_empty() {
+                    interval.reset_immediately();
+                }
+                continue;
+            }
+            Some(reply) = rescan_rx.recv() => {
+                if consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
+                    // Degraded: no port data will come, answer with the last snapshot
+                    let _ = reply.send(detected_ports.read().unwrap().clone());
+                } else {
+                    pending_rescans.push(reply);
+                    interval.reset_immediately();
+                }
+                continue;
+            }
+            result = disconnect_rx.recv() => {
                 debug!("SSH disconnected, stopping profiler for {}", connection_id);
-                break;
+                break match result {
+                    Ok(()) => "ssh disconnected",
+                    Err(_) => "channel closed",
+                };
             }
             _ = &mut stop_rx => {
                 debug!("Profiler stop requested for {}", connection_id);
-                break;
+                break "stop requested";
+            }
+        }
+
+        // A tick was processed; its snapshot answers any forced rescan
+        if !pending_rescans.is_empty() {
+            let snapshot = detected_ports.read().unwrap().clone();
+            for reply in pending_rescans.drain(..) {
+                let _ = reply.send(snapshot.clone());
             }
         }
+    };
+
+    // The last coalesced updates would otherwise be lost
+    if let Some(metrics) = metrics_throttle.finish() {
+        emit_event(&app_handle, &metrics_event, &metrics);
+    }
+    if let Some(event) = ports_throttle.finish() {
+        emit_port_event(&app_handle, &ports_event, &event);
     }
 
     // Close the persistent channel
     let _ = shell_channel.close().await;
-    *state.write().unwrap() = ProfilerState::Stopped;
+    emit_state(ProfilerState::Stopped, &|| stop_reason.to_string());
     debug!("Resource profiler stopped for {}", connection_id);
 }
 
+/// Delay before the next sampling attempt after `failures` consecutive
+/// failed samples: the interval doubled per failure, capped at `MAX_BACKOFF`
+/// but never shorter than the interval itself
+fn failure_backoff(period: Duration, failures: u32) -> Duration {
+    let factor = 1u32.checked_shl(failures).unwrap_or(u32::MAX);
+    period.saturating_mul(factor).min(MAX_BACKOFF).max(period)
+}
+
 /// Open a persistent shell channel for sampling
 async fn open_shell_channel(
     controller: &HandleController,
-    os_type: &str,
+    os_family: OsFamily,
 ) -> Result<Channel<Msg>, String> {
     let channel = timeout(CHANNEL_OPEN_TIMEOUT, controller.open_session_channel())
         .await
         .map_err(|_| "Timeout opening shell channel".to_string())?
         .map_err(|e| format!("Failed to open shell channel: {}", e))?;
 
-    // Request a shell (not exec) so we can send multiple commands
-    channel
-        .request_shell(false)
-        .await
-        .map_err(|e| format!("Failed to request shell: {}", e))?;
+    // Unix: exec a plain `sh` reading commands from stdin instead of the login
+    // shell, so fish/csh users still get POSIX syntax (and no prompt at all).
+    // Windows: request the default shell (cmd / PowerShell).
+    match os_family {
+        OsFamily::Windows => channel
+            .request_shell(false)
+            .await
+            .map_err(|e| format!("Failed to request shell: {}", e))?,
+        _ => channel
+            .exec(false, "sh")
+            .await
+            .map_err(|e| format!("Failed to start sh: {}", e))?,
+    }
 
     // Platform-specific init command:
     // - Unix: disable echo/prompt via stty, set C locale
     // - Windows: stty not available, but prompt should still be suppressed
-    let init_cmd = match os_type {
-        "Windows" | "windows" => {
+    let init_cmd = match os_family {
+        OsFamily::Windows => {
             // PowerShell / cmd.exe: set prompt to empty
             "set PROMPT=\r\n"
         }
@@ -538,13 +1320,36 @@ async fn open_shell_channel(
     Ok(channel)
 }
 
+/// Force-close the current shell channel and replace it with a fresh one.
+///
+/// If opening fails the closed channel stays in place; the next sample then
+/// fails and retries the reopen.
+async fn reopen_shell_channel(
+    channel: &mut Channel<Msg>,
+    controller: &HandleController,
+    os_family: OsFamily,
+    connection_id: &str,
+) {
+    let _ = channel.close().await;
+    match open_shell_channel(controller, os_family).await {
+        Ok(new_ch) => {
+            *channel = new_ch;
+            debug!("Profiler reopened shell channel for {}", connection_id);
+        }
+        Err(e) => warn!(
+            "Profiler failed to reopen shell channel for {}: {}",
+            connection_id, e
+        ),
+    }
+}
+
 /// Send the sampling command to the persistent shell and read output until ===END===
-async fn shell_sample(channel: &mut Channel<Msg>, command: &str) -> Result<String, String> {
+async fn shell_sample(channel: &mut Channel<Msg>, command: &str) -> Result<String, SampleError> {
     // Write command to stdin
     channel
         .data(command.as_bytes())
         .await
-        .map_err(|e| format!("Failed to write to shell: {}", e))?;
+        .map_err(|e| SampleError::Channel(format!("Failed to write to shell: {}", e)))?;
 
     let mut stdout = Vec::new();
 
@@ -555,7 +1360,7 @@ async fn shell_sample(channel: &mut Channel<Msg>, command: &str) -> Result<Strin
                     stdout.extend_from_slice(&data);
                     // Check if we've received the end marker (always preferred over truncation)
                     if let Ok(s) = std::str::from_utf8(&stdout) {
-                        if s.contains("===END===") {
+                        if find_marker(s, "===END===").is_some() {
                             break;
                         }
                     }
@@ -568,11 +1373,13 @@ async fn shell_sample(channel: &mut Channel<Msg>, command: &str) -> Result<Strin
                 }
                 Some(ChannelMsg::ExtendedData { .. }) => {}
                 Some(ChannelMsg::Eof) | Some(ChannelMsg::Close) => {
-                    return Err("Shell channel closed".to_string());
+                    return Err(SampleError::Channel("Shell channel closed".to_string()));
                 }
                 Some(_) => {}
                 None => {
-                    return Err("Shell channel returned None".to_string());
+                    return Err(SampleError::Channel(
+                        "Shell channel returned None".to_string(),
+                    ));
                 }
             }
         }
@@ -581,19 +1388,38 @@ async fn shell_sample(channel: &mut Channel<Msg>, command: &str) -> Result<Strin
     .await;
 
     match result {
-        Err(_) => Err("Sample command timed out".into()),
+        Err(_) => Err(SampleError::TimedOut),
         Ok(Err(e)) => Err(e),
         Ok(Ok(())) => {
-            let full = String::from_utf8(stdout).map_err(|e| format!("Invalid UTF-8: {}", e))?;
-            // Extract only the portion from ===STAT=== to ===END===
-            if let Some(start) = full.find("===STAT===") {
-                if let Some(end) = full.find("===END===") {
-                    return Ok(full[start..end + "===END===".len()].to_string());
-                }
-            }
-            Ok(full)
+            let full = String::from_utf8(stdout)
+                .map_err(|e| SampleError::Channel(format!("Invalid UTF-8: {}", e)))?;
+            Ok(extract_sample(&full).to_string())
+        }
+    }
+}
+
+/// Find a marker printed by the sampling command.
+///
+/// Only an occurrence that ends a line counts: the command text echoed back by
+/// a shell that ignored `stty -echo` (`echo '===END==='`) is skipped, while a
+/// prompt printed before the marker on the same line is tolerated.
+fn find_marker(output: &str, marker: &str) -> Option<usize> {
+    output.match_indices(marker).map(|(i, _)| i).find(|&i| {
+        let rest = &output[i + marker.len()..];
+        rest.is_empty() || rest.starts_with('\n') || rest.starts_with("\r\n")
+    })
+}
+
+/// Extract only the portion from ===STAT=== to ===END===, dropping prompt
+/// noise and echoed input around it. Returns the input unchanged if either
+/// marker is missing.
+fn extract_sample(full: &str) -> &str {
+    if let Some(start) = find_marker(full, "===STAT===") {
+        if let Some(end) = find_marker(&full[start..], "===END===") {
+            return &full[start..start + end + "===END===".len()];
         }
     }
+    full
 }
 
 /// Create empty metrics with a given source
@@ -604,12 +1430,17 @@ fn make_empty_metrics(source: MetricsSource) -> ResourceMetrics {
         memory_used: None,
         memory_total: None,
         memory_percent: None,
+        disk_used: None,
+        disk_total: None,
+        disk_percent: None,
         load_avg_1: None,
         load_avg_5: None,
         load_avg_15: None,
         cpu_cores: None,
         net_rx_bytes_per_sec: None,
         net_tx_bytes_per_sec: None,
+        disk_read_bytes_per_sec: None,
+        disk_write_bytes_per_sec: None,
         ssh_rtt_ms: None,
         source,
     }
@@ -620,9 +1451,11 @@ fn parse_metrics(output: &str, prev: &Option<PreviousSample>) -> ResourceMetrics
     let ts = now_ms();
     let cpu_snap = parse_cpu_snapshot(output);
     let net_snap = parse_net_snapshot(output);
+    let disk_snap = parse_disk_snapshot(output);
     let mem = parse_meminfo(output);
     let load = parse_loadavg(output);
     let nproc = parse_nproc(output);
+    let disk = parse_disk_usage(output);
 
     // CPU% via delta
     let cpu_percent = match (&cpu_snap, prev) {
@@ -656,6 +1489,24 @@ fn parse_metrics(output: &str, prev: &Option<PreviousSample>) -> ResourceMetrics
         _ => (None, None),
     };
 
+    // Disk I/O rate via delta
+    let (disk_read_rate, disk_write_rate) = match (&disk_snap, prev) {
+        (Some(curr), Some(prev_s)) => {
+            let elapsed_ms = ts.saturating_sub(prev_s.timestamp_ms);
+            if elapsed_ms > 0 {
+                let elapsed_secs = elapsed_ms as f64 / 1000.0;
+                let read = ((curr.read_bytes.saturating_sub(prev_s.disk.read_bytes)) as f64
+                    / elapsed_secs) as u64;
+                let write = ((curr.write_bytes.saturating_sub(prev_s.disk.write_bytes)) as f64
+                    / elapsed_secs) as u64;
+                (Some(read), Some(write))
+            } else {
+                (None, None)
+            }
+        }
+        _ => (None, None),
+    };
+
     // Memory
     let (mem_used, mem_total, mem_percent) = match mem {
         Some((used, total)) => {
@@ -669,6 +1520,15 @@ fn parse_metrics(output: &str, prev: &Option<PreviousSample>) -> ResourceMetrics
         None => (None, None, None),
     };
 
+    // Disk (root filesystem)
+    let (disk_used, disk_total, disk_percent) = match disk {
+        Some((used, total)) => {
+            let pct = (total > 0).then(|| (used as f64 / total as f64) * 100.0);
+            (Some(used), Some(total), pct)
+        }
+        None => (None, None, None),
+    };
+
     // Determine source quality
     let has_cpu = cpu_snap.is_some();
     let has_mem = mem.is_some();
@@ -687,12 +1547,17 @@ fn parse_metrics(output: &str, prev: &Option<PreviousSample>) -> ResourceMetrics
         memory_used: mem_used,
         memory_total: mem_total,
         memory_percent: mem_percent,
+        disk_used,
+        disk_total,
+        disk_percent,
         load_avg_1: load.map(|(a, _, _)| a),
         load_avg_5: load.map(|(_, b, _)| b),
         load_avg_15: load.map(|(_, _, c)| c),
         cpu_cores: nproc,
         net_rx_bytes_per_sec: net_rx_rate,
         net_tx_bytes_per_sec: net_tx_rate,
+        disk_read_bytes_per_sec: disk_read_rate,
+        disk_write_bytes_per_sec: disk_write_rate,
         ssh_rtt_ms: None, // Filled by frontend from HealthTracker
         source,
     }
@@ -818,53 +1683,190 @@ fn parse_net_snapshot(output: &str) -> Option<NetSnapshot> {
     }
 }
 
+/// Parse /proc/diskstats → aggregate DiskSnapshot over physical devices
+///
+/// Partitions are skipped when their whole disk is listed (`sda1` next to
+/// `sda`, `nvme0n1p1` next to `nvme0n1`) so their I/O is not counted twice.
+fn parse_disk_snapshot(output: &str) -> Option<DiskSnapshot> {
+    let section = extract_section(output, "DISKSTATS")?;
+
+    // Format: "major minor name reads merged sectors_read ms writes merged sectors_written ..."
+    let devices: Vec<(&str, u64, u64)> = section
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() < 10 {
+                return None;
+            }
+            let name = parts[2];
+            if DISKSTATS_EXCLUDED_PREFIXES
+                .iter()
+                .any(|prefix| name.starts_with(prefix))
+            {
+                return None;
+            }
+            Some((name, parts[5].parse().ok()?, parts[9].parse().ok()?))
+        })
+        .collect();
+
+    let is_partition = |name: &str| {
+        devices.iter().any(|(disk, _, _)| {
+            name.strip_prefix(disk).is_some_and(|rest| {
+                let number = rest.strip_prefix('p').unwrap_or(rest);
+                !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit())
+            })
+        })
+    };
+
+    let mut snapshot = DiskSnapshot::default();
+    let mut found = false;
+    for (name, sectors_read, sectors_written) in &devices {
+        if is_partition(name) {
+            continue;
+        }
+        snapshot.read_bytes += sectors_read * DISKSTATS_SECTOR_SIZE;
+        snapshot.write_bytes += sectors_written * DISKSTATS_SECTOR_SIZE;
+        found = true;
+    }
+
+    found.then_some(snapshot)
+}
+
 /// Parse nproc output → core count
+///
+/// Falls back to the `===CPUINFO===` section when `nproc` is missing (minimal
+/// images): a `grep -c ^processor` count, or the `processor` lines themselves.
 fn parse_nproc(output: &str) -> Option<u32> {
-    let section = extract_section(output, "NPROC")?;
-    section.lines().next()?.trim().parse().ok()
+    let nproc = extract_section(output, "NPROC")
+        .and_then(|section| section.lines().next()?.trim().parse().ok());
+    nproc.or_else(|| parse_cpuinfo_processors(output))
+}
+
+/// Count processors from the `===CPUINFO===` section
+fn parse_cpuinfo_processors(output: &str) -> Option<u32> {
+    let section = extract_section(output, "CPUINFO")?;
+    if let Ok(count) = section.lines().next()?.trim().parse::<u32>() {
+        return (count > 0).then_some(count);
+    }
+    let count = section
+        .lines()
+        .filter(|line| line.starts_with("processor"))
+        .count() as u32;
+    (count > 0).then_some(count)
+}
+
+/// Parse `df -kP /` → (used_bytes, total_bytes)
+///
+/// Skips the header. A long device name may sit alone on its own line with
+/// the figures on the next (non-POSIX `df`), so fields are joined across
+/// lines and read from the end of the row.
+fn parse_disk_usage(output: &str) -> Option<(u64, u64)> {
+    let section = extract_section(output, "DISK")?;
+    let mut fields: Vec<&str> = Vec::new();
+
+    for line in section.lines() {
+        if line.starts_with("Filesystem") {
+            continue;
+        }
+        fields.extend(line.split_whitespace());
+        // Filesystem, size, used, available, capacity, mount point
+        if fields.len() < 6 {
+            continue;
+        }
+        let n = fields.len();
+        let total_kb: u64 = fields[n - 5].parse().ok()?;
+        let used_kb: u64 = fields[n - 4].parse().ok()?;
+        return Some((used_kb * 1024, total_kb * 1024));
+    }
+    None
 }
 
 // ─── Port Detection Parsers ──────────────────────────────────────────────
 
-/// Parse listening ports from the ===PORTS=== section, dispatching by OS type.
-fn parse_listening_ports(output: &str, os_type: &str) -> Vec<DetectedPort> {
-    let section = match extract_section(output, "PORTS") {
-        Some(s) => s,
-        None => return Vec::new(),
+/// Parse listening ports from the ===PORTS=== section, dispatching by OS family.
+fn parse_listening_ports(
+    output: &str,
+    os_family: OsFamily,
+    include_udp: bool,
+) -> Vec<DetectedPort> {
+    let Some(section) = port_section(output, "PORTS") else {
+        return Vec::new();
     };
 
-    // Strip the ===PORTS_END=== residual if present
+    let mut ports = match os_family {
+        OsFamily::Linux | OsFamily::WindowsPosix => parse_ports_ss(section),
+        OsFamily::MacOs => parse_ports_lsof(section),
+        OsFamily::Windows => {
+            let mut ports = parse_ports_powershell(section);
+            for dp in parse_ports_netstat_windows(section) {
+                merge_port(&mut ports, dp);
+            }
+            ports
+        }
+        OsFamily::Bsd => parse_ports_sockstat(section),
+        OsFamily::Unknown => parse_ports_ss(section), // fallback
+    };
+
+    if include_udp {
+        for dp in parse_udp_ports(output, os_family) {
+            merge_port(&mut ports, dp);
+        }
+    }
+
+    // Merge Docker-mapped ports (handles iptables DNAT where ss can't see them)
+    let docker_ports = parse_ports_docker(output);
+    for dp in docker_ports {
+        if include_udp || dp.protocol == PortProtocol::Tcp {
+            merge_port(&mut ports, dp);
+        }
+    }
+
+    ports
+}
+
+/// Body of a `===NAME===` … `===NAME_END===` section, `None` if missing or empty
+fn port_section<'a>(output: &'a str, name: &str) -> Option<&'a str> {
+    let section = extract_section(output, name)?;
+    // Strip the end marker residual if present
     let section = section
-        .strip_suffix("===PORTS_END===")
+        .strip_suffix(&format!("==={}_END===", name))
         .unwrap_or(section)
         .trim();
+    (!section.is_empty()).then_some(section)
+}
 
-    if section.is_empty() {
+/// Parse the `===UDP===` section (bound UDP sockets) for the given OS
+fn parse_udp_ports(output: &str, os_family: OsFamily) -> Vec<DetectedPort> {
+    let Some(section) = port_section(output, "UDP") else {
         return Vec::new();
-    }
-
-    let mut ports = match os_type {
-        "Linux" | "linux" | "Windows_MinGW" | "Windows_MSYS" | "Windows_Cygwin" => {
-            parse_ports_ss(section)
-        }
-        "macOS" | "macos" | "Darwin" => parse_ports_lsof(section),
-        "Windows" | "windows" => parse_ports_powershell(section),
-        "FreeBSD" | "freebsd" | "OpenBSD" | "NetBSD" => parse_ports_sockstat(section),
-        _ => parse_ports_ss(section), // fallback
     };
 
-    // Merge Docker-mapped ports (handles iptables DNAT where ss can't see them)
-    let docker_ports = parse_ports_docker(output);
-    if !docker_ports.is_empty() {
-        let mut seen: HashSet<u16> = ports.iter().map(|p| p.port).collect();
-        for dp in docker_ports {
-            if seen.insert(dp.port) {
-                ports.push(dp);
+    match os_family {
+        OsFamily::Linux | OsFamily::WindowsPosix | OsFamily::Unknown => parse_udp_ports_ss(section),
+        OsFamily::MacOs => as_udp(parse_ports_lsof(section)),
+        OsFamily::Windows => {
+            let mut ports = as_udp(parse_ports_powershell(section));
+            for dp in parse_udp_ports_netstat_windows(section) {
+                merge_port(&mut ports, dp);
             }
+            ports
         }
+        OsFamily::Bsd => as_udp(parse_ports_sockstat(section)),
     }
+}
 
+/// Tag ports from a protocol-agnostic parser as UDP
+///
+/// Safe after the parse: every port in one parse shares a protocol, so the
+/// dedup inside the parser is unaffected.
+fn as_udp(ports: Vec<DetectedPort>) -> Vec<DetectedPort> {
     ports
+        .into_iter()
+        .map(|dp| DetectedPort {
+            protocol: PortProtocol::Udp,
+            ..dp
+        })
+        .collect()
 }
 
 /// Parse `ss -tlnp` or `netstat -tlnp` output.
@@ -880,8 +1882,31 @@ fn parse_listening_ports(output: &str, os_type: &str) -> Vec<DetectedPort> {
 /// tcp  0  0  0.0.0.0:22  0.0.0.0:*  LISTEN  1234/sshd
 /// ```
 fn parse_ports_ss(section: &str) -> Vec<DetectedPort> {
-    let mut ports = Vec::new();
-    let mut seen = HashSet::new();
+    parse_ss_sockets(section, PortProtocol::Tcp)
+}
+
+/// Parse `ss -ulnp` or `netstat -ulnp` output.
+///
+/// UDP sockets have no LISTEN state: ss reports bound sockets as `UNCONN`,
+/// netstat leaves the State column out.
+/// ```text
+/// UNCONN  0  0  127.0.0.53%lo:53  0.0.0.0:*  users:(("systemd-resolve",pid=612,fd=13))
+/// udp     0  0  0.0.0.0:68        0.0.0.0:*  812/dhclient
+/// ```
+fn parse_udp_ports_ss(section: &str) -> Vec<DetectedPort> {
+    parse_ss_sockets(section, PortProtocol::Udp)
+}
+
+/// Shared `ss` / `netstat` parser for listening TCP or bound UDP sockets
+fn parse_ss_sockets(section: &str, protocol: PortProtocol) -> Vec<DetectedPort> {
+    // ss state of a listening socket
+    let ss_state = match protocol {
+        PortProtocol::Tcp => "listen",
+        PortProtocol::Udp => "unconn",
+    };
+    let mut ports: Vec<DetectedPort> = Vec::new();
+    // Index of the socket parsed from the previous ss line, for wrapped Process columns
+    let mut last_ss_socket: Option<usize> = None;
 
     for line in section.lines() {
         let line = line.trim();
@@ -891,26 +1916,58 @@ fn parse_ports_ss(section: &str) -> Vec<DetectedPort> {
 
         let parts: Vec<&str> = line.split_whitespace().collect();
 
+        // Column header row: "State Recv-Q Send-Q Local Address:Port ..." (ss)
+        // or "Proto Recv-Q Send-Q Local Address ..." (netstat)
+        if parts.len() >= 2
+            && (parts[0].eq_ignore_ascii_case("state") || parts[0].eq_ignore_ascii_case("proto"))
+            && parts[1].eq_ignore_ascii_case("recv-q")
+        {
+            last_ss_socket = None;
+            continue;
+        }
+
+        // Narrow terminals wrap ss's Process column onto its own line:
+        // it belongs to the socket on the previous line
+        if parts[0].starts_with("users:") {
+            if let Some(dp) = last_ss_socket.take().and_then(|i| ports.get_mut(i)) {
+                if dp.process_name.is_none() {
+                    *dp = extract_process_from_ss_users(line, dp.clone());
+                }
+            }
+            continue;
+        }
+
         // Try ss format first: LISTEN 0 128 addr:port ...
-        if parts.len() >= 4 && parts[0].eq_ignore_ascii_case("listen") {
+        if parts.len() >= 4 && parts[0].eq_ignore_ascii_case(ss_state) {
+            last_ss_socket = None;
             // addr:port is at index 3
             if let Some(dp) = parse_addr_port(parts[3]) {
                 // Extract process info from users:(...) if present
-                let mut dp = dp;
-                if let Some(users_part) = parts.iter().find(|p| p.starts_with("users:")) {
-                    dp = extract_process_from_ss_users(users_part, dp);
-                }
-                if seen.insert(dp.port) {
-                    ports.push(dp);
+                let mut dp = DetectedPort { protocol, ..dp };
+                // Rest of the line: process names may contain spaces
+                if let Some(users_start) = line.find("users:") {
+                    dp = extract_process_from_ss_users(&line[users_start..], dp);
                 }
+                last_ss_socket = merge_port(&mut ports, dp);
             }
             continue;
         }
+        last_ss_socket = None;
 
         // Try netstat format: tcp 0 0 addr:port addr:port LISTEN pid/name
-        if parts.len() >= 6 && parts.iter().any(|p| p.eq_ignore_ascii_case("listen")) {
+        // (udp rows: udp 0 0 addr:port addr:port pid/name)
+        let is_netstat_socket = match protocol {
+            PortProtocol::Tcp => {
+                parts.len() >= 6 && parts.iter().any(|p| p.eq_ignore_ascii_case("listen"))
+            }
+            PortProtocol::Udp => {
+                parts.len() >= 5 && parts[0].to_ascii_lowercase().starts_with("udp")
+            }
+        };
+        if is_netstat_socket {
             // addr:port is at index 3
-            if let Some(mut dp) = parse_addr_port(parts[3]) {
+            if let Some(dp) = parse_addr_port(parts[3]) {
+                let mut dp = DetectedPort { protocol, ..dp };
                 // pid/name is the last column
                 if let Some(last) = parts.last() {
                     if let Some((pid_str, name)) = last.split_once('/') {
@@ -918,9 +1975,7 @@ fn parse_ports_ss(section: &str) -> Vec<DetectedPort> {
                         dp.process_name = Some(name.to_string());
                     }
                 }
-                if seen.insert(dp.port) {
-                    ports.push(dp);
-                }
+                merge_port(&mut ports, dp);
             }
         }
     }
@@ -935,6 +1990,7 @@ fn parse_ports_ss(section: &str) -> Vec<DetectedPort> {
 /// 0.0.0.0:8080->80/tcp, :::8080->80/tcp
 /// 0.0.0.0:3306->3306/tcp
 /// 0.0.0.0:5432->5432/tcp, 0.0.0.0:5433->5433/tcp, :::5432->5432/tcp, :::5433->5433/tcp
+/// 0.0.0.0:51820->51820/udp    (UDP, kept only when UDP scanning is on)
 /// 80/tcp                      (exposed but not mapped - skip)
 /// ```
 fn parse_ports_docker(output: &str) -> Vec<DetectedPort> {
@@ -983,12 +2039,17 @@ fn parse_ports_docker(output: &str) -> Vec<DetectedPort> {
             }
 
             // Split on "->" → left is host side ("0.0.0.0:8080"), right is container ("80/tcp")
-            if let Some((host_part, _container_part)) = segment.split_once("->") {
+            if let Some((host_part, container_part)) = segment.split_once("->") {
+                let protocol = if container_part.ends_with("/udp") {
+                    PortProtocol::Udp
+                } else {
+                    PortProtocol::Tcp
+                };
                 // Extract host port from host_part — last colon-separated value
                 if let Some(last_colon) = host_part.rfind(':') {
                     let port_str = &host_part[last_colon + 1..];
                     if let Ok(port) = port_str.parse::<u16>() {
-                        if seen.insert(port) {
+                        if seen.insert((port, protocol)) {
                             let bind_addr = &host_part[..last_colon];
                             let bind_addr = if bind_addr.is_empty() || bind_addr == "*" {
                                 "0.0.0.0".to_string()
@@ -997,9 +2058,11 @@ fn parse_ports_docker(output: &str) -> Vec<DetectedPort> {
                             };
                             ports.push(DetectedPort {
                                 port,
+                                protocol,
                                 bind_addr,
                                 process_name: Some(format!("docker:{}", container_name)),
                                 pid: None,
+                                shared: false,
                             });
                         }
                     }
@@ -1020,7 +2083,6 @@ fn parse_ports_docker(output: &str) -> Vec<DetectedPort> {
 /// ```
 fn parse_ports_lsof(section: &str) -> Vec<DetectedPort> {
     let mut ports = Vec::new();
-    let mut seen = HashSet::new();
 
     for line in section.lines() {
         let line = line.trim();
@@ -1038,10 +2100,12 @@ fn parse_ports_lsof(section: &str) -> Vec<DetectedPort> {
 
         // TCP field is typically at index 8: "*:3000" or "[::1]:8080"
         let tcp_field = parts[8];
+        // Connected sockets ("10.0.0.2:5353->10.0.0.1:53", UDP scans) are not bound services
+        if tcp_field.contains("->") {
+            continue;
+        }
         if let Some(dp) = parse_lsof_addr(tcp_field, Some(process_name), pid) {
-            if seen.insert(dp.port) {
-                ports.push(dp);
-            }
+            merge_port(&mut ports, dp);
         }
     }
 
@@ -1057,7 +2121,6 @@ fn parse_ports_lsof(section: &str) -> Vec<DetectedPort> {
 /// ```
 fn parse_ports_powershell(section: &str) -> Vec<DetectedPort> {
     let mut ports = Vec::new();
-    let mut seen = HashSet::new();
 
     for line in section.lines() {
         let line = line.trim();
@@ -1073,14 +2136,74 @@ fn parse_ports_powershell(section: &str) -> Vec<DetectedPort> {
         let bind_addr = parts[0].to_string();
         if let Ok(port) = parts[1].parse::<u16>() {
             let pid = parts.get(2).and_then(|p| p.parse().ok());
-            if seen.insert(port) {
-                ports.push(DetectedPort {
+            merge_port(
+                &mut ports,
+                DetectedPort {
                     port,
+                    protocol: PortProtocol::Tcp,
                     bind_addr,
                     process_name: None,
                     pid,
-                });
-            }
+                    shared: false,
+                },
+            );
+        }
+    }
+
+    ports
+}
+
+/// Parse Windows `netstat -ano` output (merged with `Get-NetTCPConnection`).
+///
+/// Only TCP rows in the `LISTENING` state are kept:
+/// ```text
+///   Proto  Local Address          Foreign Address        State           PID
+///   TCP    0.0.0.0:135            0.0.0.0:0              LISTENING       1004
+///   TCP    [::]:445               [::]:0                 LISTENING       4
+///   UDP    0.0.0.0:5353           *:*                                    2460
+/// ```
+fn parse_ports_netstat_windows(section: &str) -> Vec<DetectedPort> {
+    let mut ports = Vec::new();
+
+    for line in section.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        // Proto Local Foreign State PID (UDP rows have no State column)
+        if parts.len() < 5
+            || !parts[0].eq_ignore_ascii_case("tcp")
+            || !parts[3].eq_ignore_ascii_case("listening")
+        {
+            continue;
+        }
+
+        if let Some(mut dp) = parse_addr_port(parts[1]) {
+            dp.pid = parts[4].parse().ok();
+            merge_port(&mut ports, dp);
+        }
+    }
+
+    ports
+}
+
+/// Parse Windows `netstat -ano` UDP rows (fallback for `Get-NetUDPEndpoint`).
+///
+/// UDP rows have no State column:
+/// ```text
+///   UDP    0.0.0.0:5353           *:*                                    2460
+/// ```
+fn parse_udp_ports_netstat_windows(section: &str) -> Vec<DetectedPort> {
+    let mut ports = Vec::new();
+
+    for line in section.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        // Proto Local Foreign PID
+        if parts.len() < 4 || !parts[0].eq_ignore_ascii_case("udp") {
+            continue;
+        }
+
+        if let Some(mut dp) = parse_addr_port(parts[1]) {
+            dp.protocol = PortProtocol::Udp;
+            dp.pid = parts[3].parse().ok();
+            merge_port(&mut ports, dp);
         }
     }
 
@@ -1096,7 +2219,6 @@ fn parse_ports_powershell(section: &str) -> Vec<DetectedPort> {
 /// ```
 fn parse_ports_sockstat(section: &str) -> Vec<DetectedPort> {
     let mut ports = Vec::new();
-    let mut seen = HashSet::new();
 
     for line in section.lines() {
         let line = line.trim();
@@ -1119,52 +2241,72 @@ fn parse_ports_sockstat(section: &str) -> Vec<DetectedPort> {
                 pid,
                 ..dp
             };
-            if seen.insert(dp.port) {
-                ports.push(dp);
-            }
+            merge_port(&mut ports, dp);
         }
     }
 
     ports
 }
 
-/// Parse "addr:port" format (handles IPv6 bracket notation).
-/// Examples: "0.0.0.0:8080", "[::]:3000", "*:22", ":::80"
+/// Parse "addr:port" format (handles IPv6 bracket notation and `%zone` scopes).
+/// Examples: "0.0.0.0:8080", "[::]:3000", "*:22", ":::80",
+/// "[fe80::1%eth0]:8080", "[fe80::1]%eth0:8080", "127.0.0.53%lo:53"
+///
+/// The zone is kept in `bind_addr`, inside the brackets when there are any.
 fn parse_addr_port(s: &str) -> Option<DetectedPort> {
-    // IPv6 with brackets: [::1]:8080
-    if let Some(bracket_end) = s.rfind("]:") {
-        let addr = &s[..bracket_end + 1];
-        let port_str = &s[bracket_end + 2..];
-        let port: u16 = port_str.parse().ok()?;
-        return Some(DetectedPort {
-            port,
-            bind_addr: addr.to_string(),
-            process_name: None,
-            pid: None,
-        });
-    }
-
-    // IPv6 without brackets (ss format): :::80 → addr="::", port=80
-    // Also handles *:port
-    if let Some(last_colon) = s.rfind(':') {
-        let port_str = &s[last_colon + 1..];
-        let addr = &s[..last_colon];
-        if let Ok(port) = port_str.parse::<u16>() {
-            let bind_addr = if addr.is_empty() || addr == "*" {
-                "0.0.0.0".to_string()
-            } else {
-                addr.to_string()
-            };
-            return Some(DetectedPort {
-                port,
-                bind_addr,
-                process_name: None,
-                pid: None,
-            });
+    // The port always follows the last ':' (the zone never contains one)
+    let (addr, port_str) = s.rsplit_once(':')?;
+    let port: u16 = port_str.parse().ok()?;
+
+    let bind_addr = match addr.split_once('%') {
+        // ss prints "[fe80::1]%eth0"; normalize to "[fe80::1%eth0]"
+        Some((base, zone)) if base.starts_with('[') && base.ends_with(']') => {
+            format!("{}%{}]", &base[..base.len() - 1], zone)
         }
+        Some((base, zone)) => format!("{}%{}", wildcard_to_any(base), zone),
+        None => wildcard_to_any(addr).to_string(),
+    };
+
+    Some(DetectedPort {
+        port,
+        protocol: PortProtocol::Tcp,
+        bind_addr,
+        process_name: None,
+        pid: None,
+        shared: false,
+    })
+}
+
+/// "*" and "" (from ":::80" style IPv6 wildcards) bind to every address
+fn wildcard_to_any(addr: &str) -> &str {
+    if addr.is_empty() || addr == "*" {
+        "0.0.0.0"
+    } else {
+        addr
     }
+}
 
-    None
+/// Add a port unless it is already listed, returning its index in `ports`.
+///
+/// Dedup is by port number and protocol. A listener bound to a zone-scoped address
+/// (`fe80::1%eth0`) is replaced by one on an unscoped address for the same
+/// port, so a link-local socket never hides a `0.0.0.0` or `[::]` listener.
+fn merge_port(ports: &mut Vec<DetectedPort>, dp: DetectedPort) -> Option<usize> {
+    let Some(i) = ports.iter().position(|p| p.key() == dp.key()) else {
+        ports.push(dp);
+        return Some(ports.len() - 1);
+    };
+
+    // Separate sockets of different processes on one port (SO_REUSEPORT)
+    let other_pid = matches!((ports[i].pid, dp.pid), (Some(a), Some(b)) if a != b);
+    let shared = ports[i].shared || dp.shared || other_pid;
+    if ports[i].is_zone_scoped() && !dp.is_zone_scoped() {
+        ports[i] = DetectedPort { shared, ..dp };
+        Some(i)
+    } else {
+        ports[i].shared = shared;
+        None
+    }
 }
 
 /// Parse lsof TCP field: "*:3000", "[::1]:8080", "127.0.0.1:4000"
@@ -1179,22 +2321,67 @@ fn parse_lsof_addr(
     Some(dp)
 }
 
+/// One `("name",pid=N,fd=M)` entry of an ss `users:` field
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SsUser<'a> {
+    name: &'a str,
+    pid: Option<u32>,
+    fd: Option<u32>,
+}
+
+/// Parse every entry of an ss `users:((...),(...))` field.
+///
+/// A name runs up to the `",pid=` after it, so names containing quotes,
+/// commas or parentheses are kept whole.
+fn parse_ss_users(users_field: &str) -> Vec<SsUser<'_>> {
+    let mut users = Vec::new();
+    let mut rest = users_field;
+
+    while let Some(start) = rest.find("(\"") {
+        rest = &rest[start + 2..];
+        let Some(end) = rest.find("\",pid=") else {
+            break;
+        };
+        let name = &rest[..end];
+        rest = &rest[end + 2..];
+
+        let attrs_end = rest.find(')').unwrap_or(rest.len());
+        let attrs = &rest[..attrs_end];
+        let attr = |key: &str| {
+            attrs
+                .split(',')
+                .find_map(|kv| kv.strip_prefix(key)?.parse().ok())
+        };
+        users.push(SsUser {
+            name,
+            pid: attr("pid="),
+            fd: attr("fd="),
+        });
+        rest = &rest[attrs_end..];
+    }
+    users
+}
+
 /// Extract process name and PID from ss `users:((...))` field.
-/// Format: `users:(("node",pid=1234,fd=3))`
+/// Format: `users:(("node",pid=1234,fd=3))`, or one entry per process
+/// sharing the socket: `users:(("nginx",pid=11,fd=6),("nginx",pid=10,fd=6))`.
+///
+/// The first process is reported, from its lowest-fd entry; `shared` is set
+/// when other processes hold the socket too.
 fn extract_process_from_ss_users(users_field: &str, mut dp: DetectedPort) -> DetectedPort {
-    // Find process name between quotes
-    if let Some(start) = users_field.find("((\"") {
-        let rest = &users_field[start + 3..];
-        if let Some(end) = rest.find('"') {
-            dp.process_name = Some(rest[..end].to_string());
-        }
-    }
-    // Find pid=NNNN
-    if let Some(start) = users_field.find("pid=") {
-        let rest = &users_field[start + 4..];
-        let pid_str: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
-        dp.pid = pid_str.parse().ok();
-    }
+    let users = parse_ss_users(users_field);
+    let Some(first) = users.first() else {
+        return dp;
+    };
+
+    let user = users
+        .iter()
+        .filter(|u| u.pid == first.pid)
+        .min_by_key(|u| u.fd.unwrap_or(u32::MAX))
+        .unwrap_or(first);
+    dp.process_name = Some(user.name.to_string());
+    dp.pid = user.pid;
+    dp.shared |= users.iter().any(|u| u.pid != first.pid);
     dp
 }
 
@@ -1209,21 +2396,130 @@ fn now_ms() -> u64 {
 
 fn store_metrics(
     latest: &Arc<RwLock<Option<ResourceMetrics>>>,
-    history: &Arc<RwLock<VecDeque<ResourceMetrics>>>,
+    history: &Arc<RwLock<MetricsHistory>>,
     metrics: &ResourceMetrics,
 ) {
     *latest.write().unwrap() = Some(metrics.clone());
-    let mut hist = history.write().unwrap();
-    if hist.len() >= HISTORY_CAPACITY {
-        hist.pop_front();
-    }
-    hist.push_back(metrics.clone());
+    history.write().unwrap().push(metrics);
 }
 
 fn emit_metrics(app_handle: &tauri::AppHandle, connection_id: &str, metrics: &ResourceMetrics) {
     let event_name = format!("profiler:update:{}", connection_id);
-    if let Err(e) = app_handle.emit(&event_name, metrics) {
-        warn!("Failed to emit profiler event: {}", e);
+    emit_event(app_handle, &event_name, metrics);
+}
+
+fn emit_event<T: Serialize + Clone>(app_handle: &tauri::AppHandle, event_name: &str, payload: &T) {
+    if let Err(e) = app_handle.emit(event_name, payload) {
+        warn!("Failed to emit {}: {}", event_name, e);
+    }
+}
+
+/// Emit a (possibly coalesced) port event unless the changes cancelled out
+fn emit_port_event(app_handle: &tauri::AppHandle, event_name: &str, event: &PortDetectionEvent) {
+    if !event.new_ports.is_empty() || !event.closed_ports.is_empty() {
+        emit_event(app_handle, event_name, event);
+    }
+}
+
+// ─── Emit Coalescing ──────────────────────────────────────────────────────
+
+/// Rate limit for one event kind of one connection.
+///
+/// The first value goes out immediately; values offered within `min_gap` of the
+/// last emit are merged into a single pending value, flushed once the window ends.
+struct EmitThrottle<T> {
+    min_gap: Duration,
+    last_emit: Option<Instant>,
+    pending: Option<T>,
+}
+
+impl<T> EmitThrottle<T> {
+    fn new(min_gap: Duration) -> Self {
+        Self {
+            min_gap,
+            last_emit: None,
+            pending: None,
+        }
+    }
+
+    /// Offer a value; returns what to emit now, if the window allows it.
+    /// `merge(pending, value)` folds the value into one still waiting.
+    fn offer(&mut self, now: Instant, value: T, merge: impl FnOnce(T, T) -> T) -> Option<T> {
+        let value = match self.pending.take() {
+            Some(pending) => merge(pending, value),
+            None => value,
+        };
+        if self
+            .last_emit
+            .is_some_and(|last| now.duration_since(last) < self.min_gap)
+        {
+            self.pending = Some(value);
+            return None;
+        }
+        self.last_emit = Some(now);
+        Some(value)
+    }
+
+    /// When the pending value may go out, if there is one
+    fn deadline(&self) -> Option<Instant> {
+        self.pending.as_ref()?;
+        Some(self.last_emit? + self.min_gap)
+    }
+
+    /// Take the pending value once its window has ended
+    fn flush(&mut self, now: Instant) -> Option<T> {
+        if self.deadline()? > now {
+            return None;
+        }
+        self.last_emit = Some(now);
+        self.pending.take()
+    }
+
+    /// Take the pending value regardless of the window, when no later flush will come
+    fn finish(&mut self) -> Option<T> {
+        self.pending.take()
+    }
+}
+
+/// Collapse two port events into one describing the net change.
+///
+/// The newer snapshot wins for `all_ports`. A port opened and closed within the
+/// window never reached the frontend, so it appears in neither list; likewise a
+/// port closed and reopened never left the frontend's view.
+fn merge_port_events(older: PortDetectionEvent, newer: PortDetectionEvent) -> PortDetectionEvent {
+    let open: HashSet<PortKey> = newer.all_ports.iter().map(DetectedPort::key).collect();
+    let unannounced: HashSet<PortKey> = older.new_ports.iter().map(DetectedPort::key).collect();
+    // Still open as far as the frontend knows: their close has not been emitted
+    let seen_open: HashSet<PortKey> = older.closed_ports.iter().map(DetectedPort::key).collect();
+
+    let mut new_ports: Vec<DetectedPort> = newer
+        .new_ports
+        .into_iter()
+        .filter(|p| !seen_open.contains(&p.key()))
+        .collect();
+    for port in older.new_ports {
+        if open.contains(&port.key()) && !new_ports.iter().any(|p| p.key() == port.key()) {
+            // Prefer the latest metadata for the port
+            let latest = newer.all_ports.iter().find(|p| p.key() == port.key());
+            new_ports.push(latest.cloned().unwrap_or(port));
+        }
+    }
+
+    let mut closed_ports: Vec<DetectedPort> = Vec::new();
+    for port in older.closed_ports.into_iter().chain(newer.closed_ports) {
+        if !open.contains(&port.key())
+            && !unannounced.contains(&port.key())
+            && !closed_ports.iter().any(|p| p.key() == port.key())
+        {
+            closed_ports.push(port);
+        }
+    }
+
+    PortDetectionEvent {
+        connection_id: newer.connection_id,
+        new_ports,
+        closed_ports,
+        all_ports: newer.all_ports,
     }
 }
 
@@ -1249,8 +2545,21 @@ Inter-|   Receive                                                |  Transmit
  face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
     lo: 1234567     890    0    0    0     0          0         0  1234567     890    0    0    0     0       0          0
   eth0: 987654321  12345    0    0    0     0          0         0 123456789   6789    0    0    0     0       0          0
+===DISKSTATS===
+   7       0 loop0 1280 0 4096 112 0 0 0 0 0 140 112 0 0 0 0 0 0
+ 259       0 nvme0n1 524288 10240 8388608 98304 262144 131072 4194304 65536 0 120000 163840 0 0 0 0 4096 2048
+ 259       1 nvme0n1p1 1024 0 16384 256 2 0 16 4 0 260 260 0 0 0 0 0 0
+ 259       2 nvme0n1p2 523264 10240 8372224 98048 262142 131072 4194288 65532 0 119740 163580 0 0 0 0 0 0
+   8       0 sda 4096 512 65536 2048 2048 256 32768 1024 0 3072 3072 0 0 0 0 0 0
+   8       1 sda1 4096 512 65536 2048 2048 256 32768 1024 0 3072 3072 0 0 0 0 0 0
+ 253       0 dm-0 500000 0 8000000 90000 250000 0 4000000 60000 0 110000 150000 0 0 0 0 0 0
 ===NPROC===
 4
+===CPUINFO===
+4
+===DISK===
+Filesystem     1024-blocks     Used Available Capacity Mounted on
+/dev/nvme0n1p2   490691512 98138302 367566542      22% /
 ===END==="#;
 
     #[test]
@@ -1291,6 +2600,135 @@ Inter-|   Receive                                                |  Transmit
         assert_eq!(cores, 4);
     }
 
+    #[test]
+    fn test_parse_disk_usage() {
+        let (used, total) = parse_disk_usage(SAMPLE_OUTPUT).unwrap();
+        assert_eq!(total, 490691512 * 1024);
+        assert_eq!(used, 98138302 * 1024);
+
+        let metrics = parse_metrics(SAMPLE_OUTPUT, &None);
+        assert_eq!(metrics.disk_used, Some(used));
+        assert_eq!(metrics.disk_total, Some(total));
+        let pct = metrics.disk_percent.unwrap();
+        assert!((pct - 20.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_parse_disk_usage_wrapped_device_name() {
+        // LVM device names overflow the column and push the figures to the next line
+        let output = "===DISK===
+Filesystem           1K-blocks      Used Available Use% Mounted on
+/dev/mapper/ubuntu--vg-ubuntu--lv
+                      102626232  41895164  55471804  44% /
+===END===";
+        let (used, total) = parse_disk_usage(output).unwrap();
+        assert_eq!(total, 102626232 * 1024);
+        assert_eq!(used, 41895164 * 1024);
+
+        // macOS
+        let output = "===DISK===
+Filesystem     1024-blocks      Used Available Capacity  Mounted on
+/dev/disk3s1s1   482797652  10035248 218367164     5%    /
+===END===";
+        assert_eq!(
+            parse_disk_usage(output),
+            Some((10035248 * 1024, 482797652 * 1024))
+        );
+
+        // df missing or failed
+        assert!(parse_disk_usage("===DISK===\n===END===").is_none());
+        assert!(parse_disk_usage("===NPROC===\n4\n===END===").is_none());
+    }
+
+    #[test]
+    fn test_failure_backoff() {
+        let secs = |failures| failure_backoff(DEFAULT_INTERVAL, failures).as_secs();
+        assert_eq!(secs(1), 20);
+        assert_eq!(secs(2), 40);
+        assert_eq!(secs(3), 60);
+        assert_eq!(secs(4), 60);
+        assert_eq!(secs(64), 60);
+
+        // Never faster than the configured interval
+        assert_eq!(failure_backoff(MAX_INTERVAL, 1), MAX_INTERVAL);
+        assert_eq!(failure_backoff(MIN_INTERVAL, 3), Duration::from_secs(8));
+    }
+
+    #[test]
+    fn test_profiler_state_serialization() {
+        let degraded = ProfilerState::Degraded {
+            retry_in_secs: Some(30),
+        };
+        assert_eq!(
+            serde_json::to_value(degraded).unwrap(),
+            serde_json::json!({ "state": "degraded", "retry_in_secs": 30 })
+        );
+        assert_eq!(
+            serde_json::to_value(ProfilerState::Running).unwrap(),
+            serde_json::json!({ "state": "running" })
+        );
+    }
+
+    #[test]
+    fn test_set_state_reports_each_transition_once() {
+        let state = RwLock::new(ProfilerState::Running);
+        let degraded = |secs| ProfilerState::Degraded {
+            retry_in_secs: Some(secs),
+        };
+
+        assert!(set_state(&state, "conn", ProfilerState::Running, || "ok".into()).is_none());
+
+        let event = set_state(&state, "conn", degraded(20), || {
+            "3 consecutive failures".into()
+        })
+        .unwrap();
+        assert_eq!(event.connection_id, "conn");
+        assert_eq!(event.old_state, ProfilerState::Running);
+        assert_eq!(event.new_state, degraded(20));
+        assert_eq!(event.reason, "3 consecutive failures");
+
+        // Still degraded on later ticks: the retry delay is updated silently
+        assert!(set_state(&state, "conn", degraded(40), || unreachable!()).is_none());
+        assert_eq!(*state.read().unwrap(), degraded(40));
+
+        let event = set_state(&state, "conn", ProfilerState::Stopped, || {
+            "stop requested".into()
+        })
+        .unwrap();
+        assert_eq!(event.old_state, degraded(40));
+        assert_eq!(event.new_state, ProfilerState::Stopped);
+    }
+
+    #[test]
+    fn test_parse_nproc_falls_back_to_cpuinfo() {
+        // nproc missing (minimal Alpine): the NPROC section is empty
+        let output = "===NPROC===
+===CPUINFO===
+processor\t: 0
+model name\t: Intel(R) Xeon(R) CPU E5-2680 v4 @ 2.40GHz
+processor\t: 1
+model name\t: Intel(R) Xeon(R) CPU E5-2680 v4 @ 2.40GHz
+processor\t: 2
+model name\t: Intel(R) Xeon(R) CPU E5-2680 v4 @ 2.40GHz
+processor\t: 3
+model name\t: Intel(R) Xeon(R) CPU E5-2680 v4 @ 2.40GHz
+===DISK===
+===END===";
+        assert_eq!(parse_nproc(output), Some(4));
+
+        // `grep -c` count, with unparseable nproc output
+        let output = "===NPROC===\nsh: nproc: not found\n===CPUINFO===\n8\n===END===";
+        assert_eq!(parse_nproc(output), Some(8));
+
+        // nproc wins when it works (e.g. limited by cgroup affinity)
+        let output = "===NPROC===\n2\n===CPUINFO===\n8\n===END===";
+        assert_eq!(parse_nproc(output), Some(2));
+
+        // Neither source (macOS has no /proc/cpuinfo)
+        let output = "===NPROC===\n===CPUINFO===\n0\n===END===";
+        assert_eq!(parse_nproc(output), None);
+    }
+
     #[test]
     fn test_parse_metrics_first_sample_no_delta() {
         let metrics = parse_metrics(SAMPLE_OUTPUT, &None);
@@ -1298,6 +2736,8 @@ Inter-|   Receive                                                |  Transmit
         assert!(metrics.cpu_percent.is_none());
         assert!(metrics.net_rx_bytes_per_sec.is_none());
         assert!(metrics.net_tx_bytes_per_sec.is_none());
+        assert!(metrics.disk_read_bytes_per_sec.is_none());
+        assert!(metrics.disk_write_bytes_per_sec.is_none());
         // But memory and load should be present
         assert!(metrics.memory_used.is_some());
         assert!(metrics.load_avg_1.is_some());
@@ -1322,6 +2762,10 @@ Inter-|   Receive                                                |  Transmit
                 rx_bytes: 900000000,
                 tx_bytes: 100000000,
             },
+            disk: DiskSnapshot {
+                read_bytes: 4000000000,
+                write_bytes: 2000000000,
+            },
             timestamp_ms: now_ms() - 5000,
         };
 
@@ -1329,6 +2773,62 @@ Inter-|   Receive                                                |  Transmit
         assert!(metrics.cpu_percent.is_some());
         assert!(metrics.net_rx_bytes_per_sec.is_some());
         assert!(metrics.net_tx_bytes_per_sec.is_some());
+        assert!(metrics.disk_read_bytes_per_sec.is_some());
+        assert!(metrics.disk_write_bytes_per_sec.is_some());
+    }
+
+    #[test]
+    fn test_parse_disk_snapshot() {
+        let snap = parse_disk_snapshot(SAMPLE_OUTPUT).unwrap();
+        // nvme0n1 + sda: partitions, loop and dm devices are skipped
+        assert_eq!(snap.read_bytes, (8388608 + 65536) * 512);
+        assert_eq!(snap.write_bytes, (4194304 + 32768) * 512);
+
+        // Only partitions listed (e.g. inside some containers)
+        let output = "===DISKSTATS===\n 8 1 sda1 10 0 20 0 5 0 40 0 0 0 0\n===END===";
+        let snap = parse_disk_snapshot(output).unwrap();
+        assert_eq!((snap.read_bytes, snap.write_bytes), (20 * 512, 40 * 512));
+
+        assert!(parse_disk_snapshot("===DISKSTATS===\n===NPROC===\n4").is_none());
+    }
+
+    #[test]
+    fn test_parse_metrics_disk_rate_delta() {
+        let curr = parse_disk_snapshot(SAMPLE_OUTPUT).unwrap();
+        let prev = PreviousSample {
+            cpu: CpuSnapshot::default(),
+            net: NetSnapshot::default(),
+            disk: DiskSnapshot {
+                read_bytes: curr.read_bytes - 20 * 1024 * 1024,
+                write_bytes: curr.write_bytes - 5 * 1024 * 1024,
+            },
+            timestamp_ms: now_ms() - 10_000,
+        };
+
+        let metrics = parse_metrics(SAMPLE_OUTPUT, &Some(prev));
+        // ~2 MiB/s read, ~0.5 MiB/s write over 10 s (timing slack for the test run)
+        let read = metrics.disk_read_bytes_per_sec.unwrap();
+        let write = metrics.disk_write_bytes_per_sec.unwrap();
+        assert!(
+            (2_000_000..=2_097_152).contains(&read),
+            "read rate {}",
+            read
+        );
+        assert!((500_000..=524_288).contains(&write), "write rate {}", write);
+
+        // Counters reset (device re-attached): no negative rate
+        let reset = PreviousSample {
+            cpu: CpuSnapshot::default(),
+            net: NetSnapshot::default(),
+            disk: DiskSnapshot {
+                read_bytes: u64::MAX,
+                write_bytes: u64::MAX,
+            },
+            timestamp_ms: now_ms() - 10_000,
+        };
+        let metrics = parse_metrics(SAMPLE_OUTPUT, &Some(reset));
+        assert_eq!(metrics.disk_read_bytes_per_sec, Some(0));
+        assert_eq!(metrics.disk_write_bytes_per_sec, Some(0));
     }
 
     #[test]
@@ -1337,6 +2837,132 @@ Inter-|   Receive                                                |  Transmit
         assert!(section.starts_with("0.52"));
     }
 
+    #[test]
+    fn test_extract_sample_tolerates_prompt_noise() {
+        // fish ignored the bourne init: its prompt precedes the output and the
+        // command line is echoed back, quotes included
+        let full = concat!(
+            "Welcome to fish\r\n",
+            "user@host ~> echo '===STAT==='; head -1 /proc/stat; echo '===END==='\r\n",
+            "user@host ~> ===STAT===\r\ncpu  1 2 3\r\n===END===\r\n",
+            "user@host ~> ",
+        );
+        assert_eq!(
+            extract_sample(full),
+            "===STAT===\r\ncpu  1 2 3\r\n===END==="
+        );
+
+        // The echoed command alone never counts as the end marker
+        let partial = "user@host ~> echo '===STAT==='; echo '===END==='\n";
+        assert!(find_marker(partial, "===END===").is_none());
+        assert_eq!(extract_sample(partial), partial);
+
+        // End marker at the very end of the buffer (no newline yet)
+        let clean = "===STAT===\ncpu 1\n===END===";
+        assert_eq!(find_marker(clean, "===END==="), Some(17));
+        assert_eq!(extract_sample(clean), clean);
+    }
+
+    #[test]
+    fn test_wedge_detector_repeated_truncated_reads() {
+        let mut detector = WedgeDetector::default();
+
+        // A single truncated read is tolerated
+        assert!(!detector.record(false));
+        assert!(!detector.record(true));
+        assert!(!detector.record(false));
+
+        // The second truncated read in a row triggers a reopen, then the count restarts
+        assert!(detector.record(false));
+        assert!(!detector.record(false));
+        assert!(detector.record(false));
+
+        // A truncated sample really is detected as incomplete
+        let truncated = &SAMPLE_OUTPUT[..SAMPLE_OUTPUT.len() / 2];
+        assert!(find_marker(truncated, "===END===").is_none());
+        assert!(find_marker(SAMPLE_OUTPUT, "===END===").is_some());
+    }
+
+    #[test]
+    fn test_history_downsampling_long_series() {
+        // Aligned to a 5-minute boundary so every bucket is full
+        let t0 = 5_666_667 * 300_000;
+        let sample = |i: u64| {
+            let mut m = make_empty_metrics(MetricsSource::Full);
+            m.timestamp_ms = t0 + i * 10_000;
+            m.cpu_percent = Some((i % 6) as f64 * 10.0);
+            // Every other sample lacks memory data; the gaps must not drag the mean down
+            m.memory_used = (i % 2 == 0).then_some(1_000);
+            m.cpu_cores = Some(4);
+            m
+        };
+
+        let mut history = MetricsHistory::new();
+        // 2 hours at 10s cadence
+        for i in 0..720 {
+            history.push(&sample(i));
+        }
+
+        assert_eq!(
+            history.series(HistoryResolution::Raw).len(),
+            HISTORY_CAPACITY
+        );
+
+        // 119 finished minutes plus the one in progress
+        let minutes = history.series(HistoryResolution::OneMinute);
+        assert_eq!(minutes.len(), 120);
+        assert_eq!(minutes[0].timestamp_ms, t0);
+        assert!(minutes
+            .windows(2)
+            .all(|w| w[1].timestamp_ms - w[0].timestamp_ms == 60_000));
+        for m in &minutes {
+            assert_eq!(m.cpu_percent, Some(25.0));
+            assert_eq!(m.memory_used, Some(1_000));
+            assert_eq!(m.load_avg_1, None);
+            assert_eq!(m.cpu_cores, Some(4));
+            assert_eq!(m.source, MetricsSource::Full);
+        }
+
+        let five = history.series(HistoryResolution::FiveMinutes);
+        assert_eq!(five.len(), 24);
+        assert_eq!(five[1].timestamp_ms, t0 + 300_000);
+        assert_eq!(five[0].cpu_percent, Some(25.0));
+
+        // 13 hours in total: tiers stay bounded, plus the bucket in progress
+        for i in 720..4680 {
+            history.push(&sample(i));
+        }
+        assert_eq!(
+            history.series(HistoryResolution::Raw).len(),
+            HISTORY_CAPACITY
+        );
+        assert_eq!(
+            history.series(HistoryResolution::OneMinute).len(),
+            MINUTE_HISTORY_CAPACITY + 1
+        );
+        let five = history.series(HistoryResolution::FiveMinutes);
+        assert_eq!(five.len(), FIVE_MINUTE_HISTORY_CAPACITY + 1);
+        assert_eq!(five.last().unwrap().timestamp_ms, t0 + 155 * 300_000);
+    }
+
+    #[test]
+    fn test_average_metrics_mixed_sources() {
+        let mut ok = make_empty_metrics(MetricsSource::Full);
+        ok.cpu_percent = Some(40.0);
+        ok.ssh_rtt_ms = Some(10);
+        let mut failed = make_empty_metrics(MetricsSource::Failed);
+        failed.ssh_rtt_ms = Some(21);
+
+        let avg = average_metrics(&[ok, failed.clone()], 0);
+        assert_eq!(avg.cpu_percent, Some(40.0));
+        assert_eq!(avg.ssh_rtt_ms, Some(16));
+        assert_eq!(avg.source, MetricsSource::Partial);
+
+        let avg = average_metrics(&[failed.clone(), failed], 0);
+        assert_eq!(avg.cpu_percent, None);
+        assert_eq!(avg.source, MetricsSource::Failed);
+    }
+
     #[test]
     fn test_empty_output() {
         let metrics = parse_metrics("", &None);
@@ -1362,6 +2988,212 @@ LISTEN  0  128  127.0.0.1:5432  0.0.0.0:*  users:(("postgres",pid=999,fd=5))"#;
         assert_eq!(ports[2].bind_addr, "127.0.0.1");
     }
 
+    #[test]
+    fn test_parse_ports_ss_shared_socket() {
+        let section = r#"State   Recv-Q  Send-Q  Local Address:Port  Peer Address:Port  Process
+LISTEN  0  511  0.0.0.0:80  0.0.0.0:*  users:(("nginx",pid=1202,fd=6),("nginx",pid=1201,fd=6),("nginx",pid=1200,fd=6))
+LISTEN  0  128  0.0.0.0:8000  0.0.0.0:*  users:(("gunicorn: main",pid=300,fd=9),("gunicorn: main",pid=300,fd=5))
+LISTEN  0  128  127.0.0.1:9000  0.0.0.0:*  users:(("a"b,c)",pid=77,fd=3))
+LISTEN  0  4096  0.0.0.0:7000  0.0.0.0:*  users:(("envoy",pid=500,fd=40))
+LISTEN  0  4096  0.0.0.0:7000  0.0.0.0:*  users:(("envoy",pid=501,fd=41))"#;
+        let ports = parse_ports_ss(section);
+        assert_eq!(ports.len(), 4);
+
+        // Forked workers on one socket: the first process, flagged as shared
+        assert_eq!(ports[0].port, 80);
+        assert_eq!(ports[0].process_name.as_deref(), Some("nginx"));
+        assert_eq!(ports[0].pid, Some(1202));
+        assert!(ports[0].shared);
+
+        // One process holding the socket twice: whole name, not shared
+        assert_eq!(ports[1].process_name.as_deref(), Some("gunicorn: main"));
+        assert_eq!(ports[1].pid, Some(300));
+        assert!(!ports[1].shared);
+
+        assert_eq!(ports[2].process_name.as_deref(), Some("a\"b,c)"));
+        assert_eq!(ports[2].pid, Some(77));
+
+        // SO_REUSEPORT: separate sockets of different processes
+        assert_eq!(ports[3].pid, Some(500));
+        assert!(ports[3].shared);
+
+        let users = parse_ss_users(r#"users:(("sshd",pid=900,fd=7),("sshd",pid=900,fd=3))"#);
+        assert_eq!(users.len(), 2);
+        assert_eq!(
+            users[1],
+            SsUser {
+                name: "sshd",
+                pid: Some(900),
+                fd: Some(3),
+            }
+        );
+        assert!(parse_ss_users("users:(())").is_empty());
+    }
+
+    #[test]
+    fn test_parse_ports_ss_udp() {
+        let section = r#"State   Recv-Q  Send-Q  Local Address:Port  Peer Address:Port  Process
+UNCONN  0  0  127.0.0.53%lo:53  0.0.0.0:*  users:(("systemd-resolve",pid=612,fd=13))
+UNCONN  0  0  0.0.0.0:51820  0.0.0.0:*
+UNCONN  0  0  [::]:5353  [::]:*  users:(("avahi-daemon",pid=700,fd=14))
+UNCONN  0  0  0.0.0.0:5353  0.0.0.0:*  users:(("avahi-daemon",pid=700,fd=12))"#;
+        let ports = parse_udp_ports_ss(section);
+        assert_eq!(ports.len(), 3);
+        assert!(ports.iter().all(|p| p.protocol == PortProtocol::Udp));
+        assert_eq!(ports[0].port, 53);
+        assert_eq!(ports[0].bind_addr, "127.0.0.53%lo");
+        assert_eq!(ports[0].process_name.as_deref(), Some("systemd-resolve"));
+        assert_eq!(ports[0].pid, Some(612));
+        assert_eq!(ports[1].port, 51820);
+        assert_eq!(ports[1].process_name, None);
+        assert_eq!(ports[2].port, 5353);
+        assert_eq!(ports[2].process_name.as_deref(), Some("avahi-daemon"));
+
+        // TCP parser ignores UDP rows and vice versa
+        assert!(parse_ports_ss(section).is_empty());
+        assert!(parse_udp_ports_ss("LISTEN  0  128  0.0.0.0:8080  0.0.0.0:*").is_empty());
+    }
+
+    #[test]
+    fn test_parse_ports_netstat_udp() {
+        let section = r#"Active Internet connections (only servers)
+Proto Recv-Q Send-Q Local Address  Foreign Address  State   PID/Program name
+udp        0      0 0.0.0.0:68     0.0.0.0:*                812/dhclient
+udp6       0      0 :::5353        :::*                     700/avahi-daemon"#;
+        let ports = parse_udp_ports_ss(section);
+        assert_eq!(ports.len(), 2);
+        assert_eq!(ports[0].port, 68);
+        assert_eq!(ports[0].process_name.as_deref(), Some("dhclient"));
+        assert_eq!(ports[0].pid, Some(812));
+        assert_eq!(ports[1].port, 5353);
+        assert_eq!(ports[1].bind_addr, "::");
+        assert_eq!(ports[1].protocol, PortProtocol::Udp);
+    }
+
+    #[test]
+    fn test_parse_listening_ports_udp_behind_flag() {
+        let output = r#"===PORTS===
+LISTEN  0  128  0.0.0.0:53  0.0.0.0:*  users:(("dnsmasq",pid=42,fd=5))
+LISTEN  0  128  0.0.0.0:8080  0.0.0.0:*  users:(("node",pid=1234,fd=3))
+===PORTS_END===
+===DOCKER===
+abc123	wireguard	0.0.0.0:51820->51820/udp
+===DOCKER_END===
+===UDP===
+UNCONN  0  0  0.0.0.0:53  0.0.0.0:*  users:(("dnsmasq",pid=42,fd=4))
+===UDP_END===
+===END==="#;
+
+        // Default: TCP only, Docker UDP mappings dropped
+        let ports = parse_listening_ports(output, OsFamily::Linux, false);
+        let keys: Vec<PortKey> = ports.iter().map(DetectedPort::key).collect();
+        assert_eq!(
+            keys,
+            vec![(53, PortProtocol::Tcp), (8080, PortProtocol::Tcp)]
+        );
+
+        // Port 53 on both protocols stays two entries
+        let ports = parse_listening_ports(output, OsFamily::Linux, true);
+        let keys: Vec<PortKey> = ports.iter().map(DetectedPort::key).collect();
+        assert_eq!(
+            keys,
+            vec![
+                (53, PortProtocol::Tcp),
+                (8080, PortProtocol::Tcp),
+                (53, PortProtocol::Udp),
+                (51820, PortProtocol::Udp),
+            ]
+        );
+        assert_eq!(ports[2].process_name.as_deref(), Some("dnsmasq"));
+        assert_eq!(ports[3].process_name.as_deref(), Some("docker:wireguard"));
+    }
+
+    #[test]
+    fn test_build_sample_command_udp_flag() {
+        for os_family in [
+            OsFamily::Linux,
+            OsFamily::MacOs,
+            OsFamily::Windows,
+            OsFamily::Bsd,
+        ] {
+            let tcp_only = build_sample_command(os_family, false);
+            assert!(!tcp_only.contains("===UDP==="));
+            let with_udp = build_sample_command(os_family, true);
+            assert!(with_udp.contains("===UDP===") && with_udp.contains("===UDP_END==="));
+            assert!(with_udp.ends_with("echo '===END==='\n"));
+        }
+        assert!(build_sample_command(OsFamily::Linux, true).contains("ss -ulnp"));
+    }
+
+    #[test]
+    fn test_custom_sample_command() {
+        assert_eq!(
+            custom_sample_command("  /opt/metrics.sh; echo '===END===' \n").unwrap(),
+            "/opt/metrics.sh; echo '===END==='\n"
+        );
+        assert!(custom_sample_command("/opt/metrics.sh").is_err());
+        assert!(custom_sample_command(" \n").is_err());
+
+        // What such a script might print: its own sections, plus a banner that is dropped
+        let output = "metrics.sh v2
+===STAT===
+cpu  2000 0 1000 7000 0 0 0 0 0 0
+===MEMINFO===
+MemTotal:        8000000 kB
+MemAvailable:    6000000 kB
+===LOADAVG===
+0.25 0.50 0.75 1/180 4321
+===END===";
+        let sample = extract_sample(output);
+        assert!(sample.starts_with("===STAT==="));
+
+        let metrics = parse_metrics(sample, &None);
+        assert_eq!(metrics.source, MetricsSource::Full);
+        assert_eq!(metrics.memory_total, Some(8_000_000 * 1024));
+        assert_eq!(metrics.memory_used, Some(2_000_000 * 1024));
+        assert_eq!(metrics.load_avg_15, Some(0.75));
+        assert!(metrics.disk_total.is_none());
+        assert!(parse_listening_ports(sample, OsFamily::Linux, false).is_empty());
+    }
+
+    #[test]
+    fn test_parse_ports_ss_header_row() {
+        let section = r#"State   Recv-Q  Send-Q  Local Address:Port  Peer Address:Port  Process
+LISTEN  0  128  0.0.0.0:8080  0.0.0.0:*  users:(("node",pid=1234,fd=3))"#;
+        let ports = parse_ports_ss(section);
+        assert_eq!(ports.len(), 1);
+        assert_eq!(ports[0].port, 8080);
+        assert_eq!(ports[0].process_name.as_deref(), Some("node"));
+
+        let netstat = r#"Proto Recv-Q Send-Q Local Address  Foreign Address  State   PID/Program name
+tcp  0  0  0.0.0.0:80  0.0.0.0:*  LISTEN  5678/nginx"#;
+        let ports = parse_ports_ss(netstat);
+        assert_eq!(ports.len(), 1);
+        assert_eq!(ports[0].port, 80);
+    }
+
+    #[test]
+    fn test_parse_ports_ss_wrapped_process() {
+        let section = r#"LISTEN  0  128  0.0.0.0:8080  0.0.0.0:*
+     users:(("node",pid=1234,fd=3))
+LISTEN  0  128  127.0.0.1:5432  0.0.0.0:*  users:(("postgres",pid=999,fd=5))
+LISTEN  0  128  [::]:3000  [::]:*
+	users:(("python3",pid=5678,fd=4))
+LISTEN  0  128  [::]:9000  [::]:*"#;
+        let ports = parse_ports_ss(section);
+        assert_eq!(ports.len(), 4);
+        assert_eq!(ports[0].port, 8080);
+        assert_eq!(ports[0].process_name.as_deref(), Some("node"));
+        assert_eq!(ports[0].pid, Some(1234));
+        assert_eq!(ports[1].process_name.as_deref(), Some("postgres"));
+        assert_eq!(ports[2].port, 3000);
+        assert_eq!(ports[2].process_name.as_deref(), Some("python3"));
+        assert_eq!(ports[2].pid, Some(5678));
+        // No continuation line: process stays unknown
+        assert_eq!(ports[3].port, 9000);
+        assert_eq!(ports[3].process_name, None);
+    }
+
     #[test]
     fn test_parse_ports_netstat() {
         let section = r#"tcp  0  0  0.0.0.0:22  0.0.0.0:*  LISTEN  1234/sshd
@@ -1403,6 +3235,51 @@ python3 5678  user   4u   IPv6  0x5678  0t0  TCP [::1]:8080 (LISTEN)"#;
         assert_eq!(ports[2].port, 5432);
     }
 
+    #[test]
+    fn test_parse_ports_netstat_windows() {
+        let section = r#"
+Active Connections
+
+  Proto  Local Address          Foreign Address        State           PID
+  TCP    0.0.0.0:135            0.0.0.0:0              LISTENING       1004
+  TCP    0.0.0.0:3389           0.0.0.0:0              LISTENING       1212
+  TCP    10.0.0.5:3389          10.0.0.9:51234         ESTABLISHED     1212
+  TCP    127.0.0.1:5432         0.0.0.0:0              LISTENING       2840
+  TCP    [::]:135               [::]:0                 LISTENING       1004
+  TCP    [::]:8080              [::]:0                 LISTENING       4412
+  TCP    [fe80::1%12]:9000      [::]:0                 LISTENING       77
+  UDP    0.0.0.0:5353           *:*                                    2460
+  UDP    [::]:5353              *:*                                    2460"#;
+        let ports = parse_ports_netstat_windows(section);
+        let numbers: Vec<u16> = ports.iter().map(|p| p.port).collect();
+        assert_eq!(numbers, vec![135, 3389, 5432, 8080, 9000]);
+        assert_eq!(ports[0].bind_addr, "0.0.0.0");
+        assert_eq!(ports[0].pid, Some(1004));
+        assert_eq!(ports[2].bind_addr, "127.0.0.1");
+        assert_eq!(ports[3].bind_addr, "[::]");
+        assert_eq!(ports[3].pid, Some(4412));
+        assert_eq!(ports[4].bind_addr, "[fe80::1%12]");
+    }
+
+    #[test]
+    fn test_parse_listening_ports_windows_merges_netstat() {
+        // Each source lists a port the other misses; 8080 is in both
+        let output = r#"===PORTS===
+0.0.0.0   8080  1234
+127.0.0.1 3000  5678
+  TCP    0.0.0.0:8080           0.0.0.0:0              LISTENING       1234
+  TCP    [::]:445               [::]:0                 LISTENING       4
+===PORTS_END===
+===END==="#;
+        let ports = parse_listening_ports(output, OsFamily::Windows, false);
+        assert_eq!(ports.len(), 3);
+        assert_eq!(ports[0].port, 8080);
+        assert!(!ports[0].shared);
+        assert_eq!(ports[1].port, 3000);
+        assert_eq!(ports[2].port, 445);
+        assert_eq!(ports[2].pid, Some(4));
+    }
+
     #[test]
     fn test_parse_ports_sockstat() {
         let section = r#"root  sshd   1234  3  tcp4  *:22            *:*
@@ -1430,6 +3307,53 @@ www   nginx  5678  4  tcp4  *:80            *:*"#;
         assert_eq!(dp.bind_addr, "[::]");
     }
 
+    #[test]
+    fn test_parse_addr_port_ipv6_scoped() {
+        let dp = parse_addr_port("[fe80::1%eth0]:8080").unwrap();
+        assert_eq!(dp.port, 8080);
+        assert_eq!(dp.bind_addr, "[fe80::1%eth0]");
+
+        // Zone outside the brackets is moved inside
+        let dp = parse_addr_port("[fe80::1]%eth0:8080").unwrap();
+        assert_eq!(dp.port, 8080);
+        assert_eq!(dp.bind_addr, "[fe80::1%eth0]");
+
+        let dp = parse_addr_port("fe80::1%eth0:8080").unwrap();
+        assert_eq!(dp.port, 8080);
+        assert_eq!(dp.bind_addr, "fe80::1%eth0");
+
+        // systemd-resolved style device-bound listeners
+        let dp = parse_addr_port("127.0.0.53%lo:53").unwrap();
+        assert_eq!(dp.port, 53);
+        assert_eq!(dp.bind_addr, "127.0.0.53%lo");
+        assert_eq!(parse_addr_port("*%lo:53").unwrap().bind_addr, "0.0.0.0%lo");
+
+        assert!(parse_addr_port("[fe80::1%eth0]").is_none());
+    }
+
+    #[test]
+    fn test_parse_ports_ss_scoped_listener_dedup() {
+        let section = r#"LISTEN  0  128  [fe80::1%eth0]:8080  [::]:*  users:(("agent",pid=42,fd=3))
+LISTEN  0  128  0.0.0.0:8080  0.0.0.0:*  users:(("node",pid=1234,fd=4))
+LISTEN  0  128  [fe80::1]%eth0:9090  [::]:*
+LISTEN  0  128  0.0.0.0:3000  0.0.0.0:*
+LISTEN  0  128  [fe80::1%eth0]:3000  [::]:*"#;
+        let ports = parse_ports_ss(section);
+        assert_eq!(ports.len(), 3);
+
+        // The unscoped listener wins over the link-local one on the same port
+        assert_eq!(ports[0].port, 8080);
+        assert_eq!(ports[0].bind_addr, "0.0.0.0");
+        assert_eq!(ports[0].process_name.as_deref(), Some("node"));
+
+        // A scoped-only port is still reported
+        assert_eq!(ports[1].port, 9090);
+        assert_eq!(ports[1].bind_addr, "[fe80::1%eth0]");
+
+        assert_eq!(ports[2].port, 3000);
+        assert_eq!(ports[2].bind_addr, "0.0.0.0");
+    }
+
     #[test]
     fn test_parse_addr_port_wildcard() {
         let dp = parse_addr_port("*:22").unwrap();
@@ -1466,7 +3390,7 @@ LISTEN  0  128  0.0.0.0:22  0.0.0.0:*  users:(("sshd",pid=1,fd=5))
 ===PORTS_END===
 ===END==="#;
 
-        let ports = parse_listening_ports(output, "Linux");
+        let ports = parse_listening_ports(output, OsFamily::Linux, false);
         assert_eq!(ports.len(), 2);
         assert_eq!(ports[0].port, 8080);
         assert_eq!(ports[1].port, 22);
@@ -1540,7 +3464,7 @@ LISTEN  0  128  0.0.0.0:22  0.0.0.0:*  users:(("sshd",pid=1,fd=5))
 abc123	my-app	0.0.0.0:8080->80/tcp
 ===DOCKER_END===
 ===END==="#;
-        let ports = parse_listening_ports(output, "Linux");
+        let ports = parse_listening_ports(output, OsFamily::Linux, false);
         assert_eq!(ports.len(), 2);
         assert_eq!(ports[0].port, 22);
         assert_eq!(ports[0].process_name.as_deref(), Some("sshd"));
@@ -1558,10 +3482,247 @@ LISTEN  0  128  0.0.0.0:8080  0.0.0.0:*  users:(("docker-proxy",pid=999,fd=3))
 abc123	my-app	0.0.0.0:8080->80/tcp
 ===DOCKER_END===
 ===END==="#;
-        let ports = parse_listening_ports(output, "Linux");
+        let ports = parse_listening_ports(output, OsFamily::Linux, false);
         assert_eq!(ports.len(), 1);
         assert_eq!(ports[0].port, 8080);
         // ss result takes precedence (first seen)
         assert_eq!(ports[0].process_name.as_deref(), Some("docker-proxy"));
     }
+
+    #[test]
+    fn test_port_ignore_set_mutations() {
+        let mut set = PortIgnoreSet::default();
+        set.ignore(3000);
+        set.ignore(8080);
+        assert!(set.contains(3000));
+
+        assert!(set.unignore(3000));
+        assert!(!set.unignore(3000));
+        assert!(!set.contains(3000));
+        assert!(set.contains(8080));
+
+        // Re-ignoring before the next scan cancels the baseline reset
+        set.ignore(3000);
+        let mut prev: HashSet<PortKey> = tcp_keys(&[3000, 8080]);
+        let current = prev.clone();
+        set.reset_baseline(&mut prev, &current);
+        assert_eq!(prev, current);
+
+        assert_eq!(set.clear(), 2);
+        assert!(!set.contains(3000) && !set.contains(8080));
+        assert_eq!(set.clear(), 0);
+    }
+
+    #[test]
+    fn test_port_ignore_set_clear_resets_baseline() {
+        let mut set = PortIgnoreSet::default();
+        set.ignore(3000);
+        set.ignore(5432);
+        set.clear();
+
+        // 3000 still listens and is reported as new; 5432 stopped listening
+        // and stays in the baseline so it is reported as closed as usual
+        let mut prev: HashSet<PortKey> = tcp_keys(&[3000, 5432, 8080]);
+        let current: HashSet<PortKey> = tcp_keys(&[3000, 8080]);
+        set.reset_baseline(&mut prev, &current);
+        assert_eq!(prev, tcp_keys(&[5432, 8080]));
+        let new_ports: Vec<PortKey> = current.difference(&prev).copied().collect();
+        assert_eq!(new_ports, vec![(3000, PortProtocol::Tcp)]);
+
+        // The reset is one-shot
+        let mut prev = current.clone();
+        set.reset_baseline(&mut prev, &current);
+        assert_eq!(prev, current);
+    }
+
+    #[test]
+    fn test_port_ignore_set_restored_ports() {
+        let mut set = PortIgnoreSet::with_ports([8080, 3000]);
+        assert!(set.contains(3000) && set.contains(8080));
+        assert_eq!(set.ports(), vec![3000, 8080]);
+
+        // Restored ports do not re-surface on the first scan
+        let mut prev: HashSet<PortKey> = tcp_keys(&[3000, 8080]);
+        let current = prev.clone();
+        set.reset_baseline(&mut prev, &current);
+        assert_eq!(prev, current);
+
+        set.unignore(8080);
+        set.ignore(22);
+        assert_eq!(set.ports(), vec![22, 3000]);
+    }
+
+    fn tcp_keys(ports: &[u16]) -> HashSet<PortKey> {
+        ports
+            .iter()
+            .map(|&port| (port, PortProtocol::Tcp))
+            .collect()
+    }
+
+    #[test]
+    fn test_port_ignore_set_resets_both_protocols() {
+        let mut set = PortIgnoreSet::default();
+        set.ignore(53);
+        set.unignore(53);
+
+        // Only the sockets still bound re-surface
+        let mut prev: HashSet<PortKey> = [(53, PortProtocol::Tcp), (53, PortProtocol::Udp)]
+            .into_iter()
+            .collect();
+        let current: HashSet<PortKey> = [(53, PortProtocol::Udp)].into_iter().collect();
+        set.reset_baseline(&mut prev, &current);
+        assert_eq!(prev, tcp_keys(&[53]));
+    }
+
+    #[test]
+    fn test_rescan_keeps_port_baseline() {
+        let mut baseline = PortBaseline::default();
+        let diff = baseline.observe(tcp_keys(&[22, 8080]));
+        assert!(diff.initial && diff.opened.is_empty() && diff.closed.is_empty());
+
+        // A forced rescan picks up 3000; the next scheduled scan sees the
+        // same set and must not report it again
+        let diff = baseline.observe(tcp_keys(&[22, 3000, 8080]));
+        assert_eq!(diff.opened, vec![(3000, PortProtocol::Tcp)]);
+        assert!(!diff.initial && diff.closed.is_empty());
+        assert_eq!(
+            baseline.observe(tcp_keys(&[22, 3000, 8080])),
+            PortDiff::default()
+        );
+
+        // A closed port is reported once as well
+        let diff = baseline.observe(tcp_keys(&[22, 3000]));
+        assert_eq!(diff.closed, vec![(8080, PortProtocol::Tcp)]);
+        assert_eq!(baseline.observe(tcp_keys(&[22, 3000])), PortDiff::default());
+
+        // An un-ignored port still listening re-surfaces exactly once
+        let mut set = PortIgnoreSet::default();
+        set.ignore(3000);
+        set.unignore(3000);
+        let current = tcp_keys(&[22, 3000]);
+        set.reset_baseline(&mut baseline.keys, &current);
+        assert_eq!(
+            baseline.observe(current.clone()).opened,
+            vec![(3000, PortProtocol::Tcp)]
+        );
+        assert_eq!(baseline.observe(current), PortDiff::default());
+    }
+
+    fn port(port: u16) -> DetectedPort {
+        DetectedPort {
+            port,
+            protocol: PortProtocol::Tcp,
+            bind_addr: "0.0.0.0".to_string(),
+            process_name: None,
+            pid: None,
+            shared: false,
+        }
+    }
+
+    fn port_event(new: &[u16], closed: &[u16], all: &[u16]) -> PortDetectionEvent {
+        PortDetectionEvent {
+            connection_id: "conn".to_string(),
+            new_ports: new.iter().copied().map(port).collect(),
+            closed_ports: closed.iter().copied().map(port).collect(),
+            all_ports: all.iter().copied().map(port).collect(),
+        }
+    }
+
+    fn ports_of(ports: &[DetectedPort]) -> Vec<u16> {
+        let mut numbers: Vec<u16> = ports.iter().map(|p| p.port).collect();
+        numbers.sort_unstable();
+        numbers
+    }
+
+    #[test]
+    fn test_emit_throttle_bounds_burst_and_keeps_latest() {
+        let gap = Duration::from_millis(100);
+        let mut throttle = EmitThrottle::new(gap);
+        let start = Instant::now();
+        let mut emitted = Vec::new();
+
+        // 1000 updates, one per millisecond, flushing whenever a window ends
+        for i in 0..1000u64 {
+            let now = start + Duration::from_millis(i);
+            if let Some(v) = throttle.flush(now) {
+                emitted.push(v);
+            }
+            if let Some(v) = throttle.offer(now, i, |_, latest| latest) {
+                emitted.push(v);
+            }
+        }
+        let end = start + Duration::from_millis(999);
+        let last_flush = throttle.deadline().expect("last update is pending");
+        assert!(last_flush <= end + gap);
+        emitted.push(throttle.flush(last_flush).unwrap());
+
+        // One emit per 100 ms window plus the trailing flush
+        assert!(emitted.len() <= 11, "emitted {} times", emitted.len());
+        assert_eq!(emitted[0], 0);
+        assert_eq!(*emitted.last().unwrap(), 999);
+        assert!(throttle.deadline().is_none());
+    }
+
+    #[test]
+    fn test_emit_throttle_passes_sparse_updates_through() {
+        let gap = Duration::from_millis(100);
+        let mut throttle = EmitThrottle::new(gap);
+        let start = Instant::now();
+        assert_eq!(throttle.offer(start, 1, |_, v| v), Some(1));
+        assert_eq!(throttle.offer(start + gap, 2, |_, v| v), Some(2));
+        assert!(throttle.deadline().is_none());
+    }
+
+    #[test]
+    fn test_emit_throttle_finish_takes_pending() {
+        let gap = Duration::from_millis(100);
+        let mut throttle = EmitThrottle::new(gap);
+        let start = Instant::now();
+        assert_eq!(throttle.offer(start, 1, |_, v| v), Some(1));
+        assert_eq!(throttle.offer(start, 2, |_, v| v), None);
+
+        // Stopping inside the window still hands out the latest value
+        assert_eq!(throttle.flush(start), None);
+        assert_eq!(throttle.finish(), Some(2));
+        assert_eq!(throttle.finish(), None);
+    }
+
+    #[test]
+    fn test_merge_port_events_reports_net_change() {
+        // Window starts with {3000, 8080} known to the UI
+        let first = port_event(&[5000], &[8080], &[3000, 5000]);
+        let second = port_event(&[9000], &[5000], &[3000, 9000]);
+        let third = port_event(&[8080], &[], &[3000, 8080, 9000]);
+
+        let merged = merge_port_events(merge_port_events(first, second), third);
+        // 5000 opened and closed inside the window; 8080 closed and came back,
+        // so the UI never saw it go and it is not new either
+        assert_eq!(ports_of(&merged.new_ports), vec![9000]);
+        assert!(merged.closed_ports.is_empty());
+        assert_eq!(ports_of(&merged.all_ports), vec![3000, 8080, 9000]);
+
+        let merged = merge_port_events(
+            port_event(&[], &[3000], &[8080]),
+            port_event(&[], &[8080], &[]),
+        );
+        assert_eq!(ports_of(&merged.closed_ports), vec![3000, 8080]);
+    }
+
+    #[test]
+    fn test_merge_port_events_keeps_protocols_apart() {
+        let udp = |number: u16| DetectedPort {
+            protocol: PortProtocol::Udp,
+            ..port(number)
+        };
+        // UDP 53 opened, then TCP 53 closed: neither cancels the other
+        let mut first = port_event(&[], &[], &[53]);
+        first.new_ports.push(udp(53));
+        first.all_ports.push(udp(53));
+        let mut second = port_event(&[], &[53], &[]);
+        second.all_ports.push(udp(53));
+
+        let merged = merge_port_events(first, second);
+        assert_eq!(merged.new_ports, vec![udp(53)]);
+        assert_eq!(merged.closed_ports, vec![port(53)]);
+    }
 }
@@ -381,6 +381,7 @@ mod tests {
             color: None,
             cols: 80,
             rows: 24,
+            proxy_command: None,
         };
 
         let reconnector = SessionReconnector::new(
@@ -410,6 +411,7 @@ mod tests {
             color: None,
             cols: 80,
             rows: 24,
+            proxy_command: None,
         };
 
         let reconnector = SessionReconnector::new(
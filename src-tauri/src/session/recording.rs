@@ -0,0 +1,497 @@
+//! Terminal Session Recording
+//!
+//! Writes a session's output to an [asciinema v2] cast file: a JSON header
+//! line (terminal size, start time), then one JSON array per event,
+//! `[seconds, "o", text]` for output and `[seconds, "r", "COLSxROWS"]` for
+//! resizes. Files are capped at a maximum size; once the cap is reached the
+//! recording keeps the events written so far and is marked truncated.
+//!
+//! Events are written by a dedicated thread so terminal output never waits
+//! on disk I/O.
+//!
+//! [asciinema v2]: https://docs.asciinema.org/manual/asciicast/v2/
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tokio::sync::oneshot;
+
+/// Default cap on a cast file (64 MiB)
+pub const DEFAULT_MAX_CAST_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Terminal size assumed until the first resize is seen
+const DEFAULT_SIZE: (u16, u16) = (80, 24);
+
+/// asciicast v2 header line
+#[derive(Debug, Serialize)]
+struct CastHeader<'a> {
+    version: u8,
+    width: u16,
+    height: u16,
+    /// Unix seconds
+    timestamp: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<&'a str>,
+}
+
+/// Streaming asciicast v2 writer
+pub struct CastWriter<W: Write> {
+    out: W,
+    written: u64,
+    max_bytes: u64,
+    truncated: bool,
+    /// Incomplete UTF-8 sequence at the end of the last output chunk
+    carry: Vec<u8>,
+}
+
+impl<W: Write> CastWriter<W> {
+    /// Write the header and return a writer for the events
+    pub fn new(
+        mut out: W,
+        (width, height): (u16, u16),
+        timestamp: i64,
+        title: Option<&str>,
+        max_bytes: u64,
+    ) -> io::Result<Self> {
+        let header = CastHeader {
+            version: 2,
+            width,
+            height,
+            timestamp,
+            title,
+        };
+        let mut line = serde_json::to_vec(&header)?;
+        line.push(b'\n');
+        out.write_all(&line)?;
+
+        Ok(Self {
+            out,
+            written: line.len() as u64,
+            max_bytes,
+            truncated: false,
+            carry: Vec::new(),
+        })
+    }
+
+    /// Bytes written so far, header included
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// Whether events were dropped because of the size cap
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Record terminal output `elapsed` after the start
+    ///
+    /// A multi-byte character split across chunks is held back until it is
+    /// complete; invalid bytes are replaced.
+    pub fn output(&mut self, elapsed: Duration, data: &[u8]) -> io::Result<()> {
+        let mut bytes = std::mem::take(&mut self.carry);
+        bytes.extend_from_slice(data);
+
+        let complete = match std::str::from_utf8(&bytes) {
+            Ok(_) => bytes.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => bytes.len(),
+        };
+        self.carry = bytes.split_off(complete);
+
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        self.event(elapsed, "o", &String::from_utf8_lossy(&bytes))
+    }
+
+    /// Record a terminal resize
+    pub fn resize(&mut self, elapsed: Duration, cols: u16, rows: u16) -> io::Result<()> {
+        self.event(elapsed, "r", &format!("{}x{}", cols, rows))
+    }
+
+    fn event(&mut self, elapsed: Duration, code: &str, data: &str) -> io::Result<()> {
+        if self.truncated {
+            return Ok(());
+        }
+        let mut line = serde_json::to_vec(&(elapsed.as_secs_f64(), code, data))?;
+        line.push(b'\n');
+        if self.written + line.len() as u64 > self.max_bytes {
+            self.truncated = true;
+            return Ok(());
+        }
+        self.out.write_all(&line)?;
+        self.written += line.len() as u64;
+        Ok(())
+    }
+
+    /// Flush any held-back bytes and return the underlying writer
+    pub fn finish(mut self, elapsed: Duration) -> io::Result<W> {
+        if !self.carry.is_empty() {
+            let rest = std::mem::take(&mut self.carry);
+            self.event(elapsed, "o", &String::from_utf8_lossy(&rest))?;
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+/// Result of a finished recording
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingSummary {
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub duration_ms: u64,
+    /// The size cap was reached and later events were dropped
+    pub truncated: bool,
+}
+
+/// Validate a caller-supplied recording path
+///
+/// The path must be absolute, end in `.cast` and live in an existing
+/// directory; the directory part is canonicalized.
+pub fn resolve_cast_path(path: &Path) -> io::Result<PathBuf> {
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidInput, msg.to_string());
+    if !path.is_absolute() {
+        return Err(invalid("Recording path must be absolute"));
+    }
+    if path.extension().and_then(|ext| ext.to_str()) != Some("cast") {
+        return Err(invalid("Recording path must end in .cast"));
+    }
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Err(invalid("Recording path has no file name"));
+    };
+    Ok(dir.canonicalize()?.join(name))
+}
+
+/// Create the cast file; an existing file is only replaced with `overwrite`,
+/// and never through a symlink
+fn create_cast_file(path: &Path, overwrite: bool) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true);
+    if overwrite {
+        let is_symlink = std::fs::symlink_metadata(path)
+            .map(|meta| meta.file_type().is_symlink())
+            .unwrap_or(false);
+        if is_symlink {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Refusing to overwrite a symlink",
+            ));
+        }
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    options.open(path)
+}
+
+/// Event handed to the writer thread
+enum CastEvent {
+    Output(Duration, Vec<u8>),
+    Resize(Duration, u16, u16),
+    /// Finish the file; the recording ran for this long
+    Stop(Duration),
+}
+
+/// Write events until stopped (or the recorder is dropped), then finish the file
+fn run_writer(
+    mut writer: CastWriter<BufWriter<File>>,
+    events: mpsc::Receiver<CastEvent>,
+    path: PathBuf,
+    started: Instant,
+) -> io::Result<RecordingSummary> {
+    let mut elapsed = None;
+    for event in events {
+        match event {
+            CastEvent::Output(at, data) => writer.output(at, &data)?,
+            CastEvent::Resize(at, cols, rows) => writer.resize(at, cols, rows)?,
+            CastEvent::Stop(at) => {
+                elapsed = Some(at);
+                break;
+            }
+        }
+    }
+    let elapsed = elapsed.unwrap_or_else(|| started.elapsed());
+    let truncated = writer.is_truncated();
+    writer.finish(elapsed)?;
+    Ok(RecordingSummary {
+        bytes: std::fs::metadata(&path).map_or(0, |m| m.len()),
+        path,
+        duration_ms: elapsed.as_millis() as u64,
+        truncated,
+    })
+}
+
+struct ActiveRecording {
+    events: mpsc::Sender<CastEvent>,
+    /// Resolves once the writer thread has finished the file
+    done_rx: oneshot::Receiver<io::Result<RecordingSummary>>,
+    path: PathBuf,
+    started: Instant,
+    /// Stops the output tap task
+    stop_tx: oneshot::Sender<()>,
+}
+
+impl ActiveRecording {
+    fn send(&self, event: CastEvent) -> io::Result<()> {
+        self.events
+            .send(event)
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "Recording writer has stopped"))
+    }
+}
+
+/// Per-session recording state
+///
+/// Tracks the terminal size even while idle so a new recording starts with
+/// the current dimensions in its header.
+pub struct SessionRecorder {
+    size: (u16, u16),
+    active: Option<ActiveRecording>,
+}
+
+impl SessionRecorder {
+    pub fn new() -> Self {
+        Self {
+            size: DEFAULT_SIZE,
+            active: None,
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.active.is_some()
+    }
+
+    /// Path of the running recording
+    pub fn path(&self) -> Option<&Path> {
+        self.active.as_ref().map(|a| a.path.as_path())
+    }
+
+    /// Start recording to `path`; the returned receiver fires when the
+    /// recording stops so the output tap can exit
+    ///
+    /// Fails if `path` exists, unless `overwrite` is set.
+    pub fn start(
+        &mut self,
+        path: PathBuf,
+        title: Option<&str>,
+        max_bytes: u64,
+        overwrite: bool,
+    ) -> io::Result<oneshot::Receiver<()>> {
+        if self.active.is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "Session is already being recorded",
+            ));
+        }
+
+        let file = BufWriter::new(create_cast_file(&path, overwrite)?);
+        let writer = CastWriter::new(
+            file,
+            self.size,
+            chrono::Utc::now().timestamp(),
+            title,
+            max_bytes,
+        )?;
+        let started = Instant::now();
+        let (events, events_rx) = mpsc::channel();
+        let (done_tx, done_rx) = oneshot::channel();
+        let writer_path = path.clone();
+        std::thread::Builder::new()
+            .name("cast-writer".to_string())
+            .spawn(move || {
+                let _ = done_tx.send(run_writer(writer, events_rx, writer_path, started));
+            })?;
+
+        let (stop_tx, stop_rx) = oneshot::channel();
+        self.active = Some(ActiveRecording {
+            events,
+            done_rx,
+            path,
+            started,
+            stop_tx,
+        });
+        Ok(stop_rx)
+    }
+
+    /// Record terminal output (no-op when not recording)
+    ///
+    /// Fails once the writer thread has stopped on an I/O error.
+    pub fn output(&mut self, data: &[u8]) -> io::Result<()> {
+        match &self.active {
+            Some(active) => active.send(CastEvent::Output(active.started.elapsed(), data.to_vec())),
+            None => Ok(()),
+        }
+    }
+
+    /// Note a terminal resize, recording it when active
+    pub fn resize(&mut self, cols: u16, rows: u16) -> io::Result<()> {
+        self.size = (cols, rows);
+        match &self.active {
+            Some(active) => active.send(CastEvent::Resize(active.started.elapsed(), cols, rows)),
+            None => Ok(()),
+        }
+    }
+
+    /// Stop the running recording
+    ///
+    /// The returned receiver resolves once the file is complete.
+    pub fn stop(&mut self) -> Option<oneshot::Receiver<io::Result<RecordingSummary>>> {
+        let active = self.active.take()?;
+        let _ = active.stop_tx.send(());
+        // A writer that already failed reports its error through `done_rx`
+        let _ = active.send(CastEvent::Stop(active.started.elapsed()));
+        Some(active.done_rx)
+    }
+}
+
+impl Default for SessionRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(out: Vec<u8>) -> Vec<serde_json::Value> {
+        String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_cast_header_and_timed_events() {
+        let mut writer = CastWriter::new(
+            Vec::new(),
+            (120, 40),
+            1_700_000_000,
+            Some("prod-db"),
+            DEFAULT_MAX_CAST_BYTES,
+        )
+        .unwrap();
+        writer
+            .output(Duration::from_millis(500), b"$ ls\r\n")
+            .unwrap();
+        writer.resize(Duration::from_millis(1250), 100, 30).unwrap();
+        // "é" split across two chunks
+        writer.output(Duration::from_secs(2), b"caf\xc3").unwrap();
+        writer
+            .output(Duration::from_millis(2250), b"\xa9\r\n")
+            .unwrap();
+        let lines = lines(writer.finish(Duration::from_secs(3)).unwrap());
+
+        assert_eq!(
+            lines[0],
+            serde_json::json!({
+                "version": 2,
+                "width": 120,
+                "height": 40,
+                "timestamp": 1_700_000_000,
+                "title": "prod-db"
+            })
+        );
+        assert_eq!(lines[1], serde_json::json!([0.5, "o", "$ ls\r\n"]));
+        assert_eq!(lines[2], serde_json::json!([1.25, "r", "100x30"]));
+        assert_eq!(lines[3], serde_json::json!([2.0, "o", "caf"]));
+        assert_eq!(lines[4], serde_json::json!([2.25, "o", "é\r\n"]));
+        assert_eq!(lines.len(), 5);
+    }
+
+    #[test]
+    fn test_cast_size_cap_truncates() {
+        let mut writer = CastWriter::new(Vec::new(), (80, 24), 0, None, 80).unwrap();
+        let header_len = writer.written();
+
+        writer.output(Duration::ZERO, b"ok").unwrap();
+        assert!(!writer.is_truncated());
+        writer.output(Duration::ZERO, &[b'x'; 100]).unwrap();
+        assert!(writer.is_truncated());
+        // Nothing after the cap, even if it would fit
+        writer.output(Duration::ZERO, b"y").unwrap();
+
+        assert!(writer.written() <= 80);
+        let lines = lines(writer.finish(Duration::ZERO).unwrap());
+        assert_eq!(lines.len(), 2);
+        assert!(header_len < 80);
+    }
+
+    #[test]
+    fn test_recorder_uses_latest_size() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("session.cast");
+        let mut recorder = SessionRecorder::new();
+
+        recorder.resize(132, 43).unwrap();
+        let _stop_rx = recorder
+            .start(path.clone(), None, DEFAULT_MAX_CAST_BYTES, false)
+            .unwrap();
+        assert!(recorder
+            .start(
+                dir.path().join("other.cast"),
+                None,
+                DEFAULT_MAX_CAST_BYTES,
+                false
+            )
+            .is_err());
+        recorder.output(b"hello").unwrap();
+
+        let summary = recorder.stop().unwrap().blocking_recv().unwrap().unwrap();
+        assert!(!recorder.is_recording());
+        assert_eq!(summary.path, path);
+        assert!(!summary.truncated);
+
+        let lines = lines(std::fs::read(&path).unwrap());
+        assert_eq!(lines[0]["width"], 132);
+        assert_eq!(lines[0]["height"], 43);
+        assert_eq!(lines[1][2], "hello");
+        assert_eq!(summary.bytes, std::fs::metadata(&path).unwrap().len());
+    }
+
+    #[test]
+    fn test_recorder_keeps_existing_file_unless_overwriting() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("session.cast");
+        std::fs::write(&path, "keep me").unwrap();
+        let mut recorder = SessionRecorder::new();
+
+        let err = recorder
+            .start(path.clone(), None, DEFAULT_MAX_CAST_BYTES, false)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "keep me");
+
+        let _stop_rx = recorder
+            .start(path.clone(), None, DEFAULT_MAX_CAST_BYTES, true)
+            .unwrap();
+        recorder.stop().unwrap().blocking_recv().unwrap().unwrap();
+        assert!(std::fs::read_to_string(&path)
+            .unwrap()
+            .starts_with("{\"version\":2"));
+    }
+
+    #[test]
+    fn test_resolve_cast_path() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("session.cast");
+        assert_eq!(
+            resolve_cast_path(&path).unwrap(),
+            dir.path().canonicalize().unwrap().join("session.cast")
+        );
+
+        for bad in [
+            PathBuf::from("session.cast"),
+            dir.path().join("session.txt"),
+            dir.path().join("missing").join("session.cast"),
+        ] {
+            assert!(resolve_cast_path(&bad).is_err(), "{}", bad.display());
+        }
+    }
+}
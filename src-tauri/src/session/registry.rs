@@ -10,6 +10,7 @@ use std::time::Duration;
 use tokio::sync::{broadcast, mpsc, oneshot};
 use tracing::{debug, info, warn};
 
+use super::scroll_buffer::ScrollBuffer;
 use super::state::SessionState;
 use super::types::{SessionConfig, SessionEntry, SessionInfo, SessionStats};
 use crate::ssh::{HandleController, SessionCommand};
@@ -255,8 +256,10 @@ impl SessionRegistry {
         Ok(())
     }
 
-    /// Update WebSocket info for a session after PTY recreation
-    /// Used when connection reconnects and Shell PTY needs to be recreated
+    /// Update WebSocket info for a session after PTY recreation or reattach
+    /// Used when connection reconnects and Shell PTY needs to be recreated,
+    /// or when a new bridge reattaches to a WS-detached PTY. Either way the
+    /// session is attached again, so a pending detach cleanup is cancelled.
     pub fn update_ws_info(
         &self,
         session_id: &str,
@@ -274,6 +277,10 @@ impl SessionRegistry {
         entry.ws_token = Some(ws_token);
         entry.cmd_tx = Some(cmd_tx);
         entry.handle_controller = Some(handle_controller);
+        entry.ws_detached = false;
+        if let Some(cancel) = entry.ws_detach_cancel.take() {
+            let _ = cancel.send(());
+        }
 
         info!(
             "Session {} ws_info updated after PTY recreation (port: {})",
@@ -518,6 +525,14 @@ impl SessionRegistry {
             .and_then(|entry| entry.handle_controller.clone())
     }
 
+    /// Scroll buffers of all sessions, keyed by session ID
+    pub fn scroll_buffers(&self) -> Vec<(String, Arc<ScrollBuffer>)> {
+        self.sessions
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.scroll_buffer.clone()))
+            .collect()
+    }
+
     /// List all sessions
     pub fn list(&self) -> Vec<SessionInfo> {
         let mut sessions: Vec<_> = self
@@ -708,8 +723,18 @@ impl SessionRegistry {
                 // Only save buffer if enabled in config
                 let buffer_data = if buffer_config.save_on_disconnect {
                     // Serialize buffer
-                    match entry.scroll_buffer.save_to_bytes().await {
-                        Ok(data) => Some(data),
+                    match entry.scroll_buffer.save_to_bytes(&buffer_config).await {
+                        Ok(saved) => {
+                            debug!(
+                                "Serialized buffer for {} ({} lines, {:?}): {} -> {} bytes",
+                                session_id,
+                                saved.persisted_lines,
+                                saved.compression,
+                                saved.original_size,
+                                saved.stored_size()
+                            );
+                            Some(saved.bytes)
+                        }
                         Err(e) => {
                             tracing::warn!("Failed to serialize buffer for {}: {}", session_id, e);
                             None
@@ -923,4 +948,67 @@ mod tests {
             .unwrap();
         assert_eq!(registry.active_count(), 0);
     }
+
+    /// Registry with one connected session; returns the PTY command receiver
+    fn connected_session() -> (
+        Arc<SessionRegistry>,
+        String,
+        mpsc::Sender<SessionCommand>,
+        mpsc::Receiver<SessionCommand>,
+    ) {
+        let registry = create_shared_registry();
+        let config = SessionConfig::with_password("example.com", 22, "user", "pass");
+        let id = registry.create_session(config).unwrap();
+        registry.start_connecting(&id).unwrap();
+
+        let (cmd_tx, cmd_rx) = mpsc::channel(8);
+        let (handle_tx, _) = mpsc::channel(1);
+        registry
+            .connect_success(&id, 4000, cmd_tx.clone(), HandleController::new(handle_tx))
+            .unwrap();
+        (registry, id, cmd_tx, cmd_rx)
+    }
+
+    #[tokio::test]
+    async fn test_reattach_within_grace_keeps_pty() {
+        let (registry, id, cmd_tx, mut cmd_rx) = connected_session();
+        let grace = Duration::from_millis(100);
+
+        // Page reload: bridge drops, PTY is kept for the grace period
+        registry.mark_ws_detached(&id, grace).unwrap();
+        assert!(registry.is_ws_detached(&id));
+
+        // New bridge attaches to the same PTY before the grace period ends
+        let (handle_tx, _) = mpsc::channel(1);
+        registry
+            .update_ws_info(
+                &id,
+                4001,
+                "new-token".to_string(),
+                cmd_tx,
+                HandleController::new(handle_tx),
+            )
+            .unwrap();
+        assert!(!registry.is_ws_detached(&id));
+
+        tokio::time::sleep(grace * 3).await;
+        assert!(registry.get(&id).is_some(), "reattached session was reaped");
+        assert!(cmd_rx.try_recv().is_err(), "reattached PTY was closed");
+    }
+
+    #[tokio::test]
+    async fn test_detached_session_closed_after_grace() {
+        let (registry, id, _cmd_tx, mut cmd_rx) = connected_session();
+
+        registry
+            .mark_ws_detached(&id, Duration::from_millis(50))
+            .unwrap();
+
+        let command = tokio::time::timeout(Duration::from_secs(2), cmd_rx.recv())
+            .await
+            .expect("PTY should be closed when the grace period ends");
+        assert!(matches!(command, Some(SessionCommand::Close)));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(registry.get(&id).is_none());
+    }
 }
@@ -3,17 +3,24 @@
 //! Provides a thread-safe circular buffer for terminal output with:
 //! - Configurable max lines (default 100,000)
 //! - Efficient append and range query operations
-//! - Serialization support for persistence
+//! - Serialization support for persistence (optionally zstd/LZ4 compressed)
 //! - Memory usage tracking
+//! - Trigram search index for large buffers (see `search_index`)
 
 use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use thiserror::Error;
 use tokio::sync::RwLock;
 
-use super::search::{search_lines, SearchOptions, SearchResult};
+use super::search::{
+    refine_search, search_lines, search_numbered_lines, SearchOptions, SearchResult,
+};
+use super::search_index::SearchIndex;
+use crate::state::{BufferCompression, BufferConfig};
 
 /// Default maximum lines to keep in buffer
 /// 30K lines ≈ ~3.6 MB/session (vs 100K ≈ ~11 MB).
@@ -66,6 +73,84 @@ pub struct SerializedBuffer {
     pub max_lines: usize,
 }
 
+/// Magic prefix of a saved buffer blob
+///
+/// Compressed blob layout: `OXSB` + algorithm byte + payload. Uncompressed
+/// blobs keep the original header-less MessagePack format, so they stay
+/// readable by older versions; they always start with a map marker, so they
+/// cannot be mistaken for a header.
+const BLOB_MAGIC: &[u8; 4] = b"OXSB";
+
+/// zstd level for buffer blobs (fast, reasonable ratio)
+const ZSTD_LEVEL: i32 = 3;
+
+impl BufferCompression {
+    /// Algorithm byte stored in the blob header
+    fn header_byte(self) -> u8 {
+        match self {
+            BufferCompression::None => 0,
+            BufferCompression::Zstd => 1,
+            BufferCompression::Lz4 => 2,
+        }
+    }
+
+    fn from_header_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(BufferCompression::None),
+            1 => Some(BufferCompression::Zstd),
+            2 => Some(BufferCompression::Lz4),
+            _ => None,
+        }
+    }
+}
+
+/// Scroll buffer persistence errors
+#[derive(Debug, Error)]
+pub enum ScrollBufferError {
+    #[error("Failed to encode buffer: {0}")]
+    Encode(#[from] rmp_serde::encode::Error),
+
+    #[error("Failed to decode buffer: {0}")]
+    Decode(#[from] rmp_serde::decode::Error),
+
+    #[error("{algorithm:?} compression failed: {message}")]
+    Compression {
+        algorithm: BufferCompression,
+        message: String,
+    },
+
+    #[error("Unknown buffer compression byte: {0}")]
+    UnknownCompression(u8),
+}
+
+/// Saved buffer blob with its size before and after compression
+#[derive(Debug, Clone)]
+pub struct SavedBuffer {
+    /// Header + compressed payload, or bare MessagePack when uncompressed
+    pub bytes: Vec<u8>,
+    /// Algorithm used for the payload
+    pub compression: BufferCompression,
+    /// Number of lines written (tail of the live buffer)
+    pub persisted_lines: usize,
+    /// Size of the uncompressed MessagePack payload
+    pub original_size: usize,
+}
+
+impl SavedBuffer {
+    /// Size of the stored blob, header (if any) included
+    pub fn stored_size(&self) -> usize {
+        self.bytes.len()
+    }
+}
+
+/// Most recent search, kept so the next keystroke can refine it
+struct LastSearch {
+    options: SearchOptions,
+    result: SearchResult,
+    /// `total_lines` when the search ran; any append invalidates the result
+    generation: u64,
+}
+
 /// Thread-safe scroll buffer for terminal output
 pub struct ScrollBuffer {
     /// Circular buffer of terminal lines
@@ -74,6 +159,10 @@ pub struct ScrollBuffer {
     max_lines: usize,
     /// Total lines written (including scrolled out)
     total_lines: AtomicU64,
+    /// Trigram index over `lines`; only locked while `lines` is held
+    index: Mutex<SearchIndex>,
+    /// Last search result, reused when the next query extends it
+    last_search: Mutex<Option<LastSearch>>,
 }
 
 impl ScrollBuffer {
@@ -88,6 +177,8 @@ impl ScrollBuffer {
             lines: RwLock::new(VecDeque::with_capacity(max_lines.min(1024))),
             max_lines,
             total_lines: AtomicU64::new(0),
+            index: Mutex::new(SearchIndex::Off),
+            last_search: Mutex::new(None),
         }
     }
 
@@ -95,12 +186,16 @@ impl ScrollBuffer {
     /// If buffer is full, oldest line is removed
     pub async fn append(&self, line: TerminalLine) {
         let mut lines = self.lines.write().await;
+        let mut index = self.index.lock();
 
         // Remove oldest line if at capacity
         if lines.len() >= self.max_lines {
-            lines.pop_front();
+            if let Some(evicted) = lines.pop_front() {
+                index.on_evict(&evicted.text);
+            }
         }
 
+        index.on_append(&line.text);
         lines.push_back(line);
         self.total_lines.fetch_add(1, Ordering::Relaxed);
     }
@@ -112,12 +207,16 @@ impl ScrollBuffer {
         }
 
         let mut lines = self.lines.write().await;
+        let mut index = self.index.lock();
         let count = new_lines.len();
 
         for line in new_lines {
             if lines.len() >= self.max_lines {
-                lines.pop_front();
+                if let Some(evicted) = lines.pop_front() {
+                    index.on_evict(&evicted.text);
+                }
             }
+            index.on_append(&line.text);
             lines.push_back(line);
         }
 
@@ -178,6 +277,8 @@ impl ScrollBuffer {
     pub async fn clear(&self) {
         let mut lines = self.lines.write().await;
         lines.clear();
+        self.index.lock().reset();
+        *self.last_search.lock() = None;
         // Note: We don't reset total_lines counter - it's a historical count
     }
 
@@ -194,27 +295,102 @@ impl ScrollBuffer {
     }
 
     /// Serialize buffer to bytes for persistence
-    pub async fn save_to_bytes(&self) -> Result<Vec<u8>, rmp_serde::encode::Error> {
-        let lines = self.lines.read().await;
+    ///
+    /// Only the last `config.persist_max_lines` lines are written; the
+    /// restored buffer keeps the live capacity and total line count. A
+    /// compressed blob carries its algorithm in a header so `load_from_bytes`
+    /// can decode it without knowing the current setting; an uncompressed
+    /// one is written in the header-less legacy format.
+    pub async fn save_to_bytes(
+        &self,
+        config: &BufferConfig,
+    ) -> Result<SavedBuffer, ScrollBufferError> {
+        let compression = config.compression;
+        let serialized = {
+            let lines = self.lines.read().await;
+            let skip = lines.len().saturating_sub(config.persist_max_lines);
+            SerializedBuffer {
+                lines: lines.iter().skip(skip).cloned().collect(),
+                total_lines: self.total_lines.load(Ordering::Relaxed),
+                captured_at: Utc::now(),
+                max_lines: self.max_lines,
+            }
+        };
+        let persisted_lines = serialized.lines.len();
+
+        let payload = rmp_serde::to_vec_named(&serialized)?;
+        let original_size = payload.len();
+        let compressed = match compression {
+            // Uncompressed blobs keep the header-less legacy format
+            BufferCompression::None => None,
+            BufferCompression::Zstd => Some(
+                zstd::encode_all(payload.as_slice(), ZSTD_LEVEL).map_err(|e| {
+                    ScrollBufferError::Compression {
+                        algorithm: compression,
+                        message: e.to_string(),
+                    }
+                })?,
+            ),
+            BufferCompression::Lz4 => Some(lz4_flex::compress_prepend_size(&payload)),
+        };
 
-        let serialized = SerializedBuffer {
-            lines: lines.iter().cloned().collect(),
-            total_lines: self.total_lines.load(Ordering::Relaxed),
-            captured_at: Utc::now(),
-            max_lines: self.max_lines,
+        let bytes = match compressed {
+            Some(compressed) => {
+                let mut bytes = Vec::with_capacity(BLOB_MAGIC.len() + 1 + compressed.len());
+                bytes.extend_from_slice(BLOB_MAGIC);
+                bytes.push(compression.header_byte());
+                bytes.extend_from_slice(&compressed);
+                bytes
+            }
+            None => payload,
         };
 
-        rmp_serde::to_vec_named(&serialized)
+        Ok(SavedBuffer {
+            bytes,
+            compression,
+            persisted_lines,
+            original_size,
+        })
     }
 
     /// Load buffer from serialized bytes
-    pub async fn load_from_bytes(data: &[u8]) -> Result<Arc<Self>, rmp_serde::decode::Error> {
-        let serialized: SerializedBuffer = rmp_serde::from_slice(data)?;
+    ///
+    /// The compression algorithm is detected from the blob header; blobs
+    /// without a header (uncompressed, or from before compression support)
+    /// are read as plain MessagePack.
+    pub async fn load_from_bytes(data: &[u8]) -> Result<Arc<Self>, ScrollBufferError> {
+        let serialized: SerializedBuffer = match data.strip_prefix(BLOB_MAGIC.as_slice()) {
+            Some([algorithm, payload @ ..]) => {
+                let compression = BufferCompression::from_header_byte(*algorithm)
+                    .ok_or(ScrollBufferError::UnknownCompression(*algorithm))?;
+                let compression_err = |message: String| ScrollBufferError::Compression {
+                    algorithm: compression,
+                    message,
+                };
+                match compression {
+                    BufferCompression::None => rmp_serde::from_slice(payload)?,
+                    BufferCompression::Zstd => {
+                        let raw = zstd::decode_all(payload)
+                            .map_err(|e| compression_err(e.to_string()))?;
+                        rmp_serde::from_slice(&raw)?
+                    }
+                    BufferCompression::Lz4 => {
+                        let raw = lz4_flex::decompress_size_prepended(payload)
+                            .map_err(|e| compression_err(e.to_string()))?;
+                        rmp_serde::from_slice(&raw)?
+                    }
+                }
+            }
+            // Header-less: uncompressed, or from before compression support
+            _ => rmp_serde::from_slice(data)?,
+        };
 
         let buffer = Self {
             lines: RwLock::new(serialized.lines.into_iter().collect()),
             max_lines: serialized.max_lines,
             total_lines: AtomicU64::new(serialized.total_lines),
+            index: Mutex::new(SearchIndex::Off),
+            last_search: Mutex::new(None),
         };
 
         Ok(Arc::new(buffer))
@@ -232,24 +408,72 @@ impl ScrollBuffer {
 
     /// Search buffer contents asynchronously
     /// Uses spawn_blocking to avoid blocking the tokio runtime.
-    /// Reads directly from the lock to avoid cloning all lines.
+    /// Large buffers consult the trigram index and only snapshot candidate lines,
+    /// unless context lines are requested.
+    /// A query extending the previous one only re-checks its matches.
     pub async fn search(&self, options: SearchOptions) -> SearchResult {
         let lines = self.lines.read().await;
-        // Collect a Vec<TerminalLine> only of the current buffer snapshot
-        // so we can move it into spawn_blocking (RwLockReadGuard is !Send).
-        let snapshot: Vec<TerminalLine> = lines.iter().cloned().collect();
-        drop(lines); // release lock before blocking
+        let generation = self.total_lines.load(Ordering::Relaxed);
+
+        let refined = self
+            .last_search
+            .lock()
+            .as_ref()
+            .filter(|last| last.generation == generation)
+            .and_then(|last| refine_search(&last.result, &last.options, options.clone()));
+        if let Some(result) = refined {
+            self.remember_search(options, &result, generation);
+            return result;
+        }
+
+        // Context lines need the neighbours of each match, not just candidates
+        let wants_context = options.context_before > 0 || options.context_after > 0;
+        let candidates = if wants_context {
+            None
+        } else {
+            self.index.lock().candidates(&lines, &options)
+        };
+
+        // Snapshot the lines to search so they can move into spawn_blocking
+        // (RwLockReadGuard is !Send).
+        let task = match candidates {
+            Some(positions) => {
+                let options = options.clone();
+                let snapshot: Vec<(usize, TerminalLine)> = positions
+                    .into_iter()
+                    .map(|pos| (pos, lines[pos].clone()))
+                    .collect();
+                drop(lines); // release lock before blocking
+                tokio::task::spawn_blocking(move || {
+                    search_numbered_lines(snapshot.iter().map(|(pos, line)| (*pos, line)), options)
+                })
+            }
+            None => {
+                let options = options.clone();
+                let snapshot: Vec<TerminalLine> = lines.iter().cloned().collect();
+                drop(lines); // release lock before blocking
+                tokio::task::spawn_blocking(move || search_lines(&snapshot, options))
+            }
+        };
 
         // Execute search in a blocking task to avoid blocking the async runtime
-        tokio::task::spawn_blocking(move || search_lines(&snapshot, options))
-            .await
-            .unwrap_or_else(|_| SearchResult {
-                matches: vec![],
-                total_matches: 0,
-                duration_ms: 0,
-                truncated: false,
-                error: Some("Search task failed".to_string()),
-            })
+        let result = task.await.unwrap_or_else(|_| SearchResult {
+            matches: vec![],
+            total_matches: 0,
+            duration_ms: 0,
+            truncated: false,
+            error: Some("Search task failed".to_string()),
+        });
+        self.remember_search(options, &result, generation);
+        result
+    }
+
+    fn remember_search(&self, options: SearchOptions, result: &SearchResult, generation: u64) {
+        *self.last_search.lock() = Some(LastSearch {
+            options,
+            result: result.clone(),
+            generation,
+        });
     }
 }
 
@@ -335,8 +559,11 @@ mod tests {
         buffer.append(TerminalLine::new("line 1".to_string())).await;
         buffer.append(TerminalLine::new("line 2".to_string())).await;
 
-        let bytes = buffer.save_to_bytes().await.unwrap();
-        let restored = ScrollBuffer::load_from_bytes(&bytes).await.unwrap();
+        let saved = buffer
+            .save_to_bytes(&BufferConfig::default())
+            .await
+            .unwrap();
+        let restored = ScrollBuffer::load_from_bytes(&saved.bytes).await.unwrap();
 
         assert_eq!(restored.len().await, 2);
         let lines = restored.get_all().await;
@@ -344,6 +571,127 @@ mod tests {
         assert_eq!(lines[1].text, "line 2");
     }
 
+    async fn filled_buffer() -> ScrollBuffer {
+        let buffer = ScrollBuffer::with_capacity(500);
+        for i in 0..400 {
+            buffer
+                .append(TerminalLine::with_timestamp(
+                    format!("drwxr-xr-x  2 root root 4096 Jan  1 00:00 dir-{}", i),
+                    1_700_000_000_000 + i,
+                ))
+                .await;
+        }
+        buffer
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_each_compression() {
+        let buffer = filled_buffer().await;
+
+        for compression in [
+            BufferCompression::None,
+            BufferCompression::Zstd,
+            BufferCompression::Lz4,
+        ] {
+            let config = BufferConfig {
+                compression,
+                ..BufferConfig::default()
+            };
+            let saved = buffer.save_to_bytes(&config).await.unwrap();
+            assert_eq!(saved.compression, compression);
+            if compression == BufferCompression::None {
+                // Legacy header-less format
+                assert!(!saved.bytes.starts_with(BLOB_MAGIC));
+                assert_eq!(saved.stored_size(), saved.original_size);
+            } else {
+                assert_eq!(&saved.bytes[..4], BLOB_MAGIC);
+                assert_eq!(saved.bytes[4], compression.header_byte());
+                // Repetitive terminal output compresses well
+                assert!(saved.stored_size() < saved.original_size / 2);
+            }
+
+            let restored = ScrollBuffer::load_from_bytes(&saved.bytes).await.unwrap();
+            assert_eq!(restored.len().await, 400);
+            assert_eq!(restored.max_lines(), 500);
+            assert_eq!(restored.total_lines(), 400);
+            let lines = restored.get_all().await;
+            assert_eq!(
+                lines[0].text,
+                "drwxr-xr-x  2 root root 4096 Jan  1 00:00 dir-0"
+            );
+            assert_eq!(lines[399].timestamp, 1_700_000_000_399);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_detects_format_from_header() {
+        let buffer = filled_buffer().await;
+
+        // Legacy header-less MessagePack blob
+        let legacy = rmp_serde::to_vec_named(&SerializedBuffer {
+            lines: buffer.get_all().await,
+            total_lines: buffer.total_lines(),
+            captured_at: Utc::now(),
+            max_lines: buffer.max_lines(),
+        })
+        .unwrap();
+        let restored = ScrollBuffer::load_from_bytes(&legacy).await.unwrap();
+        assert_eq!(restored.len().await, 400);
+
+        // The header, not the payload, decides how to decode
+        let mut saved = buffer
+            .save_to_bytes(&BufferConfig {
+                compression: BufferCompression::Lz4,
+                ..BufferConfig::default()
+            })
+            .await
+            .unwrap()
+            .bytes;
+        saved[4] = BufferCompression::Zstd.header_byte();
+        assert!(matches!(
+            ScrollBuffer::load_from_bytes(&saved).await,
+            Err(ScrollBufferError::Compression {
+                algorithm: BufferCompression::Zstd,
+                ..
+            })
+        ));
+
+        saved[4] = 0xff;
+        assert!(matches!(
+            ScrollBuffer::load_from_bytes(&saved).await,
+            Err(ScrollBufferError::UnknownCompression(0xff))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_persist_keeps_only_tail() {
+        let buffer = ScrollBuffer::with_capacity(100_000);
+        buffer
+            .append_batch(
+                (0..50_000)
+                    .map(|i| TerminalLine::with_timestamp(format!("line {}", i), i))
+                    .collect(),
+            )
+            .await;
+
+        let config = BufferConfig {
+            persist_max_lines: 5_000,
+            ..BufferConfig::default()
+        };
+        let saved = buffer.save_to_bytes(&config).await.unwrap();
+        assert_eq!(saved.persisted_lines, 5_000);
+        // Live buffer is untouched
+        assert_eq!(buffer.len().await, 50_000);
+
+        let restored = ScrollBuffer::load_from_bytes(&saved.bytes).await.unwrap();
+        assert_eq!(restored.len().await, 5_000);
+        assert_eq!(restored.max_lines(), 100_000);
+        assert_eq!(restored.total_lines(), 50_000);
+        let lines = restored.get_all().await;
+        assert_eq!(lines[0].text, "line 45000");
+        assert_eq!(lines[4_999].text, "line 49999");
+    }
+
     #[tokio::test]
     async fn test_batch_append() {
         let buffer = ScrollBuffer::with_capacity(10);
@@ -360,6 +708,101 @@ mod tests {
         assert_eq!(buffer.total_lines(), 3);
     }
 
+    #[tokio::test]
+    async fn test_indexed_search_matches_full_scan() {
+        let buffer = ScrollBuffer::with_capacity(12_000);
+        let lines: Vec<TerminalLine> = (0..15_000)
+            .map(|i| match i % 3 {
+                0 => TerminalLine::new(format!("GET /api/items/{} 200", i)),
+                1 => TerminalLine::new(format!("error: timeout after {}ms", i % 500)),
+                _ => TerminalLine::new(format!("$ ls -la /var/log # {}", i)),
+            })
+            .collect();
+        // Index is built on the first search, then kept up to date by appends
+        buffer.append_batch(lines[..11_000].to_vec()).await;
+        let options = |query: &str| SearchOptions {
+            query: query.to_string(),
+            case_sensitive: false,
+            regex: false,
+            whole_word: false,
+            max_matches: 0,
+            context_before: 0,
+            context_after: 0,
+        };
+        buffer.search(options("ERROR")).await;
+        for line in &lines[11_000..] {
+            buffer.append(line.clone()).await;
+        }
+        assert!(matches!(*buffer.index.lock(), SearchIndex::Active(_)));
+
+        let snapshot = buffer.get_all().await;
+        for query in ["ERROR", "api/items/1499", "/var/log # 3", "no match here"] {
+            let indexed = buffer.search(options(query)).await;
+            let scanned = search_lines(&snapshot, options(query));
+            assert_eq!(indexed.total_matches, scanned.total_matches, "{}", query);
+            let positions = |r: &SearchResult| {
+                r.matches
+                    .iter()
+                    .map(|m| (m.line_number, m.column_start, m.column_end))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(positions(&indexed), positions(&scanned), "{}", query);
+        }
+
+        buffer.clear().await;
+        assert!(matches!(*buffer.index.lock(), SearchIndex::Off));
+    }
+
+    #[tokio::test]
+    async fn test_search_refines_until_buffer_changes() {
+        let buffer = ScrollBuffer::new();
+        buffer
+            .append(TerminalLine::new("error: disk full".to_string()))
+            .await;
+        buffer
+            .append(TerminalLine::new("all good".to_string()))
+            .await;
+        buffer
+            .append(TerminalLine::new("errno 2".to_string()))
+            .await;
+
+        let options = |query: &str| SearchOptions {
+            query: query.to_string(),
+            case_sensitive: false,
+            regex: false,
+            whole_word: false,
+            max_matches: 100,
+            context_before: 0,
+            context_after: 0,
+        };
+        let generation = |buffer: &ScrollBuffer| {
+            buffer
+                .last_search
+                .lock()
+                .as_ref()
+                .map(|last| last.generation)
+        };
+
+        assert_eq!(buffer.search(options("err")).await.total_matches, 2);
+        let refined = buffer.search(options("error")).await;
+        assert_eq!(refined.total_matches, 1);
+        assert_eq!(refined.matches[0].line_number, 0);
+        assert_eq!(generation(&buffer), Some(3));
+
+        // A new line invalidates the cached result, so it must be found
+        buffer
+            .append(TerminalLine::new("another error".to_string()))
+            .await;
+        let result = buffer.search(options("error:")).await;
+        assert_eq!(result.total_matches, 1);
+        let result = buffer.search(options("error")).await;
+        assert_eq!(result.total_matches, 2);
+        assert_eq!(generation(&buffer), Some(4));
+
+        buffer.clear().await;
+        assert!(buffer.last_search.lock().is_none());
+    }
+
     #[tokio::test]
     async fn test_get_range() {
         let buffer = ScrollBuffer::new();
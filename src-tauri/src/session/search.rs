@@ -6,8 +6,9 @@
 //! - Multi-threaded execution (spawn_blocking)
 //! - Performance optimized for large buffers
 
-use regex::RegexBuilder;
+use regex::{Regex, RegexBuilder};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::time::Instant;
 
 use super::scroll_buffer::TerminalLine;
@@ -26,6 +27,12 @@ pub struct SearchOptions {
     /// Maximum matches to return (0 = unlimited, default 1000)
     #[serde(default = "default_max_matches")]
     pub max_matches: usize,
+    /// Lines of context to include before each match (like `grep -B`)
+    #[serde(default)]
+    pub context_before: usize,
+    /// Lines of context to include after each match (like `grep -A`)
+    #[serde(default)]
+    pub context_after: usize,
 }
 
 fn default_max_matches() -> usize {
@@ -33,7 +40,7 @@ fn default_max_matches() -> usize {
 }
 
 /// Single search match result
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchMatch {
     /// Line number in buffer (0-indexed)
     pub line_number: usize,
@@ -45,10 +52,16 @@ pub struct SearchMatch {
     pub matched_text: String,
     /// Full line content for context
     pub line_content: String,
+    /// Up to `context_before` lines preceding the match, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lines_before: Vec<String>,
+    /// Up to `context_after` lines following the match
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lines_after: Vec<String>,
 }
 
 /// Search result with all matches and metadata
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResult {
     /// All matches found (up to max_matches)
     pub matches: Vec<SearchMatch>,
@@ -60,16 +73,13 @@ pub struct SearchResult {
     #[serde(default)]
     pub truncated: bool,
     /// Error message if regex is invalid (None = no error)
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub error: Option<String>,
 }
 
-/// Execute search on terminal lines
-pub fn search_lines(lines: &[TerminalLine], options: SearchOptions) -> SearchResult {
-    let start = Instant::now();
-    let mut matches = Vec::new();
-
-    // Build regex pattern
+/// Compile the regex described by `options` (query, regex/literal, whole
+/// word and case flags); `max_matches` is ignored
+pub fn build_search_regex(options: &SearchOptions) -> Result<Regex, regex::Error> {
     let pattern = if options.regex {
         // User provided regex
         options.query.clone()
@@ -83,11 +93,59 @@ pub fn search_lines(lines: &[TerminalLine], options: SearchOptions) -> SearchRes
         }
     };
 
-    // Build regex with appropriate flags
-    let regex = match RegexBuilder::new(&pattern)
+    RegexBuilder::new(&pattern)
         .case_insensitive(!options.case_sensitive)
         .build()
-    {
+}
+
+/// Execute search on terminal lines
+pub fn search_lines(lines: &[TerminalLine], options: SearchOptions) -> SearchResult {
+    let (before, after) = (options.context_before, options.context_after);
+    let mut result = search_numbered_lines(lines.iter().enumerate(), options);
+    attach_context(&mut result, lines, before, after);
+    result
+}
+
+/// Fill `lines_before` / `lines_after` of each match from the buffer its
+/// line numbers refer to
+///
+/// Context is clipped at the start and end of the buffer, so a match on line
+/// 0 has no lines before it whatever `before` is.
+pub fn attach_context(
+    result: &mut SearchResult,
+    lines: &[TerminalLine],
+    before: usize,
+    after: usize,
+) {
+    if before == 0 && after == 0 {
+        return;
+    }
+
+    let text = |range: &[TerminalLine]| range.iter().map(|line| line.text.clone()).collect();
+    for m in &mut result.matches {
+        let n = m.line_number;
+        if n >= lines.len() {
+            continue;
+        }
+        let end = n.saturating_add(after).saturating_add(1).min(lines.len());
+        m.lines_before = text(&lines[n.saturating_sub(before)..n]);
+        m.lines_after = text(&lines[n + 1..end]);
+    }
+}
+
+/// Execute search on a subset of buffer lines, each paired with its line number
+///
+/// Used with search index candidates; numbers must be ascending so matches
+/// come out in buffer order. Context lines are not filled in since the
+/// neighbours of a candidate aren't available (see [`attach_context`]).
+pub fn search_numbered_lines<'a>(
+    lines: impl IntoIterator<Item = (usize, &'a TerminalLine)>,
+    options: SearchOptions,
+) -> SearchResult {
+    let start = Instant::now();
+    let mut matches = Vec::new();
+
+    let regex = match build_search_regex(&options) {
         Ok(re) => re,
         Err(e) => {
             // Invalid regex, return error with message
@@ -107,7 +165,7 @@ pub fn search_lines(lines: &[TerminalLine], options: SearchOptions) -> SearchRes
     let capped = limit < usize::MAX;
 
     // Search through all lines
-    for (_line_number, line) in lines.iter().enumerate() {
+    for (line_number, line) in lines {
         // Find all matches in this line; share line_content across matches on the same line
         let mut line_content_cached: Option<String> = None;
         for cap in regex.find_iter(&line.text) {
@@ -122,11 +180,13 @@ pub fn search_lines(lines: &[TerminalLine], options: SearchOptions) -> SearchRes
                     }
                 };
                 matches.push(SearchMatch {
-                    line_number: _line_number,
+                    line_number,
                     column_start: cap.start(),
                     column_end: cap.end(),
                     matched_text: cap.as_str().to_string(),
                     line_content,
+                    lines_before: Vec::new(),
+                    lines_after: Vec::new(),
                 });
             } else if !capped {
                 // Unlimited mode: should not happen, but break defensively
@@ -148,6 +208,141 @@ pub fn search_lines(lines: &[TerminalLine], options: SearchOptions) -> SearchRes
     }
 }
 
+/// Search several buffers at once, one `spawn_blocking` task per buffer
+///
+/// Results are keyed by the ID paired with each buffer. `options.max_matches`
+/// is a budget shared by all buffers rather than a per-buffer cap (see
+/// [`share_match_budget`]).
+pub async fn search_buffers(
+    buffers: Vec<(String, Vec<TerminalLine>)>,
+    options: SearchOptions,
+) -> HashMap<String, SearchResult> {
+    let budget = options.max_matches;
+    let tasks = buffers.into_iter().map(|(id, lines)| {
+        let options = options.clone();
+        async move {
+            let result = tokio::task::spawn_blocking(move || search_lines(&lines, options))
+                .await
+                .unwrap_or_else(|_| SearchResult {
+                    matches: vec![],
+                    total_matches: 0,
+                    duration_ms: 0,
+                    truncated: false,
+                    error: Some("Search task failed".to_string()),
+                });
+            (id, result)
+        }
+    });
+
+    let mut results: HashMap<String, SearchResult> = futures_util::future::join_all(tasks)
+        .await
+        .into_iter()
+        .collect();
+    share_match_budget(&mut results, budget);
+    results
+}
+
+/// Trim per-buffer matches so together they fit in `budget` (0 = unlimited)
+///
+/// The budget is split evenly; buffers needing less than their share hand the
+/// rest to the others, so one noisy session can't crowd out the quiet ones.
+/// `total_matches` keeps the real count and trimmed results are marked
+/// `truncated`.
+pub fn share_match_budget(results: &mut HashMap<String, SearchResult>, budget: usize) {
+    if budget == 0 {
+        return;
+    }
+
+    // Smallest demand first; ties by ID so the split is deterministic
+    let mut demand: Vec<(String, usize)> = results
+        .iter()
+        .map(|(id, result)| (id.clone(), result.matches.len()))
+        .collect();
+    demand.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+
+    let mut remaining = budget;
+    let mut left = demand.len();
+    for (id, wanted) in demand {
+        let allowance = wanted.min(remaining / left);
+        if let Some(result) = results.get_mut(&id) {
+            if allowance < result.matches.len() {
+                result.matches.truncate(allowance);
+                result.truncated = true;
+            }
+        }
+        remaining -= allowance;
+        left -= 1;
+    }
+}
+
+/// Whether every line matching `next` also matches `previous`
+///
+/// True for literal queries with the same case sensitivity when `next`
+/// contains `previous`: a line containing the longer text contains the
+/// shorter one. Regex changes and case toggles can't be proven this way.
+fn is_refinement(previous: &SearchOptions, next: &SearchOptions) -> bool {
+    if previous.regex || next.regex || previous.case_sensitive != next.case_sensitive {
+        return false;
+    }
+    // A whole-word match of "error" is not a whole-word match of "err"
+    if previous.whole_word {
+        return false;
+    }
+    if previous.case_sensitive {
+        next.query.contains(&previous.query)
+    } else if previous.query.is_ascii() && next.query.is_ascii() {
+        next.query
+            .to_ascii_lowercase()
+            .contains(&previous.query.to_ascii_lowercase())
+    } else {
+        // Unicode case folding can change lengths; only trust an exact extension
+        next.query.contains(&previous.query)
+    }
+}
+
+/// Re-run a search over only the lines matched by `previous`
+///
+/// Used while the user types ("err" → "erro" → "error"): each query narrows
+/// the last one, so the previous match set already holds every candidate
+/// line. Returns `None` when that can't be proven — a different regex flag or
+/// case setting, a whole-word previous search, or a truncated/failed previous
+/// result, or a search asking for context lines the previous matches can't
+/// supply — and the caller must run a full search instead.
+///
+/// `previous` must come from the same, unchanged buffer.
+pub fn refine_search(
+    previous: &SearchResult,
+    previous_options: &SearchOptions,
+    options: SearchOptions,
+) -> Option<SearchResult> {
+    if previous.truncated || previous.error.is_some() || !is_refinement(previous_options, &options)
+    {
+        return None;
+    }
+    if options.context_before > 0 || options.context_after > 0 {
+        return None;
+    }
+
+    let mut candidates: Vec<(usize, TerminalLine)> = previous
+        .matches
+        .iter()
+        .map(|m| {
+            (
+                m.line_number,
+                TerminalLine::with_timestamp(m.line_content.clone(), 0),
+            )
+        })
+        .collect();
+    candidates.dedup_by_key(|(line_number, _)| *line_number);
+
+    Some(search_numbered_lines(
+        candidates
+            .iter()
+            .map(|(line_number, line)| (*line_number, line)),
+        options,
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -168,7 +363,10 @@ mod tests {
             query: "Hello".to_string(),
             case_sensitive: true,
             regex: false,
-            whole_word: false, max_matches: 0,
+            whole_word: false,
+            max_matches: 0,
+            context_before: 0,
+            context_after: 0,
         };
 
         let result = search_lines(&lines, options);
@@ -189,7 +387,10 @@ mod tests {
             query: "hello".to_string(),
             case_sensitive: false,
             regex: false,
-            whole_word: false, max_matches: 0,
+            whole_word: false,
+            max_matches: 0,
+            context_before: 0,
+            context_after: 0,
         };
 
         let result = search_lines(&lines, options);
@@ -208,7 +409,10 @@ mod tests {
             query: "hello".to_string(),
             case_sensitive: false,
             regex: false,
-            whole_word: true, max_matches: 0,
+            whole_word: true,
+            max_matches: 0,
+            context_before: 0,
+            context_after: 0,
         };
 
         let result = search_lines(&lines, options);
@@ -230,7 +434,10 @@ mod tests {
             query: r"^Error:".to_string(),
             case_sensitive: true,
             regex: true,
-            whole_word: false, max_matches: 0,
+            whole_word: false,
+            max_matches: 0,
+            context_before: 0,
+            context_after: 0,
         };
 
         let result = search_lines(&lines, options);
@@ -247,7 +454,10 @@ mod tests {
             query: "test".to_string(),
             case_sensitive: true,
             regex: false,
-            whole_word: false, max_matches: 0,
+            whole_word: false,
+            max_matches: 0,
+            context_before: 0,
+            context_after: 0,
         };
 
         let result = search_lines(&lines, options);
@@ -262,7 +472,10 @@ mod tests {
             query: "Rust".to_string(),
             case_sensitive: true,
             regex: false,
-            whole_word: false, max_matches: 0,
+            whole_word: false,
+            max_matches: 0,
+            context_before: 0,
+            context_after: 0,
         };
 
         let result = search_lines(&lines, options);
@@ -278,7 +491,10 @@ mod tests {
             query: "[invalid(".to_string(),
             case_sensitive: true,
             regex: true,
-            whole_word: false, max_matches: 0,
+            whole_word: false,
+            max_matches: 0,
+            context_before: 0,
+            context_after: 0,
         };
 
         let result = search_lines(&lines, options);
@@ -295,11 +511,230 @@ mod tests {
             query: "file.txt".to_string(),
             case_sensitive: true,
             regex: false, // Literal search, dot should be escaped
-            whole_word: false, max_matches: 0,
+            whole_word: false,
+            max_matches: 0,
+            context_before: 0,
+            context_after: 0,
         };
 
         let result = search_lines(&lines, options);
         assert_eq!(result.total_matches, 1); // Only matches exact "file.txt"
         assert_eq!(result.matches[0].line_number, 0);
     }
+
+    fn literal(query: &str, case_sensitive: bool, whole_word: bool) -> SearchOptions {
+        SearchOptions {
+            query: query.to_string(),
+            case_sensitive,
+            regex: false,
+            whole_word,
+            max_matches: 0,
+            context_before: 0,
+            context_after: 0,
+        }
+    }
+
+    fn positions(result: &SearchResult) -> Vec<(usize, usize, usize)> {
+        result
+            .matches
+            .iter()
+            .map(|m| (m.line_number, m.column_start, m.column_end))
+            .collect()
+    }
+
+    #[test]
+    fn test_refine_extended_query() {
+        let lines = vec![
+            make_line("error: disk full"),
+            make_line("no errors here, err... one"),
+            make_line("Erroneous input"),
+            make_line("all good"),
+            make_line("ERROR again, error twice"),
+        ];
+
+        let steps = [
+            (literal("err", false, false), literal("erro", false, false)),
+            (literal("erro", false, false), literal("ERROR", false, true)),
+            (
+                literal("Err", true, false),
+                literal("Erroneous", true, false),
+            ),
+            (literal("rro", false, false), literal("error", false, false)),
+        ];
+        for (previous_options, options) in steps {
+            let previous = search_lines(&lines, previous_options.clone());
+            let refined = refine_search(&previous, &previous_options, options.clone())
+                .expect("query extension should be refinable");
+            let full = search_lines(&lines, options);
+            assert_eq!(positions(&refined), positions(&full));
+            assert_eq!(refined.total_matches, full.total_matches);
+        }
+    }
+
+    #[test]
+    fn test_refine_falls_back_when_unprovable() {
+        let lines = vec![make_line("error: disk full"), make_line("Error again")];
+        let check = |previous_options: SearchOptions, options: SearchOptions| {
+            let previous = search_lines(&lines, previous_options.clone());
+            refine_search(&previous, &previous_options, options).is_none()
+        };
+
+        // Case toggle, either direction
+        assert!(check(
+            literal("err", true, false),
+            literal("error", false, false)
+        ));
+        assert!(check(
+            literal("err", false, false),
+            literal("error", true, false)
+        ));
+        // Not an extension
+        assert!(check(
+            literal("error", false, false),
+            literal("err", false, false)
+        ));
+        assert!(check(
+            literal("err", false, false),
+            literal("disk", false, false)
+        ));
+        // Whole-word previous search excludes lines the new query matches
+        assert!(check(
+            literal("err", false, true),
+            literal("error", false, false)
+        ));
+        // Regex on either side
+        let regex = SearchOptions {
+            regex: true,
+            ..literal("err", false, false)
+        };
+        assert!(check(regex.clone(), literal("error", false, false)));
+        assert!(check(
+            literal("err", false, false),
+            SearchOptions {
+                query: "err.r".to_string(),
+                ..regex
+            }
+        ));
+
+        // A truncated previous result doesn't hold every candidate line
+        let capped = SearchOptions {
+            max_matches: 1,
+            ..literal("err", false, false)
+        };
+        let previous = search_lines(&lines, capped.clone());
+        assert!(previous.truncated);
+        assert!(refine_search(&previous, &capped, literal("error", false, false)).is_none());
+    }
+
+    #[test]
+    fn test_context_lines_clipped_at_buffer_edges() {
+        let lines: Vec<TerminalLine> = ["boot", "error: disk", "retry", "ok", "error: net"]
+            .into_iter()
+            .map(make_line)
+            .collect();
+        let options = |context_before, context_after| SearchOptions {
+            query: "error".to_string(),
+            case_sensitive: false,
+            regex: false,
+            whole_word: false,
+            max_matches: 0,
+            context_before,
+            context_after,
+        };
+
+        let result = search_lines(&lines, options(2, 2));
+        assert_eq!(result.matches.len(), 2);
+        // Line 1 has only one line above it
+        assert_eq!(result.matches[0].lines_before, vec!["boot"]);
+        assert_eq!(result.matches[0].lines_after, vec!["retry", "ok"]);
+        // Line 4 is the last line
+        assert_eq!(result.matches[1].lines_before, vec!["retry", "ok"]);
+        assert!(result.matches[1].lines_after.is_empty());
+
+        let first = search_lines(
+            &lines,
+            SearchOptions {
+                query: "boot".to_string(),
+                ..options(2, 0)
+            },
+        );
+        assert!(first.matches[0].lines_before.is_empty());
+
+        // No context by default
+        let result = search_lines(&lines, options(0, 0));
+        assert!(result
+            .matches
+            .iter()
+            .all(|m| m.lines_before.is_empty() && m.lines_after.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn test_search_buffers_shares_budget() {
+        let noisy: Vec<TerminalLine> = (0..50)
+            .map(|i| make_line(&format!("ERROR retry {}", i)))
+            .collect();
+        let quiet = vec![
+            make_line("ERROR disk full"),
+            make_line("ok"),
+            make_line("ERROR again"),
+        ];
+        let buffers = vec![
+            ("noisy".to_string(), noisy),
+            ("quiet".to_string(), quiet),
+        ];
+        let options = |max_matches| SearchOptions {
+            query: "error".to_string(),
+            case_sensitive: false,
+            regex: false,
+            whole_word: false,
+            max_matches,
+            context_before: 0,
+            context_after: 0,
+        };
+
+        let results = search_buffers(buffers.clone(), options(0)).await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results["noisy"].matches.len(), 50);
+        assert_eq!(results["quiet"].matches.len(), 2);
+        assert_eq!(results["quiet"].matches[1].line_number, 2);
+
+        // The quiet session keeps all its matches; the noisy one gets the rest
+        let results = search_buffers(buffers, options(10)).await;
+        assert_eq!(results["quiet"].matches.len(), 2);
+        assert!(!results["quiet"].truncated);
+        assert_eq!(results["noisy"].matches.len(), 8);
+        assert_eq!(results["noisy"].total_matches, 50);
+        assert!(results["noisy"].truncated);
+    }
+
+    #[test]
+    fn test_share_match_budget_even_split() {
+        let result = |n: usize| SearchResult {
+            matches: (0..n)
+                .map(|i| SearchMatch {
+                    line_number: i,
+                    column_start: 0,
+                    column_end: 1,
+                    matched_text: "x".to_string(),
+                    line_content: "x".to_string(),
+                    lines_before: Vec::new(),
+                    lines_after: Vec::new(),
+                })
+                .collect(),
+            total_matches: n,
+            duration_ms: 0,
+            truncated: false,
+            error: None,
+        };
+        let mut results: HashMap<String, SearchResult> = ["a", "b", "c"]
+            .into_iter()
+            .map(|id| (id.to_string(), result(100)))
+            .collect();
+
+        share_match_budget(&mut results, 10);
+        let kept: usize = results.values().map(|r| r.matches.len()).sum();
+        assert_eq!(kept, 10);
+        assert!(results.values().all(|r| (3..=4).contains(&r.matches.len())));
+        assert!(results.values().all(|r| r.truncated && r.total_matches == 100));
+    }
 }
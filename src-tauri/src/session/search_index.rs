@@ -0,0 +1,468 @@
+//! Trigram index for repeated scroll buffer searches
+//!
+//! Maps every 3-byte window of a line (ASCII-lowercased) to the lines that
+//! contain it. A literal query can only match lines holding all of its
+//! trigrams, so a search runs the regex over those candidates instead of the
+//! whole buffer. The index is:
+//! - Built lazily, on the first search of a buffer with `INDEX_MIN_LINES`+ lines
+//! - Maintained incrementally as lines are appended and evicted
+//! - Dropped for good (until the buffer is cleared) past `MAX_INDEX_POSTINGS`
+
+use std::collections::{HashMap, VecDeque};
+
+use super::scroll_buffer::TerminalLine;
+use super::search::SearchOptions;
+
+/// Smaller buffers are always scanned directly
+pub const INDEX_MIN_LINES: usize = 10_000;
+
+/// Posting entries (4 bytes each) the index may hold before it is dropped
+pub const MAX_INDEX_POSTINGS: usize = 8_000_000;
+
+/// Three bytes packed into the low 24 bits
+type Trigram = u32;
+
+/// Non-ASCII characters that regex case folding treats as ASCII letters:
+/// KELVIN SIGN matches `k` and LATIN SMALL LETTER LONG S matches `s`
+const ASCII_FOLDS: [(char, &str); 2] = [('\u{212A}', "k"), ('\u{017F}', "s")];
+
+/// Distinct trigrams of `text`, sorted.
+///
+/// Bytes are ASCII-lowercased, so one index serves case-sensitive and
+/// case-insensitive queries (the regex re-checks every candidate anyway).
+fn trigrams(text: &str) -> Vec<Trigram> {
+    let mut grams: Vec<Trigram> = text.as_bytes().windows(3).map(pack).collect();
+    if ASCII_FOLDS.iter().any(|(c, _)| text.contains(*c)) {
+        let folded = ASCII_FOLDS
+            .iter()
+            .fold(text.to_string(), |t, (c, ascii)| t.replace(*c, ascii));
+        grams.extend(folded.as_bytes().windows(3).map(pack));
+    }
+    grams.sort_unstable();
+    grams.dedup();
+    grams
+}
+
+fn pack(window: &[u8]) -> Trigram {
+    (window[0].to_ascii_lowercase() as u32) << 16
+        | (window[1].to_ascii_lowercase() as u32) << 8
+        | window[2].to_ascii_lowercase() as u32
+}
+
+/// Whether the index can answer `options` without missing a match.
+///
+/// Only literal queries of 3+ bytes qualify. Case-insensitive ones must also be
+/// ASCII: Unicode case folding relates byte sequences the index doesn't track.
+fn is_indexable(options: &SearchOptions) -> bool {
+    !options.regex
+        && options.query.len() >= 3
+        && (options.case_sensitive || options.query.is_ascii())
+}
+
+/// Inverted trigram index over a window of consecutive lines.
+///
+/// Lines are identified by a wrapping sequence number; buffer position `i`
+/// is sequence `first_seq + i`.
+pub struct TrigramIndex {
+    /// Trigram → sequence numbers of the lines containing it, oldest first
+    postings: HashMap<Trigram, VecDeque<u32>>,
+    /// Sequence number of the line at buffer position 0
+    first_seq: u32,
+    /// Sequence number the next appended line gets
+    next_seq: u32,
+    /// Entries across all posting lists
+    total_postings: usize,
+}
+
+impl TrigramIndex {
+    pub fn new() -> Self {
+        Self {
+            postings: HashMap::new(),
+            first_seq: 0,
+            next_seq: 0,
+            total_postings: 0,
+        }
+    }
+
+    /// Index existing lines; `None` if they don't fit the posting budget
+    pub fn build<'a>(lines: impl IntoIterator<Item = &'a TerminalLine>) -> Option<Self> {
+        let mut index = Self::new();
+        for line in lines {
+            if !index.push(&line.text) {
+                return None;
+            }
+        }
+        Some(index)
+    }
+
+    /// Index a line appended at the back.
+    /// Returns false once the index is over budget and should be dropped.
+    pub fn push(&mut self, text: &str) -> bool {
+        let seq = self.next_seq;
+        self.next_seq = seq.wrapping_add(1);
+        for gram in trigrams(text) {
+            self.postings.entry(gram).or_default().push_back(seq);
+            self.total_postings += 1;
+        }
+        self.total_postings <= MAX_INDEX_POSTINGS
+    }
+
+    /// Forget the oldest line, evicted from the front; `text` is its content
+    pub fn pop_front(&mut self, text: &str) {
+        let seq = self.first_seq;
+        self.first_seq = seq.wrapping_add(1);
+        for gram in trigrams(text) {
+            if let Some(list) = self.postings.get_mut(&gram) {
+                if list.front() == Some(&seq) {
+                    list.pop_front();
+                    self.total_postings -= 1;
+                }
+                if list.is_empty() {
+                    self.postings.remove(&gram);
+                }
+            }
+        }
+    }
+
+    /// Number of indexed lines
+    pub fn len(&self) -> usize {
+        self.next_seq.wrapping_sub(self.first_seq) as usize
+    }
+
+    /// Whether no lines are indexed
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Ascending buffer positions of the lines that may match `options`, or
+    /// `None` when the query can't be answered from the index.
+    pub fn candidates(&self, options: &SearchOptions) -> Option<Vec<usize>> {
+        if !is_indexable(options) {
+            return None;
+        }
+
+        let mut lists = Vec::new();
+        for gram in trigrams(&options.query) {
+            match self.postings.get(&gram) {
+                Some(list) => lists.push(list),
+                // No line has this trigram, so no line can match
+                None => return Some(Vec::new()),
+            }
+        }
+        // Walk the rarest list, galloping through the others
+        lists.sort_by_key(|list| list.len());
+        let (rarest, rest) = lists.split_first()?;
+        let mut cursors = vec![0; rest.len()];
+
+        let first_seq = self.first_seq;
+        let position = |seq: u32| seq.wrapping_sub(first_seq) as usize;
+        Some(
+            rarest
+                .iter()
+                .map(|&seq| position(seq))
+                .filter(|&pos| {
+                    rest.iter().zip(cursors.iter_mut()).all(|(list, cursor)| {
+                        *cursor = seek(list, *cursor, |seq| position(seq) >= pos);
+                        list.get(*cursor).is_some_and(|&seq| position(seq) == pos)
+                    })
+                })
+                .collect(),
+        )
+    }
+}
+
+/// First index at or after `from` whose entry satisfies `reached`, which must
+/// be monotonic over `list`. Gallops forward, then bisects the last step.
+fn seek(list: &VecDeque<u32>, from: usize, reached: impl Fn(u32) -> bool) -> usize {
+    let mut low = from;
+    let mut step = 1;
+    while low < list.len() && !reached(list[low]) {
+        let next = low + step;
+        if next >= list.len() || reached(list[next]) {
+            // Answer is in (low, next]
+            let mut high = next.min(list.len());
+            while low + 1 < high {
+                let mid = low + (high - low) / 2;
+                if reached(list[mid]) {
+                    high = mid;
+                } else {
+                    low = mid;
+                }
+            }
+            return high;
+        }
+        low = next;
+        step *= 2;
+    }
+    low
+}
+
+impl Default for TrigramIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Search index state kept alongside a scroll buffer
+#[derive(Default)]
+pub enum SearchIndex {
+    /// Not built yet (buffer small or never searched)
+    #[default]
+    Off,
+    Active(TrigramIndex),
+    /// Outgrew `MAX_INDEX_POSTINGS`; stays off until the buffer is cleared
+    OverBudget,
+}
+
+impl SearchIndex {
+    /// Track a line appended to the buffer
+    pub fn on_append(&mut self, text: &str) {
+        if let SearchIndex::Active(index) = self {
+            if !index.push(text) {
+                *self = SearchIndex::OverBudget;
+            }
+        }
+    }
+
+    /// Track the oldest line being evicted from the buffer
+    pub fn on_evict(&mut self, text: &str) {
+        if let SearchIndex::Active(index) = self {
+            index.pop_front(text);
+        }
+    }
+
+    /// Forget everything (buffer cleared)
+    pub fn reset(&mut self) {
+        *self = SearchIndex::Off;
+    }
+
+    /// Candidate positions in `lines` for `options`, building the index first
+    /// if the buffer has grown large enough. `None` means scan every line.
+    pub fn candidates(
+        &mut self,
+        lines: &VecDeque<TerminalLine>,
+        options: &SearchOptions,
+    ) -> Option<Vec<usize>> {
+        if !is_indexable(options) {
+            return None;
+        }
+        if matches!(self, SearchIndex::Off) && lines.len() >= INDEX_MIN_LINES {
+            *self = match TrigramIndex::build(lines) {
+                Some(index) => SearchIndex::Active(index),
+                None => SearchIndex::OverBudget,
+            };
+        }
+        match self {
+            SearchIndex::Active(index) => index.candidates(options),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::search::{search_lines, search_numbered_lines, SearchResult};
+
+    fn options(query: &str, case_sensitive: bool, whole_word: bool) -> SearchOptions {
+        SearchOptions {
+            query: query.to_string(),
+            case_sensitive,
+            regex: false,
+            whole_word,
+            max_matches: 0,
+            context_before: 0,
+            context_after: 0,
+        }
+    }
+
+    fn summary(result: &SearchResult) -> Vec<(usize, usize, usize, String)> {
+        result
+            .matches
+            .iter()
+            .map(|m| {
+                (
+                    m.line_number,
+                    m.column_start,
+                    m.column_end,
+                    m.matched_text.clone(),
+                )
+            })
+            .collect()
+    }
+
+    /// Log-like lines with a sprinkle of mixed case and non-ASCII text
+    fn sample_lines(count: usize) -> Vec<TerminalLine> {
+        (0..count)
+            .map(|i| {
+                let text = match i % 7 {
+                    0 => format!("[INFO] request {} served in {}ms", i, i % 97),
+                    1 => format!("error: connection reset by peer (fd={})", i),
+                    2 => format!("WARN disk usage {}% on /dev/sda{}", i % 100, i % 4),
+                    3 => format!("user@host:~/proj$ cargo build --release # run {}", i),
+                    4 => format!("Erreur réseau: délai dépassé ({})", i),
+                    5 => format!("temp 300\u{212A} ok, ſtatus {}", i),
+                    _ => format!("{}", i),
+                };
+                TerminalLine::new(text)
+            })
+            .collect()
+    }
+
+    fn assert_same_results(index: &TrigramIndex, lines: &[TerminalLine], opts: SearchOptions) {
+        let expected = search_lines(lines, opts.clone());
+        let query = opts.query.clone();
+        match index.candidates(&opts) {
+            Some(positions) => {
+                let indexed =
+                    search_numbered_lines(positions.iter().map(|&i| (i, &lines[i])), opts);
+                assert_eq!(summary(&indexed), summary(&expected), "query {:?}", query);
+                assert_eq!(indexed.total_matches, expected.total_matches);
+            }
+            None => assert!(!is_indexable(&opts), "indexable query {:?} skipped", query),
+        }
+    }
+
+    const QUERIES: &[&str] = &[
+        "error",
+        "ERROR",
+        "request 12",
+        "cargo build",
+        "réseau",
+        "RÉSEAU",
+        "dépassé",
+        "300k",
+        "status",
+        "STATUS 5",
+        "/dev/sda3",
+        "no such text",
+        "ok",
+        "9",
+    ];
+
+    #[test]
+    fn test_indexed_results_match_full_scan() {
+        let lines = sample_lines(2_000);
+        let index = TrigramIndex::build(&lines).unwrap();
+        assert_eq!(index.len(), lines.len());
+
+        for query in QUERIES {
+            for case_sensitive in [true, false] {
+                for whole_word in [true, false] {
+                    assert_same_results(&index, &lines, options(query, case_sensitive, whole_word));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_index_follows_appends_and_evictions() {
+        let all = sample_lines(3_000);
+        let window = 1_000;
+        let mut index = TrigramIndex::build(&all[..window]).unwrap();
+
+        // Slide the window like a full circular buffer
+        for (i, line) in all.iter().enumerate().skip(window) {
+            index.pop_front(&all[i - window].text);
+            assert!(index.push(&line.text));
+        }
+        let lines = &all[all.len() - window..];
+        assert_eq!(index.len(), window);
+
+        for query in QUERIES {
+            assert_same_results(&index, lines, options(query, false, false));
+            assert_same_results(&index, lines, options(query, true, false));
+        }
+    }
+
+    #[test]
+    fn test_candidates_skip_most_lines() {
+        let lines = sample_lines(7_000);
+        let index = TrigramIndex::build(&lines).unwrap();
+        let candidates = index
+            .candidates(&options("cargo build", true, false))
+            .unwrap();
+        assert_eq!(candidates.len(), 1_000);
+        assert!(candidates.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_unindexable_queries_fall_back_to_scan() {
+        let index = TrigramIndex::build(&sample_lines(100)).unwrap();
+        assert!(index.candidates(&options("ok", true, false)).is_none());
+        assert!(index.candidates(&options("réseau", false, false)).is_none());
+        let regex = SearchOptions {
+            regex: true,
+            ..options("err.r", true, false)
+        };
+        assert!(index.candidates(&regex).is_none());
+    }
+
+    #[test]
+    fn test_search_index_builds_only_for_large_buffers() {
+        let opts = options("error", false, false);
+        let mut state = SearchIndex::default();
+
+        let small: VecDeque<TerminalLine> = sample_lines(100).into();
+        assert!(state.candidates(&small, &opts).is_none());
+        assert!(matches!(state, SearchIndex::Off));
+
+        let large: VecDeque<TerminalLine> = sample_lines(INDEX_MIN_LINES).into();
+        assert!(state.candidates(&large, &opts).is_some());
+        assert!(matches!(state, SearchIndex::Active(_)));
+
+        state.reset();
+        assert!(matches!(state, SearchIndex::Off));
+    }
+
+    /// Run manually: cargo test --release test_search_index_bench -- --ignored
+    ///
+    /// Set `OXIDE_BENCH_REPORT=1` and pass `--nocapture` to print the timings.
+    #[test]
+    #[ignore]
+    fn test_search_index_bench() {
+        let report = std::env::var_os("OXIDE_BENCH_REPORT").is_some();
+        let lines = sample_lines(100_000);
+        let started = std::time::Instant::now();
+        let index = TrigramIndex::build(&lines).unwrap();
+        if report {
+            eprintln!(
+                "build: {:?} ({} postings)",
+                started.elapsed(),
+                index.total_postings
+            );
+        }
+
+        for query in [
+            "cargo build",
+            "request 9999",
+            "connection reset",
+            "no such text",
+        ] {
+            let opts = options(query, false, false);
+            let rounds = 20;
+
+            let started = std::time::Instant::now();
+            let mut scanned = 0;
+            for _ in 0..rounds {
+                scanned = search_lines(&lines, opts.clone()).total_matches;
+            }
+            let scan = started.elapsed() / rounds;
+
+            let started = std::time::Instant::now();
+            let mut found = 0;
+            for _ in 0..rounds {
+                let positions = index.candidates(&opts).unwrap();
+                found =
+                    search_numbered_lines(positions.iter().map(|&i| (i, &lines[i])), opts.clone())
+                        .total_matches;
+            }
+            let indexed = started.elapsed() / rounds;
+
+            assert_eq!(found, scanned, "{}", query);
+            if report {
+                eprintln!("{:>20}: scan {:?}, indexed {:?}", query, scan, indexed);
+            }
+        }
+    }
+}
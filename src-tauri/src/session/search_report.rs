@@ -0,0 +1,287 @@
+//! Shareable reports of terminal search results
+//!
+//! Renders a [`SearchResult`] as plain text or Markdown: a header with the
+//! query, match counts and duration, then one entry per matching line with
+//! every match on it marked, surrounded by any context lines the search
+//! asked for. Line numbers are 1-based for readers; the result itself stays
+//! 0-based.
+
+use serde::Deserialize;
+
+use super::search::{SearchMatch, SearchResult};
+
+/// Marks around matched text in plain-text reports
+const TEXT_MARK_OPEN: &str = "[[";
+const TEXT_MARK_CLOSE: &str = "]]";
+
+/// Report output format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Text,
+    Markdown,
+}
+
+/// Render `result` (produced for `query`) as a report
+pub fn render_search_report(query: &str, result: &SearchResult, format: ReportFormat) -> String {
+    let mut out = String::new();
+    let shown = if result.truncated {
+        format!(
+            "{} (showing first {}, truncated)",
+            result.total_matches,
+            result.matches.len()
+        )
+    } else {
+        result.total_matches.to_string()
+    };
+
+    match format {
+        ReportFormat::Text => {
+            out.push_str("Search report\n");
+            out.push_str(&format!("Query: \"{}\"\n", query));
+            out.push_str(&format!("Matches: {}\n", shown));
+            out.push_str(&format!("Duration: {} ms\n", result.duration_ms));
+            if let Some(error) = &result.error {
+                out.push_str(&format!("Error: {}\n", error));
+            }
+            out.push('\n');
+        }
+        ReportFormat::Markdown => {
+            out.push_str("# Search report\n\n");
+            out.push_str(&format!("- **Query:** {}\n", escape_markdown(query)));
+            out.push_str(&format!("- **Matches:** {}\n", shown));
+            out.push_str(&format!("- **Duration:** {} ms\n", result.duration_ms));
+            if let Some(error) = &result.error {
+                out.push_str(&format!("- **Error:** {}\n", escape_markdown(error)));
+            }
+            out.push('\n');
+        }
+    }
+
+    let matches = &result.matches;
+    let width = matches
+        .iter()
+        .map(|m| m.line_number + m.lines_after.len() + 1)
+        .max()
+        .map_or(1, |n| n.to_string().len());
+    let has_context = matches
+        .iter()
+        .any(|m| !m.lines_before.is_empty() || !m.lines_after.is_empty());
+    let context = |out: &mut String, index: usize, text: &str| match format {
+        ReportFormat::Text => {
+            out.push_str(&format!("{:>width$} - {}\n", index + 1, text));
+        }
+        ReportFormat::Markdown => {
+            out.push_str(&format!("- {}: {}\n", index + 1, escape_markdown(text)));
+        }
+    };
+
+    // Last buffer line written, so overlapping context isn't repeated
+    let mut last_written: Option<usize> = None;
+    let mut start = 0;
+    while start < matches.len() {
+        let number = matches[start].line_number;
+        let end = matches[start..]
+            .iter()
+            .position(|m| m.line_number != number)
+            .map_or(matches.len(), |len| start + len);
+        let line = &matches[start..end];
+        start = end;
+
+        let before_start = number - line[0].lines_before.len().min(number);
+        if has_context && last_written.is_some_and(|last| before_start > last + 1) {
+            out.push_str(match format {
+                ReportFormat::Text => "--\n",
+                ReportFormat::Markdown => "- …\n",
+            });
+        }
+        for (index, text) in (before_start..number).zip(&line[0].lines_before) {
+            if !last_written.is_some_and(|last| index <= last) {
+                context(&mut out, index, text);
+            }
+        }
+
+        match format {
+            ReportFormat::Text => {
+                let text = mark_matches(line, TEXT_MARK_OPEN, TEXT_MARK_CLOSE, |s| s.to_string());
+                out.push_str(&format!("{:>width$} | {}\n", number + 1, text));
+            }
+            ReportFormat::Markdown => {
+                let text = mark_matches(line, "**", "**", escape_markdown);
+                out.push_str(&format!("- **{}:** {}\n", number + 1, text));
+            }
+        }
+        last_written = Some(number);
+
+        // Stop short of the next matching line; it gets its own entry
+        let next_match = matches.get(end).map(|m| m.line_number);
+        for (index, text) in (number + 1..).zip(&line[0].lines_after) {
+            if next_match.is_some_and(|next| index >= next) {
+                break;
+            }
+            context(&mut out, index, text);
+            last_written = Some(index);
+        }
+    }
+
+    out
+}
+
+/// Line content with every match in `matches` (all on one line) wrapped in
+/// `open`/`close`; the rest of the line goes through `escape`
+fn mark_matches(
+    matches: &[SearchMatch],
+    open: &str,
+    close: &str,
+    escape: impl Fn(&str) -> String,
+) -> String {
+    let content = &matches[0].line_content;
+    let mut out = String::new();
+    let mut pos = 0;
+
+    for m in matches {
+        // Offsets are byte positions from the regex; skip any that no longer
+        // fit the line (e.g. a hand-edited result)
+        let (Some(before), Some(text)) = (
+            content.get(pos..m.column_start),
+            content.get(m.column_start..m.column_end),
+        ) else {
+            continue;
+        };
+        out.push_str(&escape(before));
+        out.push_str(open);
+        out.push_str(&escape(text));
+        out.push_str(close);
+        pos = m.column_end;
+    }
+    out.push_str(&escape(content.get(pos..).unwrap_or_default()));
+    out
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~'
+        ) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::scroll_buffer::TerminalLine;
+    use crate::session::search::{search_lines, SearchOptions};
+
+    fn sample_result() -> SearchResult {
+        let lines: Vec<TerminalLine> = [
+            "boot ok",
+            "error: disk *full*",
+            "retry",
+            "error again, error",
+        ]
+        .iter()
+        .map(|text| TerminalLine::with_timestamp(text.to_string(), 0))
+        .collect();
+        search_lines(
+            &lines,
+            SearchOptions {
+                query: "error".to_string(),
+                case_sensitive: false,
+                regex: false,
+                whole_word: false,
+                max_matches: 2,
+                context_before: 0,
+                context_after: 0,
+            },
+        )
+    }
+
+    #[test]
+    fn test_text_report_lists_each_match() {
+        let result = sample_result();
+        let report = render_search_report("error", &result, ReportFormat::Text);
+
+        assert!(report.contains("Query: \"error\""));
+        assert!(report.contains("Matches: 3 (showing first 2, truncated)"));
+        assert!(report.contains(&format!("Duration: {} ms", result.duration_ms)));
+        for m in &result.matches {
+            assert!(report.contains(&format!("{} | ", m.line_number + 1)));
+            assert!(report.contains(&format!("[[{}]]", m.matched_text)));
+        }
+        assert!(report.contains("2 | [[error]]: disk *full*"));
+        assert!(report.contains("4 | [[error]] again, error"));
+    }
+
+    #[test]
+    fn test_markdown_report_escapes_and_groups_lines() {
+        let mut result = sample_result();
+        result.matches.push(SearchMatch {
+            line_number: 3,
+            column_start: 13,
+            column_end: 18,
+            matched_text: "error".to_string(),
+            line_content: "error again, error".to_string(),
+            lines_before: Vec::new(),
+            lines_after: Vec::new(),
+        });
+        let report = render_search_report("error", &result, ReportFormat::Markdown);
+
+        assert!(report.starts_with("# Search report"));
+        assert!(report.contains("- **2:** **error**: disk \\*full\\*"));
+        assert!(report.contains("- **4:** **error** again, **error**"));
+        assert_eq!(report.matches("- **4:**").count(), 1);
+    }
+
+    #[test]
+    fn test_context_lines_surround_matches_once() {
+        let lines: Vec<TerminalLine> = [
+            "boot ok",
+            "error: disk",
+            "retry",
+            "error again",
+            "x",
+            "y",
+            "z",
+            "error: net",
+        ]
+        .iter()
+        .map(|text| TerminalLine::with_timestamp(text.to_string(), 0))
+        .collect();
+        let result = search_lines(
+            &lines,
+            SearchOptions {
+                query: "error".to_string(),
+                case_sensitive: false,
+                regex: false,
+                whole_word: false,
+                max_matches: 0,
+                context_before: 1,
+                context_after: 1,
+            },
+        );
+
+        let report = render_search_report("error", &result, ReportFormat::Text);
+        let body = report.split_once("\n\n").unwrap().1;
+        assert_eq!(
+            body,
+            "1 - boot ok\n\
+             2 | [[error]]: disk\n\
+             3 - retry\n\
+             4 | [[error]] again\n\
+             5 - x\n\
+             --\n\
+             7 - z\n\
+             8 | [[error]]: net\n"
+        );
+
+        let report = render_search_report("error", &result, ReportFormat::Markdown);
+        assert!(report.contains("- 3: retry\n- **4:** **error** again\n"));
+        assert!(report.contains("- 5: x\n- …\n- 7: z\n"));
+    }
+}
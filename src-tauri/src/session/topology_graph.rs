@@ -77,6 +77,21 @@ pub struct TopologyEdge {
     /// Cost (can be latency, hop count, etc.)
     #[serde(default = "default_cost")]
     pub cost: i32,
+    /// Measured round-trip time of this link (ms); replaces `cost` in route
+    /// calculation when known. Runtime only, see `update_edge_rtt`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub measured_rtt_ms: Option<u32>,
+}
+
+impl TopologyEdge {
+    /// Weight used by route calculation: the measured RTT if known,
+    /// otherwise the static cost
+    pub fn route_weight(&self) -> i64 {
+        match self.measured_rtt_ms {
+            Some(rtt) => i64::from(rtt),
+            None => i64::from(self.cost),
+        }
+    }
 }
 
 fn default_cost() -> i32 {
@@ -92,6 +107,43 @@ pub struct RouteResult {
     pub total_cost: i32,
 }
 
+/// Display info for one node of a route preview
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RouteHopDetail {
+    pub id: String,
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub display_name: Option<String>,
+    pub auth_type: String,
+}
+
+impl From<&TopologyNodeConfig> for RouteHopDetail {
+    fn from(node: &TopologyNodeConfig) -> Self {
+        Self {
+            id: node.id.clone(),
+            host: node.host.clone(),
+            port: node.port,
+            username: node.username.clone(),
+            display_name: node.display_name.clone(),
+            auth_type: node.auth_type.clone(),
+        }
+    }
+}
+
+/// Computed route with display info, without touching the session tree
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoutePreview {
+    /// Intermediate node IDs (excluding "local" and target)
+    pub route: Vec<String>,
+    /// Total cost
+    pub total_cost: i32,
+    /// Details for each route hop, followed by the target
+    pub hop_details: Vec<RouteHopDetail>,
+}
+
 /// Node info for frontend display
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -127,7 +179,7 @@ pub struct TopologyEdgesConfig {
 /// Dijkstra priority queue state
 #[derive(Eq, PartialEq)]
 struct DijkstraState {
-    cost: i32,
+    cost: i64,
     node: String,
 }
 
@@ -204,6 +256,7 @@ impl NetworkTopology {
                     from: "local".to_string(),
                     to: node_id.clone(),
                     cost: 1,
+                    measured_rtt_ms: None,
                 });
             } else {
                 // Has proxy chain:
@@ -219,6 +272,7 @@ impl NetworkTopology {
                         from: prev.clone(),
                         to: hop_id.clone(),
                         cost: 1,
+                        measured_rtt_ms: None,
                     });
 
                     prev = hop_id;
@@ -229,6 +283,7 @@ impl NetworkTopology {
                     from: prev,
                     to: node_id,
                     cost: 1,
+                    measured_rtt_ms: None,
                 });
             }
         }
@@ -346,7 +401,12 @@ impl NetworkTopology {
     pub fn add_custom_edge(from: String, to: String, cost: i32) -> Result<(), String> {
         let mut config = Self::load_edges_overlay().unwrap_or_default();
 
-        let edge = TopologyEdge { from, to, cost };
+        let edge = TopologyEdge {
+            from,
+            to,
+            cost,
+            measured_rtt_ms: None,
+        };
         if !config.custom_edges.contains(&edge) {
             config.custom_edges.push(edge);
             Self::save_edges_overlay(&config)?;
@@ -371,7 +431,12 @@ impl NetworkTopology {
     pub fn exclude_edge(from: String, to: String) -> Result<(), String> {
         let mut config = Self::load_edges_overlay().unwrap_or_default();
 
-        let edge = TopologyEdge { from, to, cost: 1 };
+        let edge = TopologyEdge {
+            from,
+            to,
+            cost: 1,
+            measured_rtt_ms: None,
+        };
         if !config.excluded_edges.contains(&edge) {
             config.excluded_edges.push(edge);
             Self::save_edges_overlay(&config)?;
@@ -390,15 +455,158 @@ impl NetworkTopology {
         Self::load_edges_overlay().unwrap_or_default()
     }
 
+    /// Record the measured RTT of the `from` → `to` link
+    ///
+    /// The RTT replaces the link's static cost in route calculation (see
+    /// [`TopologyEdge::route_weight`]). Returns false if there is no such edge.
+    pub fn update_edge_rtt(&mut self, from: &str, to: &str, rtt_ms: u32) -> bool {
+        let mut found = false;
+        for edge in self
+            .edges
+            .iter_mut()
+            .filter(|e| e.from == from && e.to == to)
+        {
+            edge.measured_rtt_ms = Some(rtt_ms);
+            found = true;
+        }
+        found
+    }
+
+    /// Node for a host:port and username, if the topology has one
+    pub fn find_node_by_endpoint(&self, host: &str, port: u16, username: &str) -> Option<&str> {
+        self.nodes
+            .values()
+            .find(|n| n.host == host && n.port == port && n.username == username)
+            .map(|n| n.id.as_str())
+    }
+
     /// Compute shortest path from local to target node (Dijkstra)
+    ///
+    /// Edges are weighted by [`TopologyEdge::route_weight`].
     pub fn compute_route(&self, target_id: &str) -> Result<RouteResult, String> {
+        self.compute_route_excluding(target_id, &HashSet::new())
+    }
+
+    /// Like [`compute_route`](Self::compute_route), but as if the `excluded`
+    /// nodes (e.g. hosts that recently failed to connect) were not in the graph
+    pub fn compute_route_excluding(
+        &self,
+        target_id: &str,
+        excluded: &HashSet<String>,
+    ) -> Result<RouteResult, String> {
         // Validate target exists
         if !self.nodes.contains_key(target_id) {
             return Err(format!("Target node '{}' not found in topology", target_id));
         }
 
-        // Build adjacency list
-        let mut adj: HashMap<String, Vec<(String, i32)>> = HashMap::new();
+        let adj = self.adjacency();
+        let Some((nodes, total_cost)) =
+            Self::shortest_path(&adj, "local", target_id, excluded, &HashSet::new())
+        else {
+            if excluded.is_empty() {
+                return Err(format!("No route found to '{}'", target_id));
+            }
+            let mut avoided: Vec<&str> = excluded.iter().map(String::as_str).collect();
+            avoided.sort_unstable();
+            return Err(format!(
+                "No route found to '{}' avoiding unhealthy nodes {}",
+                target_id,
+                avoided.join(", ")
+            ));
+        };
+
+        Ok(Self::route_result(nodes, total_cost))
+    }
+
+    /// Up to `k` loopless routes from local to target, cheapest first (Yen's
+    /// algorithm)
+    ///
+    /// Returns fewer than `k` routes when fewer distinct paths exist; the
+    /// first one is the [`compute_route`](Self::compute_route) result.
+    pub fn compute_k_routes(&self, target_id: &str, k: usize) -> Vec<RouteResult> {
+        self.compute_k_routes_excluding(target_id, k, &HashSet::new())
+    }
+
+    /// [`compute_k_routes`](Self::compute_k_routes) avoiding the `excluded` nodes
+    pub fn compute_k_routes_excluding(
+        &self,
+        target_id: &str,
+        k: usize,
+        excluded: &HashSet<String>,
+    ) -> Vec<RouteResult> {
+        if k == 0 || !self.nodes.contains_key(target_id) {
+            return Vec::new();
+        }
+
+        let adj = self.adjacency();
+        let Some(first) = Self::shortest_path(&adj, "local", target_id, excluded, &HashSet::new())
+        else {
+            return Vec::new();
+        };
+
+        // Full node paths (local..=target) with their route weights
+        let mut found: Vec<(Vec<String>, i64)> = vec![first];
+        let mut candidates: Vec<(Vec<String>, i64)> = Vec::new();
+
+        while found.len() < k {
+            let (last, _) = &found[found.len() - 1];
+
+            // Branch off the last route at each of its nodes
+            for spur_index in 0..last.len() - 1 {
+                let spur_node = &last[spur_index];
+                let root = &last[..=spur_index];
+
+                // Don't rediscover a known route sharing this root
+                let removed_edges: HashSet<(String, String)> = found
+                    .iter()
+                    .filter(|(path, _)| path.len() > spur_index + 1 && path[..=spur_index] == *root)
+                    .map(|(path, _)| (path[spur_index].clone(), path[spur_index + 1].clone()))
+                    .collect();
+
+                // Keep the route loopless: the root can't be revisited
+                let mut blocked = excluded.clone();
+                blocked.extend(root[..spur_index].iter().cloned());
+
+                let Some((spur_path, spur_cost)) =
+                    Self::shortest_path(&adj, spur_node, target_id, &blocked, &removed_edges)
+                else {
+                    continue;
+                };
+
+                let root_cost = root
+                    .windows(2)
+                    .map(|hop| Self::edge_cost(&adj, &hop[0], &hop[1]))
+                    .fold(0i64, i64::saturating_add);
+                let mut path = root[..spur_index].to_vec();
+                path.extend(spur_path);
+
+                let candidate = (path, root_cost.saturating_add(spur_cost));
+                if !found.contains(&candidate) && !candidates.contains(&candidate) {
+                    candidates.push(candidate);
+                }
+            }
+
+            // Cheapest candidate next (ties broken by path for stable output)
+            let Some(best) = candidates
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)))
+                .map(|(index, _)| index)
+            else {
+                break;
+            };
+            found.push(candidates.swap_remove(best));
+        }
+
+        found
+            .into_iter()
+            .map(|(nodes, total_cost)| Self::route_result(nodes, total_cost))
+            .collect()
+    }
+
+    /// Adjacency list over all edges, weighted by route weight
+    fn adjacency(&self) -> HashMap<String, Vec<(String, i64)>> {
+        let mut adj: HashMap<String, Vec<(String, i64)>> = HashMap::new();
         adj.insert("local".to_string(), vec![]);
         for node_id in self.nodes.keys() {
             adj.insert(node_id.clone(), vec![]);
@@ -406,33 +614,59 @@ impl NetworkTopology {
         for edge in &self.edges {
             adj.entry(edge.from.clone())
                 .or_default()
-                .push((edge.to.clone(), edge.cost));
+                .push((edge.to.clone(), edge.route_weight()));
         }
+        adj
+    }
 
-        // Dijkstra algorithm
-        let mut dist: HashMap<String, i32> = HashMap::new();
+    /// Cheapest direct edge weight from `from` to `to`
+    fn edge_cost(adj: &HashMap<String, Vec<(String, i64)>>, from: &str, to: &str) -> i64 {
+        adj.get(from)
+            .into_iter()
+            .flatten()
+            .filter(|(next, _)| next == to)
+            .map(|(_, cost)| *cost)
+            .min()
+            .unwrap_or(i64::MAX)
+    }
+
+    /// Dijkstra from `source` to `target`, skipping `excluded` nodes and
+    /// `removed_edges`. Returns the full node path (source..=target) and weight.
+    fn shortest_path(
+        adj: &HashMap<String, Vec<(String, i64)>>,
+        source: &str,
+        target: &str,
+        excluded: &HashSet<String>,
+        removed_edges: &HashSet<(String, String)>,
+    ) -> Option<(Vec<String>, i64)> {
+        let mut dist: HashMap<String, i64> = HashMap::new();
         let mut prev: HashMap<String, String> = HashMap::new();
         let mut heap = BinaryHeap::new();
 
-        dist.insert("local".to_string(), 0);
+        dist.insert(source.to_string(), 0);
         heap.push(DijkstraState {
             cost: 0,
-            node: "local".to_string(),
+            node: source.to_string(),
         });
 
         while let Some(DijkstraState { cost, node }) = heap.pop() {
-            if node == target_id {
+            if node == target {
                 break;
             }
 
-            if cost > *dist.get(&node).unwrap_or(&i32::MAX) {
+            if cost > *dist.get(&node).unwrap_or(&i64::MAX) {
                 continue;
             }
 
             if let Some(neighbors) = adj.get(&node) {
                 for (next, edge_cost) in neighbors {
-                    let next_cost = cost + edge_cost;
-                    if next_cost < *dist.get(next).unwrap_or(&i32::MAX) {
+                    if excluded.contains(next)
+                        || removed_edges.contains(&(node.clone(), next.clone()))
+                    {
+                        continue;
+                    }
+                    let next_cost = cost.saturating_add(*edge_cost);
+                    if next_cost < *dist.get(next).unwrap_or(&i64::MAX) {
                         dist.insert(next.clone(), next_cost);
                         prev.insert(next.clone(), node.clone());
                         heap.push(DijkstraState {
@@ -445,24 +679,66 @@ impl NetworkTopology {
         }
 
         // Check reachability
-        if !prev.contains_key(target_id) {
-            return Err(format!("No route found to '{}'", target_id));
+        if !prev.contains_key(target) {
+            return None;
         }
 
-        // Backtrack path (excluding local and target)
-        let mut path = vec![];
-        let mut current = target_id.to_string();
-        while let Some(p) = prev.get(&current) {
-            if p == "local" {
+        // Backtrack path
+        let mut path = vec![target.to_string()];
+        let mut current = target;
+        while let Some(p) = prev.get(current) {
+            path.push(p.clone());
+            if p == source {
                 break;
             }
-            path.push(p.clone());
-            current = p.clone();
+            current = p;
         }
         path.reverse();
 
-        let total_cost = *dist.get(target_id).unwrap_or(&0);
-        Ok(RouteResult { path, total_cost })
+        Some((path, dist[target]))
+    }
+
+    /// Strip "local" and the target from a full node path
+    fn route_result(mut nodes: Vec<String>, weight: i64) -> RouteResult {
+        nodes.pop();
+        nodes.remove(0);
+        RouteResult {
+            path: nodes,
+            total_cost: i32::try_from(weight).unwrap_or(i32::MAX),
+        }
+    }
+
+    /// Compute the route to `target_id` and resolve display info for every
+    /// node on it (read-only counterpart of auto-route expansion)
+    pub fn preview_route(&self, target_id: &str) -> Result<RoutePreview, String> {
+        self.preview_route_excluding(target_id, &HashSet::new())
+    }
+
+    /// [`preview_route`](Self::preview_route) avoiding the `excluded` nodes
+    pub fn preview_route_excluding(
+        &self,
+        target_id: &str,
+        excluded: &HashSet<String>,
+    ) -> Result<RoutePreview, String> {
+        let RouteResult { path, total_cost } = self.compute_route_excluding(target_id, excluded)?;
+
+        let hop_details = path
+            .iter()
+            .map(String::as_str)
+            .chain(std::iter::once(target_id))
+            .map(|id| {
+                self.nodes
+                    .get(id)
+                    .map(RouteHopDetail::from)
+                    .ok_or_else(|| format!("Hop node '{}' not found", id))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(RoutePreview {
+            route: path,
+            total_cost,
+            hop_details,
+        })
     }
 
     /// Get all nodes info (for frontend display)
@@ -567,16 +843,19 @@ mod tests {
                     from: "local".to_string(),
                     to: "jump".to_string(),
                     cost: 1,
+                    measured_rtt_ms: None,
                 },
                 TopologyEdge {
                     from: "jump".to_string(),
                     to: "bastion".to_string(),
                     cost: 1,
+                    measured_rtt_ms: None,
                 },
                 TopologyEdge {
                     from: "bastion".to_string(),
                     to: "target".to_string(),
                     cost: 1,
+                    measured_rtt_ms: None,
                 },
             ],
         }
@@ -598,6 +877,7 @@ mod tests {
             from: "local".to_string(),
             to: "target".to_string(),
             cost: 10,
+            measured_rtt_ms: None,
         });
 
         // Should choose shorter path
@@ -606,6 +886,218 @@ mod tests {
         assert_eq!(result.total_cost, 3);
     }
 
+    #[test]
+    fn test_preview_route_details() {
+        let mut topology = create_test_topology();
+        topology.nodes.get_mut("target").unwrap().display_name = Some("prod-db".to_string());
+
+        let preview = topology.preview_route("target").unwrap();
+        assert_eq!(preview.route, vec!["jump", "bastion"]);
+        assert_eq!(preview.total_cost, 3);
+
+        let hosts: Vec<&str> = preview
+            .hop_details
+            .iter()
+            .map(|h| h.host.as_str())
+            .collect();
+        assert_eq!(hosts, vec!["jump.test", "bastion.test", "target.test"]);
+        assert_eq!(
+            preview.hop_details[2].display_name.as_deref(),
+            Some("prod-db")
+        );
+
+        // Direct route: only the target
+        let mut direct = create_test_topology();
+        direct.edges.push(TopologyEdge {
+            from: "local".to_string(),
+            to: "target".to_string(),
+            cost: 1,
+            measured_rtt_ms: None,
+        });
+        let preview = direct.preview_route("target").unwrap();
+        assert!(preview.route.is_empty());
+        assert_eq!(preview.hop_details.len(), 1);
+
+        assert!(topology.preview_route("nonexistent").is_err());
+    }
+
+    #[test]
+    fn test_measured_rtt_picks_faster_path() {
+        let mut topology = create_test_topology();
+        // Second path of the same static cost: local -> relay -> target
+        let mut relay = topology.nodes["jump"].clone();
+        relay.id = "relay".to_string();
+        relay.host = "relay.test".to_string();
+        topology.nodes.insert("relay".to_string(), relay);
+        topology.edges.retain(|e| e.from != "jump");
+        for (from, to) in [("jump", "target"), ("local", "relay"), ("relay", "target")] {
+            topology.edges.push(TopologyEdge {
+                from: from.to_string(),
+                to: to.to_string(),
+                cost: 1,
+                measured_rtt_ms: None,
+            });
+        }
+        assert_eq!(topology.compute_route("target").unwrap().total_cost, 2);
+
+        for (from, to, rtt) in [
+            ("local", "jump", 40),
+            ("jump", "target", 35),
+            ("local", "relay", 10),
+            ("relay", "target", 15),
+        ] {
+            assert!(topology.update_edge_rtt(from, to, rtt));
+        }
+        let result = topology.compute_route("target").unwrap();
+        assert_eq!(result.path, vec!["relay"]);
+        assert_eq!(result.total_cost, 25);
+
+        // The relay link degrades: route moves back to the jump host
+        topology.update_edge_rtt("relay", "target", 200);
+        let result = topology.compute_route("target").unwrap();
+        assert_eq!(result.path, vec!["jump"]);
+        assert_eq!(result.total_cost, 75);
+
+        assert!(!topology.update_edge_rtt("local", "target", 5));
+        assert_eq!(
+            topology.find_node_by_endpoint("relay.test", 22, "user"),
+            Some("relay")
+        );
+    }
+
+    #[test]
+    fn test_measured_rtt_overrides_static_cost() {
+        let mut topology = create_test_topology();
+        // Shortcut of lower static cost: local -> relay -> target
+        let mut relay = topology.nodes["jump"].clone();
+        relay.id = "relay".to_string();
+        topology.nodes.insert("relay".to_string(), relay);
+        for (from, to) in [("local", "relay"), ("relay", "target")] {
+            topology.edges.push(TopologyEdge {
+                from: from.to_string(),
+                to: to.to_string(),
+                cost: 1,
+                measured_rtt_ms: None,
+            });
+        }
+        let result = topology.compute_route("target").unwrap();
+        assert_eq!(result.path, vec!["relay"]);
+        assert_eq!(result.total_cost, 2);
+
+        // Three fast hops beat two slow ones
+        for (from, to, rtt) in [
+            ("local", "jump", 10),
+            ("jump", "bastion", 10),
+            ("bastion", "target", 10),
+            ("local", "relay", 500),
+            ("relay", "target", 500),
+        ] {
+            assert!(topology.update_edge_rtt(from, to, rtt));
+        }
+        let result = topology.compute_route("target").unwrap();
+        assert_eq!(result.path, vec!["jump", "bastion"]);
+        assert_eq!(result.total_cost, 30);
+    }
+
+    #[test]
+    fn test_route_avoids_excluded_hop() {
+        let mut topology = create_test_topology();
+        // Backup path: local -> relay -> bastion
+        let mut relay = topology.nodes["jump"].clone();
+        relay.id = "relay".to_string();
+        topology.nodes.insert("relay".to_string(), relay);
+        for (from, to, cost) in [("local", "relay", 2), ("relay", "bastion", 2)] {
+            topology.edges.push(TopologyEdge {
+                from: from.to_string(),
+                to: to.to_string(),
+                cost,
+                measured_rtt_ms: None,
+            });
+        }
+        assert_eq!(
+            topology.compute_route("target").unwrap().path,
+            vec!["jump", "bastion"]
+        );
+
+        let excluded: HashSet<String> = ["jump".to_string()].into();
+        let result = topology
+            .compute_route_excluding("target", &excluded)
+            .unwrap();
+        assert_eq!(result.path, vec!["relay", "bastion"]);
+        assert_eq!(result.total_cost, 5);
+    }
+
+    #[test]
+    fn test_route_excluding_partitioned_target() {
+        let topology = create_test_topology();
+        let excluded: HashSet<String> = ["jump".to_string(), "bastion".to_string()].into();
+
+        let err = topology
+            .compute_route_excluding("target", &excluded)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            "No route found to 'target' avoiding unhealthy nodes bastion, jump"
+        );
+    }
+
+    #[test]
+    fn test_k_routes_on_diamond() {
+        // local -> jump -> target (cost 2) and local -> bastion -> target (cost 4)
+        let mut topology = create_test_topology();
+        topology.edges = [
+            ("local", "jump", 1),
+            ("jump", "target", 1),
+            ("local", "bastion", 1),
+            ("bastion", "target", 3),
+        ]
+        .into_iter()
+        .map(|(from, to, cost)| TopologyEdge {
+            from: from.to_string(),
+            to: to.to_string(),
+            cost,
+            measured_rtt_ms: None,
+        })
+        .collect();
+
+        let routes = topology.compute_k_routes("target", 5);
+        let found: Vec<(Vec<String>, i32)> =
+            routes.into_iter().map(|r| (r.path, r.total_cost)).collect();
+        assert_eq!(
+            found,
+            vec![
+                (vec!["jump".to_string()], 2),
+                (vec!["bastion".to_string()], 4)
+            ]
+        );
+
+        assert_eq!(topology.compute_k_routes("target", 1).len(), 1);
+        assert!(topology.compute_k_routes("target", 0).is_empty());
+        assert!(topology.compute_k_routes("nonexistent", 3).is_empty());
+    }
+
+    #[test]
+    fn test_k_routes_are_loopless_and_distinct() {
+        // Chain with a shortcut and a back edge: local -> jump -> bastion -> target,
+        // local -> bastion, bastion -> jump
+        let mut topology = create_test_topology();
+        for (from, to, cost) in [("local", "bastion", 5), ("bastion", "jump", 1)] {
+            topology.edges.push(TopologyEdge {
+                from: from.to_string(),
+                to: to.to_string(),
+                cost,
+                measured_rtt_ms: None,
+            });
+        }
+
+        let paths: Vec<Vec<String>> = topology
+            .compute_k_routes("target", 10)
+            .into_iter()
+            .map(|r| r.path)
+            .collect();
+        assert_eq!(paths, vec![vec!["jump", "bastion"], vec!["bastion"]]);
+    }
+
     #[test]
     fn test_compute_route_not_found() {
         let topology = create_test_topology();
@@ -20,7 +20,7 @@
 //! └────────────────────────────────────────────────────────────┘
 //! ```
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
@@ -28,6 +28,12 @@ use uuid::Uuid;
 
 use super::types::AuthMethod;
 
+/// 树的最大层数（depth 取值 0..MAX_TREE_DEPTH）
+///
+/// 每一层都在整条路径的跳板上占用一个 SSH channel，过深的链会耗尽
+/// 上游的 MaxSessions。
+pub const MAX_TREE_DEPTH: u32 = 16;
+
 // ============================================================================
 // 核心数据结构
 // ============================================================================
@@ -67,6 +73,9 @@ pub struct SessionNode {
 
     /// 创建时间
     pub created_at: chrono::DateTime<Utc>,
+
+    /// 最近一次进入 Failed 状态的时间
+    pub failed_at: Option<chrono::DateTime<Utc>>,
 }
 
 /// 节点连接信息
@@ -82,6 +91,9 @@ pub struct NodeConnection {
     pub auth: AuthMethod,
     /// 可选的显示名称
     pub display_name: Option<String>,
+    /// 可选的 ProxyCommand（仅对直连节点生效，子节点走父隧道）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_command: Option<String>,
 }
 
 impl NodeConnection {
@@ -93,6 +105,7 @@ impl NodeConnection {
             username: username.into(),
             auth: AuthMethod::Agent,
             display_name: None,
+            proxy_command: None,
         }
     }
 
@@ -125,6 +138,23 @@ impl NodeConnection {
             .clone()
             .unwrap_or_else(|| format!("{}@{}", self.username, self.host))
     }
+
+    /// 是否指向同一端点（host 不区分大小写，port 与 username 精确匹配）
+    pub fn same_endpoint(&self, other: &NodeConnection) -> bool {
+        self.host.eq_ignore_ascii_case(&other.host)
+            && self.port == other.port
+            && self.username == other.username
+    }
+
+    /// 是否匹配搜索词（`query_lower` 需已转小写）
+    fn matches_query(&self, query_lower: &str) -> bool {
+        self.host.to_lowercase().contains(query_lower)
+            || self.username.to_lowercase().contains(query_lower)
+            || self
+                .display_name
+                .as_ref()
+                .is_some_and(|name| name.to_lowercase().contains(query_lower))
+    }
 }
 
 /// 节点来源 - 区分三种跳板机模式
@@ -213,6 +243,26 @@ impl NodeState {
 // 会话树
 // ============================================================================
 
+/// 钻入结果
+#[derive(Debug, Clone)]
+pub struct DrillDownOutcome {
+    /// 新建子节点 ID
+    pub node_id: String,
+    /// 新连接的端点已出现在祖先链上时，该祖先节点的 ID
+    ///
+    /// 仅为提示：经不同端口转发等场景下回到同一主机是合理的，不阻止钻入。
+    pub cycle_ancestor: Option<String>,
+}
+
+/// 连接断开的级联结果（见 `SessionTree::mark_connection_lost`）
+#[derive(Debug, Clone, Default)]
+pub struct LostConnection {
+    /// 状态发生变化的节点 ID（自底向上）
+    pub changed: Vec<String>,
+    /// 被清除的子孙节点 SSH 连接 ID（自底向上）
+    pub orphaned: Vec<String>,
+}
+
 /// 会话树 - 管理所有会话节点
 pub struct SessionTree {
     /// 所有节点（ID -> Node）
@@ -285,18 +335,39 @@ impl SessionTree {
             sftp_session_id: None,
             ssh_connection_id: None,
             created_at: Utc::now(),
+            failed_at: None,
         };
         self.nodes.insert(id.clone(), node);
         self.root_ids.push(id.clone());
         id
     }
 
+    /// 检查 `depth` 是否在 [`MAX_TREE_DEPTH`] 以内
+    pub fn check_depth(depth: u32) -> Result<(), TreeError> {
+        if depth >= MAX_TREE_DEPTH {
+            return Err(TreeError::DepthLimitExceeded(depth));
+        }
+        Ok(())
+    }
+
     /// 从父节点钻入，添加子节点（模式3: 动态钻入）
     pub fn drill_down(
         &mut self,
         parent_id: &str,
         connection: NodeConnection,
     ) -> Result<String, TreeError> {
+        self.drill_down_flagged(parent_id, connection)
+            .map(|outcome| outcome.node_id)
+    }
+
+    /// 同 [`Self::drill_down`]，并标记重复的祖先端点
+    ///
+    /// 目标端点已在父节点路径上时仍会添加，并通过 `cycle_ancestor` 返回该祖先。
+    pub fn drill_down_flagged(
+        &mut self,
+        parent_id: &str,
+        connection: NodeConnection,
+    ) -> Result<DrillDownOutcome, TreeError> {
         let parent = self
             .nodes
             .get(parent_id)
@@ -307,6 +378,10 @@ impl SessionTree {
         }
 
         let depth = parent.depth + 1;
+        Self::check_depth(depth)?;
+        let cycle_ancestor = self
+            .find_on_path(parent_id, &connection)
+            .map(|node| node.id.clone());
         let id = Uuid::new_v4().to_string();
 
         let node = SessionNode {
@@ -323,6 +398,7 @@ impl SessionTree {
             sftp_session_id: None,
             ssh_connection_id: None,
             created_at: Utc::now(),
+            failed_at: None,
         };
 
         self.nodes.insert(id.clone(), node);
@@ -332,7 +408,10 @@ impl SessionTree {
             parent.children_ids.push(id.clone());
         }
 
-        Ok(id)
+        Ok(DrillDownOutcome {
+            node_id: id,
+            cycle_ancestor,
+        })
     }
 
     /// 展开静态手工预设链为树节点（模式1）
@@ -373,6 +452,9 @@ impl SessionTree {
     where
         F: Fn(u32) -> NodeOrigin,
     {
+        // 目标节点的 depth 等于跳板数，先检查再修改树
+        Self::check_depth(u32::try_from(hops.len()).unwrap_or(u32::MAX))?;
+
         if hops.is_empty() {
             // 无跳板，直接添加目标
             return Ok(self.add_root_node(target, NodeOrigin::Direct));
@@ -399,6 +481,7 @@ impl SessionTree {
                 sftp_session_id: None,
                 ssh_connection_id: None,
                 created_at: Utc::now(),
+                failed_at: None,
             };
 
             if let Some(parent) = self.nodes.get_mut(&current_id) {
@@ -424,6 +507,7 @@ impl SessionTree {
             sftp_session_id: None,
             ssh_connection_id: None,
             created_at: Utc::now(),
+            failed_at: None,
         };
 
         if let Some(parent) = self.nodes.get_mut(&current_id) {
@@ -440,10 +524,28 @@ impl SessionTree {
             .nodes
             .get_mut(node_id)
             .ok_or_else(|| TreeError::NodeNotFound(node_id.to_string()))?;
+        if matches!(new_state, NodeState::Failed { .. }) {
+            node.failed_at = Some(Utc::now());
+        }
         node.state = new_state;
         Ok(())
     }
 
+    /// 节点已断开：状态置为 Disconnected 并清除所有会话 ID
+    ///
+    /// 对已断开的节点重复调用是安全的。
+    pub fn mark_disconnected(&mut self, node_id: &str) -> Result<(), TreeError> {
+        let node = self
+            .nodes
+            .get_mut(node_id)
+            .ok_or_else(|| TreeError::NodeNotFound(node_id.to_string()))?;
+        node.state = NodeState::Disconnected;
+        node.ssh_connection_id = None;
+        node.terminal_session_id = None;
+        node.sftp_session_id = None;
+        Ok(())
+    }
+
     /// 关联 SSH 连接 ID
     pub fn set_ssh_connection_id(
         &mut self,
@@ -567,6 +669,78 @@ impl SessionTree {
         }
     }
 
+    /// 子树节点（含自身），自底向上：子节点总在父节点之前
+    pub fn subtree_bottom_up(&self, node_id: &str) -> Vec<&SessionNode> {
+        let mut result = Vec::new();
+        self.collect_bottom_up(node_id, &mut result);
+        result
+    }
+
+    /// 所有根节点下的全部节点，自底向上（用于整体断开）
+    pub fn all_nodes_bottom_up(&self) -> Vec<&SessionNode> {
+        let mut result = Vec::new();
+        for root_id in &self.root_ids {
+            self.collect_bottom_up(root_id, &mut result);
+        }
+        result
+    }
+
+    /// 节点的 SSH 连接已断开：整个子树标记为 Disconnected 并清除会话 ID
+    ///
+    /// 子节点的隧道经由该连接，父连接断开后它们也随之失效。
+    /// 仅当节点仍关联 `ssh_connection_id` 时生效，避免旧连接的断开通知覆盖重连后的状态。
+    /// 返回状态发生变化的节点 ID（自底向上）及被清除的子孙连接 ID；
+    /// 后者在注册表中仍存在，调用方须将其断开。
+    pub fn mark_connection_lost(
+        &mut self,
+        node_id: &str,
+        ssh_connection_id: &str,
+    ) -> LostConnection {
+        let mut lost = LostConnection::default();
+        let attached = self
+            .nodes
+            .get(node_id)
+            .and_then(|node| node.ssh_connection_id.as_deref())
+            == Some(ssh_connection_id);
+        if !attached {
+            return lost;
+        }
+
+        let subtree: Vec<String> = self
+            .subtree_bottom_up(node_id)
+            .into_iter()
+            .map(|node| node.id.clone())
+            .collect();
+
+        for id in subtree {
+            if let Some(node) = self.nodes.get_mut(&id) {
+                if let Some(ssh_id) = node.ssh_connection_id.take() {
+                    if id != node_id {
+                        lost.orphaned.push(ssh_id);
+                    }
+                }
+                node.terminal_session_id = None;
+                node.sftp_session_id = None;
+                // Pending / Failed 节点没有活动连接，保持原状态
+                if matches!(node.state, NodeState::Connecting | NodeState::Connected) {
+                    node.state = NodeState::Disconnected;
+                    lost.changed.push(id);
+                }
+            }
+        }
+        lost
+    }
+
+    fn collect_bottom_up<'a>(&'a self, node_id: &str, result: &mut Vec<&'a SessionNode>) {
+        if let Some(node) = self.nodes.get(node_id) {
+            // 先处理所有子节点，最后处理自己
+            for child_id in &node.children_ids {
+                self.collect_bottom_up(child_id, result);
+            }
+            result.push(node);
+        }
+    }
+
     /// 获取从根到目标节点的完整路径
     pub fn get_path_to_node(&self, node_id: &str) -> Vec<&SessionNode> {
         let mut path = self.get_ancestors(node_id);
@@ -579,6 +753,56 @@ impl SessionTree {
         path
     }
 
+    /// 路径上需要重连的区段（从根到 `node_id`）
+    ///
+    /// 从第一个未处于 Connected 或已失去 SSH 连接的节点开始，直到 `node_id`。
+    /// 之前仍然存活的上游跳板不包含在内；整条路径都已连接时返回空列表。
+    pub fn broken_segment(&self, node_id: &str) -> Result<Vec<String>, TreeError> {
+        if !self.nodes.contains_key(node_id) {
+            return Err(TreeError::NodeNotFound(node_id.to_string()));
+        }
+
+        Ok(self
+            .get_path_to_node(node_id)
+            .into_iter()
+            .skip_while(|node| node.state.is_connected() && node.ssh_connection_id.is_some())
+            .map(|node| node.id.clone())
+            .collect())
+    }
+
+    /// 重建 `node_id` 子树需要重连的节点，自顶向下（父节点总在子节点之前）
+    ///
+    /// 先是路径上的断开区段（见 [`Self::broken_segment`]），因此父节点断开时会先重连父节点；
+    /// 再是子树中未连接的后代。父节点需要重连的后代即使显示 Connected 也一并重连，
+    /// 因为它的隧道经由旧连接。
+    pub fn subtree_reconnect_plan(&self, node_id: &str) -> Result<Vec<String>, TreeError> {
+        let mut plan = self.broken_segment(node_id)?;
+        let mut planned: HashSet<String> = plan.iter().cloned().collect();
+
+        for node in self.get_descendants(node_id) {
+            let parent_planned = node
+                .parent_id
+                .as_ref()
+                .is_some_and(|parent_id| planned.contains(parent_id));
+            let live = node.state.is_connected() && node.ssh_connection_id.is_some();
+            if parent_planned || !live {
+                planned.insert(node.id.clone());
+                plan.push(node.id.clone());
+            }
+        }
+
+        Ok(plan)
+    }
+
+    /// 从根到 `node_id`（含自身）的路径上第一个与 `connection` 端点相同的节点
+    ///
+    /// 用于钻入前检查：目标已在路径上时再钻入只会形成多余的嵌套隧道。
+    pub fn find_on_path(&self, node_id: &str, connection: &NodeConnection) -> Option<&SessionNode> {
+        self.get_path_to_node(node_id)
+            .into_iter()
+            .find(|node| node.connection.same_endpoint(connection))
+    }
+
     /// 扁平化输出（用于前端渲染）
     pub fn flatten(&self) -> Vec<FlatNode> {
         let mut result = Vec::new();
@@ -590,6 +814,27 @@ impl SessionTree {
         result
     }
 
+    /// 搜索节点（host / display_name / username，大小写不敏感的子串匹配）
+    ///
+    /// 结果保持 `flatten()` 的顺序和 depth / is_last_child 等渲染信息。
+    /// `state_filter` 只比较状态种类，`Failed` 的错误信息不参与匹配。
+    pub fn search_nodes(&self, query: &str, state_filter: Option<&NodeState>) -> Vec<FlatNode> {
+        let query_lower = query.to_lowercase();
+
+        self.flatten()
+            .into_iter()
+            .filter(|flat| {
+                let Some(node) = self.nodes.get(&flat.id) else {
+                    return false;
+                };
+                let state_matches = state_filter.is_none_or(|wanted| {
+                    std::mem::discriminant(wanted) == std::mem::discriminant(&node.state)
+                });
+                state_matches && node.connection.matches_query(&query_lower)
+            })
+            .collect()
+    }
+
     fn flatten_recursive(&self, node_id: &str, result: &mut Vec<FlatNode>) {
         if let Some(node) = self.nodes.get(node_id) {
             result.push(FlatNode::from_node(node, self.is_last_child(node)));
@@ -696,6 +941,285 @@ impl FlatNode {
     }
 }
 
+// ============================================================================
+// 持久化（崩溃/重启后恢复树结构）
+// ============================================================================
+
+/// 持久化的认证方式
+///
+/// 口令和私钥密码不落盘，只保存钥匙串引用（来自对应的保存连接）；
+/// 节点恢复后由调用方经 [`PersistedAuth::resolve`] 取回凭据。没有引用的节点
+/// 重连时由用户重新输入。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PersistedAuth {
+    Password {
+        /// 口令的钥匙串条目 ID
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        keychain_id: Option<String>,
+    },
+    Key {
+        key_path: String,
+        has_passphrase: bool,
+        /// 私钥密码的钥匙串条目 ID
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        passphrase_keychain_id: Option<String>,
+    },
+    Certificate {
+        key_path: String,
+        cert_path: String,
+        has_passphrase: bool,
+        /// 私钥密码的钥匙串条目 ID
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        passphrase_keychain_id: Option<String>,
+    },
+    Agent,
+    KeyboardInteractive,
+}
+
+impl From<&AuthMethod> for PersistedAuth {
+    fn from(auth: &AuthMethod) -> Self {
+        match auth {
+            AuthMethod::Password { .. } => PersistedAuth::Password { keychain_id: None },
+            AuthMethod::Key {
+                key_path,
+                passphrase,
+            } => PersistedAuth::Key {
+                key_path: key_path.clone(),
+                has_passphrase: passphrase.is_some(),
+                passphrase_keychain_id: None,
+            },
+            AuthMethod::Certificate {
+                key_path,
+                cert_path,
+                passphrase,
+            } => PersistedAuth::Certificate {
+                key_path: key_path.clone(),
+                cert_path: cert_path.clone(),
+                has_passphrase: passphrase.is_some(),
+                passphrase_keychain_id: None,
+            },
+            AuthMethod::Agent => PersistedAuth::Agent,
+            AuthMethod::KeyboardInteractive => PersistedAuth::KeyboardInteractive,
+        }
+    }
+}
+
+impl PersistedAuth {
+    /// 是否带有需要从钥匙串取回的凭据引用
+    pub fn has_keychain_ref(&self) -> bool {
+        match self {
+            PersistedAuth::Password { keychain_id } => keychain_id.is_some(),
+            PersistedAuth::Key {
+                passphrase_keychain_id,
+                ..
+            }
+            | PersistedAuth::Certificate {
+                passphrase_keychain_id,
+                ..
+            } => passphrase_keychain_id.is_some(),
+            PersistedAuth::Agent | PersistedAuth::KeyboardInteractive => false,
+        }
+    }
+
+    /// 按 `renamed`（旧 ID → 新 ID）替换钥匙串引用，不在其中的引用保持不变
+    pub fn rename_keychain_refs(&mut self, renamed: &HashMap<String, String>) {
+        let id = match self {
+            PersistedAuth::Password { keychain_id } => keychain_id,
+            PersistedAuth::Key {
+                passphrase_keychain_id,
+                ..
+            }
+            | PersistedAuth::Certificate {
+                passphrase_keychain_id,
+                ..
+            } => passphrase_keychain_id,
+            PersistedAuth::Agent | PersistedAuth::KeyboardInteractive => return,
+        };
+        if let Some(new_id) = id.as_ref().and_then(|old| renamed.get(old)) {
+            *id = Some(new_id.clone());
+        }
+    }
+
+    /// 还原为认证方式，钥匙串引用经 `lookup` 解析
+    ///
+    /// 无引用或 `lookup` 返回 `None` 时密码为空、私钥密码为 None。
+    pub fn resolve<E>(
+        &self,
+        mut lookup: impl FnMut(&str) -> Result<Option<String>, E>,
+    ) -> Result<AuthMethod, E> {
+        let mut secret = |id: &Option<String>| match id {
+            Some(id) => lookup(id),
+            None => Ok(None),
+        };
+        Ok(match self {
+            PersistedAuth::Password { keychain_id } => AuthMethod::Password {
+                password: secret(keychain_id)?.unwrap_or_default(),
+            },
+            PersistedAuth::Key {
+                key_path,
+                passphrase_keychain_id,
+                ..
+            } => AuthMethod::Key {
+                key_path: key_path.clone(),
+                passphrase: secret(passphrase_keychain_id)?,
+            },
+            PersistedAuth::Certificate {
+                key_path,
+                cert_path,
+                passphrase_keychain_id,
+                ..
+            } => AuthMethod::Certificate {
+                key_path: key_path.clone(),
+                cert_path: cert_path.clone(),
+                passphrase: secret(passphrase_keychain_id)?,
+            },
+            PersistedAuth::Agent => AuthMethod::Agent,
+            PersistedAuth::KeyboardInteractive => AuthMethod::KeyboardInteractive,
+        })
+    }
+
+    /// 还原为不含凭据的认证方式（密码为空，私钥密码为 None）
+    fn to_auth_method(&self) -> AuthMethod {
+        match self.resolve(|_| Ok::<_, std::convert::Infallible>(None)) {
+            Ok(auth) => auth,
+            Err(never) => match never {},
+        }
+    }
+}
+
+/// 持久化的节点（仅配置，不含状态和会话 ID）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersistedNode {
+    pub id: String,
+    pub parent_id: Option<String>,
+    pub children_ids: Vec<String>,
+    pub depth: u32,
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub auth: PersistedAuth,
+    pub display_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_command: Option<String>,
+    pub origin: NodeOrigin,
+    pub created_at: chrono::DateTime<Utc>,
+}
+
+/// 持久化的会话树
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PersistedSessionTree {
+    pub root_ids: Vec<String>,
+    pub nodes: Vec<PersistedNode>,
+}
+
+impl PersistedSessionTree {
+    /// 序列化为 MessagePack
+    pub fn to_bytes(&self) -> Result<Vec<u8>, rmp_serde::encode::Error> {
+        rmp_serde::to_vec_named(self)
+    }
+
+    /// 从 MessagePack 反序列化
+    pub fn from_bytes(data: &[u8]) -> Result<Self, rmp_serde::decode::Error> {
+        rmp_serde::from_slice(data)
+    }
+}
+
+impl SessionTree {
+    /// 导出树结构用于持久化（不含钥匙串引用，见 [`Self::to_persisted_with`]）
+    pub fn to_persisted(&self) -> PersistedSessionTree {
+        self.to_persisted_with(|node| PersistedAuth::from(&node.connection.auth))
+    }
+
+    /// 导出树结构用于持久化，`auth_of` 决定每个节点保存的认证方式
+    ///
+    /// 调用方借此附上节点凭据的钥匙串引用。
+    pub fn to_persisted_with(
+        &self,
+        mut auth_of: impl FnMut(&SessionNode) -> PersistedAuth,
+    ) -> PersistedSessionTree {
+        let nodes = self
+            .flatten()
+            .iter()
+            .filter_map(|flat| self.nodes.get(&flat.id))
+            .map(|node| PersistedNode {
+                id: node.id.clone(),
+                parent_id: node.parent_id.clone(),
+                children_ids: node.children_ids.clone(),
+                depth: node.depth,
+                host: node.connection.host.clone(),
+                port: node.connection.port,
+                username: node.connection.username.clone(),
+                auth: auth_of(node),
+                display_name: node.connection.display_name.clone(),
+                proxy_command: node.connection.proxy_command.clone(),
+                origin: node.origin.clone(),
+                created_at: node.created_at,
+            })
+            .collect();
+
+        PersistedSessionTree {
+            root_ids: self.root_ids.clone(),
+            nodes,
+        }
+    }
+
+    /// 从持久化数据恢复树，所有节点均为 `Disconnected`
+    ///
+    /// 引用了不存在节点的 ID 会被丢弃，父节点缺失的节点连同子树一起跳过。
+    pub fn from_persisted(persisted: PersistedSessionTree) -> Self {
+        let mut nodes: HashMap<String, SessionNode> = persisted
+            .nodes
+            .into_iter()
+            .map(|p| {
+                let node = SessionNode {
+                    id: p.id.clone(),
+                    parent_id: p.parent_id,
+                    children_ids: p.children_ids,
+                    depth: p.depth,
+                    connection: NodeConnection {
+                        host: p.host,
+                        port: p.port,
+                        username: p.username,
+                        auth: p.auth.to_auth_method(),
+                        display_name: p.display_name,
+                        proxy_command: p.proxy_command,
+                    },
+                    state: NodeState::Disconnected,
+                    origin: p.origin,
+                    terminal_session_id: None,
+                    sftp_session_id: None,
+                    ssh_connection_id: None,
+                    created_at: p.created_at,
+                    failed_at: None,
+                };
+                (p.id, node)
+            })
+            .collect();
+
+        let mut tree = SessionTree::new();
+        let mut stack: Vec<String> = persisted
+            .root_ids
+            .into_iter()
+            .filter(|id| nodes.get(id).is_some_and(|n| n.parent_id.is_none()))
+            .rev()
+            .collect();
+        tree.root_ids = stack.iter().rev().cloned().collect();
+
+        while let Some(id) = stack.pop() {
+            let Some(mut node) = nodes.remove(&id) else {
+                continue;
+            };
+            node.children_ids
+                .retain(|child| nodes.get(child).and_then(|c| c.parent_id.as_deref()) == Some(&id));
+            stack.extend(node.children_ids.iter().rev().cloned());
+            tree.nodes.insert(id, node);
+        }
+
+        tree
+    }
+}
+
 // ============================================================================
 // 错误类型
 // ============================================================================
@@ -714,6 +1238,9 @@ pub enum TreeError {
 
     #[error("Connection failed: {0}")]
     ConnectionFailed(String),
+
+    #[error("Session tree depth {0} exceeds the limit of {max} levels", max = MAX_TREE_DEPTH)]
+    DepthLimitExceeded(u32),
 }
 
 // ============================================================================
@@ -774,6 +1301,60 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_drill_down_depth_limit() {
+        let mut tree = SessionTree::new();
+        let mut current = tree.add_root_node(make_connection("level-0"), NodeOrigin::Direct);
+        for level in 1..MAX_TREE_DEPTH {
+            tree.update_state(&current, NodeState::Connected).unwrap();
+            current = tree
+                .drill_down(&current, make_connection(&format!("level-{}", level)))
+                .unwrap();
+        }
+        assert_eq!(tree.get_node(&current).unwrap().depth, MAX_TREE_DEPTH - 1);
+        assert_eq!(tree.len(), MAX_TREE_DEPTH as usize);
+
+        // One more level is rejected without touching the tree
+        tree.update_state(&current, NodeState::Connected).unwrap();
+        let err = tree
+            .drill_down(&current, make_connection("too-deep"))
+            .unwrap_err();
+        assert!(matches!(err, TreeError::DepthLimitExceeded(depth) if depth == MAX_TREE_DEPTH));
+        assert_eq!(tree.len(), MAX_TREE_DEPTH as usize);
+        assert!(tree.get_node(&current).unwrap().children_ids.is_empty());
+    }
+
+    #[test]
+    fn test_expand_preset_depth_limit() {
+        let mut tree = SessionTree::new();
+        let hops = |count: u32| -> Vec<NodeConnection> {
+            (0..count)
+                .map(|i| make_connection(&format!("hop-{}", i)))
+                .collect()
+        };
+
+        // Target lands exactly on the last allowed level
+        tree.expand_manual_preset(
+            "conn-1",
+            hops(MAX_TREE_DEPTH - 1),
+            make_connection("target"),
+        )
+        .unwrap();
+        assert_eq!(tree.len(), MAX_TREE_DEPTH as usize);
+
+        let err = tree
+            .expand_auto_route(
+                "target",
+                "route-1",
+                hops(MAX_TREE_DEPTH),
+                make_connection("target"),
+            )
+            .unwrap_err();
+        assert!(matches!(err, TreeError::DepthLimitExceeded(_)));
+        assert_eq!(tree.len(), MAX_TREE_DEPTH as usize);
+        assert_eq!(tree.root_nodes().len(), 1);
+    }
+
     #[test]
     fn test_expand_manual_preset() {
         let mut tree = SessionTree::new();
@@ -860,4 +1441,383 @@ mod tests {
         let root = tree.get_node(&root_id).unwrap();
         assert!(root.children_ids.is_empty());
     }
+
+    /// prod-web (connected)
+    /// ├── prod-db "Primary DB" (connected)
+    /// └── cache (failed)
+    /// staging (connected, user "deploy")
+    /// └── prod-mirror (pending)
+    fn make_search_tree() -> SessionTree {
+        let mut tree = SessionTree::new();
+
+        let web = tree.add_root_node(make_connection("prod-web"), NodeOrigin::Direct);
+        tree.update_state(&web, NodeState::Connected).unwrap();
+        let db = tree
+            .drill_down(
+                &web,
+                make_connection("prod-db").with_display_name("Primary DB"),
+            )
+            .unwrap();
+        tree.update_state(&db, NodeState::Connected).unwrap();
+        let cache = tree.drill_down(&web, make_connection("cache")).unwrap();
+        tree.update_state(
+            &cache,
+            NodeState::Failed {
+                error: "timeout".to_string(),
+            },
+        )
+        .unwrap();
+
+        let staging = tree.add_root_node(
+            NodeConnection::new("staging", 22, "deploy"),
+            NodeOrigin::Direct,
+        );
+        tree.update_state(&staging, NodeState::Connected).unwrap();
+        tree.drill_down(&staging, make_connection("Prod-Mirror"))
+            .unwrap();
+
+        tree
+    }
+
+    fn hosts(nodes: &[FlatNode]) -> Vec<&str> {
+        nodes.iter().map(|n| n.host.as_str()).collect()
+    }
+
+    #[test]
+    fn test_search_nodes_case_insensitive() {
+        let tree = make_search_tree();
+
+        let found = tree.search_nodes("PROD", None);
+        assert_eq!(hosts(&found), vec!["prod-web", "prod-db", "Prod-Mirror"]);
+
+        // Rendering metadata comes from the full tree, not the filtered list
+        let db = &found[1];
+        assert_eq!(db.depth, 1);
+        assert!(!db.is_last_child);
+        assert!(found[2].is_last_child);
+
+        // display_name and username are searched too
+        assert_eq!(hosts(&tree.search_nodes("primary", None)), vec!["prod-db"]);
+        assert_eq!(hosts(&tree.search_nodes("DEPLOY", None)), vec!["staging"]);
+
+        // Empty query matches everything
+        assert_eq!(tree.search_nodes("", None).len(), tree.len());
+    }
+
+    #[test]
+    fn test_search_nodes_state_filter() {
+        let tree = make_search_tree();
+
+        let connected = tree.search_nodes("prod", Some(&NodeState::Connected));
+        assert_eq!(hosts(&connected), vec!["prod-web", "prod-db"]);
+
+        let pending = tree.search_nodes("prod", Some(&NodeState::Pending));
+        assert_eq!(hosts(&pending), vec!["Prod-Mirror"]);
+
+        // Failed matches regardless of the error text
+        let failed = tree.search_nodes(
+            "",
+            Some(&NodeState::Failed {
+                error: String::new(),
+            }),
+        );
+        assert_eq!(hosts(&failed), vec!["cache"]);
+
+        assert!(tree
+            .search_nodes("prod", Some(&NodeState::Disconnected))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_all_nodes_bottom_up_children_first() {
+        let tree = make_search_tree();
+        assert!(SessionTree::new().all_nodes_bottom_up().is_empty());
+
+        let order: Vec<&str> = tree
+            .all_nodes_bottom_up()
+            .iter()
+            .map(|n| n.connection.host.as_str())
+            .collect();
+        assert_eq!(
+            order,
+            vec!["prod-db", "cache", "prod-web", "Prod-Mirror", "staging"]
+        );
+
+        // Every node comes after all of its descendants
+        let nodes = tree.all_nodes_bottom_up();
+        let position = |id: &str| nodes.iter().position(|n| n.id == id).unwrap();
+        for node in &nodes {
+            if let Some(parent_id) = &node.parent_id {
+                assert!(position(&node.id) < position(parent_id));
+            }
+        }
+    }
+    #[test]
+    fn test_mark_connection_lost_cascades_to_descendants() {
+        let mut tree = make_search_tree();
+        let web = tree.root_ids[0].clone();
+        let db = tree.get_node(&web).unwrap().children_ids[0].clone();
+        let cache = tree.get_node(&web).unwrap().children_ids[1].clone();
+        tree.set_ssh_connection_id(&web, "ssh-web".to_string())
+            .unwrap();
+        tree.set_ssh_connection_id(&db, "ssh-db".to_string())
+            .unwrap();
+        tree.set_terminal_session_id(&db, "term-db".to_string())
+            .unwrap();
+
+        // A stale notification for an old connection is ignored
+        assert!(tree
+            .mark_connection_lost(&web, "ssh-old")
+            .changed
+            .is_empty());
+        assert!(tree.get_node(&db).unwrap().state.is_connected());
+
+        let lost = tree.mark_connection_lost(&web, "ssh-web");
+        assert_eq!(lost.changed, vec![db.clone(), web.clone()]);
+        // The child's connection is still registered and must be torn down
+        assert_eq!(lost.orphaned, vec!["ssh-db".to_string()]);
+
+        for id in [&web, &db] {
+            let node = tree.get_node(id).unwrap();
+            assert_eq!(node.state, NodeState::Disconnected);
+            assert!(node.ssh_connection_id.is_none());
+            assert!(node.terminal_session_id.is_none());
+        }
+        // Failed nodes keep their error
+        assert!(matches!(
+            tree.get_node(&cache).unwrap().state,
+            NodeState::Failed { .. }
+        ));
+        // The other root is untouched
+        let staging = tree.root_ids[1].clone();
+        assert!(tree.get_node(&staging).unwrap().state.is_connected());
+    }
+
+    #[test]
+    fn test_subtree_reconnect_plan() {
+        let mut tree = SessionTree::new();
+        let root = tree.add_root_node(make_connection("bastion"), NodeOrigin::Direct);
+        let connect = |tree: &mut SessionTree, id: &str| {
+            tree.update_state(id, NodeState::Connected).unwrap();
+            tree.set_ssh_connection_id(id, format!("ssh-{}", id))
+                .unwrap();
+        };
+        connect(&mut tree, &root);
+        let mid = tree.drill_down(&root, make_connection("mid")).unwrap();
+        connect(&mut tree, &mid);
+        let leaf = tree.drill_down(&mid, make_connection("leaf")).unwrap();
+        connect(&mut tree, &leaf);
+        let pending = tree.drill_down(&mid, make_connection("pending")).unwrap();
+        let sibling = tree.drill_down(&root, make_connection("sibling")).unwrap();
+        connect(&mut tree, &sibling);
+
+        // Everything live except the never-connected leaf
+        assert_eq!(
+            tree.subtree_reconnect_plan(&root).unwrap(),
+            vec![pending.clone()]
+        );
+
+        // The hop drops: its whole subtree goes with it
+        tree.mark_connection_lost(&mid, &format!("ssh-{}", mid));
+        let plan = vec![mid.clone(), leaf.clone(), pending.clone()];
+        assert_eq!(tree.subtree_reconnect_plan(&root).unwrap(), plan);
+        assert_eq!(tree.subtree_reconnect_plan(&mid).unwrap(), plan);
+
+        // Asking for a leaf whose parent is down brings the parent up first
+        assert_eq!(
+            tree.subtree_reconnect_plan(&leaf).unwrap(),
+            vec![mid.clone(), leaf.clone()]
+        );
+        assert!(tree.subtree_reconnect_plan(&sibling).unwrap().is_empty());
+
+        // A descendant still marked Connected under a hop being rebuilt is redone
+        connect(&mut tree, &leaf);
+        tree.update_state(
+            &mid,
+            NodeState::Failed {
+                error: "reset".to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(tree.subtree_reconnect_plan(&mid).unwrap(), plan);
+
+        assert!(matches!(
+            tree.subtree_reconnect_plan("missing"),
+            Err(TreeError::NodeNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_find_on_path_detects_ancestor() {
+        let mut tree = SessionTree::new();
+        let bastion = tree.add_root_node(make_connection("bastion"), NodeOrigin::Direct);
+        tree.update_state(&bastion, NodeState::Connected).unwrap();
+        let app = tree.drill_down(&bastion, make_connection("app")).unwrap();
+        tree.update_state(&app, NodeState::Connected).unwrap();
+
+        // Grandparent and parent both count, host is case-insensitive
+        let found = tree
+            .find_on_path(&app, &make_connection("BASTION"))
+            .unwrap();
+        assert_eq!(found.id, bastion);
+        assert_eq!(
+            tree.find_on_path(&app, &make_connection("app")).unwrap().id,
+            app
+        );
+
+        // A different port or user is a different endpoint
+        let mut other_port = make_connection("bastion");
+        other_port.port = 2222;
+        assert!(tree.find_on_path(&app, &other_port).is_none());
+        let other_user = NodeConnection::new("bastion", 22, "root");
+        assert!(tree.find_on_path(&app, &other_user).is_none());
+
+        // Siblings are not on the path
+        let db = tree.drill_down(&bastion, make_connection("db")).unwrap();
+        assert!(tree.find_on_path(&app, &make_connection("db")).is_none());
+        assert!(tree.find_on_path(&db, &make_connection("db")).is_some());
+    }
+
+    #[test]
+    fn test_persisted_roundtrip_multi_level() {
+        let mut tree = SessionTree::new();
+        let app = tree
+            .expand_manual_preset(
+                "saved-1",
+                vec![make_connection("jump").with_password("hunter2")],
+                make_connection("app").with_key("~/.ssh/id_ed25519", Some("secret".into())),
+            )
+            .unwrap();
+        let jump = tree.get_node(&app).unwrap().parent_id.clone().unwrap();
+        tree.update_state(&app, NodeState::Connected).unwrap();
+        tree.set_ssh_connection_id(&app, "ssh-app".to_string())
+            .unwrap();
+        let db = tree
+            .drill_down(&app, make_connection("db").with_display_name("DB"))
+            .unwrap();
+        let other = tree.add_root_node(make_connection("other"), NodeOrigin::Direct);
+
+        let bytes = tree.to_persisted().to_bytes().unwrap();
+        let restored =
+            SessionTree::from_persisted(PersistedSessionTree::from_bytes(&bytes).unwrap());
+
+        assert_eq!(restored.len(), 4);
+        let ids = |t: &SessionTree| t.flatten().into_iter().map(|n| n.id).collect::<Vec<_>>();
+        assert_eq!(ids(&restored), ids(&tree));
+        assert_eq!(
+            ids(&restored),
+            vec![jump.clone(), app.clone(), db.clone(), other]
+        );
+
+        for id in restored.node_ids() {
+            let node = restored.get_node(&id).unwrap();
+            assert_eq!(node.state, NodeState::Disconnected);
+            assert!(node.ssh_connection_id.is_none());
+        }
+        let db_node = restored.get_node(&db).unwrap();
+        assert_eq!(db_node.depth, 2);
+        assert_eq!(db_node.parent_id.as_deref(), Some(app.as_str()));
+        assert_eq!(db_node.connection.display_name.as_deref(), Some("DB"));
+        assert!(matches!(
+            restored.get_node(&app).unwrap().origin,
+            NodeOrigin::ManualPreset { ref saved_connection_id, hop_index: 1 }
+                if saved_connection_id == "saved-1"
+        ));
+
+        // Credentials never reach the serialized form
+        let raw = String::from_utf8_lossy(&bytes);
+        assert!(!raw.contains("hunter2"));
+        assert!(!raw.contains("secret"));
+        assert!(matches!(
+            &restored.get_node(&jump).unwrap().connection.auth,
+            AuthMethod::Password { password } if password.is_empty()
+        ));
+        assert!(matches!(
+            &restored.get_node(&app).unwrap().connection.auth,
+            AuthMethod::Key {
+                passphrase: None,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_drill_down_flags_ancestor_host() {
+        let mut tree = SessionTree::new();
+        let bastion = tree.add_root_node(make_connection("bastion"), NodeOrigin::Direct);
+        tree.update_state(&bastion, NodeState::Connected).unwrap();
+        let app = tree
+            .drill_down_flagged(&bastion, make_connection("app"))
+            .unwrap();
+        assert!(app.cycle_ancestor.is_none());
+        tree.update_state(&app.node_id, NodeState::Connected)
+            .unwrap();
+
+        // Back into the grandparent: flagged, but the node is still added
+        let back = tree
+            .drill_down_flagged(&app.node_id, make_connection("bastion"))
+            .unwrap();
+        assert_eq!(back.cycle_ancestor.as_deref(), Some(bastion.as_str()));
+        assert!(tree.get_node(&back.node_id).is_some());
+
+        // Sibling host of `app`: not an ancestor, not flagged
+        let db = tree
+            .drill_down_flagged(&app.node_id, make_connection("db"))
+            .unwrap();
+        assert!(db.cycle_ancestor.is_none());
+        let sibling = tree
+            .drill_down_flagged(&bastion, make_connection("db"))
+            .unwrap();
+        assert!(sibling.cycle_ancestor.is_none());
+    }
+
+    #[test]
+    fn test_broken_segment_after_mid_chain_failure() {
+        let mut tree = SessionTree::new();
+        let hops = vec![make_connection("jump-01"), make_connection("bastion")];
+        let target = tree
+            .expand_manual_preset("saved-conn-123", hops, make_connection("internal-db"))
+            .unwrap();
+        let path: Vec<String> = tree
+            .get_path_to_node(&target)
+            .iter()
+            .map(|n| n.id.clone())
+            .collect();
+        let (jump, bastion) = (path[0].clone(), path[1].clone());
+
+        // Nothing connected yet: the whole chain
+        assert_eq!(tree.broken_segment(&target).unwrap(), path);
+
+        for (i, id) in path.iter().enumerate() {
+            tree.update_state(id, NodeState::Connected).unwrap();
+            tree.set_ssh_connection_id(id, format!("ssh-{}", i))
+                .unwrap();
+        }
+        assert!(tree.broken_segment(&target).unwrap().is_empty());
+
+        // The middle hop drops: it and everything below need reconnecting
+        tree.mark_connection_lost(&bastion, "ssh-1");
+        assert_eq!(
+            tree.broken_segment(&target).unwrap(),
+            vec![bastion.clone(), target.clone()]
+        );
+
+        // Targeted reconnect of the segment leaves the first hop alone
+        tree.update_state(&bastion, NodeState::Connected).unwrap();
+        tree.set_ssh_connection_id(&bastion, "ssh-1b".to_string())
+            .unwrap();
+        assert_eq!(tree.broken_segment(&target).unwrap(), vec![target.clone()]);
+        tree.update_state(&target, NodeState::Connected).unwrap();
+        tree.set_ssh_connection_id(&target, "ssh-2b".to_string())
+            .unwrap();
+        assert!(tree.broken_segment(&target).unwrap().is_empty());
+
+        let first = tree.get_node(&jump).unwrap();
+        assert_eq!(first.ssh_connection_id.as_deref(), Some("ssh-0"));
+
+        assert!(matches!(
+            tree.broken_segment("missing"),
+            Err(TreeError::NodeNotFound(_))
+        ));
+    }
 }
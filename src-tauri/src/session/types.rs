@@ -1,12 +1,17 @@
 //! Session Types and Data Structures
 
+use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::sync::{broadcast, mpsc, oneshot};
 
+use super::idle::IdleTracker;
+use super::notify::ActivityNotifier;
+use super::recording::SessionRecorder;
 use super::scroll_buffer::ScrollBuffer;
 use super::state::{SessionState, SessionStateMachine};
+use crate::bridge::PasteGuard;
 use crate::ssh::{HandleController, SessionCommand};
 
 // Re-export AuthMethod from ssh module (single source of truth)
@@ -35,6 +40,9 @@ pub struct SessionConfig {
     /// Initial terminal rows
     #[serde(default = "default_rows")]
     pub rows: u32,
+    /// Optional ProxyCommand used instead of a direct TCP connection
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_command: Option<String>,
 }
 
 fn default_cols() -> u32 {
@@ -62,6 +70,7 @@ impl SessionConfig {
             color: None,
             cols: 80,
             rows: 24,
+            proxy_command: None,
         }
     }
 
@@ -82,6 +91,7 @@ impl SessionConfig {
             color: None,
             cols: 80,
             rows: 24,
+            proxy_command: None,
         }
     }
 
@@ -150,6 +160,14 @@ pub struct SessionEntry {
     pub scroll_buffer: Arc<ScrollBuffer>,
     /// Output broadcast channel for terminal data (supports WS reattach)
     pub output_tx: broadcast::Sender<Vec<u8>>,
+    /// Last PTY I/O time, used for idle-timeout disconnect
+    pub idle_tracker: Arc<IdleTracker>,
+    /// Output watcher for bell / idle / pattern notifications
+    pub activity_notifier: Arc<Mutex<ActivityNotifier>>,
+    /// Multi-line paste confirmation, shared with every bridge of this session
+    pub paste_guard: Arc<PasteGuard>,
+    /// asciicast recorder (idle unless `session_start_recording` was called)
+    pub recorder: Arc<Mutex<SessionRecorder>>,
     /// WS detached flag (true while client disconnected)
     pub ws_detached: bool,
     /// Cancel handle for WS detach cleanup task
@@ -177,6 +195,10 @@ impl SessionEntry {
             handle_controller: None,
             scroll_buffer: Arc::new(ScrollBuffer::new()), // Default 10k lines
             output_tx,
+            idle_tracker: Arc::new(IdleTracker::new()),
+            activity_notifier: Arc::new(Mutex::new(ActivityNotifier::default())),
+            paste_guard: Arc::new(PasteGuard::default()),
+            recorder: Arc::new(Mutex::new(SessionRecorder::new())),
             ws_detached: false,
             ws_detach_cancel: None,
             created_at: Instant::now(),
@@ -203,6 +225,10 @@ impl SessionEntry {
             handle_controller: None,
             scroll_buffer: Arc::new(ScrollBuffer::with_capacity(max_lines)),
             output_tx,
+            idle_tracker: Arc::new(IdleTracker::new()),
+            activity_notifier: Arc::new(Mutex::new(ActivityNotifier::default())),
+            paste_guard: Arc::new(PasteGuard::default()),
+            recorder: Arc::new(Mutex::new(SessionRecorder::new())),
             ws_detached: false,
             ws_detach_cancel: None,
             created_at: Instant::now(),
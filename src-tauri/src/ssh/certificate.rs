@@ -0,0 +1,331 @@
+//! OpenSSH Certificate Inspection
+//!
+//! Parses the public part of an OpenSSH certificate (`*-cert.pub`) to read
+//! its validity window and principals, so short-lived certificates can be
+//! flagged before a connection attempt instead of failing with an opaque
+//! authentication error.
+//!
+//! # Wire Format (PROTOCOL.certkeys)
+//!
+//! ```text
+//! string    cert type        e.g. "ssh-ed25519-cert-v01@openssh.com"
+//! string    nonce
+//! ....      key-specific public key fields
+//! uint64    serial
+//! uint32    type             1 = user, 2 = host
+//! string    key id
+//! string    valid principals (packed list of strings)
+//! uint64    valid after      (unix seconds)
+//! uint64    valid before     (unix seconds, u64::MAX = forever)
+//! ....      critical options, extensions, reserved, CA key, signature
+//! ```
+//!
+//! Only the fields up to `valid before` are decoded; the signature is not
+//! verified here (the server does that during authentication).
+
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::error::SshError;
+use super::proxy::expand_tilde;
+
+/// Default window before expiry in which a certificate is reported as "expiring soon"
+pub const DEFAULT_EXPIRY_WARNING_SECS: u64 = 15 * 60;
+
+/// Decoded certificate metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertificateInfo {
+    /// Certificate key type (e.g. `ssh-ed25519-cert-v01@openssh.com`)
+    pub cert_type: String,
+    /// Serial number assigned by the CA
+    pub serial: u64,
+    /// Key ID assigned by the CA
+    pub key_id: String,
+    /// Principals the certificate is valid for (empty = any)
+    pub principals: Vec<String>,
+    /// Start of the validity window (unix seconds)
+    pub valid_after: u64,
+    /// End of the validity window (unix seconds, `u64::MAX` = forever)
+    pub valid_before: u64,
+}
+
+/// Certificate validity status returned to the frontend
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct CertStatus {
+    /// Certificate file that was inspected
+    pub cert_path: String,
+    /// Key ID assigned by the CA
+    pub key_id: String,
+    /// Principals the certificate is valid for
+    pub principals: Vec<String>,
+    /// Start of the validity window (RFC 3339), `None` if unbounded
+    pub valid_from: Option<String>,
+    /// End of the validity window (RFC 3339), `None` if the cert never expires
+    pub valid_until: Option<String>,
+    /// The validity window has passed (or not yet started)
+    pub expired: bool,
+    /// The certificate expires within the warning threshold
+    pub expires_soon: bool,
+    /// Seconds until expiry (negative once expired), `None` if the cert never expires
+    pub seconds_remaining: Option<i64>,
+}
+
+impl CertificateInfo {
+    /// Parse a certificate from the contents of a `*-cert.pub` file
+    pub fn parse(contents: &str) -> Result<Self, SshError> {
+        let mut fields = contents.split_whitespace();
+        let declared_type = fields
+            .next()
+            .ok_or_else(|| SshError::CertificateParseError("Empty certificate file".into()))?;
+        let blob_b64 = fields
+            .next()
+            .ok_or_else(|| SshError::CertificateParseError("Missing certificate data".into()))?;
+
+        if !declared_type.contains("-cert-v01@openssh.com") {
+            return Err(SshError::CertificateParseError(format!(
+                "Not an OpenSSH certificate: {}",
+                declared_type
+            )));
+        }
+
+        let blob = base64::engine::general_purpose::STANDARD
+            .decode(blob_b64)
+            .map_err(|e| SshError::CertificateParseError(format!("Invalid base64: {}", e)))?;
+
+        Self::parse_blob(&blob)
+    }
+
+    /// Parse the decoded certificate blob
+    pub fn parse_blob(blob: &[u8]) -> Result<Self, SshError> {
+        let mut r = WireReader::new(blob);
+
+        let cert_type = r.read_string_utf8()?;
+        let _nonce = r.read_string()?;
+        skip_public_key_fields(&mut r, &cert_type)?;
+
+        let serial = r.read_u64()?;
+        let _kind = r.read_u32()?;
+        let key_id = r.read_string_utf8()?;
+
+        let principals_blob = r.read_string()?;
+        let mut pr = WireReader::new(principals_blob);
+        let mut principals = Vec::new();
+        while !pr.is_empty() {
+            principals.push(pr.read_string_utf8()?);
+        }
+
+        let valid_after = r.read_u64()?;
+        let valid_before = r.read_u64()?;
+
+        Ok(Self {
+            cert_type,
+            serial,
+            key_id,
+            principals,
+            valid_after,
+            valid_before,
+        })
+    }
+
+    /// Evaluate validity at `now` (unix seconds) with a warning threshold
+    pub fn status_at(&self, cert_path: &str, now: u64, warn_within_secs: u64) -> CertStatus {
+        let never_expires = self.valid_before == u64::MAX;
+        let expired = now < self.valid_after || (!never_expires && now >= self.valid_before);
+        let expires_soon =
+            !expired && !never_expires && self.valid_before.saturating_sub(now) <= warn_within_secs;
+
+        CertStatus {
+            cert_path: cert_path.to_string(),
+            key_id: self.key_id.clone(),
+            principals: self.principals.clone(),
+            valid_from: (self.valid_after > 0)
+                .then(|| to_rfc3339(self.valid_after))
+                .flatten(),
+            valid_until: (!never_expires)
+                .then(|| to_rfc3339(self.valid_before))
+                .flatten(),
+            expired,
+            expires_soon,
+            seconds_remaining: (!never_expires).then(|| self.valid_before as i64 - now as i64),
+        }
+    }
+}
+
+/// Read and evaluate a certificate file against the current time
+///
+/// `~` in `cert_path` is expanded to the home directory.
+pub fn check_certificate_file(
+    cert_path: &str,
+    warn_within_secs: u64,
+) -> Result<CertStatus, SshError> {
+    let path = expand_tilde(cert_path);
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| SshError::CertificateLoadError(format!("{}: {}", path, e)))?;
+    let info = CertificateInfo::parse(&contents)?;
+    let now = Utc::now().timestamp().max(0) as u64;
+    Ok(info.status_at(&path, now, warn_within_secs))
+}
+
+fn to_rfc3339(secs: u64) -> Option<String> {
+    let secs = i64::try_from(secs).ok()?;
+    DateTime::<Utc>::from_timestamp(secs, 0).map(|t| t.to_rfc3339())
+}
+
+/// Skip the key-type-specific public key fields that precede the serial
+fn skip_public_key_fields(r: &mut WireReader<'_>, cert_type: &str) -> Result<(), SshError> {
+    let field_count = match cert_type {
+        // e, n
+        "ssh-rsa-cert-v01@openssh.com" => 2,
+        // p, q, g, y
+        "ssh-dss-cert-v01@openssh.com" => 4,
+        // curve, public key
+        t if t.starts_with("ecdsa-sha2-") => 2,
+        // public key
+        "ssh-ed25519-cert-v01@openssh.com" => 1,
+        // curve, public key, application
+        "sk-ecdsa-sha2-nistp256-cert-v01@openssh.com" => 3,
+        // public key, application
+        "sk-ssh-ed25519-cert-v01@openssh.com" => 2,
+        other => {
+            return Err(SshError::CertificateParseError(format!(
+                "Unsupported certificate type: {}",
+                other
+            )))
+        }
+    };
+    for _ in 0..field_count {
+        r.read_string()?;
+    }
+    Ok(())
+}
+
+/// Minimal SSH wire-format reader (RFC 4251 §5)
+struct WireReader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> WireReader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], SshError> {
+        if self.buf.len() < n {
+            return Err(SshError::CertificateParseError(
+                "Truncated certificate data".into(),
+            ));
+        }
+        let (head, tail) = self.buf.split_at(n);
+        self.buf = tail;
+        Ok(head)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, SshError> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn read_u64(&mut self) -> Result<u64, SshError> {
+        let b = self.take(8)?;
+        let mut arr = [0u8; 8];
+        arr.copy_from_slice(b);
+        Ok(u64::from_be_bytes(arr))
+    }
+
+    fn read_string(&mut self) -> Result<&'a [u8], SshError> {
+        let len = self.read_u32()? as usize;
+        self.take(len)
+    }
+
+    fn read_string_utf8(&mut self) -> Result<String, SshError> {
+        let bytes = self.read_string()?;
+        String::from_utf8(bytes.to_vec())
+            .map_err(|_| SshError::CertificateParseError("Invalid UTF-8 in certificate".into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Valid 2020-01-01 .. 2020-01-02, principals: alice, deploy
+    const EXPIRED_CERT: &str = "ssh-ed25519-cert-v01@openssh.com AAAAIHNzaC1lZDI1NTE5LWNlcnQtdjAxQG9wZW5zc2guY29tAAAAINySlLQqPInhkvpJU6Ym9pB6GwOby/kvufn/3bclfeI4AAAAIMLuPzhlVcYF+bV8kvmaV6oyFWPUA/vuN4JUrwbvp3eFAAAAAAAAAAAAAAABAAAACmV4cGlyZWQtaWQAAAATAAAABWFsaWNlAAAABmRlcGxveQAAAABeC+EAAAAAAF4NMoAAAAAAAAAAggAAABVwZXJtaXQtWDExLWZvcndhcmRpbmcAAAAAAAAAF3Blcm1pdC1hZ2VudC1mb3J3YXJkaW5nAAAAAAAAABZwZXJtaXQtcG9ydC1mb3J3YXJkaW5nAAAAAAAAAApwZXJtaXQtcHR5AAAAAAAAAA5wZXJtaXQtdXNlci1yYwAAAAAAAAAAAAAAMwAAAAtzc2gtZWQyNTUxOQAAACDBXM0N4pCk3MAGcdaIlr802GHN32Fa4hC4XhlMM0u2gwAAAFMAAAALc3NoLWVkMjU1MTkAAABAFeoxAgiYK2F8mVe355COoUIhXKJOK1nHMQqzS881fl7gSiVLqkXg7H2gIFYu3rbV50v9HCt/YYGuhHjXnmNiCA== user";
+
+    /// Valid 2020-01-01 .. 2999-12-31, principal: alice
+    const VALID_CERT: &str = "ssh-ed25519-cert-v01@openssh.com AAAAIHNzaC1lZDI1NTE5LWNlcnQtdjAxQG9wZW5zc2guY29tAAAAIHtzHoedf+Gt/JYFVMylvbLvw0G/VOzADAfGk12/yc2yAAAAIMLuPzhlVcYF+bV8kvmaV6oyFWPUA/vuN4JUrwbvp3eFAAAAAAAAAAAAAAABAAAACHZhbGlkLWlkAAAACQAAAAVhbGljZQAAAABeC+EAAAAAB5FdeoAAAAAAAAAAggAAABVwZXJtaXQtWDExLWZvcndhcmRpbmcAAAAAAAAAF3Blcm1pdC1hZ2VudC1mb3J3YXJkaW5nAAAAAAAAABZwZXJtaXQtcG9ydC1mb3J3YXJkaW5nAAAAAAAAAApwZXJtaXQtcHR5AAAAAAAAAA5wZXJtaXQtdXNlci1yYwAAAAAAAAAAAAAAMwAAAAtzc2gtZWQyNTUxOQAAACDBXM0N4pCk3MAGcdaIlr802GHN32Fa4hC4XhlMM0u2gwAAAFMAAAALc3NoLWVkMjU1MTkAAABAwEjXhHcHDQZh/Z63Ci4bUxfY84GfGMhUzFF/l8LmUuTfdxLwEYUYFAnwgfN3zkCcZQWUJv94d1eDeFy29P2hAQ== user";
+
+    const JAN_1_2020: u64 = 1_577_836_800;
+
+    #[test]
+    fn test_parse_expired_cert() {
+        let info = CertificateInfo::parse(EXPIRED_CERT).unwrap();
+        assert_eq!(info.cert_type, "ssh-ed25519-cert-v01@openssh.com");
+        assert_eq!(info.key_id, "expired-id");
+        assert_eq!(info.principals, vec!["alice", "deploy"]);
+        assert_eq!(info.valid_after, JAN_1_2020);
+        assert_eq!(info.valid_before, JAN_1_2020 + 86_400);
+
+        let status = info.status_at("expired-cert.pub", JAN_1_2020 + 100_000, 900);
+        assert!(status.expired);
+        assert!(!status.expires_soon);
+        assert_eq!(
+            status.valid_until.as_deref(),
+            Some("2020-01-02T00:00:00+00:00")
+        );
+    }
+
+    #[test]
+    fn test_parse_valid_cert() {
+        let info = CertificateInfo::parse(VALID_CERT).unwrap();
+        assert_eq!(info.key_id, "valid-id");
+        assert_eq!(info.principals, vec!["alice"]);
+
+        let now = Utc::now().timestamp() as u64;
+        let status = info.status_at("valid-cert.pub", now, DEFAULT_EXPIRY_WARNING_SECS);
+        assert!(!status.expired);
+        assert!(!status.expires_soon);
+        assert!(status.seconds_remaining.unwrap() > 0);
+    }
+
+    #[test]
+    fn test_expires_soon_threshold() {
+        let info = CertificateInfo::parse(EXPIRED_CERT).unwrap();
+        let ten_min_before_expiry = info.valid_before - 600;
+
+        let status = info.status_at("c", ten_min_before_expiry, 900);
+        assert!(!status.expired);
+        assert!(status.expires_soon);
+        assert_eq!(status.seconds_remaining, Some(600));
+
+        let status = info.status_at("c", ten_min_before_expiry, 300);
+        assert!(!status.expires_soon);
+    }
+
+    #[test]
+    fn test_not_yet_valid_is_expired() {
+        let info = CertificateInfo::parse(VALID_CERT).unwrap();
+        let status = info.status_at("c", JAN_1_2020 - 1, 900);
+        assert!(status.expired);
+    }
+
+    #[test]
+    fn test_rejects_plain_public_key() {
+        let plain =
+            "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIMLuPzhlVcYF+bV8kvmaV6oyFWPUA/vuN4JUrwbvp3eF user";
+        assert!(matches!(
+            CertificateInfo::parse(plain),
+            Err(SshError::CertificateParseError(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_truncated_blob() {
+        let info = CertificateInfo::parse_blob(&[0, 0, 0, 10, b's']);
+        assert!(info.is_err());
+    }
+}
@@ -12,16 +12,28 @@ use tracing::{debug, info, warn};
 use super::config::{AuthMethod, SshConfig};
 use super::error::SshError;
 use super::known_hosts::{get_known_hosts, HostKeyVerification};
+use super::preflight::{BannerTap, ServerBanner};
+use super::proxy_command::{expand_proxy_command, ProxyCommandStream};
 use super::session::SshSession;
 
 /// SSH Client handler for russh
 pub struct SshClient {
     config: SshConfig,
+    banner: Option<ServerBanner>,
 }
 
 impl SshClient {
     pub fn new(config: SshConfig) -> Self {
-        Self { config }
+        Self {
+            config,
+            banner: None,
+        }
+    }
+
+    /// Record the server identification string during the handshake
+    pub fn with_banner(mut self, banner: ServerBanner) -> Self {
+        self.banner = Some(banner);
+        self
     }
 
     /// Connect to the SSH server and return a session
@@ -30,13 +42,6 @@ impl SshClient {
 
         info!("Connecting to SSH server at {}", addr);
 
-        // Resolve address
-        let socket_addr = addr
-            .to_socket_addrs()
-            .map_err(|e| SshError::ConnectionFailed(format!("Failed to resolve address: {}", e)))?
-            .next()
-            .ok_or_else(|| SshError::ConnectionFailed("No address found".to_string()))?;
-
         // SSH keepalive config (defense-in-depth):
         // Layer 1 (here): russh native keepalive — safety net in case app heartbeat stalls
         // Layer 2: App-level heartbeat (15s) in connection_registry — provides granular
@@ -60,14 +65,58 @@ impl SshClient {
             self.config.trust_host_key,
         );
 
-        // Connect with timeout
-        let mut handle = tokio::time::timeout(
-            Duration::from_secs(self.config.timeout_secs),
-            client::connect(Arc::new(ssh_config), socket_addr, handler),
-        )
-        .await
-        .map_err(|_| SshError::Timeout("Connection timed out".to_string()))?
-        .map_err(|e| SshError::ConnectionFailed(e.to_string()))?;
+        // Connect with timeout, either over TCP or over a ProxyCommand's stdio
+        let connect_timeout = Duration::from_secs(self.config.timeout_secs);
+        let connect_result = if let Some(template) = self.config.proxy_command.as_deref() {
+            let command = expand_proxy_command(
+                template,
+                &self.config.host,
+                self.config.port,
+                &self.config.username,
+            );
+            warn!(
+                "Connecting to {} through ProxyCommand (runs a local command)",
+                addr
+            );
+            let stream = ProxyCommandStream::spawn(&command)?;
+            tokio::time::timeout(connect_timeout, async {
+                match self.banner.clone() {
+                    Some(banner) => {
+                        let stream = BannerTap::new(stream, banner);
+                        client::connect_stream(Arc::new(ssh_config), stream, handler).await
+                    }
+                    None => client::connect_stream(Arc::new(ssh_config), stream, handler).await,
+                }
+            })
+            .await
+        } else {
+            // Resolve address
+            let socket_addr = addr
+                .to_socket_addrs()
+                .map_err(|e| {
+                    SshError::ConnectionFailed(format!("Failed to resolve address: {}", e))
+                })?
+                .next()
+                .ok_or_else(|| SshError::ConnectionFailed("No address found".to_string()))?;
+
+            tokio::time::timeout(connect_timeout, async {
+                match self.banner.clone() {
+                    Some(banner) => {
+                        let stream = tokio::net::TcpStream::connect(socket_addr)
+                            .await
+                            .map_err(|e| SshError::ConnectionFailed(e.to_string()))?;
+                        let stream = BannerTap::new(stream, banner);
+                        client::connect_stream(Arc::new(ssh_config), stream, handler).await
+                    }
+                    None => client::connect(Arc::new(ssh_config), socket_addr, handler).await,
+                }
+            })
+            .await
+        };
+
+        let mut handle = connect_result
+            .map_err(|_| SshError::Timeout("Connection timed out".to_string()))?
+            .map_err(|e| SshError::ConnectionFailed(e.to_string()))?;
 
         debug!("SSH handshake completed");
 
@@ -34,6 +34,12 @@ pub struct SshConfig {
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub proxy_chain: Option<Vec<ProxyHopConfig>>,
 
+    /// Optional ProxyCommand (OpenSSH semantics, `%h`/`%p`/`%r` substituted).
+    /// When set, the command's stdio is used as the transport instead of a
+    /// TCP socket. See `ssh::proxy_command` for the security notes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_command: Option<String>,
+
     /// Strict host key checking (default: false for user-friendly behavior)
     /// - true: reject connections to unknown hosts
     /// - false: auto-accept unknown hosts, still reject changed keys
@@ -154,6 +160,7 @@ impl Default for SshConfig {
             cols: 80,
             rows: 24,
             proxy_chain: None,
+            proxy_command: None,
             strict_host_key_checking: false,
             trust_host_key: None,
         }
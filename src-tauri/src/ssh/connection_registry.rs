@@ -44,7 +44,7 @@ use tokio::task::JoinHandle;
 use tracing::{debug, error, info, warn};
 
 use super::handle_owner::HandleController;
-use super::{AuthMethod as SshAuthMethod, SshClient, SshConfig};
+use super::{AuthMethod as SshAuthMethod, ServerBanner, SshClient, SshConfig, SshError};
 use crate::session::{AuthMethod, RemoteEnvInfo, SessionConfig};
 use crate::sftp::error::SftpError;
 use crate::sftp::session::SftpSession;
@@ -65,6 +65,9 @@ const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
 /// Heartbeat consecutive failure threshold → mark LinkDown
 const HEARTBEAT_FAIL_THRESHOLD: u32 = 2;
 
+/// Connect timeout for `test_connection` (shorter than the 30s pool default)
+const TEST_CONNECT_TIMEOUT_SECS: u64 = 10;
+
 // ═══════════════════════════════════════════════════════════════════════════════
 // 🛑 RECONNECT CONSTANTS - REMOVED
 // ═══════════════════════════════════════════════════════════════════════════════
@@ -872,46 +875,15 @@ impl SshConnectionRegistry {
             connection_id, config.username, config.host, config.port
         );
 
+        self.warn_if_certificate_expiring(&config).await;
+
         // 转换 SessionConfig 到 SshConfig
-        let ssh_config = SshConfig {
-            host: config.host.clone(),
-            port: config.port,
-            username: config.username.clone(),
-            auth: match &config.auth {
-                AuthMethod::Password { password } => SshAuthMethod::Password {
-                    password: password.clone(),
-                },
-                AuthMethod::Key {
-                    key_path,
-                    passphrase,
-                } => SshAuthMethod::Key {
-                    key_path: key_path.clone(),
-                    passphrase: passphrase.clone(),
-                },
-                AuthMethod::Certificate {
-                    key_path,
-                    cert_path,
-                    passphrase,
-                } => SshAuthMethod::Certificate {
-                    key_path: key_path.clone(),
-                    cert_path: cert_path.clone(),
-                    passphrase: passphrase.clone(),
-                },
-                AuthMethod::Agent => SshAuthMethod::Agent,
-                AuthMethod::KeyboardInteractive => {
-                    // KBI sessions must use the dedicated ssh_connect_kbi command
-                    return Err(ConnectionRegistryError::ConnectionFailed(
-                        "KeyboardInteractive must use ssh_connect_kbi command".to_string(),
-                    ));
-                }
-            },
-            timeout_secs: 30,
-            cols: config.cols,
-            rows: config.rows,
-            proxy_chain: None,
-            strict_host_key_checking: false,
-            trust_host_key: None, // Connection pool uses known_hosts, no TOFU here
-        };
+        let ssh_config = pool_ssh_config(&config).ok_or_else(|| {
+            // KBI sessions must use the dedicated ssh_connect_kbi command
+            ConnectionRegistryError::ConnectionFailed(
+                "KeyboardInteractive must use ssh_connect_kbi command".to_string(),
+            )
+        })?;
 
         // 建立 SSH 连接
         let client = SshClient::new(ssh_config);
@@ -974,6 +946,107 @@ impl SshConnectionRegistry {
         Ok(connection_id)
     }
 
+    /// 测试连接：认证、探测远程系统，然后立即断开
+    ///
+    /// 与 `connect` 不同，连接不会进入注册表：没有心跳、没有事件，
+    /// 也不会出现在会话树中。返回 SshError 以便调用方区分认证失败与网络失败。
+    /// 服务器标识行在握手时记录到 `banner`（认证失败时同样可用）。
+    pub async fn test_connection(
+        &self,
+        config: SessionConfig,
+        banner: ServerBanner,
+    ) -> Result<RemoteEnvInfo, SshError> {
+        use crate::session::env_detector::detect_remote_env;
+
+        let mut ssh_config = pool_ssh_config(&config).ok_or_else(|| {
+            SshError::AuthenticationFailed(
+                "KeyboardInteractive must use ssh_connect_kbi command".to_string(),
+            )
+        })?;
+        ssh_config.timeout_secs = TEST_CONNECT_TIMEOUT_SECS;
+
+        let test_id = format!("test-{}", uuid::Uuid::new_v4());
+        info!(
+            "Testing SSH connection {} -> {}@{}:{}",
+            test_id, config.username, config.host, config.port
+        );
+
+        let session = SshClient::new(ssh_config)
+            .with_banner(banner)
+            .connect()
+            .await?;
+        let controller = session.start(test_id.clone());
+
+        // 复用环境探测（uname），与 profiler 使用同一份 os_type
+        let env = detect_remote_env(&controller, &test_id).await;
+
+        controller.disconnect().await;
+        info!(
+            "Test connection {} closed (os_type={})",
+            test_id, env.os_type
+        );
+
+        Ok(env)
+    }
+
+    /// 连接前检查证书有效期
+    ///
+    /// 证书已过期或即将过期时发送 `ssh:cert_expiry` 事件。仅为提示，
+    /// 不阻止连接（服务器可能接受其他认证方式或时钟存在偏差）。
+    async fn warn_if_certificate_expiring(&self, config: &SessionConfig) {
+        let AuthMethod::Certificate { cert_path, .. } = &config.auth else {
+            return;
+        };
+
+        let status = match super::certificate::check_certificate_file(
+            cert_path,
+            super::certificate::DEFAULT_EXPIRY_WARNING_SECS,
+        ) {
+            Ok(status) => status,
+            Err(e) => {
+                warn!("Could not inspect certificate {}: {}", cert_path, e);
+                return;
+            }
+        };
+
+        if !status.expired && !status.expires_soon {
+            return;
+        }
+
+        let verdict = if status.expired {
+            "has expired"
+        } else {
+            "expires soon"
+        };
+        warn!(
+            "Certificate {} for {}@{} {} (valid until {:?})",
+            status.cert_path, config.username, config.host, verdict, status.valid_until
+        );
+
+        use tauri::Emitter;
+
+        // 字段与展开的 CertStatus 一致，均为 snake_case（前端类型 `CertExpiryEvent`）
+        #[derive(Clone, serde::Serialize)]
+        struct CertExpiryEvent {
+            host: String,
+            username: String,
+            #[serde(flatten)]
+            status: super::certificate::CertStatus,
+        }
+
+        let app_handle = self.app_handle.read().await;
+        if let Some(handle) = app_handle.as_ref() {
+            let event = CertExpiryEvent {
+                host: config.host.clone(),
+                username: config.username.clone(),
+                status,
+            };
+            if let Err(e) = handle.emit("ssh:cert_expiry", &event) {
+                error!("Failed to emit ssh:cert_expiry: {}", e);
+            }
+        }
+    }
+
     /// 通过已有连接建立隧道连接（用于动态钻入跳板机）
     ///
     /// # 工作原理
@@ -1018,6 +1091,8 @@ impl SshConnectionRegistry {
             parent_connection_id, target_config.username, target_config.host, target_config.port
         );
 
+        self.warn_if_certificate_expiring(&target_config).await;
+
         // 2. 通过父连接打开 direct-tcpip 隧道
         let channel = parent_conn
             .handle_controller
@@ -2659,6 +2734,49 @@ impl SshConnectionRegistry {
     }
 }
 
+/// 把 SessionConfig 转换为连接池使用的 SshConfig
+///
+/// KeyboardInteractive 返回 `None`：KBI 必须走 `ssh_connect_kbi` 命令。
+fn pool_ssh_config(config: &SessionConfig) -> Option<SshConfig> {
+    let auth = match &config.auth {
+        AuthMethod::Password { password } => SshAuthMethod::Password {
+            password: password.clone(),
+        },
+        AuthMethod::Key {
+            key_path,
+            passphrase,
+        } => SshAuthMethod::Key {
+            key_path: key_path.clone(),
+            passphrase: passphrase.clone(),
+        },
+        AuthMethod::Certificate {
+            key_path,
+            cert_path,
+            passphrase,
+        } => SshAuthMethod::Certificate {
+            key_path: key_path.clone(),
+            cert_path: cert_path.clone(),
+            passphrase: passphrase.clone(),
+        },
+        AuthMethod::Agent => SshAuthMethod::Agent,
+        AuthMethod::KeyboardInteractive => return None,
+    };
+
+    Some(SshConfig {
+        host: config.host.clone(),
+        port: config.port,
+        username: config.username.clone(),
+        auth,
+        timeout_secs: 30,
+        cols: config.cols,
+        rows: config.rows,
+        proxy_chain: None,
+        proxy_command: config.proxy_command.clone(),
+        strict_host_key_checking: false,
+        trust_host_key: None, // Connection pool uses known_hosts, no TOFU here
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2686,6 +2804,7 @@ mod tests {
                 color: None,
                 cols: 80,
                 rows: 24,
+                proxy_command: None,
             },
             handle_controller: {
                 // 创建一个 mock controller
@@ -45,6 +45,9 @@ pub enum SshError {
 
     #[error("SSH Agent error: {0}")]
     AgentError(String),
+
+    #[error(transparent)]
+    Forward(#[from] crate::forwarding::ForwardError),
 }
 
 impl From<russh::Error> for SshError {
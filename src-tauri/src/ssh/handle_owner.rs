@@ -32,6 +32,7 @@ use tracing::{debug, info, warn};
 
 use super::client::ClientHandler;
 use super::error::SshError;
+use crate::forwarding::ForwardError;
 
 /// Ping 结果类型，区分不同的失败原因
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -183,7 +184,9 @@ impl HandleController {
 
     /// Request remote port forward (tcpip-forward)
     ///
-    /// Returns the actual bound port (may differ if requested port was 0)
+    /// Returns the actual bound port (may differ if requested port was 0).
+    /// A refused request for a specific port is reported as
+    /// `ForwardError::RemotePortInUse`.
     pub async fn tcpip_forward(&self, address: &str, port: u32) -> Result<u32, SshError> {
         let (reply_tx, reply_rx) = oneshot::channel();
         self.cmd_tx
@@ -197,7 +200,17 @@ impl HandleController {
         reply_rx
             .await
             .map_err(|_| SshError::Disconnected)?
-            .map_err(|e| SshError::ConnectionFailed(e.to_string()))
+            .map_err(|e| match e {
+                // The server answers with a bare REQUEST_FAILURE when it
+                // cannot bind; port 0 can't collide, so only name it for
+                // specific ports
+                russh::Error::RequestDenied if port != 0 => ForwardError::RemotePortInUse {
+                    port: port as u16,
+                    process: None,
+                }
+                .into(),
+                e => SshError::ConnectionFailed(e.to_string()),
+            })
     }
 
     /// Cancel a remote port forward
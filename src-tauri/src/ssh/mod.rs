@@ -5,13 +5,16 @@
 //! # Features
 //! - Direct SSH connections
 //! - ProxyJump (jump host) support for HPC environments
+//! - ProxyCommand transports (e.g. `cloudflared`, `connect-proxy`)
 //! - Port forwarding (local, remote, dynamic)
 //! - SSH config file parsing
 //! - Host key verification via ~/.ssh/known_hosts
 //! - Connection pool with idle timeout (see `connection_registry`)
 //! - Keyboard-Interactive authentication (2FA) support
+//! - OpenSSH certificate validity inspection (see `certificate`)
 
 mod agent;
+pub mod certificate;
 mod client;
 mod config;
 pub mod connection_registry;
@@ -21,9 +24,11 @@ pub mod keyboard_interactive;
 pub mod known_hosts;
 pub mod preflight;
 mod proxy;
+mod proxy_command;
 mod session;
 
 pub use agent::{is_agent_available, SshAgentClient};
+pub use certificate::{check_certificate_file, CertStatus, CertificateInfo};
 pub use client::{ClientHandler, SshClient};
 pub use config::{AuthMethod, ProxyHopConfig, SshConfig};
 pub use connection_registry::{
@@ -38,7 +43,9 @@ pub use keyboard_interactive::{
 };
 pub use known_hosts::{get_known_hosts, HostKeyVerification, KnownHostsStore};
 pub use preflight::{
-    accept_host_key, check_host_key, get_host_key_cache, HostKeyCache, HostKeyStatus,
+    accept_host_key, check_host_key, get_host_key_cache, BannerTap, HostKeyCache, HostKeyStatus,
+    ServerBanner,
 };
 pub use proxy::{connect_via_proxy, connect_via_single_hop, ProxyChain, ProxyConnection, ProxyHop};
+pub use proxy_command::{expand_proxy_command, ProxyCommandStream};
 pub use session::{ExtendedSessionHandle, SessionCommand, SessionHandle, SshSession};
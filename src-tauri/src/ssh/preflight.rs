@@ -12,13 +12,16 @@
 //! 5. Frontend proceeds with `ssh_connect` with `trust_host_key` flag if user approves
 
 use std::net::ToSocketAddrs;
-use std::sync::Arc;
+use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
+use std::task::{Context, Poll};
 use std::time::{Duration, SystemTime};
 
 use dashmap::DashMap;
 use russh::client::{self, Config};
 use russh::keys::PublicKey;
 use serde::Serialize;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 use tracing::{debug, info, warn};
 
 use super::error::SshError;
@@ -27,6 +30,9 @@ use super::known_hosts::{get_known_hosts, HostKeyVerification, KnownHostsStore};
 /// Cache TTL for verified hosts (1 hour)
 const CACHE_TTL_SECS: u64 = 3600;
 
+/// Upper bound on bytes inspected while looking for the server banner
+const MAX_BANNER_BYTES: u64 = 8192;
+
 /// Result of host key preflight check
 #[derive(Debug, Clone, Serialize)]
 #[serde(tag = "status", rename_all = "snake_case")]
@@ -340,6 +346,97 @@ pub async fn check_host_key(host: &str, port: u16, timeout_secs: u64) -> HostKey
     }
 }
 
+/// Server identification string (e.g. `SSH-2.0-OpenSSH_9.6`) recorded by a [`BannerTap`]
+#[derive(Debug, Clone, Default)]
+pub struct ServerBanner(Arc<OnceLock<String>>);
+
+impl ServerBanner {
+    pub fn get(&self) -> Option<String> {
+        self.0.get().cloned()
+    }
+}
+
+/// Transport wrapper that records the server banner as the SSH handshake reads it
+///
+/// Lets a connection test report the banner without opening a second
+/// connection. Lines before the `SSH-` line are skipped, since servers may
+/// send other text first (RFC 4253 §4.2). Bytes pass through unchanged.
+pub struct BannerTap<S> {
+    inner: S,
+    banner: ServerBanner,
+    line: Vec<u8>,
+    seen: u64,
+    done: bool,
+}
+
+impl<S> BannerTap<S> {
+    pub fn new(inner: S, banner: ServerBanner) -> Self {
+        Self {
+            inner,
+            banner,
+            line: Vec::new(),
+            seen: 0,
+            done: false,
+        }
+    }
+
+    fn observe(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.seen += 1;
+            if byte != b'\n' {
+                self.line.push(byte);
+            } else {
+                let line = String::from_utf8_lossy(&self.line);
+                let trimmed = line.trim_end();
+                if trimmed.starts_with("SSH-") {
+                    let _ = self.banner.0.set(trimmed.to_string());
+                    self.done = true;
+                }
+                self.line.clear();
+            }
+            if self.done || self.seen >= MAX_BANNER_BYTES {
+                self.done = true;
+                self.line = Vec::new();
+                return;
+            }
+        }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for BannerTap<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        let before = buf.filled().len();
+        let result = Pin::new(&mut this.inner).poll_read(cx, buf);
+        if !this.done && matches!(result, Poll::Ready(Ok(()))) {
+            this.observe(&buf.filled()[before..]);
+        }
+        result
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for BannerTap<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.get_mut().inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
 /// Add a host key to known_hosts (called after user confirms Unknown status)
 pub fn accept_host_key(host: &str, port: u16, fingerprint: &str) -> Result<(), String> {
     // Note: We can't directly add from fingerprint alone - we need the full public key.
@@ -388,4 +485,42 @@ mod tests {
         cache.invalidate("test.example.com", 22);
         assert!(cache.get_verified("test.example.com", 22).is_none());
     }
+
+    #[tokio::test]
+    async fn test_banner_tap_skips_preamble() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (client, mut server) = tokio::io::duplex(64);
+        let banner = ServerBanner::default();
+        let mut tap = BannerTap::new(client, banner.clone());
+        server
+            .write_all(b"Welcome\r\nSSH-2.0-OpenSSH_9.6 Ubuntu\r\nkex")
+            .await
+            .unwrap();
+        drop(server);
+
+        // The handshake still sees every byte
+        let mut read = Vec::new();
+        tap.read_to_end(&mut read).await.unwrap();
+        assert_eq!(read, b"Welcome\r\nSSH-2.0-OpenSSH_9.6 Ubuntu\r\nkex");
+        assert_eq!(banner.get().as_deref(), Some("SSH-2.0-OpenSSH_9.6 Ubuntu"));
+    }
+
+    #[tokio::test]
+    async fn test_banner_tap_gives_up_after_limit() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (client, mut server) = tokio::io::duplex(1024);
+        let banner = ServerBanner::default();
+        let mut tap = BannerTap::new(client, banner.clone());
+        tokio::spawn(async move {
+            let noise = vec![b'x'; MAX_BANNER_BYTES as usize];
+            server.write_all(&noise).await.unwrap();
+            server.write_all(b"\nSSH-2.0-late\r\n").await.unwrap();
+        });
+
+        let mut read = Vec::new();
+        tap.read_to_end(&mut read).await.unwrap();
+        assert!(banner.get().is_none());
+    }
 }
@@ -36,7 +36,7 @@ use super::error::SshError;
 
 /// Expand ~ to home directory for path normalization
 /// This ensures paths like ~/... work correctly with russh::keys
-fn expand_tilde(path: &str) -> String {
+pub(super) fn expand_tilde(path: &str) -> String {
     if let Some(stripped) = path.strip_prefix("~/") {
         if let Some(home) = dirs::home_dir() {
             return home.join(stripped).to_string_lossy().into_owned();
@@ -0,0 +1,263 @@
+//! ProxyCommand Transport
+//!
+//! Runs a local command and uses its stdin/stdout as the SSH transport,
+//! equivalent to OpenSSH's `ProxyCommand` option. Typical uses are SSO
+//! gateways (`cloudflared access ssh --hostname %h`) and HTTP CONNECT
+//! helpers (`connect-proxy -H proxy:8080 %h %p`, `nc -X connect ...`).
+//!
+//! # Substitution
+//!
+//! | Token | Value |
+//! |-------|-------|
+//! | `%h`  | Target host |
+//! | `%p`  | Target port |
+//! | `%r`  | Remote username |
+//! | `%%`  | Literal `%` |
+//!
+//! # Security
+//!
+//! A proxy command is an arbitrary local program executed through the user's
+//! shell with the privileges of OxideTerm. It is only ever taken from the
+//! connection's own saved options (never from remote input), and the host,
+//! port and username substituted into it are shell-quoted so a crafted
+//! hostname cannot inject extra commands. Users importing connections from
+//! untrusted `.oxide` bundles or SSH configs should still review any proxy
+//! command before connecting, exactly as they would with OpenSSH.
+//!
+//! The child process is killed when the transport is dropped, so a closed
+//! SSH session never leaves an orphaned proxy behind.
+
+use std::io;
+use std::pin::Pin;
+use std::process::Stdio;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, BufReader, ReadBuf};
+use tokio::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command};
+use tracing::{debug, info, warn};
+
+use super::error::SshError;
+
+/// Expand `%h`, `%p`, `%r` and `%%` tokens in a proxy command template.
+///
+/// Substituted values are shell-quoted. Unknown tokens are left untouched
+/// so that commands containing literal `%` sequences keep working.
+pub fn expand_proxy_command(template: &str, host: &str, port: u16, username: &str) -> String {
+    let mut out = String::with_capacity(template.len() + host.len());
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.peek() {
+            Some('h') => {
+                chars.next();
+                out.push_str(&shell_quote(host));
+            }
+            Some('p') => {
+                chars.next();
+                out.push_str(&port.to_string());
+            }
+            Some('r') => {
+                chars.next();
+                out.push_str(&shell_quote(username));
+            }
+            Some('%') => {
+                chars.next();
+                out.push('%');
+            }
+            _ => out.push('%'),
+        }
+    }
+
+    out
+}
+
+/// Quote a value for safe interpolation into a shell command line.
+///
+/// Plain hostnames/usernames are returned unchanged to keep the resulting
+/// command readable in logs.
+fn shell_quote(value: &str) -> String {
+    let is_plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | ':' | '@'));
+    if is_plain {
+        return value.to_string();
+    }
+
+    #[cfg(windows)]
+    {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    }
+    #[cfg(not(windows))]
+    {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// A spawned proxy command whose stdio acts as a bidirectional byte stream.
+///
+/// Implements `AsyncRead + AsyncWrite` so it can be passed directly to
+/// `russh::client::connect_stream()`.
+pub struct ProxyCommandStream {
+    /// Kept alive for `kill_on_drop`
+    _child: Child,
+    stdin: ChildStdin,
+    stdout: ChildStdout,
+}
+
+impl ProxyCommandStream {
+    /// Spawn `command` through the platform shell.
+    ///
+    /// stderr is piped and forwarded line by line to the app log.
+    pub fn spawn(command: &str) -> Result<Self, SshError> {
+        if command.trim().is_empty() {
+            return Err(SshError::ConnectionFailed(
+                "ProxyCommand is empty".to_string(),
+            ));
+        }
+
+        info!("Spawning ProxyCommand: {}", command);
+
+        #[cfg(windows)]
+        let mut cmd = {
+            let mut c = Command::new("cmd");
+            c.arg("/C").arg(command);
+            c
+        };
+        #[cfg(not(windows))]
+        let mut cmd = {
+            let mut c = Command::new("sh");
+            c.arg("-c").arg(command);
+            c
+        };
+
+        cmd.stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        let mut child = cmd.spawn().map_err(|e| {
+            SshError::ConnectionFailed(format!("Failed to spawn ProxyCommand: {}", e))
+        })?;
+
+        let stdin = child.stdin.take().ok_or_else(|| {
+            SshError::ConnectionFailed("ProxyCommand stdin unavailable".to_string())
+        })?;
+        let stdout = child.stdout.take().ok_or_else(|| {
+            SshError::ConnectionFailed("ProxyCommand stdout unavailable".to_string())
+        })?;
+
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(forward_stderr(stderr));
+        }
+
+        debug!("ProxyCommand spawned (pid {:?})", child.id());
+
+        Ok(Self {
+            _child: child,
+            stdin,
+            stdout,
+        })
+    }
+}
+
+/// Log each stderr line of the proxy command until it exits.
+async fn forward_stderr(stderr: ChildStderr) {
+    let mut lines = BufReader::new(stderr).lines();
+    loop {
+        match lines.next_line().await {
+            Ok(Some(line)) => warn!("ProxyCommand: {}", line),
+            Ok(None) => break,
+            Err(e) => {
+                debug!("ProxyCommand stderr closed: {}", e);
+                break;
+            }
+        }
+    }
+}
+
+impl AsyncRead for ProxyCommandStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stdout).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for ProxyCommandStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stdin).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stdin).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stdin).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_basic_tokens() {
+        let cmd = expand_proxy_command(
+            "nc -X connect -x proxy:8080 %h %p",
+            "db.internal",
+            22,
+            "root",
+        );
+        assert_eq!(cmd, "nc -X connect -x proxy:8080 db.internal 22");
+    }
+
+    #[test]
+    fn test_expand_user_and_literal_percent() {
+        let cmd = expand_proxy_command("proxy --user %r --rate 50%% %h", "host", 2222, "alice");
+        assert_eq!(cmd, "proxy --user alice --rate 50% host");
+    }
+
+    #[test]
+    fn test_expand_unknown_token_preserved() {
+        let cmd = expand_proxy_command("echo %x %", "h", 22, "u");
+        assert_eq!(cmd, "echo %x %");
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_expand_quotes_hostile_host() {
+        let cmd = expand_proxy_command("nc %h %p", "evil; rm -rf ~", 22, "u");
+        assert_eq!(cmd, "nc 'evil; rm -rf ~' 22");
+    }
+
+    #[test]
+    fn test_spawn_empty_command_rejected() {
+        assert!(ProxyCommandStream::spawn("   ").is_err());
+    }
+
+    #[cfg(not(windows))]
+    #[tokio::test]
+    async fn test_stream_round_trip() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut stream = ProxyCommandStream::spawn("cat").unwrap();
+        stream.write_all(b"SSH-2.0-test\r\n").await.unwrap();
+        stream.flush().await.unwrap();
+
+        let mut buf = [0u8; 14];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"SSH-2.0-test\r\n");
+    }
+}
@@ -1,19 +1,26 @@
 //! Port forwarding rules persistence
 //!
-//! Handles serialization and deserialization of forward rules for recovery.
+//! Handles serialization and deserialization of forward rules for recovery,
+//! and the opt-in per-forward connection event log.
 
 // Allow large error types from StateError (contains redb::TransactionError ~160 bytes)
 #![allow(clippy::result_large_err)]
 
 use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 use super::store::{StateError, StateStore};
 use crate::forwarding::manager::ForwardRule;
+use crate::forwarding::{ConnectionEvent, ConnectionLogSink};
+
+/// Maximum connection events kept per forward; older ones are evicted
+pub const MAX_FORWARD_LOG_EVENTS: usize = 1000;
 
 /// Forward type enum for persistence
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "lowercase")]
 pub enum ForwardType {
     Local,
@@ -42,6 +49,10 @@ pub struct PersistedForward {
     /// Whether to auto-start on session restore
     pub auto_start: bool,
 
+    /// Whether connection accept/close events are recorded for this forward
+    #[serde(default)]
+    pub logging: bool,
+
     /// Version for migration support
     #[serde(default)]
     pub version: u32,
@@ -63,6 +74,7 @@ impl PersistedForward {
             rule,
             created_at: Utc::now(),
             auto_start,
+            logging: false,
             version: 1,
         }
     }
@@ -76,6 +88,65 @@ impl PersistedForward {
     pub fn from_bytes(data: &[u8]) -> Result<Self, rmp_serde::decode::Error> {
         rmp_serde::from_slice(data)
     }
+
+    /// Identity used to detect duplicate rules (ignores ID, session and timestamps)
+    fn dedup_key(&self) -> (ForwardType, &str, u16, &str, u16) {
+        (
+            self.forward_type.clone(),
+            self.rule.bind_address.as_str(),
+            self.rule.bind_port,
+            self.rule.target_host.as_str(),
+            self.rule.target_port,
+        )
+    }
+}
+
+/// Pick the redundant forwards among `forwards`.
+///
+/// Rules are duplicates when they share type, bind address/port and target
+/// host/port. Within a session only the newest duplicate is kept. Rules of
+/// different existing sessions are never merged; a rule whose session no
+/// longer exists is merged into any other surviving duplicate.
+fn find_redundant(
+    forwards: &[PersistedForward],
+    session_exists: impl Fn(&str) -> bool,
+) -> Vec<String> {
+    let mut groups: HashMap<_, Vec<&PersistedForward>> = HashMap::new();
+    for forward in forwards {
+        groups.entry(forward.dedup_key()).or_default().push(forward);
+    }
+
+    let mut redundant = Vec::new();
+    for (_, mut group) in groups {
+        if group.len() < 2 {
+            continue;
+        }
+
+        // Existing sessions first, newest first within each half
+        group.sort_by_key(|f| {
+            (
+                !session_exists(&f.session_id),
+                std::cmp::Reverse(f.created_at),
+            )
+        });
+
+        let mut kept: Vec<&PersistedForward> = Vec::new();
+        for forward in group {
+            let is_redundant = if session_exists(&forward.session_id) {
+                kept.iter().any(|k| k.session_id == forward.session_id)
+            } else {
+                !kept.is_empty()
+            };
+
+            if is_redundant {
+                redundant.push(forward.id.clone());
+            } else {
+                kept.push(forward);
+            }
+        }
+    }
+
+    redundant
 }
 
 /// Forward persistence operations
@@ -114,14 +185,19 @@ impl ForwardPersistence {
         Ok(PersistedForward::from_bytes(&data)?)
     }
 
-    /// Delete a forward rule (synchronous)
+    /// Delete a forward rule and its event log (synchronous)
     pub fn delete(&self, id: &str) -> Result<(), StateError> {
-        self.store.delete_forward(id)
+        self.store.delete_forward(id)?;
+        self.store.delete_forward_events(id)
     }
 
-    /// Delete a forward rule (async, non-blocking)
+    /// Delete a forward rule and its event log (async, non-blocking)
     pub async fn delete_async(&self, id: String) -> Result<(), StateError> {
-        self.store.delete_forward_async(id).await
+        self.store.delete_forward_async(id.clone()).await?;
+        let store = self.store.clone();
+        tokio::task::spawn_blocking(move || store.delete_forward_events(&id))
+            .await
+            .map_err(|e| StateError::Io(std::io::Error::other(format!("Task join error: {}", e))))?
     }
 
     /// Update auto-start flag for a forward
@@ -132,6 +208,38 @@ impl ForwardPersistence {
         Ok(())
     }
 
+    /// Update logging flag for a forward
+    pub fn update_logging(&self, id: &str, logging: bool) -> Result<(), StateError> {
+        let mut forward = self.load(id)?;
+        forward.logging = logging;
+        self.save(&forward)?;
+        Ok(())
+    }
+
+    /// Append a connection event to a forward's log, keeping at most
+    /// `max_events`
+    pub fn append_event(
+        &self,
+        id: &str,
+        event: &ConnectionEvent,
+        max_events: usize,
+    ) -> Result<(), StateError> {
+        let data = rmp_serde::to_vec_named(event)?;
+        self.store.append_forward_event(id, &data, max_events)
+    }
+
+    /// Load the newest `limit` connection events of a forward, oldest first
+    pub fn load_events(&self, id: &str, limit: usize) -> Result<Vec<ConnectionEvent>, StateError> {
+        let mut events = Vec::new();
+        for data in self.store.load_forward_events(id, limit)? {
+            match rmp_serde::from_slice(&data) {
+                Ok(event) => events.push(event),
+                Err(e) => tracing::warn!("Failed to deserialize event for forward {}: {:?}", id, e),
+            }
+        }
+        Ok(events)
+    }
+
     /// Load all forwards (synchronous)
     pub fn load_all(&self) -> Result<Vec<PersistedForward>, StateError> {
         let ids = self.store.list_forwards()?;
@@ -174,6 +282,36 @@ impl ForwardPersistence {
         Ok(forwards)
     }
 
+    /// Merge duplicate forward rules, deleting the redundant rows.
+    ///
+    /// A session exists while it is in the persisted sessions table, so rules
+    /// of sessions that are waiting to be restored are kept apart. See
+    /// [`find_redundant`] for the merge rules. Returns how many rows were
+    /// removed.
+    pub fn dedupe(&self) -> Result<usize, StateError> {
+        let sessions: HashSet<String> = self.store.list_sessions()?.into_iter().collect();
+        let forwards = self.load_all()?;
+        let redundant = find_redundant(&forwards, |session_id| sessions.contains(session_id));
+
+        for id in &redundant {
+            self.delete(id)?;
+        }
+
+        if !redundant.is_empty() {
+            tracing::info!("Merged {} duplicate forward rules", redundant.len());
+        }
+
+        Ok(redundant.len())
+    }
+
+    /// Merge duplicate forward rules (async, non-blocking)
+    pub async fn dedupe_async(&self) -> Result<usize, StateError> {
+        let persistence = Self::new(self.store.clone());
+        tokio::task::spawn_blocking(move || persistence.dedupe())
+            .await
+            .map_err(|e| StateError::Io(std::io::Error::other(format!("Task join error: {}", e))))?
+    }
+
     /// Load forwards for a specific session
     pub fn load_by_session(&self, session_id: &str) -> Result<Vec<PersistedForward>, StateError> {
         let all_forwards = self.load_all()?;
@@ -202,10 +340,71 @@ impl ForwardPersistence {
     }
 }
 
+/// Records connection events for forwards whose `logging` flag is set
+///
+/// Installed on every `ForwardingManager`. The enabled set mirrors the
+/// persisted flags so forwards without logging cost one set lookup per
+/// connection.
+pub struct ForwardEventLog {
+    store: Arc<StateStore>,
+    enabled: RwLock<HashSet<String>>,
+}
+
+impl ForwardEventLog {
+    /// Create an event log, enabling the forwards persisted with `logging`
+    pub fn new(store: Arc<StateStore>) -> Self {
+        let enabled = ForwardPersistence::new(store.clone())
+            .load_all()
+            .map(|forwards| {
+                forwards
+                    .into_iter()
+                    .filter(|f| f.logging)
+                    .map(|f| f.id)
+                    .collect()
+            })
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to load forward logging flags: {:?}", e);
+                HashSet::new()
+            });
+
+        Self {
+            store,
+            enabled: RwLock::new(enabled),
+        }
+    }
+
+    /// Start or stop recording events for a forward
+    pub fn set_enabled(&self, forward_id: &str, enabled: bool) {
+        if enabled {
+            self.enabled.write().insert(forward_id.to_string());
+        } else {
+            self.enabled.write().remove(forward_id);
+        }
+    }
+}
+
+impl ConnectionLogSink for ForwardEventLog {
+    fn is_enabled(&self, forward_id: &str) -> bool {
+        self.enabled.read().contains(forward_id)
+    }
+
+    fn record(&self, forward_id: &str, event: ConnectionEvent) {
+        let persistence = ForwardPersistence::new(self.store.clone());
+        let forward_id = forward_id.to_string();
+        // redb commits are blocking; keep them off the forwarding tasks
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = persistence.append_event(&forward_id, &event, MAX_FORWARD_LOG_EVENTS) {
+                tracing::warn!("Failed to record event for forward {}: {:?}", forward_id, e);
+            }
+        });
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::forwarding::manager::ForwardRule;
+    use crate::forwarding::ConnectionEventKind;
     use tempfile::TempDir;
 
     fn create_test_store() -> (TempDir, Arc<StateStore>) {
@@ -334,4 +533,180 @@ mod tests {
         let remaining = persistence.load_by_session("session-1").unwrap();
         assert_eq!(remaining.len(), 0);
     }
+
+    fn forward_at(id: &str, session_id: &str, bind_port: u16, age_secs: i64) -> PersistedForward {
+        let mut rule = create_test_forward_rule();
+        rule.id = id.to_string();
+        rule.bind_port = bind_port;
+        let mut forward = PersistedForward::new(
+            id.to_string(),
+            session_id.to_string(),
+            ForwardType::Local,
+            rule,
+            true,
+        );
+        forward.created_at = Utc::now() - chrono::Duration::seconds(age_secs);
+        forward
+    }
+
+    #[test]
+    fn test_dedupe_duplicated_rule_set() {
+        let (_temp_dir, store) = create_test_store();
+        store.save_session("session-a", b"a").unwrap();
+        store.save_session("session-b", b"b").unwrap();
+        let persistence = ForwardPersistence::new(store);
+
+        let forwards = [
+            // Same session, same rule three times: keep the newest
+            forward_at("a-old", "session-a", 8080, 300),
+            forward_at("a-mid", "session-a", 8080, 200),
+            forward_at("a-new", "session-a", 8080, 100),
+            // Different port: not a duplicate
+            forward_at("a-other", "session-a", 9090, 300),
+            // Same rule on another session: kept
+            forward_at("b", "session-b", 8080, 400),
+            forward_at("b-old", "session-b", 8080, 500),
+            // Same rule on a session that no longer exists: merged
+            forward_at("gone", "session-gone", 8080, 10),
+        ];
+        for forward in &forwards {
+            persistence.save(forward).unwrap();
+        }
+
+        let merged = persistence.dedupe().unwrap();
+        assert_eq!(merged, 4);
+
+        let mut remaining: Vec<String> = persistence
+            .load_all()
+            .unwrap()
+            .into_iter()
+            .map(|f| f.id)
+            .collect();
+        remaining.sort();
+        assert_eq!(remaining, vec!["a-new", "a-other", "b"]);
+
+        // Idempotent
+        assert_eq!(persistence.dedupe().unwrap(), 0);
+    }
+
+    fn connection_event(kind: ConnectionEventKind, peer_addr: &str) -> ConnectionEvent {
+        ConnectionEvent {
+            kind,
+            timestamp: Utc::now(),
+            peer_addr: peer_addr.to_string(),
+            bytes_sent: 10,
+            bytes_received: 20,
+        }
+    }
+
+    #[test]
+    fn test_event_log_records_and_caps() {
+        let (_temp_dir, store) = create_test_store();
+        let persistence = ForwardPersistence::new(store);
+
+        for i in 0..8 {
+            let event =
+                connection_event(ConnectionEventKind::Accept, &format!("10.0.0.{}:5000", i));
+            persistence.append_event("forward-1", &event, 5).unwrap();
+        }
+        persistence
+            .append_event(
+                "forward-2",
+                &connection_event(ConnectionEventKind::Close, "10.0.0.9:5000"),
+                5,
+            )
+            .unwrap();
+
+        // Only the newest five remain, oldest first
+        let events = persistence.load_events("forward-1", 100).unwrap();
+        let peers: Vec<&str> = events.iter().map(|e| e.peer_addr.as_str()).collect();
+        assert_eq!(
+            peers,
+            vec![
+                "10.0.0.3:5000",
+                "10.0.0.4:5000",
+                "10.0.0.5:5000",
+                "10.0.0.6:5000",
+                "10.0.0.7:5000"
+            ]
+        );
+        assert_eq!(events[0].kind, ConnectionEventKind::Accept);
+        assert_eq!(events[0].bytes_received, 20);
+
+        // `limit` keeps the newest
+        let latest = persistence.load_events("forward-1", 2).unwrap();
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest[1].peer_addr, "10.0.0.7:5000");
+
+        // Logs are per forward and removed with the forward
+        assert_eq!(persistence.load_events("forward-2", 100).unwrap().len(), 1);
+        persistence.delete("forward-1").unwrap();
+        assert!(persistence
+            .load_events("forward-1", 100)
+            .unwrap()
+            .is_empty());
+        assert_eq!(persistence.load_events("forward-2", 100).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_event_log_only_records_enabled_forwards() {
+        let (_temp_dir, store) = create_test_store();
+        let persistence = ForwardPersistence::new(store.clone());
+
+        let mut logged = forward_at("logged", "session-1", 8080, 0);
+        logged.logging = true;
+        persistence.save(&logged).unwrap();
+        persistence
+            .save(&forward_at("quiet", "session-1", 9090, 0))
+            .unwrap();
+
+        let log = ForwardEventLog::new(store);
+        assert!(log.is_enabled("logged"));
+        assert!(!log.is_enabled("quiet"));
+
+        log.set_enabled("quiet", true);
+        log.set_enabled("logged", false);
+        assert!(log.is_enabled("quiet"));
+        assert!(!log.is_enabled("logged"));
+    }
+
+    #[test]
+    fn test_dedupe_keeps_identical_rules_of_other_sessions() {
+        let (_temp_dir, store) = create_test_store();
+        store.save_session("session-1", b"1").unwrap();
+        store.save_session("session-2", b"2").unwrap();
+        let persistence = ForwardPersistence::new(store);
+
+        persistence
+            .save(&forward_at("one", "session-1", 8080, 200))
+            .unwrap();
+        persistence
+            .save(&forward_at("two", "session-2", 8080, 100))
+            .unwrap();
+
+        // After a restart no session is live yet, but both are persisted
+        // and will be restored
+        assert_eq!(persistence.dedupe().unwrap(), 0);
+        assert!(persistence.load("one").is_ok());
+        assert!(persistence.load("two").is_ok());
+    }
+
+    #[test]
+    fn test_dedupe_orphaned_duplicates_keep_newest() {
+        let (_temp_dir, store) = create_test_store();
+        let persistence = ForwardPersistence::new(store);
+
+        persistence
+            .save(&forward_at("old", "session-1", 8080, 200))
+            .unwrap();
+        persistence
+            .save(&forward_at("new", "session-2", 8080, 100))
+            .unwrap();
+
+        // Neither session is persisted any more: collapse to the newest
+        let merged = persistence.dedupe().unwrap();
+        assert_eq!(merged, 1);
+        assert!(persistence.load("new").is_ok());
+        assert!(persistence.load("old").is_err());
+    }
 }
@@ -11,5 +11,5 @@ pub use ai_chat::{
     PersistedMessage,
 };
 pub use forwarding::PersistedForward;
-pub use session::{BufferConfig, PersistedSession, SessionPersistence};
+pub use session::{BufferCompression, BufferConfig, PersistedSession, SessionPersistence};
 pub use store::{StateError, StateStore};
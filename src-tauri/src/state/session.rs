@@ -47,15 +47,43 @@ pub struct BufferConfig {
     #[serde(default = "default_max_lines")]
     pub max_lines: usize,
 
+    /// Maximum lines written to disk (only the tail of the live buffer)
+    #[serde(default = "default_persist_max_lines")]
+    pub persist_max_lines: usize,
+
     /// Whether to save buffer on disconnect
     #[serde(default = "default_save_on_disconnect")]
     pub save_on_disconnect: bool,
+
+    /// Compression applied to the saved buffer blob
+    #[serde(default)]
+    pub compression: BufferCompression,
+}
+
+/// Compression algorithm for persisted terminal buffers
+///
+/// The algorithm is recorded in the blob header, so changing this setting
+/// never breaks loading buffers saved with a different one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BufferCompression {
+    /// Plain MessagePack (default)
+    #[default]
+    None,
+    /// zstd (best ratio)
+    Zstd,
+    /// LZ4 block format (fastest)
+    Lz4,
 }
 
 fn default_max_lines() -> usize {
     100_000
 }
 
+fn default_persist_max_lines() -> usize {
+    5_000
+}
+
 fn default_save_on_disconnect() -> bool {
     true
 }
@@ -64,7 +92,9 @@ impl Default for BufferConfig {
     fn default() -> Self {
         Self {
             max_lines: default_max_lines(),
+            persist_max_lines: default_persist_max_lines(),
             save_on_disconnect: default_save_on_disconnect(),
+            compression: BufferCompression::default(),
         }
     }
 }
@@ -7,7 +7,8 @@
 #![allow(clippy::result_large_err)]
 
 use redb::{Database, ReadableTable, TableDefinition};
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use thiserror::Error;
 use tracing::{error, info, warn};
@@ -19,6 +20,14 @@ pub const STATE_VERSION: u32 = 1;
 const SESSIONS_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("sessions");
 const FORWARDS_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("forwards");
 const METADATA_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("metadata");
+/// Forward connection events, keyed by (forward id, sequence number)
+const FORWARD_EVENTS_TABLE: TableDefinition<(&str, u64), &[u8]> =
+    TableDefinition::new("forward_events");
+/// Ports dismissed in port detection, keyed by saved connection id
+const IGNORED_PORTS_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("ignored_ports");
+/// Snapshot of the jump-host session tree (single entry)
+const SESSION_TREE_TABLE: TableDefinition<&str, &[u8]> = TableDefinition::new("session_tree");
+const SESSION_TREE_KEY: &str = "current";
 
 /// State persistence errors
 ///
@@ -70,6 +79,7 @@ impl From<rmp_serde::decode::Error> for StateError {
 /// High-performance state store using redb
 pub struct StateStore {
     db: Arc<Database>,
+    path: PathBuf,
 }
 
 impl StateStore {
@@ -123,7 +133,10 @@ impl StateStore {
             warn!("File permission restrictions not implemented on Windows - database may be world-readable");
         }
 
-        let store = Self { db: Arc::new(db) };
+        let store = Self {
+            db: Arc::new(db),
+            path,
+        };
 
         // Initialize tables and metadata
         store.initialize()?;
@@ -146,6 +159,9 @@ impl StateStore {
             let _ = write_txn.open_table(SESSIONS_TABLE)?;
             let _ = write_txn.open_table(FORWARDS_TABLE)?;
             let _ = write_txn.open_table(METADATA_TABLE)?;
+            let _ = write_txn.open_table(FORWARD_EVENTS_TABLE)?;
+            let _ = write_txn.open_table(IGNORED_PORTS_TABLE)?;
+            let _ = write_txn.open_table(SESSION_TREE_TABLE)?;
         }
 
         write_txn.commit().map_err(|e| {
@@ -717,6 +733,164 @@ impl StateStore {
         }
     }
 
+    /// Append an event to a forward's log, evicting the oldest events so at
+    /// most `max_events` are kept
+    pub fn append_forward_event(
+        &self,
+        id: &str,
+        data: &[u8],
+        max_events: usize,
+    ) -> Result<(), StateError> {
+        let write_txn = self.db.begin_write()?;
+
+        {
+            let mut table = write_txn.open_table(FORWARD_EVENTS_TABLE)?;
+
+            let next_seq = match table.range((id, 0)..=(id, u64::MAX))?.next_back() {
+                Some(item) => item?.0.value().1 + 1,
+                None => 0,
+            };
+            table.insert((id, next_seq), data)?;
+
+            // Sequence numbers are contiguous, so everything below the
+            // cutoff is past the cap
+            let cutoff = (next_seq + 1).saturating_sub(max_events as u64);
+            if cutoff > 0 {
+                table.retain_in((id, 0)..(id, cutoff), |_, _| false)?;
+            }
+        }
+
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Load the newest `limit` events of a forward's log, oldest first
+    pub fn load_forward_events(&self, id: &str, limit: usize) -> Result<Vec<Vec<u8>>, StateError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(FORWARD_EVENTS_TABLE)?;
+
+        let mut events = Vec::new();
+        for item in table.range((id, 0)..=(id, u64::MAX))?.rev().take(limit) {
+            let (_, value) = item?;
+            events.push(value.value().to_vec());
+        }
+        events.reverse();
+
+        Ok(events)
+    }
+
+    /// Delete a forward's event log
+    pub fn delete_forward_events(&self, id: &str) -> Result<(), StateError> {
+        let write_txn = self.db.begin_write()?;
+
+        {
+            let mut table = write_txn.open_table(FORWARD_EVENTS_TABLE)?;
+            table.retain_in((id, 0)..=(id, u64::MAX), |_, _| false)?;
+        }
+
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Save the ignored ports of a saved connection, replacing any earlier set.
+    /// An empty set removes the entry.
+    pub fn save_ignored_ports(&self, connection_id: &str, ports: &[u16]) -> Result<(), StateError> {
+        let mut ports = ports.to_vec();
+        ports.sort_unstable();
+        ports.dedup();
+        let data = rmp_serde::to_vec(&ports)?;
+
+        let write_txn = self.db.begin_write()?;
+
+        {
+            let mut table = write_txn.open_table(IGNORED_PORTS_TABLE)?;
+            if ports.is_empty() {
+                table.remove(connection_id)?;
+            } else {
+                table.insert(connection_id, data.as_slice())?;
+            }
+        }
+
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Load the ignored ports of a saved connection (empty if none were saved)
+    pub fn load_ignored_ports(&self, connection_id: &str) -> Result<Vec<u16>, StateError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(IGNORED_PORTS_TABLE)?;
+
+        match table.get(connection_id)? {
+            Some(value) => Ok(rmp_serde::from_slice(value.value())?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Delete the ignored ports of a saved connection
+    pub fn delete_ignored_ports(&self, connection_id: &str) -> Result<(), StateError> {
+        let write_txn = self.db.begin_write()?;
+
+        {
+            let mut table = write_txn.open_table(IGNORED_PORTS_TABLE)?;
+            table.remove(connection_id)?;
+        }
+
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Drop ignored-port entries of connections not in `connection_ids`.
+    /// Returns how many entries were removed.
+    pub fn retain_ignored_ports(
+        &self,
+        connection_ids: &HashSet<String>,
+    ) -> Result<usize, StateError> {
+        let write_txn = self.db.begin_write()?;
+
+        let mut removed = 0;
+        {
+            let mut table = write_txn.open_table(IGNORED_PORTS_TABLE)?;
+            table.retain(|id, _| {
+                let keep = connection_ids.contains(id);
+                if !keep {
+                    removed += 1;
+                }
+                keep
+            })?;
+        }
+
+        write_txn.commit()?;
+        Ok(removed)
+    }
+
+    /// Save the session tree snapshot, replacing the previous one
+    pub fn save_session_tree(&self, data: &[u8]) -> Result<(), StateError> {
+        let write_txn = self.db.begin_write()?;
+
+        {
+            let mut table = write_txn.open_table(SESSION_TREE_TABLE)?;
+            table.insert(SESSION_TREE_KEY, data)?;
+        }
+
+        write_txn.commit()?;
+        Ok(())
+    }
+
+    /// Load the session tree snapshot, if one was saved
+    pub fn load_session_tree(&self) -> Result<Option<Vec<u8>>, StateError> {
+        let read_txn = self.db.begin_read()?;
+        let table = read_txn.open_table(SESSION_TREE_TABLE)?;
+
+        Ok(table
+            .get(SESSION_TREE_KEY)?
+            .map(|value| value.value().to_vec()))
+    }
+
+    /// Path of the database file on disk
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
     /// Get statistics about the database
     pub fn stats(&self) -> Result<StateStats, StateError> {
         let read_txn = self.db.begin_read()?;
@@ -734,9 +908,13 @@ impl StateStore {
             forward_count += 1;
         }
 
+        // Get database file size on disk
+        let db_bytes = self.path.metadata().map(|m| m.len()).unwrap_or(0);
+
         Ok(StateStats {
             session_count,
             forward_count,
+            db_bytes,
         })
     }
 }
@@ -746,6 +924,7 @@ impl StateStore {
 pub struct StateStats {
     pub session_count: usize,
     pub forward_count: usize,
+    pub db_bytes: u64,
 }
 
 #[cfg(test)]
@@ -758,11 +937,29 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
         let db_path = temp_dir.path().join("test.redb");
 
-        let store = StateStore::new(db_path).unwrap();
+        let store = StateStore::new(db_path.clone()).unwrap();
         let stats = store.stats().unwrap();
 
         assert_eq!(stats.session_count, 0);
         assert_eq!(stats.forward_count, 0);
+        assert_eq!(store.path(), db_path.as_path());
+        assert_eq!(stats.db_bytes, std::fs::metadata(&db_path).unwrap().len());
+        assert!(stats.db_bytes > 0);
+    }
+
+    #[test]
+    fn test_stats_counts_entries() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.redb");
+        let store = StateStore::new(db_path).unwrap();
+
+        store.save_session("session1", b"a").unwrap();
+        store.save_session("session2", b"b").unwrap();
+        store.save_forward("forward1", b"c").unwrap();
+
+        let stats = store.stats().unwrap();
+        assert_eq!(stats.session_count, 2);
+        assert_eq!(stats.forward_count, 1);
     }
 
     #[test]
@@ -812,4 +1009,70 @@ mod tests {
         store.delete_forward("forward1").unwrap();
         assert!(store.load_forward("forward1").is_err());
     }
+
+    #[test]
+    fn test_ignored_ports_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.redb");
+        let store = StateStore::new(db_path).unwrap();
+
+        assert!(store.load_ignored_ports("conn1").unwrap().is_empty());
+
+        store
+            .save_ignored_ports("conn1", &[8080, 3000, 8080])
+            .unwrap();
+        store.save_ignored_ports("conn2", &[5432]).unwrap();
+        assert_eq!(store.load_ignored_ports("conn1").unwrap(), vec![3000, 8080]);
+        assert_eq!(store.load_ignored_ports("conn2").unwrap(), vec![5432]);
+
+        // Saving replaces the whole set
+        store.save_ignored_ports("conn1", &[9000]).unwrap();
+        assert_eq!(store.load_ignored_ports("conn1").unwrap(), vec![9000]);
+
+        store.save_ignored_ports("conn1", &[]).unwrap();
+        store.delete_ignored_ports("conn2").unwrap();
+        assert!(store.load_ignored_ports("conn1").unwrap().is_empty());
+        assert!(store.load_ignored_ports("conn2").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_session_tree_snapshot() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.redb");
+        let store = StateStore::new(db_path.clone()).unwrap();
+
+        assert!(store.load_session_tree().unwrap().is_none());
+
+        store.save_session_tree(b"first").unwrap();
+        store.save_session_tree(b"second").unwrap();
+        assert_eq!(
+            store.load_session_tree().unwrap().as_deref(),
+            Some(&b"second"[..])
+        );
+
+        // Survives reopening the database
+        drop(store);
+        let store = StateStore::new(db_path).unwrap();
+        assert_eq!(
+            store.load_session_tree().unwrap().as_deref(),
+            Some(&b"second"[..])
+        );
+    }
+
+    #[test]
+    fn test_retain_ignored_ports_drops_deleted_connections() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.redb");
+        let store = StateStore::new(db_path).unwrap();
+
+        store.save_ignored_ports("kept", &[8080]).unwrap();
+        store.save_ignored_ports("deleted1", &[3000]).unwrap();
+        store.save_ignored_ports("deleted2", &[5000]).unwrap();
+
+        let existing = HashSet::from(["kept".to_string(), "never-saved".to_string()]);
+        assert_eq!(store.retain_ignored_ports(&existing).unwrap(), 2);
+        assert_eq!(store.load_ignored_ports("kept").unwrap(), vec![8080]);
+        assert!(store.load_ignored_ports("deleted1").unwrap().is_empty());
+        assert_eq!(store.retain_ignored_ports(&existing).unwrap(), 0);
+    }
 }
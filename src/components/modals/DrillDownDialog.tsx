@@ -8,7 +8,8 @@ import { Button } from '../ui/button';
 import { Tabs, TabsContent, TabsList, TabsTrigger } from '../ui/tabs';
 import { Loader2, ArrowDownRight } from 'lucide-react';
 import { api } from '../../lib/api';
-import { useSessionTreeStore } from '../../store/sessionTreeStore';
+import { formatCommandError } from '../../lib/commandError';
+import { useSessionTreeStore, notifyDrillDownCycle } from '../../store/sessionTreeStore';
 
 interface DrillDownDialogProps {
   /** 父节点 ID */
@@ -93,7 +94,7 @@ export const DrillDownDialog: React.FC<DrillDownDialogProps> = ({
 
     try {
       // 1. 调用 tree_drill_down 在树中添加子节点
-      const nodeId = await api.treeDrillDown({
+      const { nodeId, cycleAncestor } = await api.treeDrillDown({
         parentNodeId,
         host,
         port: parseInt(port) || 22,
@@ -103,6 +104,9 @@ export const DrillDownDialog: React.FC<DrillDownDialogProps> = ({
         keyPath: authType === 'key' ? keyPath : undefined,
         passphrase: authType === 'key' && passphrase ? passphrase : undefined,
       });
+      if (cycleAncestor) {
+        notifyDrillDownCycle(cycleAncestor);
+      }
 
       // 2. 调用 connect_tree_node 建立实际连接
       const result = await api.connectTreeNode({
@@ -121,7 +125,7 @@ export const DrillDownDialog: React.FC<DrillDownDialogProps> = ({
       handleClose();
     } catch (err) {
       console.error('Drill down failed:', err);
-      setError(err instanceof Error ? err.message : String(err));
+      setError(formatCommandError(err));
       // 刷新树以显示失败状态
       await fetchTree();
     } finally {
@@ -26,7 +26,8 @@ import {
 } from '../ui/tabs';
 import { open } from '@tauri-apps/plugin-dialog';
 import { api } from '../../lib/api';
-import type { ConnectionInfo } from '../../types';
+import { formatCommandError } from '../../lib/commandError';
+import type { ConnectionInfo, ConnectionTestResult } from '../../types';
 
 type EditConnectionPropertiesModalProps = {
   open: boolean;
@@ -53,6 +54,8 @@ export const EditConnectionPropertiesModal = ({
   const [color, setColor] = useState('');
   const [groups, setGroups] = useState<string[]>([]);
   const [saving, setSaving] = useState(false);
+  const [testing, setTesting] = useState(false);
+  const [testResult, setTestResult] = useState<ConnectionTestResult | null>(null);
 
   useEffect(() => {
     if (isOpen && connection) {
@@ -64,6 +67,7 @@ export const EditConnectionPropertiesModal = ({
       setKeyPath(connection.key_path || '');
       setGroup(connection.group || 'Ungrouped');
       setColor(connection.color || '');
+      setTestResult(null);
       api.getGroups().then(setGroups).catch(() => setGroups([]));
     }
   }, [isOpen, connection]);
@@ -115,6 +119,41 @@ export const EditConnectionPropertiesModal = ({
     }
   };
 
+  // Tests the saved connection (unsaved edits are not included)
+  const handleTest = async () => {
+    if (!connection) return;
+    setTesting(true);
+    setTestResult(null);
+    try {
+      setTestResult(await api.testConnection(connection.id));
+    } catch (e) {
+      console.error('Failed to test connection:', e);
+      setTestResult({
+        ok: false,
+        os_type: null,
+        banner: null,
+        auth_method_used: connection.auth_type,
+        duration_ms: 0,
+        failure: null,
+        error: { code: 'internal', message: formatCommandError(e) },
+      });
+    } finally {
+      setTesting(false);
+    }
+  };
+
+  const testSummary = (result: ConnectionTestResult): string => {
+    if (result.ok) {
+      return t('sessionManager.edit_properties.test_ok', {
+        os: result.os_type ?? 'Unknown',
+        ms: result.duration_ms,
+      });
+    }
+    const reason = result.failure ? t(`sessionManager.edit_properties.test_failed_${result.failure}`) : '';
+    const detail = result.error ? formatCommandError(result.error) : '';
+    return [reason, detail].filter(Boolean).join(': ');
+  };
+
   if (!connection) return null;
 
   return (
@@ -252,7 +291,19 @@ export const EditConnectionPropertiesModal = ({
           </div>
         </div>
 
+        {testResult && (
+          <div
+            className={`text-xs ${testResult.ok ? 'text-green-500' : 'text-red-500'}`}
+            title={testResult.banner ?? undefined}
+          >
+            {testSummary(testResult)}
+          </div>
+        )}
+
         <DialogFooter>
+          <Button variant="outline" className="mr-auto" onClick={handleTest} disabled={testing}>
+            {testing ? t('sessionManager.edit_properties.testing') : t('sessionManager.edit_properties.test')}
+          </Button>
           <Button variant="ghost" onClick={() => onOpenChange(false)}>
             {t('sessionManager.edit_properties.cancel')}
           </Button>
@@ -10,6 +10,7 @@ import { Input } from '../ui/input';
 import { Checkbox } from '../ui/checkbox';
 import { Label } from '../ui/label';
 import { useAppStore } from '../../store/appStore';
+import { formatCommandError } from '../../lib/commandError';
 import type { ExportPreflightResult } from '../../types';
 
 interface OxideExportModalProps {
@@ -20,7 +21,7 @@ interface OxideExportModalProps {
 type ExportStage = 'idle' | 'preflight' | 'reading_keys' | 'encrypting' | 'writing' | 'done';
 
 export function OxideExportModal({ isOpen, onClose }: OxideExportModalProps) {
-  const { t } = useTranslation();
+  const { t, i18n } = useTranslation();
   const { savedConnections, loadSavedConnections } = useAppStore();
   const [selectedIds, setSelectedIds] = useState<string[]>([]);
   const [password, setPassword] = useState('');
@@ -153,6 +154,7 @@ export function OxideExportModal({ isOpen, onClose }: OxideExportModalProps) {
         password,
         description: description || null,
         embedKeys: embedKeys || null,
+        locale: i18n.language,
       });
 
       // Stage 3: Save file dialog
@@ -175,7 +177,7 @@ export function OxideExportModal({ isOpen, onClose }: OxideExportModalProps) {
       }
     } catch (err) {
       console.error('Export failed:', err);
-      setError(`${t('modals.export.error_export_failed')}: ${err}`);
+      setError(`${t('modals.export.error_export_failed')}: ${formatCommandError(err)}`);
       setExportStage('idle');
     } finally {
       setExporting(false);
@@ -280,6 +282,21 @@ export function OxideExportModal({ isOpen, onClose }: OxideExportModalProps) {
                 </div>
               )}
 
+              {/* Unreadable credentials block the export */}
+              {preflight && preflight.missingCredentials.length > 0 && (
+                <div className="bg-red-500/10 border border-red-500/20 text-red-500 px-2 py-1.5 rounded text-xs">
+                  <div className="flex items-center gap-1.5 font-semibold">
+                    <AlertTriangle className="h-3 w-3" />
+                    {t('modals.export.warning_missing_credentials', { count: preflight.missingCredentials.length })}
+                  </div>
+                  <ul className="mt-1 space-y-0.5 max-h-16 overflow-y-auto">
+                    {preflight.missingCredentials.map(([context, reason], i) => (
+                      <li key={i} className="opacity-80">• {context}: {reason}</li>
+                    ))}
+                  </ul>
+                </div>
+              )}
+
               {/* Key size info when embedding */}
               {preflight && embedKeys && preflight.totalKeyBytes > 0 && (
                 <div className="text-xs text-theme-text-muted">
@@ -367,7 +384,7 @@ export function OxideExportModal({ isOpen, onClose }: OxideExportModalProps) {
             </Button>
             <Button 
               onClick={handleExport} 
-              disabled={exporting || selectedIds.length === 0}
+              disabled={exporting || selectedIds.length === 0 || preflight?.canExport === false}
               className="bg-theme-accent text-white hover:bg-theme-accent-hover disabled:opacity-50 min-w-[140px]"
             >
               {exporting ? (
@@ -8,8 +8,17 @@ import { Dialog, DialogContent, DialogHeader, DialogTitle, DialogClose } from '.
 import { Button } from '../ui/button';
 import { Input } from '../ui/input';
 import { Label } from '../ui/label';
+import { Select, SelectContent, SelectItem, SelectTrigger, SelectValue } from '../ui/select';
 import { useAppStore } from '../../store/appStore';
-import type { OxideMetadata, ImportResult, ImportPreview } from '../../types';
+import { commandErrorCode, formatCommandError } from '../../lib/commandError';
+import type { OxideMetadata, ImportMode, ImportResult, ImportPreview } from '../../types';
+
+/** What the import does to already-saved connections, per mode */
+const MODE_WARNING_KEYS: Record<ImportMode, string> = {
+  createNew: 'modals.import.warning_text_create_new',
+  skipExisting: 'modals.import.warning_text_skip_existing',
+  overwrite: 'modals.import.warning_text_overwrite',
+};
 
 interface OxideImportModalProps {
   isOpen: boolean;
@@ -17,11 +26,12 @@ interface OxideImportModalProps {
 }
 
 export function OxideImportModal({ isOpen, onClose }: OxideImportModalProps) {
-  const { t } = useTranslation();
+  const { t, i18n } = useTranslation();
   const { loadSavedConnections } = useAppStore();
   const [fileData, setFileData] = useState<Uint8Array | null>(null);
   const [metadata, setMetadata] = useState<OxideMetadata | null>(null);
   const [password, setPassword] = useState('');
+  const [mode, setMode] = useState<ImportMode>('createNew');
   const [preview, setPreview] = useState<ImportPreview | null>(null);
   const [importing, setImporting] = useState(false);
   const [previewing, setPreviewing] = useState(false);
@@ -50,11 +60,12 @@ export function OxideImportModal({ isOpen, onClose }: OxideImportModalProps) {
         try {
           const meta: OxideMetadata = await invoke('validate_oxide_file', {
             fileData: Array.from(data),
+            locale: i18n.language,
           });
           setMetadata(meta);
         } catch (err) {
           console.error('File validation failed:', err);
-          setError(`Invalid .oxide file: ${err}`);
+          setError(formatCommandError(err));
           setFileData(null);
         }
       }
@@ -77,17 +88,19 @@ export function OxideImportModal({ isOpen, onClose }: OxideImportModalProps) {
       const previewResult: ImportPreview = await invoke('preview_oxide_import', {
         fileData: Array.from(fileData),
         password,
+        locale: i18n.language,
+        mode,
       });
       setPreview(previewResult);
     } catch (err) {
       console.error('Preview failed:', err);
-      const errorMsg = String(err).toLowerCase();
-      if ((errorMsg.includes('password') && (errorMsg.includes('incorrect') || errorMsg.includes('wrong') || errorMsg.includes('failed'))) || errorMsg.includes('decryption failed') || errorMsg.includes('密码错误')) {
+      const code = commandErrorCode(err);
+      if (code === 'decryption_failed') {
         setError(t('modals.import.error_password'));
-      } else if (errorMsg.includes('checksum') || errorMsg.includes('tamper') || errorMsg.includes('verification failed')) {
+      } else if (code === 'checksum_mismatch') {
         setError(t('modals.import.error_tampered'));
       } else {
-        setError(`${t('modals.import.title')}: ${err}`);
+        setError(`${t('modals.import.title')}: ${formatCommandError(err)}`);
       }
     } finally {
       setPreviewing(false);
@@ -107,6 +120,8 @@ export function OxideImportModal({ isOpen, onClose }: OxideImportModalProps) {
       const importResult: ImportResult = await invoke('import_from_oxide', {
         fileData: Array.from(fileData),
         password,
+        locale: i18n.language,
+        mode,
       });
 
       setResult(importResult);
@@ -121,13 +136,13 @@ export function OxideImportModal({ isOpen, onClose }: OxideImportModalProps) {
       }
     } catch (err) {
       console.error('Import failed:', err);
-      const errorMsg = String(err).toLowerCase();
-      if ((errorMsg.includes('password') && (errorMsg.includes('incorrect') || errorMsg.includes('wrong') || errorMsg.includes('failed'))) || errorMsg.includes('decryption failed')) {
+      const code = commandErrorCode(err);
+      if (code === 'decryption_failed') {
         setError(t('modals.import.error_password'));
-      } else if (errorMsg.includes('checksum') || errorMsg.includes('tamper') || errorMsg.includes('verification failed')) {
+      } else if (code === 'checksum_mismatch') {
         setError(t('modals.import.error_tampered'));
       } else {
-        setError(`${t('modals.import.title')}: ${err}`);
+        setError(`${t('modals.import.title')}: ${formatCommandError(err)}`);
       }
     } finally {
       setImporting(false);
@@ -138,6 +153,7 @@ export function OxideImportModal({ isOpen, onClose }: OxideImportModalProps) {
     setFileData(null);
     setMetadata(null);
     setPassword('');
+    setMode('createNew');
     setPreview(null);
     setError(null);
     setResult(null);
@@ -187,6 +203,9 @@ export function OxideImportModal({ isOpen, onClose }: OxideImportModalProps) {
                 {result.skipped > 0 && (
                   <p className="text-sm mt-1">{t('modals.import.skipped', { count: result.skipped })}</p>
                 )}
+                {result.overwritten > 0 && (
+                  <p className="text-sm mt-1">{t('modals.import.overwritten', { count: result.overwritten })}</p>
+                )}
                 {result.renamed > 0 && (
                   <div className="mt-2">
                     <p className="text-sm font-semibold text-yellow-400">{t('modals.import.renamed', { count: result.renamed })}</p>
@@ -197,12 +216,22 @@ export function OxideImportModal({ isOpen, onClose }: OxideImportModalProps) {
                     </ul>
                   </div>
                 )}
+                {result.proxyCommandsRemoved.length > 0 && (
+                  <div className="mt-2">
+                    <p className="text-sm font-semibold text-yellow-400">{t('modals.import.proxy_command_removed', { count: result.proxyCommandsRemoved.length })}</p>
+                    <ul className="text-xs mt-1 space-y-1 opacity-90 max-h-24 overflow-y-auto">
+                      {result.proxyCommandsRemoved.map((name, i) => (
+                        <li key={i}>• {name}</li>
+                      ))}
+                    </ul>
+                  </div>
+                )}
                 {result.errors.length > 0 && (
                   <div className="mt-2">
                     <p className="text-sm font-semibold">{t('modals.import.errors')}</p>
                     <ul className="text-xs mt-1 space-y-1 opacity-90">
                       {result.errors.map((err, i) => (
-                        <li key={i}>• {err}</li>
+                        <li key={i}>• {err.connectionName}: {err.message}</li>
                       ))}
                     </ul>
                   </div>
@@ -259,6 +288,37 @@ export function OxideImportModal({ isOpen, onClose }: OxideImportModalProps) {
                   </div>
                 )}
 
+                {/* Already saved connections that will be left alone */}
+                {preview.willSkip.length > 0 && (
+                  <div>
+                    <p className="text-sm font-semibold text-theme-text-muted">
+                      {t('modals.import.preview_will_skip', { count: preview.willSkip.length })}
+                    </p>
+                    <ul className="text-xs text-theme-text-muted mt-1 space-y-1 max-h-24 overflow-y-auto">
+                      {preview.willSkip.map((name, i) => (
+                        <li key={i}>• {name}</li>
+                      ))}
+                    </ul>
+                  </div>
+                )}
+
+                {/* Already saved connections that will be updated in place */}
+                {preview.willOverwrite.length > 0 && (
+                  <div>
+                    <div className="flex items-center gap-2">
+                      <AlertTriangle className="h-4 w-4 text-yellow-500" />
+                      <p className="text-sm font-semibold text-yellow-500">
+                        {t('modals.import.preview_will_overwrite', { count: preview.willOverwrite.length })}
+                      </p>
+                    </div>
+                    <ul className="text-xs text-yellow-400 mt-1 space-y-1 max-h-24 overflow-y-auto">
+                      {preview.willOverwrite.map((name, i) => (
+                        <li key={i}>• {name}</li>
+                      ))}
+                    </ul>
+                  </div>
+                )}
+
                 {/* Embedded keys notice */}
                 {preview.hasEmbeddedKeys && (
                   <div className="bg-blue-500/10 border border-blue-500/20 text-blue-500 px-3 py-2 rounded text-xs">
@@ -330,6 +390,21 @@ export function OxideImportModal({ isOpen, onClose }: OxideImportModalProps) {
                 />
               </div>
 
+              {/* Existing connection handling */}
+              <div>
+                <Label className="text-theme-text">{t('modals.import.mode')}</Label>
+                <Select value={mode} onValueChange={(v) => setMode(v as ImportMode)}>
+                  <SelectTrigger className="mt-1 w-full bg-theme-bg border-theme-border text-theme-text focus:ring-theme-accent">
+                    <SelectValue />
+                  </SelectTrigger>
+                  <SelectContent className="bg-theme-bg-panel border-theme-border text-theme-text">
+                    <SelectItem value="createNew">{t('modals.import.mode_create_new')}</SelectItem>
+                    <SelectItem value="skipExisting">{t('modals.import.mode_skip_existing')}</SelectItem>
+                    <SelectItem value="overwrite">{t('modals.import.mode_overwrite')}</SelectItem>
+                  </SelectContent>
+                </Select>
+              </div>
+
               {/* Error Message */}
               {error && (
                 <div className="bg-red-500/10 border border-red-500/20 text-red-500 px-3 py-2 rounded text-sm">
@@ -341,7 +416,7 @@ export function OxideImportModal({ isOpen, onClose }: OxideImportModalProps) {
               <div className="bg-yellow-500/10 border border-yellow-500/20 text-yellow-500 px-3 py-2 rounded text-sm">
                 <p className="font-semibold">{t('modals.import.warning_title')}</p>
                 <p className="text-xs mt-1 opacity-90">
-                  {t('modals.import.warning_text')}
+                  {t(MODE_WARNING_KEYS[mode])}
                 </p>
               </div>
 
@@ -117,7 +117,11 @@ export const SettingsModal = () => {
   const handleImportHost = async (alias: string) => {
       try {
           const imported = await api.importSshHost(alias);
-          alert(t('modals.settings.errors.import_host_success', { name: imported.name }));
+          const jumps = imported.undefined_jump_hosts;
+          const warning = jumps.length > 0
+              ? '\n' + t('modals.settings.errors.import_host_undefined_jumps', { hosts: jumps.join(', ') })
+              : '';
+          alert(t('modals.settings.errors.import_host_success', { name: imported.name }) + warning);
           // Remove from list to show it's imported
           setSshHosts(prev => prev.filter(h => h.alias !== alias));
           // Refresh saved connections in sidebar
@@ -920,7 +920,11 @@ export const SettingsView = () => {
     const handleImportHost = async (alias: string) => {
         try {
             const imported = await api.importSshHost(alias);
-            alert(t('settings_view.errors.import_success', { name: imported.name }));
+            const jumps = imported.undefined_jump_hosts;
+            const warning = jumps.length > 0
+                ? '\n' + t('settings_view.errors.import_undefined_jumps', { hosts: jumps.join(', ') })
+                : '';
+            alert(t('settings_view.errors.import_success', { name: imported.name }) + warning);
             // Remove from list to show it's imported
             setSshHosts(prev => prev.filter(h => h.alias !== alias));
             // Refresh saved connections in sidebar
@@ -20,7 +20,7 @@ import { useProfilerStore } from '../store/profilerStore';
 import { topologyResolver } from '../lib/topologyResolver';
 import { slog } from '../lib/structuredLog';
 import i18n from '../i18n';
-import type { SshConnectionState } from '../types';
+import type { RemoteEnvInfo, SshConnectionState } from '../types';
 
 interface ConnectionStatusEvent {
   connection_id: string;
@@ -33,6 +33,7 @@ interface ConnectionStatusEvent {
 interface EnvDetectedEvent {
   connectionId: string;
   osType: string;
+  osFamily: RemoteEnvInfo['osFamily'];
   osVersion?: string;
   kernel?: string;
   arch?: string;
@@ -205,11 +206,12 @@ export function useConnectionEvents(): void {
       try {
         const unlistenEnvDetected = await listen<EnvDetectedEvent>('env:detected', (event) => {
           if (!mounted) return;
-          const { connectionId, osType, osVersion, kernel, arch, shell, detectedAt } = event.payload;
+          const { connectionId, osType, osFamily, osVersion, kernel, arch, shell, detectedAt } = event.payload;
           console.log(`[ConnectionEvents] env:detected for ${connectionId}: ${osType}`);
           
           updateConnectionRemoteEnv(connectionId, {
             osType,
+            osFamily,
             osVersion,
             kernel,
             arch,
@@ -86,7 +86,7 @@ export function usePortDetection(connectionId: string | undefined): UsePortDetec
         await ensureProfiler();
       }
       try {
-        const ports = await api.getDetectedPorts(connectionId);
+        const ports = await api.profilerGetPorts(connectionId);
         if (!mounted) return;
         // Always update — including empty array to clear stale data
         setAllPorts(ports);
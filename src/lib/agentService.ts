@@ -18,7 +18,6 @@ import {
   nodeAgentGitStatus,
   nodeAgentWatchStart,
   nodeAgentWatchStop,
-  nodeAgentStartWatchRelay,
   nodeAgentSymbolIndex,
   nodeAgentSymbolComplete,
   nodeAgentSymbolDefinitions,
@@ -26,6 +25,7 @@ import {
   nodeSftpPreview,
   nodeSftpWrite,
 } from './api';
+import { commandErrorCode, formatCommandError } from './commandError';
 import type {
   AgentStatus,
   AgentFileEntry,
@@ -33,6 +33,7 @@ import type {
   AgentGrepMatch,
   AgentGitStatusResult,
   AgentWatchEvent,
+  AgentReconnectEvent,
   AgentSymbolInfo,
   AgentSymbolIndexResult,
   FileInfo,
@@ -90,7 +91,7 @@ export async function ensureAgent(nodeId: string): Promise<AgentStatus> {
     .catch((err) => {
       agentReadyCache.set(nodeId, false);
       deployPromises.delete(nodeId);
-      return { type: 'failed', reason: String(err) } as AgentStatus;
+      return { type: 'failed', reason: formatCommandError(err) } as AgentStatus;
     });
 
   deployPromises.set(nodeId, promise);
@@ -165,7 +166,7 @@ export async function writeFile(
       return { mtime: result.mtime, hash: result.hash };
     } catch (err) {
       // If it's a hash conflict, propagate it — don't fallback
-      if (String(err).includes('CONFLICT') || String(err).includes('hash mismatch') || String(err).includes('File modified externally')) {
+      if (commandErrorCode(err) === 'conflict') {
         throw err;
       }
       agentReadyCache.set(nodeId, false);
@@ -292,22 +293,33 @@ export async function watchDirectory(
   if (!(await isAgentReady(nodeId))) return null;
 
   try {
-    // Start the watch on the agent side
-    await nodeAgentWatchStart(nodeId, path, ignore);
-
-    // Start the relay (backend → frontend Tauri events)
-    await nodeAgentStartWatchRelay(nodeId);
+    // Start the watch on the agent side; the backend pumps changes to
+    // `agent:watch:{connectionId}` events
+    const connectionId = await nodeAgentWatchStart(nodeId, path, ignore);
 
     // Subscribe to the Tauri event
     const unlisten = await listen<AgentWatchEvent>(
-      `agent:watch-event:${nodeId}`,
+      `agent:watch:${connectionId}`,
       (event) => {
         onEvent(event.payload);
       },
     );
 
+    // The backend restores the watch when it restarts the agent; after a
+    // restart attempt, re-check readiness so a failed one falls back to SFTP
+    const unlistenReconnect = await listen<AgentReconnectEvent>(
+      'agent:reconnect',
+      (event) => {
+        const { connectionId: id, reconnecting } = event.payload;
+        if (id === connectionId && !reconnecting) {
+          agentReadyCache.delete(nodeId);
+        }
+      },
+    );
+
     return async () => {
       unlisten();
+      unlistenReconnect();
       try {
         await nodeAgentWatchStop(nodeId, path);
       } catch {
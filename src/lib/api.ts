@@ -3,11 +3,22 @@ import {
   SessionInfo,
   ConnectRequest,
   ConnectionInfo,
+  ImportedSshHost,
+  KeychainStatus,
+  ConsolidationReport,
+  KeychainRotationReport,
   SaveConnectionRequest,
+  ValidateConnectionRequest,
+  ImportSnippetRequest,
+  ConnectionCheck,
+  ConnectionTestResult,
+  ReconnectChainSegmentResponse,
+  ReconnectSubtreeResponse,
   HealthMetrics,
   FileInfo,
   PreviewContent,
   ForwardRequest,
+  ForwardConnectionEvent,
   ForwardRule,
   ForwardResponse,
   SshHostInfo,
@@ -18,14 +29,18 @@ import {
   BufferStats,
   SearchOptions,
   SearchResult,
+  SearchReportFormat,
   SessionStats,
   QuickHealthCheck,
   IncompleteTransferInfo,
   // Connection pool types
   SshConnectionInfo,
   CreateTerminalRequest,
+  NotifyOn,
+  PasteGuardPolicy,
   CreateTerminalResponse,
   ConnectionPoolConfig,
+  BridgeConfig,
   ConnectionPoolStats,
   // Host key preflight types (TOFU)
   SshPreflightRequest,
@@ -33,6 +48,8 @@ import {
   AcceptHostKeyRequest,
   // Resource profiler types
   ResourceMetrics,
+  ProfilerState,
+  HistoryResolution,
   // Smart port detection types
   DetectedPort,
   // Remote environment detection
@@ -185,6 +202,30 @@ export const api = {
     return invoke('ssh_set_pool_config', { config });
   },
 
+  /**
+   * Get WebSocket bridge settings
+   */
+  bridgeGetConfig: async (): Promise<BridgeConfig> => {
+    if (USE_MOCK) {
+      return {
+        allowedOrigins: ['tauri://localhost', 'http://localhost:1420'],
+        maxConnections: 4,
+        heartbeatTimeoutSecs: 300,
+        detachGraceSecs: 300,
+        replayLines: 50,
+      };
+    }
+    return invoke('bridge_get_config');
+  },
+
+  /**
+   * Set WebSocket bridge settings; applies to terminals opened afterwards
+   */
+  bridgeSetConfig: async (config: BridgeConfig): Promise<void> => {
+    if (USE_MOCK) return;
+    return invoke('bridge_set_config', { config });
+  },
+
   /**
    * Get connection pool statistics
    * Returns real-time stats for monitoring panel
@@ -243,6 +284,57 @@ export const api = {
     return invoke('close_terminal', { sessionId });
   },
 
+  /**
+   * Replace a terminal's activity notification settings
+   */
+  setSessionNotify: async (sessionId: string, notifyOn: NotifyOn): Promise<void> => {
+    if (USE_MOCK) return;
+    return invoke('set_session_notify', { sessionId, notifyOn });
+  },
+
+  /**
+   * Replace a terminal's multi-line paste confirmation policy
+   */
+  setPasteGuard: async (sessionId: string, policy: PasteGuardPolicy): Promise<void> => {
+    if (USE_MOCK) return;
+    return invoke('set_paste_guard', { sessionId, policy });
+  },
+
+  /**
+   * Send (`send = true`) or discard a paste held for confirmation
+   */
+  confirmPaste: async (sessionId: string, pasteId: number, send: boolean): Promise<void> => {
+    if (USE_MOCK) return;
+    return invoke('confirm_paste', { sessionId, pasteId, send });
+  },
+
+  /**
+   * Start recording terminal output to an asciinema v2 cast file.
+   * Without a path the file goes to the app's recordings directory; a given
+   * path must be an absolute `.cast` path, and an existing file is only
+   * replaced with `overwrite`. Returns the cast file path.
+   */
+  startSessionRecording: async (
+    sessionId: string,
+    path?: string,
+    overwrite?: boolean,
+  ): Promise<string> => {
+    if (USE_MOCK) return `/mock/recordings/${sessionId}.cast`;
+    return invoke('session_start_recording', {
+      sessionId,
+      path: path ?? null,
+      overwrite: overwrite ?? false,
+    });
+  },
+
+  /**
+   * Stop recording; returns the cast file path
+   */
+  stopSessionRecording: async (sessionId: string): Promise<string> => {
+    if (USE_MOCK) return `/mock/recordings/${sessionId}.cast`;
+    return invoke('session_stop_recording', { sessionId });
+  },
+
   /**
    * Recreate terminal PTY after connection reconnect
    * Returns new WebSocket URL and token for the existing session
@@ -301,11 +393,47 @@ export const api = {
     return invoke('search_connections', { query });
   },
 
+  filterConnectionsByTags: async (tags: string[], matchAll = false): Promise<ConnectionInfo[]> => {
+    if (USE_MOCK) return mockConnections.filter(c => tags.some(t => c.tags.includes(t)));
+    return invoke('config_filter_by_tags', { tags, matchAll });
+  },
+
+  addTagToConnections: async (ids: string[], tag: string): Promise<number> => {
+    if (USE_MOCK) return ids.length;
+    return invoke('config_add_tag_to_many', { ids, tag });
+  },
+
+  removeTagFromConnections: async (ids: string[], tag: string): Promise<number> => {
+    if (USE_MOCK) return ids.length;
+    return invoke('config_remove_tag_from_many', { ids, tag });
+  },
+
   saveConnection: async (request: SaveConnectionRequest): Promise<ConnectionInfo> => {
     if (USE_MOCK) return mockConnections[0];
     return invoke('save_connection', { request });
   },
 
+  validateConnection: async (request: ValidateConnectionRequest): Promise<ConnectionCheck[]> => {
+    if (USE_MOCK) return [
+      { kind: 'dns', status: 'ok', message: 'Resolved to 127.0.0.1' },
+      { kind: 'tcp', status: 'ok', message: `Port ${request.port} reachable at 127.0.0.1:${request.port}` },
+    ];
+    return invoke('config_validate_connection', { request });
+  },
+
+  testConnection: async (id: string): Promise<ConnectionTestResult> => {
+    if (USE_MOCK) return {
+      ok: true,
+      os_type: 'Linux',
+      banner: 'SSH-2.0-OpenSSH_9.6',
+      auth_method_used: 'key',
+      duration_ms: 120,
+      failure: null,
+      error: null,
+    };
+    return invoke('config_test_connection', { id });
+  },
+
   deleteConnection: async (id: string): Promise<void> => {
     if (USE_MOCK) return;
     return invoke('delete_connection', { id });
@@ -321,6 +449,29 @@ export const api = {
     return invoke('get_connection_password', { id });
   },
 
+  getKeychainStatus: async (): Promise<KeychainStatus> => {
+    if (USE_MOCK) return { systemAvailable: true, fileFallback: false, usable: true };
+    return invoke('get_keychain_status');
+  },
+
+  consolidateCredentials: async (): Promise<ConsolidationReport> => {
+    if (USE_MOCK) return { moved: [], unchanged: 0, failures: [] };
+    return invoke('config_consolidate_credentials');
+  },
+
+  /**
+   * Re-key all known credentials under new keychain service names.
+   * Pass custom AI provider IDs from settings; built-in ones are always included.
+   */
+  rotateKeychain: async (dryRun: boolean, aiProviderIds?: string[]): Promise<KeychainRotationReport> => {
+    return invoke('rotate_keychain', { dryRun, aiProviderIds });
+  },
+
+  /** Only call after the user agreed to store credentials in a local file */
+  enableKeychainFileFallback: async (): Promise<KeychainStatus> => {
+    return invoke('enable_keychain_file_fallback');
+  },
+
   /**
    * Get saved connection with credentials for connecting
    * Returns full connection info including passwords from keychain
@@ -380,7 +531,7 @@ export const api = {
     return invoke('list_ssh_config_hosts');
   },
   
-  importSshHost: async (alias: string): Promise<ConnectionInfo> => {
+  importSshHost: async (alias: string): Promise<ImportedSshHost> => {
     if (USE_MOCK) throw new Error("Mock import not implemented");
     return invoke('import_ssh_host', { alias });
   },
@@ -389,6 +540,23 @@ export const api = {
     if (USE_MOCK) return '~/.ssh/config';
     return invoke('get_ssh_config_path');
   },
+
+  // ============ Connection Snippets ============
+  exportConnectionSnippet: async (id: string): Promise<string> => {
+    if (USE_MOCK) return '{"oxide_snippet":1}';
+    return invoke('config_export_connection_snippet', { id });
+  },
+
+  /** Export connections as an OpenSSH config fragment (no credentials) */
+  exportToSshConfig: async (connectionIds: string[]): Promise<string> => {
+    if (USE_MOCK) return '';
+    return invoke('export_to_ssh_config', { connectionIds });
+  },
+
+  importConnectionSnippet: async (request: ImportSnippetRequest): Promise<ConnectionInfo> => {
+    if (USE_MOCK) return mockConnections[0];
+    return invoke('config_import_connection_snippet', { request });
+  },
   
   checkSshKeys: async (): Promise<SshKeyInfo[]> => {
     if (USE_MOCK) return mockSshKeys;
@@ -524,6 +692,16 @@ export const api = {
     return invoke('set_forward_auto_start', { forwardId, autoStart });
   },
 
+  setForwardLogging: async (forwardId: string, logging: boolean): Promise<void> => {
+    if (USE_MOCK) return;
+    return invoke('set_forward_logging', { forwardId, logging });
+  },
+
+  getForwardLog: async (forwardId: string, limit?: number): Promise<ForwardConnectionEvent[]> => {
+    if (USE_MOCK) return [];
+    return invoke('forward_get_log', { forwardId, limit });
+  },
+
   deleteSavedForward: async (forwardId: string): Promise<void> => {
     if (USE_MOCK) return;
     return invoke('delete_saved_forward', { forwardId });
@@ -651,9 +829,37 @@ export const api = {
   },
 
   // ============ Resource Profiler ============
-  startResourceProfiler: async (connectionId: string): Promise<void> => {
+  /**
+   * `emitIntervalMs`: minimum gap between profiler events for this connection (default 1000).
+   * `savedConnectionId`: persist ignored ports under this saved connection across restarts
+   *   (defaults to the saved connection matching the live endpoint).
+   * `customCommand`: replaces the built-in sampling command; must print the section markers and `===END===`.
+   */
+  startResourceProfiler: async (
+    connectionId: string,
+    emitIntervalMs?: number,
+    scanUdp?: boolean,
+    intervalMs?: number,
+    savedConnectionId?: string,
+    customCommand?: string,
+  ): Promise<void> => {
+    if (USE_MOCK) return;
+    return invoke('start_resource_profiler', {
+      connectionId,
+      emitIntervalMs,
+      scanUdp,
+      intervalMs,
+      savedConnectionId,
+      customCommand,
+    });
+  },
+
+  /**
+   * Change a running profiler's sampling interval (1000–300000 ms)
+   */
+  setProfilerInterval: async (connectionId: string, millis: number): Promise<void> => {
     if (USE_MOCK) return;
-    return invoke('start_resource_profiler', { connectionId });
+    return invoke('set_profiler_interval', { connectionId, millis });
   },
 
   stopResourceProfiler: async (connectionId: string): Promise<void> => {
@@ -661,14 +867,23 @@ export const api = {
     return invoke('stop_resource_profiler', { connectionId });
   },
 
+  /** `null` if no profiler was started for the connection */
+  getProfilerState: async (connectionId: string): Promise<ProfilerState | null> => {
+    if (USE_MOCK) return null;
+    return invoke('get_profiler_state', { connectionId });
+  },
+
   getResourceMetrics: async (connectionId: string): Promise<ResourceMetrics | null> => {
     if (USE_MOCK) return null;
     return invoke('get_resource_metrics', { connectionId });
   },
 
-  getResourceHistory: async (connectionId: string): Promise<ResourceMetrics[]> => {
+  getResourceHistory: async (
+    connectionId: string,
+    resolution?: HistoryResolution,
+  ): Promise<ResourceMetrics[]> => {
     if (USE_MOCK) return [];
-    return invoke('get_resource_history', { connectionId });
+    return invoke('get_resource_history', { connectionId, resolution });
   },
 
   // ============ Smart Port Detection ============
@@ -677,11 +892,35 @@ export const api = {
     return invoke('get_detected_ports', { connectionId });
   },
 
+  /** Ports of the last complete scan, including the silent first one */
+  profilerGetPorts: async (connectionId: string): Promise<DetectedPort[]> => {
+    if (USE_MOCK) return [];
+    return invoke('profiler_get_ports', { connectionId });
+  },
+
+  /** Force an immediate port scan instead of waiting for the next sample */
+  profilerRescanPorts: async (connectionId: string): Promise<DetectedPort[]> => {
+    if (USE_MOCK) return [];
+    return invoke('profiler_rescan_ports', { connectionId });
+  },
+
   ignoreDetectedPort: async (connectionId: string, port: number): Promise<void> => {
     if (USE_MOCK) return;
     return invoke('ignore_detected_port', { connectionId, port });
   },
 
+  /** Un-ignore a port; it is reported as new again if still listening */
+  profilerUnignorePort: async (connectionId: string, port: number): Promise<boolean> => {
+    if (USE_MOCK) return false;
+    return invoke('profiler_unignore_port', { connectionId, port });
+  },
+
+  /** Clear all ignored ports; returns how many were un-ignored */
+  profilerClearIgnored: async (connectionId: string): Promise<number> => {
+    if (USE_MOCK) return 0;
+    return invoke('profiler_clear_ignored', { connectionId });
+  },
+
   // ============ Network & Reconnect ============
   networkStatusChanged: async (online: boolean): Promise<void> => {
     if (USE_MOCK) return;
@@ -753,11 +992,25 @@ export const api = {
     return invoke('search_terminal', { sessionId, options });
   },
 
+  /** Search every session's scroll buffer; max_matches is shared across sessions */
+  searchAllSessions: async (options: SearchOptions): Promise<Record<string, SearchResult>> => {
+    if (USE_MOCK) return {};
+    return invoke('search_all_sessions', { options });
+  },
+
   scrollToLine: async (sessionId: string, lineNumber: number, contextLines: number): Promise<TerminalLine[]> => {
     if (USE_MOCK) return [];
     return invoke('scroll_to_line', { sessionId, lineNumber, contextLines });
   },
 
+  /**
+   * Render search results as a report; also writes it to `path` when given
+   */
+  exportSearchResults: async (query: string, result: SearchResult, format: SearchReportFormat, path?: string): Promise<string> => {
+    if (USE_MOCK) return '';
+    return invoke('search_export_results', { query, result, format, path: path ?? null });
+  },
+
   // ============ Session Tree (Dynamic Jump Host) ============
 
   /**
@@ -768,6 +1021,18 @@ export const api = {
     return invoke('get_session_tree');
   },
 
+  /**
+   * 搜索会话树节点（host / displayName / username，大小写不敏感）
+   * stateFilter 只比较 status，failed 的 error 不参与匹配
+   */
+  searchSessionTree: async (
+    query: string,
+    stateFilter?: import('../types').TreeNodeState
+  ): Promise<import('../types').FlatNode[]> => {
+    if (USE_MOCK) return [];
+    return invoke('search_session_tree', { query, stateFilter });
+  },
+
   /**
    * 获取会话树摘要信息
    */
@@ -787,8 +1052,8 @@ export const api = {
   /**
    * 从已连接节点钻入新服务器（模式3: 动态钻入）
    */
-  treeDrillDown: async (request: import('../types').DrillDownRequest): Promise<string> => {
-    if (USE_MOCK) return 'mock-child-node-id';
+  treeDrillDown: async (request: import('../types').DrillDownRequest): Promise<import('../types').DrillDownResponse> => {
+    if (USE_MOCK) return { nodeId: 'mock-child-node-id', cycleAncestor: null };
     return invoke('tree_drill_down', { request });
   },
 
@@ -842,6 +1107,14 @@ export const api = {
     return invoke('exclude_topology_edge', { from, to });
   },
 
+  /**
+   * Preview auto-route path and cost without creating tree nodes
+   */
+  previewAutoRoute: async (targetId: string): Promise<import('../types').RoutePreview> => {
+    if (USE_MOCK) return { route: [], totalCost: 0, hopDetails: [] };
+    return invoke('preview_auto_route', { targetId });
+  },
+
   /**
    * Expand auto-route node chain (Mode 2: Static Auto-Route)
    */
@@ -929,6 +1202,35 @@ export const api = {
     return invoke('connect_tree_node', { request });
   },
 
+  /**
+   * 只重连链路中断开的区段（上游仍存活的跳板不重新认证）
+   */
+  reconnectChainSegment: async (targetNodeId: string, cols?: number, rows?: number): Promise<ReconnectChainSegmentResponse> => {
+    if (USE_MOCK) {
+      return {
+        targetNodeId,
+        targetSshConnectionId: crypto.randomUUID(),
+        reconnectedNodeIds: [targetNodeId],
+        untouchedNodeIds: [],
+      };
+    }
+    return invoke('reconnect_chain_segment', { targetNodeId, cols, rows });
+  },
+
+  /**
+   * 重建以 nodeId 为根的子树（断开的祖先先重连，失败时回滚本次重连）
+   */
+  reconnectTreeSubtree: async (nodeId: string, cols?: number, rows?: number): Promise<ReconnectSubtreeResponse> => {
+    if (USE_MOCK) {
+      return {
+        nodeId,
+        reconnectedNodeIds: [nodeId],
+        skippedNodeIds: [],
+      };
+    }
+    return invoke('reconnect_tree_subtree', { nodeId, cols, rows });
+  },
+
   /**
    * 断开树节点（断开 SSH 连接）
    */
@@ -937,6 +1239,14 @@ export const api = {
     return invoke('disconnect_tree_node', { nodeId });
   },
 
+  /**
+   * 断开整个会话树（叶子先于根），返回断开的节点 ID
+   */
+  disconnectAllTreeNodes: async (): Promise<string[]> => {
+    if (USE_MOCK) return [];
+    return invoke('disconnect_all_tree_nodes');
+  },
+
   /**
    * 展开手工预设链为树节点（不执行连接）
    * 
@@ -1214,14 +1524,15 @@ export const api = {
     connectionId: string,
     command: string,
     cwd?: string,
-    timeoutSecs?: number
+    timeoutSecs?: number,
+    env?: [string, string][]
   ): Promise<{
     stdout: string;
     stderr: string;
     exitCode: number | null;
   }> => {
     if (USE_MOCK) return { stdout: '', stderr: '', exitCode: 0 };
-    return invoke('ide_exec_command', { connectionId, command, cwd, timeoutSecs });
+    return invoke('ide_exec_command', { connectionId, command, cwd, env, timeoutSecs });
   },
 
   // ═══════════════════════════════════════════════════════════════════
@@ -1406,10 +1717,26 @@ export const nodeIdeOpenProject = (nodeId: string, path: string): Promise<{
   invoke('node_ide_open_project', { nodeId, path });
 
 /** IDE: 执行远程命令 */
-export const nodeIdeExecCommand = (nodeId: string, command: string, cwd?: string, timeoutSecs?: number): Promise<{
+export const nodeIdeExecCommand = (
+  nodeId: string, command: string, cwd?: string, timeoutSecs?: number, env?: [string, string][]
+): Promise<{
   stdout: string; stderr: string; exitCode: number | null;
 }> =>
-  invoke('node_ide_exec_command', { nodeId, command, cwd, timeoutSecs });
+  invoke('node_ide_exec_command', { nodeId, command, cwd, env, timeoutSecs });
+
+/**
+ * IDE: 流式执行远程命令
+ *
+ * 输出以 `ide:exec:${execId}` 事件推送（见 ExecStreamEvent）；先订阅再调用。
+ */
+export const nodeIdeExecStreamed = (
+  nodeId: string, execId: string, command: string, cwd?: string, env?: [string, string][]
+): Promise<void> =>
+  invoke('node_ide_exec_streamed', { nodeId, execId, command, cwd, env });
+
+/** IDE: 取消流式执行；已结束时返回 false */
+export const ideExecCancel = (execId: string): Promise<boolean> =>
+  invoke('ide_exec_cancel', { execId });
 
 /** IDE: 检查文件是否可编辑 */
 export const nodeIdeCheckFile = (nodeId: string, path: string): Promise<
@@ -1436,7 +1763,11 @@ export const nodeTerminalUrl = (nodeId: string): Promise<{ wsPort: number; wsTok
 
 import type {
   AgentStatus,
+  AgentOperationMode,
+  AgentUsage,
   AgentReadFileResult,
+  AgentReadFileStreamResult,
+  AgentStatResult,
   AgentWriteFileResult,
   AgentListTreeResult,
   AgentGrepMatch,
@@ -1457,6 +1788,14 @@ export const nodeAgentRemove = (nodeId: string): Promise<void> =>
 export const nodeAgentStatus = (nodeId: string): Promise<AgentStatus> =>
   invoke('node_agent_status', { nodeId });
 
+/** Get which backend (agent or SFTP fallback) serves file operations for a node */
+export const nodeAgentOperationMode = (nodeId: string): Promise<AgentOperationMode> =>
+  invoke('node_agent_operation_mode', { nodeId });
+
+/** Get the agent's memory use and uptime for a node */
+export const nodeAgentUsage = (nodeId: string): Promise<AgentUsage> =>
+  invoke('node_agent_usage', { nodeId });
+
 /** Read file via agent (returns content + hash for optimistic locking) */
 export const nodeAgentReadFile = (nodeId: string, path: string): Promise<AgentReadFileResult> =>
   invoke('node_agent_read_file', { nodeId, path });
@@ -1467,35 +1806,49 @@ export const nodeAgentWriteFile = (
 ): Promise<AgentWriteFileResult> =>
   invoke('node_agent_write_file', { nodeId, path, content, expectHash });
 
+/** Download a file of any size via agent, streamed to localPath and hash-verified */
+export const nodeAgentDownloadFile = (
+  nodeId: string, remotePath: string, localPath: string
+): Promise<AgentReadFileStreamResult> =>
+  invoke('node_agent_download_file', { nodeId, remotePath, localPath });
+
+/** Get file metadata via agent; detectBinary also reports whether a file looks binary */
+export const nodeAgentStat = (
+  nodeId: string, path: string, detectBinary?: boolean
+): Promise<AgentStatResult> =>
+  invoke('node_agent_stat', { nodeId, path, detectBinary });
+
 /** List directory tree (recursive) via agent — returns entries + truncation metadata */
 export const nodeAgentListTree = (
-  nodeId: string, path: string, maxDepth?: number, maxEntries?: number
+  nodeId: string, path: string, maxDepth?: number, maxEntries?: number, cursor?: string,
+  respectGitignore?: boolean
 ): Promise<AgentListTreeResult> =>
-  invoke('node_agent_list_tree', { nodeId, path, maxDepth, maxEntries });
+  invoke('node_agent_list_tree', { nodeId, path, maxDepth, maxEntries, cursor, respectGitignore });
 
 /** Search files for pattern via agent */
 export const nodeAgentGrep = (
   nodeId: string, pattern: string, path: string,
-  caseSensitive?: boolean, maxResults?: number
+  caseSensitive?: boolean, maxResults?: number, respectGitignore?: boolean
 ): Promise<AgentGrepMatch[]> =>
-  invoke('node_agent_grep', { nodeId, pattern, path, caseSensitive, maxResults });
+  invoke('node_agent_grep', { nodeId, pattern, path, caseSensitive, maxResults, respectGitignore });
 
 /** Get git status via agent */
 export const nodeAgentGitStatus = (nodeId: string, path: string): Promise<AgentGitStatusResult> =>
   invoke('node_agent_git_status', { nodeId, path });
 
-/** Start watching a directory for changes via agent */
-export const nodeAgentWatchStart = (nodeId: string, path: string, ignore?: string[]): Promise<void> =>
-  invoke('node_agent_watch_start', { nodeId, path, ignore });
+/**
+ * Start watching a directory for changes via agent.
+ * Resolves to the connection ID; changes arrive as `agent:watch:{connectionId}` events.
+ */
+export const nodeAgentWatchStart = (
+  nodeId: string, path: string, ignore?: string[], debounceMs?: number
+): Promise<string> =>
+  invoke('node_agent_watch_start', { nodeId, path, ignore, debounceMs });
 
 /** Stop watching a directory */
 export const nodeAgentWatchStop = (nodeId: string, path: string): Promise<void> =>
   invoke('node_agent_watch_stop', { nodeId, path });
 
-/** Start relaying agent watch events to Tauri frontend events */
-export const nodeAgentStartWatchRelay = (nodeId: string): Promise<void> =>
-  invoke('node_agent_start_watch_relay', { nodeId });
-
 /** Index symbols in a remote project directory via agent */
 export const nodeAgentSymbolIndex = (
   nodeId: string, path: string, maxFiles?: number
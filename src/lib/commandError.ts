@@ -0,0 +1,36 @@
+/**
+ * Helpers for command rejections
+ *
+ * Migrated commands reject with a structured `CommandError`; the rest still
+ * reject with a string. These helpers accept either, so callers can branch on
+ * `code` without caring which kind of command they invoked.
+ *
+ * @module lib/commandError
+ */
+
+import type { CommandError, CommandErrorCode } from '../types';
+
+export function isCommandError(err: unknown): err is CommandError {
+  return (
+    typeof err === 'object' &&
+    err !== null &&
+    typeof (err as CommandError).code === 'string' &&
+    typeof (err as CommandError).message === 'string'
+  );
+}
+
+/** Error code of a rejection, or undefined for string / unknown errors */
+export function commandErrorCode(err: unknown): CommandErrorCode | undefined {
+  return isCommandError(err) ? err.code : undefined;
+}
+
+/** Human-readable text for any rejection (includes `detail` when present) */
+export function formatCommandError(err: unknown): string {
+  if (isCommandError(err)) {
+    return err.detail ? `${err.message}: ${err.detail}` : err.message;
+  }
+  if (err instanceof Error) {
+    return err.message;
+  }
+  return String(err);
+}
@@ -12,6 +12,7 @@ import {
   nodeIdeCheckFile,
 } from '../lib/api';
 import * as agentService from '../lib/agentService';
+import { commandErrorCode, formatCommandError } from '../lib/commandError';
 import {
   normalizePath,
   joinPath,
@@ -523,9 +524,9 @@ export const useIdeStore = create<IdeState & IdeActions>()(
               conflictState: null,
             }));
           } catch (err) {
-            const msg = err instanceof Error ? err.message : String(err);
+            const msg = formatCommandError(err);
             // Agent hash 冲突 → 转为 UI 冲突状态
-            if (msg.includes('CONFLICT') || msg.includes('hash mismatch') || msg.includes('File modified externally')) {
+            if (commandErrorCode(err) === 'conflict' || msg.includes('hash mismatch') || msg.includes('File modified externally')) {
               set({
                 conflictState: {
                   tabId,
@@ -14,8 +14,11 @@ import { subscribeWithSelector } from 'zustand/middleware';
 import { api, nodeSftpInit } from '../lib/api';
 import { useReconnectOrchestratorStore } from './reconnectOrchestratorStore';
 import { topologyResolver } from '../lib/topologyResolver';
+import { formatCommandError } from '../lib/commandError';
 import { useSettingsStore } from './settingsStore';
 import { useAppStore } from './appStore';
+import { useToastStore } from '../hooks/useToast';
+import i18n from '../i18n';
 import type { 
   FlatNode, 
   SessionTreeSummary,
@@ -92,8 +95,7 @@ function computeUnifiedStatus(
 }
 
 function isAlreadyConnectedError(error: unknown): boolean {
-  const message = error instanceof Error ? error.message : String(error);
-  return message.includes('already connected');
+  return formatCommandError(error).includes('already connected');
 }
 
 async function waitForConnectionInStore(
@@ -456,7 +458,10 @@ export const useSessionTreeStore = create<SessionTreeStore>()(
       
       set({ isLoading: true, error: null });
       try {
-        const nodeId = await api.treeDrillDown(request);
+        const { nodeId, cycleAncestor } = await api.treeDrillDown(request);
+        if (cycleAncestor) {
+          notifyDrillDownCycle(cycleAncestor);
+        }
         await get().fetchTree();
         // 展开父节点（通过 settingsStore）
         const settingsStore = useSettingsStore.getState();
@@ -712,7 +717,7 @@ export const useSessionTreeStore = create<SessionTreeStore>()(
         // 失败时回滚到 failed 状态
         console.error(`[connectNode] Node ${nodeId} connection failed:`, e);
         try {
-          await api.updateTreeNodeState(nodeId, 'failed', String(e));
+          await api.updateTreeNodeState(nodeId, 'failed', formatCommandError(e));
         } catch (updateErr) {
           console.warn(`[connectNode] Failed to update node state to failed:`, updateErr);
         }
@@ -1010,7 +1015,7 @@ export const useSessionTreeStore = create<SessionTreeStore>()(
             }
           } catch (e) {
             // ========== 熔断：连接失败，中断链式连接 ==========
-            const errorMsg = e instanceof Error ? e.message : String(e);
+            const errorMsg = formatCommandError(e);
             console.error(`[connectNodeWithAncestors] Node ${node.id} connection failed: ${errorMsg}`);
             
             // 标记失败节点
@@ -2139,6 +2144,21 @@ export const useSessionTreeStore = create<SessionTreeStore>()(
   }))
 );
 
+/**
+ * 钻入的新节点绕回了祖先节点的端点：节点照常创建，但提示用户是哪个祖先
+ */
+export function notifyDrillDownCycle(ancestorId: string): void {
+  const ancestor = useSessionTreeStore.getState().getNode(ancestorId);
+  const node = ancestor
+    ? ancestor.displayName || `${ancestor.username}@${ancestor.host}:${ancestor.port}`
+    : ancestorId;
+  useToastStore.getState().addToast({
+    title: i18n.t('modals.drill_down.cycle_title'),
+    description: i18n.t('modals.drill_down.cycle_notice', { node }),
+    variant: 'warning',
+  });
+}
+
 // ============================================================================
 // Subscriptions & Side Effects
 // ============================================================================
@@ -2,6 +2,47 @@
 export type SessionState = 'disconnected' | 'connecting' | 'connected' | 'error' | 'reconnecting';
 export type AuthType = 'password' | 'key' | 'default_key' | 'agent' | 'certificate' | 'keyboard_interactive';
 
+// ═══════════════════════════════════════════════════════════════════════════
+// Command Errors
+// ═══════════════════════════════════════════════════════════════════════════
+
+/**
+ * Machine-readable failure kind (mirrors Rust ErrorCode, snake_case)
+ */
+export type CommandErrorCode =
+  | 'not_found'
+  | 'invalid_input'
+  | 'permission_denied'
+  | 'connection_refused'
+  | 'connection_failed'
+  | 'not_connected'
+  | 'auth_failed'
+  | 'host_key_changed'
+  | 'invalid_key'
+  | 'ssh_agent_unavailable'
+  | 'timeout'
+  | 'limit_reached'
+  | 'invalid_file'
+  | 'decryption_failed'
+  | 'checksum_mismatch'
+  | 'agent_not_deployed'
+  | 'conflict'
+  | 'agent_failed'
+  | 'keychain'
+  | 'io'
+  | 'internal';
+
+/**
+ * Structured rejection payload of migrated commands
+ * (connection, .oxide import/export, node agent).
+ * Other commands still reject with a plain string.
+ */
+export interface CommandError {
+  code: CommandErrorCode;
+  message: string;
+  detail?: string;
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // SSH Connection Pool Types (New Architecture)
 // ═══════════════════════════════════════════════════════════════════════════
@@ -102,6 +143,8 @@ export type NodeStateEvent =
 export interface RemoteEnvInfo {
   /** OS type: "Linux", "macOS", "Windows", "FreeBSD", "Windows_MinGW", "Unknown", etc. */
   osType: string;
+  /** Typed OS family the backend dispatches platform-specific commands on */
+  osFamily: 'linux' | 'macOs' | 'bsd' | 'windows' | 'windowsPosix' | 'unknown';
   /** Human-readable OS version (e.g., "Ubuntu 22.04.3 LTS") */
   osVersion?: string;
   /** Kernel version (uname -r) */
@@ -114,6 +157,16 @@ export interface RemoteEnvInfo {
   detectedAt: number;
 }
 
+/**
+ * Streamed IDE exec output, emitted as `ide:exec:${execId}`
+ * (see nodeIdeExecStreamed). `exit` is always last; a cancelled exec
+ * stops without it.
+ */
+export type ExecStreamEvent =
+  | { type: 'stdout'; data: string }
+  | { type: 'stderr'; data: string }
+  | { type: 'exit'; exitCode: number | null };
+
 /**
  * SSH connection info from the connection pool
  */
@@ -145,6 +198,22 @@ export interface ConnectionPoolConfig {
   protectOnExit: boolean;
 }
 
+/**
+ * WebSocket bridge settings, applied to terminals opened afterwards
+ */
+export interface BridgeConfig {
+  /** Allowed `Origin` header values */
+  allowedOrigins: string[];
+  /** Connections that may be authenticating at once */
+  maxConnections: number;
+  /** Client silence after which the terminal detaches (at least 10s) */
+  heartbeatTimeoutSecs: number;
+  /** How long a detached terminal's shell is kept for reattach */
+  detachGraceSecs: number;
+  /** Scroll buffer lines replayed on (re)attach */
+  replayLines: number;
+}
+
 /**
  * Connection pool statistics (for monitoring panel)
  */
@@ -275,8 +344,53 @@ export interface CreateTerminalRequest {
   cols?: number;
   rows?: number;
   maxBufferLines?: number;
+  notifyOn?: NotifyOn;
+  pasteGuard?: PasteGuardPolicy;
+}
+
+/**
+ * Multi-line paste confirmation policy
+ */
+export interface PasteGuardPolicy {
+  /** Hold multi-line pastes until confirmed */
+  confirmMultiline: boolean;
+  /** Input shorter than this is never held (default 8) */
+  minBytes?: number;
 }
 
+/**
+ * Payload of the `session:paste_confirm` event
+ */
+export interface PasteConfirmPayload {
+  sessionId: string;
+  pasteId: number;
+  byteLen: number;
+  lineCount: number;
+  /** Start of the pasted text */
+  preview: string;
+}
+
+/**
+ * Session activity notification settings
+ */
+export interface NotifyOn {
+  bell?: boolean;
+  idleThenOutput?: boolean;
+  /** Regex matched against each output line (case-sensitive) */
+  pattern?: string | null;
+  /** Quiet period for idleThenOutput (default 30) */
+  idleSecs?: number;
+}
+
+/**
+ * Payload of the `session:notify:{sessionId}` event
+ */
+export type SessionNotifyPayload = { sessionId: string } & (
+  | { trigger: 'bell' }
+  | { trigger: 'outputAfterIdle'; idleSecs: number }
+  | { trigger: 'pattern'; line: string }
+);
+
 /**
  * Create terminal response
  */
@@ -468,6 +582,44 @@ export interface ProxyHopInfo {
   key_path?: string;
 }
 
+/** Credential storage status (get_keychain_status) */
+export interface KeychainStatus {
+  /** OS keychain answered the startup probe */
+  systemAvailable: boolean;
+  /** Credentials go to the encrypted file fallback */
+  fileFallback: boolean;
+  /** Credentials can be stored at all */
+  usable: boolean;
+}
+
+/** Result of config_consolidate_credentials (failed items keep their old copy) */
+export interface ConsolidationReport {
+  moved: { from: string; to: string }[];
+  /** Secrets already stored under their canonical ID */
+  unchanged: number;
+  failures: { source: string; error: string }[];
+}
+
+/** One keychain service's part of a rotate_keychain run */
+export interface KeychainRotationServiceReport {
+  fromService: string;
+  toService: string;
+  dryRun: boolean;
+  /** IDs copied (or, in a dry run, that would be copied) */
+  migrated: string[];
+  /** Known IDs with no secret stored */
+  missing: string[];
+  failed: { id: string; error: string }[];
+  /** The keychain now uses toService */
+  committed: boolean;
+}
+
+/** Result of rotate_keychain */
+export interface KeychainRotationReport {
+  ssh: KeychainRotationServiceReport;
+  ai: KeychainRotationServiceReport;
+}
+
 export interface ConnectionInfo {
   id: string;
   name: string;
@@ -484,21 +636,46 @@ export interface ConnectionInfo {
   proxy_chain?: ProxyHopInfo[];
 }
 
+/** Result of `import_ssh_host` */
+export interface ImportedSshHost extends ConnectionInfo {
+  /** ProxyJump hosts with no Host entry, imported as written */
+  undefined_jump_hosts: string[];
+}
+
+/** Argon2id cost of an .oxide export: 64MB/2, 128MB/3 or 256MB/4 iterations */
+export type KdfStrength = 'interactive' | 'moderate' | 'sensitive';
+
 export interface OxideMetadata {
   exported_at: string;
   exported_by: string;
   description?: string;
   num_connections: number;
   connection_names: string[];
+  kdf_strength: KdfStrength;
+}
+
+/** How imported connections already saved (same host, port and username) are handled */
+export type ImportMode = 'createNew' | 'skipExisting' | 'overwrite';
+
+/** A connection that was not imported, under its name in the .oxide file */
+export interface ImportError {
+  connectionName: string;
+  stage: 'keychain' | 'config';
+  message: string;
 }
 
 export interface ImportResult {
   imported: number;
+  /** Already saved and left alone (`skipExisting`) */
   skipped: number;
+  /** Already saved and updated in place (`overwrite`) */
+  overwritten: number;
   renamed: number;
-  errors: string[];
+  errors: ImportError[];
   /** List of name changes: [original_name, new_name][] */
   renames: [string, string][];
+  /** Connections whose ProxyCommand was dropped (it runs a local command) */
+  proxyCommandsRemoved: string[];
 }
 
 export interface ImportPreview {
@@ -508,6 +685,10 @@ export interface ImportPreview {
   unchanged: string[];
   /** Connections that will be renamed: [original_name, new_name][] */
   willRename: [string, string][];
+  /** Already saved, will be skipped (`skipExisting`) */
+  willSkip: string[];
+  /** Already saved, will be updated in place (`overwrite`) */
+  willOverwrite: string[];
   /** Whether any embedded keys will be extracted */
   hasEmbeddedKeys: boolean;
 }
@@ -525,6 +706,8 @@ export interface ExportPreflightResult {
   connectionsWithAgent: number;
   /** Total bytes of key files (if embed_keys is enabled) */
   totalKeyBytes: number;
+  /** Credentials that could not be read from the keychain: [context, error][] */
+  missingCredentials: [string, string][];
   /** Whether all connections can be exported */
   canExport: boolean;
 }
@@ -544,6 +727,82 @@ export interface SaveConnectionRequest {
   tags?: string[];
 }
 
+/** Fresh credentials for one hop of an imported connection snippet */
+export interface SnippetCredential {
+  password?: string;
+  key_path?: string;
+  cert_path?: string;
+  passphrase?: string;
+}
+
+/** Import a pasted connection snippet (credentials are never in the snippet) */
+export interface ImportSnippetRequest {
+  snippet: string;
+  credentials?: SnippetCredential;
+  /** One entry per proxy hop, in chain order */
+  hop_credentials?: SnippetCredential[];
+}
+
+/** Dry-run validation of a connection form (no credentials are sent) */
+export interface ValidateConnectionRequest {
+  host: string;
+  port: number;
+  auth_type: 'password' | 'key' | 'agent' | 'certificate';
+  key_path?: string;
+  cert_path?: string;
+  /** Nothing is probed behind a ProxyCommand */
+  proxy_command?: string;
+  /** Jump hosts, first hop first; only the first one is probed */
+  proxy_chain?: { host: string; port: number }[];
+}
+
+export type ConnectionCheckKind = 'dns' | 'tcp' | 'key_file' | 'cert_file';
+
+export type ConnectionCheckStatus = 'ok' | 'failed' | 'skipped';
+
+export interface ConnectionCheck {
+  kind: ConnectionCheckKind;
+  status: ConnectionCheckStatus;
+  message: string;
+}
+
+export type ConnectionTestFailure = 'network' | 'host_key' | 'auth';
+
+/** Result of `config_test_connection` (authenticate, detect OS, disconnect) */
+export interface ConnectionTestResult {
+  ok: boolean;
+  /** Same values as the profiler's os type ("Linux", "macOS", ...) */
+  os_type: string | null;
+  /** Server identification string, e.g. "SSH-2.0-OpenSSH_9.6" */
+  banner: string | null;
+  auth_method_used: 'password' | 'key' | 'agent' | 'certificate';
+  duration_ms: number;
+  failure: ConnectionTestFailure | null;
+  error: CommandError | null;
+}
+
+/** Validity of an OpenSSH certificate (`config_check_certificate`) */
+export interface CertStatus {
+  cert_path: string;
+  /** Key ID assigned by the CA */
+  key_id: string;
+  principals: string[];
+  /** RFC 3339; null if unbounded */
+  valid_from: string | null;
+  /** RFC 3339; null if the certificate never expires */
+  valid_until: string | null;
+  expired: boolean;
+  expires_soon: boolean;
+  /** Negative once expired; null if the certificate never expires */
+  seconds_remaining: number | null;
+}
+
+/** Payload of `ssh:cert_expiry`, sent before connecting with an expired or expiring certificate */
+export interface CertExpiryEvent extends CertStatus {
+  host: string;
+  username: string;
+}
+
 // Terminal Config
 export interface TerminalConfig {
   themeId: string;
@@ -643,9 +902,19 @@ export interface PersistedForwardInfo {
   target_host: string;
   target_port: number;
   auto_start: boolean;
+  logging: boolean;
   created_at: string;
 }
 
+/** Connection event recorded for a forward with logging enabled */
+export interface ForwardConnectionEvent {
+  kind: 'accept' | 'close';
+  timestamp: string;
+  peer_addr: string;
+  bytes_sent: number;
+  bytes_received: number;
+}
+
 export interface ForwardRule {
   id: string;
   forward_type: ForwardType;
@@ -676,11 +945,17 @@ export interface ForwardResponse {
 }
 
 // Smart Port Detection Types
+export type PortProtocol = 'tcp' | 'udp';
+
 export interface DetectedPort {
   port: number;
+  /** Always 'tcp' unless the profiler was started with UDP scanning */
+  protocol: PortProtocol;
   bind_addr: string;
   process_name?: string;
   pid?: number;
+  /** Several processes hold the port; process_name/pid name the first */
+  shared: boolean;
 }
 
 export interface PortDetectionEvent {
@@ -729,16 +1004,39 @@ export type ResourceMetrics = {
   memoryUsed: number | null;
   memoryTotal: number | null;
   memoryPercent: number | null;
+  /** Root filesystem usage */
+  diskUsed: number | null;
+  diskTotal: number | null;
+  diskPercent: number | null;
   loadAvg1: number | null;
   loadAvg5: number | null;
   loadAvg15: number | null;
   cpuCores: number | null;
   netRxBytesPerSec: number | null;
   netTxBytesPerSec: number | null;
+  diskReadBytesPerSec: number | null;
+  diskWriteBytesPerSec: number | null;
   sshRttMs: number | null;
   source: MetricsSource;
 };
 
+/** `retry_in_secs`: delay before the next sampling attempt as of the last failure, null if the profiler gave up */
+export type ProfilerState =
+  | { state: 'running' }
+  | { state: 'stopped' }
+  | { state: 'degraded'; retry_in_secs: number | null };
+
+/** Payload of `profiler:state:{connectionId}`, emitted once per state change */
+export interface ProfilerStateEvent {
+  connection_id: string;
+  old_state: ProfilerState;
+  new_state: ProfilerState;
+  reason: string;
+}
+
+/** History tier: raw samples (~10 min), 1-minute (~2 h) or 5-minute (~12 h) averages */
+export type HistoryResolution = 'raw' | 'one_minute' | 'five_minutes';
+
 // SSH Types
 export interface SshHostInfo {
     alias: string;
@@ -784,6 +1082,10 @@ export interface SearchOptions {
   whole_word: boolean;
   /** Maximum matches to return (0 = unlimited, default 1000) */
   max_matches?: number;
+  /** Lines of context before each match (default 0) */
+  context_before?: number;
+  /** Lines of context after each match (default 0) */
+  context_after?: number;
 }
 
 export interface SearchMatch {
@@ -792,6 +1094,10 @@ export interface SearchMatch {
   column_end: number;
   matched_text: string;
   line_content: string;
+  /** Context lines preceding the match (present only when requested) */
+  lines_before?: string[];
+  /** Context lines following the match (present only when requested) */
+  lines_after?: string[];
 }
 
 export interface SearchResult {
@@ -804,6 +1110,8 @@ export interface SearchResult {
   error?: string;
 }
 
+export type SearchReportFormat = 'text' | 'markdown';
+
 // SFTP Resume Transfer Types
 export type TransferStatusType = 'Active' | 'Paused' | 'Failed' | 'Completed' | 'Cancelled';
 export type TransferType = 'Upload' | 'Download';
@@ -856,6 +1164,15 @@ export type TreeNodeState =
   | { status: 'disconnected' }
   | { status: 'failed'; error: string };
 
+/**
+ * `tree:node_state` 事件负载
+ * 节点状态在命令之外发生变化时发送（例如父节点隧道断开后子树被标记为 disconnected）
+ */
+export interface TreeNodeStateEvent {
+  nodeId: string;
+  state: TreeNodeState;
+}
+
 /**
  * 统一节点状态 (前端扩展)
  * NodeState = f(ConnectionStatus, TerminalSessionCount)
@@ -971,6 +1288,15 @@ export interface DrillDownRequest {
   displayName?: string;
 }
 
+/**
+ * 钻入响应
+ */
+export interface DrillDownResponse {
+  nodeId: string;
+  /** 新节点的端点已出现在祖先链上时，该祖先节点的 ID（仅提示） */
+  cycleAncestor: string | null;
+}
+
 /**
  * 跳板机信息
  */
@@ -1026,6 +1352,29 @@ export interface ConnectManualPresetResponse {
   chainDepth: number;
 }
 
+/**
+ * 重连链路区段响应
+ */
+export interface ReconnectChainSegmentResponse {
+  /** 目标节点 ID */
+  targetNodeId: string;
+  /** 目标节点的 SSH 连接 ID */
+  targetSshConnectionId: string;
+  /** 重新建立连接的节点 ID（从断点到目标） */
+  reconnectedNodeIds: string[];
+  /** 仍然存活、未被触碰的上游节点 ID */
+  untouchedNodeIds: string[];
+}
+
+export interface ReconnectSubtreeResponse {
+  /** 子树根节点 ID */
+  nodeId: string;
+  /** 重新建立连接的节点 ID（自顶向下，可能包含断开的祖先） */
+  reconnectedNodeIds: string[];
+  /** 已连接、被跳过的子树节点 ID */
+  skippedNodeIds: string[];
+}
+
 // ===== Auto-Route (Auto-generated from Saved Connections) =====
 
 /**
@@ -1064,6 +1413,8 @@ export interface TopologyEdge {
   to: string;
   /** Cost (hop count, latency, etc.) */
   cost: number;
+  /** Measured link RTT in ms; replaces `cost` for routing when present */
+  measured_rtt_ms?: number;
 }
 
 /**
@@ -1098,6 +1449,27 @@ export interface ExpandAutoRouteResponse {
   totalCost: number;
   /** All expanded node IDs (from root to target) */
   allNodeIds: string[];
+  /** Next-best routes (intermediate hop node IDs), cheapest first */
+  alternatives: string[][];
+}
+
+/** Display info for one node of a route preview */
+export interface RouteHopDetail {
+  id: string;
+  host: string;
+  port: number;
+  username: string;
+  displayName: string | null;
+  authType: string;
+}
+
+export interface RoutePreview {
+  /** Computed route path (intermediate hop node IDs) */
+  route: string[];
+  /** Total route cost */
+  totalCost: number;
+  /** Details for each route hop, followed by the target */
+  hopDetails: RouteHopDetail[];
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -1255,10 +1627,43 @@ export type AgentStatus =
   | { type: 'notDeployed' }
   | { type: 'deploying' }
   | { type: 'ready'; version: string; arch: string; pid: number }
+  | { type: 'reconnecting' }
   | { type: 'failed'; reason: string }
   | { type: 'unsupportedArch'; arch: string }
   | { type: 'manualUploadRequired'; arch: string; remotePath: string };
 
+/** Backend serving file operations for a connection */
+export type AgentOperationMode = 'agent' | 'sftpFallback';
+
+/** Agent self-reported footprint (diagnostics) */
+export type AgentUsage = {
+  pid: number;
+  /** Resident memory in bytes; 0 if the agent can't measure it */
+  rssBytes: number;
+  uptimeSecs: number;
+};
+
+/** `fs:backend_changed` event payload */
+export type FsBackendChangedEvent = {
+  connectionId: string;
+  mode: AgentOperationMode;
+  previous: AgentOperationMode;
+  /** Why the backend changed (e.g. "agent exited") */
+  reason: string;
+};
+
+/**
+ * `agent:reconnect` event payload. Pending reads are replayed and active
+ * watches are started again on the restarted agent.
+ */
+export type AgentReconnectEvent = {
+  connectionId: string;
+  /** True while the agent is being restarted */
+  reconnecting: boolean;
+  /** Why reconnecting failed (the backend then falls back to SFTP) */
+  error: string | null;
+};
+
 /** Agent fs/readFile result */
 export type AgentReadFileResult = {
   content: string;
@@ -1269,6 +1674,28 @@ export type AgentReadFileResult = {
   encoding?: string;
 };
 
+/** Agent fs/readFileStream result (node_agent_download_file) */
+export type AgentReadFileStreamResult = {
+  /** SHA-256 hex digest, verified against the downloaded bytes */
+  hash: string;
+  size: number;
+  mtime: number;
+  chunks: number;
+};
+
+/** Agent fs/stat result */
+export type AgentStatResult = {
+  exists: boolean;
+  /** "file" | "directory" | "symlink" | "other" */
+  file_type: string | null;
+  size: number | null;
+  mtime: number | null;
+  /** Octal, e.g. "755" */
+  permissions: string | null;
+  /** Regular files, only when requested with detectBinary */
+  is_binary: boolean | null;
+};
+
 /** Agent fs/writeFile result */
 export type AgentWriteFileResult = {
   hash: string;
@@ -1288,6 +1715,8 @@ export type AgentFileEntry = {
   children?: AgentFileEntry[];
   /** True if this directory's listing was cut short by the entry budget */
   truncated?: boolean;
+  /** True if already sent in an earlier page and repeated only to carry new children */
+  continued?: boolean;
 };
 
 /** Agent fs/listTree result — entries + truncation metadata */
@@ -1297,6 +1726,8 @@ export type AgentListTreeResult = {
   truncated: boolean;
   /** Total scanned entry count */
   total_scanned: number;
+  /** Pass as `cursor` to fetch the next page (present only when truncated) */
+  next_cursor?: string | null;
 };
 
 /** Agent search/grep match */